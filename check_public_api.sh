@@ -0,0 +1,39 @@
+#!/bin/bash
+
+# Public API Check for the extractor crate
+# Usage: ./check_public_api.sh [base-ref]
+#
+# Lists what the extractor crate's public API gained, changed and lost since
+# base-ref (the latest tag by default) and fails if anything was changed or
+# removed. Needs cargo-public-api and a nightly toolchain:
+#   cargo install cargo-public-api --locked
+#   rustup toolchain install nightly
+
+set -e
+
+# Colors for output
+RED='\033[0;31m'
+GREEN='\033[0;32m'
+YELLOW='\033[1;33m'
+NC='\033[0m' # No Color
+
+BASE=${1:-$(git describe --tags --abbrev=0 2>/dev/null || true)}
+if [ -z "$BASE" ]; then
+    echo -e "${RED}Error: No tag to compare with. Pass a base ref, e.g. ./check_public_api.sh main${NC}"
+    exit 1
+fi
+
+if ! cargo public-api --version > /dev/null 2>&1; then
+    echo -e "${RED}Error: cargo-public-api is not installed. Run 'cargo install cargo-public-api --locked'${NC}"
+    exit 1
+fi
+
+echo -e "Comparing the extractor API with ${YELLOW}${BASE}${NC}..."
+
+# Breaking changes fail the check; additions are listed for the release notes
+if cargo public-api -p extractor --all-features diff "$BASE..HEAD" --deny=changed --deny=removed; then
+    echo -e "${GREEN}✓ No breaking changes to the public API${NC}"
+else
+    echo -e "${RED}✗ The public API changed in a breaking way; bump the major version or keep the old items${NC}"
+    exit 1
+fi
@@ -4,8 +4,14 @@
 //! and probing archive metadata from the command line.
 
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use extractor::{ContentKind, FilenameEncoding};
+use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Exit code used whenever an operation is cancelled by a signal.
+const EXIT_CANCELLED: i32 = 130;
 
 #[derive(Parser)]
 #[command(name = "unarchive")]
@@ -18,41 +24,350 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Extract one or more archives
-    Extract {
-        /// Archive files to extract
+    Extract(Box<ExtractArgs>),
+
+    /// Probe archive metadata
+    Probe {
+        /// Archive files to probe
         #[arg(required = true)]
         archives: Vec<PathBuf>,
 
-        /// Output directory
-        #[arg(short, long)]
-        out: PathBuf,
+        /// Output as JSON (an array when several archives are given)
+        #[arg(long)]
+        json: bool,
+
+        /// Output one JSON object per line, for each archive
+        #[arg(long, conflicts_with = "json")]
+        ndjson: bool,
 
-        /// Overwrite mode: replace, skip, rename
-        #[arg(long, default_value = "rename")]
-        overwrite: String,
+        /// Sort the entry list: path, size, modified
+        #[arg(long)]
+        sort: Option<String>,
 
-        /// Password for encrypted archives
+        /// Sort in descending order
+        #[arg(long, requires = "sort")]
+        desc: bool,
+
+        /// Byte offset of an archive embedded inside the file (see `scan`)
+        #[arg(long)]
+        offset: Option<u64>,
+
+        /// Password for archives whose entry list is encrypted
         #[arg(long)]
         password: Option<String>,
 
-        /// Strip leading path components
-        #[arg(long, default_value = "0")]
-        strip_components: u32,
+        /// Also check the archive's structure (headers, checksums, volumes) and any checksum file next to it
+        #[arg(long, conflicts_with = "offset")]
+        deep: bool,
 
-        /// Size limit in bytes
+        /// List the members of tar archives appended after the first one's end marker
         #[arg(long)]
-        size_limit: Option<u64>,
+        concatenated: bool,
+
+        /// Encoding of ZIP entry names not marked as UTF-8 (e.g. cp437, cp932, gbk)
+        #[arg(long, value_parser = parse_filename_encoding)]
+        filename_encoding: Option<FilenameEncoding>,
     },
 
-    /// Probe archive metadata
-    Probe {
-        /// Archive file to probe
-        archive: PathBuf,
+    /// Scan a file for embedded archives and print their offsets
+    Scan {
+        /// File to scan (firmware image, installer, ...)
+        file: PathBuf,
 
         /// Output as JSON
         #[arg(long)]
         json: bool,
     },
+
+    /// Write one file from an archive to stdout
+    Cat {
+        /// Archive containing the file
+        archive: PathBuf,
+
+        /// Path of the file inside the archive
+        entry: String,
+
+        /// Password for encrypted archives
+        #[arg(long)]
+        password: Option<String>,
+    },
+
+    /// Test archives by decoding every file without writing anything
+    Test {
+        /// Archive files to test
+        #[arg(required = true)]
+        archives: Vec<PathBuf>,
+
+        /// Password for encrypted archives
+        #[arg(long)]
+        password: Option<String>,
+
+        /// Output as JSON (an array when several archives are given)
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show, resume or roll back an extraction interrupted by a crash (see `extract --journal`)
+    Journal {
+        /// Output directory of the interrupted extraction
+        output_dir: PathBuf,
+
+        /// Output the journal as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Extract the rest of the archive, skipping the entries already written
+        #[arg(long, conflicts_with = "rollback")]
+        resume: bool,
+
+        /// Remove the files and folders the extraction created
+        #[arg(long)]
+        rollback: bool,
+
+        /// Password for encrypted archives, when resuming
+        #[arg(long, requires = "resume")]
+        password: Option<String>,
+    },
+}
+
+/// Arguments of the `extract` subcommand.
+#[derive(clap::Args)]
+struct ExtractArgs {
+    /// Archive files to extract, http(s) URLs to extract while downloading, or
+    /// directories of archives (with --recursive)
+    #[arg(required = true)]
+    archives: Vec<PathBuf>,
+
+    /// Output directory
+    #[arg(short, long)]
+    out: PathBuf,
+
+    /// Overwrite mode: replace, skip, rename, ifnewer, error, ask
+    #[arg(long, default_value = "rename")]
+    overwrite: String,
+
+    /// Name for renamed files, from {stem}, {n}, {ext} and {timestamp} (e.g. "{stem}-{n}.{ext}")
+    #[arg(long)]
+    rename_template: Option<String>,
+
+    /// Password for encrypted archives
+    #[arg(long)]
+    password: Option<String>,
+
+    /// Strip leading path components
+    #[arg(long, default_value = "0")]
+    strip_components: u32,
+
+    /// Extract each archive into a folder named after it inside the output directory: 'auto'
+    /// (without a value) only when its entries don't share one top-level folder, or 'always'
+    #[arg(long, value_name = "MODE", num_args = 0..=1, default_missing_value = "auto")]
+    subfolder: Option<String>,
+
+    /// Size limit in bytes
+    #[arg(long)]
+    size_limit: Option<u64>,
+
+    /// Largest size of a single file in bytes
+    #[arg(long, value_name = "BYTES")]
+    max_entry_size: Option<u64>,
+
+    /// Skip files over --max-entry-size instead of failing
+    #[arg(long, requires = "max_entry_size")]
+    skip_oversized: bool,
+
+    /// Reject entries expanding more than this many times their compressed size
+    #[arg(long, value_name = "RATIO")]
+    max_compression_ratio: Option<u32>,
+
+    /// Reject archives expanding to more than this many times their own size
+    #[arg(long, value_name = "RATIO")]
+    max_expansion_ratio: Option<u32>,
+
+    /// Write a provenance file (.extraction-info.json) into the output directory
+    #[arg(long)]
+    provenance: bool,
+
+    /// Write the SHA-256 of every extracted file into a SHA256SUMS file in the output directory
+    #[arg(long)]
+    sha256sums: bool,
+
+    /// Apply the permission bits stored in the archive as-is (default when run as root)
+    #[arg(short = 'p', long, visible_alias = "preserve-permissions")]
+    same_permissions: bool,
+
+    /// Mask the stored permission bits with the umask (default for other users)
+    #[arg(long, conflicts_with = "same_permissions")]
+    no_same_permissions: bool,
+
+    /// Symbolic links: skip, preserve, or materialize (copy what they point
+    /// to). Links pointing outside the output directory are refused
+    #[arg(long, default_value = "skip")]
+    symlinks: String,
+
+    /// Recreate the hard links stored in tar archives
+    #[arg(long)]
+    hardlinks: bool,
+
+    /// Restore the extended attributes stored in PAX tar archives
+    #[arg(long)]
+    xattrs: bool,
+
+    /// Also restore security-relevant extended attributes (SELinux labels,
+    /// file capabilities, ACLs)
+    #[arg(long, requires = "xattrs")]
+    unsafe_xattrs: bool,
+
+    /// Give extracted files the user and group IDs stored in the archive
+    /// (root only)
+    #[arg(long)]
+    same_owner: bool,
+
+    /// Don't restore modification times; files get the time they are extracted
+    #[arg(short = 'm', long)]
+    touch: bool,
+
+    /// Fixed octal mode for extracted files (e.g. 644), ignoring the archive
+    #[arg(long, value_parser = parse_mode)]
+    mode: Option<u32>,
+
+    /// Fixed octal mode for created directories (e.g. 755)
+    #[arg(long, value_parser = parse_mode)]
+    dir_mode: Option<u32>,
+
+    /// List each archive first and fail early if the destination is short of inodes
+    #[arg(long)]
+    check_inodes: bool,
+
+    /// List each archive first and fail early (or just warn, with 'warn') if the
+    /// destination hasn't enough free space
+    #[arg(long, value_name = "MODE", num_args = 0..=1, default_missing_value = "fail")]
+    check_space: Option<String>,
+
+    /// Fail on entry names with control characters instead of replacing them with '_'
+    #[arg(long)]
+    reject_control_chars: bool,
+
+    /// Fail on entry paths more than this many levels deep (0 allows any depth)
+    #[arg(long, value_name = "LEVELS", default_value = "1024")]
+    max_path_depth: u32,
+
+    /// Fail on entry paths longer than this many bytes (0 allows any length)
+    #[arg(long, value_name = "BYTES", default_value = "4096")]
+    max_path_bytes: u32,
+
+    /// Fail on entry paths with a name longer than this many bytes
+    #[arg(long, value_name = "BYTES")]
+    max_component_bytes: Option<u32>,
+
+    /// Replace dots and spaces ending entry names and dashes starting them with '_'
+    #[arg(long)]
+    sanitize_names: bool,
+
+    /// Shorten entry names longer than this many bytes, keeping their extension (0 keeps them)
+    #[arg(long, value_name = "BYTES", default_value = "255")]
+    max_name_bytes: u32,
+
+    /// Names Windows can't create (CON, aux.txt, a?b): allow, strict, rename, or
+    /// skip (default: rename on Windows, allow elsewhere)
+    #[arg(long)]
+    windows_names: Option<String>,
+
+    /// Encoding of ZIP entry names not marked as UTF-8 (e.g. cp437, cp932, gbk),
+    /// instead of the one detected from them
+    #[arg(long, value_parser = parse_filename_encoding)]
+    filename_encoding: Option<FilenameEncoding>,
+
+    /// Convert entry names to a Unicode normalization form: nfc, or nfd (as macOS stores them)
+    #[arg(long)]
+    normalize: Option<String>,
+
+    /// Don't extract files of these content types: executable, script (comma-separated)
+    #[arg(long, value_delimiter = ',', value_parser = parse_content_kind)]
+    deny_content: Vec<ContentKind>,
+
+    /// Move denied files into this folder instead of leaving them out
+    #[arg(long, requires = "deny_content")]
+    quarantine_dir: Option<PathBuf>,
+
+    /// Copy the archive's macOS quarantine attribute to the extracted files
+    #[arg(long)]
+    propagate_quarantine: bool,
+
+    /// Give up on an archive after this many seconds
+    #[arg(long, value_name = "SECONDS")]
+    max_duration: Option<u64>,
+
+    /// Extract every archive found in directories given as inputs, including subdirectories
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Write files directly under their final names instead of renaming them into place
+    #[arg(long)]
+    no_atomic_writes: bool,
+
+    /// Read every file back once extracted and check it against the archive's CRC-32 or size
+    #[arg(long)]
+    verify: bool,
+
+    /// Check each archive against a .sha512, .sha256 or .md5 file next to it before extracting
+    #[arg(long)]
+    verify_checksum: bool,
+
+    /// Chunks of 256 KiB that decoding may run ahead of writing (0 writes on the decoding thread)
+    #[arg(long, value_name = "CHUNKS", default_value = "8")]
+    write_queue_depth: usize,
+
+    /// Threads decoding and writing ZIP entries at once
+    #[arg(long, value_name = "N", default_value = "1")]
+    threads: usize,
+
+    /// Extract into a staging folder and move it into place only once everything succeeded
+    /// (the output folder must be new or empty)
+    #[arg(long)]
+    transactional: bool,
+
+    /// Leave the files written so far in place when an extraction fails or is cancelled
+    #[arg(long)]
+    keep_partial: bool,
+
+    /// Skip entries that can't be extracted and list them at the end instead of stopping
+    #[arg(long)]
+    keep_going: bool,
+
+    /// Write files around the page cache, so a huge extraction doesn't evict everything else
+    #[arg(long)]
+    no_page_cache: bool,
+
+    /// Leave runs of zeros in extracted files as holes, for disk images and database dumps
+    #[arg(long)]
+    sparse: bool,
+
+    /// Bytes of buffer for reading archives and writing files (0 disables buffering)
+    #[arg(long, value_name = "BYTES", default_value_t = extractor::DEFAULT_BUFFER_SIZE)]
+    buffer_size: usize,
+
+    /// Folder for temporary files such as spooled downloads (default: next to the output folder)
+    #[arg(long, value_name = "DIR")]
+    temp_dir: Option<PathBuf>,
+
+    /// Read ZIP and 7-Zip archives from a memory map (the archive must not change meanwhile)
+    #[arg(long)]
+    mmap: bool,
+
+    /// Keep a journal next to the output folder, so a crashed extraction can be resumed or
+    /// rolled back with the `journal` command
+    #[arg(long)]
+    journal: bool,
+
+    /// Fail on data that isn't a tar header after the last entry instead of ignoring it with a
+    /// warning
+    #[arg(long)]
+    strict_end: bool,
+
+    /// Read tar archives past their end marker, extracting the members of tar archives appended
+    /// after them
+    #[arg(long)]
+    concatenated: bool,
 }
 
 fn main() {
@@ -67,15 +382,48 @@ fn main() {
     let cli = Cli::parse();
 
     let result = match cli.command {
-        Commands::Extract {
+        Commands::Extract(args) => handle_extract(*args),
+        Commands::Probe {
             archives,
-            out,
-            overwrite,
+            json,
+            ndjson,
+            sort,
+            desc,
+            offset,
             password,
-            strip_components,
-            size_limit,
-        } => handle_extract(archives, out, overwrite, password, strip_components, size_limit),
-        Commands::Probe { archive, json } => handle_probe(archive, json),
+            deep,
+            concatenated,
+            filename_encoding,
+        } => handle_probe(
+            archives,
+            json,
+            ndjson,
+            sort,
+            desc,
+            offset,
+            password,
+            deep,
+            concatenated,
+            filename_encoding,
+        ),
+        Commands::Scan { file, json } => handle_scan(file, json),
+        Commands::Cat {
+            archive,
+            entry,
+            password,
+        } => handle_cat(archive, entry, password),
+        Commands::Test {
+            archives,
+            password,
+            json,
+        } => handle_test(archives, password, json),
+        Commands::Journal {
+            output_dir,
+            json,
+            resume,
+            rollback,
+            password,
+        } => handle_journal(output_dir, json, resume, rollback, password),
     };
 
     if let Err(e) = result {
@@ -84,26 +432,146 @@ fn main() {
     }
 }
 
-fn handle_extract(
-    archives: Vec<PathBuf>,
-    out: PathBuf,
-    overwrite: String,
-    password: Option<String>,
-    strip_components: u32,
-    size_limit: Option<u64>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    use extractor::{extract, ExtractOptions, OverwriteMode};
+fn handle_extract(args: ExtractArgs) -> Result<(), Box<dyn std::error::Error>> {
+    use extractor::{
+        ContentPolicy, EntryAction, ErrorPolicy, ExtractError, ExtractOptions, FreeSpaceCheck,
+        NameSanitizer, NormalizationForm, OversizePolicy, OverwriteMode, OverwritePrompt,
+        PathLimits, ProgressListener, SubfolderPolicy, SymlinkPolicy, TimestampPolicy,
+        WindowsNamePolicy,
+    };
     use indicatif::{ProgressBar, ProgressStyle};
-    use std::sync::atomic::{AtomicBool, Ordering};
-    use std::sync::Arc;
+    use std::sync::atomic::AtomicU64;
+
+    let ExtractArgs {
+        archives,
+        out,
+        overwrite,
+        rename_template,
+        password,
+        strip_components,
+        subfolder,
+        size_limit,
+        max_entry_size,
+        skip_oversized,
+        max_compression_ratio,
+        max_expansion_ratio,
+        provenance,
+        sha256sums,
+        same_permissions,
+        no_same_permissions,
+        symlinks,
+        hardlinks,
+        xattrs,
+        unsafe_xattrs,
+        same_owner,
+        touch,
+        mode,
+        dir_mode,
+        check_inodes,
+        check_space,
+        reject_control_chars,
+        max_path_depth,
+        max_path_bytes,
+        max_component_bytes,
+        sanitize_names,
+        max_name_bytes,
+        windows_names,
+        filename_encoding,
+        normalize,
+        deny_content,
+        quarantine_dir,
+        propagate_quarantine,
+        max_duration,
+        recursive,
+        no_atomic_writes,
+        verify,
+        verify_checksum,
+        write_queue_depth,
+        threads,
+        transactional,
+        keep_partial,
+        keep_going,
+        no_page_cache,
+        sparse,
+        buffer_size,
+        temp_dir,
+        mmap,
+        journal,
+        strict_end,
+        concatenated,
+    } = args;
 
     // Parse overwrite mode
     let overwrite_mode = match overwrite.as_str() {
         "replace" => OverwriteMode::Replace,
         "skip" => OverwriteMode::Skip,
         "rename" => OverwriteMode::Rename,
+        "ifnewer" => OverwriteMode::IfNewer,
+        "error" => OverwriteMode::Error,
+        "ask" => OverwriteMode::Ask,
+        _ => {
+            eprintln!("Invalid overwrite mode: {}. Use 'replace', 'skip', 'rename', 'ifnewer', 'error', or 'ask'.", overwrite);
+            process::exit(1);
+        }
+    };
+
+    let symlink_policy = match symlinks.as_str() {
+        "skip" => SymlinkPolicy::Skip,
+        "preserve" => SymlinkPolicy::Preserve,
+        "materialize" => SymlinkPolicy::Materialize,
         _ => {
-            eprintln!("Invalid overwrite mode: {}. Use 'replace', 'skip', or 'rename'.", overwrite);
+            eprintln!(
+                "Invalid symlink policy: {}. Use 'skip', 'preserve', or 'materialize'.",
+                symlinks
+            );
+            process::exit(1);
+        }
+    };
+
+    let create_subfolder = match subfolder.as_deref() {
+        None | Some("never") => SubfolderPolicy::Never,
+        Some("auto") => SubfolderPolicy::Auto,
+        Some("always") => SubfolderPolicy::Always,
+        Some(other) => {
+            eprintln!(
+                "Invalid subfolder mode: {}. Use 'auto', 'always' or 'never'.",
+                other
+            );
+            process::exit(1);
+        }
+    };
+
+    let check_free_space = match check_space.as_deref() {
+        None => FreeSpaceCheck::Off,
+        Some("warn") => FreeSpaceCheck::Warn,
+        Some("fail") => FreeSpaceCheck::Fail,
+        Some(other) => {
+            eprintln!("Invalid free space check: {}. Use 'warn' or 'fail'.", other);
+            process::exit(1);
+        }
+    };
+
+    let windows_names = match windows_names.as_deref() {
+        None => WindowsNamePolicy::default(),
+        Some("allow") => WindowsNamePolicy::Allow,
+        Some("strict") => WindowsNamePolicy::Strict,
+        Some("rename") => WindowsNamePolicy::Rename,
+        Some("skip") => WindowsNamePolicy::Skip,
+        Some(other) => {
+            eprintln!(
+                "Invalid Windows name policy: {}. Use 'allow', 'strict', 'rename', or 'skip'.",
+                other
+            );
+            process::exit(1);
+        }
+    };
+
+    let normalize_names = match normalize.as_deref() {
+        None => NormalizationForm::Keep,
+        Some("nfc") => NormalizationForm::Nfc,
+        Some("nfd") => NormalizationForm::Nfd,
+        Some(other) => {
+            eprintln!("Invalid normalization form: {}. Use 'nfc' or 'nfd'.", other);
             process::exit(1);
         }
     };
@@ -111,11 +579,86 @@ fn handle_extract(
     // Create extraction options
     let options = ExtractOptions {
         overwrite: overwrite_mode,
+        rename_template,
+        overwrite_prompt: None,
         size_limit_bytes: size_limit,
+        max_entry_size_bytes: max_entry_size,
+        oversized_entries: if skip_oversized {
+            OversizePolicy::Skip
+        } else {
+            OversizePolicy::Reject
+        },
+        max_compression_ratio,
+        max_expansion_ratio,
         strip_components,
-        allow_symlinks: false,
-        allow_hardlinks: false,
+        create_subfolder,
+        symlink_policy,
+        allow_hardlinks: hardlinks,
         password: password.clone(),
+        detect_case_sensitivity: true,
+        write_provenance: provenance,
+        hash_files: false,
+        write_sha256sums: sha256sums,
+        // Like tar, archived modes are masked by the umask unless running as root
+        preserve_permissions: Some(true),
+        apply_umask: !(same_permissions || (!no_same_permissions && running_as_root())),
+        preserve_xattrs: xattrs,
+        allow_unsafe_xattrs: unsafe_xattrs,
+        preserve_ownership: same_owner,
+        timestamp_policy: if touch {
+            TimestampPolicy::Now
+        } else {
+            TimestampPolicy::Preserve
+        },
+        file_mode: mode,
+        dir_mode,
+        check_free_inodes: check_inodes,
+        check_free_space,
+        sanitize_control_chars: !reject_control_chars,
+        path_limits: PathLimits {
+            max_depth: (max_path_depth > 0).then_some(max_path_depth),
+            max_component_bytes,
+            max_path_bytes: (max_path_bytes > 0).then_some(max_path_bytes),
+        },
+        sanitize_names: NameSanitizer {
+            trailing_dots: sanitize_names,
+            leading_dashes: sanitize_names,
+            max_name_bytes: (max_name_bytes > 0).then_some(max_name_bytes),
+        },
+        windows_names,
+        filename_encoding,
+        normalize_names,
+        entry_filter: None,
+        pre_write_hook: None,
+        map_path: None,
+        on_entry: None,
+        on_progress: None,
+        content_policy: (!deny_content.is_empty()).then_some(ContentPolicy {
+            deny: deny_content,
+            quarantine_dir,
+        }),
+        propagate_quarantine,
+        max_duration: max_duration.map(std::time::Duration::from_secs),
+        atomic_writes: !no_atomic_writes,
+        verify_after_extract: verify,
+        verify_checksum,
+        write_queue_depth,
+        threads,
+        transactional,
+        cleanup_on_failure: !keep_partial,
+        on_error: if keep_going {
+            ErrorPolicy::Skip
+        } else {
+            ErrorPolicy::Abort
+        },
+        bypass_page_cache: no_page_cache,
+        sparse_files: sparse,
+        buffer_size,
+        temp_dir,
+        memory_map: mmap,
+        journal,
+        tolerate_trailing_data: !strict_end,
+        read_concatenated_tar: concatenated,
     };
 
     // Create output directory if it doesn't exist
@@ -123,14 +666,72 @@ fn handle_extract(
         std::fs::create_dir_all(&out)?;
     }
 
+    // One handler for the whole run, shared by every archive
+    let cancel_flag = install_cancel_handler();
+
+    // Directories stand for the archives inside them. Each of those goes into
+    // a folder named after it, at the same place relative to --out as it was
+    // found, and a failure doesn't stop the rest.
+    let mut jobs: Vec<(PathBuf, Option<PathBuf>)> = Vec::new();
+    let mut from_folders = false;
+    for input in archives {
+        if !input.is_dir() {
+            jobs.push((input, None));
+            continue;
+        }
+        if !recursive {
+            eprintln!(
+                "Error: {} is a directory; pass --recursive to extract the archives in it",
+                input.display()
+            );
+            process::exit(1);
+        }
+
+        from_folders = true;
+        let found = extractor::find_archives(&input, true)?;
+        if found.is_empty() {
+            println!("No archives found in {}", input.display());
+        }
+        for archive in found {
+            let relative = archive
+                .parent()
+                .and_then(|parent| parent.strip_prefix(&input).ok())
+                .unwrap_or(Path::new(""));
+            let parent = out.join(relative);
+            jobs.push((archive, Some(parent)));
+        }
+    }
+
+    let total = jobs.len();
+    let mut failed: Vec<(PathBuf, String)> = Vec::new();
+
     // Process each archive
-    for archive_path in archives {
+    for (archive_path, parent) in jobs {
         println!("\nExtracting: {}", archive_path.display());
 
+        // Pick the folder now, so archives sharing a name don't share a folder
+        let out = match parent {
+            Some(parent) => extractor::unique_output_dir(&parent, &archive_path),
+            None => out.clone(),
+        };
+
         // Check if archive exists
-        if !archive_path.exists() {
+        if !is_url(&archive_path) && !archive_path.exists() {
             eprintln!("Error: Archive not found: {}", archive_path.display());
-            process::exit(1);
+            if !from_folders {
+                process::exit(1);
+            }
+            failed.push((archive_path, "archive not found".to_string()));
+            continue;
+        }
+
+        // Extracting to tmpfs fills memory; warn if the archive won't fit
+        if !is_url(&archive_path) && extractor::memory_backed_space(&out).is_some() {
+            if let Ok(plan) = extractor::plan_extraction(&archive_path, &out) {
+                for warning in &plan.warnings {
+                    eprintln!("Warning: {}", warning);
+                }
+            }
         }
 
         // Create progress bar
@@ -142,38 +743,73 @@ fn handle_extract(
                 .progress_chars("#>-"),
         );
 
-        let cancel_flag = Arc::new(AtomicBool::new(false));
-        let cancel_flag_clone = cancel_flag.clone();
-
-        // Set up Ctrl+C handler
-        ctrlc::set_handler(move || {
-            cancel_flag_clone.store(true, Ordering::SeqCst);
-        })
-        .ok(); // Ignore error if handler already set
-
-        // Track progress
+        // Track progress, keeping counts for the summary printed on cancellation
+        let files_done = Arc::new(AtomicU64::new(0));
+        let bytes_done = Arc::new(AtomicU64::new(0));
+        let files_done_clone = files_done.clone();
+        let bytes_done_clone = bytes_done.clone();
         let pb_clone = pb.clone();
-        let progress_cb = move |file: &str, _bytes_written: u64, total_bytes: Option<u64>| {
-            pb_clone.set_message(format!("{}", file));
-            
-            if let Some(total) = total_bytes {
+        let pb_prompt = pb.clone();
+        let options = ExtractOptions {
+            overwrite_prompt: Some(OverwritePrompt::new(move |existing, _entry| {
+                pb_prompt.suspend(|| ask_overwrite(existing))
+            })),
+            on_progress: Some(ProgressListener::new(move |event| {
+                pb_clone.set_message(event.path.clone());
+                if matches!(event.action, Some(EntryAction::Written | EntryAction::Renamed)) {
+                    files_done_clone.fetch_add(1, Ordering::Relaxed);
+                }
+                bytes_done_clone.store(event.bytes_written, Ordering::Relaxed);
+
+                // The share of the whole job when its size is known, otherwise
+                // of the current file
+                let (done, total) = match (event.total_bytes, event.entry_total) {
+                    (Some(total), _) => (event.bytes_written, total),
+                    (None, Some(total)) => (event.entry_bytes, total),
+                    (None, None) => return true,
+                };
                 if total > 0 {
-                    let percent = (_bytes_written as f64 / total as f64 * 100.0) as u64;
+                    let percent = (done as f64 / total as f64 * 100.0) as u64;
                     pb_clone.set_position(percent.min(100));
                 }
-            }
-            
-            true // Continue extraction
+
+                true // Continue extraction
+            })),
+            ..options.clone()
         };
+        let progress_cb = |_file: &str, _bytes_written: u64, _total_bytes: Option<u64>| true;
 
         // Extract archive
-        match extract(&archive_path, &out, &options, &progress_cb, cancel_flag.clone()) {
+        let result = extract_path_or_url(
+            &archive_path,
+            &out,
+            &options,
+            &progress_cb,
+            cancel_flag.clone(),
+        );
+
+        // With --keep-partial a failure also lists the files it left behind
+        let mut left_behind = Vec::new();
+        let result = match result {
+            Err(ExtractError::Partial { error, stats }) => {
+                left_behind = stats.partial_files;
+                Err(*error)
+            }
+            result => result,
+        };
+        let hints = result
+            .as_ref()
+            .err()
+            .map(ExtractError::hints)
+            .unwrap_or_default();
+
+        match result {
             Ok(stats) => {
                 pb.finish_with_message("Done");
                 
                 if stats.cancelled {
                     println!("✗ Extraction cancelled");
-                    process::exit(130); // Standard exit code for SIGINT
+                    exit_cancelled();
                 } else {
                     println!(
                         "✓ Extracted {} files ({:.2} MB) in {:.2}s",
@@ -181,56 +817,734 @@ fn handle_extract(
                         stats.bytes_written as f64 / 1_048_576.0,
                         stats.duration.as_secs_f64()
                     );
+                    if stats.files_skipped > 0 {
+                        println!("  {} files skipped", stats.files_skipped);
+                    }
+                    if let Some(subfolder) = &stats.subfolder {
+                        println!("  into {}", subfolder);
+                    }
+                    if let Some(check) = &stats.checksum {
+                        println!("  {} matches {}", check.algorithm, check.file);
+                    }
+
+                    for blocked in &stats.blocked {
+                        match &blocked.quarantined_to {
+                            Some(to) => println!(
+                                "Quarantined {} ({}) to {}",
+                                blocked.path, blocked.kind, to
+                            ),
+                            None => println!("Blocked {} ({})", blocked.path, blocked.kind),
+                        }
+                    }
+
+                    for renamed in &stats.renamed {
+                        println!("Renamed {} to {}", renamed.path, renamed.renamed_to);
+                    }
+                    for warning in &stats.warnings {
+                        println!("Warning: {}", warning);
+                    }
+                    for failure in &stats.errors {
+                        eprintln!("Failed {}: {}", failure.path, failure.error);
+                    }
+                    for mismatch in &stats.mismatches {
+                        eprintln!("Mismatch {}: {}", mismatch.path, mismatch.reason);
+                    }
+                    if !stats.mismatches.is_empty() {
+                        let error = format!("{} files failed verification", stats.mismatches.len());
+                        failed.push((archive_path, error));
+                    }
+                }
+            }
+            Err(ExtractError::Cancelled) => {
+                pb.abandon_with_message("Cancelled");
+                let partial = if left_behind.is_empty() {
+                    "partial output removed".to_string()
+                } else {
+                    format!("partial output left in {}", out.display())
+                };
+                println!(
+                    "✗ Extraction cancelled after {} files ({:.2} MB); {}",
+                    files_done.load(Ordering::Relaxed),
+                    bytes_done.load(Ordering::Relaxed) as f64 / 1_048_576.0,
+                    partial
+                );
+                exit_cancelled();
+            }
+            Err(ExtractError::IncompleteVolumes(problems)) => {
+                pb.finish_with_message("Failed");
+                eprintln!(
+                    "Error extracting {}: incomplete volume set",
+                    archive_path.display()
+                );
+                for problem in &problems {
+                    eprintln!("  {}", problem);
                 }
+                print_hints(&hints);
+                failed.push((archive_path, "incomplete volume set".to_string()));
             }
             Err(e) => {
                 pb.finish_with_message("Failed");
                 eprintln!("Error extracting {}: {}", archive_path.display(), e);
-                process::exit(1);
+                for file in &left_behind {
+                    eprintln!("  left behind: {}", file);
+                }
+                print_hints(&hints);
+                failed.push((archive_path, e.to_string()));
             }
         }
+
+        if !from_folders && !failed.is_empty() {
+            process::exit(1);
+        }
+    }
+
+    if from_folders {
+        println!("\nExtracted {} of {} archives", total - failed.len(), total);
+        for (archive_path, error) in &failed {
+            println!("  ✗ {}: {}", archive_path.display(), error);
+        }
+        if !failed.is_empty() {
+            process::exit(1);
+        }
     }
 
     Ok(())
 }
 
-fn handle_probe(archive: PathBuf, json: bool) -> Result<(), Box<dyn std::error::Error>> {
-    use extractor::probe;
+/// Whether an archive argument is a URL to extract from rather than a path.
+fn is_url(archive: &Path) -> bool {
+    cfg!(feature = "http")
+        && archive
+            .to_str()
+            .is_some_and(|s| s.starts_with("http://") || s.starts_with("https://"))
+}
 
-    // Check if archive exists
-    if !archive.exists() {
-        eprintln!("Error: Archive not found: {}", archive.display());
-        process::exit(1);
+/// Extract an archive given as a path or, with the `http` feature, as a URL.
+fn extract_path_or_url(
+    archive: &Path,
+    out: &Path,
+    options: &extractor::ExtractOptions,
+    progress_cb: &extractor::ProgressCallback,
+    cancel_flag: Arc<AtomicBool>,
+) -> Result<extractor::ExtractStats, extractor::ExtractError> {
+    #[cfg(feature = "http")]
+    if is_url(archive) {
+        let url = archive.to_string_lossy();
+        return extractor::extract_url(&url, out, options, progress_cb, cancel_flag);
     }
 
-    // Probe the archive
-    match probe(&archive) {
-        Ok(info) => {
-            if json {
-                // Output as JSON
-                let json_output = serde_json::to_string_pretty(&info)?;
-                println!("{}", json_output);
-            } else {
-                // Output as human-readable text
-                println!("Archive: {}", archive.display());
-                println!("Format: {}", info.format);
-                println!("Entries: {}", info.entries);
-                
-                if let Some(compressed) = info.compressed_bytes {
-                    println!("Compressed: {:.2} MB", compressed as f64 / 1_048_576.0);
+    extractor::extract(archive, out, options, progress_cb, cancel_flag)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_probe(
+    archives: Vec<PathBuf>,
+    json: bool,
+    ndjson: bool,
+    sort: Option<String>,
+    desc: bool,
+    offset: Option<u64>,
+    password: Option<String>,
+    deep: bool,
+    concatenated: bool,
+    filename_encoding: Option<FilenameEncoding>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use extractor::{sort_entries, EntrySort, ExtractError, SortKey};
+
+    // Parse sort key
+    let sort = sort.map(|key| {
+        let key = match key.as_str() {
+            "path" => SortKey::Path,
+            "size" => SortKey::Size,
+            "modified" => SortKey::Modified,
+            _ => {
+                eprintln!(
+                    "Invalid sort key: {}. Use 'path', 'size', or 'modified'.",
+                    key
+                );
+                process::exit(1);
+            }
+        };
+        EntrySort {
+            key,
+            descending: desc,
+        }
+    });
+
+    // Abort long scans on Ctrl+C or termination signals
+    let cancel_flag = install_cancel_handler();
+
+    // Several archives are all probed, with failures reported alongside the
+    // results instead of stopping the batch
+    let batch = archives.len() > 1;
+    let mut results = Vec::new();
+    let mut failed = false;
+
+    for (index, archive) in archives.iter().enumerate() {
+        let result = probe_one(
+            archive,
+            offset,
+            password.as_deref(),
+            deep,
+            concatenated,
+            filename_encoding,
+            cancel_flag.clone(),
+        );
+
+        let info = match result {
+            Ok(mut info) => {
+                if let Some(sort) = sort {
+                    sort_entries(&mut info.entry_list, sort);
                 }
-                
-                if let Some(uncompressed) = info.uncompressed_estimate {
-                    println!("Uncompressed: {:.2} MB (estimated)", uncompressed as f64 / 1_048_576.0);
+                info
+            }
+            Err(ExtractError::Cancelled) => {
+                println!("✗ Probe cancelled");
+                exit_cancelled();
+            }
+            Err(e) => {
+                failed = true;
+                if ndjson || (json && batch) {
+                    let result = ProbeResult::failed(archive, &e);
+                    if ndjson {
+                        println!("{}", serde_json::to_string(&result)?);
+                    } else {
+                        results.push(result);
+                    }
+                } else if batch {
+                    eprintln!("Error probing {}: {}", archive.display(), e);
+                    print_hints(&e.hints());
+                } else {
+                    eprintln!("Error probing archive: {}", e);
+                    print_hints(&e.hints());
                 }
-                
-                println!("Encrypted: {}", if info.encrypted { "Yes" } else { "No" });
+                continue;
             }
-            Ok(())
+        };
+
+        if ndjson {
+            println!(
+                "{}",
+                serde_json::to_string(&ProbeResult::probed(archive, info))?
+            );
+        } else if json && batch {
+            results.push(ProbeResult::probed(archive, info));
+        } else if json {
+            // Output as JSON
+            let json_output = serde_json::to_string_pretty(&info)?;
+            println!("{}", json_output);
+        } else {
+            if index > 0 {
+                println!();
+            }
+            print_probe_info(archive, &info);
         }
-        Err(e) => {
-            eprintln!("Error probing archive: {}", e);
-            process::exit(1);
+    }
+
+    if json && batch {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    }
+
+    if failed {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Probe one archive the way the `probe` options ask, with a spinner showing
+/// running counts while it is scanned.
+fn probe_one(
+    archive: &Path,
+    offset: Option<u64>,
+    password: Option<&str>,
+    deep: bool,
+    concatenated: bool,
+    filename_encoding: Option<FilenameEncoding>,
+    cancel_flag: Arc<AtomicBool>,
+) -> Result<extractor::ArchiveInfo, extractor::ExtractError> {
+    use extractor::{probe_at_offset, probe_deep, probe_with_options, ProbeOptions, ProbeProgress};
+    use indicatif::{ProgressBar, ProgressStyle};
+    use std::time::Duration;
+
+    // Spinner with running counts while the archive is scanned
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.green} {msg}")
+            .expect("Invalid spinner template"),
+    );
+    spinner.enable_steady_tick(Duration::from_millis(100));
+    spinner.set_message("Scanning archive...");
+
+    let spinner_clone = spinner.clone();
+    let progress_cb = move |bytes_scanned: u64, entries_found: u64| {
+        spinner_clone.set_message(format!(
+            "Scanning archive... {} entries, {:.2} MB read",
+            entries_found,
+            bytes_scanned as f64 / 1_048_576.0
+        ));
+    };
+
+    let options = ProbeOptions {
+        password: password.map(str::to_string),
+        read_concatenated_tar: concatenated,
+        filename_encoding,
+        on_progress: Some(ProbeProgress::new(progress_cb)),
+        cancel_flag: Some(cancel_flag),
+    };
+    let result = match offset {
+        Some(offset) => probe_at_offset(archive, offset, &options),
+        None if deep => probe_deep(archive, &options),
+        None => probe_with_options(archive, &options),
+    };
+    spinner.finish_and_clear();
+    result
+}
+
+/// One archive's entry in `probe --json` output for several archives, or in
+/// `probe --ndjson` output.
+#[derive(serde::Serialize)]
+struct ProbeResult {
+    /// Archive path as given on the command line
+    path: String,
+
+    /// Probe result, on success
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    info: Option<extractor::ArchiveInfo>,
+
+    /// Why the archive couldn't be probed, on failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ProbeError>,
+}
+
+/// Failure to probe one archive.
+#[derive(serde::Serialize)]
+struct ProbeError {
+    /// Stable identifier for the kind of failure
+    kind: &'static str,
+
+    /// Human-readable description
+    message: String,
+
+    /// Codes of suggested next steps, such as "missing_volume"
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    hints: Vec<&'static str>,
+}
+
+impl ProbeResult {
+    fn probed(archive: &Path, info: extractor::ArchiveInfo) -> Self {
+        Self {
+            path: archive.display().to_string(),
+            info: Some(info),
+            error: None,
+        }
+    }
+
+    fn failed(archive: &Path, error: &extractor::ExtractError) -> Self {
+        use extractor::ExtractError;
+
+        let kind = match error {
+            ExtractError::NotFound(_) => "not_found",
+            ExtractError::UnsupportedFormat(_) => "unsupported_format",
+            ExtractError::PasswordRequired => "password_required",
+            ExtractError::InvalidPassword => "invalid_password",
+            ExtractError::Corrupted(_) => "corrupted",
+            ExtractError::IncompleteVolumes(_) => "incomplete_volumes",
+            ExtractError::Io(_) => "io",
+            _ => "other",
+        };
+
+        Self {
+            path: archive.display().to_string(),
+            info: None,
+            error: Some(ProbeError {
+                kind,
+                message: error.to_string(),
+                hints: error.hints().into_iter().map(|hint| hint.code()).collect(),
+            }),
+        }
+    }
+}
+
+/// Print the human-readable probe report for one archive.
+fn print_probe_info(archive: &Path, info: &extractor::ArchiveInfo) {
+    use extractor::Integrity;
+
+    println!("Archive: {}", archive.display());
+    println!("Format: {}", info.format);
+    println!("Entries: {}", info.entries);
+
+    if let Some(compressed) = info.compressed_bytes {
+        println!("Compressed: {:.2} MB", compressed as f64 / 1_048_576.0);
+    }
+
+    if let Some(uncompressed) = info.uncompressed_estimate {
+        let note = if info.uncompressed_is_estimate {
+            " (estimated)"
+        } else {
+            ""
+        };
+        println!(
+            "Uncompressed: {:.2} MB{}",
+            uncompressed as f64 / 1_048_576.0,
+            note
+        );
+    }
+
+    println!("Encrypted: {}", if info.encrypted { "Yes" } else { "No" });
+
+    match &info.integrity {
+        Some(Integrity::Ok) => println!("Integrity: OK"),
+        Some(Integrity::Truncated) => println!("Integrity: truncated"),
+        Some(Integrity::Corrupted { detail }) => {
+            println!("Integrity: corrupted ({})", detail)
+        }
+        None => {}
+    }
+    match &info.checksum {
+        Some(check) if check.matches => {
+            println!("Checksum: {} matches {}", check.algorithm, check.file)
+        }
+        Some(check) => println!(
+            "Checksum: {} is {}, {} lists {}",
+            check.algorithm, check.actual, check.file, check.expected
+        ),
+        None => {}
+    }
+
+    if info.header_encrypted {
+        println!("The entry list is encrypted; use --password to list its contents");
+    }
+
+    let summary = &info.summary;
+    println!(
+        "Files: {}, Folders: {}",
+        summary.file_count, summary.directory_count
+    );
+
+    if let Some(largest) = &summary.largest_entry {
+        println!(
+            "Largest: {} ({:.2} MB)",
+            largest.path,
+            largest.size as f64 / 1_048_576.0
+        );
+    }
+
+    if let Some(ratio) = summary.average_compression_ratio {
+        println!("Compression ratio: {:.1}%", ratio * 100.0);
+    }
+
+    if !summary.extensions.is_empty() {
+        let types: Vec<String> = summary
+            .extensions
+            .iter()
+            .take(5)
+            .map(|e| {
+                let name = if e.extension.is_empty() {
+                    "(none)"
+                } else {
+                    &e.extension
+                };
+                format!("{} ({})", name, e.count)
+            })
+            .collect();
+        println!("Types: {}", types.join(", "));
+    }
+
+    let resources = &info.resources;
+    if let Some(inodes) = resources.inodes_needed {
+        println!("Files and folders to create: {}", inodes);
+        if inodes >= extractor::preflight::MANY_ENTRIES_THRESHOLD {
+            println!(
+                "Warning: many small files; extraction may be slow and needs {} free inodes",
+                inodes
+            );
         }
     }
+    if let Some(output) = resources.output_space_bytes {
+        println!("Disk space needed: {:.2} MB", output as f64 / 1_048_576.0);
+    }
+    if let Some(temp) = resources.temp_space_bytes.filter(|&temp| temp > 0) {
+        println!(
+            "Temporary space needed: {:.2} MB",
+            temp as f64 / 1_048_576.0
+        );
+    }
+    if let Some(memory) = resources.peak_memory_bytes {
+        println!("Peak memory: {:.2} MB", memory as f64 / 1_048_576.0);
+    }
+
+    if let Some(root) = &info.single_root {
+        println!("Root folder: {}", root);
+    }
+
+    if info.is_tarbomb {
+        println!("Warning: entries have no common top-level folder; consider extracting into a subfolder");
+    }
+}
+
+fn handle_scan(file: PathBuf, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    use extractor::scan_signatures;
+
+    let matches = scan_signatures(&file)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&matches)?);
+    } else if matches.is_empty() {
+        println!("No embedded archives found in {}", file.display());
+    } else {
+        println!("Embedded archives in {}:", file.display());
+        for m in &matches {
+            println!("  {:>12}  {}", m.offset, m.format);
+        }
+        println!("Use `probe --offset <OFFSET>` to inspect one.");
+    }
+
+    Ok(())
+}
+
+fn handle_cat(
+    archive: PathBuf,
+    entry: String,
+    password: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use extractor::{extract_entry_to_writer, ExtractOptions};
+    use std::io::Write;
+
+    let options = ExtractOptions {
+        password,
+        size_limit_bytes: None,
+        ..ExtractOptions::default()
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    extract_entry_to_writer(&archive, &entry, &mut stdout, &options)?;
+    stdout.flush()?;
+
+    Ok(())
+}
+
+fn handle_test(
+    archives: Vec<PathBuf>,
+    password: Option<String>,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use extractor::{verify, ExtractOptions};
+
+    let options = ExtractOptions {
+        password,
+        ..ExtractOptions::default()
+    };
+
+    let mut reports = Vec::new();
+    for archive in &archives {
+        let report = verify(archive, &options)?;
+        if !json {
+            for entry in &report.entries {
+                if let Some(error) = &entry.error {
+                    eprintln!("Failed {}: {}", entry.path, error);
+                }
+            }
+            for warning in &report.warnings {
+                println!("Warning: {}", warning);
+            }
+            let failed = report.entries.iter().filter(|e| e.error.is_some()).count();
+            match &report.error {
+                Some(error) => println!("✗ {}: {}", archive.display(), error),
+                None if failed > 0 => println!(
+                    "✗ {}: {} of {} files failed",
+                    archive.display(),
+                    failed,
+                    report.entries.len()
+                ),
+                None => println!("✓ {}: {} files OK", archive.display(), report.entries.len()),
+            }
+        }
+        reports.push(report);
+    }
+
+    if json {
+        if reports.len() == 1 {
+            println!("{}", serde_json::to_string_pretty(&reports[0])?);
+        } else {
+            println!("{}", serde_json::to_string_pretty(&reports)?);
+        }
+    }
+    if reports.iter().any(|report| !report.ok) {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn handle_journal(
+    output_dir: PathBuf,
+    json: bool,
+    resume: bool,
+    rollback: bool,
+    password: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use extractor::{read_journal, resume_extraction, ExtractError, ExtractOptions};
+
+    let Some(journal) = read_journal(&output_dir)? else {
+        println!("No interrupted extraction into {}", output_dir.display());
+        return Ok(());
+    };
+
+    if rollback {
+        extractor::rollback(&journal)?;
+        println!(
+            "✓ Rolled back the extraction of {} ({} files removed)",
+            journal.archive_path,
+            journal.created_files.len()
+        );
+        return Ok(());
+    }
+
+    if resume {
+        let options = ExtractOptions {
+            password,
+            ..ExtractOptions::default()
+        };
+        let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+        let cancel_flag = install_cancel_handler();
+
+        println!(
+            "Resuming the extraction of {} after {} entries",
+            journal.archive_path, journal.entries_completed
+        );
+        return match resume_extraction(&output_dir, &options, &progress_cb, cancel_flag) {
+            Ok(stats) => {
+                println!(
+                    "✓ Extracted {} more files ({:.2} MB) in {:.2}s",
+                    stats.files_extracted,
+                    stats.bytes_written as f64 / 1_048_576.0,
+                    stats.duration.as_secs_f64()
+                );
+                Ok(())
+            }
+            Err(ExtractError::Cancelled) => {
+                println!("✗ Resumed extraction cancelled");
+                exit_cancelled();
+            }
+            Err(e) => Err(e.into()),
+        };
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&journal)?);
+        return Ok(());
+    }
+
+    println!("Interrupted extraction of {}", journal.archive_path);
+    println!("  Into:     {}", journal.output_dir);
+    println!(
+        "  Written:  {} entries ({:.2} MB)",
+        journal.entries_completed,
+        journal.bytes_written as f64 / 1_048_576.0
+    );
+    if let Some(current) = &journal.current_file {
+        println!("  Stopped:  while writing {}", current);
+    }
+    println!("Use `journal --resume` to finish it or `journal --rollback` to undo it.");
+
+    Ok(())
+}
+
+/// Install a handler for Ctrl+C, SIGTERM, SIGHUP and console close events that
+/// sets the returned cancel flag, so the running operation can stop at the next
+/// entry and report what it finished. A second signal exits immediately.
+fn install_cancel_handler() -> Arc<AtomicBool> {
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let cancel_flag_clone = cancel_flag.clone();
+
+    ctrlc::set_handler(move || {
+        if cancel_flag_clone.swap(true, Ordering::SeqCst) {
+            process::exit(EXIT_CANCELLED);
+        }
+    })
+    .ok(); // Ignore error if handler already set
+
+    cancel_flag
+}
+
+/// Ask on the terminal what to do with the existing file `existing`, for
+/// `--overwrite ask`. Stops the extraction when there's no answer to read.
+fn ask_overwrite(existing: &Path) -> extractor::OverwriteDecision {
+    use extractor::{OverwriteDecision, OverwriteMode};
+    use std::io::Write;
+
+    loop {
+        eprint!(
+            "{} already exists. Replace? [y]es, [n]o, [r]ename, [A]ll, [N]one, [q]uit: ",
+            existing.display()
+        );
+        let _ = std::io::stderr().flush();
+        let mut answer = String::new();
+        match std::io::stdin().read_line(&mut answer) {
+            Ok(0) | Err(_) => return OverwriteDecision::Abort,
+            Ok(_) => {}
+        }
+        match answer.trim() {
+            "y" | "yes" => return OverwriteDecision::Replace,
+            "n" | "no" => return OverwriteDecision::Skip,
+            "r" | "rename" => return OverwriteDecision::Rename,
+            "A" | "all" => return OverwriteDecision::ApplyToAll(OverwriteMode::Replace),
+            "N" | "none" => return OverwriteDecision::ApplyToAll(OverwriteMode::Skip),
+            "q" | "quit" => return OverwriteDecision::Abort,
+            _ => {}
+        }
+    }
+}
+
+/// Print the suggested next steps for a failure.
+fn print_hints(hints: &[extractor::ErrorHint]) {
+    for hint in hints {
+        eprintln!("  hint: {}", hint);
+    }
+}
+
+/// Flush pending output and exit with the cancelled exit code.
+fn exit_cancelled() -> ! {
+    use std::io::Write;
+
+    let _ = std::io::stdout().flush();
+    let _ = std::io::stderr().flush();
+    process::exit(EXIT_CANCELLED);
+}
+
+/// Parse a permission mode given in octal (e.g. "644" or "0755").
+fn parse_mode(value: &str) -> Result<u32, String> {
+    match u32::from_str_radix(value, 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(mode),
+        _ => Err(format!("invalid octal mode: {}", value)),
+    }
+}
+
+/// Parse a content type name for `--deny-content`.
+fn parse_content_kind(value: &str) -> Result<ContentKind, String> {
+    match value {
+        "executable" => Ok(ContentKind::Executable),
+        "script" => Ok(ContentKind::Script),
+        _ => Err(format!(
+            "unknown content type: {} (use executable or script)",
+            value
+        )),
+    }
+}
+
+/// Parse an encoding label for `--filename-encoding`.
+fn parse_filename_encoding(value: &str) -> Result<FilenameEncoding, String> {
+    FilenameEncoding::for_label(value).ok_or_else(|| format!("unknown encoding: {}", value))
+}
+
+/// Whether the CLI runs with root privileges.
+#[cfg(unix)]
+fn running_as_root() -> bool {
+    // SAFETY: geteuid has no preconditions and cannot fail
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(not(unix))]
+fn running_as_root() -> bool {
+    false
 }
@@ -0,0 +1,205 @@
+//! Caching of probe results keyed by a cheap file fingerprint.
+//!
+//! The desktop app probes the same archive on hover, on selection and again
+//! before extracting. A fingerprint made of the file size, modification time and
+//! a hash of the first and last blocks lets repeated probes of an unchanged file
+//! return immediately, without trusting the path alone.
+
+use crate::error::ExtractError;
+use crate::types::ArchiveInfo;
+use crate::ProbeProgressCallback;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Number of bytes hashed at the start and at the end of the file.
+const FINGERPRINT_BLOCK: u64 = 64 * 1024;
+
+/// Default number of archives kept by [`ProbeCache::default`].
+const DEFAULT_CAPACITY: usize = 64;
+
+/// Cheap identity of a file's contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// File size in bytes
+    pub size: u64,
+
+    /// Last modification time (if the platform reports one)
+    pub modified: Option<SystemTime>,
+
+    /// SHA-256 over the first and last blocks of the file
+    pub edge_hash: [u8; 32],
+}
+
+impl Fingerprint {
+    /// Compute the fingerprint of the file at `path`.
+    pub fn of(path: &Path) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let metadata = file.metadata()?;
+        let size = metadata.len();
+
+        let mut hasher = Sha256::new();
+        io::copy(&mut (&mut file).take(FINGERPRINT_BLOCK), &mut hasher)?;
+        if size > FINGERPRINT_BLOCK {
+            let tail_start = size
+                .saturating_sub(FINGERPRINT_BLOCK)
+                .max(FINGERPRINT_BLOCK);
+            file.seek(SeekFrom::Start(tail_start))?;
+            io::copy(&mut file, &mut hasher)?;
+        }
+
+        Ok(Self {
+            size,
+            modified: metadata.modified().ok(),
+            edge_hash: hasher.finalize().into(),
+        })
+    }
+}
+
+/// Cached probe result for one archive.
+struct CacheEntry {
+    fingerprint: Fingerprint,
+    info: ArchiveInfo,
+    last_used: u64,
+}
+
+struct CacheState {
+    entries: HashMap<PathBuf, CacheEntry>,
+    clock: u64,
+}
+
+/// Thread-safe cache of probe results.
+///
+/// Entries are keyed by canonical path and validated against a [`Fingerprint`]
+/// on every lookup, so a file that was replaced or modified is probed again. The
+/// least recently used entry is evicted once `capacity` archives are cached.
+/// For multi-part archives only the part passed in is fingerprinted.
+pub struct ProbeCache {
+    state: Mutex<CacheState>,
+    capacity: usize,
+}
+
+impl Default for ProbeCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl ProbeCache {
+    /// Create a cache holding up to `capacity` archives.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            state: Mutex::new(CacheState {
+                entries: HashMap::new(),
+                clock: 0,
+            }),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Probe an archive, returning the cached result if the file is unchanged.
+    pub fn probe(&self, path: &Path) -> Result<ArchiveInfo, ExtractError> {
+        self.probe_with_progress(path, &|_, _| {}, Arc::new(AtomicBool::new(false)))
+    }
+
+    /// Like [`crate::probe_with_progress`], but served from the cache when the
+    /// file is unchanged (in which case no progress is reported).
+    pub fn probe_with_progress(
+        &self,
+        path: &Path,
+        progress_cb: &ProbeProgressCallback,
+        cancel_flag: Arc<AtomicBool>,
+    ) -> Result<ArchiveInfo, ExtractError> {
+        if !path.exists() {
+            return Err(ExtractError::NotFound(path.to_path_buf()));
+        }
+
+        let key = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let fingerprint = Fingerprint::of(path)?;
+
+        if let Some(info) = self.lookup(&key, &fingerprint) {
+            return Ok(info);
+        }
+
+        let info = crate::probe::probe_archive_with_progress(path, progress_cb, cancel_flag)?;
+        self.insert(key, fingerprint, info.clone());
+
+        Ok(info)
+    }
+
+    /// Drop the cached result for `path`, if any.
+    pub fn invalidate(&self, path: &Path) {
+        let key = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.lock().entries.remove(&key);
+    }
+
+    /// Drop all cached results.
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+    }
+
+    /// Number of cached archives.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lookup(&self, key: &Path, fingerprint: &Fingerprint) -> Option<ArchiveInfo> {
+        let mut state = self.lock();
+        state.clock += 1;
+        let now = state.clock;
+
+        match state.entries.get_mut(key) {
+            Some(entry) if entry.fingerprint == *fingerprint => {
+                entry.last_used = now;
+                Some(entry.info.clone())
+            }
+            Some(_) => {
+                // Stale: the file changed since it was cached
+                state.entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&self, key: PathBuf, fingerprint: Fingerprint, info: ArchiveInfo) {
+        let mut state = self.lock();
+        state.clock += 1;
+        let now = state.clock;
+
+        if !state.entries.contains_key(&key) && state.entries.len() >= self.capacity {
+            let oldest = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(path, _)| path.clone());
+            if let Some(oldest) = oldest {
+                state.entries.remove(&oldest);
+            }
+        }
+
+        state.entries.insert(
+            key,
+            CacheEntry {
+                fingerprint,
+                info,
+                last_used: now,
+            },
+        );
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        // A panic while holding the lock leaves the map itself intact
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,372 @@
+//! Support for archives embedded inside other files (carving).
+//!
+//! Firmware blobs, installers and self-extracting executables often carry an
+//! archive at some offset. These helpers find candidate archive signatures and
+//! copy the embedded data into a temporary file so the regular probe and extract
+//! code paths can handle it.
+
+use crate::error::ExtractError;
+use crate::types::{ArchiveInfo, Compression, ExtractOptions, ExtractStats, Format, ProbeOptions};
+use crate::ProgressCallback;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use tempfile::TempDir;
+use ts_rs::TS;
+
+/// A candidate archive found inside another file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct SignatureMatch {
+    /// Byte offset where the embedded archive starts
+    #[ts(type = "number")]
+    pub offset: u64,
+
+    /// Archive format suggested by the signature
+    pub format: Format,
+}
+
+/// Size of the chunks read while scanning.
+const SCAN_CHUNK: usize = 1024 * 1024;
+
+/// Bytes carried over between chunks so signatures spanning a boundary are found.
+/// Covers the tar magic at offset 257 plus its length.
+const SCAN_OVERLAP: usize = 263;
+
+/// Scan a file for embedded archive signatures.
+///
+/// ZIP local file headers following an earlier ZIP match are skipped until an end
+/// of central directory record is seen, so each ZIP archive is reported once. The
+/// compressed stream formats (gzip, bzip2, xz) are reported as such; whether they
+/// wrap a tar archive is decided when the data is opened.
+pub fn scan_signatures(path: &Path) -> Result<Vec<SignatureMatch>, ExtractError> {
+    if !path.exists() {
+        return Err(ExtractError::NotFound(path.to_path_buf()));
+    }
+
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut matches = Vec::new();
+    let mut in_zip = false;
+    let mut next_tar_header = None;
+
+    // `window` holds the overlap from the previous chunk followed by new data
+    let mut window: Vec<u8> = Vec::with_capacity(SCAN_CHUNK + SCAN_OVERLAP);
+    let mut window_start = 0u64;
+    let mut chunk = vec![0u8; SCAN_CHUNK];
+    let mut eof = false;
+
+    while !eof {
+        let n = read_full(&mut reader, &mut chunk)?;
+        eof = n < chunk.len();
+        window.extend_from_slice(&chunk[..n]);
+
+        // Leave the tail for the next round unless this is the last chunk
+        let scan_end = if eof {
+            window.len()
+        } else {
+            window.len().saturating_sub(SCAN_OVERLAP)
+        };
+
+        for pos in 0..scan_end {
+            let rest = &window[pos..];
+            let offset = window_start + pos as u64;
+
+            if rest.starts_with(b"PK\x05\x06") {
+                in_zip = false;
+            } else if rest.starts_with(b"PK\x03\x04") {
+                if !in_zip {
+                    in_zip = true;
+                    matches.push(signature(offset, Format::Zip));
+                }
+            } else if let Some(format) = match_signature(rest) {
+                matches.push(signature(offset, format));
+            } else if rest.len() >= 262 && &rest[257..262] == b"ustar" {
+                // Tar magic sits 257 bytes into each header; only report headers
+                // that don't directly follow the previous member
+                if next_tar_header != Some(offset) {
+                    matches.push(signature(offset, Format::Tar(Compression::None)));
+                }
+                next_tar_header =
+                    tar_member_size(rest).map(|size| offset + 512 + size.div_ceil(512) * 512);
+            }
+        }
+
+        window.drain(..scan_end);
+        window_start += scan_end as u64;
+    }
+
+    Ok(matches)
+}
+
+/// Match the fixed-offset signatures of the non-ZIP formats.
+fn match_signature(data: &[u8]) -> Option<Format> {
+    if data.starts_with(b"7z\xBC\xAF\x27\x1C") {
+        Some(Format::SevenZ)
+    } else if data.starts_with(b"Rar!\x1A\x07") {
+        Some(Format::Rar)
+    } else if data.starts_with(b"\xFD7zXZ\x00") {
+        Some(Format::Xz)
+    } else if data.len() >= 10
+        && data.starts_with(b"BZh")
+        && (b'1'..=b'9').contains(&data[3])
+        && &data[4..10] == b"\x31\x41\x59\x26\x53\x59"
+    {
+        Some(Format::Bzip2)
+    } else if data.len() >= 4 && data.starts_with(b"\x1F\x8B\x08") && data[3] & 0xE0 == 0 {
+        // Deflate method with the reserved flag bits clear
+        Some(Format::Gzip)
+    } else {
+        None
+    }
+}
+
+/// Parse the octal size field of a tar header.
+fn tar_member_size(header: &[u8]) -> Option<u64> {
+    let field = std::str::from_utf8(header.get(124..136)?).ok()?;
+    u64::from_str_radix(field.trim_matches(|c: char| c == '\0' || c == ' '), 8).ok()
+}
+
+fn signature(offset: u64, format: Format) -> SignatureMatch {
+    SignatureMatch { offset, format }
+}
+
+/// Probe an archive embedded in `path` starting at `offset`.
+///
+/// The format is taken from the signature at that offset. The reported
+/// `compressed_bytes` covers the embedded data through the end of the file.
+/// The embedded archive is read the way `options` ask (see
+/// [`crate::probe_with_options`]).
+pub fn probe_at_offset(
+    path: &Path,
+    offset: u64,
+    options: &ProbeOptions,
+) -> Result<ArchiveInfo, ExtractError> {
+    let carved = carve(path, offset, tempfile::tempdir()?)?;
+    crate::probe::probe_archive_with_options(&carved.path, options)
+}
+
+/// Extract an archive embedded in `path` starting at `offset`.
+///
+/// Behaves like [`crate::extract`] once the embedded data has been copied out.
+pub fn extract_at_offset(
+    path: &Path,
+    offset: u64,
+    output_dir: &Path,
+    options: &ExtractOptions,
+    progress_cb: &ProgressCallback,
+    cancel_flag: Arc<AtomicBool>,
+) -> Result<ExtractStats, ExtractError> {
+    let dir = tempfile::Builder::new()
+        .prefix(".unarchiver-carved-")
+        .tempdir_in(crate::extract::temp_dir_for(output_dir, options)?)?;
+    let carved = carve(path, offset, dir)?;
+    crate::extract::extract_archive(&carved.path, output_dir, options, progress_cb, cancel_flag)
+}
+
+/// Embedded archive copied to a temporary file (removed on drop).
+struct CarvedArchive {
+    path: PathBuf,
+    _dir: TempDir,
+}
+
+/// Copy the data from `offset` to the end of `path` into a file in `dir` whose
+/// extension matches the detected format.
+fn carve(path: &Path, offset: u64, dir: TempDir) -> Result<CarvedArchive, ExtractError> {
+    if !path.exists() {
+        return Err(ExtractError::NotFound(path.to_path_buf()));
+    }
+
+    let mut file = File::open(path)?;
+    if offset >= file.metadata()?.len() {
+        return Err(ExtractError::Corrupted(format!(
+            "Offset {} is past the end of the file",
+            offset
+        )));
+    }
+
+    let extension = match detect_stream_format(&mut file, offset)? {
+        Some(format) => format_extension(format),
+        None => {
+            return Err(ExtractError::UnsupportedFormat(format!(
+                "No archive signature at offset {}",
+                offset
+            )))
+        }
+    };
+
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "embedded".to_string());
+
+    let carved_path = dir
+        .path()
+        .join(format!("{}_{}.{}", stem, offset, extension));
+
+    file.seek(SeekFrom::Start(offset))?;
+    let mut out = File::create(&carved_path)?;
+    io::copy(&mut file, &mut out)?;
+    out.flush()?;
+
+    Ok(CarvedArchive {
+        path: carved_path,
+        _dir: dir,
+    })
+}
+
+/// Work out the format of the archive starting at `offset` in `source`,
+/// peeking inside compressed streams to tell tarballs from single compressed
+/// files.
+pub(crate) fn detect_stream_format<R: Read + Seek>(
+    source: &mut R,
+    offset: u64,
+) -> Result<Option<Format>, ExtractError> {
+    source.seek(SeekFrom::Start(offset))?;
+    let mut header = vec![0u8; 512];
+    let n = read_full(source, &mut header)?;
+    header.truncate(n);
+
+    if header.starts_with(b"PK\x03\x04") {
+        return Ok(Some(Format::Zip));
+    }
+    if header.len() >= 262 && &header[257..262] == b"ustar" {
+        return Ok(Some(Format::Tar(Compression::None)));
+    }
+
+    let format = match match_signature(&header) {
+        Some(format) => format,
+        None => return Ok(None),
+    };
+
+    if !format.is_single_file() {
+        return Ok(Some(format));
+    }
+
+    let compression = format.compression();
+    source.seek(SeekFrom::Start(offset))?;
+    let wraps_tar = decompressed_prefix(compression, BufReader::new(&mut *source))
+        .map(|prefix| prefix.len() >= 262 && &prefix[257..262] == b"ustar")
+        .unwrap_or(false);
+
+    Ok(Some(if wraps_tar {
+        Format::Tar(compression)
+    } else {
+        format
+    }))
+}
+
+/// Detect the format of the archive at the start of `source`, failing if it
+/// has no known signature.
+pub(crate) fn stream_format<R: Read + Seek>(source: &mut R) -> Result<Format, ExtractError> {
+    detect_stream_format(source, 0)?
+        .ok_or_else(|| ExtractError::UnsupportedFormat("No archive signature found".to_string()))
+}
+
+/// File extension that makes format detection pick `format` again.
+fn format_extension(format: Format) -> &'static str {
+    match format {
+        Format::Zip => "zip",
+        Format::Tar(Compression::None) => "tar",
+        Format::Tar(Compression::Gzip) => "tar.gz",
+        Format::Tar(Compression::Bzip2) => "tar.bz2",
+        Format::Tar(Compression::Xz) => "tar.xz",
+        Format::Gzip => "gz",
+        Format::Bzip2 => "bz2",
+        Format::Xz => "xz",
+        Format::SevenZ => "7z",
+        Format::Rar => "rar",
+    }
+}
+
+/// Decompress just the first 512 bytes of a compressed stream.
+fn decompressed_prefix<R: io::BufRead>(compression: Compression, reader: R) -> io::Result<Vec<u8>> {
+    let mut prefix = vec![0u8; 512];
+
+    let n = match compression {
+        Compression::Gzip => read_full(&mut flate2::read::GzDecoder::new(reader), &mut prefix)?,
+        Compression::Bzip2 => read_full(&mut bzip2::read::BzDecoder::new(reader), &mut prefix)?,
+        Compression::Xz => {
+            // lzma-rs only decompresses into a writer, so stop it once the
+            // prefix is full by failing the write
+            let mut sink = PrefixWriter {
+                buf: Vec::with_capacity(512),
+            };
+            let mut reader = reader;
+            let _ = lzma_rs::xz_decompress(&mut reader, &mut sink);
+            prefix = sink.buf;
+            prefix.len()
+        }
+        Compression::None => 0,
+    };
+
+    prefix.truncate(n);
+    Ok(prefix)
+}
+
+/// Writer that keeps the first 512 bytes and then refuses further data.
+struct PrefixWriter {
+    buf: Vec<u8>,
+}
+
+impl Write for PrefixWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let room = 512 - self.buf.len();
+        if room == 0 {
+            return Err(io::Error::other("prefix complete"));
+        }
+        let n = room.min(data.len());
+        self.buf.extend_from_slice(&data[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Read until `buf` is full or the reader is exhausted.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut total = 0;
+    while total < buf.len() {
+        match reader.read(&mut buf[total..]) {
+            Ok(0) => break,
+            Ok(n) => total += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_signature() {
+        assert_eq!(
+            match_signature(b"7z\xBC\xAF\x27\x1C\x00\x04"),
+            Some(Format::SevenZ)
+        );
+        assert_eq!(match_signature(b"Rar!\x1A\x07\x01\x00"), Some(Format::Rar));
+        assert_eq!(match_signature(b"\xFD7zXZ\x00\x00\x04"), Some(Format::Xz));
+        assert_eq!(match_signature(b"BZh91AY&SY\x00"), Some(Format::Bzip2));
+        assert_eq!(match_signature(b"\x1F\x8B\x08\x00\x00"), Some(Format::Gzip));
+
+        // Reserved gzip flag bits set, or a bare "BZh" prefix, are rejected
+        assert_eq!(match_signature(b"\x1F\x8B\x08\xE0\x00"), None);
+        assert_eq!(match_signature(b"BZh9 not bzip"), None);
+        assert_eq!(match_signature(b"plain text"), None);
+    }
+
+    #[test]
+    fn test_tar_member_size() {
+        let mut header = [0u8; 512];
+        header[124..136].copy_from_slice(b"00000001750\0");
+        assert_eq!(tar_member_size(&header), Some(1000));
+        assert_eq!(tar_member_size(&[0u8; 100]), None);
+    }
+}
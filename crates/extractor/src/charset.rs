@@ -0,0 +1,144 @@
+//! Decoding ZIP entry names stored in a legacy code page.
+//!
+//! ZIP archives only mark names as UTF-8 with a flag. Names without it are in
+//! whatever code page the archiving tool used: officially CP437, in practice
+//! the system's OEM code page (CP932 on Japanese Windows, GBK on Chinese
+//! Windows, ...) or even unflagged UTF-8. Such names are decoded with an
+//! encoding detected from all of an archive's legacy names, unless one is
+//! given (see `ExtractOptions::filename_encoding`).
+
+use encoding_rs::{Encoding, BIG5, EUC_JP, EUC_KR, GBK, SHIFT_JIS, UTF_8};
+use std::fmt;
+
+/// Character set of the entry names an archive doesn't mark as UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilenameEncoding {
+    /// IBM PC code page 437, the ZIP format's default
+    Cp437,
+    /// An encoding of the WHATWG Encoding Standard, such as UTF-8, Shift_JIS
+    /// (CP932) or GBK
+    Other(&'static Encoding),
+}
+
+/// Encodings worth guessing for legacy names besides CP437 and UTF-8: those of
+/// Windows versions whose OEM code page is a multi-byte one
+const GUESSED_ENCODINGS: &[&Encoding] = &[SHIFT_JIS, GBK, BIG5, EUC_KR, EUC_JP];
+
+impl FilenameEncoding {
+    /// Encoding named `label`: `cp437` (or `ibm437`, `437`) or any label of
+    /// the Encoding Standard, such as `utf-8`, `shift_jis`, `cp932` or `gbk`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use extractor::FilenameEncoding;
+    ///
+    /// assert_eq!(FilenameEncoding::for_label("CP437"), Some(FilenameEncoding::Cp437));
+    /// assert_eq!(FilenameEncoding::for_label("cp932").unwrap().to_string(), "Shift_JIS");
+    /// assert_eq!(FilenameEncoding::for_label("klingon"), None);
+    /// ```
+    pub fn for_label(label: &str) -> Option<Self> {
+        let label = label.trim();
+        if ["cp437", "ibm437", "437"]
+            .iter()
+            .any(|name| label.eq_ignore_ascii_case(name))
+        {
+            return Some(Self::Cp437);
+        }
+        // Not part of the Encoding Standard, but what Windows calls Shift_JIS
+        if label.eq_ignore_ascii_case("cp932") {
+            return Some(Self::Other(SHIFT_JIS));
+        }
+        Encoding::for_label(label.as_bytes()).map(Self::Other)
+    }
+
+    /// Guess the encoding of an archive's legacy names from their raw bytes.
+    ///
+    /// Names that are valid UTF-8 throughout are taken for UTF-8. Otherwise a
+    /// multi-byte code page is picked if the names read as one without errors,
+    /// and CP437 is kept if not.
+    pub(crate) fn detect<'a>(names: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        let names: Vec<&[u8]> = names.into_iter().filter(|name| !name.is_ascii()).collect();
+        if names.iter().all(|name| std::str::from_utf8(name).is_ok()) {
+            return if names.is_empty() {
+                Self::Cp437
+            } else {
+                Self::Other(UTF_8)
+            };
+        }
+
+        let mut detector = chardetng::EncodingDetector::new();
+        for name in &names {
+            detector.feed(name, false);
+            detector.feed(b"\n", false);
+        }
+        detector.feed(b"", true);
+        let guess = detector.guess(None, false);
+        let decodes = names.iter().all(|name| {
+            guess
+                .decode_without_bom_handling_and_without_replacement(name)
+                .is_some()
+        });
+        if decodes && GUESSED_ENCODINGS.contains(&guess) {
+            Self::Other(guess)
+        } else {
+            Self::Cp437
+        }
+    }
+
+    /// Decode the legacy name `raw`, which the `zip` crate read as CP437 into
+    /// `cp437`.
+    pub(crate) fn decode(self, raw: &[u8], cp437: &str) -> String {
+        match self {
+            Self::Cp437 => cp437.to_string(),
+            Self::Other(encoding) => encoding.decode_without_bom_handling(raw).0.into_owned(),
+        }
+    }
+}
+
+impl fmt::Display for FilenameEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cp437 => f.write_str("IBM437"),
+            Self::Other(encoding) => f.write_str(encoding.name()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(encoding: &'static Encoding, names: &[&str]) -> Vec<Vec<u8>> {
+        names
+            .iter()
+            .map(|name| encoding.encode(name).0.into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_filename_encoding() {
+        let japanese = encode(SHIFT_JIS, &["日本語のファイル名.txt", "資料/報告書.docx"]);
+        let detected = FilenameEncoding::detect(japanese.iter().map(Vec::as_slice));
+        assert_eq!(detected, FilenameEncoding::Other(SHIFT_JIS));
+        assert_eq!(detected.decode(&japanese[1], ""), "資料/報告書.docx");
+
+        let chinese = encode(GBK, &["中文文件名.txt", "文档/报告.docx"]);
+        let detected = FilenameEncoding::detect(chinese.iter().map(Vec::as_slice));
+        assert_eq!(detected, FilenameEncoding::Other(GBK));
+
+        let utf8 = ["résumé.pdf".as_bytes(), b"plain.txt"];
+        assert_eq!(
+            FilenameEncoding::detect(utf8),
+            FilenameEncoding::Other(UTF_8)
+        );
+
+        // "Größe.txt" in CP437
+        let german: &[u8] = b"Gr\x94\xe1e.txt";
+        assert_eq!(FilenameEncoding::detect([german]), FilenameEncoding::Cp437);
+        assert_eq!(
+            FilenameEncoding::detect([&b"ascii.txt"[..]]),
+            FilenameEncoding::Cp437
+        );
+    }
+}
@@ -0,0 +1,307 @@
+//! Checking an archive against a checksum file published next to it.
+//!
+//! Download pages often offer `archive.zip.sha256` (or `.sha512`, `.md5`)
+//! beside the archive. Such a file holds the archive's digest on its own, in
+//! the `sha256sum` format (`digest  name`, one line per file) or in the BSD
+//! format (`SHA256 (name) = digest`).
+
+use crate::error::ExtractError;
+use crate::provenance::hex_digest;
+use crate::types::{ChecksumAlgorithm, ChecksumCheck};
+use sha2::{Digest, Sha256, Sha512};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Algorithms looked for, strongest first.
+const ALGORITHMS: [ChecksumAlgorithm; 3] = [
+    ChecksumAlgorithm::Sha512,
+    ChecksumAlgorithm::Sha256,
+    ChecksumAlgorithm::Md5,
+];
+
+/// Hash the archive at `path` and compare the digest with the one its checksum
+/// file lists.
+///
+/// Looks for `<archive>.sha512`, `<archive>.sha256` and `<archive>.md5` in
+/// that order and returns `None` if there is none. A checksum file that lists
+/// no digest for the archive is an error.
+pub fn verify_checksum(path: &Path) -> Result<Option<ChecksumCheck>, ExtractError> {
+    if !path.exists() {
+        return Err(ExtractError::NotFound(path.to_path_buf()));
+    }
+    let Some((file, algorithm)) = find_checksum_file(path) else {
+        return Ok(None);
+    };
+
+    let listing = fs::read_to_string(&file)?;
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let expected = expected_digest(&listing, &name, algorithm).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} lists no {} digest for {}",
+                file.display(),
+                algorithm,
+                name
+            ),
+        )
+    })?;
+    let actual = hash_file(path, algorithm)?;
+
+    Ok(Some(ChecksumCheck {
+        file: file.display().to_string(),
+        algorithm,
+        matches: actual == expected,
+        expected,
+        actual,
+    }))
+}
+
+/// The checksum file next to the archive at `path`, if there is one.
+fn find_checksum_file(path: &Path) -> Option<(PathBuf, ChecksumAlgorithm)> {
+    ALGORITHMS.into_iter().find_map(|algorithm| {
+        let mut file = path.as_os_str().to_owned();
+        file.push(".");
+        file.push(algorithm.extension());
+        let file = PathBuf::from(file);
+        file.is_file().then_some((file, algorithm))
+    })
+}
+
+/// The lowercase digest `listing` gives for the file `name`, or the only
+/// digest it gives, for a renamed download.
+fn expected_digest(listing: &str, name: &str, algorithm: ChecksumAlgorithm) -> Option<String> {
+    let digests: Vec<(Option<&str>, &str)> = listing
+        .lines()
+        .filter_map(|line| parse_line(line.trim()))
+        .filter(|(_, digest)| {
+            digest.len() == algorithm.hex_len() && digest.chars().all(|c| c.is_ascii_hexdigit())
+        })
+        .collect();
+
+    let named = digests.iter().find(|(listed, _)| {
+        // Listed names may carry a directory, such as `./archive.zip`
+        listed.is_some_and(|listed| listed.rsplit(['/', '\\']).next() == Some(name))
+    });
+    let (_, digest) = match (named, digests.as_slice()) {
+        (Some(named), _) => named,
+        (None, [only]) => only,
+        (None, _) => return None,
+    };
+    Some(digest.to_ascii_lowercase())
+}
+
+/// Split a checksum line into the file name, if it gives one, and the digest.
+fn parse_line(line: &str) -> Option<(Option<&str>, &str)> {
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    // BSD format: `SHA256 (archive.zip) = digest`
+    if let Some((head, digest)) = line.rsplit_once(") = ") {
+        let (_, name) = head.split_once(" (")?;
+        return Some((Some(name), digest.trim()));
+    }
+    // sha256sum format: `digest  archive.zip`, or `digest *archive.zip` for
+    // binary mode
+    match line.split_once(char::is_whitespace) {
+        Some((digest, name)) => {
+            let name = name.trim_start();
+            Some((Some(name.strip_prefix('*').unwrap_or(name)), digest))
+        }
+        None => Some((None, line)),
+    }
+}
+
+/// Lowercase hex digest of the file at `path`.
+fn hash_file(path: &Path, algorithm: ChecksumAlgorithm) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    Ok(match algorithm {
+        ChecksumAlgorithm::Md5 => {
+            let mut hasher = Md5::new();
+            io::copy(&mut file, &mut hasher)?;
+            hasher
+                .finish()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect()
+        }
+        ChecksumAlgorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            io::copy(&mut file, &mut hasher)?;
+            hex_digest(hasher)
+        }
+        ChecksumAlgorithm::Sha512 => {
+            let mut hasher = Sha512::new();
+            io::copy(&mut file, &mut hasher)?;
+            hex_digest(hasher)
+        }
+    })
+}
+
+/// Per-round shift amounts of MD5 (RFC 1321).
+const MD5_SHIFTS: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9,
+    14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15,
+    21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+/// Per-round constants of MD5, the integer part of `abs(sin(i + 1)) * 2^32`.
+const MD5_CONSTANTS: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+/// MD5 (RFC 1321), which is broken for security but still what many download
+/// pages publish.
+struct Md5 {
+    state: [u32; 4],
+    /// Data not yet making up a whole block
+    block: [u8; 64],
+    /// Number of bytes hashed so far
+    len: u64,
+}
+
+impl Md5 {
+    fn new() -> Self {
+        Self {
+            state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476],
+            block: [0; 64],
+            len: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let filled = (self.len % 64) as usize;
+            let take = data.len().min(64 - filled);
+            self.block[filled..filled + take].copy_from_slice(&data[..take]);
+            self.len += take as u64;
+            data = &data[take..];
+            if filled + take == 64 {
+                self.compress();
+            }
+        }
+    }
+
+    fn finish(mut self) -> [u8; 16] {
+        let bits = self.len.wrapping_mul(8);
+        self.update(&[0x80]);
+        while self.len % 64 != 56 {
+            self.update(&[0]);
+        }
+        self.update(&bits.to_le_bytes());
+
+        let mut digest = [0; 16];
+        for (bytes, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        digest
+    }
+
+    /// Mix the full block into the state.
+    fn compress(&mut self) {
+        let mut words = [0u32; 16];
+        for (word, bytes) in words.iter_mut().zip(self.block.chunks_exact(4)) {
+            *word = u32::from_le_bytes(bytes.try_into().expect("4 bytes"));
+        }
+
+        let [mut a, mut b, mut c, mut d] = self.state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let f = f
+                .wrapping_add(a)
+                .wrapping_add(MD5_CONSTANTS[i])
+                .wrapping_add(words[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(MD5_SHIFTS[i]));
+        }
+
+        for (state, word) in self.state.iter_mut().zip([a, b, c, d]) {
+            *state = state.wrapping_add(word);
+        }
+    }
+}
+
+impl Write for Md5 {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.update(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn md5_hex(data: &[u8]) -> String {
+        let mut hasher = Md5::new();
+        hasher.update(data);
+        hasher
+            .finish()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    #[test]
+    fn test_md5() {
+        assert_eq!(md5_hex(b""), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(md5_hex(b"abc"), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(
+            md5_hex(
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890"
+            ),
+            "57edf4a22be3c955ac49da2e2107b67a"
+        );
+    }
+
+    #[test]
+    fn test_expected_digest() {
+        let md5 = "900150983cd24fb0d6963f7d28e17f72";
+        let other = "d41d8cd98f00b204e9800998ecf8427e";
+        let algorithm = ChecksumAlgorithm::Md5;
+
+        let bare = format!("{}\n", md5.to_uppercase());
+        assert_eq!(
+            expected_digest(&bare, "a.zip", algorithm).as_deref(),
+            Some(md5)
+        );
+
+        let listing = format!("{}  other.zip\n{} *dist/a.zip\n", other, md5);
+        assert_eq!(
+            expected_digest(&listing, "a.zip", algorithm).as_deref(),
+            Some(md5)
+        );
+        assert_eq!(expected_digest(&listing, "b.zip", algorithm), None);
+
+        let bsd = format!("MD5 (a.zip) = {}\n", md5);
+        assert_eq!(
+            expected_digest(&bsd, "renamed.zip", algorithm).as_deref(),
+            Some(md5)
+        );
+
+        // Not an MD5 digest
+        assert_eq!(expected_digest("abc123  a.zip", "a.zip", algorithm), None);
+    }
+}
@@ -0,0 +1,202 @@
+//! Creating extracted files through a handle to the output directory.
+//!
+//! Entry paths are validated before anything is written, but a path that
+//! checks out can still lead elsewhere once it's used: one of its directories
+//! may be a symbolic link an earlier entry or another process put there, even
+//! between the check and the write. Files, directories and links are therefore
+//! created, and given their permissions and times, through a handle to the
+//! output directory, opened once per extraction, which resolves each path
+//! beneath it and refuses any that would leave it (see the `cap-std` crate).
+//!
+//! The RAR library writes the files it extracts by path itself, so RAR entries
+//! only get the checks made before writing.
+
+use cap_std::ambient_authority;
+use cap_std::fs::{Dir, OpenOptions, Permissions};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The output directory of an extraction, through which everything extracted
+/// is created.
+///
+/// Paths are given in full, as they're used elsewhere, and must lie under the
+/// output directory.
+#[derive(Debug)]
+pub(crate) struct OutputRoot {
+    path: PathBuf,
+    dir: Dir,
+}
+
+impl OutputRoot {
+    /// Open the existing directory at `path`.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            dir: Dir::open_ambient_dir(path, ambient_authority())?,
+        })
+    }
+
+    /// `path` relative to the output directory.
+    fn relative<'a>(&self, path: &'a Path) -> io::Result<&'a Path> {
+        path.strip_prefix(&self.path).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{} is outside the output directory", path.display()),
+            )
+        })
+    }
+
+    /// Create the directory at `path` and any missing parents.
+    pub fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let relative = self.relative(path)?;
+        if relative.as_os_str().is_empty() {
+            return Ok(());
+        }
+        self.dir.create_dir_all(relative)
+    }
+
+    /// Create the directory at `path`, whose parent must exist.
+    pub fn create_dir(&self, path: &Path) -> io::Result<()> {
+        self.dir.create_dir(self.relative(path)?)
+    }
+
+    /// Create (or truncate) the file at `path` for writing.
+    pub fn create_file(&self, path: &Path) -> io::Result<File> {
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        self.open_file(path, &options)
+    }
+
+    /// Open the file at `path` with `options`.
+    pub fn open_file(&self, path: &Path, options: &OpenOptions) -> io::Result<File> {
+        let file = self.dir.open_with(self.relative(path)?, options)?;
+        Ok(file.into_std())
+    }
+
+    /// Move the file at `from` to `to`, replacing any file there.
+    pub fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.dir
+            .rename(self.relative(from)?, &self.dir, self.relative(to)?)
+    }
+
+    /// `path` relative to the output directory, with the output directory
+    /// itself given as `.`.
+    fn relative_or_root<'a>(&self, path: &'a Path) -> io::Result<&'a Path> {
+        let relative = self.relative(path)?;
+        Ok(if relative.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            relative
+        })
+    }
+
+    /// Delete the file or symbolic link at `path`.
+    pub fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.dir.remove_file(self.relative(path)?)
+    }
+
+    /// Delete the empty directory at `path`.
+    pub fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        self.dir.remove_dir(self.relative(path)?)
+    }
+
+    /// Change the permissions of the file or directory at `path`.
+    pub fn set_permissions(&self, path: &Path, permissions: fs::Permissions) -> io::Result<()> {
+        self.dir.set_permissions(
+            self.relative_or_root(path)?,
+            Permissions::from_std(permissions),
+        )
+    }
+
+    /// Open the file or directory at `path` for changing its times.
+    #[cfg(not(windows))]
+    pub fn open_for_times(&self, path: &Path) -> io::Result<File> {
+        Ok(self.dir.open(self.relative_or_root(path)?)?.into_std())
+    }
+
+    /// Open the file or directory at `path` for changing its times.
+    #[cfg(windows)]
+    pub fn open_for_times(&self, path: &Path) -> io::Result<File> {
+        use cap_std::fs::OpenOptionsExt;
+
+        // Directories can only be opened with backup semantics
+        const FILE_WRITE_ATTRIBUTES: u32 = 0x0100;
+        const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+        let mut options = OpenOptions::new();
+        options
+            .access_mode(FILE_WRITE_ATTRIBUTES)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS);
+        let file = self.dir.open_with(self.relative_or_root(path)?, &options)?;
+        Ok(file.into_std())
+    }
+
+    /// Create a hard link at `link` to the file at `target`.
+    pub fn hard_link(&self, target: &Path, link: &Path) -> io::Result<()> {
+        self.dir
+            .hard_link(self.relative(target)?, &self.dir, self.relative(link)?)
+    }
+
+    /// Copy the file at `from` to `to` along with its permissions, returning
+    /// the number of bytes copied.
+    pub fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        self.dir
+            .copy(self.relative(from)?, &self.dir, self.relative(to)?)
+    }
+
+    /// Create a symbolic link at `link` pointing to `target`, which isn't
+    /// resolved.
+    #[cfg(unix)]
+    pub fn symlink(&self, target: &str, link: &Path) -> io::Result<()> {
+        self.dir.symlink(target, self.relative(link)?)
+    }
+
+    /// Create a symbolic link at `link` pointing to `target`, a directory link
+    /// if `target` names a directory.
+    #[cfg(windows)]
+    pub fn symlink(&self, target: &str, link: &Path) -> io::Result<()> {
+        let relative = self.relative(link)?;
+        let resolved = link.parent().unwrap_or(&self.path).join(target);
+        if resolved.is_dir() {
+            self.dir.symlink_dir(target, relative)
+        } else {
+            self.dir.symlink_file(target, relative)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_output_root_stays_inside() {
+        let outside = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let root = OutputRoot::open(output.path()).unwrap();
+
+        root.create_dir_all(&output.path().join("a/b")).unwrap();
+        let mut file = root
+            .create_file(&output.path().join("a/b/file.txt"))
+            .unwrap();
+        io::Write::write_all(&mut file, b"inside").unwrap();
+        assert_eq!(
+            std::fs::read(output.path().join("a/b/file.txt")).unwrap(),
+            b"inside"
+        );
+
+        // A directory swapped for a link to elsewhere doesn't lead there
+        std::os::unix::fs::symlink(outside.path(), output.path().join("escape")).unwrap();
+        assert!(root
+            .create_file(&output.path().join("escape/file.txt"))
+            .is_err());
+        assert!(root
+            .create_dir_all(&output.path().join("escape/nested"))
+            .is_err());
+        assert!(!outside.path().join("file.txt").exists());
+        assert!(!outside.path().join("nested").exists());
+
+        assert!(root.create_file(&outside.path().join("file.txt")).is_err());
+    }
+}
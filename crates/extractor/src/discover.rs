@@ -0,0 +1,215 @@
+//! Finding archives among files on disk.
+//!
+//! Shared by the app's file browser and by everything that extracts a whole
+//! folder, so both agree on what counts as an archive and on where each one is
+//! unpacked.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Extensions of the archives the extractor handles.
+const ARCHIVE_EXTENSIONS: &[&str] = &[
+    "zip", "7z", "rar", "tar", "gz", "bz2", "xz", "tgz", "tbz2", "txz",
+];
+
+/// Whether `path` names an archive the extractor supports, judging by its name.
+///
+/// Every volume of a multi-volume set counts (`.part2.rar`, `.r00`, `.7z.002`,
+/// ...); use [`is_continuation_volume`] to tell the first volume apart.
+pub fn is_supported_archive(path: &Path) -> bool {
+    let filename = path
+        .file_name()
+        .and_then(|f| f.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    // Check for multi-part archives
+    // RAR: .part1.rar, .part01.rar, .r00, .r01, etc.
+    if filename.contains(".part") && filename.ends_with(".rar") {
+        return true;
+    }
+
+    // 7z and ZIP splits: .7z.001, .zip.002, etc.
+    if filename.contains(".7z.") || filename.contains(".zip.") {
+        if let Some(ext) = path.extension() {
+            if ext.to_string_lossy().chars().all(|c| c.is_ascii_digit()) {
+                return true;
+            }
+        }
+    }
+
+    // Check standard extensions
+    if let Some(ext) = path.extension() {
+        let ext_lower = ext.to_string_lossy().to_lowercase();
+
+        // Check for .rXX extensions (RAR multi-part)
+        if ext_lower.starts_with('r')
+            && ext_lower.len() >= 2
+            && ext_lower[1..].chars().all(|c| c.is_ascii_digit())
+        {
+            return true;
+        }
+
+        ARCHIVE_EXTENSIONS.contains(&ext_lower.as_str())
+    } else {
+        false
+    }
+}
+
+/// Whether `path` is a later volume of a multi-volume set (`.part2.rar`,
+/// `.r00`, `.7z.002`, ...), which is extracted through the set's first volume.
+pub fn is_continuation_volume(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    let lower = name.to_lowercase();
+    let Some((base, ext)) = lower.rsplit_once('.') else {
+        return false;
+    };
+    let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+
+    // Old-style RAR sets: name.rar, name.r00, name.r01, ...
+    if ext.strip_prefix('r').is_some_and(is_number) {
+        return true;
+    }
+
+    // Numbered splits: name.7z.001, name.7z.002, ...
+    if is_number(ext) && (base.ends_with(".7z") || base.ends_with(".zip")) {
+        return ext.parse::<u64>() != Ok(1);
+    }
+
+    // New-style RAR sets: name.part1.rar, name.part2.rar, ...
+    match base.rsplit_once(".part") {
+        Some((_, part)) if ext == "rar" && is_number(part) => part.parse::<u64>() != Ok(1),
+        _ => false,
+    }
+}
+
+/// List the archives in `dir`, descending into subdirectories when `recursive`
+/// is set.
+///
+/// Multi-volume sets are listed once, by their first volume. Symbolic links are
+/// not followed. The result is sorted by path.
+pub fn find_archives(dir: &Path, recursive: bool) -> io::Result<Vec<PathBuf>> {
+    let mut archives = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+
+            if file_type.is_dir() {
+                if recursive {
+                    pending.push(path);
+                }
+            } else if file_type.is_file()
+                && is_supported_archive(&path)
+                && !is_continuation_volume(&path)
+            {
+                archives.push(path);
+            }
+        }
+    }
+
+    archives.sort();
+    Ok(archives)
+}
+
+/// Folder to extract `archive` into inside `parent`: named after the archive,
+/// with " (1)", " (2)", ... appended until the name is free.
+pub fn unique_output_dir(parent: &Path, archive: &Path) -> PathBuf {
+    let base_name = archive
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "archive".to_string());
+
+    let mut output_path = parent.join(&base_name);
+    let mut counter = 1;
+    while output_path.exists() {
+        output_path = parent.join(format!("{} ({})", base_name, counter));
+        counter += 1;
+    }
+    output_path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_names() {
+        for name in [
+            "a.zip",
+            "a.TAR.GZ",
+            "a.tgz",
+            "a.7z.001",
+            "a.part2.rar",
+            "a.r00",
+        ] {
+            assert!(is_supported_archive(Path::new(name)), "{}", name);
+        }
+        for name in ["a.txt", "a.7z.bak", "README"] {
+            assert!(!is_supported_archive(Path::new(name)), "{}", name);
+        }
+
+        for name in [
+            "a.r00",
+            "a.7z.002",
+            "a.zip.010",
+            "a.part2.rar",
+            "a.Part02.RAR",
+        ] {
+            assert!(is_continuation_volume(Path::new(name)), "{}", name);
+        }
+        for name in [
+            "a.rar",
+            "a.7z.001",
+            "a.part1.rar",
+            "a.part01.rar",
+            "a.zip",
+            "a.partial.rar",
+        ] {
+            assert!(!is_continuation_volume(Path::new(name)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_find_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("nested");
+        fs::create_dir(&nested).unwrap();
+        for name in ["b.zip", "notes.txt", "set.part1.rar", "set.part2.rar"] {
+            fs::write(dir.path().join(name), b"").unwrap();
+        }
+        fs::write(nested.join("c.tar.gz"), b"").unwrap();
+
+        let top = find_archives(dir.path(), false).unwrap();
+        assert_eq!(
+            top,
+            vec![dir.path().join("b.zip"), dir.path().join("set.part1.rar")]
+        );
+
+        let all = find_archives(dir.path(), true).unwrap();
+        assert_eq!(all.len(), 3);
+        assert!(all.contains(&nested.join("c.tar.gz")));
+    }
+
+    #[test]
+    fn test_unique_output_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("photos.zip");
+
+        assert_eq!(
+            unique_output_dir(dir.path(), &archive),
+            dir.path().join("photos")
+        );
+        fs::create_dir(dir.path().join("photos")).unwrap();
+        assert_eq!(
+            unique_output_dir(dir.path(), &archive),
+            dir.path().join("photos (1)")
+        );
+    }
+}
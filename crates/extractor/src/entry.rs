@@ -0,0 +1,286 @@
+//! Access to individual archive entries without extracting to disk.
+//!
+//! Used for previews and `unarchive cat`: the bytes of one entry are streamed
+//! into any [`Write`] implementation, or read incrementally through an
+//! [`EntryReader`], and nothing is written to the filesystem.
+
+use crate::error::ExtractError;
+use crate::reader::open_archive_reader;
+use crate::safety::validate_entry_path;
+use crate::types::{ExtractOptions, Format};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+
+/// Number of chunks buffered between an [`EntryReader`] and the thread
+/// decompressing its entry.
+const READER_CHUNKS: usize = 16;
+
+/// An archive opened for reading individual entries.
+///
+/// ```rust,no_run
+/// use extractor::Archive;
+/// use std::io::Read;
+/// use std::path::Path;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut readme = String::new();
+/// Archive::open(Path::new("docs.zip"))?
+///     .entry_reader("docs/readme.md")?
+///     .read_to_string(&mut readme)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Archive {
+    path: PathBuf,
+    format: Format,
+    options: ExtractOptions,
+}
+
+impl Archive {
+    /// Open the archive at `path`, detecting its format.
+    pub fn open(path: &Path) -> Result<Self, ExtractError> {
+        if !path.exists() {
+            return Err(ExtractError::NotFound(path.to_path_buf()));
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            format: crate::probe::detect_format(path)?,
+            options: ExtractOptions {
+                size_limit_bytes: None,
+                ..ExtractOptions::default()
+            },
+        })
+    }
+
+    /// Use `password` to decrypt entries.
+    pub fn with_password(mut self, password: impl Into<String>) -> Self {
+        self.options.password = Some(password.into());
+        self
+    }
+
+    /// Path of the archive file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Detected archive format.
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Open the file `entry_path` for reading.
+    ///
+    /// The entry is decompressed on a background thread as the reader is
+    /// consumed, so large entries can be hashed, previewed or piped without
+    /// being held in memory (except RAR entries, see
+    /// [`extract_entry_to_writer`]). This waits until the entry is found and its
+    /// first bytes are ready; a missing entry fails here with
+    /// `ExtractError::EntryNotFound`, while later errors surface from `read`.
+    pub fn entry_reader(&self, entry_path: &str) -> Result<EntryReader, ExtractError> {
+        let (sender, chunks) = mpsc::sync_channel(READER_CHUNKS);
+        let archive = self.clone();
+        let entry_path = entry_path.to_string();
+        let worker = thread::spawn(move || {
+            let mut writer = ChannelWriter(sender);
+            write_entry_data(
+                &archive.path,
+                archive.format,
+                &entry_path,
+                &mut writer,
+                &archive.options,
+            )
+        });
+
+        let mut reader = EntryReader {
+            chunks,
+            current: Vec::new(),
+            pos: 0,
+            worker: Some(worker),
+        };
+        match reader.chunks.recv() {
+            Ok(chunk) => reader.current = chunk,
+            // Nothing was written: either an empty entry or a failure
+            Err(_) => reader.finish()?,
+        }
+        Ok(reader)
+    }
+}
+
+/// Streaming reader over one archive entry, returned by [`Archive::entry_reader`].
+///
+/// Dropping the reader early stops the decompression.
+pub struct EntryReader {
+    chunks: Receiver<Vec<u8>>,
+    current: Vec<u8>,
+    pos: usize,
+    worker: Option<JoinHandle<Result<u64, ExtractError>>>,
+}
+
+impl EntryReader {
+    /// Wait for the decompression thread and return its outcome (once).
+    fn finish(&mut self) -> Result<(), ExtractError> {
+        match self.worker.take().map(JoinHandle::join) {
+            Some(Ok(result)) => result.map(|_| ()),
+            Some(Err(_)) => Err(ExtractError::Io(io::Error::other(
+                "entry reader thread panicked",
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Read for EntryReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.current.len() {
+            match self.chunks.recv() {
+                Ok(chunk) => {
+                    self.current = chunk;
+                    self.pos = 0;
+                }
+                Err(_) => {
+                    return match self.finish() {
+                        Ok(()) => Ok(0),
+                        Err(ExtractError::Io(e)) => Err(e),
+                        Err(e) => Err(io::Error::other(e)),
+                    };
+                }
+            }
+        }
+
+        let n = buf.len().min(self.current.len() - self.pos);
+        buf[..n].copy_from_slice(&self.current[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Writer handing each write to an [`EntryReader`] as a chunk.
+struct ChannelWriter(SyncSender<Vec<u8>>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // The reader hung up; stop decompressing
+        self.0
+            .send(buf.to_vec())
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Write the contents of the file `entry_path` inside an archive to `writer`.
+///
+/// `entry_path` is compared with the archive's entry names after both are
+/// normalized the way extraction does (so `./docs/a.txt` matches `docs/a.txt`).
+/// The options' password and size limit apply; other options are ignored. When
+/// the size limit is exceeded, part of the entry may already have been written.
+///
+/// RAR entries are decompressed into memory before being written; the other
+/// formats are streamed.
+///
+/// Returns the number of bytes written.
+pub fn extract_entry_to_writer<W: Write>(
+    archive_path: &Path,
+    entry_path: &str,
+    writer: &mut W,
+    options: &ExtractOptions,
+) -> Result<u64, ExtractError> {
+    if !archive_path.exists() {
+        return Err(ExtractError::NotFound(archive_path.to_path_buf()));
+    }
+
+    let format = crate::probe::detect_format(archive_path)?;
+    write_entry_data(archive_path, format, entry_path, writer, options)
+}
+
+fn write_entry_data<W: Write>(
+    archive_path: &Path,
+    format: Format,
+    entry_path: &str,
+    writer: &mut W,
+    options: &ExtractOptions,
+) -> Result<u64, ExtractError> {
+    let wanted = validate_entry_path(Path::new(entry_path))?;
+    let mut out = LimitedWriter {
+        inner: writer,
+        written: 0,
+        limit: options.size_limit_bytes,
+        exceeded: None,
+    };
+
+    // Single compressed files hold one entry, named after the archive without
+    // its compression extension (as when extracting)
+    let mut reader = open_archive_reader(archive_path, format, options)?;
+    reader.set_tolerate_trailing_data(options.tolerate_trailing_data);
+    reader.set_read_concatenated(options.read_concatenated_tar);
+    if let Some(encoding) = options.filename_encoding {
+        reader.set_filename_encoding(encoding);
+    }
+    let mut written = None;
+    reader.read_entries(&mut |entry, data| {
+        if entry.is_directory || !same_entry(&entry.path, &wanted) {
+            return Ok(true);
+        }
+        written = Some(out.copy_from(data.reader()?)?);
+        Ok(false)
+    })?;
+
+    written.ok_or_else(|| ExtractError::EntryNotFound(entry_path.to_string()))
+}
+
+/// Whether an archive entry name refers to the normalized path `wanted`.
+fn same_entry(name: &str, wanted: &Path) -> bool {
+    validate_entry_path(Path::new(name)).is_ok_and(|path| path == wanted)
+}
+
+/// Writer enforcing the size limit from the options.
+struct LimitedWriter<'a, W> {
+    inner: &'a mut W,
+    written: u64,
+    limit: Option<u64>,
+    /// Total size of the write that would have crossed the limit
+    exceeded: Option<u64>,
+}
+
+impl<W: Write> LimitedWriter<'_, W> {
+    /// Copy all of `reader` into this writer.
+    fn copy_from<R: Read + ?Sized>(&mut self, reader: &mut R) -> Result<u64, ExtractError> {
+        match io::copy(reader, self) {
+            Ok(_) => Ok(self.written),
+            Err(_) if self.exceeded.is_some() => Err(self.limit_error()),
+            Err(e) => Err(ExtractError::Io(e)),
+        }
+    }
+
+    fn limit_error(&self) -> ExtractError {
+        ExtractError::SizeLimitExceeded {
+            current: self.exceeded.unwrap_or(self.written),
+            limit: self.limit.unwrap_or(0),
+        }
+    }
+}
+
+impl<W: Write> Write for LimitedWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let room = self.limit.map_or(u64::MAX, |limit| limit - self.written);
+        if buf.len() as u64 > room {
+            self.exceeded = Some(self.written + buf.len() as u64);
+            return Err(io::Error::other("size limit exceeded"));
+        }
+
+        let n = self.inner.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
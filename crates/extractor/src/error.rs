@@ -1,10 +1,19 @@
 //! Error types for archive extraction operations.
 
+use crate::types::{ChecksumCheck, ExtractStats, VolumeIssue, VolumeProblem};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io;
 use std::path::PathBuf;
 use thiserror::Error;
+use ts_rs::TS;
 
 /// Main error type for extraction operations.
+///
+/// New kinds of failure are added in minor releases, so matches need a
+/// catch-all arm.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum ExtractError {
     /// Archive file not found at the specified path.
     #[error("Archive not found: {0}")]
@@ -35,6 +44,21 @@ pub enum ExtractError {
         limit: u64,
     },
 
+    /// A file is larger than `ExtractOptions::max_entry_size_bytes`.
+    #[error("Entry too large: {entry} is {size} bytes > {limit} bytes")]
+    EntryTooLarge {
+        /// Archive path of the entry
+        entry: String,
+        /// Recorded or written size of the entry in bytes
+        size: u64,
+        /// Configured per-entry limit in bytes
+        limit: u64,
+    },
+
+    /// The requested entry is not a file in the archive.
+    #[error("Entry not found in archive: {0}")]
+    EntryNotFound(String),
+
     /// The archive is corrupted or malformed.
     #[error("Corrupted archive: {0}")]
     Corrupted(String),
@@ -43,13 +67,245 @@ pub enum ExtractError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    /// The destination filesystem has too few free inodes for the archive's entries.
+    #[error("Not enough free inodes: {needed} needed, {available} available")]
+    InsufficientInodes {
+        /// Files and directories the archive would create
+        needed: u64,
+        /// Inodes available on the destination filesystem
+        available: u64,
+    },
+
+    /// The destination filesystem has less free space than the archive expands to.
+    #[error("Not enough free space: {needed} bytes needed, {available} available")]
+    InsufficientSpace {
+        /// Bytes the archive's files take once extracted
+        needed: u64,
+        /// Bytes available on the destination filesystem
+        available: u64,
+    },
+
+    /// A destination file already exists and the overwrite mode forbids touching it.
+    #[error("Destination already exists: {0}")]
+    AlreadyExists(PathBuf),
+
+    /// A pre-write hook aborted the extraction at this entry.
+    #[error("Rejected by pre-write hook: {0}")]
+    Rejected(String),
+
+    /// The extraction ran longer than `ExtractOptions::max_duration`.
+    #[error("Time limit of {0:?} exceeded")]
+    TimedOut(std::time::Duration),
+
+    /// Volumes of a multi-volume archive are missing or damaged.
+    #[error("Incomplete volume set: {}", describe_volumes(.0))]
+    IncompleteVolumes(Vec<VolumeProblem>),
+
+    /// The archive's digest differs from the one its checksum file lists
+    /// (see `ExtractOptions::verify_checksum`).
+    #[error(
+        "Checksum mismatch: {} of the archive is {}, {} lists {}",
+        .0.algorithm, .0.actual, .0.file, .0.expected
+    )]
+    ChecksumMismatch(Box<ChecksumCheck>),
+
     /// The extraction was cancelled by the user.
     #[error("Cancelled by user")]
     Cancelled,
+
+    /// The extraction failed with `error` and, with cleanup on failure
+    /// disabled, left the files in `stats.partial_files` behind.
+    #[error("{error} ({} partially extracted files left behind)", stats.partial_files.len())]
+    Partial {
+        /// Why the extraction stopped
+        #[source]
+        error: Box<ExtractError>,
+        /// Statistics of the extraction up to the failure
+        stats: Box<ExtractStats>,
+    },
+}
+
+impl ExtractError {
+    /// The error an extraction stopped with, looking through
+    /// [`ExtractError::Partial`].
+    pub fn root_cause(&self) -> &ExtractError {
+        match self {
+            ExtractError::Partial { error, .. } => error.root_cause(),
+            other => other,
+        }
+    }
+
+    /// Suggested next steps for the user, most relevant first, derived from
+    /// the kind of error and what the archive library reported.
+    pub fn hints(&self) -> Vec<ErrorHint> {
+        match self.root_cause() {
+            ExtractError::PasswordRequired => vec![ErrorHint::EnterPassword],
+            ExtractError::InvalidPassword => vec![ErrorHint::RetryPassword],
+            ExtractError::IncompleteVolumes(problems) => {
+                let mut hints = Vec::new();
+                if problems.iter().any(|p| p.issue == VolumeIssue::Missing) {
+                    hints.push(ErrorHint::MissingVolume);
+                }
+                if problems
+                    .iter()
+                    .any(|p| matches!(p.issue, VolumeIssue::Corrupted { .. }))
+                {
+                    hints.push(ErrorHint::Redownload);
+                }
+                hints
+            }
+            ExtractError::UnsupportedFormat(_) => vec![ErrorHint::NotAnArchive],
+            ExtractError::ChecksumMismatch(_) => vec![ErrorHint::Redownload],
+            ExtractError::Corrupted(message) => corruption_hints(message),
+            ExtractError::SizeLimitExceeded { .. } | ExtractError::EntryTooLarge { .. } => {
+                vec![ErrorHint::RaiseSizeLimit]
+            }
+            ExtractError::TimedOut(_) => vec![ErrorHint::RaiseTimeLimit],
+            ExtractError::InsufficientInodes { .. } | ExtractError::InsufficientSpace { .. } => {
+                vec![ErrorHint::FreeDiskSpace]
+            }
+            ExtractError::AlreadyExists(_) => vec![ErrorHint::ChooseOverwriteMode],
+            ExtractError::Io(e) => match e.kind() {
+                io::ErrorKind::StorageFull
+                | io::ErrorKind::QuotaExceeded
+                | io::ErrorKind::FileTooLarge => vec![ErrorHint::FreeDiskSpace],
+                io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem => {
+                    vec![ErrorHint::CheckPermissions]
+                }
+                io::ErrorKind::UnexpectedEof => vec![ErrorHint::Redownload],
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Hints for an archive library's report of a damaged archive.
+fn corruption_hints(message: &str) -> Vec<ErrorHint> {
+    let message = message.to_lowercase();
+    // unrar reports RAR versions it doesn't know as an unknown format
+    if message.contains("unknownformat")
+        || message.contains("unknown format")
+        || message.contains("unsupported version")
+    {
+        return vec![ErrorHint::NewerFormatVersion];
+    }
+    if ["volume", "split", "multi-part", "multipart"]
+        .iter()
+        .any(|word| message.contains(word))
+    {
+        return vec![ErrorHint::MissingVolume, ErrorHint::Redownload];
+    }
+    vec![ErrorHint::Redownload]
+}
+
+/// Suggested next step after a failed extraction.
+///
+/// Frontends show [`ErrorHint::advice`] or their own wording keyed on the
+/// serialized code, such as `"missing_volume"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ErrorHint {
+    /// A volume of a multi-part set is missing
+    MissingVolume,
+
+    /// The archive needs a password
+    EnterPassword,
+
+    /// The password given is wrong
+    RetryPassword,
+
+    /// The archive was made by a newer version of its format than is supported
+    NewerFormatVersion,
+
+    /// The archive looks truncated or damaged, as by an interrupted download
+    Redownload,
+
+    /// The file isn't an archive, or not of a supported format
+    NotAnArchive,
+
+    /// The destination ran out of space or inodes
+    FreeDiskSpace,
+
+    /// The archive or destination can't be accessed
+    CheckPermissions,
+
+    /// The extraction went over the size limit
+    RaiseSizeLimit,
+
+    /// The extraction went over the time limit
+    RaiseTimeLimit,
+
+    /// A file in the way of the extraction wasn't to be overwritten
+    ChooseOverwriteMode,
+}
+
+impl ErrorHint {
+    /// Stable, machine-readable code of the hint.
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorHint::MissingVolume => "missing_volume",
+            ErrorHint::EnterPassword => "enter_password",
+            ErrorHint::RetryPassword => "retry_password",
+            ErrorHint::NewerFormatVersion => "newer_format_version",
+            ErrorHint::Redownload => "redownload",
+            ErrorHint::NotAnArchive => "not_an_archive",
+            ErrorHint::FreeDiskSpace => "free_disk_space",
+            ErrorHint::CheckPermissions => "check_permissions",
+            ErrorHint::RaiseSizeLimit => "raise_size_limit",
+            ErrorHint::RaiseTimeLimit => "raise_time_limit",
+            ErrorHint::ChooseOverwriteMode => "choose_overwrite_mode",
+        }
+    }
+
+    /// What the user can do about it, in a sentence.
+    pub fn advice(self) -> &'static str {
+        match self {
+            ErrorHint::MissingVolume => {
+                "Make sure every part of the multi-part set is in the same folder"
+            }
+            ErrorHint::EnterPassword => "Extract again and enter the archive's password",
+            ErrorHint::RetryPassword => {
+                "Check the password, including its letter case and keyboard layout"
+            }
+            ErrorHint::NewerFormatVersion => {
+                "The archive was made by a newer version of its format; extract it with the archiver that made it"
+            }
+            ErrorHint::Redownload => {
+                "The archive seems incomplete or damaged; download or copy it again"
+            }
+            ErrorHint::NotAnArchive => {
+                "Check that the file is an archive; its extension may not match its contents"
+            }
+            ErrorHint::FreeDiskSpace => "Free up space on the destination or extract elsewhere",
+            ErrorHint::CheckPermissions => {
+                "Check that the archive can be read and the destination written"
+            }
+            ErrorHint::RaiseSizeLimit => "Raise or remove the size limit",
+            ErrorHint::RaiseTimeLimit => "Raise or remove the time limit",
+            ErrorHint::ChooseOverwriteMode => {
+                "Choose another overwrite mode or an empty destination"
+            }
+        }
+    }
+}
+
+impl fmt::Display for ErrorHint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.advice())
+    }
+}
+
+fn describe_volumes(problems: &[VolumeProblem]) -> String {
+    let problems: Vec<String> = problems.iter().map(ToString::to_string).collect();
+    problems.join("; ")
 }
 
 /// Security-related errors during extraction.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum SecurityError {
     /// Path traversal attempt detected (e.g., "../../../etc/passwd").
     #[error("Path traversal attempt: {0}")]
@@ -59,7 +315,33 @@ pub enum SecurityError {
     #[error("Absolute path not allowed: {0}")]
     AbsolutePath(String),
 
+    /// Entry name contains NUL, newline or other control characters.
+    #[error("Control characters in entry name: {0}")]
+    ControlCharacters(String),
+
+    /// Entry path nested deeper than `PathLimits::max_depth` allows.
+    #[error("Path nested too deeply: {0}")]
+    PathTooDeep(String),
+
+    /// Entry path, or a name in it, longer than `PathLimits` allows.
+    #[error("Path too long: {0}")]
+    PathTooLong(String),
+
+    /// Entry name Windows can't create: a reserved device name such as `CON`,
+    /// one of `<>:"|?*`, or a trailing dot or space.
+    #[error("Name not valid on Windows: {0}")]
+    NonPortableName(String),
+
     /// Unsafe entry type detected (e.g., symlink when not allowed).
     #[error("Unsafe entry type: {0}")]
     UnsafeEntryType(String),
+
+    /// Symbolic link whose target is absolute or outside the output directory.
+    #[error("Symlink target outside the output directory: {0}")]
+    SymlinkEscape(String),
+
+    /// An entry or the whole archive expands past the compression ratio
+    /// limits, as decompression bombs such as 42.zip do.
+    #[error("Suspected archive bomb: {0}")]
+    SuspectedArchiveBomb(String),
 }
@@ -1,18 +1,38 @@
 //! Archive extraction implementation with security features.
 
-use crate::error::ExtractError;
-use crate::safety::validate_entry_path;
-use crate::types::{ExtractOptions, ExtractStats, OverwriteMode};
+use crate::confine::OutputRoot;
+use crate::error::{ExtractError, SecurityError};
+use crate::journal::{ExtractionJournal, JournalWriter};
+use crate::parallel::{QueuedFile, Workers, Written};
+use crate::pipeline::{FileJob, Writer, PROGRESS_STEP};
+use crate::probe::Totals;
+use crate::provenance::{hex_digest, sha256_file};
+use crate::reader::{
+    open_archive_reader, open_stream_reader_in, ArchiveReader, Entry, EntryData, EntryDecoder,
+};
+use crate::safety::{
+    is_safe_entry_type, is_safe_xattr, normalize_unicode, sanitize_control_chars, sanitize_names,
+    sanitize_windows_names, validate_entry_path_with_limits, validate_symlink_target, EntryType,
+};
+use crate::sniff::{sniff_content, SNIFF_LEN};
+use crate::types::{
+    ArchiveEntry, BlockedEntry, EntryAction, EntryError, ErrorPolicy, ExtractOptions, ExtractStats,
+    Format, FreeSpaceCheck, OversizePolicy, OverwriteDecision, OverwriteMode, ProbeOptions,
+    ProbeProgress, ProgressEvent, ProgressListener, ProgressPhase, ProgressSink, RenamedEntry,
+    SubfolderPolicy, SymlinkPolicy, TimestampPolicy, VerifyMismatch, WindowsNamePolicy,
+    WriteDecision,
+};
+use crate::verify::check_data;
 use crate::ProgressCallback;
-use bzip2::read::BzDecoder;
-use flate2::read::GzDecoder;
-use lzma_rs::xz_decompress;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::{self, File};
-use std::io::{self, Read};
-use std::path::{Path, PathBuf};
+use std::io::{self, Read, Seek, Write};
+use std::path::{Component, Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::time::Instant;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant, UNIX_EPOCH};
 
 /// Extract an archive to the specified output directory.
 ///
@@ -41,6 +61,44 @@ pub fn extract_archive(
     options: &ExtractOptions,
     progress_cb: &ProgressCallback,
     cancel_flag: Arc<AtomicBool>,
+) -> Result<ExtractStats, ExtractError> {
+    extract_resumable(
+        archive_path,
+        output_dir,
+        options,
+        progress_cb,
+        cancel_flag,
+        None,
+    )
+}
+
+/// Extract an archive like [`extract_archive`], continuing the interrupted
+/// extraction `journal` was kept for (see [`crate::journal`]).
+pub(crate) fn extract_resumed(
+    archive_path: &Path,
+    output_dir: &Path,
+    options: &ExtractOptions,
+    progress_cb: &ProgressCallback,
+    cancel_flag: Arc<AtomicBool>,
+    journal: ExtractionJournal,
+) -> Result<ExtractStats, ExtractError> {
+    extract_resumable(
+        archive_path,
+        output_dir,
+        options,
+        progress_cb,
+        cancel_flag,
+        Some(journal),
+    )
+}
+
+fn extract_resumable(
+    archive_path: &Path,
+    output_dir: &Path,
+    options: &ExtractOptions,
+    progress_cb: &ProgressCallback,
+    cancel_flag: Arc<AtomicBool>,
+    resumed: Option<ExtractionJournal>,
 ) -> Result<ExtractStats, ExtractError> {
     let start_time = Instant::now();
 
@@ -49,6 +107,13 @@ pub fn extract_archive(
         return Err(ExtractError::NotFound(archive_path.to_path_buf()));
     }
 
+    // Check for unsupported multi-part archives
+    if is_multipart_archive(archive_path) && !is_rar_archive(archive_path) {
+        return Err(ExtractError::UnsupportedFormat(
+            "Multi-part 7-Zip and ZIP archives are not currently supported. Please use the first part (.001) or combine the parts using an external tool.".to_string()
+        ));
+    }
+
     // For RAR multi-part archives, we need to use the first part
     let actual_archive_path = if is_rar_archive(archive_path) {
         use unrar::Archive;
@@ -61,8 +126,76 @@ pub fn extract_archive(
         archive_path.to_path_buf()
     };
 
+    // Refuse incomplete volume sets before anything is written
+    let volumes = crate::integrity::verify_volumes(&actual_archive_path)?;
+    if let Some(report) = &volumes {
+        if !report.is_complete() {
+            return Err(ExtractError::IncompleteVolumes(report.problems.clone()));
+        }
+    }
+
+    // Give the archive a folder of its own and extract into that
+    if let Some(subfolder) = archive_subfolder(archive_path, output_dir, options, &cancel_flag)? {
+        let options = ExtractOptions {
+            create_subfolder: SubfolderPolicy::Never,
+            ..options.clone()
+        };
+        let mut stats = extract_resumable(
+            archive_path,
+            &subfolder,
+            &options,
+            progress_cb,
+            cancel_flag,
+            resumed,
+        )?;
+        stats.subfolder = Some(subfolder.display().to_string());
+        return Ok(stats);
+    }
+
+    if options.transactional {
+        return extract_transactionally(output_dir, options, |staging, options| {
+            extract_archive(archive_path, staging, options, progress_cb, cancel_flag)
+        });
+    }
+
+    // Cancel the job once its time budget runs out
+    let watchdog = options
+        .max_duration
+        .map(|limit| Watchdog::start(limit, cancel_flag.clone()));
+
+    // Refuse an archive that doesn't match its published digest
+    let checksum = if options.verify_checksum {
+        crate::checksum::verify_checksum(archive_path)?
+    } else {
+        None
+    };
+    if let Some(check) = checksum.as_ref().filter(|check| !check.matches) {
+        return Err(ExtractError::ChecksumMismatch(Box::new(check.clone())));
+    }
+
+    // What the expansion ratio limit measures against
+    let archive_size = match &volumes {
+        Some(report) => report
+            .volumes
+            .iter()
+            .filter_map(|volume| fs::metadata(volume).ok())
+            .map(|metadata| metadata.len())
+            .sum(),
+        None => fs::metadata(&actual_archive_path)?.len(),
+    };
+
     // Create output directory if it doesn't exist
+    let mut created = CreatedPaths::default();
+    created.record_missing_dirs(output_dir);
     fs::create_dir_all(output_dir)?;
+    let root = Arc::new(OutputRoot::open(output_dir)?);
+
+    // Kept next to the output directory until this function returns
+    let mut journal = if options.journal {
+        Some(JournalWriter::start(archive_path, output_dir, resumed)?)
+    } else {
+        None
+    };
 
     // Track extraction statistics
     let mut stats = ExtractStats {
@@ -70,707 +203,2329 @@ pub fn extract_archive(
         bytes_written: 0,
         duration: std::time::Duration::from_secs(0),
         cancelled: false,
+        files_skipped: 0,
+        directories: 0,
+        blocked: Vec::new(),
+        warnings: Vec::new(),
+        errors: Vec::new(),
+        renamed: Vec::new(),
+        partial_files: Vec::new(),
+        mismatches: Vec::new(),
+        sha256: BTreeMap::new(),
+        checksum,
+        subfolder: None,
     };
 
-    // Check for unsupported multi-part archives
-    if is_multipart_archive(archive_path) && !is_rar_archive(archive_path) {
-        return Err(ExtractError::UnsupportedFormat(
-            "Multi-part 7-Zip and ZIP archives are not currently supported. Please use the first part (.001) or combine the parts using an external tool.".to_string()
-        ));
+    // Only flag case collisions where they would actually clobber files
+    let mut collisions = CaseCollisions::new(output_dir, options);
+
+    // Downloaded archives pass their Gatekeeper quarantine on to their contents
+    let quarantine = options
+        .propagate_quarantine
+        .then(|| crate::quarantine::read_quarantine(archive_path))
+        .flatten();
+
+    // Fail before writing anything if the entries can't all be created
+    let mut totals = None;
+    let check_space = options.check_free_space != FreeSpaceCheck::Off;
+    if options.check_free_inodes || check_space {
+        let info = crate::probe::probe_archive_with_options(
+            &actual_archive_path,
+            &options.probe_options(cancel_flag.clone()),
+        )?;
+        totals = Some(Totals::listed(&info));
+        let resources = &info.resources;
+        if let (true, Some(needed)) = (options.check_free_inodes, resources.inodes_needed) {
+            if let Some(warning) = crate::preflight::check_inodes(output_dir, needed)? {
+                stats.warnings.push(warning);
+            }
+        }
+        if let (true, Some(needed)) = (check_space, resources.output_space_bytes) {
+            match crate::preflight::check_free_space(output_dir, needed) {
+                Err(e) if options.check_free_space == FreeSpaceCheck::Warn => {
+                    stats.warnings.push(e.to_string());
+                }
+                result => result?,
+            }
+        }
     }
 
     // Detect format
     let format = crate::probe::detect_format(&actual_archive_path)?;
-    
-    // Use appropriate extraction method based on archive type
-    let result = match format.as_str() {
-        "ZIP" => extract_zip_archive(
-            &actual_archive_path,
-            output_dir,
-            options,
-            progress_cb,
-            cancel_flag.clone(),
-            &mut stats,
-        ),
-        "TAR" | "TAR.GZ" | "TAR.BZ2" | "TAR.XZ" => extract_tar_archive(
-            &actual_archive_path,
-            output_dir,
-            options,
-            progress_cb,
-            cancel_flag.clone(),
-            &mut stats,
-            &format,
-        ),
-        "GZIP" | "BZIP2" | "XZ" => extract_compressed_file(
-            &actual_archive_path,
-            output_dir,
-            options,
-            progress_cb,
-            cancel_flag.clone(),
-            &mut stats,
-            &format,
-        ),
-        "7Z" => extract_7z_archive(
-            &actual_archive_path,
-            output_dir,
-            options,
-            progress_cb,
-            cancel_flag.clone(),
-            &mut stats,
-        ),
-        "RAR" => extract_rar_archive(
-            &actual_archive_path,
-            output_dir,
-            options,
-            progress_cb,
-            cancel_flag.clone(),
-            &mut stats,
-        ),
-        _ => Err(ExtractError::UnsupportedFormat(format)),
+
+    // Progress events report against the whole job, so count what's ahead
+    let totals = match (totals, &options.on_progress) {
+        (Some(totals), _) => totals,
+        (None, Some(listener)) => {
+            let path = actual_archive_path.as_path();
+            scan_totals(path, format, options, listener, &cancel_flag)?
+        }
+        (None, None) => Totals::default(),
     };
 
-    // Check if cancelled
-    if cancel_flag.load(Ordering::Relaxed) {
-        stats.cancelled = true;
-        stats.duration = start_time.elapsed();
-        return Err(ExtractError::Cancelled);
+    // Use the reader for the archive's format
+    let mut extraction = Extraction {
+        output_dir,
+        root: root.clone(),
+        options,
+        progress_cb,
+        cancel_flag: &cancel_flag,
+        quarantine: quarantine.as_deref(),
+        collisions: &mut collisions,
+        created: &mut created,
+        journal: journal.as_mut(),
+        stats: &mut stats,
+        entries_done: 0,
+        warnings_reported: 0,
+        total_entries: totals.entries,
+        total_bytes: totals.bytes,
+        archive_size,
+        preserve_modes: options.preserves_permissions(format),
+        dir_metadata: Vec::new(),
+        restore_owners: options.preserve_ownership && running_as_root(),
+        umask: permission_mask(options),
+        links: Vec::new(),
+        linkable: HashMap::new(),
+        overwrite: options.overwrite,
+        written: BTreeMap::new(),
+    };
+    let result = open_archive_reader(&actual_archive_path, format, options)
+        .and_then(|mut reader| extraction.run(reader.as_mut()));
+    if let Err(error) = finish_extraction(result, watchdog, &cancel_flag, &mut stats, start_time) {
+        return Err(created.settle_failure(error, &root, output_dir, options, &stats));
     }
 
-    // Handle extraction result
-    result?;
+    if options.write_provenance {
+        crate::provenance::write_provenance(&actual_archive_path, output_dir, options, &stats)?;
+    }
+    if options.write_sha256sums {
+        crate::provenance::write_sha256sums(output_dir, &stats)?;
+    }
 
-    stats.duration = start_time.elapsed();
     Ok(stats)
 }
 
-/// Extract ZIP archive using zip crate.
-fn extract_zip_archive(
-    archive_path: &Path,
+/// Extract an archive read from `source` to the specified output directory.
+///
+/// Behaves like [`extract_archive`] for archive data that isn't a file of its
+/// own, such as an archive embedded in another file or an already-open handle.
+/// The format is detected from the data's signature, and the checks tied to an
+/// archive path (volume sets, checksum files, free inodes, quarantine,
+/// provenance, subfolders named after it) don't apply.
+pub fn extract_from_reader<R: Read + Seek>(
+    mut source: R,
     output_dir: &Path,
     options: &ExtractOptions,
     progress_cb: &ProgressCallback,
     cancel_flag: Arc<AtomicBool>,
-    stats: &mut ExtractStats,
-) -> Result<(), ExtractError> {
-    let file = File::open(archive_path)?;
-    let mut archive = zip::ZipArchive::new(file).map_err(|e| {
-        if e.to_string().contains("password") || e.to_string().contains("encrypted") {
-            if options.password.is_some() {
-                ExtractError::InvalidPassword
-            } else {
-                ExtractError::PasswordRequired
-            }
-        } else {
-            ExtractError::Corrupted(e.to_string())
-        }
-    })?;
-
-    for i in 0..archive.len() {
-        // Check cancellation
-        if cancel_flag.load(Ordering::Relaxed) {
-            return Err(ExtractError::Cancelled);
-        }
+) -> Result<ExtractStats, ExtractError> {
+    if options.transactional {
+        return extract_transactionally(output_dir, options, |staging, options| {
+            extract_from_reader(source, staging, options, progress_cb, cancel_flag)
+        });
+    }
 
-        let mut file = match archive.by_index(i) {
-            Ok(f) => f,
-            Err(e) => {
-                let err_str = e.to_string();
-                if err_str.contains("password") || err_str.contains("encrypted") {
-                    if options.password.is_some() {
-                        return Err(ExtractError::InvalidPassword);
-                    } else {
-                        return Err(ExtractError::PasswordRequired);
-                    }
-                }
-                return Err(ExtractError::Corrupted(err_str));
-            }
-        };
+    let start_time = Instant::now();
 
-        let entry_path = file.enclosed_name().ok_or_else(|| {
-            ExtractError::Security(crate::error::SecurityError::PathTraversal(file.name().to_string()))
-        })?;
+    // Cancel the job once its time budget runs out
+    let watchdog = options
+        .max_duration
+        .map(|limit| Watchdog::start(limit, cancel_flag.clone()));
 
-        // Validate and strip path components
-        let validated_path = validate_entry_path(&entry_path)?;
-        let final_path = strip_path_components(&validated_path, options.strip_components);
+    let format = crate::carve::stream_format(&mut source)?;
+    let archive_size = source.seek(io::SeekFrom::End(0))?;
 
-        if final_path.as_os_str().is_empty() {
-            continue;
-        }
+    let mut created = CreatedPaths::default();
+    created.record_missing_dirs(output_dir);
+    fs::create_dir_all(output_dir)?;
+    let root = Arc::new(OutputRoot::open(output_dir)?);
+
+    let mut stats = ExtractStats::default();
+    let mut collisions = CaseCollisions::new(output_dir, options);
+    let mut extraction = Extraction {
+        output_dir,
+        root: root.clone(),
+        options,
+        progress_cb,
+        cancel_flag: &cancel_flag,
+        quarantine: None,
+        collisions: &mut collisions,
+        created: &mut created,
+        journal: None,
+        stats: &mut stats,
+        entries_done: 0,
+        warnings_reported: 0,
+        total_entries: None,
+        total_bytes: None,
+        archive_size,
+        preserve_modes: options.preserves_permissions(format),
+        dir_metadata: Vec::new(),
+        restore_owners: options.preserve_ownership && running_as_root(),
+        umask: permission_mask(options),
+        links: Vec::new(),
+        linkable: HashMap::new(),
+        overwrite: options.overwrite,
+        written: BTreeMap::new(),
+    };
+    let result = temp_dir_for(output_dir, options)
+        .map_err(ExtractError::from)
+        .and_then(|temp_dir| {
+            open_stream_reader_in(source, format, options.password.as_deref(), &temp_dir)
+        })
+        .and_then(|mut reader| extraction.run(reader.as_mut()));
+    if let Err(error) = finish_extraction(result, watchdog, &cancel_flag, &mut stats, start_time) {
+        return Err(created.settle_failure(error, &root, output_dir, options, &stats));
+    }
 
-        let output_path = output_dir.join(&final_path);
+    if options.write_sha256sums {
+        crate::provenance::write_sha256sums(output_dir, &stats)?;
+    }
 
-        if file.is_dir() {
-            fs::create_dir_all(&output_path)?;
-        } else {
-            // Create parent directories
-            if let Some(parent) = output_path.parent() {
-                fs::create_dir_all(parent)?;
-            }
-
-            // Check size limits
-            let file_size = file.size();
-            let new_total = stats.bytes_written + file_size;
-            if let Some(limit) = options.size_limit_bytes {
-                if new_total > limit {
-                    return Err(ExtractError::SizeLimitExceeded {
-                        current: new_total,
-                        limit,
-                    });
-                }
-            }
+    Ok(stats)
+}
 
-            // Handle overwrite mode
-            let actual_output_path = handle_overwrite_mode(&output_path, options.overwrite)?;
+/// Extract into a staging folder next to `output_dir` with `extract_into`, then
+/// rename the staging folder to `output_dir` once everything is extracted (see
+/// [`ExtractOptions::transactional`]). On failure the staging folder is removed
+/// and `output_dir` is left as it was.
+fn extract_transactionally(
+    output_dir: &Path,
+    options: &ExtractOptions,
+    extract_into: impl FnOnce(&Path, &ExtractOptions) -> Result<ExtractStats, ExtractError>,
+) -> Result<ExtractStats, ExtractError> {
+    // Only a new or empty folder can be swapped in with one rename
+    if fs::read_dir(output_dir).is_ok_and(|mut entries| entries.next().is_some()) {
+        return Err(ExtractError::AlreadyExists(output_dir.to_path_buf()));
+    }
 
-            if options.overwrite == OverwriteMode::Skip && actual_output_path.exists() {
-                continue;
-            }
+    // Staged next to the destination, so the rename stays on one filesystem
+    let parent = parent_dir(output_dir);
+    fs::create_dir_all(parent)?;
+    let name = output_dir.file_name().unwrap_or(output_dir.as_os_str());
+    let staging = tempfile::Builder::new()
+        .prefix(&format!(".{}.staging-", name.to_string_lossy()))
+        .tempdir_in(parent)?;
+
+    // Temporary folders are private; give this one the mode the output
+    // directory would have been created with
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
 
-            // Extract file
-            let mut outfile = File::create(&actual_output_path)?;
-            io::copy(&mut file, &mut outfile)?;
+        let mode = options.dir_mode.unwrap_or(0o777 & !process_umask());
+        fs::set_permissions(staging.path(), fs::Permissions::from_mode(mode & 0o7777))?;
+    }
 
-            // Update stats
-            stats.bytes_written += file_size;
-            stats.files_extracted += 1;
+    // The staging folder is removed on failure anyway, and after a crash
+    // there is nothing in place to resume
+    let options = ExtractOptions {
+        transactional: false,
+        cleanup_on_failure: true,
+        journal: false,
+        ..options.clone()
+    };
+    let stats = extract_into(staging.path(), &options)?;
 
-            // Progress callback
-            let continue_extraction = progress_cb(
-                &final_path.to_string_lossy(),
-                stats.bytes_written,
-                Some(file_size),
-            );
+    // Not every platform lets a rename replace an empty folder
+    if output_dir.exists() {
+        fs::remove_dir(output_dir)?;
+    }
+    fs::rename(staging.path(), output_dir)?;
+    Ok(stats)
+}
 
-            if !continue_extraction {
-                return Err(ExtractError::Cancelled);
-            }
+/// Folder inside `output_dir` to extract `archive_path` into, if
+/// `options.create_subfolder` asks for one.
+fn archive_subfolder(
+    archive_path: &Path,
+    output_dir: &Path,
+    options: &ExtractOptions,
+    cancel_flag: &Arc<AtomicBool>,
+) -> Result<Option<PathBuf>, ExtractError> {
+    let create = match options.create_subfolder {
+        SubfolderPolicy::Never => false,
+        SubfolderPolicy::Always => true,
+        SubfolderPolicy::Auto => {
+            // Without the password an encrypted entry list reads as empty, and
+            // gets a folder to be safe
+            let info = crate::probe::probe_archive_with_options(
+                archive_path,
+                &options.probe_options(cancel_flag.clone()),
+            )?;
+            !info.format.is_single_file() && info.single_root.is_none()
         }
+    };
+    Ok(create.then(|| crate::discover::unique_output_dir(output_dir, archive_path)))
+}
+
+/// The folder holding `output_dir`.
+fn parent_dir(output_dir: &Path) -> &Path {
+    match output_dir.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
     }
+}
 
-    Ok(())
+/// Folder for the temporary files of an extraction into `output_dir`:
+/// `ExtractOptions::temp_dir` if set, and otherwise the folder holding
+/// `output_dir`, so that they're on the same volume. Created if missing.
+pub(crate) fn temp_dir_for(output_dir: &Path, options: &ExtractOptions) -> io::Result<PathBuf> {
+    let dir = options
+        .temp_dir
+        .clone()
+        .unwrap_or_else(|| parent_dir(output_dir).to_path_buf());
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
 }
 
-/// Extract a single compressed file (gz, bz2, xz) - not a tar archive.
-fn extract_compressed_file(
-    archive_path: &Path,
-    output_dir: &Path,
+/// Work out the totals of extracting the archive at `path` (see
+/// [`crate::probe::scan_totals`]), telling `listener` how the listing goes.
+/// Only a cancellation is an error: when the archive can't be listed, its
+/// extraction will say why.
+fn scan_totals(
+    path: &Path,
+    format: Format,
     options: &ExtractOptions,
-    progress_cb: &ProgressCallback,
-    cancel_flag: Arc<AtomicBool>,
+    listener: &ProgressListener,
+    cancel_flag: &Arc<AtomicBool>,
+) -> Result<Totals, ExtractError> {
+    let name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let (listener, cancel) = (listener.clone(), cancel_flag.clone());
+    let on_progress = ProbeProgress::new(move |_bytes_scanned, entries_found| {
+        let event = ProgressEvent {
+            phase: ProgressPhase::Scanning,
+            entry_index: entries_found,
+            total_entries: None,
+            path: name.clone(),
+            entry_bytes: 0,
+            entry_total: None,
+            bytes_written: 0,
+            total_bytes: None,
+            action: None,
+        };
+        if !listener.on_bytes(&event) {
+            cancel.store(true, Ordering::Relaxed);
+        }
+    });
+    let probe_options = ProbeOptions {
+        on_progress: Some(on_progress),
+        ..options.probe_options(cancel_flag.clone())
+    };
+    match crate::probe::scan_totals(path, format, &probe_options) {
+        Ok(totals) => Ok(totals),
+        Err(ExtractError::Cancelled) => Err(ExtractError::Cancelled),
+        Err(_) => Ok(Totals::default()),
+    }
+}
+
+/// Settle how an extraction ended: running out of time or being cancelled
+/// takes precedence over the error the reader stopped with.
+fn finish_extraction(
+    result: Result<(), ExtractError>,
+    watchdog: Option<Watchdog>,
+    cancel_flag: &AtomicBool,
     stats: &mut ExtractStats,
-    format: &str,
+    start_time: Instant,
 ) -> Result<(), ExtractError> {
-    // Check cancellation
+    // A cancellation caused by the time limit gets its own error
+    if let Some(watchdog) = watchdog {
+        let limit = watchdog.limit;
+        if watchdog.stop() {
+            stats.duration = start_time.elapsed();
+            return Err(ExtractError::TimedOut(limit));
+        }
+    }
+
+    // Check if cancelled
     if cancel_flag.load(Ordering::Relaxed) {
+        stats.cancelled = true;
+        stats.duration = start_time.elapsed();
         return Err(ExtractError::Cancelled);
     }
 
-    let file = File::open(archive_path)?;
-    
-    // Determine output filename by removing the compression extension
-    let output_filename = archive_path
-        .file_stem()
-        .ok_or_else(|| ExtractError::Corrupted("Invalid filename".to_string()))?;
-    
-    let output_path = output_dir.join(output_filename);
-    
-    // Handle overwrite mode
-    let actual_output_path = handle_overwrite_mode(&output_path, options.overwrite)?;
-    
-    if options.overwrite == OverwriteMode::Skip && actual_output_path.exists() {
-        return Ok(());
-    }
-    
-    // Create parent directories
-    if let Some(parent) = actual_output_path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    
-    // Decompress based on format
-    let mut reader: Box<dyn Read> = match format {
-        "GZIP" => Box::new(GzDecoder::new(file)),
-        "BZIP2" => Box::new(BzDecoder::new(file)),
-        "XZ" => {
-            // lzma-rs requires decompressing to memory first
-            let mut compressed = Vec::new();
-            let mut file = file;
-            file.read_to_end(&mut compressed)?;
-            let mut decompressed = Vec::new();
-            xz_decompress(&mut compressed.as_slice(), &mut decompressed)
-                .map_err(|e| ExtractError::Corrupted(format!("XZ decompression failed: {}", e)))?;
-            Box::new(std::io::Cursor::new(decompressed))
-        }
-        _ => return Err(ExtractError::UnsupportedFormat(format.to_string())),
-    };
-    
-    // Write decompressed data to output file
-    let mut outfile = File::create(&actual_output_path)?;
-    let bytes_written = io::copy(&mut reader, &mut outfile)?;
-    
-    // Check size limits
-    if let Some(limit) = options.size_limit_bytes {
-        if bytes_written > limit {
-            // Clean up the file we just created
-            let _ = fs::remove_file(&actual_output_path);
-            return Err(ExtractError::SizeLimitExceeded {
-                current: bytes_written,
-                limit,
+    // Handle extraction result
+    result?;
+
+    stats.duration = start_time.elapsed();
+    Ok(())
+}
+
+/// State shared by the entries of one extraction.
+struct Extraction<'a> {
+    output_dir: &'a Path,
+    /// Handle to the output directory, through which everything is created
+    root: Arc<OutputRoot>,
+    options: &'a ExtractOptions,
+    progress_cb: &'a ProgressCallback,
+    cancel_flag: &'a AtomicBool,
+    quarantine: Option<&'a [u8]>,
+    collisions: &'a mut CaseCollisions,
+    created: &'a mut CreatedPaths,
+    journal: Option<&'a mut JournalWriter>,
+    stats: &'a mut ExtractStats,
+    /// Number of entries handled so far
+    entries_done: u64,
+    /// Number of `stats.warnings` the progress listener was told of
+    warnings_reported: usize,
+    /// Number of entries and bytes the extraction will get through, if known
+    total_entries: Option<u64>,
+    total_bytes: Option<u64>,
+    /// Size of the archive data, for the expansion ratio limit
+    archive_size: u64,
+    /// Whether the modes recorded for entries are applied
+    preserve_modes: bool,
+    /// Directories to give their recorded metadata once everything is
+    /// extracted
+    dir_metadata: Vec<DirMetadata>,
+    /// Whether the owners recorded for entries are restored
+    restore_owners: bool,
+    /// Bits cleared from the modes recorded for entries (see
+    /// [`permission_mask`])
+    umask: u32,
+    /// Links to write copies of their targets for once everything is extracted
+    links: Vec<MaterializedLink>,
+    /// Where the files hard links can link to were extracted, by their path
+    /// in the archive
+    linkable: HashMap<String, PathBuf>,
+    /// Overwrite mode in effect, which an answer to the overwrite prompt may
+    /// have changed for the rest of the extraction
+    overwrite: OverwriteMode,
+    /// Files to read back once everything is extracted, by where they were
+    /// written (see `ExtractOptions::verify_after_extract`)
+    written: BTreeMap<PathBuf, ExpectedFile>,
+}
+
+/// What a written file should hold, from its entry.
+struct ExpectedFile {
+    /// Path of the entry in the archive
+    entry: String,
+    /// Recorded size, unless only estimated
+    size: Option<u64>,
+    crc32: Option<u32>,
+}
+
+/// A symbolic link entry written as a copy of its target.
+struct MaterializedLink {
+    /// Name of the entry, as reported
+    name: String,
+    /// Where the copy goes
+    path: PathBuf,
+    /// Whether `path` was renamed to avoid an existing file
+    renamed: bool,
+    /// What the link points to
+    target: PathBuf,
+}
+
+/// Mode and modification time recorded for a directory entry.
+struct DirMetadata {
+    path: PathBuf,
+    mode: Option<u32>,
+    modified: Option<u64>,
+}
+
+impl Extraction<'_> {
+    /// Extract every entry the archive reader produces, collecting the
+    /// reader's warnings.
+    fn run(&mut self, reader: &mut dyn ArchiveReader) -> Result<(), ExtractError> {
+        reader.set_tolerate_trailing_data(self.options.tolerate_trailing_data);
+        reader.set_read_concatenated(self.options.read_concatenated_tar);
+        if let Some(encoding) = self.options.filename_encoding {
+            reader.set_filename_encoding(encoding);
+        }
+        if self.options.preserve_ownership && !self.restore_owners {
+            self.stats
+                .warnings
+                .push("Not restoring file ownership: only root can change it".to_string());
+        }
+        let result = self.read_all(reader);
+        self.stats.warnings.extend(reader.take_warnings());
+        self.report_warnings();
+        result?;
+        self.verify_written()?;
+        self.materialize_links()?;
+
+        // Applied last, so read-only directories don't stop their contents
+        // from being written and writing them doesn't touch the times
+        for dir in self.dir_metadata.drain(..) {
+            if let Some(modified) = dir.modified {
+                set_modified(&self.root, &dir.path, modified)?;
+            }
+            apply_permissions(&self.root, &dir.path, dir.mode, None, self.umask)?;
+        }
+        Ok(())
+    }
+
+    /// Hand every entry the archive reader produces to `extract_entry`.
+    ///
+    /// With `threads` above 1 and a reader whose entries can be decoded on
+    /// other threads, files are decoded and written by a pool of workers (see
+    /// [`crate::parallel`]). Otherwise, unless `write_queue_depth` is 0,
+    /// entries are decoded on this thread and written out by a second one (see
+    /// [`crate::pipeline`]). A journal only records files once they are
+    /// written, and skipping failed entries needs each file's failure before
+    /// going on, so with either they are written here.
+    fn read_all(&mut self, reader: &mut dyn ArchiveReader) -> Result<(), ExtractError> {
+        let single_file = reader.is_single_file();
+        let write_here = self.journal.is_some() || self.options.on_error == ErrorPolicy::Skip;
+        if !write_here && self.options.threads > 1 {
+            if let Some(decoder) = reader.open_decoder()? {
+                let mut decoders = vec![decoder];
+                for _ in 1..self.options.threads {
+                    decoders.extend(reader.open_decoder()?);
+                }
+                return self.read_in_parallel(reader, decoders, single_file);
+            }
+        }
+
+        let queue_depth = if write_here {
+            0
+        } else {
+            self.options.write_queue_depth
+        };
+        if queue_depth == 0 {
+            return reader.read_entries(&mut |entry, data| {
+                self.extract_or_skip(entry, data, single_file)?;
+                Ok(true)
             });
         }
+
+        let (options, quarantine) = (self.options, self.quarantine);
+        thread::scope(|scope| {
+            let mut writer = Writer::spawn(scope, queue_depth, move |job: &FileJob| {
+                finish_file(job, options, quarantine)
+            });
+            let read = reader.read_entries(&mut |entry, data| {
+                self.extract_entry(entry, data, single_file, Sink::Queue(&mut writer))?;
+                Ok(true)
+            });
+
+            // A failed write stops the reading too, with a less useful error
+            writer.finish().and(read)
+        })
     }
-    
-    // Update stats
-    stats.bytes_written = bytes_written;
-    stats.files_extracted = 1;
-    
-    // Progress callback
-    let continue_extraction = progress_cb(
-        &output_filename.to_string_lossy(),
-        bytes_written,
-        Some(bytes_written),
-    );
-    
-    if !continue_extraction {
-        return Err(ExtractError::Cancelled);
+
+    /// Hand every entry to `extract_entry`, with workers decoding and writing
+    /// the files, one for each of `decoders`.
+    fn read_in_parallel(
+        &mut self,
+        reader: &mut dyn ArchiveReader,
+        decoders: Vec<Box<dyn EntryDecoder>>,
+        single_file: bool,
+    ) -> Result<(), ExtractError> {
+        let (options, quarantine, cancel_flag) = (self.options, self.quarantine, self.cancel_flag);
+        thread::scope(|scope| {
+            let mut workers = Workers::spawn(scope, decoders, cancel_flag, move |job: &FileJob| {
+                finish_file(job, options, quarantine)
+            });
+            let read = reader.read_entries(&mut |entry, data| {
+                self.extract_entry(entry, data, single_file, Sink::Workers(&mut workers))?;
+                Ok(true)
+            });
+
+            // Workers report their own errors while reading goes on
+            if read.is_err() {
+                workers.stop();
+            }
+            let written = workers.finish();
+            read?;
+            self.record_written(written?)
+        })
     }
-    
-    Ok(())
-}
 
-/// Extract TAR archive (with optional compression) using tar crate.
-fn extract_tar_archive(
-    archive_path: &Path,
-    output_dir: &Path,
-    options: &ExtractOptions,
-    progress_cb: &ProgressCallback,
-    cancel_flag: Arc<AtomicBool>,
-    stats: &mut ExtractStats,
-    format: &str,
-) -> Result<(), ExtractError> {
-    let file = File::open(archive_path)?;
-
-    // Create appropriate decompressor based on format
-    let reader: Box<dyn Read> = match format {
-        "TAR.GZ" => Box::new(GzDecoder::new(file)),
-        "TAR.BZ2" => Box::new(BzDecoder::new(file)),
-        "TAR.XZ" => {
-            // lzma-rs requires decompressing to memory first
-            let mut compressed = Vec::new();
-            let mut file = file;
-            file.read_to_end(&mut compressed)?;
-            let mut decompressed = Vec::new();
-            xz_decompress(&mut compressed.as_slice(), &mut decompressed)
-                .map_err(|e| ExtractError::Corrupted(format!("XZ decompression failed: {}", e)))?;
-            Box::new(std::io::Cursor::new(decompressed))
-        }
-        _ => Box::new(file),
-    };
+    /// Extract one entry, writing its file here. If it fails on its own and
+    /// `on_error` is `Skip`, the failure is recorded instead and extraction
+    /// goes on.
+    fn extract_or_skip(
+        &mut self,
+        entry: &Entry,
+        data: &mut dyn EntryData,
+        single_file: bool,
+    ) -> Result<(), ExtractError> {
+        match self.extract_entry(entry, data, single_file, Sink::Here) {
+            Err(e) if self.options.on_error == ErrorPolicy::Skip && is_entry_error(&e) => {
+                self.stats.errors.push(EntryError {
+                    path: entry.path.clone(),
+                    error: e.to_string(),
+                });
+                self.report_entry(&entry.path, EntryAction::Failed, 0, None)
+            }
+            result => result,
+        }
+    }
 
-    let mut archive = tar::Archive::new(reader);
+    /// Extract one entry: validate and map its path, apply the filter, size
+    /// limit and overwrite mode, then write it out and report progress.
+    ///
+    /// A single compressed file's entry is named after the archive, so it is
+    /// neither validated nor stripped like paths stored in an archive. File
+    /// data goes to `sink`; files handed to workers are counted and reported
+    /// once a worker has written them.
+    fn extract_entry(
+        &mut self,
+        entry: &Entry,
+        data: &mut dyn EntryData,
+        single_file: bool,
+        mut sink: Sink<'_, '_>,
+    ) -> Result<(), ExtractError> {
+        let options = self.options;
 
-    for entry_result in archive.entries()? {
         // Check cancellation
-        if cancel_flag.load(Ordering::Relaxed) {
+        if self.cancel_flag.load(Ordering::Relaxed) {
             return Err(ExtractError::Cancelled);
         }
 
-        let mut entry = entry_result?;
-        let entry_path = entry.path()?.to_path_buf();
-
         // Validate and strip path components
-        let validated_path = validate_entry_path(&entry_path)?;
-        let final_path = strip_path_components(&validated_path, options.strip_components);
+        let mut renamed = false;
+        let (validated_path, stripped_path) = if single_file {
+            (PathBuf::from(&entry.path), PathBuf::from(&entry.path))
+        } else {
+            let validated_path = checked_entry_path(Path::new(&entry.path), options, &mut renamed)?;
+            let stripped_path = strip_path_components(&validated_path, options.strip_components);
+            (validated_path, stripped_path)
+        };
+        let final_path = map_entry_path(stripped_path, options)?;
 
         if final_path.as_os_str().is_empty() {
-            continue;
+            return Ok(());
         }
 
-        let output_path = output_dir.join(&final_path);
+        let sanitized = sanitize_names(&final_path, &options.sanitize_names);
+        renamed |= sanitized.is_some();
+        let final_path = sanitized.unwrap_or(final_path);
 
-        if entry.header().entry_type().is_dir() {
-            fs::create_dir_all(&output_path)?;
-        } else {
-            // Create parent directories
-            if let Some(parent) = output_path.parent() {
-                fs::create_dir_all(parent)?;
-            }
-
-            // Check size limits
-            let file_size = entry.header().size()?;
-            let new_total = stats.bytes_written + file_size;
-            if let Some(limit) = options.size_limit_bytes {
-                if new_total > limit {
-                    return Err(ExtractError::SizeLimitExceeded {
-                        current: new_total,
-                        limit,
-                    });
-                }
+        // Names Windows can't create
+        let final_path = match (options.windows_names, sanitize_windows_names(&final_path)) {
+            (WindowsNamePolicy::Allow, _) | (_, None) => final_path,
+            (WindowsNamePolicy::Strict, Some(_)) => {
+                let name = final_path.display().to_string();
+                return Err(crate::error::SecurityError::NonPortableName(name).into());
+            }
+            (WindowsNamePolicy::Rename, Some(portable)) => {
+                renamed = true;
+                portable
+            }
+            (WindowsNamePolicy::Skip, Some(_)) => {
+                let name = final_path.to_string_lossy().to_string();
+                self.stats.files_skipped += 1;
+                self.stats
+                    .warnings
+                    .push(format!("Skipped {} (name not valid on Windows)", name));
+                return self.report_entry(&name, EntryAction::Skipped, 0, None);
+            }
+        };
+        let name = final_path.to_string_lossy().to_string();
+        if renamed {
+            self.stats.renamed.push(RenamedEntry {
+                path: entry.path.clone(),
+                renamed_to: name.clone(),
+            });
+        }
+        // A single compressed file's size is only an estimate
+        let size = (!single_file).then_some(entry.size);
+        if !self.report_progress(&name, 0, size, None, ProgressListener::on_entry_start) {
+            return Err(ExtractError::Cancelled);
+        }
+
+        // Listing metadata for the entry filter and pre-write hook
+        let meta = wants_entry_meta(options).then(|| ArchiveEntry {
+            path: validated_path.to_string_lossy().to_string(),
+            ..ArchiveEntry::from(entry)
+        });
+        if filtered_out(options, meta.as_ref()) {
+            return self.report_entry(&name, EntryAction::Skipped, 0, Some(entry.size));
+        }
+
+        // Written before an interrupted run being resumed stopped
+        let entry_name = validated_path.to_string_lossy();
+        if let Some(journal) = &self.journal {
+            if journal.already_written(&entry_name) {
+                return self.report_entry(&name, EntryAction::Skipped, 0, Some(entry.size));
             }
+        }
 
-            // Handle overwrite mode
-            let actual_output_path = handle_overwrite_mode(&output_path, options.overwrite)?;
+        if !is_safe_entry_type(entry.kind, options) {
+            self.stats.files_skipped += 1;
+            self.stats
+                .warnings
+                .push(format!("Skipped {} ({})", name, blocked_reason(entry.kind)));
+            return self.report_entry(&name, EntryAction::Skipped, 0, None);
+        }
 
-            if options.overwrite == OverwriteMode::Skip && actual_output_path.exists() {
-                continue;
+        self.collisions.check(&final_path, self.stats);
+
+        let output_path = self.output_dir.join(&final_path);
+
+        // Let queued files at this path land before looking at what's there
+        self.wait_for(&mut sink, &output_path)?;
+
+        if entry.is_directory {
+            create_dirs(
+                &self.root,
+                &output_path,
+                options,
+                self.quarantine,
+                self.created,
+            )?;
+            restore_owner(&output_path, entry.owner.filter(|_| self.restore_owners))?;
+            restore_xattrs(&output_path, &restored_xattrs(entry, options))?;
+            let mode = entry
+                .mode
+                .filter(|_| self.preserve_modes && options.dir_mode.is_none());
+            let modified = entry.modified.filter(|_| preserve_times(options));
+            if mode.is_some() || modified.is_some() {
+                self.dir_metadata.push(DirMetadata {
+                    path: output_path,
+                    mode,
+                    modified,
+                });
             }
+            self.stats.directories += 1;
+            return self.report_entry(&name, EntryAction::Directory, 0, None);
+        }
+
+        // Create parent directories
+        if let Some(parent) = output_path.parent() {
+            create_dirs(&self.root, parent, options, self.quarantine, self.created)?;
+        }
+
+        if entry.kind == EntryType::Symlink {
+            let target = symlink_target(entry, data)?;
+            return self.extract_symlink(entry, &name, &output_path, &target);
+        }
+        if let Some(target) = &entry.hardlink_target {
+            return self.extract_hardlink(entry, &name, &output_path, target, &mut sink);
+        }
+
+        // Files recorded as over the per-entry limit, unless that fails them
+        let oversized = options
+            .max_entry_size_bytes
+            .filter(|&limit| entry.size > limit);
+        if let (Some(limit), OversizePolicy::Skip) = (oversized, options.oversized_entries) {
+            self.stats.files_skipped += 1;
+            self.stats.warnings.push(format!(
+                "Skipped {} ({} bytes, over the {}-byte limit)",
+                name, entry.size, limit
+            ));
+            return self.report_entry(&name, EntryAction::Skipped, 0, size);
+        }
 
-            // Extract file
-            let mut outfile = File::create(&actual_output_path)?;
-            io::copy(&mut entry, &mut outfile)?;
+        // Check size limits, counting the files the workers are still writing
+        let pending = match &sink {
+            Sink::Workers(workers) => workers.reserved(),
+            _ => 0,
+        };
+        self.check_limits(
+            &entry_name,
+            entry.size,
+            entry.compressed_size,
+            self.stats.bytes_written + pending,
+        )?;
+
+        // Handle overwrite mode
+        let overwrite = self.overwrite_mode(&output_path, entry);
+        let actual_output_path =
+            handle_overwrite_mode(&output_path, overwrite, options.rename_template.as_deref())?;
+
+        if skip_existing(&actual_output_path, overwrite, entry.modified, size) {
+            self.stats.files_skipped += 1;
+            return self.report_entry(&name, EntryAction::Skipped, 0, size);
+        }
+        let action = if actual_output_path == output_path {
+            EntryAction::Written
+        } else {
+            EntryAction::Renamed
+        };
+
+        // Extract file, unless the pre-write hook or content policy rejects it
+        let inspected = match inspect_entry(
+            data,
+            &actual_output_path,
+            self.output_dir,
+            meta.as_ref(),
+            options,
+            self.stats,
+        )? {
+            Inspection::Untouched => None,
+            Inspection::Write(inspected) => Some(inspected),
+            Inspection::Rejected => return self.report_entry(&name, EntryAction::Skipped, 0, size),
+        };
+        if !actual_output_path.exists() {
+            self.created.files.push(actual_output_path.clone());
+        }
+        if options.allow_hardlinks {
+            self.linkable
+                .insert(entry_name.to_string(), actual_output_path.clone());
+        }
+        if let Some(journal) = self.journal.as_deref_mut() {
+            journal.start_file(&actual_output_path, &self.created.files, &self.created.dirs);
+        }
+        if options.verify_after_extract {
+            self.written.insert(
+                actual_output_path.clone(),
+                ExpectedFile {
+                    entry: entry.path.clone(),
+                    size,
+                    crc32: entry.crc32,
+                },
+            );
+        }
+        let job = FileJob {
+            root: self.root.clone(),
+            write_path: if options.atomic_writes {
+                partial_path(&actual_output_path)
+            } else {
+                actual_output_path.clone()
+            },
+            final_path: actual_output_path,
+            mode: entry.mode.filter(|_| self.preserve_modes),
+            modified: entry.modified.filter(|_| preserve_times(options)),
+            xattrs: restored_xattrs(entry, options),
+            owner: entry.owner.filter(|_| self.restore_owners),
+            umask: self.umask,
+            uncached: options.bypass_page_cache,
+            buffer_size: options.buffer_size,
+            sparse: options.sparse_files,
+            size,
+        };
+
+        if let (Sink::Workers(workers), Some(index), None) = (&mut sink, data.index(), &inspected) {
+            let max_len = self.max_len(entry.compressed_size, self.stats.bytes_written + pending);
+            let written = workers.extract(QueuedFile {
+                index,
+                requested: output_path,
+                job,
+                max_len,
+                reserved: entry.size,
+                compressed_size: entry.compressed_size,
+                name,
+                entry: entry_name.to_string(),
+                action,
+                hash: options.hashes_files(),
+            })?;
+            return self.record_written(written);
+        }
+
+        let written = match sink {
+            Sink::Queue(writer) if !data.writes_directly() => {
+                let max_len = self.max_len(entry.compressed_size, self.stats.bytes_written);
+                let inspected = inspected.unwrap_or_default();
+                let mut reader =
+                    HashingReader::new(inspected.chain(data.reader()?), options.hashes_files());
+                let final_path = job.final_path.clone();
+                let mut tick = |queued| self.report_partial(&name, queued, size);
+                let written =
+                    writer.write_file(&output_path, job, &mut reader, max_len, &mut tick)?;
+                // The writer discards a file going over the limit
+                self.check_limits(
+                    &entry_name,
+                    written,
+                    entry.compressed_size,
+                    self.stats.bytes_written,
+                )?;
+                self.record_hash(&final_path, reader.digest());
+                written
+            }
+            _ => self.write_file(&name, data, inspected, &job, entry)?,
+        };
+        self.record_file(&name, &entry_name, written, action)
+    }
+
+    /// Report progress of the file `name`, of `size` bytes if known, after its
+    /// first `copied` bytes, returning whether to go on.
+    fn report_partial(&self, name: &str, copied: u64, size: Option<u64>) -> bool {
+        !self.cancel_flag.load(Ordering::Relaxed)
+            && (self.progress_cb)(name, self.stats.bytes_written + copied, None)
+            && self.report_progress(name, copied, size, None, ProgressListener::on_bytes)
+    }
+
+    /// Overwrite mode to write `entry` to `path` with. With
+    /// `OverwriteMode::Ask` and a file already at `path`, it's what the
+    /// overwrite prompt answers.
+    fn overwrite_mode(&mut self, path: &Path, entry: &Entry) -> OverwriteMode {
+        if self.overwrite != OverwriteMode::Ask || path.is_dir() || path.symlink_metadata().is_err()
+        {
+            return self.overwrite;
+        }
+        let Some(prompt) = &self.options.overwrite_prompt else {
+            return OverwriteMode::Error;
+        };
+        match prompt.ask(path, &ArchiveEntry::from(entry)) {
+            OverwriteDecision::Replace => OverwriteMode::Replace,
+            OverwriteDecision::Skip => OverwriteMode::Skip,
+            OverwriteDecision::Rename => OverwriteMode::Rename,
+            OverwriteDecision::Abort | OverwriteDecision::ApplyToAll(OverwriteMode::Ask) => {
+                OverwriteMode::Error
+            }
+            OverwriteDecision::ApplyToAll(mode) => {
+                self.overwrite = mode;
+                mode
+            }
+        }
+    }
+
+    /// Tell the entry observer and the progress callbacks that the entry
+    /// `name`, of `size` bytes if it has any, was handled as `action`, writing
+    /// `written` bytes.
+    fn report_entry(
+        &mut self,
+        name: &str,
+        action: EntryAction,
+        written: u64,
+        size: Option<u64>,
+    ) -> Result<(), ExtractError> {
+        if let Some(observer) = &self.options.on_entry {
+            observer.notify(name, action);
+        }
+        self.report_warnings();
+        let go_on = (self.progress_cb)(name, self.stats.bytes_written, Some(written))
+            && self.report_progress(
+                name,
+                written,
+                size,
+                Some(action),
+                ProgressListener::on_entry_done,
+            );
+        self.entries_done += 1;
+        if !go_on {
+            return Err(ExtractError::Cancelled);
+        }
+        Ok(())
+    }
+
+    /// Pass the progress listener, if any, to `report` with an event for the
+    /// current entry, returning whether to go on.
+    fn report_progress(
+        &self,
+        name: &str,
+        entry_bytes: u64,
+        entry_total: Option<u64>,
+        action: Option<EntryAction>,
+        report: fn(&ProgressListener, &ProgressEvent) -> bool,
+    ) -> bool {
+        let Some(listener) = &self.options.on_progress else {
+            return true;
+        };
+        let event = ProgressEvent {
+            phase: ProgressPhase::Extracting,
+            entry_index: self.entries_done,
+            total_entries: self.total_entries,
+            path: name.to_string(),
+            entry_bytes,
+            entry_total,
+            // A finished entry's bytes are already counted
+            bytes_written: match action {
+                Some(_) => self.stats.bytes_written,
+                None => self.stats.bytes_written + entry_bytes,
+            },
+            total_bytes: self.total_bytes,
+            action,
+        };
+        report(listener, &event)
+    }
+
+    /// Tell the progress listener, if any, of the warnings recorded since it
+    /// was last told.
+    fn report_warnings(&mut self) {
+        if let Some(listener) = &self.options.on_progress {
+            for warning in &self.stats.warnings[self.warnings_reported..] {
+                listener.on_warning(warning);
+            }
+        }
+        self.warnings_reported = self.stats.warnings.len();
+    }
+
+    /// Count a file of `written` bytes, written as `name` from the archive
+    /// entry `entry` and handled as `action`, and report progress.
+    fn record_file(
+        &mut self,
+        name: &str,
+        entry: &str,
+        written: u64,
+        action: EntryAction,
+    ) -> Result<(), ExtractError> {
+        // Update stats
+        self.stats.bytes_written += written;
+        self.stats.files_extracted += 1;
+        if let Some(journal) = self.journal.as_deref_mut() {
+            journal.finish_entry(entry, written);
+        }
+
+        self.report_entry(name, action, written, Some(written))
+    }
+
+    /// Create the symbolic link entry `name` at `output_path`, if links are
+    /// allowed and its target stays inside the output directory.
+    fn extract_symlink(
+        &mut self,
+        entry: &Entry,
+        name: &str,
+        output_path: &Path,
+        target: &str,
+    ) -> Result<(), ExtractError> {
+        let options = self.options;
+
+        // Checked from where the link's folder really is, which an earlier
+        // link in the archive may have moved
+        let output_dir = fs::canonicalize(self.output_dir)?;
+        let link_dir = fs::canonicalize(parent_dir(output_path))?;
+        let link_dir = link_dir
+            .strip_prefix(&output_dir)
+            .map_err(|_| SecurityError::SymlinkEscape(name.to_string()))?;
+        validate_symlink_target(link_dir, target)?;
+
+        let overwrite = self.overwrite_mode(output_path, entry);
+        let link_path =
+            handle_overwrite_mode(output_path, overwrite, options.rename_template.as_deref())?;
+        if skip_existing(&link_path, overwrite, entry.modified, None) {
+            self.stats.files_skipped += 1;
+            return self.report_entry(name, EntryAction::Skipped, 0, None);
+        }
+        if options.symlink_policy == SymlinkPolicy::Materialize {
+            // The target's leading ".." components climb from the link's folder
+            let mut target_path = output_dir.join(link_dir);
+            for component in Path::new(target).components() {
+                match component {
+                    Component::ParentDir => {
+                        target_path.pop();
+                    }
+                    Component::Normal(part) => target_path.push(part),
+                    _ => {}
+                }
+            }
+            self.links.push(MaterializedLink {
+                name: name.to_string(),
+                renamed: link_path != output_path,
+                path: link_path,
+                target: target_path,
+            });
+            return Ok(());
+        }
+        if link_path.symlink_metadata().is_ok() {
+            self.root.remove_file(&link_path)?;
+        } else {
+            self.created.files.push(link_path.clone());
+        }
+        self.root.symlink(target, &link_path)?;
+
+        self.stats.files_extracted += 1;
+        let action = if link_path == output_path {
+            EntryAction::Written
+        } else {
+            EntryAction::Renamed
+        };
+        self.report_entry(name, action, 0, None)
+    }
+
+    /// Create the hard link entry `name` at `output_path`, linking to the file
+    /// extracted for the entry at `target` in the archive.
+    fn extract_hardlink(
+        &mut self,
+        entry: &Entry,
+        name: &str,
+        output_path: &Path,
+        target: &str,
+        sink: &mut Sink<'_, '_>,
+    ) -> Result<(), ExtractError> {
+        let options = self.options;
+
+        // Only files this extraction wrote can be linked to, so links can't
+        // reach outside the output directory
+        let target = validate_entry_path_with_limits(Path::new(target), &options.path_limits)?;
+        let target = normalized(target, options);
+        let Some(target_path) = self.linkable.get(&*target.to_string_lossy()).cloned() else {
+            self.stats.files_skipped += 1;
+            self.stats.warnings.push(format!(
+                "Skipped {} (its link target {} wasn't extracted)",
+                name,
+                target.display()
+            ));
+            return self.report_entry(name, EntryAction::Skipped, 0, None);
+        };
+        self.wait_for(sink, &target_path)?;
+
+        let overwrite = self.overwrite_mode(output_path, entry);
+        let link_path =
+            handle_overwrite_mode(output_path, overwrite, options.rename_template.as_deref())?;
+        if skip_existing(&link_path, overwrite, entry.modified, None) {
+            self.stats.files_skipped += 1;
+            return self.report_entry(name, EntryAction::Skipped, 0, None);
+        }
+        if link_path.symlink_metadata().is_ok() {
+            self.root.remove_file(&link_path)?;
+        } else {
+            self.created.files.push(link_path.clone());
+        }
+        let written = match self.root.hard_link(&target_path, &link_path) {
+            Ok(()) => 0,
+            // Across filesystems, or on one without hard links
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::CrossesDevices | io::ErrorKind::Unsupported
+                ) =>
+            {
+                self.root.copy(&target_path, &link_path)?
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        self.stats.files_extracted += 1;
+        self.stats.bytes_written += written;
+        let action = if link_path == output_path {
+            EntryAction::Written
+        } else {
+            EntryAction::Renamed
+        };
+        self.report_entry(name, action, written, Some(written))
+    }
+
+    /// Let files queued for writing at `path` land.
+    fn wait_for(&mut self, sink: &mut Sink<'_, '_>, path: &Path) -> Result<(), ExtractError> {
+        match sink {
+            Sink::Here => {}
+            Sink::Queue(writer) => writer.wait_for(path)?,
+            Sink::Workers(workers) => {
+                let written = workers.wait_for(path)?;
+                self.record_written(written)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write copies of the targets of materialized links in their place. A
+    /// link to another link waits for that one's copy.
+    fn materialize_links(&mut self) -> Result<(), ExtractError> {
+        let mut pending = std::mem::take(&mut self.links);
+        loop {
+            let waiting = pending.len();
+            let mut unresolved = Vec::new();
+            for link in pending {
+                if link.target.exists() {
+                    self.materialize_link(link)?;
+                } else {
+                    unresolved.push(link);
+                }
+            }
+            pending = unresolved;
+            if pending.is_empty() || pending.len() == waiting {
+                break;
+            }
+        }
+
+        for link in pending {
+            self.stats.files_skipped += 1;
+            self.stats.warnings.push(format!(
+                "Skipped {} (its link target isn't in the archive)",
+                link.name
+            ));
+            self.report_entry(&link.name, EntryAction::Skipped, 0, None)?;
+        }
+        Ok(())
+    }
+
+    /// Write a copy of a materialized link's target in its place.
+    fn materialize_link(&mut self, link: MaterializedLink) -> Result<(), ExtractError> {
+        // A copy of a folder holding the link would hold another copy
+        if link.path.starts_with(&link.target) {
+            self.stats.files_skipped += 1;
+            self.stats.warnings.push(format!(
+                "Skipped {} (it links to a folder holding it)",
+                link.name
+            ));
+            return self.report_entry(&link.name, EntryAction::Skipped, 0, None);
+        }
+
+        if link.path.symlink_metadata().is_ok() {
+            self.root.remove_file(&link.path)?;
+        }
+        let written = copy_tree(&self.root, &link.target, &link.path, self.created)?;
+        self.stats.files_extracted += 1;
+        self.stats.bytes_written += written;
+        let action = if link.renamed {
+            EntryAction::Renamed
+        } else {
+            EntryAction::Written
+        };
+        self.report_entry(&link.name, action, written, Some(written))
+    }
+
+    /// Read back the files written and list those that differ from their
+    /// entries in `stats.mismatches`. Files of entries that failed are left
+    /// out.
+    fn verify_written(&mut self) -> Result<(), ExtractError> {
+        let failed: HashSet<&str> = self
+            .stats
+            .errors
+            .iter()
+            .map(|failure| failure.path.as_str())
+            .collect();
+        let mut mismatches = Vec::new();
+        for (path, expected) in &self.written {
+            if failed.contains(expected.entry.as_str()) {
+                continue;
+            }
+            if self.cancel_flag.load(Ordering::Relaxed) {
+                return Err(ExtractError::Cancelled);
+            }
+            if let Some(reason) = verify_file(path, expected, self.options.buffer_size)? {
+                mismatches.push(VerifyMismatch {
+                    path: expected.entry.clone(),
+                    reason,
+                });
+            }
+        }
+        self.stats.mismatches.extend(mismatches);
+        Ok(())
+    }
+
+    /// Count the files workers have written.
+    fn record_written(&mut self, written: Vec<Written>) -> Result<(), ExtractError> {
+        for file in written {
+            // A worker discards a file going over the limit
+            self.check_limits(
+                &file.entry,
+                file.len,
+                file.compressed_size,
+                self.stats.bytes_written,
+            )?;
+            self.record_hash(&file.path, file.sha256);
+            self.record_file(&file.name, &file.entry, file.len, file.action)?;
+        }
+        Ok(())
+    }
+
+    /// List the SHA-256 `digest` of the file written to `path`, if it was
+    /// hashed, in `stats.sha256`.
+    fn record_hash(&mut self, path: &Path, digest: Option<String>) {
+        if let Some(digest) = digest {
+            let relative = path.strip_prefix(self.output_dir).unwrap_or(path);
+            self.stats
+                .sha256
+                .insert(relative.to_string_lossy().replace('\\', "/"), digest);
+        }
+    }
+
+    /// Write the file `job` of `entry`, extracted as `name`, on this thread,
+    /// starting with the data `inspected` already read from it, if any.
+    /// Returns the number of bytes written.
+    fn write_file(
+        &mut self,
+        name: &str,
+        data: &mut dyn EntryData,
+        inspected: Option<Inspected>,
+        job: &FileJob,
+        entry: &Entry,
+    ) -> Result<u64, ExtractError> {
+        let hash = self.options.hashes_files();
+        let mut digest = None;
+        let mut tick = |copied| self.report_partial(name, copied, job.size);
+        let result = match inspected {
+            // Nothing read the data, so the reader may write the file its own way
+            None if data.writes_directly() => data.extract_to(&job.write_path),
+            inspected => {
+                let inspected = inspected.unwrap_or_default();
+                let mut reader = HashingReader::new(inspected.chain(data.reader()?), hash);
+                let result = copy_to_file(&mut reader, job, &mut tick);
+                digest = reader.digest();
+                result
+            }
+        };
+        let finished = result.and_then(|written| {
+            finish_file(job, self.options, self.quarantine)?;
+            // The data never went by, so read back what the reader wrote
+            if hash && digest.is_none() {
+                digest = Some(sha256_file(&job.final_path)?);
+            }
+            Ok(written)
+        });
+        let written = match finished {
+            Ok(written) => written,
+            Err(e) => {
+                // Don't leave the incomplete temporary file behind
+                if job.write_path != job.final_path {
+                    let _ = job.root.remove_file(&job.write_path);
+                }
+                return Err(e);
+            }
+        };
+
+        // Recorded sizes can be wrong, so check again with what was written
+        let limits = self.check_limits(
+            &entry.path,
+            written,
+            entry.compressed_size,
+            self.stats.bytes_written,
+        );
+        if let Err(e) = limits {
+            // Clean up the file we just created
+            let _ = job.root.remove_file(&job.final_path);
+            return Err(e);
+        }
+        self.record_hash(&job.final_path, digest);
+        Ok(written)
+    }
+
+    /// Fail if the entry `name`, of `len` bytes from `compressed` ones, takes
+    /// the extraction from `before` bytes past the size limits or the
+    /// compression ratio limits.
+    fn check_limits(
+        &self,
+        name: &str,
+        len: u64,
+        compressed: Option<u64>,
+        before: u64,
+    ) -> Result<(), ExtractError> {
+        let total = before + len;
+        check_size_limit(total, self.options)?;
+        let entry_limit = self.options.max_entry_size_bytes;
+        if let Some(limit) = entry_limit.filter(|&limit| len > limit) {
+            return Err(ExtractError::EntryTooLarge {
+                entry: name.to_string(),
+                size: len,
+                limit,
+            });
+        }
+        if let (Some(ratio), Some(compressed)) = (self.options.max_compression_ratio, compressed) {
+            if len > expansion_cap(compressed, ratio) {
+                return Err(SecurityError::SuspectedArchiveBomb(format!(
+                    "{} expands {} compressed bytes to {}, over {}:1",
+                    name, compressed, len, ratio
+                ))
+                .into());
+            }
+        }
+        if let Some(ratio) = self.options.max_expansion_ratio {
+            if total > expansion_cap(self.archive_size, ratio) {
+                return Err(SecurityError::SuspectedArchiveBomb(format!(
+                    "the archive expands {} bytes to {}, over {}:1",
+                    self.archive_size, total, ratio
+                ))
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Most bytes a file with `compressed` compressed ones may be written to
+    /// with `before` bytes already extracted, if limited.
+    fn max_len(&self, compressed: Option<u64>, before: u64) -> Option<u64> {
+        let options = self.options;
+        let size_cap = options
+            .size_limit_bytes
+            .map(|limit| limit.saturating_sub(before));
+        let total_cap = options
+            .max_expansion_ratio
+            .map(|ratio| expansion_cap(self.archive_size, ratio).saturating_sub(before));
+        let entry_cap = options
+            .max_compression_ratio
+            .zip(compressed)
+            .map(|(ratio, compressed)| expansion_cap(compressed, ratio));
+        [size_cap, options.max_entry_size_bytes, total_cap, entry_cap]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+}
+
+/// Most bytes `compressed` ones may expand to at `ratio`:1.
+fn expansion_cap(compressed: u64, ratio: u32) -> u64 {
+    compressed.max(1).saturating_mul(u64::from(ratio))
+}
+
+/// Where [`Extraction::extract_entry`] has file data written.
+enum Sink<'w, 'scope> {
+    /// On the decoding thread
+    Here,
+    /// By the writer thread
+    Queue(&'w mut Writer<'scope>),
+    /// By the worker pool
+    Workers(&'w mut Workers<'scope>),
+}
+
+/// Write `data` to a new file at the write path of `job`, returning the number
+/// of bytes written. `tick` is called as in [`copy_with_progress`].
+fn copy_to_file(
+    data: &mut dyn Read,
+    job: &FileJob,
+    tick: &mut dyn FnMut(u64) -> bool,
+) -> Result<u64, ExtractError> {
+    let mut outfile = job.create()?;
+    let written = copy_with_progress(data, &mut outfile, tick)?;
+    outfile.finish()?;
+    Ok(written)
+}
+
+/// Reader hashing the data read through it with SHA-256, if asked to.
+struct HashingReader<R> {
+    inner: R,
+    hasher: Option<Sha256>,
+}
+
+impl<R: Read> HashingReader<R> {
+    fn new(inner: R, hash: bool) -> Self {
+        Self {
+            inner,
+            hasher: hash.then(Sha256::new),
+        }
+    }
+
+    /// Lowercase hex digest of the data read so far, if hashing.
+    fn digest(self) -> Option<String> {
+        self.hasher.map(hex_digest)
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if let Some(hasher) = &mut self.hasher {
+            hasher.update(&buf[..read]);
+        }
+        Ok(read)
+    }
+}
+
+/// Copy `data` to `out` like [`io::copy`], calling `tick` with the number of
+/// bytes copied so far every [`PROGRESS_STEP`] bytes. The copy stops with
+/// [`ExtractError::Cancelled`] once `tick` returns false.
+fn copy_with_progress(
+    data: &mut dyn Read,
+    out: &mut dyn Write,
+    tick: &mut dyn FnMut(u64) -> bool,
+) -> Result<u64, ExtractError> {
+    let mut copied = 0;
+    loop {
+        let step = io::copy(&mut (&mut *data).take(PROGRESS_STEP), out)?;
+        copied += step;
+        if step < PROGRESS_STEP {
+            return Ok(copied);
+        }
+        if !tick(copied) {
+            return Err(ExtractError::Cancelled);
+        }
+    }
+}
+
+/// How the file at `path` differs from what its entry records, if it does.
+fn verify_file(
+    path: &Path,
+    expected: &ExpectedFile,
+    buffer_size: usize,
+) -> Result<Option<String>, ExtractError> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Ok(Some("file is missing".to_string()))
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let (_, mismatch) = check_data(
+        &mut file,
+        expected.size,
+        expected.crc32,
+        buffer_size,
+        &mut |_| true,
+    )?;
+    Ok(mismatch)
+}
+
+/// Give a fully written file its modification time, owner, extended
+/// attributes, permissions and quarantine attribute, then move it into place.
+/// Changing the owner clears file capabilities and set-user-ID bits, so it
+/// comes before the attributes and permissions.
+fn finish_file(
+    job: &FileJob,
+    options: &ExtractOptions,
+    quarantine: Option<&[u8]>,
+) -> Result<(), ExtractError> {
+    if let Some(modified) = job.modified {
+        set_modified(&job.root, &job.write_path, modified)?;
+    }
+    restore_owner(&job.write_path, job.owner)?;
+    restore_xattrs(&job.write_path, &job.xattrs)?;
+    apply_permissions(
+        &job.root,
+        &job.write_path,
+        job.mode,
+        options.file_mode,
+        job.umask,
+    )?;
+    tag_quarantine(&job.write_path, quarantine)?;
+    if job.write_path != job.final_path {
+        job.root.rename(&job.write_path, &job.final_path)?;
+    }
+    Ok(())
+}
+
+/// Fail if `total` bytes would exceed the size limit from the options.
+pub(crate) fn check_size_limit(total: u64, options: &ExtractOptions) -> Result<(), ExtractError> {
+    match options.size_limit_bytes {
+        Some(limit) if total > limit => Err(ExtractError::SizeLimitExceeded {
+            current: total,
+            limit,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Check if a file is a multi-part archive (any format).
+fn is_multipart_archive(path: &Path) -> bool {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let filename = path
+        .file_name()
+        .and_then(|f| f.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    // Check for .partXX.rar or .partXX.zip pattern
+    if filename.contains(".part") && (filename.ends_with(".rar") || filename.ends_with(".zip")) {
+        return true;
+    }
+
+    // Check for .7z.XXX or .zip.XXX pattern
+    if (filename.contains(".7z.") || filename.contains(".zip."))
+        && extension.chars().all(|c| c.is_ascii_digit())
+    {
+        return true;
+    }
+
+    // Check for .rXX extensions (RAR)
+    if extension.starts_with('r') && extension.len() >= 2 {
+        if extension[1..].chars().all(|c| c.is_ascii_digit()) {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Check if a file is a RAR archive based on extension.
+fn is_rar_archive(path: &Path) -> bool {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let filename = path
+        .file_name()
+        .and_then(|f| f.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    // Check for .rar extension
+    if extension == "rar" {
+        return true;
+    }
+
+    // Check for multi-part RAR (.part1.rar, .r00, etc.)
+    if filename.contains(".part") && filename.ends_with(".rar") {
+        return true;
+    }
+
+    // Check for .rXX extensions
+    if extension.starts_with('r') && extension.len() >= 2 {
+        if extension[1..].chars().all(|c| c.is_ascii_digit()) {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Background thread setting the cancel flag when a job exceeds its time limit.
+struct Watchdog {
+    limit: Duration,
+    fired: Arc<AtomicBool>,
+    /// Dropped to wake the thread when the job ends first
+    done: Option<mpsc::Sender<()>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Watchdog {
+    fn start(limit: Duration, cancel_flag: Arc<AtomicBool>) -> Self {
+        let fired = Arc::new(AtomicBool::new(false));
+        let (done, finished) = mpsc::channel::<()>();
+
+        let thread = {
+            let fired = fired.clone();
+            thread::spawn(move || {
+                if let Err(mpsc::RecvTimeoutError::Timeout) = finished.recv_timeout(limit) {
+                    fired.store(true, Ordering::Relaxed);
+                    cancel_flag.store(true, Ordering::Relaxed);
+                }
+            })
+        };
+
+        Self {
+            limit,
+            fired,
+            done: Some(done),
+            thread: Some(thread),
+        }
+    }
+
+    /// Stop the watchdog, returning whether the time limit was hit.
+    fn stop(mut self) -> bool {
+        self.shutdown();
+        self.fired.load(Ordering::Relaxed)
+    }
+
+    fn shutdown(&mut self) {
+        drop(self.done.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Strip leading path components from a path.
+fn strip_path_components(path: &Path, count: u32) -> PathBuf {
+    if count == 0 {
+        return path.to_path_buf();
+    }
+
+    let components: Vec<_> = path.components().collect();
+    let skip = count as usize;
+
+    if skip >= components.len() {
+        return PathBuf::new();
+    }
+
+    components[skip..].iter().collect()
+}
+
+/// Files and folders an extraction created, to remove or report if it fails.
+#[derive(Default)]
+struct CreatedPaths {
+    files: Vec<PathBuf>,
+    dirs: Vec<PathBuf>,
+}
+
+impl CreatedPaths {
+    /// Record the folders that creating `dir` would add.
+    fn record_missing_dirs(&mut self, dir: &Path) {
+        let missing = dir.ancestors().take_while(|p| !p.exists());
+        self.dirs.extend(missing.map(Path::to_path_buf));
+    }
+
+    /// Deal with what an extraction that failed with `error` created: remove
+    /// it, or with `cleanup_on_failure` off, report the files left behind
+    /// along with the error.
+    fn settle_failure(
+        mut self,
+        error: ExtractError,
+        root: &OutputRoot,
+        output_dir: &Path,
+        options: &ExtractOptions,
+        stats: &ExtractStats,
+    ) -> ExtractError {
+        if options.cleanup_on_failure {
+            for file in &self.files {
+                let _ = root.remove_file(file);
+            }
+            // Deepest first; folders that aren't empty are left alone
+            self.dirs
+                .sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+            for dir in &self.dirs {
+                // The output directory and the folders above it were created
+                // before the handle to it was opened
+                let _ = if dir.starts_with(output_dir) && dir != output_dir {
+                    root.remove_dir(dir)
+                } else {
+                    fs::remove_dir(dir)
+                };
+            }
+            return error;
+        }
+
+        let partial_files: Vec<String> = self
+            .files
+            .iter()
+            .filter(|file| file.exists())
+            .filter_map(|file| file.strip_prefix(output_dir).ok())
+            .map(|file| file.to_string_lossy().to_string())
+            .collect();
+        if partial_files.is_empty() {
+            return error;
+        }
+        ExtractError::Partial {
+            error: Box::new(error),
+            stats: Box::new(ExtractStats {
+                partial_files,
+                ..stats.clone()
+            }),
+        }
+    }
+}
+
+/// Tracks output paths that differ only by letter case.
+///
+/// On a case-insensitive destination such entries land on the same file, so the
+/// later one silently replaces (or is renamed next to) the earlier one.
+struct CaseCollisions {
+    /// Whether collisions should be reported for this destination
+    active: bool,
+    /// Lower-cased path -> first path seen with that spelling
+    seen: HashMap<String, PathBuf>,
+    /// Lower-cased paths that have already been reported
+    reported: HashSet<String>,
+}
+
+impl CaseCollisions {
+    fn new(output_dir: &Path, options: &ExtractOptions) -> Self {
+        // Assume the worst if the destination cannot be checked
+        let active =
+            !options.detect_case_sensitivity || !is_case_sensitive(output_dir).unwrap_or(false);
+
+        Self {
+            active,
+            seen: HashMap::new(),
+            reported: HashSet::new(),
+        }
+    }
+
+    /// Record `path` (and its parent folders), warning about case collisions.
+    fn check(&mut self, path: &Path, stats: &mut ExtractStats) {
+        if !self.active {
+            return;
+        }
+
+        for ancestor in path.ancestors() {
+            if ancestor.as_os_str().is_empty() {
+                break;
+            }
+
+            let key = ancestor.to_string_lossy().to_lowercase();
+            match self.seen.get(&key) {
+                Some(first) if first != ancestor => {
+                    if self.reported.insert(key) {
+                        stats.warnings.push(format!(
+                            "Case collision: '{}' and '{}' refer to the same path on a case-insensitive destination",
+                            first.display(),
+                            ancestor.display()
+                        ));
+                    }
+                }
+                Some(_) => {}
+                None => {
+                    self.seen.insert(key, ancestor.to_path_buf());
+                }
+            }
+        }
+    }
+}
+
+/// Check whether the filesystem holding `dir` distinguishes letter case.
+///
+/// Creates a short-lived probe file and looks it up again under an upper-cased
+/// name, which only resolves on case-insensitive filesystems.
+fn is_case_sensitive(dir: &Path) -> io::Result<bool> {
+    let probe = tempfile::Builder::new()
+        .prefix(".unarchiver-case-probe-")
+        .tempfile_in(dir)?;
+
+    let name = probe
+        .path()
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default()
+        .to_uppercase();
+
+    Ok(!dir.join(name).exists())
+}
+
+/// Whether an entry should be left alone because of the existing file at `path`.
+///
+/// `modified` is the entry's modification time in seconds since the Unix epoch
+/// and `size` its uncompressed size, when known.
+fn skip_existing(
+    path: &Path,
+    mode: OverwriteMode,
+    modified: Option<u64>,
+    size: Option<u64>,
+) -> bool {
+    match mode {
+        OverwriteMode::Skip => path.exists(),
+        OverwriteMode::IfNewer => {
+            let metadata = match fs::metadata(path) {
+                Ok(metadata) => metadata,
+                Err(_) => return false,
+            };
+
+            match (modified, file_modified_secs(&metadata)) {
+                (Some(entry_time), Some(existing_time)) => entry_time <= existing_time,
+                // Without both times, only replace files whose size differs
+                _ => size.is_none_or(|size| size == metadata.len()),
+            }
+        }
+        OverwriteMode::Replace
+        | OverwriteMode::Rename
+        | OverwriteMode::Error
+        | OverwriteMode::Ask => false,
+    }
+}
+
+/// Longest symbolic link target read from an entry's data
+const MAX_LINK_TARGET: u64 = 4096;
+
+/// Target of the symbolic link entry `entry`: recorded with the entry for tar,
+/// or read from the data of an entry with a link's mode.
+fn symlink_target(entry: &Entry, data: &mut dyn EntryData) -> Result<String, ExtractError> {
+    if let Some(target) = &entry.symlink_target {
+        return Ok(target.clone());
+    }
+    let mut target = Vec::new();
+    data.reader()?
+        .take(MAX_LINK_TARGET)
+        .read_to_end(&mut target)?;
+    Ok(String::from_utf8_lossy(&target).to_string())
+}
+
+/// Whether `error` is a failure of one entry, which an extraction skipping
+/// failed entries goes on past, rather than of the whole extraction.
+fn is_entry_error(error: &ExtractError) -> bool {
+    match error {
+        ExtractError::Security(_)
+        | ExtractError::Corrupted(_)
+        | ExtractError::AlreadyExists(_)
+        | ExtractError::EntryNotFound(_) => true,
+        // Nothing more fits on a full disk
+        ExtractError::Io(e) => !matches!(
+            e.kind(),
+            io::ErrorKind::StorageFull | io::ErrorKind::QuotaExceeded
+        ),
+        _ => false,
+    }
+}
+
+/// Why entries of a kind [`is_safe_entry_type`] rejects are skipped.
+fn blocked_reason(kind: EntryType) -> &'static str {
+    match kind {
+        EntryType::Symlink => "symbolic links are not allowed",
+        EntryType::Hardlink => "hard links are not allowed",
+        _ => "devices, FIFOs and other special files are not extracted",
+    }
+}
+
+/// Copy the file or folder `from` to `to` under `root`, recording what is
+/// created in `created`. Returns the number of bytes copied.
+fn copy_tree(
+    root: &OutputRoot,
+    from: &Path,
+    to: &Path,
+    created: &mut CreatedPaths,
+) -> io::Result<u64> {
+    if !from.is_dir() {
+        created.files.push(to.to_path_buf());
+        return root.copy(from, to);
+    }
 
-            // Update stats
-            stats.bytes_written += file_size;
-            stats.files_extracted += 1;
+    root.create_dir(to)?;
+    created.dirs.push(to.to_path_buf());
+    let mut copied = 0;
+    for child in fs::read_dir(from)? {
+        let child = child?;
+        copied += copy_tree(root, &child.path(), &to.join(child.file_name()), created)?;
+    }
+    Ok(copied)
+}
 
-            // Progress callback
-            let continue_extraction = progress_cb(
-                &final_path.to_string_lossy(),
-                stats.bytes_written,
-                Some(file_size),
-            );
+/// Whether the options need each entry's listing metadata.
+fn wants_entry_meta(options: &ExtractOptions) -> bool {
+    options.entry_filter.is_some()
+        || options.pre_write_hook.is_some()
+        || options.content_policy.is_some()
+}
 
-            if !continue_extraction {
-                return Err(ExtractError::Cancelled);
-            }
-        }
+/// Whether the entry filter from the options rejects an entry.
+fn filtered_out(options: &ExtractOptions, meta: Option<&ArchiveEntry>) -> bool {
+    match (&options.entry_filter, meta) {
+        (Some(filter), Some(meta)) => !filter.matches(meta),
+        _ => false,
     }
+}
 
-    Ok(())
+/// What the pre-write hook and content policy made of an entry.
+enum Inspection {
+    /// Nothing inspected the data
+    Untouched,
+    /// The entry is to be written, starting with the data already read
+    Write(Inspected),
+    /// The entry was skipped or blocked
+    Rejected,
 }
 
-/// Extract 7Z archive using sevenz-rust2 crate.
-fn extract_7z_archive(
-    archive_path: &Path,
-    output_dir: &Path,
-    options: &ExtractOptions,
-    progress_cb: &ProgressCallback,
-    cancel_flag: Arc<AtomicBool>,
-    stats: &mut ExtractStats,
-) -> Result<(), ExtractError> {
-    // sevenz-rust2 extracts directly to output directory
-    // We need to validate paths after extraction
-    let temp_dir = tempfile::tempdir()?;
-    
-    // Extract to temp directory first
-    sevenz_rust2::decompress_file(archive_path, temp_dir.path())
-        .map_err(|e| {
-            let err_msg = e.to_string();
-            if err_msg.contains("password") || err_msg.contains("encrypted") {
-                if options.password.is_some() {
-                    ExtractError::InvalidPassword
-                } else {
-                    ExtractError::PasswordRequired
-                }
-            } else {
-                ExtractError::Corrupted(err_msg)
-            }
-        })?;
+/// Most of an entry's data the pre-write hook and content policy may read that
+/// is held in memory; the rest goes to a temporary file.
+const INSPECT_MEMORY_BYTES: usize = 8 * 1024 * 1024;
+
+/// Data read from an entry before it is written: the first
+/// `INSPECT_MEMORY_BYTES` in memory and the rest, if any, in an anonymous
+/// temporary file in `spool_dir`. Written to while the entry is inspected, then
+/// read back from the start.
+#[derive(Default)]
+struct Inspected {
+    head: Vec<u8>,
+    spill: Option<File>,
+    spool_dir: PathBuf,
+    /// Position in `head` when reading back
+    pos: usize,
+}
 
-    // Walk through extracted files and move them with validation
-    for entry in walkdir::WalkDir::new(temp_dir.path()) {
-        // Check cancellation
-        if cancel_flag.load(Ordering::Relaxed) {
-            return Err(ExtractError::Cancelled);
+impl Inspected {
+    /// Empty, spilling into `ExtractOptions::temp_dir` or next to `output_dir`.
+    fn new(output_dir: &Path, options: &ExtractOptions) -> Self {
+        Self {
+            spool_dir: options
+                .temp_dir
+                .clone()
+                .unwrap_or_else(|| parent_dir(output_dir).to_path_buf()),
+            ..Self::default()
         }
+    }
 
-        let entry = entry.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-        let temp_path = entry.path();
-        
-        // Get relative path from temp dir
-        let relative_path = temp_path.strip_prefix(temp_dir.path())
-            .map_err(|_| ExtractError::Security(crate::error::SecurityError::PathTraversal(temp_path.display().to_string())))?;
+    /// Number of bytes held.
+    fn len(&self) -> io::Result<u64> {
+        let spilled = match &self.spill {
+            Some(spill) => spill.metadata()?.len(),
+            None => 0,
+        };
+        Ok(self.head.len() as u64 + spilled)
+    }
 
-        if relative_path.as_os_str().is_empty() {
-            continue;
+    /// Go back to the start for reading.
+    fn rewind(&mut self) -> io::Result<()> {
+        self.pos = 0;
+        if let Some(spill) = &mut self.spill {
+            spill.rewind()?;
         }
+        Ok(())
+    }
+}
 
-        // Validate and strip path components
-        let validated_path = validate_entry_path(relative_path)?;
-        let final_path = strip_path_components(&validated_path, options.strip_components);
-
-        if final_path.as_os_str().is_empty() {
-            continue;
+impl Write for Inspected {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let room = INSPECT_MEMORY_BYTES.saturating_sub(self.head.len());
+        if self.spill.is_none() && buf.len() <= room {
+            self.head.extend_from_slice(buf);
+            return Ok(buf.len());
         }
-
-        let output_path = output_dir.join(&final_path);
-
-        if entry.file_type().is_dir() {
-            fs::create_dir_all(&output_path)?;
-        } else {
-            // Create parent directories
-            if let Some(parent) = output_path.parent() {
-                fs::create_dir_all(parent)?;
-            }
-
-            // Check size limits
-            let file_size = entry.metadata().map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?.len();
-            let new_total = stats.bytes_written + file_size;
-            if let Some(limit) = options.size_limit_bytes {
-                if new_total > limit {
-                    return Err(ExtractError::SizeLimitExceeded {
-                        current: new_total,
-                        limit,
-                    });
-                }
-            }
-
-            // Handle overwrite mode
-            let actual_output_path = handle_overwrite_mode(&output_path, options.overwrite)?;
-
-            if options.overwrite == OverwriteMode::Skip && actual_output_path.exists() {
-                continue;
+        if room > 0 {
+            self.head.extend_from_slice(&buf[..room]);
+            return Ok(room);
+        }
+        let spill = match &mut self.spill {
+            Some(spill) => spill,
+            None => {
+                fs::create_dir_all(&self.spool_dir)?;
+                self.spill.insert(tempfile::tempfile_in(&self.spool_dir)?)
             }
+        };
+        spill.write(buf)
+    }
 
-            // Copy file
-            fs::copy(temp_path, &actual_output_path)?;
-
-            // Update stats
-            stats.bytes_written += file_size;
-            stats.files_extracted += 1;
-
-            // Progress callback
-            let continue_extraction = progress_cb(
-                &final_path.to_string_lossy(),
-                stats.bytes_written,
-                Some(file_size),
-            );
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
 
-            if !continue_extraction {
-                return Err(ExtractError::Cancelled);
-            }
+impl Read for Inspected {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos < self.head.len() {
+            let n = (&self.head[self.pos..]).read(buf)?;
+            self.pos += n;
+            return Ok(n);
+        }
+        match &mut self.spill {
+            Some(spill) => spill.read(buf),
+            None => Ok(0),
         }
     }
-
-    Ok(())
 }
 
-/// Extract RAR archive using unrar library (supports multi-part archives).
-fn extract_rar_archive(
-    archive_path: &Path,
+/// Let the pre-write hook and the content policy (if any) read the data of the
+/// entry destined for `path` in `output_dir`. Whatever they read is kept (see
+/// [`Inspected`]), to be written out ahead of the rest of the data, so nothing
+/// touches the output before they decide.
+fn inspect_entry(
+    data: &mut dyn EntryData,
+    path: &Path,
     output_dir: &Path,
+    meta: Option<&ArchiveEntry>,
     options: &ExtractOptions,
-    progress_cb: &ProgressCallback,
-    cancel_flag: Arc<AtomicBool>,
     stats: &mut ExtractStats,
-) -> Result<(), ExtractError> {
-    use unrar::Archive;
-
-    // Create archive instance (will automatically handle multi-part)
-    let mut archive = if let Some(password) = &options.password {
-        Archive::with_password(archive_path, password.as_bytes())
-    } else {
-        Archive::new(archive_path)
-    };
+) -> Result<Inspection, ExtractError> {
+    if options.pre_write_hook.is_none() && options.content_policy.is_none() {
+        return Ok(Inspection::Untouched);
+    }
 
-    // Use as_first_part to ensure we start from the first part
-    archive = archive.as_first_part();
+    let mut reader = data.reader()?;
+    let mut inspected = Inspected::new(output_dir, options);
 
-    // Open for processing
-    let open_archive = archive.open_for_processing().map_err(|e| {
-        let err_msg = e.to_string().to_lowercase();
-        if err_msg.contains("password") || err_msg.contains("encrypted") {
-            if options.password.is_none() {
-                ExtractError::PasswordRequired
-            } else {
-                ExtractError::InvalidPassword
+    if let (Some(hook), Some(meta)) = (&options.pre_write_hook, meta) {
+        let mut tee = TeeReader {
+            inner: &mut reader,
+            copy: &mut inspected,
+        };
+        match hook.inspect(meta, &mut tee) {
+            WriteDecision::Write => {}
+            WriteDecision::Skip => {
+                stats.files_skipped += 1;
+                stats.warnings.push(format!(
+                    "Skipped {} (rejected by pre-write hook)",
+                    meta.path
+                ));
+                return Ok(Inspection::Rejected);
             }
-        } else if err_msg.contains("corrupt") || err_msg.contains("bad") {
-            ExtractError::Corrupted(e.to_string())
-        } else {
-            ExtractError::Io(std::io::Error::new(std::io::ErrorKind::Other, e))
-        }
-    })?;
-
-    let mut current = Some(open_archive);
-
-    while let Some(arch) = current {
-        // Check cancellation
-        if cancel_flag.load(Ordering::Relaxed) {
-            return Err(ExtractError::Cancelled);
+            WriteDecision::Abort => return Err(ExtractError::Rejected(meta.path.clone())),
         }
+    }
 
-        match arch.read_header() {
-            Ok(Some(header)) => {
-                let entry = header.entry();
-                let entry_filename = entry.filename.to_string_lossy().to_string();
-                let entry_path = Path::new(&entry_filename);
-                let is_directory = entry.is_directory();
-                let unpacked_size = entry.unpacked_size;
-
-                // Validate the entry path
-                let validated_path = match validate_entry_path(entry_path) {
-                    Ok(p) => p,
-                    Err(_) => {
-                        // Skip invalid paths
-                        current = Some(header.skip().map_err(|e| {
-                            ExtractError::Io(std::io::Error::new(std::io::ErrorKind::Other, e))
-                        })?);
-                        continue;
-                    }
-                };
-
-                // Apply strip_components
-                let final_path = strip_path_components(&validated_path, options.strip_components);
-
-                // Skip if path becomes empty after stripping
-                if final_path.as_os_str().is_empty() {
-                    current = Some(header.skip().map_err(|e| {
-                        ExtractError::Io(std::io::Error::new(std::io::ErrorKind::Other, e))
-                    })?);
-                    continue;
+    if let (Some(policy), Some(meta)) = (&options.content_policy, meta) {
+        let missing = (SNIFF_LEN as u64).saturating_sub(inspected.len()?);
+        io::copy(&mut (&mut reader).take(missing), &mut inspected)?;
+
+        let kind = sniff_content(&meta.path, &inspected.head);
+        if policy.denies(kind) {
+            let quarantined_to = match &policy.quarantine_dir {
+                Some(dir) => {
+                    inspected.rewind()?;
+                    let mut data = inspected.chain(reader);
+                    Some(quarantine_entry(&mut data, path, dir)?)
                 }
+                None => None,
+            };
+            stats.blocked.push(BlockedEntry {
+                path: meta.path.clone(),
+                kind,
+                quarantined_to: quarantined_to.map(|p| p.to_string_lossy().into_owned()),
+            });
+            return Ok(Inspection::Rejected);
+        }
+    }
 
-                let output_path = output_dir.join(&final_path);
+    inspected.rewind()?;
+    Ok(Inspection::Write(inspected))
+}
 
-                // Handle overwrite mode
-                let actual_output_path = handle_overwrite_mode(&output_path, options.overwrite)?;
+/// Hidden temporary name next to `path` that a file is written under before
+/// being renamed into place.
+fn partial_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or(path.as_os_str());
+    let mut partial = std::ffi::OsString::from(".");
+    partial.push(name);
+    partial.push(".partial");
+    path.with_file_name(partial)
+}
 
-                // Skip if file exists and mode is Skip
-                if options.overwrite == OverwriteMode::Skip && actual_output_path.exists() {
-                    current = Some(header.skip().map_err(|e| {
-                        ExtractError::Io(std::io::Error::new(std::io::ErrorKind::Other, e))
-                    })?);
-                    continue;
-                }
+/// Whether `path` is a temporary file left behind by an extraction that was
+/// interrupted while writing it (see [`ExtractOptions::atomic_writes`]).
+pub fn is_partial_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| {
+            name.len() > ".partial".len() + 1 && name.starts_with('.') && name.ends_with(".partial")
+        })
+}
 
-                // Create parent directories
-                if let Some(parent) = actual_output_path.parent() {
-                    fs::create_dir_all(parent)?;
-                }
+/// Write a file blocked by the content policy into the quarantine folder under
+/// its file name, renaming it on conflicts. The copy is never executable.
+fn quarantine_entry(data: &mut dyn Read, path: &Path, dir: &Path) -> Result<PathBuf, ExtractError> {
+    fs::create_dir_all(dir)?;
+    let name = path.file_name().unwrap_or(path.as_os_str());
+    let target = handle_overwrite_mode(&dir.join(name), OverwriteMode::Rename, None)?;
+
+    // Private from the start, and never through a file or link already there
+    let mut open = fs::OpenOptions::new();
+    open.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
 
-                // Extract the entry
-                if is_directory {
-                    fs::create_dir_all(&actual_output_path)?;
-                    current = Some(header.skip().map_err(|e| {
-                        ExtractError::Io(std::io::Error::new(std::io::ErrorKind::Other, e))
-                    })?);
-                } else {
-                    // Extract file
-                    current = Some(header.extract_to(&actual_output_path).map_err(|e| {
-                        ExtractError::Io(std::io::Error::new(std::io::ErrorKind::Other, e))
-                    })?);
+        open.mode(0o600);
+    }
+    let mut outfile = open.open(&target)?;
+    io::copy(data, &mut outfile)?;
+    Ok(target)
+}
 
-                    // Update stats
-                    stats.files_extracted += 1;
-                    stats.bytes_written += unpacked_size;
+/// Reader that keeps a copy of everything read through it.
+struct TeeReader<'a, R> {
+    inner: &'a mut R,
+    copy: &'a mut Inspected,
+}
 
-                    // Call progress callback
-                    let continue_extraction =
-                        progress_cb(&final_path.to_string_lossy(), stats.bytes_written, None);
+impl<R: Read> Read for TeeReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.copy.write_all(&buf[..n])?;
+        Ok(n)
+    }
+}
 
-                    if !continue_extraction {
-                        return Err(ExtractError::Cancelled);
-                    }
-                }
-            }
-            Ok(None) => {
-                // End of archive
-                current = None;
-            }
-            Err(e) => {
-                return Err(ExtractError::Io(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    e,
-                )));
-            }
+/// Validate an entry path, first replacing control characters in its name when
+/// the options allow it (setting `renamed` when it does), and bring it to the
+/// Unicode normalization form the options ask for.
+fn checked_entry_path(
+    path: &Path,
+    options: &ExtractOptions,
+    renamed: &mut bool,
+) -> Result<PathBuf, crate::error::SecurityError> {
+    if options.sanitize_control_chars {
+        if let Some(sanitized) = path.to_str().and_then(sanitize_control_chars) {
+            *renamed = true;
+            return validate_entry_path_with_limits(Path::new(&sanitized), &options.path_limits)
+                .map(|path| normalized(path, options));
         }
     }
+    validate_entry_path_with_limits(path, &options.path_limits)
+        .map(|path| normalized(path, options))
+}
 
-    Ok(())
+/// `path` in the Unicode normalization form the options ask for
+fn normalized(path: PathBuf, options: &ExtractOptions) -> PathBuf {
+    normalize_unicode(&path, options.normalize_names).unwrap_or(path)
 }
 
-/// Check if a file is a multi-part archive (any format).
-fn is_multipart_archive(path: &Path) -> bool {
-    let extension = path
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("")
-        .to_lowercase();
+/// Rewrite an entry's output path with the path mapping hook (if any). The new
+/// path goes through the same validation as the archive's own paths; an empty
+/// path means the entry is skipped.
+fn map_entry_path(
+    path: PathBuf,
+    options: &ExtractOptions,
+) -> Result<PathBuf, crate::error::SecurityError> {
+    let mapped = match &options.map_path {
+        Some(mapper) => mapper.map(&path),
+        None => return Ok(path),
+    };
 
-    let filename = path
-        .file_name()
-        .and_then(|f| f.to_str())
-        .unwrap_or("")
-        .to_lowercase();
+    if mapped.as_os_str().is_empty() {
+        return Ok(mapped);
+    }
+    validate_entry_path_with_limits(&mapped, &options.path_limits)
+}
 
-    // Check for .partXX.rar or .partXX.zip pattern
-    if filename.contains(".part") && (filename.ends_with(".rar") || filename.ends_with(".zip")) {
-        return true;
+/// Create `dir` and any missing parents under `root`, giving the directories
+/// created here the fixed directory mode from the options (if any) and
+/// recording them in `created`.
+fn create_dirs(
+    root: &OutputRoot,
+    dir: &Path,
+    options: &ExtractOptions,
+    quarantine: Option<&[u8]>,
+    created: &mut CreatedPaths,
+) -> Result<(), ExtractError> {
+    let missing: Vec<&Path> = dir.ancestors().take_while(|p| !p.exists()).collect();
+    root.create_dir_all(dir)?;
+    created
+        .dirs
+        .extend(missing.iter().map(|dir| dir.to_path_buf()));
+
+    for created in missing {
+        if let Some(mode) = options.dir_mode {
+            set_mode(root, created, mode)?;
+        }
+        tag_quarantine(created, quarantine)?;
     }
+    Ok(())
+}
 
-    // Check for .7z.XXX or .zip.XXX pattern
-    if (filename.contains(".7z.") || filename.contains(".zip."))
-        && extension.chars().all(|c| c.is_ascii_digit())
-    {
-        return true;
+/// The extended attributes of `entry` to restore, per the options.
+fn restored_xattrs(entry: &Entry, options: &ExtractOptions) -> Vec<(String, Vec<u8>)> {
+    if !options.preserve_xattrs {
+        return Vec::new();
     }
+    entry
+        .xattrs
+        .iter()
+        .filter(|(name, _)| options.allow_unsafe_xattrs || is_safe_xattr(name))
+        .cloned()
+        .collect()
+}
 
-    // Check for .rXX extensions (RAR)
-    if extension.starts_with('r') && extension.len() >= 2 {
-        if extension[1..].chars().all(|c| c.is_ascii_digit()) {
-            return true;
-        }
+/// Give an extracted item the user and group IDs recorded for it (if any),
+/// without following symlinks.
+#[cfg(unix)]
+fn restore_owner(path: &Path, owner: Option<(u32, u32)>) -> Result<(), ExtractError> {
+    if let Some((uid, gid)) = owner {
+        std::os::unix::fs::lchown(path, Some(uid), Some(gid))?;
     }
+    Ok(())
+}
 
-    false
+#[cfg(not(unix))]
+fn restore_owner(_path: &Path, _owner: Option<(u32, u32)>) -> Result<(), ExtractError> {
+    Ok(())
 }
 
-/// Check if a file is a RAR archive based on extension.
-fn is_rar_archive(path: &Path) -> bool {
-    let extension = path
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("")
-        .to_lowercase();
+/// Set the extended attributes of an extracted item.
+fn restore_xattrs(path: &Path, xattrs: &[(String, Vec<u8>)]) -> Result<(), ExtractError> {
+    for (name, value) in xattrs {
+        crate::xattrs::set_xattr(path, name, value)?;
+    }
+    Ok(())
+}
 
-    let filename = path
-        .file_name()
-        .and_then(|f| f.to_str())
-        .unwrap_or("")
-        .to_lowercase();
+/// Whether extracted items get the modification times recorded in the archive.
+fn preserve_times(options: &ExtractOptions) -> bool {
+    options.timestamp_policy == TimestampPolicy::Preserve
+}
 
-    // Check for .rar extension
-    if extension == "rar" {
-        return true;
-    }
+/// Set the modification time of an extracted file or directory, given in
+/// seconds since the Unix epoch.
+fn set_modified(root: &OutputRoot, path: &Path, modified: u64) -> Result<(), ExtractError> {
+    let time = UNIX_EPOCH + Duration::from_secs(modified);
+    root.open_for_times(path)?.set_modified(time)?;
+    Ok(())
+}
 
-    // Check for multi-part RAR (.part1.rar, .r00, etc.)
-    if filename.contains(".part") && filename.ends_with(".rar") {
-        return true;
+/// Copy the archive's quarantine attribute (if any) to an extracted item.
+fn tag_quarantine(path: &Path, quarantine: Option<&[u8]>) -> Result<(), ExtractError> {
+    if let Some(value) = quarantine {
+        crate::quarantine::set_quarantine(path, value)?;
     }
+    Ok(())
+}
 
-    // Check for .rXX extensions
-    if extension.starts_with('r') && extension.len() >= 2 {
-        if extension[1..].chars().all(|c| c.is_ascii_digit()) {
-            return true;
-        }
+/// Set the permissions of an extracted item. A `fixed` mode from the options
+/// wins; otherwise the mode recorded for the entry (if preserved) is applied,
+/// without the bits in `umask`.
+#[cfg(unix)]
+fn apply_permissions(
+    root: &OutputRoot,
+    path: &Path,
+    mode: Option<u32>,
+    fixed: Option<u32>,
+    umask: u32,
+) -> Result<(), ExtractError> {
+    let mode = match (fixed, mode) {
+        (Some(fixed), _) => fixed,
+        (None, Some(mode)) => mode & !umask,
+        _ => return Ok(()),
+    };
+    set_mode(root, path, mode)
+}
+
+#[cfg(not(unix))]
+fn apply_permissions(
+    _root: &OutputRoot,
+    _path: &Path,
+    _mode: Option<u32>,
+    _fixed: Option<u32>,
+    _umask: u32,
+) -> Result<(), ExtractError> {
+    Ok(())
+}
+
+/// Bits to clear from the modes recorded for entries: the process umask
+/// unless `apply_umask` is off. Read once before anything is written and
+/// handed to the writer threads, which never touch the umask themselves.
+fn permission_mask(options: &ExtractOptions) -> u32 {
+    if options.apply_umask {
+        process_umask()
+    } else {
+        0
     }
+}
+
+#[cfg(unix)]
+fn set_mode(root: &OutputRoot, path: &Path, mode: u32) -> Result<(), ExtractError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    root.set_permissions(path, fs::Permissions::from_mode(mode & 0o7777))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_mode(_root: &OutputRoot, _path: &Path, _mode: u32) -> Result<(), ExtractError> {
+    Ok(())
+}
+
+/// Whether the process runs as root, which can give files to other users.
+#[cfg(unix)]
+fn running_as_root() -> bool {
+    // SAFETY: geteuid has no preconditions and cannot fail
+    unsafe { libc::geteuid() == 0 }
+}
 
+#[cfg(not(unix))]
+fn running_as_root() -> bool {
     false
 }
 
-/// Strip leading path components from a path.
-fn strip_path_components(path: &Path, count: u32) -> PathBuf {
-    if count == 0 {
-        return path.to_path_buf();
-    }
+/// The process umask, read from `/proc/self/status` where the kernel reports
+/// it.
+#[cfg(target_os = "linux")]
+fn process_umask() -> u32 {
+    let status = fs::read_to_string("/proc/self/status").unwrap_or_default();
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Umask:"))
+        .and_then(|mask| u32::from_str_radix(mask.trim(), 8).ok())
+        .unwrap_or_else(swap_umask)
+}
 
-    let components: Vec<_> = path.components().collect();
-    let skip = count as usize;
+#[cfg(all(unix, not(target_os = "linux")))]
+fn process_umask() -> u32 {
+    swap_umask()
+}
 
-    if skip >= components.len() {
-        return PathBuf::new();
-    }
+#[cfg(not(unix))]
+fn process_umask() -> u32 {
+    0
+}
 
-    components[skip..].iter().collect()
+/// The process umask, read by setting it and immediately restoring it, as
+/// there is no call that only reads it. Files created by other threads in
+/// between get the wrong mode, so this is only called before an extraction
+/// starts writing.
+#[cfg(unix)]
+fn swap_umask() -> u32 {
+    // SAFETY: umask only swaps the process file mode creation mask
+    let mask = unsafe { libc::umask(0) };
+    unsafe { libc::umask(mask) };
+    mask as u32
+}
+
+/// Modification time of a file in seconds since the Unix epoch.
+pub(crate) fn file_modified_secs(metadata: &fs::Metadata) -> Option<u64> {
+    let modified = metadata.modified().ok()?;
+    modified
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs())
 }
 
 /// Handle file overwrite based on the configured mode.
-fn handle_overwrite_mode(path: &Path, mode: OverwriteMode) -> Result<PathBuf, ExtractError> {
+///
+/// Renamed files are named by `rename_template` (see
+/// `ExtractOptions::rename_template`).
+fn handle_overwrite_mode(
+    path: &Path,
+    mode: OverwriteMode,
+    rename_template: Option<&str>,
+) -> Result<PathBuf, ExtractError> {
     match mode {
-        OverwriteMode::Replace => {
-            // Always use the original path, will overwrite
+        OverwriteMode::Replace | OverwriteMode::IfNewer | OverwriteMode::Ask => {
+            // Always use the original path, will overwrite (IfNewer decides
+            // whether to skip separately, and Ask only gets here without a
+            // file in the way)
             Ok(path.to_path_buf())
         }
         OverwriteMode::Skip => {
@@ -782,6 +2537,13 @@ fn handle_overwrite_mode(path: &Path, mode: OverwriteMode) -> Result<PathBuf, Ex
                 Ok(path.to_path_buf())
             }
         }
+        OverwriteMode::Error => {
+            // Never clobber or skip: any existing non-directory is a hard failure
+            if path.exists() && !path.is_dir() {
+                return Err(ExtractError::AlreadyExists(path.to_path_buf()));
+            }
+            Ok(path.to_path_buf())
+        }
         OverwriteMode::Rename => {
             // If file exists, find a unique name
             if !path.exists() {
@@ -791,14 +2553,29 @@ fn handle_overwrite_mode(path: &Path, mode: OverwriteMode) -> Result<PathBuf, Ex
             let parent = path.parent().unwrap_or(Path::new(""));
             let file_stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
             let extension = path.extension().and_then(|s| s.to_str());
-
-            // Try appending (1), (2), etc.
-            for i in 1..1000 {
-                let new_name = if let Some(ext) = extension {
-                    format!("{} ({}).{}", file_stem, i, ext)
-                } else {
-                    format!("{} ({})", file_stem, i)
-                };
+            let template = rename_template.unwrap_or(DEFAULT_RENAME_TEMPLATE);
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
+
+            // Try (1), (2), etc., or just once if the template doesn't count
+            let attempts = if template.contains("{n}") {
+                MAX_RENAME_ATTEMPTS
+            } else {
+                1
+            };
+            for n in 1..=attempts {
+                let new_name = renamed_file_name(template, file_stem, extension, n, timestamp);
+                if new_name.is_empty()
+                    || new_name == "."
+                    || new_name == ".."
+                    || new_name.contains(['/', '\\'])
+                {
+                    return Err(ExtractError::Io(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Rename template {:?} doesn't make a file name", template),
+                    )));
+                }
 
                 let new_path = parent.join(new_name);
                 if !new_path.exists() {
@@ -806,7 +2583,7 @@ fn handle_overwrite_mode(path: &Path, mode: OverwriteMode) -> Result<PathBuf, Ex
                 }
             }
 
-            // If we couldn't find a unique name after 1000 tries, error out
+            // If we couldn't find a unique name, error out
             Err(ExtractError::Io(io::Error::new(
                 io::ErrorKind::AlreadyExists,
                 "Could not find unique filename",
@@ -815,6 +2592,49 @@ fn handle_overwrite_mode(path: &Path, mode: OverwriteMode) -> Result<PathBuf, Ex
     }
 }
 
+/// Name files are renamed to without `ExtractOptions::rename_template`.
+const DEFAULT_RENAME_TEMPLATE: &str = "{stem} ({n}).{ext}";
+
+/// Most names tried for a file renamed next to an existing one
+const MAX_RENAME_ATTEMPTS: u32 = 10_000;
+
+/// The `n`th name `template` gives a renamed copy of a file named `stem` with
+/// extension `ext`. Without an extension, the `.` before `{ext}` is dropped.
+fn renamed_file_name(
+    template: &str,
+    stem: &str,
+    ext: Option<&str>,
+    n: u32,
+    timestamp: u64,
+) -> String {
+    let placeholders = [
+        ("{stem}", stem.to_string()),
+        ("{n}", n.to_string()),
+        ("{ext}", ext.unwrap_or_default().to_string()),
+        ("{timestamp}", timestamp.to_string()),
+    ];
+    let mut name = String::new();
+    let mut rest = template;
+    'expand: while let Some(c) = rest.chars().next() {
+        if ext.is_none() {
+            if let Some(after) = rest.strip_prefix(".{ext}") {
+                rest = after;
+                continue;
+            }
+        }
+        for (placeholder, value) in &placeholders {
+            if let Some(after) = rest.strip_prefix(placeholder) {
+                name.push_str(value);
+                rest = after;
+                continue 'expand;
+            }
+        }
+        name.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    name
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -839,10 +2659,59 @@ mod tests {
         assert_eq!(strip_path_components(path, 10), PathBuf::new());
     }
 
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_process_umask_matches_umask() {
+        assert_eq!(process_umask(), swap_umask());
+    }
+
+    #[test]
+    fn test_inspected_spills_past_memory_cap() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let options = ExtractOptions {
+            temp_dir: Some(temp_dir.path().join("spool")),
+            ..ExtractOptions::default()
+        };
+        let data: Vec<u8> = (0..INSPECT_MEMORY_BYTES + 100_000)
+            .map(|i| (i % 251) as u8)
+            .collect();
+
+        let mut inspected = Inspected::new(&temp_dir.path().join("output"), &options);
+        for chunk in data.chunks(300_000) {
+            inspected.write_all(chunk).unwrap();
+        }
+        assert_eq!(inspected.head.len(), INSPECT_MEMORY_BYTES);
+        assert!(inspected.spill.is_some());
+        assert_eq!(inspected.len().unwrap(), data.len() as u64);
+
+        let mut read_back = Vec::new();
+        inspected.rewind().unwrap();
+        inspected.read_to_end(&mut read_back).unwrap();
+        assert!(read_back == data);
+
+        // Small reads stay in memory
+        let mut inspected = Inspected::new(&temp_dir.path().join("output"), &options);
+        inspected.write_all(b"small").unwrap();
+        assert!(inspected.spill.is_none());
+    }
+
+    #[test]
+    fn test_is_partial_file() {
+        let partial = partial_path(Path::new("out/docs/report.pdf"));
+        assert_eq!(partial, Path::new("out/docs/.report.pdf.partial"));
+        assert!(is_partial_file(&partial));
+
+        assert!(!is_partial_file(Path::new("out/report.pdf")));
+        assert!(!is_partial_file(Path::new("out/notes.partial")));
+        assert!(!is_partial_file(Path::new("out/.partial")));
+    }
+
     #[test]
     fn test_handle_overwrite_mode_replace() {
         let path = Path::new("/tmp/test_file.txt");
-        let result = handle_overwrite_mode(path, OverwriteMode::Replace);
+        let result = handle_overwrite_mode(path, OverwriteMode::Replace, None);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), path);
     }
@@ -859,7 +2728,7 @@ mod tests {
         fs::write(&file_path, "content").unwrap();
 
         // First rename should give us "test (1).txt"
-        let result = handle_overwrite_mode(&file_path, OverwriteMode::Rename);
+        let result = handle_overwrite_mode(&file_path, OverwriteMode::Rename, None);
         assert!(result.is_ok());
         let renamed = result.unwrap();
         assert_eq!(renamed, temp_dir.path().join("test (1).txt"));
@@ -868,8 +2737,64 @@ mod tests {
         fs::write(&renamed, "content").unwrap();
 
         // Second rename should give us "test (2).txt"
-        let result = handle_overwrite_mode(&file_path, OverwriteMode::Rename);
+        let result = handle_overwrite_mode(&file_path, OverwriteMode::Rename, None);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), temp_dir.path().join("test (2).txt"));
     }
+
+    #[test]
+    fn test_handle_overwrite_mode_rename_template() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+        fs::write(&file_path, "content").unwrap();
+        fs::write(temp_dir.path().join("test-1.txt"), "content").unwrap();
+
+        let template = Some("{stem}-{n}.{ext}");
+        let result = handle_overwrite_mode(&file_path, OverwriteMode::Rename, template);
+        assert_eq!(result.unwrap(), temp_dir.path().join("test-2.txt"));
+
+        // The dot goes with a missing extension
+        let bare_path = temp_dir.path().join("README");
+        fs::write(&bare_path, "content").unwrap();
+        let result = handle_overwrite_mode(&bare_path, OverwriteMode::Rename, template);
+        assert_eq!(result.unwrap(), temp_dir.path().join("README-1"));
+
+        // Placeholders in the file's own name are left alone
+        let name = renamed_file_name("{stem}.{timestamp}.{ext}", "{n}", Some("txt"), 3, 1_700_000);
+        assert_eq!(name, "{n}.1700000.txt");
+
+        // Templates can't move files out of their folder
+        let result = handle_overwrite_mode(&file_path, OverwriteMode::Rename, Some("../{stem}"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_case_collisions() {
+        let mut collisions = CaseCollisions {
+            active: true,
+            seen: HashMap::new(),
+            reported: HashSet::new(),
+        };
+        let mut stats = ExtractStats::default();
+
+        collisions.check(Path::new("Docs/README.md"), &mut stats);
+        collisions.check(Path::new("Docs/guide.md"), &mut stats);
+        assert!(stats.warnings.is_empty());
+
+        // Same folder spelled differently is reported once
+        collisions.check(Path::new("docs/a.md"), &mut stats);
+        collisions.check(Path::new("docs/b.md"), &mut stats);
+        assert_eq!(stats.warnings.len(), 1);
+
+        collisions.check(Path::new("Docs/readme.md"), &mut stats);
+        assert_eq!(stats.warnings.len(), 2);
+
+        // Inactive tracker (case-sensitive destination) never warns
+        collisions.active = false;
+        collisions.check(Path::new("DOCS/GUIDE.MD"), &mut stats);
+        assert_eq!(stats.warnings.len(), 2);
+    }
 }
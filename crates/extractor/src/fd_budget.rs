@@ -0,0 +1,210 @@
+//! Budgeting of open file handles across concurrent extractions.
+//!
+//! Every running extraction holds a few file descriptors at once (the archive,
+//! the file being written, the next RAR volume or a 7z temporary file). On
+//! systems with a low `ulimit -n` (256 by default on macOS) enough parallel jobs
+//! make `open` fail halfway through an archive. An [`FdBudget`] shared by the
+//! jobs makes extra work wait for handles instead.
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+/// File descriptors reserved for one running extraction.
+pub const FDS_PER_EXTRACTION: usize = 4;
+
+/// File descriptors reserved for one running extraction with
+/// `ExtractOptions::threads` set to `threads`. Each worker of a parallel
+/// extraction has its own handle on the archive and a file of its own open.
+pub fn fds_per_extraction(threads: usize) -> usize {
+    if threads > 1 {
+        FDS_PER_EXTRACTION.saturating_add(threads.saturating_mul(2))
+    } else {
+        FDS_PER_EXTRACTION
+    }
+}
+
+/// Descriptors left for the rest of the process (UI, logging, sockets, ...) when
+/// sizing a budget from the system limit.
+const RESERVED_FDS: usize = 64;
+
+/// Budget used when the system reports no limit.
+const UNLIMITED_BUDGET: usize = 1024;
+
+/// Soft limit on open file descriptors for this process (`ulimit -n`).
+///
+/// Returns `None` when the limit is infinite or the platform has none.
+#[cfg(unix)]
+pub fn open_file_limit() -> Option<u64> {
+    let mut limit = std::mem::MaybeUninit::<libc::rlimit>::uninit();
+
+    // SAFETY: limit is only read after getrlimit succeeds
+    let limit = unsafe {
+        if libc::getrlimit(libc::RLIMIT_NOFILE, limit.as_mut_ptr()) != 0 {
+            return None;
+        }
+        limit.assume_init()
+    };
+
+    if limit.rlim_cur == libc::RLIM_INFINITY {
+        return None;
+    }
+    // The field type differs between platforms
+    #[allow(clippy::unnecessary_cast)]
+    let soft = limit.rlim_cur as u64;
+    Some(soft)
+}
+
+#[cfg(not(unix))]
+pub fn open_file_limit() -> Option<u64> {
+    None
+}
+
+struct BudgetState {
+    capacity: usize,
+    in_use: usize,
+}
+
+/// Shared pool of file handles that concurrent extractions draw from.
+///
+/// [`FdBudget::acquire`] blocks until enough handles are free, so jobs started
+/// beyond the limit queue up rather than fail.
+pub struct FdBudget {
+    state: Mutex<BudgetState>,
+    freed: Condvar,
+}
+
+impl FdBudget {
+    /// Create a budget of `capacity` file handles (at least one extraction's worth).
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            state: Mutex::new(BudgetState {
+                capacity: capacity.max(FDS_PER_EXTRACTION),
+                in_use: 0,
+            }),
+            freed: Condvar::new(),
+        }
+    }
+
+    /// Create a budget from the process's open file limit, leaving room for the
+    /// descriptors the rest of the process needs.
+    pub fn from_system_limit() -> Self {
+        let capacity = match open_file_limit() {
+            Some(limit) => usize::try_from(limit)
+                .unwrap_or(usize::MAX)
+                .saturating_sub(RESERVED_FDS),
+            None => UNLIMITED_BUDGET,
+        };
+        Self::new(capacity)
+    }
+
+    /// Total number of handles in the budget.
+    pub fn capacity(&self) -> usize {
+        self.lock().capacity
+    }
+
+    /// Number of handles currently held by permits.
+    pub fn in_use(&self) -> usize {
+        self.lock().in_use
+    }
+
+    /// Change the size of the budget. Permits already handed out stay valid;
+    /// new ones wait until usage drops below the new capacity.
+    pub fn set_capacity(&self, capacity: usize) {
+        self.lock().capacity = capacity.max(FDS_PER_EXTRACTION);
+        self.freed.notify_all();
+    }
+
+    /// Take `count` handles from the budget, blocking until they are available.
+    ///
+    /// Requests larger than the whole budget are clamped to it. The handles are
+    /// returned when the permit is dropped.
+    pub fn acquire(self: &Arc<Self>, count: usize) -> FdPermit {
+        let mut state = self.lock();
+        let count = count.min(state.capacity);
+        while state.in_use + count > state.capacity {
+            state = self.freed.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+        state.in_use += count;
+
+        FdPermit {
+            budget: Arc::clone(self),
+            count,
+        }
+    }
+
+    /// Take `count` handles if they are available right now.
+    pub fn try_acquire(self: &Arc<Self>, count: usize) -> Option<FdPermit> {
+        let mut state = self.lock();
+        let count = count.min(state.capacity);
+        if state.in_use + count > state.capacity {
+            return None;
+        }
+        state.in_use += count;
+
+        Some(FdPermit {
+            budget: Arc::clone(self),
+            count,
+        })
+    }
+
+    fn release(&self, count: usize) {
+        self.lock().in_use -= count;
+        self.freed.notify_all();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BudgetState> {
+        // A panic while holding the lock leaves the counters intact
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// File handles taken from an [`FdBudget`], returned on drop.
+pub struct FdPermit {
+    budget: Arc<FdBudget>,
+    count: usize,
+}
+
+impl Drop for FdPermit {
+    fn drop(&mut self) {
+        self.budget.release(self.count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fd_budget_queues_beyond_capacity() {
+        let budget = Arc::new(FdBudget::new(2 * FDS_PER_EXTRACTION));
+
+        let first = budget.acquire(FDS_PER_EXTRACTION);
+        let second = budget.try_acquire(FDS_PER_EXTRACTION).unwrap();
+        assert_eq!(budget.in_use(), 2 * FDS_PER_EXTRACTION);
+        assert!(budget.try_acquire(FDS_PER_EXTRACTION).is_none());
+
+        // A blocked acquire proceeds once a permit is dropped
+        let waiter = {
+            let budget = Arc::clone(&budget);
+            std::thread::spawn(move || {
+                let _permit = budget.acquire(FDS_PER_EXTRACTION);
+            })
+        };
+        drop(first);
+        waiter.join().unwrap();
+        drop(second);
+        assert_eq!(budget.in_use(), 0);
+
+        // Oversized requests are clamped instead of waiting forever
+        let all = budget.acquire(usize::MAX);
+        assert_eq!(budget.in_use(), budget.capacity());
+        drop(all);
+    }
+
+    #[test]
+    fn test_fds_per_extraction_grows_with_threads() {
+        assert_eq!(fds_per_extraction(0), FDS_PER_EXTRACTION);
+        assert_eq!(fds_per_extraction(1), FDS_PER_EXTRACTION);
+        assert_eq!(fds_per_extraction(8), FDS_PER_EXTRACTION + 16);
+        assert_eq!(fds_per_extraction(usize::MAX), usize::MAX);
+    }
+}
@@ -0,0 +1,400 @@
+//! Extracting archives straight from a URL.
+//!
+//! [`HttpReader`] exposes a remote file as `Read + Seek` on top of HTTP range
+//! requests. Readers that jump around fetch only what they touch (ZIP reads its
+//! central directory at the end of the file before any entry), while sequential
+//! formats stream through a single response.
+
+use crate::error::ExtractError;
+use crate::extract;
+use crate::types::{ExtractOptions, ExtractStats};
+use crate::ProgressCallback;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Forward seeks up to this far read through the open response instead of
+/// starting a new request.
+const MAX_SKIP: u64 = 256 * 1024;
+
+/// Size of the chunks a download is spooled in; cancellation and the size
+/// limit are checked after each.
+const SPOOL_CHUNK: usize = 64 * 1024;
+
+/// A remote file read through HTTP range requests.
+pub struct HttpReader {
+    agent: ureq::Agent,
+    url: String,
+    len: u64,
+    pos: u64,
+    /// Open response body, if any, and the offset it reads from next
+    body: Option<Box<dyn Read + Send + Sync>>,
+    body_pos: u64,
+}
+
+impl HttpReader {
+    /// Open `url` for random access.
+    ///
+    /// Returns `Ok(None)` when the server doesn't report the file's length or
+    /// doesn't accept byte ranges, in which case it can only be read front to back.
+    pub fn open(url: &str) -> io::Result<Option<Self>> {
+        Self::with_agent(ureq::agent(), url)
+    }
+
+    fn with_agent(agent: ureq::Agent, url: &str) -> io::Result<Option<Self>> {
+        let head = send(agent.head(url))?;
+        let accepts_ranges = head
+            .header("Accept-Ranges")
+            .is_some_and(|units| units.eq_ignore_ascii_case("bytes"));
+        let len = head
+            .header("Content-Length")
+            .and_then(|len| len.trim().parse::<u64>().ok());
+
+        Ok(len.filter(|_| accepts_ranges).map(|len| Self {
+            agent,
+            url: url.to_string(),
+            len,
+            pos: 0,
+            body: None,
+            body_pos: 0,
+        }))
+    }
+
+    /// Size of the remote file in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the remote file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Make the open response read from the current position, either by skipping
+    /// ahead on it or by starting a new range request.
+    fn reposition(&mut self) -> io::Result<()> {
+        if let Some(body) = self.body.as_mut() {
+            if self.body_pos == self.pos {
+                return Ok(());
+            }
+            if self.body_pos < self.pos && self.pos - self.body_pos <= MAX_SKIP {
+                let skip = self.pos - self.body_pos;
+                if io::copy(&mut body.by_ref().take(skip), &mut io::sink())? == skip {
+                    self.body_pos = self.pos;
+                    return Ok(());
+                }
+            }
+        }
+
+        let range = format!("bytes={}-", self.pos);
+        let response = send(self.agent.get(&self.url).set("Range", &range))?;
+        // 200 means the whole file, which is only right when reading from the start
+        if response.status() != 206 && !(response.status() == 200 && self.pos == 0) {
+            return Err(io::Error::other(format!(
+                "Server ignored the range request for {}",
+                self.url
+            )));
+        }
+        self.body = Some(response.into_reader());
+        self.body_pos = self.pos;
+        Ok(())
+    }
+}
+
+impl Read for HttpReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.pos >= self.len {
+            return Ok(0);
+        }
+
+        self.reposition()?;
+        let body = self.body.as_mut().expect("reposition opens a response");
+        let n = body.read(buf)?;
+        if n == 0 {
+            self.body = None;
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("Connection closed before the end of {}", self.url),
+            ));
+        }
+
+        self.pos += n as u64;
+        self.body_pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for HttpReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.len.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+        };
+        self.pos = target.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Seek before the start of the file",
+            )
+        })?;
+        Ok(self.pos)
+    }
+}
+
+/// Send a request, turning HTTP error statuses into I/O errors.
+fn send(request: ureq::Request) -> io::Result<ureq::Response> {
+    request.call().map_err(|e| match e {
+        ureq::Error::Status(404 | 410, _) => io::Error::new(io::ErrorKind::NotFound, e.to_string()),
+        e => io::Error::other(e.to_string()),
+    })
+}
+
+/// Extract an archive served at `url`.
+///
+/// Servers that accept range requests are read in place; otherwise (including
+/// when the server refuses `HEAD`) the download is spooled to an anonymous
+/// temporary file first, since most formats can't be read without seeking.
+/// The file is created in `ExtractOptions::temp_dir`, or next to `output_dir`.
+/// A download that grows past `ExtractOptions::size_limit_bytes` is abandoned.
+pub fn extract_url(
+    url: &str,
+    output_dir: &Path,
+    options: &ExtractOptions,
+    progress_cb: &ProgressCallback,
+    cancel_flag: Arc<AtomicBool>,
+) -> Result<ExtractStats, ExtractError> {
+    let agent = ureq::agent();
+    if let Ok(Some(reader)) = HttpReader::with_agent(agent.clone(), url) {
+        return extract::extract_from_reader(reader, output_dir, options, progress_cb, cancel_flag);
+    }
+
+    let mut spool = tempfile::tempfile_in(extract::temp_dir_for(output_dir, options)?)?;
+    spool_download(
+        &mut send(agent.get(url))?.into_reader(),
+        &mut spool,
+        options,
+        &cancel_flag,
+    )?;
+    spool.rewind()?;
+    extract::extract_from_reader(spool, output_dir, options, progress_cb, cancel_flag)
+}
+
+/// Copy a whole download into `spool`, returning the number of bytes copied.
+///
+/// Stops with `ExtractError::Cancelled` once `cancel_flag` is set and with
+/// `ExtractError::SizeLimitExceeded` once the download is larger than the size
+/// limit, as none of it could be extracted anyway.
+fn spool_download(
+    body: &mut dyn Read,
+    spool: &mut dyn Write,
+    options: &ExtractOptions,
+    cancel_flag: &AtomicBool,
+) -> Result<u64, ExtractError> {
+    let mut buf = vec![0; SPOOL_CHUNK];
+    let mut total = 0u64;
+    loop {
+        if cancel_flag.load(Ordering::Relaxed) {
+            return Err(ExtractError::Cancelled);
+        }
+        let n = match body.read(&mut buf) {
+            Ok(0) => return Ok(total),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        total += n as u64;
+        extract::check_size_limit(total, options)?;
+        spool.write_all(&buf[..n])?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Cursor};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::atomic::AtomicUsize;
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    /// A local HTTP server for `data`, answering range requests if `ranges`.
+    struct Server {
+        url: String,
+        /// Number of GET requests served
+        gets: Arc<AtomicUsize>,
+    }
+
+    impl Server {
+        fn start(data: Vec<u8>, ranges: bool) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}/archive.zip", listener.local_addr().unwrap());
+            let data = Arc::new(data);
+            let gets = Arc::new(AtomicUsize::new(0));
+            let counter = Arc::clone(&gets);
+            std::thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let (data, counter) = (Arc::clone(&data), Arc::clone(&counter));
+                    std::thread::spawn(move || serve(stream, &data, ranges, &counter));
+                }
+            });
+            Self { url, gets }
+        }
+
+        fn gets(&self) -> usize {
+            self.gets.load(Ordering::SeqCst)
+        }
+    }
+
+    /// Answer one request on `stream`.
+    fn serve(mut stream: TcpStream, data: &[u8], ranges: bool, gets: &AtomicUsize) {
+        let mut request = BufReader::new(stream.try_clone().unwrap());
+        let mut line = String::new();
+        request.read_line(&mut line).unwrap();
+        let head = line.starts_with("HEAD");
+        let mut start = None;
+        loop {
+            let mut header = String::new();
+            if request.read_line(&mut header).unwrap() == 0 || header.trim().is_empty() {
+                break;
+            }
+            if let Some(range) = header.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                start = range.trim().trim_end_matches('-').parse::<usize>().ok();
+            }
+        }
+        if !head {
+            gets.fetch_add(1, Ordering::SeqCst);
+        }
+
+        let start = start.filter(|_| ranges);
+        let body = &data[start.unwrap_or(0)..];
+        let mut response = match start {
+            Some(start) => format!(
+                "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\n",
+                start,
+                data.len() - 1,
+                data.len()
+            ),
+            None => "HTTP/1.1 200 OK\r\n".to_string(),
+        };
+        if ranges {
+            response.push_str("Accept-Ranges: bytes\r\n");
+        }
+        response.push_str(&format!(
+            "Content-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        ));
+        // The client may hang up early, which is fine
+        let _ = stream.write_all(response.as_bytes());
+        if !head {
+            let _ = stream.write_all(body);
+        }
+    }
+
+    fn test_zip() -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options =
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        zip.start_file("hello.txt", options).unwrap();
+        zip.write_all(b"Hello over HTTP").unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_http_reader_range_reads() {
+        let data: Vec<u8> = (0..1024 * 1024u32).map(|i| (i % 251) as u8).collect();
+        let server = Server::start(data.clone(), true);
+        let mut reader = HttpReader::open(&server.url).unwrap().unwrap();
+        assert_eq!(reader.len(), data.len() as u64);
+
+        let mut buf = [0u8; 16];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data[..16]);
+        assert_eq!(server.gets(), 1);
+
+        // A short skip forward reads through the open response
+        reader.seek(SeekFrom::Current(1000)).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data[1016..1032]);
+        assert_eq!(server.gets(), 1);
+
+        // Seeking back or far ahead starts a new range request
+        reader.seek(SeekFrom::Start(8)).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data[8..24]);
+        assert_eq!(server.gets(), 2);
+        reader.seek(SeekFrom::End(-16)).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data[data.len() - 16..]);
+        assert_eq!(server.gets(), 3);
+
+        // Reading past the end returns nothing; seeking before the start fails
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+        assert!(reader
+            .seek(SeekFrom::Current(-(data.len() as i64) - 1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_http_reader_without_ranges() {
+        let server = Server::start(test_zip(), false);
+        assert!(HttpReader::open(&server.url).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_extract_url() {
+        for ranges in [true, false] {
+            let server = Server::start(test_zip(), ranges);
+            let temp_dir = tempfile::tempdir().unwrap();
+            let output_dir = temp_dir.path().join("output");
+            let stats = extract_url(
+                &server.url,
+                &output_dir,
+                &ExtractOptions::default(),
+                &|_, _, _| true,
+                Arc::new(AtomicBool::new(false)),
+            )
+            .unwrap();
+            assert_eq!(stats.files_extracted, 1);
+            assert_eq!(
+                std::fs::read_to_string(output_dir.join("hello.txt")).unwrap(),
+                "Hello over HTTP"
+            );
+        }
+    }
+
+    #[test]
+    fn test_extract_url_download_cancelled() {
+        let server = Server::start(test_zip(), false);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let result = extract_url(
+            &server.url,
+            &temp_dir.path().join("output"),
+            &ExtractOptions::default(),
+            &|_, _, _| true,
+            Arc::new(AtomicBool::new(true)),
+        );
+        assert!(matches!(result, Err(ExtractError::Cancelled)));
+    }
+
+    #[test]
+    fn test_extract_url_download_size_limit() {
+        let data = vec![0u8; 4 * SPOOL_CHUNK];
+        let server = Server::start(data, false);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let options = ExtractOptions {
+            size_limit_bytes: Some(SPOOL_CHUNK as u64),
+            ..ExtractOptions::default()
+        };
+        let result = extract_url(
+            &server.url,
+            &temp_dir.path().join("output"),
+            &options,
+            &|_, _, _| true,
+            Arc::new(AtomicBool::new(false)),
+        );
+        assert!(matches!(
+            result,
+            Err(ExtractError::SizeLimitExceeded { limit, .. }) if limit == SPOOL_CHUNK as u64
+        ));
+    }
+}
@@ -0,0 +1,776 @@
+//! Structural integrity checks that read an archive without extracting it.
+//!
+//! These checks walk the archive's own bookkeeping: ZIP end records and local
+//! headers, tar header checksums, compressed stream trailers, the 7z start
+//! header and RAR block headers, plus the numbering of multi-volume sets. Entry
+//! data is decompressed only where the format offers no other way to reach the
+//! next header (compressed tarballs and single compressed files).
+
+use crate::error::ExtractError;
+use crate::types::{
+    ArchiveInfo, Compression, Format, Integrity, ProbeOptions, VolumeIssue, VolumeProblem,
+    VolumeReport,
+};
+use crate::xz::XzDecoder;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Largest distance of the ZIP end of central directory record from the end of
+/// the file (record plus maximum comment length).
+const ZIP_EOCD_SEARCH: u64 = 22 + u16::MAX as u64;
+
+/// Size of the 7z signature header preceding the packed streams.
+const SEVENZ_START_HEADER: u64 = 32;
+
+/// Signatures at the start of 7z archives and of every RAR volume.
+const SEVENZ_SIGNATURE: &[u8] = b"7z\xbc\xaf\x27\x1c";
+const RAR4_SIGNATURE: &[u8] = b"Rar!\x1a\x07\x00";
+const RAR5_SIGNATURE: &[u8] = b"Rar!\x1a\x07\x01\x00";
+
+/// Largest RAR 5 block header (the format caps headers at 2 MB).
+const RAR5_MAX_HEADER: u64 = 2 * 1024 * 1024;
+
+/// Highest volume number looked for in a multi-volume set.
+const MAX_VOLUMES: u32 = 999;
+
+/// Probe an archive and check its structure without extracting any data.
+///
+/// Behaves like [`crate::probe_with_options`] and additionally fills in
+/// `integrity`, and `checksum` if a checksum file sits next to the archive
+/// (see [`crate::verify_checksum`]). The structure check doesn't decrypt
+/// anything, so archives whose headers are encrypted are reported as `Ok` as
+/// far as the readable structure goes, even with [`ProbeOptions::password`].
+pub fn probe_deep(path: &Path, options: &ProbeOptions) -> Result<ArchiveInfo, ExtractError> {
+    let mut info = crate::probe::probe_archive_with_options(path, options)?;
+    info.integrity = Some(check_integrity(path, info.format)?);
+    info.checksum = crate::checksum::verify_checksum(path)?;
+    Ok(info)
+}
+
+/// Check the structure of the archive at `path` in the given format.
+fn check_integrity(path: &Path, format: Format) -> Result<Integrity, ExtractError> {
+    // Every volume of a multi-volume set must be present and consistent
+    if let Some(report) = verify_volumes(path)? {
+        if let Some(problem) = report.problems.first() {
+            return Ok(Integrity::Corrupted {
+                detail: problem.to_string(),
+            });
+        }
+    }
+
+    // Split 7z and ZIP sets are only checked for completeness
+    if is_numbered_volume(path) {
+        return Ok(Integrity::Ok);
+    }
+
+    let integrity = match format {
+        Format::Zip => check_zip(path)?,
+        Format::Tar(compression) => check_tar(path, compression)?,
+        Format::Gzip | Format::Bzip2 | Format::Xz => check_stream(path, format.compression())?,
+        Format::SevenZ => check_7z(path)?,
+        Format::Rar => check_rar(path),
+    };
+
+    Ok(integrity)
+}
+
+/// Check the ZIP end records, central directory and each local header.
+fn check_zip(path: &Path) -> Result<Integrity, ExtractError> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+
+    // A file cut short loses its end of central directory record first
+    if !has_zip_end_record(&mut file, len)? {
+        return Ok(Integrity::Truncated);
+    }
+
+    let mut archive = match zip::ZipArchive::new(BufReader::new(file)) {
+        Ok(archive) => archive,
+        Err(e) => return Ok(zip_error(e)),
+    };
+
+    for i in 0..archive.len() {
+        // Reading the raw entry parses its local header without decompressing
+        let entry = match archive.by_index_raw(i) {
+            Ok(entry) => entry,
+            Err(e) => return Ok(zip_error(e)),
+        };
+
+        if entry.data_start().saturating_add(entry.compressed_size()) > len {
+            return Ok(Integrity::Truncated);
+        }
+    }
+
+    Ok(Integrity::Ok)
+}
+
+/// Whether the end of central directory signature appears near the end of the file.
+fn has_zip_end_record(file: &mut File, len: u64) -> io::Result<bool> {
+    let start = len.saturating_sub(ZIP_EOCD_SEARCH);
+    file.seek(SeekFrom::Start(start))?;
+
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail)?;
+
+    Ok(tail.windows(4).any(|window| window == b"PK\x05\x06"))
+}
+
+fn zip_error(error: zip::result::ZipError) -> Integrity {
+    match error {
+        zip::result::ZipError::Io(e) => io_error(e),
+        e => Integrity::Corrupted {
+            detail: e.to_string(),
+        },
+    }
+}
+
+/// Walk every tar header, verifying checksums and that each member's data is
+/// complete. Compressed tarballs are decompressed (and their checksums
+/// verified) on the way.
+fn check_tar(path: &Path, compression: Compression) -> Result<Integrity, ExtractError> {
+    let file = File::open(path)?;
+
+    let reader: Box<dyn Read> = match compression {
+        Compression::Gzip => Box::new(flate2::read::GzDecoder::new(BufReader::new(file))),
+        Compression::Bzip2 => Box::new(bzip2::read::BzDecoder::new(BufReader::new(file))),
+        Compression::Xz => Box::new(XzDecoder::new(BufReader::new(file))),
+        Compression::None => Box::new(BufReader::new(file)),
+    };
+
+    let mut archive = tar::Archive::new(reader);
+    let entries = match archive.entries() {
+        Ok(entries) => entries,
+        Err(e) => return Ok(io_error(e)),
+    };
+
+    for entry in entries {
+        let mut entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => return Ok(io_error(e)),
+        };
+
+        let expected = entry.size();
+        match io::copy(&mut entry, &mut io::sink()) {
+            Ok(read) if read < expected => return Ok(Integrity::Truncated),
+            Ok(_) => {}
+            Err(e) => return Ok(io_error(e)),
+        }
+    }
+
+    Ok(Integrity::Ok)
+}
+
+/// Decompress a single gzip, bzip2 or xz stream, which verifies its checksum.
+fn check_stream(path: &Path, compression: Compression) -> Result<Integrity, ExtractError> {
+    let reader = BufReader::new(File::open(path)?);
+
+    let result = match compression {
+        Compression::Gzip => io::copy(&mut flate2::read::GzDecoder::new(reader), &mut io::sink()),
+        Compression::Bzip2 => io::copy(&mut bzip2::read::BzDecoder::new(reader), &mut io::sink()),
+        Compression::Xz => io::copy(&mut XzDecoder::new(reader), &mut io::sink()),
+        Compression::None => return Ok(Integrity::Ok),
+    };
+
+    Ok(match result {
+        Ok(_) => Integrity::Ok,
+        Err(e) => io_error(e),
+    })
+}
+
+/// Check that the 7z header the start header points at lies within the file,
+/// then parse it (which verifies its CRC).
+fn check_7z(path: &Path) -> Result<Integrity, ExtractError> {
+    use sevenz_rust2::{Password, SevenZReader};
+
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+
+    let mut start_header = [0u8; SEVENZ_START_HEADER as usize];
+    if let Err(e) = file.read_exact(&mut start_header) {
+        return Ok(io_error(e));
+    }
+
+    let next_header_offset = u64::from_le_bytes(start_header[12..20].try_into().unwrap());
+    let next_header_size = u64::from_le_bytes(start_header[20..28].try_into().unwrap());
+    let header_end = SEVENZ_START_HEADER
+        .saturating_add(next_header_offset)
+        .saturating_add(next_header_size);
+    if header_end > len {
+        return Ok(Integrity::Truncated);
+    }
+
+    file.seek(SeekFrom::Start(0))?;
+    Ok(match SevenZReader::new(file, len, Password::empty()) {
+        Ok(_) => Integrity::Ok,
+        Err(e) => {
+            let detail = e.to_string();
+            if is_password_error(&detail) {
+                Integrity::Ok
+            } else {
+                Integrity::Corrupted { detail }
+            }
+        }
+    })
+}
+
+/// Walk the RAR block headers across all volumes without unpacking data.
+fn check_rar(path: &Path) -> Integrity {
+    use unrar::Archive;
+
+    let result = (|| -> Result<(), Box<dyn std::error::Error>> {
+        let mut current = Some(Archive::new(path).as_first_part().open_for_listing()?);
+        while let Some(archive) = current {
+            current = match archive.read_header()? {
+                Some(header) => Some(header.skip()?),
+                None => None,
+            };
+        }
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => Integrity::Ok,
+        Err(e) => {
+            let detail = e.to_string();
+            let lower = detail.to_lowercase();
+            if is_password_error(&detail) {
+                Integrity::Ok
+            } else if lower.contains("end of") || lower.contains("eof") || lower.contains("volume")
+            {
+                Integrity::Truncated
+            } else {
+                Integrity::Corrupted { detail }
+            }
+        }
+    }
+}
+
+fn is_password_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("password") || message.contains("encrypted")
+}
+
+/// Map an I/O error hit while walking an archive to an integrity status.
+fn io_error(error: io::Error) -> Integrity {
+    let detail = error.to_string();
+    // The tar crate reports a short header block as a plain error
+    if error.kind() == io::ErrorKind::UnexpectedEof
+        || detail.contains("failed to read entire block")
+    {
+        Integrity::Truncated
+    } else {
+        Integrity::Corrupted { detail }
+    }
+}
+
+/// Whether `path` is one of a numbered split set (`.7z.001`, `.zip.002`, ...).
+fn is_numbered_volume(path: &Path) -> bool {
+    numbered_volume(path).is_some()
+}
+
+/// Split a numbered volume name into its base name and the width of its number.
+fn numbered_volume(path: &Path) -> Option<(String, usize)> {
+    let name = path.file_name()?.to_str()?;
+    let (base, number) = name.rsplit_once('.')?;
+    let lower = base.to_lowercase();
+
+    if (lower.ends_with(".7z") || lower.ends_with(".zip"))
+        && !number.is_empty()
+        && number.chars().all(|c| c.is_ascii_digit())
+    {
+        Some((base.to_string(), number.len()))
+    } else {
+        None
+    }
+}
+
+/// Check that the multi-volume set `path` belongs to is complete, without
+/// extracting anything.
+///
+/// Every volume up to the last one must exist and fit its place in the set.
+/// RAR volumes each start with a signature, record their number (RAR5, and
+/// RAR4 end headers) and say whether another volume follows. Numbered 7z and
+/// ZIP splits are equal-sized pieces of one archive: only the first starts with
+/// a signature, the 7z start header gives the total size and the ZIP end record
+/// sits in the last piece, which reveals missing volumes at the end of the set.
+///
+/// Returns `None` if `path` is not part of a volume set.
+pub fn verify_volumes(path: &Path) -> Result<Option<VolumeReport>, ExtractError> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let Some(volume_name) = volume_namer(path, dir) else {
+        return Ok(None);
+    };
+
+    // Highest volume number present in the directory
+    let names: Vec<String> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .collect();
+    let Some(last) = (1..=MAX_VOLUMES)
+        .filter(|&n| names.contains(&volume_name(n)))
+        .max()
+    else {
+        return Ok(None);
+    };
+
+    let volumes: Vec<PathBuf> = (1..=last).map(|n| dir.join(volume_name(n))).collect();
+    let mut problems: Vec<VolumeProblem> = volumes
+        .iter()
+        .filter(|volume| !volume.exists())
+        .map(|volume| volume_problem(volume, VolumeIssue::Missing))
+        .collect();
+
+    // Number of volumes the headers say the set has, when they tell
+    let expected = if numbered_volume(path).is_some() {
+        check_split_volumes(&volumes, &mut problems)?
+    } else {
+        check_rar_volumes(&volumes, &mut problems)
+    };
+
+    let mut volumes: Vec<String> = volumes
+        .iter()
+        .map(|volume| volume.to_string_lossy().into_owned())
+        .collect();
+    for n in last + 1..=expected.unwrap_or(last).min(MAX_VOLUMES) {
+        let volume = dir.join(volume_name(n));
+        problems.push(volume_problem(&volume, VolumeIssue::Missing));
+        volumes.push(volume.to_string_lossy().into_owned());
+    }
+
+    // Report problems in volume order
+    problems.sort_by_key(|problem| volumes.iter().position(|v| *v == problem.path));
+    Ok(Some(VolumeReport { volumes, problems }))
+}
+
+fn volume_problem(volume: &Path, issue: VolumeIssue) -> VolumeProblem {
+    VolumeProblem {
+        path: volume.to_string_lossy().into_owned(),
+        issue,
+    }
+}
+
+fn corrupted(volume: &Path, detail: String) -> VolumeProblem {
+    volume_problem(volume, VolumeIssue::Corrupted { detail })
+}
+
+/// Check the pieces of a numbered 7z or ZIP split, returning how many pieces
+/// the set should have if that can be told.
+fn check_split_volumes(
+    volumes: &[PathBuf],
+    problems: &mut Vec<VolumeProblem>,
+) -> Result<Option<u32>, ExtractError> {
+    let first = &volumes[0];
+    if !first.exists() {
+        return Ok(None);
+    }
+
+    let mut head = [0u8; SEVENZ_START_HEADER as usize];
+    let head_len = read_at(first, 0, &mut head)?;
+    let head = &head[..head_len];
+    let is_7z = head.starts_with(SEVENZ_SIGNATURE);
+    if !is_7z && !head.starts_with(b"PK\x03\x04") && !head.starts_with(b"PK\x07\x08") {
+        problems.push(corrupted(first, "no archive signature".to_string()));
+        return Ok(None);
+    }
+
+    // Splitting tools cut every piece but the last to the same size
+    let piece_size = fs::metadata(first)?.len();
+    let (last, others) = volumes.split_last().unwrap_or((first, &[]));
+    for volume in others.iter().filter(|volume| volume.exists()) {
+        let size = fs::metadata(volume)?.len();
+        if size != piece_size {
+            problems.push(corrupted(
+                volume,
+                format!("{} bytes, but the first volume has {}", size, piece_size),
+            ));
+        }
+    }
+
+    if is_7z && head.len() == SEVENZ_START_HEADER as usize && piece_size > 0 {
+        // The start header points at the end header, which ends the archive
+        let next_header_offset = u64::from_le_bytes(head[12..20].try_into().unwrap());
+        let next_header_size = u64::from_le_bytes(head[20..28].try_into().unwrap());
+        let total = SEVENZ_START_HEADER
+            .saturating_add(next_header_offset)
+            .saturating_add(next_header_size);
+        let pieces = total.div_ceil(piece_size).min(MAX_VOLUMES as u64) as u32;
+        return Ok(Some(pieces));
+    }
+
+    if !is_7z && last.exists() {
+        let mut file = File::open(last)?;
+        let len = file.metadata()?.len();
+        if !has_zip_end_record(&mut file, len)? {
+            return Ok(Some(volumes.len() as u32 + 1));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Check the volumes of a multi-part RAR archive, returning how many volumes
+/// the set should have if that can be told.
+fn check_rar_volumes(volumes: &[PathBuf], problems: &mut Vec<VolumeProblem>) -> Option<u32> {
+    let mut expected = None;
+
+    for (index, volume) in volumes.iter().enumerate() {
+        if !volume.exists() {
+            continue;
+        }
+
+        let headers = match read_rar_volume_headers(volume) {
+            Ok(headers) => headers,
+            Err(detail) => {
+                problems.push(corrupted(volume, detail));
+                continue;
+            }
+        };
+
+        let position = index as u64 + 1;
+        if let Some(number) = headers.number.filter(|&number| number + 1 != position) {
+            problems.push(corrupted(
+                volume,
+                format!("is volume {} of the set, not {}", number + 1, position),
+            ));
+        }
+
+        match headers.more_follow {
+            Some(false) if position < volumes.len() as u64 => problems.push(corrupted(
+                volume,
+                "marked as the last volume of the set".to_string(),
+            )),
+            Some(true) if position == volumes.len() as u64 => {
+                expected = Some(volumes.len() as u32 + 1);
+            }
+            _ => {}
+        }
+    }
+
+    expected
+}
+
+/// What a RAR volume's headers say about its place in the set.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct RarVolumeHeaders {
+    /// Volume number, from 0
+    number: Option<u64>,
+    /// Whether another volume follows, from the end of archive header
+    more_follow: Option<bool>,
+}
+
+/// Walk the block headers of one RAR volume (skipping the data between them).
+///
+/// Returns a description of the problem if the volume isn't a RAR volume or
+/// its headers can't be read.
+fn read_rar_volume_headers(path: &Path) -> Result<RarVolumeHeaders, String> {
+    let mut signature = [0u8; 8];
+    let len = read_at(path, 0, &mut signature).map_err(|e| e.to_string())?;
+    let signature = &signature[..len];
+
+    let result = if signature.starts_with(RAR5_SIGNATURE) {
+        read_rar5_headers(path)
+    } else if signature.starts_with(RAR4_SIGNATURE) {
+        read_rar4_headers(path)
+    } else {
+        return Err("no RAR signature".to_string());
+    };
+    result.map_err(|e| e.to_string())
+}
+
+/// RAR 5 blocks: CRC32, header size and type as variable-length integers, then
+/// flags saying whether extra and data areas follow.
+fn read_rar5_headers(path: &Path) -> io::Result<RarVolumeHeaders> {
+    let len = fs::metadata(path)?.len();
+    let mut headers = RarVolumeHeaders::default();
+    let mut pos = RAR5_SIGNATURE.len() as u64;
+
+    while pos < len {
+        let mut start = [0u8; 4 + 3];
+        let read = read_at(path, pos, &mut start)?;
+        let mut cursor = 4;
+        let Some(header_size) = read_vint(&start[..read], &mut cursor) else {
+            // A volume cut short ends without its end of archive header
+            break;
+        };
+        if header_size == 0 || header_size > RAR5_MAX_HEADER {
+            return Err(io::Error::other(format!(
+                "bad block header at offset {}",
+                pos
+            )));
+        }
+
+        let mut header = vec![0u8; header_size as usize];
+        if read_at(path, pos + cursor as u64, &mut header)? < header.len() {
+            break;
+        }
+
+        let mut field = 0;
+        let malformed = || io::Error::other(format!("malformed block header at offset {}", pos));
+        let header_type = read_vint(&header, &mut field).ok_or_else(malformed)?;
+        let flags = read_vint(&header, &mut field).ok_or_else(malformed)?;
+        if flags & 0x0001 != 0 {
+            read_vint(&header, &mut field).ok_or_else(malformed)?;
+        }
+        let data_size = if flags & 0x0002 != 0 {
+            read_vint(&header, &mut field).ok_or_else(malformed)?
+        } else {
+            0
+        };
+
+        match header_type {
+            // Main archive header: volume flag, then the number of all but the first
+            1 => {
+                let archive_flags = read_vint(&header, &mut field).ok_or_else(malformed)?;
+                if archive_flags & 0x0001 != 0 {
+                    headers.number = Some(if archive_flags & 0x0002 != 0 {
+                        read_vint(&header, &mut field).ok_or_else(malformed)?
+                    } else {
+                        0
+                    });
+                }
+            }
+            // End of archive header: whether this is not the last volume
+            5 => {
+                let end_flags = read_vint(&header, &mut field).ok_or_else(malformed)?;
+                headers.more_follow = Some(end_flags & 0x0001 != 0);
+                break;
+            }
+            _ => {}
+        }
+
+        pos = pos
+            .saturating_add(cursor as u64)
+            .saturating_add(header_size)
+            .saturating_add(data_size);
+    }
+
+    Ok(headers)
+}
+
+/// RAR 4 blocks: CRC16, type, flags and size, plus the packed data size of file
+/// blocks and other blocks flagged as having data.
+fn read_rar4_headers(path: &Path) -> io::Result<RarVolumeHeaders> {
+    let len = fs::metadata(path)?.len();
+    let mut headers = RarVolumeHeaders::default();
+    let mut pos = RAR4_SIGNATURE.len() as u64;
+
+    while pos < len {
+        let mut block = [0u8; 36];
+        let read = read_at(path, pos, &mut block)?;
+        if read < 7 {
+            break;
+        }
+        let block = &block[..read];
+
+        let header_type = block[2];
+        let flags = u16::from_le_bytes([block[3], block[4]]);
+        let header_size = u16::from_le_bytes([block[5], block[6]]) as u64;
+        if header_size < 7 {
+            return Err(io::Error::other(format!(
+                "bad block header at offset {}",
+                pos
+            )));
+        }
+        let u32_at = |offset: usize| {
+            block
+                .get(offset..offset + 4)
+                .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as u64)
+        };
+
+        let data_size = match header_type {
+            // File and service blocks: packed size, with high bits for large files
+            0x74 | 0x7a => {
+                let low = u32_at(7).unwrap_or(0);
+                let high = if flags & 0x0100 != 0 {
+                    u32_at(32).unwrap_or(0)
+                } else {
+                    0
+                };
+                high << 32 | low
+            }
+            _ if flags & 0x8000 != 0 => u32_at(7).unwrap_or(0),
+            _ => 0,
+        };
+
+        match header_type {
+            // Main archive header: the first volume is flagged as such
+            0x73 if flags & 0x0100 != 0 => headers.number = Some(0),
+            // End of archive header: next volume flag and (optionally) the
+            // volume number after the data CRC
+            0x7b => {
+                headers.more_follow = Some(flags & 0x0001 != 0);
+                if flags & 0x0008 != 0 {
+                    let offset = if flags & 0x0002 != 0 { 11 } else { 7 };
+                    if let Some(bytes) = block.get(offset..offset + 2) {
+                        headers.number = Some(u16::from_le_bytes([bytes[0], bytes[1]]) as u64);
+                    }
+                }
+                break;
+            }
+            _ => {}
+        }
+
+        pos = pos.saturating_add(header_size).saturating_add(data_size);
+    }
+
+    Ok(headers)
+}
+
+/// Read a RAR 5 variable-length integer (7 bits per byte, low bits first).
+fn read_vint(buf: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..70).step_by(7) {
+        let byte = *buf.get(*pos)?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Read up to `buf.len()` bytes of `path` starting at `offset`, returning how
+/// many were read (fewer at the end of the file).
+fn read_at(path: &Path, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+
+    let mut filled = 0;
+    while filled < buf.len() {
+        match file.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+/// Build a function giving the file name of volume `n` (from 1) of the set
+/// `path` belongs to, or `None` if `path` is not part of a volume set.
+fn volume_namer(path: &Path, dir: &Path) -> Option<Box<dyn Fn(u32) -> String>> {
+    let name = path.file_name()?.to_str()?;
+    let lower = name.to_lowercase();
+
+    if let Some((base, width)) = numbered_volume(path) {
+        return Some(Box::new(move |n| format!("{}.{:0width$}", base, n)));
+    }
+
+    if !lower.ends_with(".rar") {
+        return None;
+    }
+
+    // New-style sets: name.part1.rar, name.part2.rar, ...
+    if let Some(part_pos) = lower.rfind(".part") {
+        let prefix = name[..part_pos].to_string();
+        let digits = name.len() - 4 - (part_pos + 5);
+        return Some(Box::new(move |n| {
+            format!("{}.part{:0digits$}.rar", prefix, n)
+        }));
+    }
+
+    // Old-style sets: name.rar, name.r00, name.r01, ...
+    let stem = name[..name.len() - 4].to_string();
+    if !dir.join(format!("{}.r00", stem)).exists() {
+        return None;
+    }
+    Some(Box::new(move |n| match n {
+        1 => format!("{}.rar", stem),
+        n => format!("{}.r{:02}", stem, n - 2),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_volumes() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "set.7z.001",
+            "set.7z.003",
+            "movie.part1.rar",
+            "movie.part2.rar",
+        ] {
+            fs::write(dir.path().join(name), b"").unwrap();
+        }
+
+        let missing = |report: VolumeReport| -> Vec<String> {
+            report
+                .problems
+                .into_iter()
+                .filter(|problem| problem.issue == VolumeIssue::Missing)
+                .map(|problem| problem.path)
+                .collect()
+        };
+
+        let report = verify_volumes(&dir.path().join("set.7z.001"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(report.volumes.len(), 3);
+        assert!(!report.is_complete());
+        assert_eq!(
+            missing(report),
+            vec![dir.path().join("set.7z.002").to_string_lossy().into_owned()]
+        );
+
+        let report = verify_volumes(&dir.path().join("movie.part1.rar"))
+            .unwrap()
+            .unwrap();
+        assert!(missing(report).is_empty());
+        assert_eq!(
+            verify_volumes(&dir.path().join("single.zip")).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rar_volume_headers() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // RAR 5: main header of volume 2, then an end header saying more follow
+        let mut rar5 = RAR5_SIGNATURE.to_vec();
+        rar5.extend_from_slice(&[0, 0, 0, 0, 4, 1, 0, 0x03, 1]);
+        rar5.extend_from_slice(&[0, 0, 0, 0, 3, 5, 0, 1]);
+        let path = dir.path().join("v5.part2.rar");
+        fs::write(&path, &rar5).unwrap();
+        assert_eq!(
+            read_rar_volume_headers(&path).unwrap(),
+            RarVolumeHeaders {
+                number: Some(1),
+                more_follow: Some(true),
+            }
+        );
+
+        // RAR 4: first volume main header, then a final end header
+        let mut rar4 = RAR4_SIGNATURE.to_vec();
+        rar4.extend_from_slice(&[0, 0, 0x73, 0x01, 0x01, 13, 0, 0, 0, 0, 0, 0, 0]);
+        rar4.extend_from_slice(&[0, 0, 0x7b, 0, 0, 7, 0]);
+        let path = dir.path().join("v4.rar");
+        fs::write(&path, &rar4).unwrap();
+        assert_eq!(
+            read_rar_volume_headers(&path).unwrap(),
+            RarVolumeHeaders {
+                number: Some(0),
+                more_follow: Some(false),
+            }
+        );
+
+        let path = dir.path().join("bad.rar");
+        fs::write(&path, b"not a rar").unwrap();
+        assert!(read_rar_volume_headers(&path).is_err());
+    }
+
+    #[test]
+    fn test_io_error_classification() {
+        let eof = io::Error::new(io::ErrorKind::UnexpectedEof, "eof");
+        assert_eq!(io_error(eof), Integrity::Truncated);
+
+        let bad = io::Error::other("archive header checksum mismatch");
+        assert!(matches!(io_error(bad), Integrity::Corrupted { .. }));
+    }
+}
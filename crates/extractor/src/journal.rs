@@ -0,0 +1,399 @@
+//! Journals of running extractions, for recovering from a crash.
+//!
+//! With `ExtractOptions::journal` an extraction keeps a small JSON file next to
+//! its output directory saying which entries it has written, how many bytes
+//! that came to and which files and folders it created. The journal goes away
+//! when the extraction returns, successfully or not, so one found later belongs
+//! to an extraction whose process died partway. [`resume_extraction`] picks
+//! such an extraction up where it left off, and [`rollback`] removes what it
+//! created.
+//!
+//! The journal is saved at most every second, so what an extraction did in the
+//! last moment before a crash may be missing from it.
+
+use crate::confine::OutputRoot;
+use crate::error::{ExtractError, SecurityError};
+use crate::types::{ExtractOptions, ExtractStats, SubfolderPolicy};
+use crate::ProgressCallback;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+
+/// Ending of the journal file name, after the output directory's name.
+pub const JOURNAL_SUFFIX: &str = ".unarchiver-journal.json";
+
+/// How often a running extraction saves its journal.
+const SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What an extraction had done when its journal was last saved.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ExtractionJournal {
+    /// Archive being extracted
+    pub archive_path: String,
+
+    /// Directory it is extracted to
+    pub output_dir: String,
+
+    /// Number of entries written completely
+    #[ts(type = "number")]
+    pub entries_completed: u64,
+
+    /// Bytes written by the completed entries
+    #[ts(type = "number")]
+    pub bytes_written: u64,
+
+    /// Archive paths of the entries written completely
+    pub completed: Vec<String>,
+
+    /// File being written when the journal was saved
+    #[ts(optional)]
+    pub current_file: Option<String>,
+
+    /// Files the extraction created
+    pub created_files: Vec<String>,
+
+    /// Folders the extraction created, including the output directory if it
+    /// didn't exist
+    pub created_dirs: Vec<String>,
+}
+
+/// Where the journal of an extraction into `output_dir` is kept: a hidden file
+/// next to it.
+pub fn journal_path(output_dir: &Path) -> PathBuf {
+    let parent = match output_dir.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let name = output_dir.file_name().unwrap_or(output_dir.as_os_str());
+    parent.join(format!(".{}{}", name.to_string_lossy(), JOURNAL_SUFFIX))
+}
+
+/// The journal of an interrupted extraction into `output_dir`, if there is one.
+///
+/// # Errors
+///
+/// Returns `ExtractError::Corrupted` if the journal can't be parsed, and
+/// `SecurityError::PathTraversal` if it is for another output directory.
+pub fn read_journal(output_dir: &Path) -> Result<Option<ExtractionJournal>, ExtractError> {
+    let json = match fs::read(journal_path(output_dir)) {
+        Ok(json) => json,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let journal: ExtractionJournal = serde_json::from_slice(&json)
+        .map_err(|e| ExtractError::Corrupted(format!("Unreadable extraction journal: {}", e)))?;
+
+    // A journal only speaks for the directory it sits next to
+    if !same_dir(Path::new(&journal.output_dir), output_dir) {
+        return Err(outside_output_dir(&journal.output_dir));
+    }
+    Ok(Some(journal))
+}
+
+/// Delete the journal of an interrupted extraction into `output_dir`, leaving
+/// its files as they are.
+pub fn discard_journal(output_dir: &Path) -> Result<(), ExtractError> {
+    match fs::remove_file(journal_path(output_dir)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Undo an interrupted extraction: remove the files and (then empty) folders
+/// its journal says it created and any partially written files, then the
+/// journal itself.
+///
+/// Only what lies inside the journal's output directory is removed, through a
+/// handle to it.
+///
+/// # Errors
+///
+/// Returns `SecurityError::PathTraversal`, having removed nothing the journal
+/// lists, if any of its paths lead outside the output directory.
+pub fn rollback(journal: &ExtractionJournal) -> Result<(), ExtractError> {
+    let output_dir = Path::new(&journal.output_dir);
+    if let Some(leftovers) = Leftovers::open(output_dir)? {
+        // Checked up front, so a planted path stops the rollback before it
+        // starts
+        let files = leftovers.resolve_all(&journal.created_files)?;
+        let mut dirs = leftovers.resolve_all(
+            journal
+                .created_dirs
+                .iter()
+                .filter(|dir| Path::new(dir) != output_dir),
+        )?;
+        leftovers.remove_leftovers(journal)?;
+        for file in &files {
+            leftovers.remove_file(file)?;
+        }
+
+        // Deepest first; folders that aren't empty are left alone
+        dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+        for dir in &dirs {
+            let _ = leftovers.root.remove_dir(dir);
+        }
+        if journal
+            .created_dirs
+            .iter()
+            .any(|dir| Path::new(dir) == output_dir)
+        {
+            drop(leftovers);
+            let _ = fs::remove_dir(output_dir);
+        }
+    }
+
+    discard_journal(output_dir)
+}
+
+/// Continue an interrupted extraction into `output_dir` from its journal.
+///
+/// The partially written file is removed and the archive extracted again with
+/// `options`, skipping the entries the journal lists as completed. The resumed
+/// extraction keeps a journal of its own, so it can be resumed in turn.
+///
+/// # Errors
+///
+/// Returns `ExtractError::NotFound` with the journal's path if there is no
+/// journal, and otherwise the same errors as [`crate::extract`].
+pub fn resume_extraction(
+    output_dir: &Path,
+    options: &ExtractOptions,
+    progress_cb: &ProgressCallback,
+    cancel_flag: Arc<AtomicBool>,
+) -> Result<ExtractStats, ExtractError> {
+    let journal = read_journal(output_dir)?
+        .ok_or_else(|| ExtractError::NotFound(journal_path(output_dir)))?;
+    if let Some(leftovers) = Leftovers::open(output_dir)? {
+        leftovers.remove_leftovers(&journal)?;
+    }
+
+    // The journal is kept for the folder the entries went into
+    let options = ExtractOptions {
+        journal: true,
+        create_subfolder: SubfolderPolicy::Never,
+        ..options.clone()
+    };
+    let archive_path = PathBuf::from(&journal.archive_path);
+    crate::extract::extract_resumed(
+        &archive_path,
+        output_dir,
+        &options,
+        progress_cb,
+        cancel_flag,
+        journal,
+    )
+}
+
+/// The output directory of an interrupted extraction, through which what it
+/// left behind is removed. Journals are plain files anyone may edit, so every
+/// path from one is checked to lie inside the directory before it's used.
+struct Leftovers {
+    root: OutputRoot,
+    /// The directory's canonical path
+    path: PathBuf,
+}
+
+impl Leftovers {
+    /// Open the output directory at `output_dir`, if it still exists.
+    fn open(output_dir: &Path) -> Result<Option<Self>, ExtractError> {
+        let path = match fs::canonicalize(output_dir) {
+            Ok(path) => path,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Some(Self {
+            root: OutputRoot::open(&path)?,
+            path,
+        }))
+    }
+
+    /// `path` from the journal with its folder canonicalized, or `None` if
+    /// that folder is gone. Fails for paths outside the output directory.
+    fn resolve(&self, path: &str) -> Result<Option<PathBuf>, ExtractError> {
+        let (Some(parent), Some(name)) = (Path::new(path).parent(), Path::new(path).file_name())
+        else {
+            return Err(outside_output_dir(path));
+        };
+        let parent = match fs::canonicalize(parent) {
+            Ok(parent) => parent,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        if !parent.starts_with(&self.path) {
+            return Err(outside_output_dir(path));
+        }
+        Ok(Some(parent.join(name)))
+    }
+
+    /// The `paths` from the journal that still have a folder, resolved.
+    fn resolve_all<'a>(
+        &self,
+        paths: impl IntoIterator<Item = &'a String>,
+    ) -> Result<Vec<PathBuf>, ExtractError> {
+        let mut resolved = Vec::new();
+        for path in paths {
+            resolved.extend(self.resolve(path)?);
+        }
+        Ok(resolved)
+    }
+
+    /// Remove the file the interrupted extraction was writing and any
+    /// partially written temporary files.
+    fn remove_leftovers(&self, journal: &ExtractionJournal) -> Result<(), ExtractError> {
+        if let Some(current) = &journal.current_file {
+            if let Some(current) = self.resolve(current)? {
+                self.remove_file(&current)?;
+            }
+        }
+        self.remove_partial_files(&self.path)?;
+        Ok(())
+    }
+
+    fn remove_partial_files(&self, dir: &Path) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                self.remove_partial_files(&entry.path())?;
+            } else if file_type.is_file() && crate::extract::is_partial_file(&entry.path()) {
+                self.root.remove_file(&entry.path())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        match self.root.remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Whether `a` and `b` are the same directory, or name the same path if
+/// either doesn't exist.
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => absolute(a) == absolute(b),
+    }
+}
+
+/// The error for a journal path outside the output directory.
+fn outside_output_dir(path: &str) -> ExtractError {
+    SecurityError::PathTraversal(format!(
+        "{} in the extraction journal is outside the output directory",
+        path
+    ))
+    .into()
+}
+
+/// The journal of a running extraction, removed from disk when dropped.
+pub(crate) struct JournalWriter {
+    path: PathBuf,
+    journal: ExtractionJournal,
+    /// Entries an earlier, interrupted run already wrote
+    resumed: HashSet<String>,
+    /// How many of the created files and folders this run reported are in
+    /// the journal
+    recorded: (usize, usize),
+    last_save: Instant,
+}
+
+impl JournalWriter {
+    /// Start the journal of extracting `archive_path` into `output_dir`,
+    /// carrying over what the interrupted run in `previous` did.
+    pub fn start(
+        archive_path: &Path,
+        output_dir: &Path,
+        previous: Option<ExtractionJournal>,
+    ) -> Result<Self, ExtractError> {
+        let previous = previous.unwrap_or_default();
+        let writer = Self {
+            path: journal_path(output_dir),
+            resumed: previous.completed.iter().cloned().collect(),
+            recorded: (0, 0),
+            journal: ExtractionJournal {
+                archive_path: absolute(archive_path),
+                output_dir: absolute(output_dir),
+                current_file: None,
+                ..previous
+            },
+            last_save: Instant::now(),
+        };
+        writer.save()?;
+        Ok(writer)
+    }
+
+    /// Whether the interrupted run being resumed already wrote `entry`.
+    pub fn already_written(&self, entry: &str) -> bool {
+        self.resumed.contains(entry)
+    }
+
+    /// Record that `file` is about to be written, along with the files and
+    /// folders this run has created so far.
+    pub fn start_file(&mut self, file: &Path, files: &[PathBuf], dirs: &[PathBuf]) {
+        self.journal.current_file = Some(absolute(file));
+        let (recorded_files, recorded_dirs) = &mut self.recorded;
+        record_paths(&mut self.journal.created_files, files, recorded_files);
+        record_paths(&mut self.journal.created_dirs, dirs, recorded_dirs);
+        self.save_now_and_then();
+    }
+
+    /// Record that `entry` was written completely, `written` bytes long.
+    pub fn finish_entry(&mut self, entry: &str, written: u64) {
+        self.journal.entries_completed += 1;
+        self.journal.bytes_written += written;
+        self.journal.completed.push(entry.to_string());
+        self.journal.current_file = None;
+        self.save_now_and_then();
+    }
+
+    fn save_now_and_then(&mut self) {
+        if self.last_save.elapsed() >= SAVE_INTERVAL {
+            // Journaling is best effort; the extraction carries on without it
+            let _ = self.save();
+            self.last_save = Instant::now();
+        }
+    }
+
+    /// Replace the journal file in one step, so a crash mid-write never leaves
+    /// it half written.
+    fn save(&self) -> Result<(), ExtractError> {
+        let json = serde_json::to_vec(&self.journal).map_err(io::Error::from)?;
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+impl Drop for JournalWriter {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Add the `paths` after the first `count` to `journaled`, which then holds
+/// them all.
+fn record_paths(journaled: &mut Vec<String>, paths: &[PathBuf], count: &mut usize) {
+    for path in paths.get(*count..).unwrap_or_default() {
+        journaled.push(absolute(path));
+    }
+    *count = paths.len();
+}
+
+/// `path` made absolute, so the journal can be used from another directory.
+fn absolute(path: &Path) -> String {
+    std::path::absolute(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .to_string()
+}
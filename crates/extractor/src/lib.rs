@@ -46,18 +46,105 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! ## API stability
+//!
+//! The items in [`prelude`] are the stable surface: they only change in
+//! breaking ways with a new major version. Everything else at the crate root
+//! is public for the bundled app and CLI and may change between minor
+//! versions. Modules hidden from the documentation are internal; use their
+//! items through the re-exports at the crate root. `check_public_api.sh` in
+//! the repository lists what a change adds to, changes in or removes from the
+//! public API.
 
+#[doc(hidden)]
+pub mod cache;
+#[doc(hidden)]
+pub mod carve;
+#[doc(hidden)]
+pub mod charset;
+#[doc(hidden)]
+pub mod checksum;
+mod confine;
+#[doc(hidden)]
+pub mod discover;
+#[doc(hidden)]
+pub mod entry;
+#[doc(hidden)]
 pub mod error;
-pub mod extract;
-pub mod probe;
+mod extract;
+#[doc(hidden)]
+pub mod fd_budget;
+#[cfg(feature = "http")]
+#[doc(hidden)]
+pub mod http;
+#[doc(hidden)]
+pub mod integrity;
+pub mod journal;
+mod mmap;
+mod nocache;
+mod parallel;
+mod pipeline;
+mod prealloc;
+pub mod preflight;
+pub mod prelude;
+mod probe;
+#[doc(hidden)]
+pub mod provenance;
+#[doc(hidden)]
+pub mod quarantine;
+#[doc(hidden)]
+pub mod reader;
 pub mod safety;
+#[doc(hidden)]
+pub mod sniff;
+#[doc(hidden)]
 pub mod types;
+mod verify;
+mod xattrs;
+mod xz;
+mod zip32;
 
 // Re-export main types
-pub use error::{ExtractError, SecurityError};
+pub use cache::{Fingerprint, ProbeCache};
+pub use carve::{extract_at_offset, probe_at_offset, scan_signatures, SignatureMatch};
+pub use charset::FilenameEncoding;
+pub use checksum::verify_checksum;
+pub use discover::{
+    find_archives, is_continuation_volume, is_supported_archive, unique_output_dir,
+};
+pub use entry::{extract_entry_to_writer, Archive, EntryReader};
+pub use error::{ErrorHint, ExtractError, SecurityError};
+pub use extract::is_partial_file;
+pub use fd_budget::{fds_per_extraction, open_file_limit, FdBudget, FdPermit, FDS_PER_EXTRACTION};
+#[cfg(feature = "http")]
+pub use http::HttpReader;
+pub use integrity::{probe_deep, verify_volumes};
+pub use journal::{discard_journal, read_journal, resume_extraction, rollback, ExtractionJournal};
+pub use preflight::{
+    check_free_space, check_inodes, check_memory_backed, free_inodes, free_space,
+    memory_backed_space,
+};
+pub use provenance::{ExtractionInfo, PROVENANCE_FILE_NAME, SHA256SUMS_FILE_NAME};
+pub use reader::{
+    open_buffered_reader, open_reader, open_stream_reader, ArchiveReader, EntryDecoder,
+};
 pub use safety::EntryType;
-pub use types::{ArchiveEntry, ArchiveInfo, ExtractOptions, ExtractStats, OverwriteMode};
+pub use sniff::sniff_content;
+pub use types::{
+    ArchiveEntry, ArchiveInfo, ArchiveSummary, BlockedEntry, ChecksumAlgorithm, ChecksumCheck,
+    Compression, ContentKind, ContentPolicy, EntryAction, EntryCheck, EntryError, EntryFilter,
+    EntryObserver, EntrySort, ErrorPolicy, ExtensionStats, ExtractOptions, ExtractOptionsBuilder,
+    ExtractStats, ExtractionPlan, Format, FreeSpaceCheck, Integrity, NameSanitizer,
+    NormalizationForm, OversizePolicy, OverwriteDecision, OverwriteMode, OverwritePrompt,
+    PathLimits, PathMapper, PreWriteHook, ProbeOptions, ProbeProgress, ProgressEvent,
+    ProgressListener, ProgressPhase, ProgressSink, RenamedEntry, ResourceHint, SortKey,
+    SubfolderPolicy, SymlinkPolicy, TimestampPolicy, VerifyMismatch, VerifyReport, VolumeIssue,
+    VolumeProblem, VolumeReport, WindowsNamePolicy, WriteDecision, DEFAULT_BUFFER_SIZE,
+    PROBE_SCHEMA_VERSION,
+};
 
+use std::io::{Cursor, Read, Seek};
 use std::path::Path;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
@@ -65,13 +152,29 @@ use std::sync::Arc;
 /// Type alias for progress callback functions.
 ///
 /// The callback receives:
-/// - `file`: The current file being extracted
+/// - `file`: The current entry being extracted
 /// - `bytes_written`: Number of bytes written so far
-/// - `total_bytes`: Optional total size of the file
+/// - `total_bytes`: Size of the file once written (0 for directories and
+///   entries that weren't written), or `None` for the interim reports made
+///   every few megabytes while a large file is written
 ///
-/// Returns `true` to continue extraction, `false` to cancel.
+/// It is called for every entry, so it keeps ticking through entries that are
+/// skipped; `ExtractOptions::on_entry` tells what became of each, and
+/// `ExtractOptions::on_progress` receives the same reports as [`ProgressEvent`]s
+/// with entry counts and totals.
+///
+/// Returns `true` to continue extraction, `false` to cancel; a large file is
+/// abandoned midway.
 pub type ProgressCallback = dyn Fn(&str, u64, Option<u64>) -> bool + Send + Sync;
 
+/// Type alias for probe progress callback functions.
+///
+/// The callback receives:
+/// - `bytes_scanned`: Number of archive bytes scanned so far (best effort; may stay
+///   at 0 for formats whose reader does not expose a read position)
+/// - `entries_found`: Number of entries listed so far
+pub type ProbeProgressCallback = dyn Fn(u64, u64) + Send + Sync;
+
 /// Probe an archive to retrieve metadata without extracting.
 ///
 /// # Arguments
@@ -92,6 +195,133 @@ pub fn probe(path: &Path) -> Result<ArchiveInfo, ExtractError> {
     probe::probe_archive(path)
 }
 
+/// Probe an archive held in memory.
+///
+/// Behaves like [`probe`] for archives received over IPC or downloaded into
+/// memory, detecting the format from the data's signature.
+///
+/// # Errors
+///
+/// Returns `ExtractError::UnsupportedFormat` if the data doesn't start with a
+/// known archive signature.
+pub fn probe_bytes(data: &[u8]) -> Result<ArchiveInfo, ExtractError> {
+    probe::probe_bytes(data)
+}
+
+/// Probe an archive, reporting progress while entries are being listed.
+///
+/// Behaves like [`probe`], but invokes `progress_cb` as entries are discovered so
+/// callers can give feedback while large or solid-compressed archives are scanned,
+/// and stops early when `cancel_flag` is set.
+///
+/// # Arguments
+///
+/// * `path` - Path to the archive file
+/// * `progress_cb` - Callback receiving bytes scanned and entries found so far
+/// * `cancel_flag` - Atomic flag to signal cancellation
+///
+/// # Errors
+///
+/// Returns the same errors as [`probe`], plus `ExtractError::Cancelled` if the
+/// cancel flag was set before the scan finished.
+pub fn probe_with_progress(
+    path: &Path,
+    progress_cb: &ProbeProgressCallback,
+    cancel_flag: Arc<AtomicBool>,
+) -> Result<ArchiveInfo, ExtractError> {
+    probe::probe_archive_with_progress(path, progress_cb, cancel_flag)
+}
+
+/// Probe an archive whose entry list is encrypted.
+///
+/// When [`probe`] reports `header_encrypted`, no entries can be listed without the
+/// password. This behaves like [`probe_with_progress`] but uses `password` to
+/// read the encrypted headers of RAR and 7-Zip archives.
+///
+/// # Errors
+///
+/// Returns the same errors as [`probe_with_progress`], plus
+/// `ExtractError::InvalidPassword` if the entry list cannot be read with
+/// `password`.
+pub fn probe_with_password(
+    path: &Path,
+    password: &str,
+    progress_cb: &ProbeProgressCallback,
+    cancel_flag: Arc<AtomicBool>,
+) -> Result<ArchiveInfo, ExtractError> {
+    probe::probe_archive_with_password(path, password, progress_cb, cancel_flag)
+}
+
+/// Probe an archive with the given options.
+///
+/// Behaves like [`probe`], reading the archive the way `options` ask:
+/// - [`ProbeOptions::password`] reads an encrypted entry list
+/// - [`ProbeOptions::read_concatenated_tar`] reads on past the zero blocks that
+///   end a tar archive, listing the members of every archive appended after it
+///   (as `tar -A` produces)
+/// - [`ProbeOptions::filename_encoding`] decodes the ZIP entry names not marked
+///   as UTF-8 in a known legacy encoding
+///
+/// Progress is reported to [`ProbeOptions::on_progress`], and setting
+/// [`ProbeOptions::cancel_flag`] stops the scan early.
+///
+/// # Errors
+///
+/// Returns the same errors as [`probe`], plus `ExtractError::InvalidPassword`
+/// if the entry list cannot be read with the password and
+/// `ExtractError::Cancelled` if the scan was cancelled before it finished.
+pub fn probe_with_options(
+    path: &Path,
+    options: &ProbeOptions,
+) -> Result<ArchiveInfo, ExtractError> {
+    probe::probe_archive_with_options(path, options)
+}
+
+/// Cheaply estimate the uncompressed size of a gzip or xz compressed archive.
+///
+/// Reads only the gzip ISIZE trailer or the xz stream index instead of
+/// decompressing the whole stream, so it is fast even for very large archives.
+/// Gzip sizes wrap at 4 GiB and only cover the last member, so treat them as
+/// estimates.
+///
+/// # Returns
+///
+/// Returns `None` for other formats or when no size information is found.
+///
+/// # Errors
+///
+/// Returns an error if the file doesn't exist, its extension is not recognized,
+/// or it cannot be read.
+pub fn estimate_uncompressed_size(path: &Path) -> Result<Option<u64>, ExtractError> {
+    probe::estimate_uncompressed_size(path)
+}
+
+/// Plan extracting an archive into `output_dir`.
+///
+/// Probes the archive for the resources it needs, like [`probe`], and checks
+/// them against the destination: a destination on a RAM-backed filesystem
+/// (tmpfs) with less free space than the archive needs is warned about, since
+/// filling it uses up memory.
+///
+/// # Errors
+///
+/// Returns the same errors as [`probe`].
+pub fn plan_extraction(
+    archive_path: &Path,
+    output_dir: &Path,
+) -> Result<ExtractionPlan, ExtractError> {
+    let info = probe::probe_archive(archive_path)?;
+    Ok(preflight::plan_for(output_dir, info.resources))
+}
+
+/// Sort archive entries in place.
+///
+/// Ties (and entries without a modification time, which always sort last) are
+/// ordered by path so the result is stable across probes.
+pub fn sort_entries(entries: &mut [ArchiveEntry], sort: EntrySort) {
+    probe::sort_entries(entries, sort)
+}
+
 /// Extract an archive to the specified output directory.
 ///
 /// # Arguments
@@ -124,6 +354,119 @@ pub fn extract(
     extract::extract_archive(archive_path, output_dir, options, progress_cb, cancel_flag)
 }
 
+/// Test an archive by decoding every entry without writing anything.
+///
+/// Each entry's data is decompressed and compared with the CRC-32 (ZIP, 7-Zip,
+/// RAR) and size the archive records for it. An entry that fails doesn't stop
+/// the test; the report lists every entry with its outcome, and an error that
+/// ends reading early (such as a truncated compressed tarball) fails the archive
+/// as a whole. The options' password, filename encoding and trailing data
+/// settings apply, and `on_progress` is told of each entry as it is checked,
+/// in the `Verifying` phase, and can cancel the test by returning false. Other
+/// options are ignored.
+///
+/// # Errors
+///
+/// Returns an error if the archive can't be opened, its format isn't supported,
+/// or its entries need a password that isn't given or is wrong.
+///
+/// # Examples
+///
+/// ```no_run
+/// use extractor::{verify, ExtractOptions};
+/// use std::path::Path;
+///
+/// let report = verify(Path::new("backup.zip"), &ExtractOptions::default())?;
+/// for entry in report.entries.iter().filter(|entry| entry.error.is_some()) {
+///     println!("{} failed", entry.path);
+/// }
+/// # Ok::<(), extractor::ExtractError>(())
+/// ```
+pub fn verify(archive_path: &Path, options: &ExtractOptions) -> Result<VerifyReport, ExtractError> {
+    verify::verify_archive(archive_path, options)
+}
+
+/// Extract an archive held in memory.
+///
+/// Behaves like [`extract_from_reader`] over the bytes of an archive received
+/// over IPC or downloaded into memory.
+///
+/// # Errors
+///
+/// Returns the same errors as [`extract_from_reader`].
+pub fn extract_bytes(
+    data: &[u8],
+    output_dir: &Path,
+    options: &ExtractOptions,
+    progress_cb: &ProgressCallback,
+    cancel_flag: Arc<AtomicBool>,
+) -> Result<ExtractStats, ExtractError> {
+    extract::extract_from_reader(
+        Cursor::new(data),
+        output_dir,
+        options,
+        progress_cb,
+        cancel_flag,
+    )
+}
+
+/// Extract an archive read from any seekable source.
+///
+/// Processes archives that don't live in a file of their own (embedded in
+/// another file, behind a custom VFS layer, or an already-open handle) without
+/// copying them out first. The format is detected from the data's signature.
+///
+/// # Arguments
+///
+/// * `source` - Reader positioned anywhere; the archive must start at offset 0
+/// * `output_dir` - Directory where files will be extracted
+/// * `options` - Extraction options (overwrite mode, size limits, etc.)
+/// * `progress_cb` - Callback function for progress updates
+/// * `cancel_flag` - Atomic flag to signal cancellation
+///
+/// # Errors
+///
+/// Returns the same errors as [`extract`], plus `ExtractError::UnsupportedFormat`
+/// if the data doesn't start with a known archive signature.
+pub fn extract_from_reader<R: Read + Seek>(
+    source: R,
+    output_dir: &Path,
+    options: &ExtractOptions,
+    progress_cb: &ProgressCallback,
+    cancel_flag: Arc<AtomicBool>,
+) -> Result<ExtractStats, ExtractError> {
+    extract::extract_from_reader(source, output_dir, options, progress_cb, cancel_flag)
+}
+
+/// Extract an archive served over HTTP(S) without downloading it first.
+///
+/// Requires the `http` feature. When the server accepts range requests the
+/// archive is read in place, so a ZIP's central directory is fetched before any
+/// entry data and nothing is written to disk but the extracted files.
+///
+/// # Arguments
+///
+/// * `url` - URL of the archive
+/// * `output_dir` - Directory where files will be extracted
+/// * `options` - Extraction options (overwrite mode, size limits, etc.)
+/// * `progress_cb` - Callback function for progress updates
+/// * `cancel_flag` - Atomic flag to signal cancellation
+///
+/// # Errors
+///
+/// Returns the same errors as [`extract_from_reader`]; failed requests surface as
+/// `ExtractError::Io`, with `NotFound` for 404 and 410 responses.
+#[cfg(feature = "http")]
+pub fn extract_url(
+    url: &str,
+    output_dir: &Path,
+    options: &ExtractOptions,
+    progress_cb: &ProgressCallback,
+    cancel_flag: Arc<AtomicBool>,
+) -> Result<ExtractStats, ExtractError> {
+    http::extract_url(url, output_dir, options, progress_cb, cancel_flag)
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -0,0 +1,129 @@
+//! Read-only memory maps of archive files.
+//!
+//! With `ExtractOptions::memory_map`, ZIP and 7-Zip archives are read from a
+//! map of the whole file instead of through buffered reads, so parsing the
+//! central directory and jumping between entries costs no system calls. A map
+//! is shared by cheap clones, one per reader or decoding thread.
+//!
+//! Reading a page of the map that's no longer backed by the file raises
+//! `SIGBUS`, so archives mustn't be truncated while mapped; that's why maps
+//! are opt-in.
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+/// A read-only map of a whole file, unmapped once the last clone is dropped.
+#[derive(Clone)]
+pub(crate) struct Mmap(Arc<Mapping>);
+
+struct Mapping {
+    ptr: *const u8,
+    len: usize,
+}
+
+// SAFETY: the mapping is read-only and never moved, so it can be read from any
+// thread
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl Mmap {
+    /// Map the file at `path`.
+    #[cfg(unix)]
+    pub fn open(path: &Path) -> io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        let file = File::open(path)?;
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| io::Error::other("File too large to map"))?;
+        // Empty maps aren't allowed
+        if len == 0 {
+            return Ok(Self(Arc::new(Mapping {
+                ptr: std::ptr::NonNull::dangling().as_ptr(),
+                len: 0,
+            })));
+        }
+
+        // SAFETY: the descriptor is open; the map outlives it by design and is
+        // only read through slices of `len` bytes
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self(Arc::new(Mapping {
+            ptr: ptr as *const u8,
+            len,
+        })))
+    }
+
+    /// Mapping files isn't supported here; callers fall back to reading them.
+    #[cfg(not(unix))]
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let _ = File::open(path)?;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Memory maps aren't supported on this platform",
+        ))
+    }
+}
+
+impl AsRef<[u8]> for Mmap {
+    fn as_ref(&self) -> &[u8] {
+        // SAFETY: ptr points to len mapped, readable bytes (or is dangling
+        // with len 0) for as long as the mapping lives
+        unsafe { std::slice::from_raw_parts(self.0.ptr, self.0.len) }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if self.len > 0 {
+            // SAFETY: ptr and len describe a map created by Mmap::open
+            unsafe { libc::munmap(self.ptr as *mut libc::c_void, self.len) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read, Seek, SeekFrom};
+
+    #[test]
+    #[cfg(unix)]
+    fn test_map_reads_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &data).unwrap();
+
+        let map = Mmap::open(&path).unwrap();
+        assert_eq!(map.as_ref(), data.as_slice());
+
+        // Clones share the map but read independently
+        let mut a = Cursor::new(map.clone());
+        let mut b = Cursor::new(map);
+        a.seek(SeekFrom::Start(99_990)).unwrap();
+        let mut tail = Vec::new();
+        a.read_to_end(&mut tail).unwrap();
+        assert_eq!(tail, &data[99_990..]);
+        let mut head = [0u8; 4];
+        b.read_exact(&mut head).unwrap();
+        assert_eq!(head, data[..4]);
+
+        let empty = dir.path().join("empty.bin");
+        std::fs::write(&empty, b"").unwrap();
+        assert!(Mmap::open(&empty).unwrap().as_ref().is_empty());
+    }
+}
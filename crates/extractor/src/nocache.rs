@@ -0,0 +1,328 @@
+//! Writing extracted files around the page cache.
+//!
+//! Extracting a huge archive normally leaves every written byte in the page
+//! cache, pushing out whatever the user had cached before. With
+//! `ExtractOptions::bypass_page_cache` files are written with `O_DIRECT` on
+//! Linux and `F_NOCACHE` on macOS instead. `O_DIRECT` only takes writes of
+//! whole, aligned blocks, so data is gathered in an aligned buffer and the
+//! tail of each file is written normally; filesystems that refuse `O_DIRECT`
+//! (tmpfs, some network mounts) get ordinary writes.
+//!
+//! With `ExtractOptions::sparse_files` blocks of zeros are skipped over rather
+//! than written, leaving holes the filesystem doesn't allocate space for.
+
+use crate::confine::OutputRoot;
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, Write};
+use std::path::Path;
+
+/// Block alignment used for `O_DIRECT` writes; covers the logical block size of
+/// common disks.
+#[cfg(target_os = "linux")]
+const ALIGN: usize = 4096;
+
+/// Size of the blocks checked for zeros when writing a sparse file; holes
+/// smaller than a filesystem block wouldn't save anything.
+const SPARSE_BLOCK: usize = 4096;
+
+/// Size of the blocks written with `O_DIRECT`.
+#[cfg(target_os = "linux")]
+const BLOCK_SIZE: usize = 1024 * 1024;
+
+/// A file being extracted, written around the page cache where supported.
+///
+/// Ordinary writes go through a buffer, while `O_DIRECT` writes are gathered in
+/// an aligned block instead. Space for the file can be reserved up front with
+/// [`OutputFile::preallocate`]. Sparse files skip blocks of zeros instead.
+pub(crate) struct OutputFile {
+    file: BufWriter<File>,
+    /// Whether blocks of zeros are left as holes
+    sparse: bool,
+    /// Length the file was extended to by preallocation, to be trimmed to
+    /// what's actually written
+    extended: Option<u64>,
+    /// Data waiting to fill a block, while writing with `O_DIRECT`
+    #[cfg(target_os = "linux")]
+    pending: Option<AlignedBuffer>,
+}
+
+impl OutputFile {
+    /// Create (or truncate) the file at `path` under `root`, bypassing the
+    /// page cache if `uncached` is set and the platform and filesystem allow
+    /// it. Ordinary writes are buffered in `buffer_size` bytes.
+    pub fn create(
+        root: &OutputRoot,
+        path: &Path,
+        uncached: bool,
+        buffer_size: usize,
+    ) -> io::Result<Self> {
+        if uncached {
+            return Self::create_uncached(root, path, buffer_size);
+        }
+        Ok(Self::plain(root.create_file(path)?, buffer_size))
+    }
+
+    /// Create (or truncate) the file at `path` under `root` to be written
+    /// sparse, through the page cache and a `buffer_size` buffer.
+    pub fn create_sparse(root: &OutputRoot, path: &Path, buffer_size: usize) -> io::Result<Self> {
+        Ok(Self {
+            sparse: true,
+            ..Self::plain(root.create_file(path)?, buffer_size)
+        })
+    }
+
+    fn plain(file: File, buffer_size: usize) -> Self {
+        Self {
+            file: BufWriter::with_capacity(buffer_size, file),
+            sparse: false,
+            extended: None,
+            #[cfg(target_os = "linux")]
+            pending: None,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn create_uncached(root: &OutputRoot, path: &Path, buffer_size: usize) -> io::Result<Self> {
+        use cap_std::fs::{OpenOptions, OpenOptionsExt};
+
+        let mut options = OpenOptions::new();
+        options
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .custom_flags(libc::O_DIRECT);
+        let direct = root.open_file(path, &options);
+        match direct {
+            // Whole blocks are gathered in the aligned buffer, so writes to
+            // the file itself aren't buffered again
+            Ok(file) => Ok(Self {
+                file: BufWriter::with_capacity(0, file),
+                sparse: false,
+                extended: None,
+                pending: Some(AlignedBuffer::new()),
+            }),
+            // The filesystem doesn't do direct I/O
+            Err(e) if e.raw_os_error() == Some(libc::EINVAL) => {
+                Ok(Self::plain(root.create_file(path)?, buffer_size))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    fn create_uncached(root: &OutputRoot, path: &Path, buffer_size: usize) -> io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        let file = root.create_file(path)?;
+        // Best effort: without it the file is simply cached as usual
+        // SAFETY: the descriptor belongs to file, which is open
+        unsafe { libc::fcntl(file.as_raw_fd(), libc::F_NOCACHE, 1) };
+        Ok(Self::plain(file, buffer_size))
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    fn create_uncached(root: &OutputRoot, path: &Path, buffer_size: usize) -> io::Result<Self> {
+        Ok(Self::plain(root.create_file(path)?, buffer_size))
+    }
+
+    /// Reserve disk space for the `len` bytes expected to be written, failing
+    /// if the disk can't hold them. Small files are left alone.
+    pub fn preallocate(&mut self, len: u64) -> io::Result<()> {
+        if len >= crate::prealloc::MIN_PREALLOCATE
+            && crate::prealloc::reserve(self.file.get_ref(), len)?
+        {
+            self.extended = Some(len);
+        }
+        Ok(())
+    }
+
+    /// Write out any data still buffered and close the file.
+    pub fn finish(mut self) -> io::Result<()> {
+        #[cfg(target_os = "linux")]
+        if let Some(pending) = self.pending.take() {
+            // Whole blocks can still go around the cache, the tail can't
+            let data = pending.data();
+            let aligned = data.len() - data.len() % ALIGN;
+            self.write_direct(&data[..aligned])?;
+            if aligned < data.len() {
+                self.stop_direct()?;
+                self.file.write_all(&data[aligned..])?;
+            }
+        }
+        self.file.flush()?;
+
+        // Skipping a hole at the end doesn't extend the file by itself
+        if self.sparse {
+            let end = self.file.get_mut().stream_position()?;
+            self.file.get_ref().set_len(end)?;
+        }
+
+        // The recorded size may have been more than there was to write
+        if let Some(extended) = self.extended {
+            let written = self.file.get_mut().stream_position()?;
+            if written < extended {
+                self.file.get_ref().set_len(written)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write `data`, a multiple of [`ALIGN`] long from an aligned buffer, with
+    /// `O_DIRECT`, switching to ordinary writes if the device wants a coarser
+    /// alignment.
+    #[cfg(target_os = "linux")]
+    fn write_direct(&mut self, data: &[u8]) -> io::Result<()> {
+        match self.file.write_all(data) {
+            Err(e) if e.raw_os_error() == Some(libc::EINVAL) => {
+                self.stop_direct()?;
+                self.file.write_all(data)
+            }
+            result => result,
+        }
+    }
+
+    /// Clear `O_DIRECT`, so that writes of any size and alignment work.
+    #[cfg(target_os = "linux")]
+    fn stop_direct(&mut self) -> io::Result<()> {
+        use std::os::unix::io::AsRawFd;
+
+        let fd = self.file.get_ref().as_raw_fd();
+        // SAFETY: the descriptor belongs to self.file, which is open
+        let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+        if flags < 0 || unsafe { libc::fcntl(fd, libc::F_SETFL, flags & !libc::O_DIRECT) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        #[cfg(target_os = "linux")]
+        if let Some(mut pending) = self.pending.take() {
+            if pending.is_full() {
+                if let Err(e) = self.write_direct(pending.data()) {
+                    self.pending = Some(pending);
+                    return Err(e);
+                }
+                pending.clear();
+            }
+            let taken = pending.push(data);
+            self.pending = Some(pending);
+            return Ok(taken);
+        }
+        if self.sparse {
+            let block = &data[..data.len().min(SPARSE_BLOCK)];
+            if block.len() == SPARSE_BLOCK && block.iter().all(|&byte| byte == 0) {
+                self.file.seek(io::SeekFrom::Current(SPARSE_BLOCK as i64))?;
+                return Ok(SPARSE_BLOCK);
+            }
+            return self.file.write(block);
+        }
+        self.file.write(data)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // Data gathered for O_DIRECT can only be written as whole blocks, or by
+        // finish
+        self.file.flush()
+    }
+}
+
+/// A block-sized buffer starting at an [`ALIGN`]ed address, as `O_DIRECT`
+/// requires of the memory written from.
+#[cfg(target_os = "linux")]
+struct AlignedBuffer {
+    storage: Vec<u8>,
+    start: usize,
+    len: usize,
+}
+
+#[cfg(target_os = "linux")]
+impl AlignedBuffer {
+    fn new() -> Self {
+        let storage = vec![0u8; BLOCK_SIZE + ALIGN];
+        let start = storage.as_ptr().align_offset(ALIGN);
+        Self {
+            storage,
+            start,
+            len: 0,
+        }
+    }
+
+    fn data(&self) -> &[u8] {
+        &self.storage[self.start..self.start + self.len]
+    }
+
+    fn is_full(&self) -> bool {
+        self.len == BLOCK_SIZE
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Append as much of `data` as fits, returning how much that was.
+    fn push(&mut self, data: &[u8]) -> usize {
+        let taken = data.len().min(BLOCK_SIZE - self.len);
+        let at = self.start + self.len;
+        self.storage[at..at + taken].copy_from_slice(&data[..taken]);
+        self.len += taken;
+        taken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_uncached_file_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = OutputRoot::open(temp_dir.path()).unwrap();
+        let path = temp_dir.path().join("data.bin");
+
+        // Several blocks and an unaligned tail, written in odd-sized pieces
+        let data: Vec<u8> = (0..2_500_003u32).map(|i| (i % 253) as u8).collect();
+        let mut file = OutputFile::create(&root, &path, true, 0).unwrap();
+        for piece in data.chunks(77_777) {
+            file.write_all(piece).unwrap();
+        }
+        file.finish().unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+    }
+
+    #[test]
+    fn test_preallocated_file_keeps_written_length() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = OutputRoot::open(temp_dir.path()).unwrap();
+        let path = temp_dir.path().join("data.bin");
+
+        // Less is written than was reserved
+        let data = vec![7u8; 1_500_000];
+        let mut file = OutputFile::create(&root, &path, false, 4096).unwrap();
+        file.preallocate(8 * 1024 * 1024).unwrap();
+        file.write_all(&data).unwrap();
+        file.finish().unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+    }
+
+    #[test]
+    fn test_sparse_file_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = OutputRoot::open(temp_dir.path()).unwrap();
+        let path = temp_dir.path().join("disk.img");
+
+        // Data between long zero runs, ending in a hole
+        let mut data = vec![0u8; 4 * 1024 * 1024];
+        data[1_000_000..1_000_100].fill(9);
+        let mut file = OutputFile::create_sparse(&root, &path, 4096).unwrap();
+        file.write_all(&data).unwrap();
+        file.finish().unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+    }
+}
@@ -0,0 +1,377 @@
+//! Extracting the entries of ZIP archives on several threads.
+//!
+//! Every ZIP entry is compressed on its own, so with `ExtractOptions::threads`
+//! above one their data is decoded and written by a pool of workers, each with
+//! an [`EntryDecoder`] of its own. The calling thread still walks the entries
+//! in archive order and settles everything else about them (paths, filters,
+//! overwrite handling, folders); the workers report each file back to it, so
+//! statistics, progress and the size limit stay in one place. Files are
+//! reported in the order they finish, not in archive order.
+
+use crate::error::ExtractError;
+use crate::nocache::OutputFile;
+use crate::pipeline::FileJob;
+use crate::provenance::hex_digest;
+use crate::reader::EntryDecoder;
+use crate::types::EntryAction;
+use sha2::{Digest, Sha256};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::{Scope, ScopedJoinHandle};
+
+/// A file to hand to the workers.
+pub(crate) struct QueuedFile {
+    /// Index of the entry for the decoders
+    pub index: usize,
+    /// Where the file was requested, before overwrite handling
+    pub requested: PathBuf,
+    /// Where and how to write it
+    pub job: FileJob,
+    /// Size beyond which the file is discarded
+    pub max_len: Option<u64>,
+    /// Whether to hash the data written with SHA-256
+    pub hash: bool,
+    /// Recorded size of the entry, held against the size limit until written
+    pub reserved: u64,
+    /// Recorded compressed size of the entry, if known
+    pub compressed_size: Option<u64>,
+    /// Name the file is reported under
+    pub name: String,
+    /// Archive path of the entry
+    pub entry: String,
+    /// Whether it's written under its own name or renamed
+    pub action: EntryAction,
+}
+
+/// A file the workers have written.
+pub(crate) struct Written {
+    /// Name the file was queued with
+    pub name: String,
+    /// Archive path of the entry
+    pub entry: String,
+    /// Where the file ended up
+    pub path: PathBuf,
+    /// Bytes written; more than the file's `max_len` if it was discarded for
+    /// going over it
+    pub len: u64,
+    /// SHA-256 of the data written, if it was hashed
+    pub sha256: Option<String>,
+    /// Recorded compressed size of the entry, if known
+    pub compressed_size: Option<u64>,
+    /// Whether it was written under its own name or renamed
+    pub action: EntryAction,
+}
+
+/// What the calling thread remembers about a queued file.
+struct InFlight {
+    id: u64,
+    requested: PathBuf,
+    final_path: PathBuf,
+    reserved: u64,
+    compressed_size: Option<u64>,
+    name: String,
+    entry: String,
+    action: EntryAction,
+}
+
+/// A file for one worker to write.
+struct Task {
+    id: u64,
+    index: usize,
+    job: FileJob,
+    max_len: Option<u64>,
+    hash: bool,
+}
+
+type Report = (u64, Result<(u64, Option<String>), ExtractError>);
+
+/// The calling thread's side of the worker pool.
+pub(crate) struct Workers<'scope> {
+    tasks: Option<SyncSender<Task>>,
+    done: Receiver<Report>,
+    in_flight: Vec<InFlight>,
+    next_id: u64,
+    /// Makes the workers drop their files and skip the queued ones
+    stop: Arc<AtomicBool>,
+    threads: Vec<ScopedJoinHandle<'scope, ()>>,
+}
+
+impl<'scope> Workers<'scope> {
+    /// Start a worker in `scope` for each of `decoders`, calling `finish` on
+    /// every file once its data is written. Workers give up on their files
+    /// when `cancel_flag` is set.
+    pub fn spawn<F>(
+        scope: &'scope Scope<'scope, '_>,
+        decoders: Vec<Box<dyn EntryDecoder>>,
+        cancel_flag: &'scope AtomicBool,
+        finish: F,
+    ) -> Self
+    where
+        F: Fn(&FileJob) -> Result<(), ExtractError> + Send + Sync + 'scope,
+    {
+        // Enough queued for every worker to pick up its next file right away
+        let (tasks, queued) = mpsc::sync_channel(decoders.len());
+        let queued = Arc::new(Mutex::new(queued));
+        let (report, done) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let finish = Arc::new(finish);
+
+        let threads = decoders
+            .into_iter()
+            .map(|decoder| {
+                let (queued, report, stop, finish) =
+                    (queued.clone(), report.clone(), stop.clone(), finish.clone());
+                scope.spawn(move || {
+                    let halted =
+                        || stop.load(Ordering::Relaxed) || cancel_flag.load(Ordering::Relaxed);
+                    work(decoder, &queued, &report, &halted, &*finish)
+                })
+            })
+            .collect();
+
+        Self {
+            tasks: Some(tasks),
+            done,
+            in_flight: Vec::new(),
+            next_id: 0,
+            stop,
+            threads,
+        }
+    }
+
+    /// Queue `file` for the next free worker, returning the files written
+    /// meanwhile.
+    pub fn extract(&mut self, file: QueuedFile) -> Result<Vec<Written>, ExtractError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.in_flight.push(InFlight {
+            id,
+            requested: file.requested,
+            final_path: file.job.final_path.clone(),
+            reserved: file.reserved,
+            compressed_size: file.compressed_size,
+            name: file.name,
+            entry: file.entry,
+            action: file.action,
+        });
+
+        let task = Task {
+            id,
+            index: file.index,
+            job: file.job,
+            max_len: file.max_len,
+            hash: file.hash,
+        };
+        let tasks = self.tasks.as_ref().expect("the workers are running");
+        tasks.send(task).map_err(|_| stopped())?;
+        self.collect(false)
+    }
+
+    /// Wait until no queued file is still to be written at or requested as
+    /// `path`, so that checks for an existing file there see the earlier one.
+    /// Returns the files written meanwhile.
+    pub fn wait_for(&mut self, path: &Path) -> Result<Vec<Written>, ExtractError> {
+        let mut written = self.collect(false)?;
+        while self
+            .in_flight
+            .iter()
+            .any(|file| file.requested == path || file.final_path == path)
+        {
+            written.extend(self.collect(true)?);
+        }
+        Ok(written)
+    }
+
+    /// Recorded size of the files queued but not yet written.
+    pub fn reserved(&self) -> u64 {
+        self.in_flight.iter().map(|file| file.reserved).sum()
+    }
+
+    /// Make the workers give up on their files, for when the extraction has
+    /// failed anyway.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// Wait for the workers to write everything queued, returning the files
+    /// written since last asked or the first error a worker stopped with.
+    pub fn finish(mut self) -> Result<Vec<Written>, ExtractError> {
+        drop(self.tasks.take());
+
+        let mut written = Vec::new();
+        let mut result = Ok(());
+        while result.is_ok() && !self.in_flight.is_empty() {
+            match self.collect(true) {
+                Ok(files) => written.extend(files),
+                Err(e) => {
+                    self.stop();
+                    result = Err(e);
+                }
+            }
+        }
+
+        for thread in self.threads.drain(..) {
+            if let Err(panic) = thread.join() {
+                std::panic::resume_unwind(panic);
+            }
+        }
+        result.map(|()| written)
+    }
+
+    /// Collect the reports of finished files, waiting for one if `wait` is
+    /// set, and fail with the first error among them.
+    fn collect(&mut self, wait: bool) -> Result<Vec<Written>, ExtractError> {
+        let mut written = Vec::new();
+        if wait {
+            let report = self.done.recv().map_err(|_| stopped())?;
+            written.push(self.settle(report)?);
+        }
+        while let Ok(report) = self.done.try_recv() {
+            written.push(self.settle(report)?);
+        }
+        Ok(written)
+    }
+
+    fn settle(&mut self, (id, result): Report) -> Result<Written, ExtractError> {
+        let at = self
+            .in_flight
+            .iter()
+            .position(|file| file.id == id)
+            .expect("reports are about queued files");
+        let file = self.in_flight.swap_remove(at);
+        let (len, sha256) = result?;
+        Ok(Written {
+            name: file.name,
+            entry: file.entry,
+            path: file.final_path,
+            len,
+            sha256,
+            compressed_size: file.compressed_size,
+            action: file.action,
+        })
+    }
+}
+
+/// Error for the calling thread when the workers have all stopped.
+fn stopped() -> ExtractError {
+    ExtractError::Io(io::Error::other("The extraction workers stopped"))
+}
+
+/// Body of a worker: write the files queued until the calling thread hangs up.
+fn work<F>(
+    mut decoder: Box<dyn EntryDecoder>,
+    queued: &Mutex<Receiver<Task>>,
+    report: &Sender<Report>,
+    halted: &dyn Fn() -> bool,
+    finish: &F,
+) where
+    F: Fn(&FileJob) -> Result<(), ExtractError>,
+{
+    loop {
+        // One worker at a time waits for the next file
+        let task = match queued.lock() {
+            Ok(queued) => queued.recv(),
+            Err(_) => return,
+        };
+        let Ok(task) = task else {
+            return;
+        };
+
+        let result = if halted() {
+            Err(ExtractError::Cancelled)
+        } else {
+            write_task(decoder.as_mut(), &task, halted, finish)
+        };
+        if report.send((task.id, result)).is_err() {
+            return;
+        }
+    }
+}
+
+/// Decode and write the file of `task`, then `finish` it, returning the number
+/// of bytes written and their SHA-256 if the task asks for it.
+fn write_task<F>(
+    decoder: &mut dyn EntryDecoder,
+    task: &Task,
+    halted: &dyn Fn() -> bool,
+    finish: &F,
+) -> Result<(u64, Option<String>), ExtractError>
+where
+    F: Fn(&FileJob) -> Result<(), ExtractError>,
+{
+    let mut out = LimitedFile {
+        file: task.job.create()?,
+        written: 0,
+        max_len: task.max_len,
+        halted,
+        hasher: task.hash.then(Sha256::new),
+    };
+    let copied = decoder.copy_entry(task.index, &mut out);
+    let (written, over_limit) = (out.written, out.over_limit());
+    let digest = out.hasher.take().map(hex_digest);
+
+    let result = match copied {
+        // Reported with the length, which tells the caller what happened
+        Err(_) if over_limit => {
+            drop(out);
+            task.job.discard(true);
+            return Ok((written, None));
+        }
+        Err(_) if halted() => Err(ExtractError::Cancelled),
+        Err(e) => Err(e),
+        Ok(_) => out
+            .file
+            .finish()
+            .map_err(ExtractError::from)
+            .and_then(|()| finish(&task.job)),
+    };
+    if let Err(e) = result {
+        task.job.discard(false);
+        return Err(e);
+    }
+    Ok((written, digest))
+}
+
+/// Output file that fails writes once the extraction is halted or the file
+/// grows past its limit.
+struct LimitedFile<'a> {
+    file: OutputFile,
+    written: u64,
+    max_len: Option<u64>,
+    halted: &'a dyn Fn() -> bool,
+    hasher: Option<Sha256>,
+}
+
+impl LimitedFile<'_> {
+    fn over_limit(&self) -> bool {
+        self.max_len.is_some_and(|max| self.written > max)
+    }
+}
+
+impl Write for LimitedFile<'_> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if (self.halted)() {
+            return Err(io::Error::other("The extraction was stopped"));
+        }
+        if self
+            .max_len
+            .is_some_and(|max| self.written + data.len() as u64 > max)
+        {
+            self.written += data.len() as u64;
+            return Err(io::Error::other("The file exceeds the size limit"));
+        }
+        let written = self.file.write(data)?;
+        self.written += written as u64;
+        if let Some(hasher) = &mut self.hasher {
+            hasher.update(&data[..written]);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
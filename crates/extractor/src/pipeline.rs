@@ -0,0 +1,287 @@
+//! Writing extracted files on a thread of their own.
+//!
+//! Decompressing is CPU-bound and writing is I/O-bound, so extraction runs as a
+//! two-stage pipeline: the calling thread decodes entries and hands their data
+//! in chunks to a writer thread through a bounded queue. When the disk falls
+//! behind the queue fills up and decoding waits, so no more than about
+//! `queue depth × CHUNK_SIZE` bytes are held in memory.
+
+use crate::confine::OutputRoot;
+use crate::error::ExtractError;
+use crate::nocache::OutputFile;
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::sync::Arc;
+use std::thread::{Scope, ScopedJoinHandle};
+
+/// Size of the chunks of decoded data handed to the writer thread.
+pub(crate) const CHUNK_SIZE: usize = 256 * 1024;
+
+/// Bytes of a file copied between progress reports and cancellation checks,
+/// so that a single huge entry can be followed and cancelled.
+pub(crate) const PROGRESS_STEP: u64 = 8 * 1024 * 1024;
+
+/// A file for the writer thread to create.
+pub(crate) struct FileJob {
+    /// Output directory the file is created through
+    pub root: Arc<OutputRoot>,
+    /// Where the data is written
+    pub write_path: PathBuf,
+    /// Where the file ends up, renamed from `write_path` if that differs
+    pub final_path: PathBuf,
+    /// Unix mode recorded for the entry
+    pub mode: Option<u32>,
+    /// Modification time to give the file, in seconds since the Unix epoch
+    pub modified: Option<u64>,
+    /// Extended attributes to restore, as name and value
+    pub xattrs: Vec<(String, Vec<u8>)>,
+    /// User and group IDs to give the file
+    pub owner: Option<(u32, u32)>,
+    /// Bits cleared from `mode`, read before the extraction started
+    pub umask: u32,
+    /// Whether to write around the page cache
+    pub uncached: bool,
+    /// Size of the buffer the file is written through
+    pub buffer_size: usize,
+    /// Whether to leave runs of zeros as holes
+    pub sparse: bool,
+    /// Size the archive records for the file, if it's known exactly
+    pub size: Option<u64>,
+}
+
+impl FileJob {
+    /// Create the file at the write path, reserving space for its recorded
+    /// size unless it's written sparse.
+    pub fn create(&self) -> io::Result<OutputFile> {
+        if self.sparse {
+            return OutputFile::create_sparse(&self.root, &self.write_path, self.buffer_size);
+        }
+        let mut file = OutputFile::create(
+            &self.root,
+            &self.write_path,
+            self.uncached,
+            self.buffer_size,
+        )?;
+        if let Some(size) = self.size {
+            if let Err(e) = file.preallocate(size) {
+                drop(file);
+                self.discard(true);
+                return Err(e);
+            }
+        }
+        Ok(file)
+    }
+
+    /// Delete the incomplete file, if it was being written under a temporary
+    /// name or `always` is set.
+    pub fn discard(&self, always: bool) {
+        if always || self.write_path != self.final_path {
+            let _ = self.root.remove_file(&self.write_path);
+        }
+    }
+}
+
+enum Op {
+    /// Create the next file
+    Start(FileJob),
+    /// Append to the current file
+    Data(Vec<u8>),
+    /// Close the current file and move it into place
+    Finish,
+    /// Stop writing the current file, deleting it if `remove` is set or it was
+    /// written under a temporary name
+    Abort { remove: bool },
+}
+
+/// The decoding side of the pipeline.
+pub(crate) struct Writer<'scope> {
+    ops: Option<SyncSender<Op>>,
+    /// One message for every file the writer is done with, in queue order
+    done: Receiver<()>,
+    /// Requested and final paths of the queued files the writer isn't done
+    /// with, oldest first
+    in_flight: VecDeque<(PathBuf, PathBuf)>,
+    thread: Option<ScopedJoinHandle<'scope, Result<(), ExtractError>>>,
+}
+
+impl<'scope> Writer<'scope> {
+    /// Start a writer thread in `scope` that queues up to `queue_depth` chunks
+    /// and calls `finish` on every file once its data is written.
+    pub fn spawn<F>(scope: &'scope Scope<'scope, '_>, queue_depth: usize, finish: F) -> Self
+    where
+        F: Fn(&FileJob) -> Result<(), ExtractError> + Send + 'scope,
+    {
+        let (ops, queued) = mpsc::sync_channel(queue_depth);
+        let (done_tx, done) = mpsc::channel();
+        let thread = scope.spawn(move || write_files(queued, done_tx, finish));
+
+        Self {
+            ops: Some(ops),
+            done,
+            in_flight: VecDeque::new(),
+            thread: Some(thread),
+        }
+    }
+
+    /// Wait until no queued file is still to be written at or requested as
+    /// `path`, so that checks for an existing file there see the earlier one.
+    pub fn wait_for(&mut self, path: &Path) -> Result<(), ExtractError> {
+        while self.done.try_recv().is_ok() {
+            self.in_flight.pop_front();
+        }
+        while self
+            .in_flight
+            .iter()
+            .any(|(requested, target)| requested == path || target == path)
+        {
+            self.done.recv().map_err(|_| stopped())?;
+            self.in_flight.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Queue `data` to be written as the file `job`, requested at `requested`
+    /// before overwrite handling. Returns the number of bytes queued.
+    ///
+    /// Reading stops after `max_len` bytes; a file that is any longer is
+    /// discarded, and the returned length exceeds `max_len`. Every
+    /// [`PROGRESS_STEP`] bytes `tick` is called with the number queued so far,
+    /// and the file is abandoned with [`ExtractError::Cancelled`] if it returns
+    /// false.
+    pub fn write_file(
+        &mut self,
+        requested: &Path,
+        job: FileJob,
+        data: &mut dyn Read,
+        max_len: Option<u64>,
+        tick: &mut dyn FnMut(u64) -> bool,
+    ) -> Result<u64, ExtractError> {
+        let limit = max_len.map_or(u64::MAX, |max| max.saturating_add(1));
+        let mut data = data.take(limit);
+
+        self.in_flight
+            .push_back((requested.to_path_buf(), job.final_path.clone()));
+        self.send(Op::Start(job))?;
+
+        let mut queued = 0u64;
+        let mut next_tick = PROGRESS_STEP;
+        loop {
+            let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+            let read = data
+                .by_ref()
+                .take(CHUNK_SIZE as u64)
+                .read_to_end(&mut chunk);
+            queued += chunk.len() as u64;
+
+            match read {
+                Ok(0) => break,
+                Ok(_) => self.send(Op::Data(chunk))?,
+                Err(e) => {
+                    // Whatever was read before the error is still written
+                    if !chunk.is_empty() {
+                        self.send(Op::Data(chunk))?;
+                    }
+                    self.send(Op::Abort { remove: false })?;
+                    return Err(e.into());
+                }
+            }
+
+            if queued >= next_tick {
+                next_tick = queued + PROGRESS_STEP;
+                if !tick(queued) {
+                    self.send(Op::Abort { remove: false })?;
+                    return Err(ExtractError::Cancelled);
+                }
+            }
+        }
+
+        if max_len.is_some_and(|max| queued > max) {
+            self.send(Op::Abort { remove: true })?;
+        } else {
+            self.send(Op::Finish)?;
+        }
+        Ok(queued)
+    }
+
+    /// Wait for the writer thread to write out everything queued, returning the
+    /// error it stopped with, if any.
+    pub fn finish(mut self) -> Result<(), ExtractError> {
+        drop(self.ops.take());
+        match self.thread.take() {
+            Some(thread) => thread
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic)),
+            None => Ok(()),
+        }
+    }
+
+    fn send(&self, op: Op) -> Result<(), ExtractError> {
+        let ops = self.ops.as_ref().expect("the writer is running");
+        ops.send(op).map_err(|_| stopped())
+    }
+}
+
+/// Error for the decoding side when the writer thread has stopped; the writer's
+/// own error is reported instead by [`Writer::finish`].
+fn stopped() -> ExtractError {
+    ExtractError::Io(io::Error::other("The writer thread stopped"))
+}
+
+/// Body of the writer thread: write files as their data arrives until the
+/// decoding side hangs up.
+fn write_files<F>(queued: Receiver<Op>, done: Sender<()>, finish: F) -> Result<(), ExtractError>
+where
+    F: Fn(&FileJob) -> Result<(), ExtractError>,
+{
+    let mut current: Option<(FileJob, OutputFile)> = None;
+
+    for op in queued {
+        let result = match op {
+            Op::Start(job) => job
+                .create()
+                .map(|file| current = Some((job, file)))
+                .map_err(ExtractError::from),
+            Op::Data(chunk) => match current.as_mut() {
+                Some((_, file)) => file.write_all(&chunk).map_err(ExtractError::from),
+                None => Ok(()),
+            },
+            Op::Finish => match current.take() {
+                Some((job, file)) => {
+                    let result = file.finish().map_err(ExtractError::from);
+                    let result = result.and_then(|()| finish(&job));
+                    if result.is_err() {
+                        job.discard(false);
+                    }
+                    let _ = done.send(());
+                    result
+                }
+                None => Ok(()),
+            },
+            Op::Abort { remove } => {
+                if let Some((job, file)) = current.take() {
+                    drop(file);
+                    job.discard(remove);
+                    let _ = done.send(());
+                }
+                Ok(())
+            }
+        };
+
+        if let Err(e) = result {
+            if let Some((job, file)) = current.take() {
+                drop(file);
+                job.discard(false);
+            }
+            return Err(e);
+        }
+    }
+
+    // The decoding side gave up partway through a file
+    if let Some((job, file)) = current.take() {
+        drop(file);
+        job.discard(false);
+    }
+    Ok(())
+}
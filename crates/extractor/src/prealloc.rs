@@ -0,0 +1,80 @@
+//! Reserving disk space for files before they're written.
+//!
+//! When an archive records an entry's size, the space is allocated up front:
+//! the filesystem can lay the file out in one piece, and a full disk fails the
+//! entry before any of it is decoded rather than halfway through. Linux uses
+//! `fallocate` and macOS `F_PREALLOCATE`, both leaving the file's length alone;
+//! elsewhere the file is extended with `set_len` and trimmed once written, in
+//! case the recorded size was too large.
+
+use std::fs::File;
+use std::io;
+
+/// Files smaller than this aren't worth a system call; they rarely fragment.
+pub(crate) const MIN_PREALLOCATE: u64 = 1024 * 1024;
+
+/// Reserve `len` bytes for `file`, returning whether its length was changed
+/// and must be trimmed to what's written. Only a lack of space is an error;
+/// filesystems that can't preallocate are written as usual.
+#[cfg(target_os = "linux")]
+pub(crate) fn reserve(file: &File, len: u64) -> io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    let Ok(len) = libc::off_t::try_from(len) else {
+        return Ok(false);
+    };
+    // SAFETY: the descriptor belongs to file, which is open
+    let result = unsafe { libc::fallocate(file.as_raw_fd(), libc::FALLOC_FL_KEEP_SIZE, 0, len) };
+    if result == 0 {
+        return Ok(false);
+    }
+    match io::Error::last_os_error() {
+        e if is_out_of_space(&e) => Err(e),
+        _ => Ok(false),
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) fn reserve(file: &File, len: u64) -> io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    let Ok(len) = libc::off_t::try_from(len) else {
+        return Ok(false);
+    };
+    let mut store = libc::fstore_t {
+        fst_flags: libc::F_ALLOCATECONTIG | libc::F_ALLOCATEALL,
+        fst_posmode: libc::F_PEOFPOSMODE,
+        fst_offset: 0,
+        fst_length: len,
+        fst_bytesalloc: 0,
+    };
+    // SAFETY: the descriptor belongs to file, which is open, and store is a
+    // valid fstore_t
+    let mut result = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_PREALLOCATE, &mut store) };
+    if result == -1 {
+        // Settle for space in several pieces
+        store.fst_flags = libc::F_ALLOCATEALL;
+        result = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_PREALLOCATE, &mut store) };
+    }
+    if result != -1 {
+        return Ok(false);
+    }
+    match io::Error::last_os_error() {
+        e if is_out_of_space(&e) => Err(e),
+        _ => Ok(false),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub(crate) fn reserve(file: &File, len: u64) -> io::Result<bool> {
+    file.set_len(len)?;
+    Ok(true)
+}
+
+#[cfg(unix)]
+fn is_out_of_space(e: &io::Error) -> bool {
+    matches!(
+        e.raw_os_error(),
+        Some(libc::ENOSPC) | Some(libc::EDQUOT) | Some(libc::EFBIG)
+    )
+}
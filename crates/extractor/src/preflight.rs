@@ -0,0 +1,235 @@
+//! Checks run against the destination before extracting.
+//!
+//! An extraction running out of disk space, or of inodes in the case of
+//! archives with hundreds of thousands of small files, fails deep into its
+//! work. These helpers compare the size of an archive's files and the number of
+//! files and directories it will create with what the destination can still
+//! hold, and notice destinations such as `/tmp` that are kept in memory.
+
+use crate::error::ExtractError;
+use crate::types::{ExtractionPlan, ResourceHint};
+use std::path::Path;
+
+/// Entry count from which an archive is reported as having many small files.
+pub const MANY_ENTRIES_THRESHOLD: u64 = 100_000;
+
+/// Number of inodes still available to unprivileged users on the filesystem
+/// holding `path`.
+///
+/// Returns `None` when the platform has no such limit or the filesystem doesn't
+/// report one (btrfs, for instance, allocates inodes dynamically).
+#[cfg(unix)]
+pub fn free_inodes(path: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+
+    // SAFETY: c_path is NUL-terminated and stat is only read after success
+    let stat = unsafe {
+        if libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return None;
+        }
+        stat.assume_init()
+    };
+
+    // The field types differ between platforms
+    #[allow(clippy::unnecessary_cast)]
+    let (total, available) = (stat.f_files as u64, stat.f_favail as u64);
+    (total > 0).then_some(available)
+}
+
+#[cfg(not(unix))]
+pub fn free_inodes(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Bytes still available to unprivileged users on the filesystem holding
+/// `path`.
+///
+/// Returns `None` on platforms other than Unix.
+#[cfg(unix)]
+pub fn free_space(path: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+
+    // SAFETY: c_path is NUL-terminated and stat is only read after success
+    let stat = unsafe {
+        if libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return None;
+        }
+        stat.assume_init()
+    };
+
+    // The field types differ between platforms
+    #[allow(clippy::unnecessary_cast)]
+    let available = (stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64);
+    Some(available)
+}
+
+#[cfg(not(unix))]
+pub fn free_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Check that the filesystem holding `output_dir` has room for `needed` more
+/// bytes.
+///
+/// Fails with `ExtractError::InsufficientSpace` when it hasn't. Space taken up
+/// beyond the files' sizes, by filesystem blocks and metadata, isn't counted.
+pub fn check_free_space(output_dir: &Path, needed: u64) -> Result<(), ExtractError> {
+    let existing = output_dir.ancestors().find(|p| p.exists());
+    match existing.and_then(free_space) {
+        Some(available) if needed > available => {
+            Err(ExtractError::InsufficientSpace { needed, available })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Check that the filesystem holding `output_dir` can create `needed` more
+/// files and directories.
+///
+/// Fails with `ExtractError::InsufficientInodes` when it can't. Otherwise returns
+/// a warning when the archive has many entries, so callers can tell the user
+/// why the extraction may take a while.
+pub fn check_inodes(output_dir: &Path, needed: u64) -> Result<Option<String>, ExtractError> {
+    // The output directory may not exist yet; its nearest existing ancestor is
+    // on the same filesystem in the common case
+    let existing = output_dir.ancestors().find(|p| p.exists());
+
+    if let Some(available) = existing.and_then(free_inodes) {
+        if needed > available {
+            return Err(ExtractError::InsufficientInodes { needed, available });
+        }
+    }
+
+    if needed >= MANY_ENTRIES_THRESHOLD {
+        return Ok(Some(format!(
+            "Archive creates {} files and folders; extraction may be slow",
+            needed
+        )));
+    }
+
+    Ok(None)
+}
+
+/// Free space of the RAM-backed filesystem (tmpfs, as used for `/dev/shm` and
+/// often `/tmp`) holding `path`.
+///
+/// Returns `None` when `path` isn't on one, or on platforms other than Linux.
+#[cfg(target_os = "linux")]
+pub fn memory_backed_space(path: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<libc::statfs>::uninit();
+
+    // SAFETY: c_path is NUL-terminated and stat is only read after success
+    let stat = unsafe {
+        if libc::statfs(c_path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return None;
+        }
+        stat.assume_init()
+    };
+
+    // The field types differ between platforms
+    #[allow(clippy::unnecessary_cast)]
+    let is_tmpfs = stat.f_type as i64 == libc::TMPFS_MAGIC as i64;
+    #[allow(clippy::unnecessary_cast)]
+    let available = (stat.f_bavail as u64).saturating_mul(stat.f_bsize as u64);
+    is_tmpfs.then_some(available)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn memory_backed_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Warn when `output_dir` is on a RAM-backed filesystem with less free space
+/// than the `needed` bytes, where extracting would eat into memory until the
+/// filesystem fills up.
+pub fn check_memory_backed(output_dir: &Path, needed: u64) -> Option<String> {
+    let existing = output_dir.ancestors().find(|p| p.exists())?;
+    let available = memory_backed_space(existing)?;
+    (needed > available).then(|| {
+        format!(
+            "{} is on a RAM-backed filesystem with {:.1} MB free, but the archive needs about {:.1} MB",
+            output_dir.display(),
+            available as f64 / 1_048_576.0,
+            needed as f64 / 1_048_576.0
+        )
+    })
+}
+
+/// Warnings about extracting an archive needing `resources` into
+/// `output_dir`.
+pub fn plan_for(output_dir: &Path, resources: ResourceHint) -> ExtractionPlan {
+    let warnings = resources
+        .output_space_bytes
+        .and_then(|needed| check_memory_backed(output_dir, needed))
+        .into_iter()
+        .collect();
+    ExtractionPlan {
+        resources,
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_inodes() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert_eq!(check_inodes(dir.path(), 10).unwrap(), None);
+        assert!(check_inodes(&dir.path().join("missing/nested"), 10).is_ok());
+
+        if let Some(available) = free_inodes(dir.path()) {
+            assert!(matches!(
+                check_inodes(dir.path(), available + 1),
+                Err(ExtractError::InsufficientInodes { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_check_free_space() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(check_free_space(dir.path(), 1).is_ok());
+        assert!(check_free_space(&dir.path().join("missing/nested"), 1).is_ok());
+
+        if free_space(dir.path()).is_some() {
+            assert!(matches!(
+                check_free_space(dir.path(), u64::MAX),
+                Err(ExtractError::InsufficientSpace {
+                    needed: u64::MAX,
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_check_memory_backed() {
+        let dir = tempfile::tempdir().unwrap();
+        if memory_backed_space(dir.path()).is_none() {
+            assert_eq!(check_memory_backed(dir.path(), u64::MAX), None);
+        }
+
+        // /dev/shm is tmpfs wherever it exists
+        let shm = Path::new("/dev/shm");
+        if let Some(available) = memory_backed_space(shm) {
+            assert_eq!(check_memory_backed(shm, available), None);
+            assert!(check_memory_backed(&shm.join("missing"), u64::MAX).is_some());
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! The stable surface of the library.
+//!
+//! Everything here follows semantic versioning: it is only removed or changed
+//! in breaking ways with a new major version, so code written against the
+//! prelude keeps compiling across minor releases. Build [`ExtractOptions`]
+//! with [`ExtractOptions::builder`] to stay clear of options added later.
+//!
+//! ```rust,no_run
+//! use extractor::prelude::*;
+//! use std::path::Path;
+//! use std::sync::atomic::AtomicBool;
+//! use std::sync::Arc;
+//!
+//! # fn main() -> Result<(), ExtractError> {
+//! let options = ExtractOptions::builder()
+//!     .overwrite(OverwriteMode::Skip)
+//!     .on_entry(EntryObserver::new(|path, action| println!("{}: {}", path, action)))
+//!     .build();
+//! let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+//! let stats = extract(
+//!     Path::new("archive.zip"),
+//!     Path::new("output"),
+//!     &options,
+//!     &progress_cb,
+//!     Arc::new(AtomicBool::new(false)),
+//! )?;
+//! println!("Extracted {} files", stats.files_extracted);
+//! # Ok(())
+//! # }
+//! ```
+
+pub use crate::entry::{Archive, EntryReader};
+pub use crate::error::{ErrorHint, ExtractError, SecurityError};
+pub use crate::types::{
+    ArchiveEntry, ArchiveInfo, Compression, EntryAction, EntryObserver, ExtractOptions,
+    ExtractOptionsBuilder, ExtractStats, Format, OverwriteMode, ProgressEvent, ProgressListener,
+    ProgressPhase, ProgressSink, SymlinkPolicy, TimestampPolicy,
+};
+pub use crate::{extract, probe, ProbeProgressCallback, ProgressCallback};
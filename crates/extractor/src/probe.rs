@@ -1,10 +1,24 @@
 //! Archive probing functionality for reading metadata without extraction.
 
+use crate::charset::FilenameEncoding;
 use crate::error::ExtractError;
-use crate::types::{ArchiveEntry, ArchiveInfo};
+use crate::reader::{
+    open_stream_reader, ArchiveReader, CompressedReader, RarReader, SevenZipReader, TarReader,
+    Zip32Reader, ZipReader,
+};
+use crate::types::{
+    ArchiveEntry, ArchiveInfo, ArchiveSummary, Compression, EntrySort, ExtensionStats, Format,
+    ProbeOptions, ResourceHint, SortKey, PROBE_SCHEMA_VERSION,
+};
+use crate::zip32;
+use crate::ProbeProgressCallback;
+use std::cell::Cell;
 use std::fs::File;
-use std::io::Read;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
 use std::path::Path;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 /// Probe an archive to retrieve metadata without extracting.
 ///
@@ -29,6 +43,64 @@ use std::path::Path;
 /// - The format is unsupported or corrupted
 /// - The archive cannot be read
 pub fn probe_archive(path: &Path) -> std::result::Result<ArchiveInfo, ExtractError> {
+    probe_archive_with_progress(path, &|_, _| {}, Arc::new(AtomicBool::new(false)))
+}
+
+/// Probe an archive, reporting progress as entries are listed.
+///
+/// `progress_cb` is invoked after each entry with the number of archive bytes
+/// scanned so far and the number of entries found. Setting `cancel_flag` stops
+/// the scan and returns `ExtractError::Cancelled`.
+pub fn probe_archive_with_progress(
+    path: &Path,
+    progress_cb: &ProbeProgressCallback,
+    cancel_flag: Arc<AtomicBool>,
+) -> std::result::Result<ArchiveInfo, ExtractError> {
+    probe_archive_inner(path, None, false, None, progress_cb, cancel_flag)
+}
+
+/// Probe an archive whose entry list is encrypted, using `password` to read it.
+///
+/// Returns `ExtractError::InvalidPassword` if the entry list still cannot be read.
+pub fn probe_archive_with_password(
+    path: &Path,
+    password: &str,
+    progress_cb: &ProbeProgressCallback,
+    cancel_flag: Arc<AtomicBool>,
+) -> std::result::Result<ArchiveInfo, ExtractError> {
+    probe_archive_inner(path, Some(password), false, None, progress_cb, cancel_flag)
+}
+
+/// Probe an archive as `options` ask: with a password, listing concatenated
+/// tar archives, reporting progress or stopping when cancelled.
+pub fn probe_archive_with_options(
+    path: &Path,
+    options: &ProbeOptions,
+) -> std::result::Result<ArchiveInfo, ExtractError> {
+    let on_progress = options.on_progress.clone();
+    let progress_cb = move |bytes_scanned, entries_found| {
+        if let Some(progress) = &on_progress {
+            progress.report(bytes_scanned, entries_found);
+        }
+    };
+    probe_archive_inner(
+        path,
+        options.password.as_deref(),
+        options.read_concatenated_tar,
+        options.filename_encoding,
+        &progress_cb,
+        options.cancel_flag.clone().unwrap_or_default(),
+    )
+}
+
+fn probe_archive_inner(
+    path: &Path,
+    password: Option<&str>,
+    concatenated: bool,
+    filename_encoding: Option<FilenameEncoding>,
+    progress_cb: &ProbeProgressCallback,
+    cancel_flag: Arc<AtomicBool>,
+) -> std::result::Result<ArchiveInfo, ExtractError> {
     // Check if file exists
     if !path.exists() {
         return Err(ExtractError::NotFound(path.to_path_buf()));
@@ -38,243 +110,705 @@ pub fn probe_archive(path: &Path) -> std::result::Result<ArchiveInfo, ExtractErr
     let file_metadata = std::fs::metadata(path)?;
     let compressed_bytes = Some(file_metadata.len());
 
-    // Open the archive file
+    // Detect format from file extension and list the entries
+    let format = detect_format(path)?;
     let file = File::open(path)?;
+    let ctx = ProbeContext {
+        progress_cb,
+        cancel_flag,
+        password,
+        concatenated,
+        filename_encoding,
+    };
+    let listing = list_entries_by_format(format, file, path, &ctx);
+    let (entry_list, encrypted, header_encrypted) = analyze_archive(listing, &ctx)?;
+
+    Ok(archive_info(
+        format,
+        entry_list,
+        encrypted,
+        header_encrypted,
+        compressed_bytes,
+        || estimate_uncompressed_size(path).ok().flatten(),
+    ))
+}
+
+/// Number of entries and bytes an extraction will go through, as far as they
+/// can be known without decoding the archive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Totals {
+    pub entries: Option<u64>,
+    pub bytes: Option<u64>,
+}
+
+impl Totals {
+    /// Totals of the entries `info` lists
+    pub fn listed(info: &ArchiveInfo) -> Self {
+        let bytes = info
+            .entry_list
+            .iter()
+            .filter(|entry| !entry.is_directory)
+            .map(|entry| entry.size)
+            .sum();
+        Totals {
+            entries: Some(info.entries),
+            bytes: Some(bytes),
+        }
+    }
+}
+
+/// Work out the totals of an extraction of the archive at `path`, for
+/// reporting progress against the whole job.
+///
+/// Formats with an index of their entries (ZIP, 7-Zip, RAR) are listed as
+/// `options` ask, which only reads headers. Listing a tar
+/// archive means decompressing it, so only its size is estimated: from the
+/// compression trailer, or the file's size for plain tar, which both count
+/// the tar headers too. The size of a bzip2 compressed archive isn't known.
+pub(crate) fn scan_totals(
+    path: &Path,
+    format: Format,
+    options: &ProbeOptions,
+) -> std::result::Result<Totals, ExtractError> {
+    match format {
+        Format::Zip | Format::SevenZ | Format::Rar => {
+            let info = probe_archive_with_options(path, options)?;
+            Ok(Totals::listed(&info))
+        }
+        Format::Tar(Compression::None) => Ok(Totals {
+            entries: None,
+            bytes: Some(std::fs::metadata(path)?.len()),
+        }),
+        _ => Ok(Totals {
+            entries: format.is_single_file().then_some(1),
+            bytes: read_size_trailer(&mut File::open(path)?, format)?,
+        }),
+    }
+}
+
+/// Probe an archive held in memory, such as one received over IPC or
+/// downloaded by the app.
+///
+/// The format is detected from the data's signature. A single compressed
+/// file's entry is named "data", as there is no file name to derive it from.
+pub fn probe_bytes(data: &[u8]) -> std::result::Result<ArchiveInfo, ExtractError> {
+    let format = crate::carve::stream_format(&mut Cursor::new(data))?;
+    let ctx = ProbeContext {
+        progress_cb: &|_, _| {},
+        cancel_flag: Arc::new(AtomicBool::new(false)),
+        password: None,
+        concatenated: false,
+        filename_encoding: None,
+    };
+    let listing = open_stream_reader(Cursor::new(data), format, None)
+        .map_err(Into::into)
+        .and_then(|mut reader| collect_entries(reader.as_mut(), &ctx, &|_| data.len() as u64));
+    let (entry_list, encrypted, header_encrypted) = analyze_archive(listing, &ctx)?;
 
-    // Detect format and analyze entries
-    let (format, entry_list, encrypted) = analyze_archive(file, path)?;
+    Ok(archive_info(
+        format,
+        entry_list,
+        encrypted,
+        header_encrypted,
+        Some(data.len() as u64),
+        || {
+            read_size_trailer(&mut Cursor::new(data), format)
+                .ok()
+                .flatten()
+        },
+    ))
+}
 
+/// Assemble the probe result from an archive's listed entries.
+///
+/// `estimate_size` is only consulted when no entries could be listed, to fall
+/// back to the compression trailer or index.
+fn archive_info(
+    format: Format,
+    entry_list: Vec<ArchiveEntry>,
+    encrypted: bool,
+    header_encrypted: bool,
+    compressed_bytes: Option<u64>,
+    estimate_size: impl FnOnce() -> Option<u64>,
+) -> ArchiveInfo {
     // Calculate statistics from entries
     let entries = entry_list.len() as u64;
-    let uncompressed_estimate = if !entry_list.is_empty() {
+    let mut uncompressed_estimate = if !entry_list.is_empty() {
         Some(entry_list.iter().map(|e| e.size).sum())
     } else {
         None
     };
 
-    Ok(ArchiveInfo {
+    // Entries could not be listed; fall back to the compression trailer or index
+    let mut uncompressed_is_estimate = false;
+    if uncompressed_estimate.is_none() {
+        if let Some(size) = estimate_size() {
+            uncompressed_estimate = Some(size);
+            uncompressed_is_estimate = true;
+        }
+    }
+
+    let single_root = detect_single_root(&entry_list);
+    let is_tarbomb = detect_tarbomb(&entry_list);
+    let summary = summarize_entries(&entry_list);
+    let mut resources = estimate_resources(uncompressed_estimate);
+    resources.inodes_needed =
+        Some(summary.file_count + summary.directory_count).filter(|&count| count > 0);
+
+    ArchiveInfo {
+        schema_version: PROBE_SCHEMA_VERSION,
         format,
         entries,
         compressed_bytes,
         uncompressed_estimate,
+        uncompressed_is_estimate,
         encrypted,
+        header_encrypted,
+        single_root,
+        is_tarbomb,
+        summary,
+        resources,
+        integrity: None,
+        checksum: None,
         entry_list,
-    })
+    }
 }
 
-/// Analyze archive contents to extract metadata.
-fn analyze_archive(
-    file: File,
-    path: &Path,
-) -> std::result::Result<(String, Vec<ArchiveEntry>, bool), ExtractError> {
-    // Detect format from file extension
+/// Estimate the uncompressed size of a gzip or xz compressed file without
+/// decompressing it.
+///
+/// For gzip (`TAR.GZ`, `GZIP`) this reads the ISIZE trailer, which only covers the
+/// last member and wraps at 4 GiB. For xz (`TAR.XZ`, `XZ`) it sums the block sizes
+/// recorded in each stream's index, which is exact. Returns `None` for other
+/// formats or when the trailer cannot be read.
+pub fn estimate_uncompressed_size(path: &Path) -> std::result::Result<Option<u64>, ExtractError> {
+    if !path.exists() {
+        return Err(ExtractError::NotFound(path.to_path_buf()));
+    }
+
     let format = detect_format(path)?;
+    Ok(read_size_trailer(&mut File::open(path)?, format)?)
+}
 
-    // List entries based on format
-    match list_entries_by_format(&format, file, path) {
-        Ok((entries, encrypted)) => Ok((format, entries, encrypted)),
-        Err(e) => {
-            // If we can't list files, it might be corrupted or password-protected
-            let error_msg = e.to_string().to_lowercase();
+/// Read the uncompressed size recorded by the compression of `format`, if any.
+fn read_size_trailer<R: Read + Seek>(reader: &mut R, format: Format) -> io::Result<Option<u64>> {
+    match format.compression() {
+        Compression::Gzip => read_gzip_isize(reader),
+        Compression::Xz => read_xz_index_size(reader),
+        _ => Ok(None),
+    }
+}
 
-            if error_msg.contains("password") || error_msg.contains("encrypted") {
-                // Archive is likely password-protected
-                Ok((format, Vec::new(), true))
-            } else {
-                // Archive is likely corrupted or unsupported
-                // Return empty list rather than failing
-                Ok((format, Vec::new(), false))
-            }
-        }
+/// Read the ISIZE field (uncompressed size modulo 2^32) from a gzip trailer.
+fn read_gzip_isize<R: Read + Seek>(reader: &mut R) -> io::Result<Option<u64>> {
+    // Smallest gzip member: 10-byte header + empty deflate block + 8-byte trailer
+    let len = reader.seek(SeekFrom::End(0))?;
+    if len < 20 {
+        return Ok(None);
+    }
+
+    let mut magic = [0u8; 2];
+    reader.seek(SeekFrom::Start(0))?;
+    reader.read_exact(&mut magic)?;
+    if magic != [0x1f, 0x8b] {
+        return Ok(None);
     }
+
+    let mut isize = [0u8; 4];
+    reader.seek(SeekFrom::End(-4))?;
+    reader.read_exact(&mut isize)?;
+
+    Ok(Some(u32::from_le_bytes(isize) as u64))
 }
 
-/// List entries based on archive format.
-fn list_entries_by_format(
-    format: &str,
-    file: File,
-    path: &Path,
-) -> std::result::Result<(Vec<ArchiveEntry>, bool), Box<dyn std::error::Error>> {
-    match format {
-        "ZIP" => list_zip_entries(file),
-        "TAR" | "TAR.GZ" | "TAR.BZ2" | "TAR.XZ" => list_tar_entries(file, format),
-        "GZIP" | "BZIP2" | "XZ" => list_compressed_file_entry(path),
-        "7Z" => list_7z_entries(path),
-        "RAR" => list_rar_entries(path),
-        _ => Err(format!("Unsupported format: {}", format).into()),
+/// Sum the uncompressed sizes recorded in the index of every xz stream, walking
+/// backwards from the end of the file.
+fn read_xz_index_size<R: Read + Seek>(reader: &mut R) -> io::Result<Option<u64>> {
+    const HEADER_LEN: u64 = 12;
+    const FOOTER_LEN: u64 = 12;
+
+    let mut end = reader.seek(SeekFrom::End(0))?;
+    let mut total = 0u64;
+
+    while end > 0 {
+        // Skip stream padding (groups of four null bytes)
+        if end < 4 {
+            return Ok(None);
+        }
+        let mut word = [0u8; 4];
+        reader.seek(SeekFrom::Start(end - 4))?;
+        reader.read_exact(&mut word)?;
+        if word == [0; 4] {
+            end -= 4;
+            continue;
+        }
+
+        if end < HEADER_LEN + FOOTER_LEN {
+            return Ok(None);
+        }
+        let mut footer = [0u8; FOOTER_LEN as usize];
+        reader.seek(SeekFrom::Start(end - FOOTER_LEN))?;
+        reader.read_exact(&mut footer)?;
+        if &footer[10..12] != b"YZ" {
+            return Ok(None);
+        }
+
+        let backward_size =
+            (u32::from_le_bytes([footer[4], footer[5], footer[6], footer[7]]) as u64 + 1) * 4;
+        let Some(index_start) = (end - FOOTER_LEN).checked_sub(backward_size) else {
+            return Ok(None);
+        };
+
+        let mut index = vec![0u8; backward_size as usize];
+        reader.seek(SeekFrom::Start(index_start))?;
+        reader.read_exact(&mut index)?;
+
+        // Index indicator, record count, then (unpadded, uncompressed) pairs
+        if index.first() != Some(&0) {
+            return Ok(None);
+        }
+        let mut pos = 1;
+        let Some(records) = read_xz_varint(&index, &mut pos) else {
+            return Ok(None);
+        };
+        let mut blocks_len = 0u64;
+        for _ in 0..records {
+            let (Some(unpadded), Some(uncompressed)) = (
+                read_xz_varint(&index, &mut pos),
+                read_xz_varint(&index, &mut pos),
+            ) else {
+                return Ok(None);
+            };
+            // Blocks are padded to a multiple of four bytes
+            blocks_len += (unpadded + 3) & !3;
+            total += uncompressed;
+        }
+
+        let Some(stream_start) = index_start
+            .checked_sub(blocks_len)
+            .and_then(|p| p.checked_sub(HEADER_LEN))
+        else {
+            return Ok(None);
+        };
+        end = stream_start;
     }
+
+    Ok(Some(total))
 }
 
-/// List entry for a single compressed file (gz, bz2, xz).
-fn list_compressed_file_entry(
-    path: &Path,
-) -> std::result::Result<(Vec<ArchiveEntry>, bool), Box<dyn std::error::Error>> {
-    // For single compressed files, we create a single entry representing the decompressed file
-    let output_filename = path
-        .file_stem()
-        .ok_or("Invalid filename")?
-        .to_string_lossy()
-        .to_string();
-    
-    // We can't easily determine the uncompressed size without decompressing,
-    // so we'll use the compressed size as an estimate
-    let file_metadata = std::fs::metadata(path)?;
-    let compressed_size = file_metadata.len();
-    
-    let entry = ArchiveEntry {
-        path: output_filename,
-        is_directory: false,
-        size: compressed_size, // Estimate - actual size may differ
-        compressed_size: Some(compressed_size),
-    };
-    
-    Ok((vec![entry], false))
+/// Decode one xz multibyte integer (7 bits per byte, little-endian).
+fn read_xz_varint(buf: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for i in 0..9 {
+        let byte = *buf.get(*pos)?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << (i * 7);
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
 }
 
-/// List entries in a ZIP archive.
-fn list_zip_entries(
-    file: File,
-) -> std::result::Result<(Vec<ArchiveEntry>, bool), Box<dyn std::error::Error>> {
-    let mut archive = zip::ZipArchive::new(file)?;
-    let mut entries = Vec::new();
-    let mut encrypted = false;
+/// Find the single top-level folder that contains every entry, if there is one.
+///
+/// Returns `None` for empty archives, archives with several top-level items, and
+/// archives whose only top-level item is a file.
+pub(crate) fn detect_single_root(entries: &[ArchiveEntry]) -> Option<String> {
+    let mut root: Option<&str> = None;
+    let mut root_is_dir = false;
+
+    for entry in entries {
+        let mut components = path_components(&entry.path);
 
-    for i in 0..archive.len() {
-        let entry = archive.by_index(i)?;
+        let first = match components.next() {
+            Some(first) => first,
+            None => continue,
+        };
 
-        // Check if any entry is encrypted
-        if entry.encrypted() {
-            encrypted = true;
+        match root {
+            None => root = Some(first),
+            Some(existing) if existing != first => return None,
+            _ => {}
         }
 
-        entries.push(ArchiveEntry {
-            path: entry.name().to_string(),
-            is_directory: entry.is_dir(),
-            size: entry.size(),
-            compressed_size: Some(entry.compressed_size()),
-        });
+        // The root is a folder if anything lives below it or it is listed as a directory
+        if components.next().is_some() || entry.is_directory {
+            root_is_dir = true;
+        }
     }
 
-    Ok((entries, encrypted))
+    if root_is_dir {
+        root.map(|r| r.to_string())
+    } else {
+        None
+    }
 }
 
-/// List entries in a TAR archive (with optional compression).
-fn list_tar_entries(
-    mut file: File,
-    format: &str,
-) -> std::result::Result<(Vec<ArchiveEntry>, bool), Box<dyn std::error::Error>> {
-    use bzip2::read::BzDecoder;
-    use flate2::read::GzDecoder;
-    use lzma_rs::xz_decompress;
-    use std::io::BufReader;
+/// Check whether extracting the entries would scatter several items directly
+/// into the output directory (a "tarbomb").
+pub(crate) fn detect_tarbomb(entries: &[ArchiveEntry]) -> bool {
+    let mut top_level = std::collections::HashSet::new();
 
-    let mut entries = Vec::new();
+    for entry in entries {
+        if let Some(first) = path_components(&entry.path).next() {
+            top_level.insert(first);
+            if top_level.len() > 1 {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+/// Working memory assumed for formats that are extracted as a stream (decoder
+/// state plus copy buffers; bzip2 blocks and LZMA dictionaries dominate).
+const STREAMING_MEMORY_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Estimate the memory and disk space needed to extract an archive with the
+/// given uncompressed size.
+pub(crate) fn estimate_resources(uncompressed: Option<u64>) -> ResourceHint {
+    ResourceHint {
+        // Every format, XZ included, is decoded as a stream, so memory is
+        // bounded by the decoder's window rather than the archive's size
+        peak_memory_bytes: Some(STREAMING_MEMORY_BYTES),
+        // Every format is streamed straight into place
+        temp_space_bytes: Some(0),
+        output_space_bytes: uncompressed,
+        inodes_needed: None,
+    }
+}
+
+/// Compute aggregate statistics (counts, largest file, compression ratio and
+/// per-extension breakdown) over an archive's entries.
+pub(crate) fn summarize_entries(entries: &[ArchiveEntry]) -> ArchiveSummary {
+    let mut summary = ArchiveSummary::default();
+    let mut directories = std::collections::HashSet::new();
+    let mut extensions: std::collections::HashMap<String, ExtensionStats> =
+        std::collections::HashMap::new();
+    let mut ratio_compressed = 0u64;
+    let mut ratio_uncompressed = 0u64;
 
-    // Wrap the file reader based on compression format
-    let reader: Box<dyn Read> = match format {
-        "TAR.GZ" => Box::new(GzDecoder::new(BufReader::new(file))),
-        "TAR.BZ2" => Box::new(BzDecoder::new(BufReader::new(file))),
-        "TAR.XZ" => {
-            // lzma-rs requires decompressing to a buffer first
-            let mut compressed = Vec::new();
-            file.read_to_end(&mut compressed)?;
-            let mut decompressed = Vec::new();
-            xz_decompress(&mut compressed.as_slice(), &mut decompressed)?;
-            Box::new(std::io::Cursor::new(decompressed))
+    for entry in entries {
+        let components: Vec<&str> = path_components(&entry.path).collect();
+        if components.is_empty() {
+            continue;
         }
-        _ => Box::new(BufReader::new(file)),
-    };
 
-    let mut archive = tar::Archive::new(reader);
+        // Every parent folder counts as a directory, even without its own entry
+        let dir_depth = if entry.is_directory {
+            components.len()
+        } else {
+            components.len() - 1
+        };
+        for depth in 1..=dir_depth {
+            directories.insert(components[..depth].join("/"));
+        }
 
-    for entry_result in archive.entries()? {
-        let entry = entry_result?;
-        let header = entry.header();
+        if entry.is_directory {
+            continue;
+        }
 
-        let path = entry.path()?.to_string_lossy().to_string();
-        let is_directory = header.entry_type().is_dir();
-        let size = header.size()?;
+        summary.file_count += 1;
 
-        entries.push(ArchiveEntry {
-            path,
-            is_directory,
-            size,
-            compressed_size: None, // TAR doesn't store per-file compressed sizes
-        });
+        if summary
+            .largest_entry
+            .as_ref()
+            .is_none_or(|largest| entry.size > largest.size)
+        {
+            summary.largest_entry = Some(entry.clone());
+        }
+
+        if let Some(compressed) = entry.compressed_size {
+            ratio_compressed += compressed;
+            ratio_uncompressed += entry.size;
+        }
+
+        let name = components[components.len() - 1];
+        let extension = match name.rsplit_once('.') {
+            Some((stem, ext)) if !stem.is_empty() => ext.to_lowercase(),
+            _ => String::new(),
+        };
+        let stats = extensions
+            .entry(extension.clone())
+            .or_insert_with(|| ExtensionStats {
+                extension,
+                count: 0,
+                total_size: 0,
+            });
+        stats.count += 1;
+        stats.total_size += entry.size;
+    }
+
+    summary.directory_count = directories.len() as u64;
+
+    if ratio_uncompressed > 0 {
+        summary.average_compression_ratio =
+            Some(ratio_compressed as f64 / ratio_uncompressed as f64);
+    }
+
+    summary.extensions = extensions.into_values().collect();
+    summary.extensions.sort_by(|a, b| {
+        b.total_size
+            .cmp(&a.total_size)
+            .then_with(|| a.extension.cmp(&b.extension))
+    });
+
+    summary
+}
+
+/// Sort entries by the requested key; ties are broken by path and entries
+/// without a modification time always come last.
+pub fn sort_entries(entries: &mut [ArchiveEntry], sort: EntrySort) {
+    entries.sort_by(|a, b| {
+        let ordering = match sort.key {
+            SortKey::Path => a.path.cmp(&b.path),
+            SortKey::Size => a.size.cmp(&b.size),
+            SortKey::Modified => match (a.modified, b.modified) {
+                (Some(a_time), Some(b_time)) => a_time.cmp(&b_time),
+                (Some(_), None) => return std::cmp::Ordering::Less,
+                (None, Some(_)) => return std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            },
+        };
+        let ordering = if sort.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        ordering.then_with(|| a.path.cmp(&b.path))
+    });
+}
+
+/// Convert a UTC calendar date and time to seconds since the Unix epoch.
+///
+/// Returns `None` for dates before 1970 or out-of-range fields.
+pub(crate) fn civil_to_unix(
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+) -> Option<u64> {
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
     }
 
-    Ok((entries, false)) // TAR archives are not encrypted
+    // Days from civil, counting years from March so the leap day comes last
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    let secs = days * 86_400 + hour as i64 * 3_600 + minute as i64 * 60 + second as i64;
+    u64::try_from(secs).ok()
+}
+
+/// Convert a ZIP entry timestamp to seconds since the Unix epoch.
+pub(crate) fn zip_time_to_unix(t: zip::DateTime) -> Option<u64> {
+    civil_to_unix(
+        t.year() as i64,
+        t.month() as u32,
+        t.day() as u32,
+        t.hour() as u32,
+        t.minute() as u32,
+        t.second() as u32,
+    )
+}
+
+/// Convert an MS-DOS packed date/time (as stored by ZIP and RAR) to Unix seconds.
+pub(crate) fn dos_to_unix(dos: u32) -> Option<u64> {
+    let date = dos >> 16;
+    let time = dos & 0xffff;
+
+    civil_to_unix(
+        1980 + (date >> 9) as i64,
+        (date >> 5) & 0x0f,
+        date & 0x1f,
+        time >> 11,
+        (time >> 5) & 0x3f,
+        (time & 0x1f) * 2,
+    )
+}
+
+/// Convert a Windows NT timestamp (100ns ticks since 1601) to Unix seconds.
+pub(crate) fn nt_to_unix(ticks: u64) -> Option<u64> {
+    const NT_TO_UNIX_SECS: u64 = 11_644_473_600;
+    (ticks / 10_000_000).checked_sub(NT_TO_UNIX_SECS)
+}
+
+/// Split an archive entry path into its meaningful components.
+fn path_components(path: &str) -> impl Iterator<Item = &str> {
+    path.split(['/', '\\'])
+        .filter(|c| !c.is_empty() && *c != ".")
+}
+
+/// Interpret the result of listing an archive's entries.
+///
+/// Returns the entries, whether any entry is encrypted and whether the entry
+/// list itself is encrypted.
+fn analyze_archive(
+    listing: std::result::Result<(Vec<ArchiveEntry>, bool), Box<dyn std::error::Error>>,
+    ctx: &ProbeContext,
+) -> std::result::Result<(Vec<ArchiveEntry>, bool, bool), ExtractError> {
+    match listing {
+        Ok((entries, encrypted)) => Ok((entries, encrypted, false)),
+        Err(_) if ctx.is_cancelled() => Err(ExtractError::Cancelled),
+        Err(e) => {
+            // If we can't list files, it might be corrupted or password-protected
+            let error_msg = e.to_string().to_lowercase();
+
+            if error_msg.contains("password") || error_msg.contains("encrypted") {
+                // The entry list itself is encrypted
+                if ctx.password.is_some() {
+                    return Err(ExtractError::InvalidPassword);
+                }
+                Ok((Vec::new(), true, true))
+            } else {
+                // Archive is likely corrupted or unsupported
+                // Return empty list rather than failing
+                Ok((Vec::new(), false, false))
+            }
+        }
+    }
 }
 
-/// List entries in a 7-Zip archive.
-fn list_7z_entries(
+/// List entries based on archive format.
+fn list_entries_by_format(
+    format: Format,
+    mut file: File,
     path: &Path,
+    ctx: &ProbeContext,
 ) -> std::result::Result<(Vec<ArchiveEntry>, bool), Box<dyn std::error::Error>> {
-    use sevenz_rust2::{Password, SevenZReader};
-
-    let file = File::open(path)?;
     let file_len = file.metadata()?.len();
 
-    // Try to open without password first
-    let sz = SevenZReader::new(file, file_len, Password::empty())?;
-    let mut entries = Vec::new();
-    let encrypted = false; // If we got here, it's not encrypted or we can read metadata
+    // Count bytes read from the archive file (before decompression)
+    let bytes_read = Rc::new(Cell::new(0u64));
+    let counted = |file: File| -> Box<dyn Read> {
+        Box::new(CountingReader {
+            inner: file,
+            count: bytes_read.clone(),
+            cancel_flag: ctx.cancel_flag.clone(),
+        })
+    };
 
-    for entry in sz.archive().files.iter() {
-        let name = entry.name().to_string();
-        entries.push(ArchiveEntry {
-            path: name,
-            is_directory: entry.is_directory(),
-            size: entry.size(),
-            compressed_size: None, // 7z doesn't expose per-file compressed size easily
-        });
+    let mut reader: Box<dyn ArchiveReader> = match format {
+        Format::Zip if zip32::needs_locating(&mut file, zip32::WRAP)? => {
+            Box::new(Zip32Reader::new(file)?)
+        }
+        Format::Zip => Box::new(ZipReader::new(file, ctx.password)?),
+        Format::Tar(compression) => Box::new(TarReader::new(counted(file), compression)?),
+        Format::Gzip | Format::Bzip2 | Format::Xz => Box::new(CompressedReader::new(
+            path,
+            counted(file),
+            format.compression(),
+        )?),
+        Format::SevenZ => Box::new(SevenZipReader::new(file, ctx.password)?),
+        Format::Rar => Box::new(RarReader::new(path, ctx.password)),
+    };
+    reader.set_read_concatenated(ctx.concatenated);
+    if let Some(encoding) = ctx.filename_encoding {
+        reader.set_filename_encoding(encoding);
     }
 
-    Ok((entries, encrypted))
+    // Streamed formats report their position in the archive file. ZIP and 7z
+    // have their index in memory already, so report the packed sizes covered
+    // or the whole file; unrar reads the volumes itself, so only the entry
+    // count is known
+    collect_entries(reader.as_mut(), ctx, &|packed_bytes| match format {
+        Format::Zip => packed_bytes,
+        Format::SevenZ => file_len,
+        _ => bytes_read.get(),
+    })
 }
 
-/// List entries in a RAR archive.
-fn list_rar_entries(
-    path: &Path,
+/// List the entries of `reader`, reporting progress with the archive bytes
+/// `bytes_scanned` gives for the packed size of the entries listed so far.
+fn collect_entries(
+    reader: &mut dyn ArchiveReader,
+    ctx: &ProbeContext,
+    bytes_scanned: &dyn Fn(u64) -> u64,
 ) -> std::result::Result<(Vec<ArchiveEntry>, bool), Box<dyn std::error::Error>> {
-    use unrar::Archive;
-
-    // For multi-part archives, use as_first_part() which returns an Archive pointing to the first part
-    // If it's not a multi-part archive, as_first_part() returns self
-    let archive = Archive::new(path).as_first_part().open_for_listing()?;
     let mut entries = Vec::new();
     let mut encrypted = false;
-    let mut current = Some(archive);
-
-    while let Some(arch) = current {
-        match arch.read_header()? {
-            Some(header) => {
-                // Check if entry is encrypted
-                if header.entry().is_encrypted() {
-                    encrypted = true;
-                }
+    let mut packed_bytes = 0u64;
 
-                let entry_data = header.entry();
-                entries.push(ArchiveEntry {
-                    path: entry_data.filename.to_string_lossy().to_string(),
-                    is_directory: entry_data.is_directory(),
-                    size: entry_data.unpacked_size,
-                    compressed_size: None, // RAR API doesn't easily expose packed size in this version
-                });
+    for entry in reader.entries()? {
+        let entry = entry?;
+        encrypted |= entry.encrypted;
+        packed_bytes += entry.compressed_size.unwrap_or(0);
+        entries.push(ArchiveEntry::from(&entry));
+        ctx.report(bytes_scanned(packed_bytes), entries.len() as u64)?;
+    }
 
-                current = Some(header.skip()?);
-            }
-            None => {
-                current = None;
-            }
+    Ok((entries, encrypted))
+}
+
+/// Progress reporting, cancellation and the reading options shared by the
+/// listing functions.
+struct ProbeContext<'a> {
+    progress_cb: &'a ProbeProgressCallback,
+    cancel_flag: Arc<AtomicBool>,
+    password: Option<&'a str>,
+    /// Whether to list concatenated tar archives past the first one's end
+    concatenated: bool,
+    /// Encoding of ZIP entry names not marked as UTF-8, if not detected
+    filename_encoding: Option<FilenameEncoding>,
+}
+
+impl ProbeContext<'_> {
+    fn is_cancelled(&self) -> bool {
+        self.cancel_flag.load(Ordering::Relaxed)
+    }
+
+    /// Report progress, failing if the probe has been cancelled.
+    fn report(
+        &self,
+        bytes_scanned: u64,
+        entries_found: u64,
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if self.is_cancelled() {
+            return Err(Box::new(ExtractError::Cancelled));
         }
+        (self.progress_cb)(bytes_scanned, entries_found);
+        Ok(())
     }
+}
 
-    Ok((entries, encrypted))
+/// Reader wrapper that counts the bytes consumed from the underlying reader.
+///
+/// Used to report scan progress for streamed formats, where the position in the
+/// compressed input is the only meaningful measure of how far along we are. Reads
+/// fail once the cancel flag is set so long entries can be abandoned mid-stream.
+struct CountingReader<R> {
+    inner: R,
+    count: Rc<Cell<u64>>,
+    cancel_flag: Arc<AtomicBool>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.cancel_flag.load(Ordering::Relaxed) {
+            return Err(io::Error::other("Probe cancelled"));
+        }
+        let n = self.inner.read(buf)?;
+        self.count.set(self.count.get() + n as u64);
+        Ok(n)
+    }
 }
 
 /// Detect archive format from file extension and magic bytes.
-pub(crate) fn detect_format(path: &Path) -> std::result::Result<String, ExtractError> {
+pub(crate) fn detect_format(path: &Path) -> std::result::Result<Format, ExtractError> {
     let extension = path
         .extension()
         .and_then(|e| e.to_str())
@@ -290,65 +824,65 @@ pub(crate) fn detect_format(path: &Path) -> std::result::Result<String, ExtractE
 
     // Handle multi-part RAR archives (.part1.rar, .part01.rar, .r00, .r01, etc.)
     if filename.contains(".part") && filename.ends_with(".rar") {
-        return Ok("RAR".to_string());
+        return Ok(Format::Rar);
     }
     if extension.starts_with('r') && extension.len() >= 2 {
         // Check if it's .r00, .r01, etc.
         if extension[1..].chars().all(|c| c.is_ascii_digit()) {
-            return Ok("RAR".to_string());
+            return Ok(Format::Rar);
         }
     }
 
     // Handle multi-part 7z archives (.7z.001, .7z.002, etc.)
     if filename.contains(".7z.") && extension.chars().all(|c| c.is_ascii_digit()) {
-        return Ok("7Z".to_string());
+        return Ok(Format::SevenZ);
     }
 
     // Handle multi-part ZIP archives (.zip.001, .zip.002, etc.)
     if filename.contains(".zip.") && extension.chars().all(|c| c.is_ascii_digit()) {
-        return Ok("ZIP".to_string());
+        return Ok(Format::Zip);
     }
 
     // Map extensions to format names
     let format = match extension.as_str() {
-        "zip" => "ZIP",
-        "7z" => "7Z",
-        "rar" => "RAR",
-        "tar" => "TAR",
+        "zip" => Format::Zip,
+        "7z" => Format::SevenZ,
+        "rar" => Format::Rar,
+        "tar" => Format::Tar(Compression::None),
         "gz" | "tgz" => {
             // Check if it's a tar.gz
             if let Some(stem) = path.file_stem() {
                 if stem.to_string_lossy().ends_with(".tar") {
-                    "TAR.GZ"
+                    Format::Tar(Compression::Gzip)
                 } else {
-                    "GZIP"
+                    Format::Gzip
                 }
             } else {
-                "GZIP"
+                Format::Gzip
             }
         }
         "bz2" | "tbz2" | "tbz" => {
             // Check if it's a tar.bz2
             if let Some(stem) = path.file_stem() {
                 if stem.to_string_lossy().ends_with(".tar") {
-                    "TAR.BZ2"
+                    Format::Tar(Compression::Bzip2)
                 } else {
-                    "BZIP2"
+                    Format::Bzip2
                 }
             } else {
-                "BZIP2"
+                Format::Bzip2
             }
         }
         "xz" | "txz" => {
             // Check if it's a tar.xz
             if let Some(stem) = path.file_stem() {
                 if stem.to_string_lossy().ends_with(".tar") {
-                    "TAR.XZ"
+                    Format::Tar(Compression::Xz)
                 } else {
-                    "XZ"
+                    Format::Xz
                 }
             } else {
-                "XZ"
+                Format::Xz
             }
         }
         _ => {
@@ -359,30 +893,54 @@ pub(crate) fn detect_format(path: &Path) -> std::result::Result<String, ExtractE
         }
     };
 
-    Ok(format.to_string())
+    Ok(format)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::safety::EntryType;
     use std::path::PathBuf;
 
     #[test]
     fn test_detect_format_zip() {
         let path = PathBuf::from("test.zip");
-        assert_eq!(detect_format(&path).unwrap(), "ZIP");
+        assert_eq!(detect_format(&path).unwrap(), Format::Zip);
     }
 
     #[test]
     fn test_detect_format_tar_gz() {
         let path = PathBuf::from("test.tar.gz");
-        assert_eq!(detect_format(&path).unwrap(), "TAR.GZ");
+        assert_eq!(
+            detect_format(&path).unwrap(),
+            Format::Tar(Compression::Gzip)
+        );
     }
 
     #[test]
     fn test_detect_format_7z() {
         let path = PathBuf::from("test.7z");
-        assert_eq!(detect_format(&path).unwrap(), "7Z");
+        assert_eq!(detect_format(&path).unwrap(), Format::SevenZ);
+    }
+
+    #[test]
+    fn test_format_names() {
+        for format in Format::ALL {
+            assert_eq!(format.to_string().parse::<Format>().unwrap(), format);
+        }
+        assert_eq!(
+            "tar.gz".parse::<Format>().unwrap(),
+            Format::Tar(Compression::Gzip)
+        );
+        assert!("CAB".parse::<Format>().is_err());
+
+        // Serialized as the plain format name
+        let json = serde_json::to_string(&Format::SevenZ).unwrap();
+        assert_eq!(json, "\"7Z\"");
+        assert_eq!(
+            serde_json::from_str::<Format>(&json).unwrap(),
+            Format::SevenZ
+        );
     }
 
     #[test]
@@ -391,6 +949,214 @@ mod tests {
         assert!(detect_format(&path).is_err());
     }
 
+    fn entry(path: &str, is_directory: bool) -> ArchiveEntry {
+        ArchiveEntry {
+            path: path.to_string(),
+            is_directory,
+            kind: if is_directory {
+                EntryType::Directory
+            } else {
+                EntryType::File
+            },
+            size: 0,
+            compressed_size: None,
+            modified: None,
+        }
+    }
+
+    #[test]
+    fn test_detect_single_root() {
+        // Everything inside one folder
+        let entries = vec![
+            entry("project/", true),
+            entry("project/src/main.rs", false),
+            entry("project/README.md", false),
+        ];
+        assert_eq!(detect_single_root(&entries), Some("project".to_string()));
+
+        // Folder implied by nested paths only, with "./" prefixes
+        let entries = vec![entry("./docs/a.txt", false), entry("./docs/b.txt", false)];
+        assert_eq!(detect_single_root(&entries), Some("docs".to_string()));
+
+        // Several top-level items
+        let entries = vec![entry("a.txt", false), entry("docs/b.txt", false)];
+        assert_eq!(detect_single_root(&entries), None);
+
+        // A single top-level file is not a root folder
+        let entries = vec![entry("a.txt", false)];
+        assert_eq!(detect_single_root(&entries), None);
+
+        // Empty archive
+        assert_eq!(detect_single_root(&[]), None);
+    }
+
+    #[test]
+    fn test_detect_tarbomb() {
+        // Several items at the top level
+        let entries = vec![
+            entry("a.txt", false),
+            entry("b.txt", false),
+            entry("lib/c.rs", false),
+        ];
+        assert!(detect_tarbomb(&entries));
+
+        // Everything inside one folder
+        let entries = vec![
+            entry("project/a.txt", false),
+            entry("./project/b.txt", false),
+        ];
+        assert!(!detect_tarbomb(&entries));
+
+        // A lone file is not a tarbomb
+        let entries = vec![entry("a.txt", false)];
+        assert!(!detect_tarbomb(&entries));
+        assert!(!detect_tarbomb(&[]));
+    }
+
+    #[test]
+    fn test_estimate_resources() {
+        let mib = 1024 * 1024;
+
+        // Memory doesn't grow with the archive, as everything is streamed
+        let small = estimate_resources(Some(100 * mib));
+        assert_eq!(small.peak_memory_bytes, Some(STREAMING_MEMORY_BYTES));
+        assert_eq!(small.temp_space_bytes, Some(0));
+        assert_eq!(small.output_space_bytes, Some(100 * mib));
+        let large = estimate_resources(Some(100 * 1024 * mib));
+        assert_eq!(large.peak_memory_bytes, Some(STREAMING_MEMORY_BYTES));
+
+        // Unknown uncompressed size leaves the output size unknown
+        let unknown = estimate_resources(None);
+        assert_eq!(unknown.peak_memory_bytes, Some(STREAMING_MEMORY_BYTES));
+        assert_eq!(unknown.output_space_bytes, None);
+    }
+
+    #[test]
+    fn test_summarize_entries() {
+        let sized = |path: &str, size: u64, compressed: Option<u64>| ArchiveEntry {
+            size,
+            compressed_size: compressed,
+            ..entry(path, false)
+        };
+        let entries = vec![
+            entry("project/", true),
+            sized("project/src/main.rs", 300, Some(100)),
+            sized("project/src/lib.RS", 100, Some(100)),
+            sized("project/docs/guide.md", 1000, None),
+            sized("project/.gitignore", 10, Some(10)),
+        ];
+
+        let summary = summarize_entries(&entries);
+        assert_eq!(summary.file_count, 4);
+        // project, project/src, project/docs
+        assert_eq!(summary.directory_count, 3);
+        assert_eq!(
+            summary.largest_entry.map(|e| e.path),
+            Some("project/docs/guide.md".to_string())
+        );
+        // (100 + 100 + 10) / (300 + 100 + 10); guide.md has no compressed size
+        let ratio = summary.average_compression_ratio.unwrap();
+        assert!((ratio - 210.0 / 410.0).abs() < 1e-9);
+
+        let extensions: Vec<(&str, u64, u64)> = summary
+            .extensions
+            .iter()
+            .map(|e| (e.extension.as_str(), e.count, e.total_size))
+            .collect();
+        assert_eq!(
+            extensions,
+            vec![("md", 1, 1000), ("rs", 2, 400), ("", 1, 10)]
+        );
+
+        let empty = summarize_entries(&[]);
+        assert_eq!(empty.file_count, 0);
+        assert!(empty.largest_entry.is_none());
+        assert!(empty.average_compression_ratio.is_none());
+    }
+
+    #[test]
+    fn test_read_compression_trailers() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::{Cursor, Write};
+
+        let data = vec![b'x'; 100_000];
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&data).unwrap();
+        let gz = encoder.finish().unwrap();
+        assert_eq!(
+            read_gzip_isize(&mut Cursor::new(gz)).unwrap(),
+            Some(100_000)
+        );
+
+        // Two concatenated xz streams, with stream padding after the first
+        let mut xz = Vec::new();
+        lzma_rs::xz_compress(&mut &data[..], &mut xz).unwrap();
+        xz.extend_from_slice(&[0; 4]);
+        lzma_rs::xz_compress(&mut &data[..500], &mut xz).unwrap();
+        assert_eq!(
+            read_xz_index_size(&mut Cursor::new(xz)).unwrap(),
+            Some(100_500)
+        );
+
+        // Not compressed at all
+        let plain = vec![b'a'; 64];
+        assert_eq!(
+            read_gzip_isize(&mut Cursor::new(plain.clone())).unwrap(),
+            None
+        );
+        assert_eq!(read_xz_index_size(&mut Cursor::new(plain)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_sort_entries() {
+        let timed = |path: &str, size: u64, modified: Option<u64>| ArchiveEntry {
+            modified,
+            size,
+            ..entry(path, false)
+        };
+        let mut entries = vec![
+            timed("b.txt", 10, Some(300)),
+            timed("a.txt", 30, None),
+            timed("c.txt", 20, Some(100)),
+            timed("d.txt", 20, Some(200)),
+        ];
+        let paths = |entries: &[ArchiveEntry]| -> Vec<String> {
+            entries.iter().map(|e| e.path.clone()).collect()
+        };
+
+        let by = |key, descending| EntrySort { key, descending };
+
+        sort_entries(&mut entries, by(SortKey::Path, false));
+        assert_eq!(paths(&entries), ["a.txt", "b.txt", "c.txt", "d.txt"]);
+
+        // Equal sizes fall back to path order
+        sort_entries(&mut entries, by(SortKey::Size, true));
+        assert_eq!(paths(&entries), ["a.txt", "c.txt", "d.txt", "b.txt"]);
+
+        // Missing times sort last in both directions
+        sort_entries(&mut entries, by(SortKey::Modified, false));
+        assert_eq!(paths(&entries), ["c.txt", "d.txt", "b.txt", "a.txt"]);
+        sort_entries(&mut entries, by(SortKey::Modified, true));
+        assert_eq!(paths(&entries), ["b.txt", "d.txt", "c.txt", "a.txt"]);
+    }
+
+    #[test]
+    fn test_timestamp_conversions() {
+        assert_eq!(civil_to_unix(1970, 1, 1, 0, 0, 0), Some(0));
+        assert_eq!(civil_to_unix(2000, 2, 29, 12, 30, 15), Some(951_827_415));
+        assert_eq!(civil_to_unix(1969, 12, 31, 23, 59, 59), None);
+        assert_eq!(civil_to_unix(2024, 13, 1, 0, 0, 0), None);
+
+        // 2024-03-15 10:20:30 as a DOS date/time
+        let dos = ((2024 - 1980) << 25) | (3 << 21) | (15 << 16) | (10 << 11) | (20 << 5) | 15;
+        assert_eq!(dos_to_unix(dos), Some(1_710_498_030));
+
+        assert_eq!(nt_to_unix(116_444_736_000_000_000), Some(0));
+        assert_eq!(nt_to_unix(0), None);
+    }
+
     #[test]
     fn test_probe_nonexistent_file() {
         let path = PathBuf::from("nonexistent.zip");
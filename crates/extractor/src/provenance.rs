@@ -0,0 +1,135 @@
+//! Provenance records written alongside extracted files.
+//!
+//! When enabled, extraction leaves a small JSON file in the output root that
+//! records where the files came from, so archives ingested in bulk can be traced
+//! back to their source, and a `SHA256SUMS` file listing the digest of every
+//! file it wrote, so the output can be checked or deduplicated later.
+
+use crate::error::ExtractError;
+use crate::types::{ExtractOptions, ExtractStats, OverwriteMode, SymlinkPolicy};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Name of the provenance file written into the output directory.
+pub const PROVENANCE_FILE_NAME: &str = ".extraction-info.json";
+
+/// Name of the file listing the SHA-256 of every extracted file, written into
+/// the output directory (see `ExtractOptions::write_sha256sums`).
+pub const SHA256SUMS_FILE_NAME: &str = "SHA256SUMS";
+
+/// Contents of the provenance file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractionInfo {
+    /// Absolute path of the source archive (first volume for multi-part archives)
+    pub source_archive: String,
+
+    /// Hex-encoded SHA-256 of the source archive
+    pub sha256: String,
+
+    /// Extraction time in seconds since the Unix epoch
+    pub extracted_at: u64,
+
+    /// Name and version of the extractor that produced the output
+    pub tool: String,
+
+    /// Options the extraction ran with (the password is never recorded)
+    pub options: ProvenanceOptions,
+
+    /// Number of files extracted
+    pub files_extracted: u64,
+
+    /// Total bytes written
+    pub bytes_written: u64,
+}
+
+/// Subset of `ExtractOptions` recorded in the provenance file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProvenanceOptions {
+    pub overwrite: OverwriteMode,
+    pub size_limit_bytes: Option<u64>,
+    pub strip_components: u32,
+    pub symlink_policy: SymlinkPolicy,
+    pub allow_hardlinks: bool,
+    pub detect_case_sensitivity: bool,
+    pub preserve_permissions: Option<bool>,
+    pub apply_umask: bool,
+    pub file_mode: Option<u32>,
+    pub dir_mode: Option<u32>,
+}
+
+impl From<&ExtractOptions> for ProvenanceOptions {
+    fn from(options: &ExtractOptions) -> Self {
+        Self {
+            overwrite: options.overwrite,
+            size_limit_bytes: options.size_limit_bytes,
+            strip_components: options.strip_components,
+            symlink_policy: options.symlink_policy,
+            allow_hardlinks: options.allow_hardlinks,
+            detect_case_sensitivity: options.detect_case_sensitivity,
+            preserve_permissions: options.preserve_permissions,
+            apply_umask: options.apply_umask,
+            file_mode: options.file_mode,
+            dir_mode: options.dir_mode,
+        }
+    }
+}
+
+/// Write the provenance file for a finished extraction into `output_dir`.
+pub(crate) fn write_provenance(
+    archive_path: &Path,
+    output_dir: &Path,
+    options: &ExtractOptions,
+    stats: &ExtractStats,
+) -> Result<(), ExtractError> {
+    let source_archive = fs::canonicalize(archive_path)
+        .unwrap_or_else(|_| archive_path.to_path_buf())
+        .to_string_lossy()
+        .to_string();
+
+    let extracted_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let info = ExtractionInfo {
+        source_archive,
+        sha256: sha256_file(archive_path)?,
+        extracted_at,
+        tool: format!("extractor {}", env!("CARGO_PKG_VERSION")),
+        options: options.into(),
+        files_extracted: stats.files_extracted,
+        bytes_written: stats.bytes_written,
+    };
+
+    let json = serde_json::to_string_pretty(&info).map_err(io::Error::other)?;
+    fs::write(output_dir.join(PROVENANCE_FILE_NAME), json)?;
+
+    Ok(())
+}
+
+/// Write the digests of a finished extraction into the `SHA256SUMS` file in
+/// `output_dir`.
+pub(crate) fn write_sha256sums(output_dir: &Path, stats: &ExtractStats) -> io::Result<()> {
+    fs::write(output_dir.join(SHA256SUMS_FILE_NAME), stats.sha256sums())
+}
+
+/// Hash a file with SHA-256, returning the lowercase hex digest.
+pub(crate) fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+
+    Ok(hex_digest(hasher))
+}
+
+/// Lowercase hex digest of the data given to `hasher`.
+pub(crate) fn hex_digest<D: Digest>(hasher: D) -> String {
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
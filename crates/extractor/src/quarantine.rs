@@ -0,0 +1,99 @@
+//! Gatekeeper quarantine tagging on macOS.
+//!
+//! Browsers and mail clients mark downloaded files with the
+//! `com.apple.quarantine` extended attribute, which makes Gatekeeper check apps
+//! and scripts before they first run. Archive Utility copies the archive's
+//! attribute to everything it extracts; without that, an app unpacked from a
+//! downloaded archive would run unchecked. These helpers do the same.
+
+use std::io;
+use std::path::Path;
+
+/// Name of the extended attribute holding quarantine information.
+pub const QUARANTINE_XATTR: &str = "com.apple.quarantine";
+
+/// Quarantine attribute value of `path`, if it has one.
+///
+/// Always `None` on platforms other than macOS.
+#[cfg(target_os = "macos")]
+pub fn read_quarantine(path: &Path) -> Option<Vec<u8>> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let c_name = CString::new(QUARANTINE_XATTR).ok()?;
+
+    // The value is a short "flags;timestamp;agent;uuid" string
+    let mut value = vec![0u8; 1024];
+    // SAFETY: both strings are NUL-terminated and value holds value.len() bytes
+    let len = unsafe {
+        libc::getxattr(
+            c_path.as_ptr(),
+            c_name.as_ptr(),
+            value.as_mut_ptr().cast(),
+            value.len(),
+            0,
+            0,
+        )
+    };
+    if len < 0 {
+        return None;
+    }
+    value.truncate(len as usize);
+    Some(value)
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn read_quarantine(_path: &Path) -> Option<Vec<u8>> {
+    None
+}
+
+/// Set the quarantine attribute of `path` (not following symlinks).
+///
+/// Does nothing on platforms other than macOS.
+#[cfg(target_os = "macos")]
+pub fn set_quarantine(path: &Path, value: &[u8]) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let c_name = CString::new(QUARANTINE_XATTR)?;
+
+    // SAFETY: both strings are NUL-terminated and value is valid for its length
+    let result = unsafe {
+        libc::setxattr(
+            c_path.as_ptr(),
+            c_name.as_ptr(),
+            value.as_ptr().cast(),
+            value.len(),
+            0,
+            libc::XATTR_NOFOLLOW,
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn set_quarantine(_path: &Path, _value: &[u8]) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(all(test, target_os = "macos"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quarantine_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("app.sh");
+        std::fs::write(&file, b"echo hi").unwrap();
+        assert_eq!(read_quarantine(&file), None);
+
+        let value = b"0083;65a1b2c3;Safari;";
+        set_quarantine(&file, value).unwrap();
+        assert_eq!(read_quarantine(&file).as_deref(), Some(&value[..]));
+    }
+}
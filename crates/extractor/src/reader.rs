@@ -0,0 +1,1423 @@
+//! Format-independent access to archive entries.
+//!
+//! Each supported format implements [`ArchiveReader`], which lists an archive's
+//! entries and streams their data. Probing, extraction and single-entry reads
+//! are all built on these readers, so the format-specific code lives here and
+//! nowhere else: supporting a new format means adding one implementation and a
+//! case in [`open_reader`] and [`open_stream_reader`].
+
+use crate::charset::FilenameEncoding;
+use crate::error::ExtractError;
+use crate::mmap::Mmap;
+use crate::safety::EntryType;
+use crate::types::{ArchiveEntry, Compression, ExtractOptions, Format, DEFAULT_BUFFER_SIZE};
+use crate::xz::XzDecoder;
+use crate::zip32::{self, LocatedEntry};
+use bzip2::read::BzDecoder;
+use flate2::read::GzDecoder;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// An archive entry as listed by an [`ArchiveReader`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Path of the entry as stored in the archive (not validated)
+    pub path: String,
+
+    /// Whether this entry is a directory
+    pub is_directory: bool,
+
+    /// What the entry is, from its type in tar or its mode in ZIP and 7-Zip
+    pub kind: EntryType,
+
+    /// Uncompressed size in bytes (an estimate for single compressed files)
+    pub size: u64,
+
+    /// Compressed size in bytes (if available)
+    pub compressed_size: Option<u64>,
+
+    /// CRC-32 of the uncompressed data, for formats recording one (ZIP,
+    /// 7-Zip, RAR)
+    pub crc32: Option<u32>,
+
+    /// Last modification time in seconds since the Unix epoch (if recorded)
+    pub modified: Option<u64>,
+
+    /// Unix permission bits (if recorded)
+    pub mode: Option<u32>,
+
+    /// Whether the entry's data is encrypted
+    pub encrypted: bool,
+
+    /// Extended attributes recorded for the entry, as name and value
+    pub xattrs: Vec<(String, Vec<u8>)>,
+
+    /// Unix user and group IDs recorded for the entry (if any)
+    pub owner: Option<(u32, u32)>,
+
+    /// Target of a symbolic link, for formats recording it apart from the
+    /// entry's data (tar). ZIP and 7-Zip store it as the data of an entry
+    /// whose mode marks a link.
+    pub symlink_target: Option<String>,
+
+    /// Path in the archive of the earlier entry a hard link entry links to
+    pub hardlink_target: Option<String>,
+}
+
+impl From<&Entry> for ArchiveEntry {
+    fn from(entry: &Entry) -> Self {
+        ArchiveEntry {
+            path: entry.path.clone(),
+            is_directory: entry.is_directory,
+            kind: entry.kind,
+            size: entry.size,
+            compressed_size: entry.compressed_size,
+            modified: entry.modified,
+        }
+    }
+}
+
+/// Access to the data of the entry being visited by
+/// [`ArchiveReader::read_entries`].
+pub trait EntryData {
+    /// Reader over the entry's uncompressed data.
+    fn reader(&mut self) -> Result<&mut dyn Read, ExtractError>;
+
+    /// Write the entry's data to a new file at `path`, returning the number of
+    /// bytes written. Formats that can write files more efficiently than by
+    /// copying from [`reader`](EntryData::reader) override this.
+    fn extract_to(&mut self, path: &Path) -> Result<u64, ExtractError> {
+        Ok(copy_to_new_file(self.reader()?, path)?)
+    }
+
+    /// Whether [`extract_to`](EntryData::extract_to) writes the file without
+    /// going through [`reader`](EntryData::reader), which may hold the whole
+    /// entry in memory. Such entries aren't handed to a writer thread.
+    fn writes_directly(&self) -> bool {
+        false
+    }
+
+    /// Position of the entry in archive order, for readers that can open an
+    /// [`EntryDecoder`] to decode it elsewhere.
+    fn index(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Decodes the entries of an archive on a thread of its own, addressed by
+/// their [`EntryData::index`].
+///
+/// Formats whose entries are compressed independently of each other (ZIP)
+/// provide these through [`ArchiveReader::open_decoder`], so that extraction
+/// can decode several entries at once.
+pub trait EntryDecoder: Send {
+    /// Write the data of the entry at `index` to `out`, returning the number of
+    /// bytes written.
+    fn copy_entry(&mut self, index: usize, out: &mut dyn Write) -> Result<u64, ExtractError>;
+}
+
+/// Callback receiving each entry and its data; returns `false` to stop reading.
+pub type EntryVisitor<'a> =
+    dyn FnMut(&Entry, &mut dyn EntryData) -> Result<bool, ExtractError> + 'a;
+
+/// Iterator over the entries of an archive.
+pub type Entries<'a> = Box<dyn Iterator<Item = Result<Entry, ExtractError>> + 'a>;
+
+/// Reads the entries of one archive format.
+///
+/// Streamed formats (tar, single compressed files, RAR) can only be read once,
+/// so each reader supports a single call to either method; open a new reader
+/// for another pass.
+pub trait ArchiveReader {
+    /// List the entries in archive order, reading as little file data as the
+    /// format allows.
+    fn entries(&mut self) -> Result<Entries<'_>, ExtractError>;
+
+    /// Walk the entries in archive order, handing each to `visit` together with
+    /// access to its data. Data that `visit` doesn't read is skipped.
+    fn read_entries(&mut self, visit: &mut EntryVisitor<'_>) -> Result<(), ExtractError>;
+
+    /// Whether the archive is a single compressed file, whose one entry is named
+    /// after the archive instead of being stored in it.
+    fn is_single_file(&self) -> bool {
+        false
+    }
+
+    /// Choose whether data after the archive's last readable entry ends the
+    /// archive with a warning (the default) or fails reading. Only tar
+    /// archives, whose end is marked by zero blocks, can have such data.
+    fn set_tolerate_trailing_data(&mut self, _tolerate: bool) {}
+
+    /// Choose whether reading goes on past the zero blocks that end a tar
+    /// archive, to pick up the members of tar archives concatenated after it
+    /// (as `tar -A` and appending tools produce). Other formats ignore this.
+    fn set_read_concatenated(&mut self, _concatenated: bool) {}
+
+    /// Decode the entry names the archive doesn't mark as UTF-8 with
+    /// `encoding` instead of the one detected from them. Only ZIP archives
+    /// store such names; other formats ignore this.
+    fn set_filename_encoding(&mut self, _encoding: FilenameEncoding) {}
+
+    /// Problems with the archive the reader worked around while reading, such
+    /// as data after the end of a tar archive.
+    fn take_warnings(&mut self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Open another, independent decoder for the entries being read, if the
+    /// format's entries can be decoded in any order on other threads.
+    fn open_decoder(&self) -> Result<Option<Box<dyn EntryDecoder>>, ExtractError> {
+        Ok(None)
+    }
+}
+
+/// Open the reader for an archive in `format` (as returned by format detection).
+///
+/// `password` is used to decrypt ZIP, 7-Zip and RAR entries. The archive is
+/// read through a buffer of [`DEFAULT_BUFFER_SIZE`] bytes.
+pub fn open_reader(
+    path: &Path,
+    format: Format,
+    password: Option<&str>,
+) -> Result<Box<dyn ArchiveReader>, ExtractError> {
+    open_buffered_reader(path, format, password, DEFAULT_BUFFER_SIZE)
+}
+
+/// Like [`open_reader`], reading the archive through a buffer of
+/// `buffer_size` bytes (unbuffered if `0`). RAR archives are read by the
+/// unrar library, which does its own buffering.
+pub fn open_buffered_reader(
+    path: &Path,
+    format: Format,
+    password: Option<&str>,
+    buffer_size: usize,
+) -> Result<Box<dyn ArchiveReader>, ExtractError> {
+    let open = || -> io::Result<BufReader<File>> {
+        Ok(BufReader::with_capacity(buffer_size, File::open(path)?))
+    };
+    let reader: Box<dyn ArchiveReader> = match format {
+        Format::Zip => {
+            let mut file = open()?;
+            if zip32::needs_locating(&mut file, zip32::WRAP)? {
+                Box::new(Zip32Reader::new(file)?)
+            } else {
+                Box::new(ZipReader::open(path, password, buffer_size)?)
+            }
+        }
+        Format::Tar(compression) => Box::new(TarReader::new(Box::new(open()?), compression)?),
+        Format::Gzip | Format::Bzip2 | Format::Xz => Box::new(CompressedReader::new(
+            path,
+            Box::new(open()?),
+            format.compression(),
+        )?),
+        Format::SevenZ => Box::new(SevenZipReader::new(open()?, password)?),
+        Format::Rar => Box::new(RarReader::new(path, password)),
+    };
+    Ok(reader)
+}
+
+/// Open the reader for an archive in `format` as `options` say: from a memory
+/// map of the file for ZIP and 7-Zip archives if `memory_map` is set and the
+/// file can be mapped, and through a buffer of `buffer_size` bytes otherwise.
+pub(crate) fn open_archive_reader(
+    path: &Path,
+    format: Format,
+    options: &ExtractOptions,
+) -> Result<Box<dyn ArchiveReader>, ExtractError> {
+    let password = options.password.as_deref();
+    let map = match format {
+        Format::Zip | Format::SevenZ if options.memory_map => Mmap::open(path).ok(),
+        _ => None,
+    };
+    let reader: Box<dyn ArchiveReader> = match (format, map) {
+        (Format::Zip, Some(map)) => {
+            let mut source = Cursor::new(map.clone());
+            if zip32::needs_locating(&mut source, zip32::WRAP)? {
+                Box::new(Zip32Reader::new(source)?)
+            } else {
+                Box::new(ZipReader::map(map, password)?)
+            }
+        }
+        (Format::SevenZ, Some(map)) => Box::new(SevenZipReader::new(Cursor::new(map), password)?),
+        _ => open_buffered_reader(path, format, password, options.buffer_size)?,
+    };
+    Ok(reader)
+}
+
+/// Write all of `data` to a new file at `path` through a buffer of
+/// [`DEFAULT_BUFFER_SIZE`] bytes, returning the number of bytes written.
+fn copy_to_new_file(data: &mut dyn Read, path: &Path) -> io::Result<u64> {
+    let mut outfile = BufWriter::with_capacity(DEFAULT_BUFFER_SIZE, File::create(path)?);
+    let written = io::copy(data, &mut outfile)?;
+    outfile.flush()?;
+    Ok(written)
+}
+
+/// Open the reader for an archive in `format` whose data comes from `source`
+/// instead of a file, such as an archive embedded in another file or an
+/// already-open handle.
+///
+/// The archive must start at the beginning of `source`. A single compressed
+/// file's entry is named "data" since there is no archive name to derive it
+/// from, and RAR data is copied to a temporary file because the unrar library
+/// only reads from files.
+pub fn open_stream_reader<'a, R: Read + Seek + 'a>(
+    source: R,
+    format: Format,
+    password: Option<&str>,
+) -> Result<Box<dyn ArchiveReader + 'a>, ExtractError> {
+    open_stream_reader_in(source, format, password, &std::env::temp_dir())
+}
+
+/// Like [`open_stream_reader`], copying RAR data to a temporary folder created
+/// in `temp_dir`.
+pub(crate) fn open_stream_reader_in<'a, R: Read + Seek + 'a>(
+    mut source: R,
+    format: Format,
+    password: Option<&str>,
+    temp_dir: &Path,
+) -> Result<Box<dyn ArchiveReader + 'a>, ExtractError> {
+    source.seek(SeekFrom::Start(0))?;
+    let reader: Box<dyn ArchiveReader + 'a> = match format {
+        Format::Zip if zip32::needs_locating(&mut source, zip32::WRAP)? => {
+            Box::new(Zip32Reader::new(source)?)
+        }
+        Format::Zip => Box::new(ZipReader::new(source, password)?),
+        Format::Tar(compression) => Box::new(TarReader::new(Box::new(source), compression)?),
+        Format::Gzip | Format::Bzip2 | Format::Xz => Box::new(CompressedReader::from_stream(
+            "data",
+            Box::new(source),
+            format.compression(),
+        )),
+        Format::SevenZ => Box::new(SevenZipReader::new(source, password)?),
+        Format::Rar => Box::new(RarReader::from_stream_in(source, password, temp_dir)?),
+    };
+    Ok(reader)
+}
+
+/// [`EntryData`] for formats that hand out a plain reader.
+struct StreamData<R>(R);
+
+impl<R: Read> EntryData for StreamData<R> {
+    fn reader(&mut self) -> Result<&mut dyn Read, ExtractError> {
+        Ok(&mut self.0)
+    }
+}
+
+/// Map an archive library error to a password error when it mentions one.
+pub(crate) fn password_error(message: String, has_password: bool) -> ExtractError {
+    let lower = message.to_lowercase();
+    if lower.contains("password") || lower.contains("encrypted") {
+        if has_password {
+            ExtractError::InvalidPassword
+        } else {
+            ExtractError::PasswordRequired
+        }
+    } else {
+        ExtractError::Corrupted(message)
+    }
+}
+
+/// Decompress the stream of a compressed tar archive or single compressed file.
+fn decompress<'a>(
+    source: Box<dyn Read + 'a>,
+    compression: Compression,
+) -> Result<Box<dyn Read + 'a>, ExtractError> {
+    let reader: Box<dyn Read + 'a> = match compression {
+        Compression::Gzip => Box::new(GzDecoder::new(source)),
+        Compression::Bzip2 => Box::new(BzDecoder::new(source)),
+        Compression::Xz => Box::new(XzDecoder::new(BufReader::new(source))),
+        Compression::None => source,
+    };
+    Ok(reader)
+}
+
+/// Reader for ZIP archives.
+///
+/// A ZIP archive opened from a file can open [`EntryDecoder`]s of its own, as
+/// every entry is compressed separately. Entries holding more data than their
+/// recorded size, as entries over 4 GB written without ZIP64 do, are warned
+/// about once read. Names not marked as UTF-8 are decoded with the encoding
+/// detected from them (see [`FilenameEncoding`]).
+pub struct ZipReader<R> {
+    archive: zip::ZipArchive<R>,
+    password: Option<String>,
+    /// Encoding of names not marked as UTF-8, once set or detected
+    filename_encoding: Option<FilenameEncoding>,
+    /// Where decoders read the archive from, if it can be read again
+    decoder_source: Option<ZipSource>,
+    /// Shared with the decoders
+    warnings: Arc<Mutex<Vec<String>>>,
+}
+
+impl ZipReader<BufReader<File>> {
+    /// Read the central directory of the ZIP archive at `path`, reading the
+    /// file through a buffer of `buffer_size` bytes.
+    pub fn open(
+        path: &Path,
+        password: Option<&str>,
+        buffer_size: usize,
+    ) -> Result<Self, ExtractError> {
+        let source = BufReader::with_capacity(buffer_size, File::open(path)?);
+        Ok(Self {
+            decoder_source: Some(ZipSource::File(path.to_path_buf(), buffer_size)),
+            ..Self::new(source, password)?
+        })
+    }
+}
+
+impl ZipReader<Cursor<Mmap>> {
+    /// Read the central directory of the ZIP archive mapped in `map`.
+    pub(crate) fn map(map: Mmap, password: Option<&str>) -> Result<Self, ExtractError> {
+        Ok(Self {
+            decoder_source: Some(ZipSource::Mapped(map.clone())),
+            ..Self::new(Cursor::new(map), password)?
+        })
+    }
+}
+
+impl<R: Read + Seek> ZipReader<R> {
+    /// Read the central directory of the ZIP archive in `source`.
+    pub fn new(source: R, password: Option<&str>) -> Result<Self, ExtractError> {
+        let archive = zip::ZipArchive::new(source)
+            .map_err(|e| password_error(e.to_string(), password.is_some()))?;
+        Ok(Self {
+            archive,
+            password: password.map(str::to_string),
+            filename_encoding: None,
+            decoder_source: None,
+            warnings: Arc::default(),
+        })
+    }
+
+    /// Encoding of the entry names not marked as UTF-8, detected from them the
+    /// first time it is needed unless it was set. Only archives with non-ASCII
+    /// names have their headers read for this.
+    fn filename_encoding(&mut self) -> Result<FilenameEncoding, ExtractError> {
+        if let Some(encoding) = self.filename_encoding {
+            return Ok(encoding);
+        }
+        let mut legacy = Vec::new();
+        if self.archive.file_names().any(|name| !name.is_ascii()) {
+            for i in 0..self.archive.len() {
+                let file = self
+                    .archive
+                    .by_index_raw(i)
+                    .map_err(|e| ExtractError::Corrupted(e.to_string()))?;
+                legacy.extend(legacy_name(&file).map(<[u8]>::to_vec));
+            }
+        }
+        let encoding = FilenameEncoding::detect(legacy.iter().map(Vec::as_slice));
+        self.filename_encoding = Some(encoding);
+        Ok(encoding)
+    }
+}
+
+/// Raw name of a ZIP entry that isn't plain ASCII and isn't marked as UTF-8,
+/// which the `zip` crate reads as CP437.
+fn legacy_name<'a>(file: &'a zip::read::ZipFile<'_>) -> Option<&'a [u8]> {
+    let raw = file.name_raw();
+    (std::str::from_utf8(raw) != Ok(file.name())).then_some(raw)
+}
+
+/// Open the `i`th entry of `archive` for reading, decrypting it with
+/// `password` if given.
+fn zip_file<'a, R: Read + Seek>(
+    archive: &'a mut zip::ZipArchive<R>,
+    i: usize,
+    password: Option<&str>,
+) -> Result<zip::read::ZipFile<'a>, ExtractError> {
+    let file = match password {
+        Some(password) => archive.by_index_decrypt(i, password.as_bytes()),
+        None => archive.by_index(i),
+    };
+    file.map_err(|e| match e {
+        zip::result::ZipError::InvalidPassword => ExtractError::InvalidPassword,
+        e => password_error(e.to_string(), password.is_some()),
+    })
+}
+
+/// Warning about a ZIP entry that held `read` bytes although its size was
+/// recorded as `recorded`, if it held more.
+fn size_warning(name: &str, recorded: u64, read: u64) -> Option<String> {
+    if read <= recorded {
+        None
+    } else if (read - recorded).is_multiple_of(zip32::WRAP) {
+        Some(format!(
+            "{} is {} bytes, over 4 GB, but its size was recorded without ZIP64 as {} bytes",
+            name, read, recorded
+        ))
+    } else {
+        Some(format!(
+            "{} holds {} bytes, more than its recorded size of {} bytes",
+            name, read, recorded
+        ))
+    }
+}
+
+/// Data of a ZIP entry, which decoders can find again by its index.
+struct ZipData<R> {
+    file: R,
+    index: Option<usize>,
+    /// Bytes read so far
+    read: u64,
+}
+
+impl<R: Read> Read for ZipData<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.file.read(buf)?;
+        self.read += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: Read> EntryData for ZipData<R> {
+    fn reader(&mut self) -> Result<&mut dyn Read, ExtractError> {
+        Ok(self)
+    }
+
+    fn index(&self) -> Option<usize> {
+        self.index
+    }
+}
+
+/// Where [`ZipDecoder`]s read a ZIP archive from.
+#[derive(Clone)]
+enum ZipSource {
+    /// The archive file, read through a buffer of this many bytes
+    File(PathBuf, usize),
+    /// A map of the archive file
+    Mapped(Mmap),
+}
+
+impl ZipSource {
+    fn open(&self) -> io::Result<ZipInput> {
+        Ok(match self {
+            ZipSource::File(path, buffer_size) => {
+                ZipInput::File(BufReader::with_capacity(*buffer_size, File::open(path)?))
+            }
+            ZipSource::Mapped(map) => ZipInput::Mapped(Cursor::new(map.clone())),
+        })
+    }
+}
+
+/// A [`ZipDecoder`]'s own handle on the archive.
+enum ZipInput {
+    File(BufReader<File>),
+    Mapped(Cursor<Mmap>),
+}
+
+impl Read for ZipInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            ZipInput::File(file) => file.read(buf),
+            ZipInput::Mapped(map) => map.read(buf),
+        }
+    }
+}
+
+impl Seek for ZipInput {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            ZipInput::File(file) => file.seek(pos),
+            ZipInput::Mapped(map) => map.seek(pos),
+        }
+    }
+}
+
+/// Decoder with its own handle on a ZIP archive file.
+struct ZipDecoder {
+    archive: zip::ZipArchive<ZipInput>,
+    password: Option<String>,
+    warnings: Arc<Mutex<Vec<String>>>,
+}
+
+impl EntryDecoder for ZipDecoder {
+    fn copy_entry(&mut self, index: usize, out: &mut dyn Write) -> Result<u64, ExtractError> {
+        let mut file = zip_file(&mut self.archive, index, self.password.as_deref())?;
+        let copied = io::copy(&mut file, out)?;
+        if let Some(warning) = size_warning(file.name(), file.size(), copied) {
+            self.warnings.lock().unwrap().push(warning);
+        }
+        Ok(copied)
+    }
+}
+
+fn zip_entry(file: &zip::read::ZipFile<'_>, encoding: FilenameEncoding) -> Entry {
+    let path = match legacy_name(file) {
+        Some(raw) => encoding.decode(raw, file.name()),
+        None => file.name().to_string(),
+    };
+    Entry {
+        path,
+        is_directory: file.is_dir(),
+        kind: kind_from_mode(file.is_dir(), file.unix_mode()),
+        size: file.size(),
+        compressed_size: Some(file.compressed_size()),
+        crc32: Some(file.crc32()),
+        modified: file
+            .last_modified()
+            .and_then(crate::probe::zip_time_to_unix),
+        mode: file.unix_mode(),
+        encrypted: file.encrypted(),
+        xattrs: Vec::new(),
+        owner: None,
+        symlink_target: None,
+        hardlink_target: None,
+    }
+}
+
+/// File type bits of a Unix mode, and their values for symbolic links and
+/// special files
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+const S_IFIFO: u32 = 0o010000;
+const S_IFCHR: u32 = 0o020000;
+const S_IFBLK: u32 = 0o060000;
+const S_IFSOCK: u32 = 0o140000;
+
+/// Kind of an entry from the Unix `mode` recorded for it, if any.
+fn kind_from_mode(is_directory: bool, mode: Option<u32>) -> EntryType {
+    if is_directory {
+        return EntryType::Directory;
+    }
+    match mode.map(|mode| mode & S_IFMT) {
+        Some(S_IFLNK) => EntryType::Symlink,
+        Some(S_IFIFO | S_IFCHR | S_IFBLK | S_IFSOCK) => EntryType::Other,
+        _ => EntryType::File,
+    }
+}
+
+impl<R: Read + Seek> ArchiveReader for ZipReader<R> {
+    fn entries(&mut self) -> Result<Entries<'_>, ExtractError> {
+        // Raw access reads the headers without needing the password
+        let encoding = self.filename_encoding()?;
+        let archive = &mut self.archive;
+        Ok(Box::new((0..archive.len()).map(move |i| {
+            let file = archive
+                .by_index_raw(i)
+                .map_err(|e| ExtractError::Corrupted(e.to_string()))?;
+            Ok(zip_entry(&file, encoding))
+        })))
+    }
+
+    fn read_entries(&mut self, visit: &mut EntryVisitor<'_>) -> Result<(), ExtractError> {
+        let encoding = self.filename_encoding()?;
+        for i in 0..self.archive.len() {
+            let file = zip_file(&mut self.archive, i, self.password.as_deref())?;
+            let entry = zip_entry(&file, encoding);
+            let mut data = ZipData {
+                file,
+                index: Some(i),
+                read: 0,
+            };
+            let more = visit(&entry, &mut data)?;
+            if let Some(warning) = size_warning(&entry.path, entry.size, data.read) {
+                self.warnings.lock().unwrap().push(warning);
+            }
+            if !more {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn set_filename_encoding(&mut self, encoding: FilenameEncoding) {
+        self.filename_encoding = Some(encoding);
+    }
+
+    fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut *self.warnings.lock().unwrap())
+    }
+
+    fn open_decoder(&self) -> Result<Option<Box<dyn EntryDecoder>>, ExtractError> {
+        let Some(source) = &self.decoder_source else {
+            return Ok(None);
+        };
+        let archive = zip::ZipArchive::new(source.open()?)
+            .map_err(|e| ExtractError::Corrupted(e.to_string()))?;
+        Ok(Some(Box::new(ZipDecoder {
+            archive,
+            password: self.password.clone(),
+            warnings: self.warnings.clone(),
+        })))
+    }
+}
+
+/// Reader for ZIP archives over 4 GB written without ZIP64, whose recorded
+/// sizes and offsets wrapped around.
+///
+/// The entries are located from the archive's layout (see [`crate::zip32`])
+/// and read without the `zip` crate, which would trust the recorded values.
+/// Only stored and deflated entries without encryption can be read, and
+/// reading warns about the archive and each entry whose values had wrapped.
+pub struct Zip32Reader<R> {
+    source: R,
+    entries: Vec<LocatedEntry>,
+    warnings: Vec<String>,
+}
+
+impl<R: Read + Seek> Zip32Reader<R> {
+    /// Locate the entries of the ZIP archive in `source`.
+    pub fn new(mut source: R) -> Result<Self, ExtractError> {
+        let entries = zip32::locate_entries(&mut source, zip32::WRAP)?;
+        Ok(Self {
+            source,
+            entries,
+            warnings: Vec::new(),
+        })
+    }
+}
+
+fn located_entry(entry: &LocatedEntry) -> Entry {
+    Entry {
+        path: entry.name.clone(),
+        is_directory: entry.is_directory(),
+        kind: kind_from_mode(entry.is_directory(), entry.mode),
+        size: entry.size,
+        compressed_size: Some(entry.compressed_size),
+        crc32: Some(entry.crc32),
+        modified: entry.modified,
+        mode: entry.mode,
+        encrypted: entry.encrypted(),
+        xattrs: Vec::new(),
+        owner: None,
+        symlink_target: None,
+        hardlink_target: None,
+    }
+}
+
+impl<R: Read + Seek> ArchiveReader for Zip32Reader<R> {
+    fn entries(&mut self) -> Result<Entries<'_>, ExtractError> {
+        Ok(Box::new(
+            self.entries.iter().map(|entry| Ok(located_entry(entry))),
+        ))
+    }
+
+    fn read_entries(&mut self, visit: &mut EntryVisitor<'_>) -> Result<(), ExtractError> {
+        self.warnings.push(
+            "The archive is over 4 GB but was written without ZIP64; its entries were located \
+             from its layout instead of its recorded offsets"
+                .to_string(),
+        );
+        for located in &self.entries {
+            if located.wrapped {
+                self.warnings.push(format!(
+                    "{} had its recorded size or offset wrapped around at 4 GB",
+                    located.name
+                ));
+            }
+            let entry = located_entry(located);
+            let mut data = ZipData {
+                file: zip32::entry_reader(&mut self.source, located)?,
+                index: None,
+                read: 0,
+            };
+            let more = visit(&entry, &mut data)?;
+            // Only stored entries have a size worked out from the layout
+            if !located.wrapped {
+                self.warnings
+                    .extend(size_warning(&entry.path, entry.size, data.read));
+            }
+            if !more {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+}
+
+/// Reader for tar archives, optionally compressed with gzip, bzip2 or xz.
+///
+/// Some tools append padding or signatures after the zero blocks that end a
+/// tar archive, or leave out the end marker altogether. Unless told otherwise,
+/// the reader stops at the end marker or at the first block that isn't a valid
+/// header where the next entry should start, and warns about what followed.
+///
+/// Reading concatenated archives skips zero blocks instead of stopping at
+/// them, so it only ends at the end of the stream.
+pub struct TarReader<'a> {
+    archive: tar::Archive<Box<dyn Read + 'a>>,
+    tolerate_trailing_data: bool,
+    concatenated: bool,
+    warnings: Vec<String>,
+}
+
+impl<'a> TarReader<'a> {
+    /// Read the tar archive in `source`, compressed with `compression`.
+    pub fn new(source: Box<dyn Read + 'a>, compression: Compression) -> Result<Self, ExtractError> {
+        Ok(Self {
+            archive: tar::Archive::new(decompress(source, compression)?),
+            tolerate_trailing_data: true,
+            concatenated: false,
+            warnings: Vec::new(),
+        })
+    }
+
+    /// Warn about the data left in the stream after the end marker, unless it
+    /// is just zero padding.
+    fn check_trailing_data(&mut self) {
+        let empty: Box<dyn Read + 'a> = Box::new(io::empty());
+        let mut rest = std::mem::replace(&mut self.archive, tar::Archive::new(empty)).into_inner();
+
+        let (mut len, mut garbage) = (0u64, false);
+        // The block holding the first non-zero byte
+        let mut first_block = Vec::with_capacity(BLOCK);
+        let mut buf = vec![0u8; 64 * 1024];
+        // A stream that fails from here on has nothing left worth reading
+        while let Ok(read @ 1..) = rest.read(&mut buf) {
+            let mut data = &buf[..read];
+            if !garbage {
+                if let Some(at) = data.iter().position(|&b| b != 0) {
+                    garbage = true;
+                    first_block.resize(((len + at as u64) % BLOCK as u64) as usize, 0);
+                    data = &data[at..];
+                }
+            }
+            if garbage {
+                let taken = data.len().min(BLOCK - first_block.len());
+                first_block.extend_from_slice(&data[..taken]);
+            }
+            len += read as u64;
+        }
+
+        if is_tar_header(&first_block) {
+            self.warnings.push(
+                "Ignored another tar archive concatenated after the end of the first one \
+                 (read concatenated archives to extract it)"
+                    .to_string(),
+            );
+        } else if garbage {
+            self.warnings.push(format!(
+                "Ignored {} bytes of data after the end of the tar archive",
+                len
+            ));
+        }
+    }
+}
+
+/// Size of a tar block.
+const BLOCK: usize = 512;
+
+/// Whether `block` is a whole tar header with a valid checksum.
+fn is_tar_header(block: &[u8]) -> bool {
+    if block.len() != BLOCK {
+        return false;
+    }
+    // The checksum is computed with its own field taken as spaces
+    let sum = block[..148]
+        .iter()
+        .chain(&block[156..])
+        .map(|&b| u64::from(b))
+        .sum::<u64>()
+        + 8 * u64::from(b' ');
+    let header = tar::Header::from_byte_slice(block);
+    header.cksum().is_ok_and(|cksum| u64::from(cksum) == sum)
+}
+
+/// Whether a tar error means the block where the next header should be isn't
+/// a tar header at all.
+fn is_not_a_header(e: &io::Error) -> bool {
+    let message = e.to_string();
+    message.contains("archive header checksum mismatch")
+        || message.contains("when getting cksum")
+        || message.contains("failed to read entire block")
+}
+
+fn tar_entry<R: Read>(entry: &mut tar::Entry<'_, R>) -> Result<Entry, ExtractError> {
+    let xattrs = tar_xattrs(entry)?;
+    let header = entry.header();
+    let owner = match (header.uid(), header.gid()) {
+        (Ok(uid), Ok(gid)) => u32::try_from(uid).ok().zip(u32::try_from(gid).ok()),
+        _ => None,
+    };
+    let link_name = match header.entry_type() {
+        tar::EntryType::Symlink | tar::EntryType::Link => entry
+            .link_name()?
+            .map(|target| target.to_string_lossy().to_string()),
+        _ => None,
+    };
+    let (symlink_target, hardlink_target) = match header.entry_type() {
+        tar::EntryType::Link => (None, link_name),
+        _ => (link_name, None),
+    };
+    Ok(Entry {
+        path: entry.path()?.to_string_lossy().to_string(),
+        is_directory: header.entry_type().is_dir(),
+        kind: tar_kind(header.entry_type()),
+        size: header.size()?,
+        // Tar doesn't store per-file compressed sizes
+        compressed_size: None,
+        crc32: None,
+        modified: header.mtime().ok(),
+        mode: header.mode().ok(),
+        encrypted: false,
+        xattrs,
+        owner,
+        symlink_target,
+        hardlink_target,
+    })
+}
+
+/// Kind of a tar entry. Devices, FIFOs and types this reader doesn't know are
+/// all special files.
+fn tar_kind(entry_type: tar::EntryType) -> EntryType {
+    match entry_type {
+        tar::EntryType::Regular | tar::EntryType::Continuous | tar::EntryType::GNUSparse => {
+            EntryType::File
+        }
+        tar::EntryType::Directory => EntryType::Directory,
+        tar::EntryType::Symlink => EntryType::Symlink,
+        tar::EntryType::Link => EntryType::Hardlink,
+        _ => EntryType::Other,
+    }
+}
+
+/// Whether `entry` is a PAX global header, holding defaults for the entries
+/// after it rather than a member of the archive.
+fn is_global_header<R: Read>(entry: &tar::Entry<'_, R>) -> bool {
+    entry.header().entry_type().is_pax_global_extensions()
+}
+
+/// Extended attributes in the PAX headers of a tar entry, stored as
+/// `SCHILY.xattr.<name>` records by GNU tar and bsdtar.
+fn tar_xattrs<R: Read>(
+    entry: &mut tar::Entry<'_, R>,
+) -> Result<Vec<(String, Vec<u8>)>, ExtractError> {
+    let Some(extensions) = entry.pax_extensions()? else {
+        return Ok(Vec::new());
+    };
+    let mut xattrs = Vec::new();
+    for extension in extensions {
+        let extension = extension?;
+        let key = extension.key().ok();
+        if let Some(name) = key.and_then(|key| key.strip_prefix("SCHILY.xattr.")) {
+            xattrs.push((name.to_string(), extension.value_bytes().to_vec()));
+        }
+    }
+    Ok(xattrs)
+}
+
+impl ArchiveReader for TarReader<'_> {
+    fn entries(&mut self) -> Result<Entries<'_>, ExtractError> {
+        let tolerate = self.tolerate_trailing_data;
+        let entries = self.archive.entries()?;
+        let entries = entries.filter(|entry| !entry.as_ref().is_ok_and(is_global_header));
+        Ok(Box::new(entries.map_while(move |entry| match entry {
+            Ok(mut entry) => Some(tar_entry(&mut entry)),
+            Err(e) if tolerate && is_not_a_header(&e) => None,
+            Err(e) => Some(Err(e.into())),
+        })))
+    }
+
+    fn read_entries(&mut self, visit: &mut EntryVisitor<'_>) -> Result<(), ExtractError> {
+        let tolerate = self.tolerate_trailing_data;
+        let mut read_to_end_marker = true;
+        for entry in self.archive.entries()? {
+            let mut entry = match entry {
+                Ok(entry) => entry,
+                Err(e) if tolerate && is_not_a_header(&e) => {
+                    read_to_end_marker = false;
+                    break;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if is_global_header(&entry) {
+                continue;
+            }
+            let listed = tar_entry(&mut entry)?;
+            if !visit(&listed, &mut StreamData(&mut entry))? {
+                return Ok(());
+            }
+        }
+
+        if !tolerate {
+            return Ok(());
+        }
+        if read_to_end_marker {
+            self.check_trailing_data();
+        } else if self.concatenated {
+            // Zero blocks are skipped, so there is no end marker to speak of
+            self.warnings
+                .push("Ignored data that isn't a tar header after the last entry".to_string());
+        } else {
+            self.warnings.push(
+                "Ignored data that isn't a tar header after the last entry (the archive has no end marker)"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    fn set_tolerate_trailing_data(&mut self, tolerate: bool) {
+        self.tolerate_trailing_data = tolerate;
+    }
+
+    fn set_read_concatenated(&mut self, concatenated: bool) {
+        self.archive.set_ignore_zeros(concatenated);
+        self.concatenated = concatenated;
+    }
+
+    fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+}
+
+/// Reader for a single gzip, bzip2 or xz compressed file.
+///
+/// Its one entry is named after the archive without the compression extension
+/// and carries the archive's own modification time.
+pub struct CompressedReader<'a> {
+    source: Option<Box<dyn Read + 'a>>,
+    compression: Compression,
+    entry: Entry,
+}
+
+impl<'a> CompressedReader<'a> {
+    /// Read the file at `path` compressed with `compression`, whose data is
+    /// streamed from `source`.
+    pub fn new(
+        path: &Path,
+        source: Box<dyn Read + 'a>,
+        compression: Compression,
+    ) -> Result<Self, ExtractError> {
+        let name = path
+            .file_stem()
+            .ok_or_else(|| ExtractError::Corrupted("Invalid filename".to_string()))?;
+        let metadata = fs::metadata(path)?;
+
+        Ok(Self {
+            source: Some(source),
+            compression,
+            entry: Entry {
+                path: name.to_string_lossy().to_string(),
+                is_directory: false,
+                kind: EntryType::File,
+                size: crate::probe::estimate_uncompressed_size(path)
+                    .ok()
+                    .flatten()
+                    .unwrap_or(0),
+                compressed_size: Some(metadata.len()),
+                crc32: None,
+                modified: crate::extract::file_modified_secs(&metadata),
+                mode: None,
+                encrypted: false,
+                xattrs: Vec::new(),
+                owner: None,
+                symlink_target: None,
+                hardlink_target: None,
+            },
+        })
+    }
+
+    /// Read compressed data from `source` with no file behind it, naming its
+    /// entry `name`. The uncompressed size is unknown until the data is read.
+    pub fn from_stream(name: &str, source: Box<dyn Read + 'a>, compression: Compression) -> Self {
+        Self {
+            source: Some(source),
+            compression,
+            entry: Entry {
+                path: name.to_string(),
+                is_directory: false,
+                kind: EntryType::File,
+                size: 0,
+                compressed_size: None,
+                crc32: None,
+                modified: None,
+                mode: None,
+                encrypted: false,
+                xattrs: Vec::new(),
+                owner: None,
+                symlink_target: None,
+                hardlink_target: None,
+            },
+        }
+    }
+
+    fn take_source(&mut self) -> Result<Box<dyn Read + 'a>, ExtractError> {
+        self.source
+            .take()
+            .ok_or_else(|| ExtractError::Io(io::Error::other("compressed file already read")))
+    }
+}
+
+impl ArchiveReader for CompressedReader<'_> {
+    fn entries(&mut self) -> Result<Entries<'_>, ExtractError> {
+        // These formats carry no reliable size header, so stream the data
+        // through a sink to learn the real uncompressed size
+        let source = self.take_source()?;
+        let size = io::copy(&mut decompress(source, self.compression)?, &mut io::sink())?;
+
+        let entry = Entry {
+            size,
+            ..self.entry.clone()
+        };
+        Ok(Box::new(std::iter::once(Ok(entry))))
+    }
+
+    fn read_entries(&mut self, visit: &mut EntryVisitor<'_>) -> Result<(), ExtractError> {
+        let reader = decompress(self.take_source()?, self.compression)?;
+        visit(&self.entry, &mut StreamData(reader))?;
+        Ok(())
+    }
+
+    fn is_single_file(&self) -> bool {
+        true
+    }
+}
+
+/// Reader for 7-Zip archives.
+///
+/// Entries are decompressed from the archive straight into the visitor, one
+/// after the other, so nothing is staged on disk and size limits and
+/// cancellation apply while a block is still being decompressed.
+pub struct SevenZipReader<R: Read + Seek> {
+    reader: sevenz_rust2::SevenZReader<R>,
+    has_password: bool,
+    /// Whether entry data is encrypted; 7-Zip encrypts all of it or none
+    encrypted: bool,
+}
+
+impl<R: Read + Seek> SevenZipReader<R> {
+    /// Read the headers of the 7-Zip archive in `source`; header-encrypted
+    /// archives can only be opened with the password.
+    pub fn new(mut source: R, password: Option<&str>) -> Result<Self, ExtractError> {
+        use sevenz_rust2::Password;
+
+        let len = source.seek(SeekFrom::End(0))?;
+        source.seek(SeekFrom::Start(0))?;
+        let has_password = password.is_some();
+        let password = password.map(Password::from).unwrap_or_else(Password::empty);
+        let reader = sevenz_rust2::SevenZReader::new(source, len, password)
+            .map_err(|e| password_error(e.to_string(), has_password))?;
+        let encrypted = reader.archive().folders.iter().any(|folder| {
+            folder.coders.iter().any(|coder| {
+                coder.decompression_method_id() == sevenz_rust2::SevenZMethod::ID_AES256SHA256
+            })
+        });
+
+        Ok(Self {
+            reader,
+            has_password,
+            encrypted,
+        })
+    }
+}
+
+fn sevenz_entry(entry: &sevenz_rust2::SevenZArchiveEntry, encrypted: bool) -> Entry {
+    let mode = sevenz_mode(entry);
+    Entry {
+        path: entry.name().to_string(),
+        is_directory: entry.is_directory(),
+        kind: kind_from_mode(entry.is_directory(), mode),
+        size: entry.size(),
+        // 7z doesn't expose per-file compressed sizes easily
+        compressed_size: None,
+        crc32: entry.has_crc.then_some(entry.crc as u32),
+        modified: if entry.has_last_modified_date {
+            crate::probe::nt_to_unix(entry.last_modified_date().to_raw())
+        } else {
+            None
+        },
+        mode,
+        encrypted: encrypted && entry.has_stream,
+        xattrs: Vec::new(),
+        owner: None,
+        symlink_target: None,
+        hardlink_target: None,
+    }
+}
+
+/// Unix mode of a 7-Zip entry. Archivers on Unix set the
+/// `FILE_ATTRIBUTE_UNIX_EXTENSION` bit and keep the mode in the upper 16 bits
+/// of the Windows attributes.
+fn sevenz_mode(entry: &sevenz_rust2::SevenZArchiveEntry) -> Option<u32> {
+    const UNIX_EXTENSION: u32 = 0x8000;
+    let attributes = entry.windows_attributes;
+    (entry.has_windows_attributes && attributes & UNIX_EXTENSION != 0).then_some(attributes >> 16)
+}
+
+/// Data of a 7-Zip entry, noting whether reading it failed.
+struct SevenZipData<'a> {
+    data: &'a mut dyn Read,
+    failed: bool,
+}
+
+impl Read for SevenZipData<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.data.read(buf);
+        self.failed |= result.is_err();
+        result
+    }
+}
+
+impl EntryData for SevenZipData<'_> {
+    fn reader(&mut self) -> Result<&mut dyn Read, ExtractError> {
+        Ok(self)
+    }
+}
+
+impl<R: Read + Seek> ArchiveReader for SevenZipReader<R> {
+    fn entries(&mut self) -> Result<Entries<'_>, ExtractError> {
+        let encrypted = self.encrypted;
+        Ok(Box::new(
+            self.reader
+                .archive()
+                .files
+                .iter()
+                .map(move |entry| Ok(sevenz_entry(entry, encrypted))),
+        ))
+    }
+
+    fn read_entries(&mut self, visit: &mut EntryVisitor<'_>) -> Result<(), ExtractError> {
+        // The callback can't return our error type, so keep it aside
+        let mut failure = None;
+        let mut stopped = false;
+        let (encrypted, has_password) = (self.encrypted, self.has_password);
+        self.reader
+            .for_each_entries(|entry, data| {
+                if stopped {
+                    return Ok(false);
+                }
+                let mut data = SevenZipData {
+                    data,
+                    failed: false,
+                };
+                match visit(&sevenz_entry(entry, encrypted), &mut data) {
+                    Ok(true) => {
+                        // Entries of a solid block share one stream; skip what
+                        // the visitor left unread
+                        io::copy(data.data, &mut io::sink())?;
+                        Ok(true)
+                    }
+                    Ok(false) => {
+                        stopped = true;
+                        Ok(false)
+                    }
+                    // Encrypted data that fails to decompress was decrypted
+                    // with the wrong key
+                    Err(_) if data.failed && encrypted => {
+                        failure = Some(if has_password {
+                            ExtractError::InvalidPassword
+                        } else {
+                            ExtractError::PasswordRequired
+                        });
+                        stopped = true;
+                        Ok(false)
+                    }
+                    Err(e) => {
+                        failure = Some(e);
+                        stopped = true;
+                        Ok(false)
+                    }
+                }
+            })
+            .map_err(|e| password_error(e.to_string(), has_password))?;
+
+        failure.map_or(Ok(()), Err)
+    }
+}
+
+/// Reader for RAR archives, including multi-part ones.
+///
+/// The unrar library reads the volumes itself, so this works from the path of
+/// any part of the archive.
+pub struct RarReader {
+    path: PathBuf,
+    password: Option<String>,
+    /// Temporary copy of an archive read from a stream
+    _spool: Option<tempfile::TempDir>,
+}
+
+impl RarReader {
+    /// Read the RAR archive at `path`, starting from its first part.
+    pub fn new(path: &Path, password: Option<&str>) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            password: password.map(str::to_string),
+            _spool: None,
+        }
+    }
+
+    /// Read a single-volume RAR archive from `source`, which is first copied to
+    /// a temporary file since the unrar library only reads files.
+    pub fn from_stream<R: Read>(source: R, password: Option<&str>) -> Result<Self, ExtractError> {
+        Self::from_stream_in(source, password, &std::env::temp_dir())
+    }
+
+    /// Like [`from_stream`](Self::from_stream), copying the data to a temporary
+    /// folder created in `temp_dir`.
+    pub fn from_stream_in<R: Read>(
+        mut source: R,
+        password: Option<&str>,
+        temp_dir: &Path,
+    ) -> Result<Self, ExtractError> {
+        let spool = tempfile::tempdir_in(temp_dir)?;
+        let path = spool.path().join("archive.rar");
+        io::copy(&mut source, &mut File::create(&path)?)?;
+
+        Ok(Self {
+            path,
+            password: password.map(str::to_string),
+            _spool: Some(spool),
+        })
+    }
+
+    fn archive(&self) -> unrar::Archive<'_> {
+        let archive = match &self.password {
+            Some(password) => unrar::Archive::with_password(&self.path, password.as_bytes()),
+            None => unrar::Archive::new(&self.path),
+        };
+        archive.as_first_part()
+    }
+}
+
+fn rar_entry(entry: &unrar::FileHeader) -> Entry {
+    Entry {
+        path: entry.filename.to_string_lossy().to_string(),
+        is_directory: entry.is_directory(),
+        kind: if entry.is_directory() {
+            EntryType::Directory
+        } else {
+            EntryType::File
+        },
+        size: entry.unpacked_size,
+        // The RAR API doesn't expose packed sizes in this version
+        compressed_size: None,
+        // RAR 5 archives hashing files with BLAKE2 leave the CRC at 0
+        crc32: Some(entry.file_crc).filter(|&crc| crc != 0 || entry.unpacked_size == 0),
+        modified: crate::probe::dos_to_unix(entry.file_time),
+        mode: None,
+        encrypted: entry.is_encrypted(),
+        xattrs: Vec::new(),
+        owner: None,
+        symlink_target: None,
+        hardlink_target: None,
+    }
+}
+
+impl ArchiveReader for RarReader {
+    fn entries(&mut self) -> Result<Entries<'_>, ExtractError> {
+        let archive = self
+            .archive()
+            .open_for_listing()
+            .map_err(|e| password_error(e.to_string(), self.password.is_some()))?;
+        Ok(Box::new(RarEntries {
+            current: Some(archive),
+        }))
+    }
+
+    fn read_entries(&mut self, visit: &mut EntryVisitor<'_>) -> Result<(), ExtractError> {
+        let has_password = self.password.is_some();
+        let mut current = Some(
+            self.archive()
+                .open_for_processing()
+                .map_err(|e| password_error(e.to_string(), has_password))?,
+        );
+
+        while let Some(archive) = current.take() {
+            let Some(header) = archive.read_header().map_err(io::Error::other)? else {
+                break;
+            };
+
+            let entry = rar_entry(header.entry());
+            let mut data = RarData {
+                header: Some(header),
+                next: None,
+                data: None,
+                has_password,
+            };
+            let keep_going = visit(&entry, &mut data)?;
+            current = Some(data.finish()?);
+            if !keep_going {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Iterator listing RAR entries header by header.
+struct RarEntries {
+    current: Option<unrar::OpenArchive<unrar::List, unrar::CursorBeforeHeader>>,
+}
+
+impl Iterator for RarEntries {
+    type Item = Result<Entry, ExtractError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let header = match self.current.take()?.read_header() {
+            Ok(header) => header?,
+            Err(e) => return Some(Err(io::Error::other(e).into())),
+        };
+
+        let entry = rar_entry(header.entry());
+        match header.skip() {
+            Ok(next) => self.current = Some(next),
+            Err(e) => return Some(Err(io::Error::other(e).into())),
+        }
+        Some(Ok(entry))
+    }
+}
+
+/// Data of the RAR entry under the cursor. unrar can only hand out a whole
+/// entry at once, so reading it buffers the entry in memory; extracting it
+/// to a file doesn't.
+struct RarData {
+    header: Option<unrar::OpenArchive<unrar::Process, unrar::CursorBeforeFile>>,
+    next: Option<unrar::OpenArchive<unrar::Process, unrar::CursorBeforeHeader>>,
+    data: Option<Cursor<Vec<u8>>>,
+    has_password: bool,
+}
+
+impl RarData {
+    /// Move past the entry, skipping its data if it wasn't read.
+    fn finish(
+        self,
+    ) -> Result<unrar::OpenArchive<unrar::Process, unrar::CursorBeforeHeader>, ExtractError> {
+        match (self.header, self.next) {
+            (Some(header), _) => Ok(header.skip().map_err(io::Error::other)?),
+            (None, Some(next)) => Ok(next),
+            (None, None) => Err(ExtractError::Io(io::Error::other("RAR entry was not read"))),
+        }
+    }
+}
+
+impl EntryData for RarData {
+    fn reader(&mut self) -> Result<&mut dyn Read, ExtractError> {
+        if let Some(header) = self.header.take() {
+            let (data, next) = header
+                .read()
+                .map_err(|e| password_error(e.to_string(), self.has_password))?;
+            self.data = Some(Cursor::new(data));
+            self.next = Some(next);
+        }
+        match &mut self.data {
+            Some(data) => Ok(data),
+            None => Err(ExtractError::Io(io::Error::other("RAR entry was not read"))),
+        }
+    }
+
+    fn extract_to(&mut self, path: &Path) -> Result<u64, ExtractError> {
+        let Some(header) = self.header.take() else {
+            return Ok(copy_to_new_file(self.reader()?, path)?);
+        };
+
+        let size = header.entry().unpacked_size;
+        let next = header
+            .extract_to(path)
+            .map_err(|e| password_error(e.to_string(), self.has_password))?;
+        self.next = Some(next);
+        Ok(size)
+    }
+
+    fn writes_directly(&self) -> bool {
+        self.header.is_some()
+    }
+}
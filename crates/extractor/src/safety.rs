@@ -4,11 +4,17 @@
 //! security policies to prevent attacks like zip-slip (path traversal).
 
 use crate::error::SecurityError;
-use crate::types::ExtractOptions;
+use crate::types::{ExtractOptions, NameSanitizer, NormalizationForm, PathLimits, SymlinkPolicy};
+use serde::{Deserialize, Serialize};
 use std::path::{Component, Path, PathBuf};
-
-/// Entry type for filtering special file types.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use ts_rs::TS;
+use unicode_normalization::UnicodeNormalization;
+
+/// Kind of an archive entry, which decides whether it's extracted (see
+/// [`is_safe_entry_type`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+#[serde(rename_all = "lowercase")]
 pub enum EntryType {
     /// Regular file
     File,
@@ -27,6 +33,10 @@ pub enum EntryType {
 /// This function performs the following checks:
 /// - Rejects absolute paths
 /// - Rejects paths containing ".." components (path traversal)
+/// - Rejects names containing NUL, newlines or other control characters, which
+///   break shells and line-based tools (see [`sanitize_control_chars`])
+/// - Rejects paths beyond the default [`PathLimits`]: more than 1024 levels
+///   deep or longer than 4096 bytes
 /// - Normalizes the path to remove redundant separators and "." components
 /// - Validates UTF-8 encoding
 ///
@@ -57,6 +67,30 @@ pub enum EntryType {
 /// assert!(result.is_err());
 /// ```
 pub fn validate_entry_path(path: &Path) -> Result<PathBuf, SecurityError> {
+    validate_entry_path_with_limits(path, &PathLimits::default())
+}
+
+/// Validates and normalizes an archive entry path like [`validate_entry_path`],
+/// holding it to `limits` instead of the default ones.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use extractor::safety::validate_entry_path_with_limits;
+/// use extractor::PathLimits;
+///
+/// let limits = PathLimits {
+///     max_depth: Some(2),
+///     ..PathLimits::unlimited()
+/// };
+/// assert!(validate_entry_path_with_limits(Path::new("dir/file.txt"), &limits).is_ok());
+/// assert!(validate_entry_path_with_limits(Path::new("a/b/file.txt"), &limits).is_err());
+/// ```
+pub fn validate_entry_path_with_limits(
+    path: &Path,
+    limits: &PathLimits,
+) -> Result<PathBuf, SecurityError> {
     // Check if path is absolute
     if path.is_absolute() {
         return Err(SecurityError::AbsolutePath(path.display().to_string()));
@@ -67,8 +101,13 @@ pub fn validate_entry_path(path: &Path) -> Result<PathBuf, SecurityError> {
         SecurityError::PathTraversal("Path contains invalid UTF-8 characters".to_string())
     })?;
 
+    if path_str.chars().any(char::is_control) {
+        return Err(SecurityError::ControlCharacters(format!("{:?}", path_str)));
+    }
+
     // Normalize and validate path components
     let mut normalized = PathBuf::new();
+    let mut depth = 0u32;
 
     for component in path.components() {
         match component {
@@ -87,6 +126,27 @@ pub fn validate_entry_path(path: &Path) -> Result<PathBuf, SecurityError> {
                     )));
                 }
 
+                // Stop early on paths thousands of levels deep
+                depth += 1;
+                if let Some(max) = limits.max_depth.filter(|&max| depth > max) {
+                    return Err(SecurityError::PathTooDeep(format!(
+                        "more than {} levels: {}",
+                        max,
+                        abbreviated(path_str)
+                    )));
+                }
+                if let Some(max) = limits
+                    .max_component_bytes
+                    .filter(|&max| part_str.len() > max as usize)
+                {
+                    return Err(SecurityError::PathTooLong(format!(
+                        "{}-byte name, over {}: {}",
+                        part_str.len(),
+                        max,
+                        abbreviated(path_str)
+                    )));
+                }
+
                 normalized.push(part);
             }
             Component::CurDir => {
@@ -118,9 +178,247 @@ pub fn validate_entry_path(path: &Path) -> Result<PathBuf, SecurityError> {
         ));
     }
 
+    let len = normalized.as_os_str().len();
+    if let Some(max) = limits.max_path_bytes.filter(|&max| len > max as usize) {
+        return Err(SecurityError::PathTooLong(format!(
+            "{} bytes, over {}: {}",
+            len,
+            max,
+            abbreviated(path_str)
+        )));
+    }
+
     Ok(normalized)
 }
 
+/// `path` cut to its first 100 characters for an error message.
+fn abbreviated(path: &str) -> String {
+    match path.char_indices().nth(100) {
+        Some((end, _)) => format!("{}...", &path[..end]),
+        None => path.to_string(),
+    }
+}
+
+/// Replaces control characters (NUL, newlines, tabs, escape sequences, ...) in an
+/// entry name with `_`.
+///
+/// Returns `None` when the name contains none, so callers can tell whether it
+/// was changed.
+///
+/// # Examples
+///
+/// ```
+/// use extractor::safety::sanitize_control_chars;
+///
+/// assert_eq!(sanitize_control_chars("bad\nname.txt").as_deref(), Some("bad_name.txt"));
+/// assert_eq!(sanitize_control_chars("fine.txt"), None);
+/// ```
+pub fn sanitize_control_chars(name: &str) -> Option<String> {
+    if !name.chars().any(char::is_control) {
+        return None;
+    }
+    Some(
+        name.chars()
+            .map(|c| if c.is_control() { '_' } else { c })
+            .collect(),
+    )
+}
+
+/// Converts `path` to the Unicode normalization form `form`.
+///
+/// Returns `None` when the path is already in that form (or `form` is
+/// `Keep`), so callers can tell whether it was changed.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use extractor::safety::normalize_unicode;
+/// use extractor::NormalizationForm;
+///
+/// let decomposed = Path::new("cafe\u{301}/menu.txt");
+/// let composed = normalize_unicode(decomposed, NormalizationForm::Nfc).unwrap();
+/// assert_eq!(composed, Path::new("caf\u{e9}/menu.txt"));
+/// assert_eq!(normalize_unicode(&composed, NormalizationForm::Nfc), None);
+/// ```
+pub fn normalize_unicode(path: &Path, form: NormalizationForm) -> Option<PathBuf> {
+    let name = path.to_str()?;
+    let normalized: String = match form {
+        NormalizationForm::Keep => return None,
+        NormalizationForm::Nfc => name.nfc().collect(),
+        NormalizationForm::Nfd => name.nfd().collect(),
+    };
+    (normalized != name).then(|| PathBuf::from(normalized))
+}
+
+/// Applies `sanitizer` to every component of `path` (see [`NameSanitizer`]).
+///
+/// Returns `None` when no name changed, so callers can tell whether the path
+/// was renamed.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use extractor::safety::sanitize_names;
+/// use extractor::NameSanitizer;
+///
+/// let sanitizer = NameSanitizer {
+///     trailing_dots: true,
+///     leading_dashes: true,
+///     max_name_bytes: Some(255),
+/// };
+/// let renamed = sanitize_names(Path::new("-rf/notes. "), &sanitizer).unwrap();
+/// assert_eq!(renamed, Path::new("_rf/notes__"));
+/// assert_eq!(sanitize_names(Path::new("dir/plain.txt"), &sanitizer), None);
+/// ```
+pub fn sanitize_names(path: &Path, sanitizer: &NameSanitizer) -> Option<PathBuf> {
+    let mut changed = false;
+    let mut sanitized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => match part.to_str() {
+                Some(name) => {
+                    let fixed = sanitize_name(name, sanitizer);
+                    changed |= fixed != name;
+                    sanitized.push(fixed);
+                }
+                None => sanitized.push(part),
+            },
+            _ => sanitized.push(component),
+        }
+    }
+    changed.then_some(sanitized)
+}
+
+/// Applies `sanitizer` to a single path component
+fn sanitize_name(name: &str, sanitizer: &NameSanitizer) -> String {
+    let mut name = name.to_string();
+    if sanitizer.leading_dashes && name.starts_with('-') {
+        name.replace_range(..1, "_");
+    }
+    if sanitizer.trailing_dots {
+        let kept = name.trim_end_matches(['.', ' ']).len();
+        let dropped = name.len() - kept;
+        name.truncate(kept);
+        name.push_str(&"_".repeat(dropped));
+    }
+    match sanitizer.max_name_bytes {
+        Some(max) => shorten_name(&name, max as usize),
+        None => name,
+    }
+}
+
+/// Cuts `name` down to at most `max` bytes on a character boundary, keeping
+/// its extension when that takes up no more than half of them.
+fn shorten_name(name: &str, max: usize) -> String {
+    if name.len() <= max {
+        return name.to_string();
+    }
+    let ext = name
+        .rfind('.')
+        .filter(|&dot| dot > 0 && name.len() - dot <= max / 2)
+        .map_or("", |dot| &name[dot..]);
+    let mut end = max - ext.len();
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    if end == 0 {
+        // Nothing of the name would be left
+        return name.to_string();
+    }
+    format!("{}{}", &name[..end], ext)
+}
+
+/// Characters Windows doesn't allow in file names
+const WINDOWS_INVALID_CHARS: &[char] = &['<', '>', ':', '"', '|', '?', '*'];
+
+/// Device names Windows reserves, with or without an extension
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Whether `name`, a single path component, is a device name Windows
+/// reserves (`CON`, `PRN`, `AUX`, `NUL`, `COM1`-`COM9`, `LPT1`-`LPT9`), in any
+/// case and with any extension.
+fn is_reserved_windows_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).trim_end_matches(' ');
+    WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+}
+
+/// Whether every component of `path` can be created on Windows: none is a
+/// reserved device name such as `CON` or `aux.txt`, contains one of `<>:"|?*`,
+/// or ends in a dot or space (which Windows silently drops).
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use extractor::safety::is_portable_path;
+///
+/// assert!(is_portable_path(Path::new("docs/console.txt")));
+/// assert!(!is_portable_path(Path::new("docs/aux.txt")));
+/// assert!(!is_portable_path(Path::new("what?.txt")));
+/// ```
+pub fn is_portable_path(path: &Path) -> bool {
+    path.components().all(|component| match component {
+        Component::Normal(part) => part.to_str().is_some_and(|name| {
+            !name.contains(WINDOWS_INVALID_CHARS)
+                && !name.ends_with(['.', ' '])
+                && !is_reserved_windows_name(name)
+        }),
+        _ => true,
+    })
+}
+
+/// Makes every component of `path` a name Windows can create (see
+/// [`is_portable_path`]): `<>:"|?*` and trailing dots and spaces become `_`,
+/// and reserved device names get `_` before their extension.
+///
+/// Returns `None` when the path is already portable, so callers can tell
+/// whether it was changed.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use extractor::safety::sanitize_windows_names;
+///
+/// let renamed = sanitize_windows_names(Path::new("CON/aux.txt")).unwrap();
+/// assert_eq!(renamed, Path::new("CON_/aux_.txt"));
+/// let renamed = sanitize_windows_names(Path::new("a:b?.txt")).unwrap();
+/// assert_eq!(renamed, Path::new("a_b_.txt"));
+/// assert_eq!(sanitize_windows_names(Path::new("fine.txt")), None);
+/// ```
+pub fn sanitize_windows_names(path: &Path) -> Option<PathBuf> {
+    if is_portable_path(path) {
+        return None;
+    }
+    let mut sanitized = PathBuf::new();
+    for component in path.components() {
+        let Component::Normal(part) = component else {
+            sanitized.push(component);
+            continue;
+        };
+        let Some(name) = part.to_str() else {
+            sanitized.push(part);
+            continue;
+        };
+        let kept = name.trim_end_matches(['.', ' ']);
+        let mut name =
+            kept.replace(WINDOWS_INVALID_CHARS, "_") + &"_".repeat(name.len() - kept.len());
+        if is_reserved_windows_name(&name) {
+            let stem_len = name.find('.').unwrap_or(name.len());
+            name.insert(stem_len, '_');
+        }
+        sanitized.push(name);
+    }
+    Some(sanitized)
+}
+
 /// Checks if the current extracted size exceeds the configured limit.
 ///
 /// # Arguments
@@ -176,7 +474,7 @@ pub fn check_size_limits(current_bytes: u64, limit: Option<u64>) -> Result<(), S
 ///
 /// ```
 /// use extractor::safety::{is_safe_entry_type, EntryType};
-/// use extractor::ExtractOptions;
+/// use extractor::{ExtractOptions, SymlinkPolicy};
 ///
 /// let options = ExtractOptions::default();
 ///
@@ -189,18 +487,103 @@ pub fn check_size_limits(current_bytes: u64, limit: Option<u64>) -> Result<(), S
 ///
 /// // Allow symlinks with option
 /// let mut options_with_symlinks = ExtractOptions::default();
-/// options_with_symlinks.allow_symlinks = true;
+/// options_with_symlinks.symlink_policy = SymlinkPolicy::Preserve;
 /// assert!(is_safe_entry_type(EntryType::Symlink, &options_with_symlinks));
 /// ```
 pub fn is_safe_entry_type(entry_type: EntryType, options: &ExtractOptions) -> bool {
     match entry_type {
         EntryType::File | EntryType::Directory => true,
-        EntryType::Symlink => options.allow_symlinks,
+        EntryType::Symlink => options.symlink_policy != SymlinkPolicy::Skip,
         EntryType::Hardlink => options.allow_hardlinks,
         EntryType::Other => false, // Always block special files
     }
 }
 
+/// Validates the target of a symbolic link entry, so the link can't point
+/// outside the output directory.
+///
+/// `link_dir` is the folder holding the link, relative to the output
+/// directory, as it resolves on disk. The target must be relative, and `..`
+/// components may only lead it, climbing no higher than the output directory.
+/// A `..` after other components is rejected: it would be resolved against
+/// whatever those point to once extracted, which another link in the archive
+/// can change.
+///
+/// # Examples
+///
+/// ```
+/// use extractor::safety::validate_symlink_target;
+/// use std::path::Path;
+///
+/// assert!(validate_symlink_target(Path::new("lib"), "libfoo.so.1").is_ok());
+/// assert!(validate_symlink_target(Path::new("bin"), "../lib/libfoo.so").is_ok());
+///
+/// // Absolute and escaping targets
+/// assert!(validate_symlink_target(Path::new("bin"), "/etc/passwd").is_err());
+/// assert!(validate_symlink_target(Path::new("bin"), "../../etc/passwd").is_err());
+/// assert!(validate_symlink_target(Path::new(""), "lib/../..").is_err());
+/// ```
+pub fn validate_symlink_target(link_dir: &Path, target: &str) -> Result<(), SecurityError> {
+    let escape = || SecurityError::SymlinkEscape(target.to_string());
+    if target.is_empty() || target.contains('\0') {
+        return Err(escape());
+    }
+
+    let mut depth = link_dir
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .count();
+    let mut descended = false;
+    for component in Path::new(target).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if !descended && depth > 0 => depth -= 1,
+            Component::Normal(_) => descended = true,
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(escape())
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Extended attribute namespaces that change how the system treats a file,
+/// rather than describing it.
+const UNSAFE_XATTR_PREFIXES: [&str; 4] = [
+    // SELinux labels, file capabilities, IMA signatures
+    "security.",
+    // Readable and writable only by root
+    "trusted.",
+    // ACLs
+    "system.",
+    // Setting it would let an archive clear the Gatekeeper check
+    "com.apple.quarantine",
+];
+
+/// Determines if an extended attribute recorded in an archive is safe to
+/// restore by default.
+///
+/// Attributes in the `security.`, `trusted.` and `system.` namespaces grant
+/// capabilities, relabel files for SELinux or change their ACLs, and macOS'
+/// `com.apple.quarantine` decides whether Gatekeeper checks a file. Everything
+/// else (`user.` attributes, macOS Finder metadata) only describes the file.
+///
+/// # Examples
+///
+/// ```
+/// use extractor::safety::is_safe_xattr;
+///
+/// assert!(is_safe_xattr("user.mime_type"));
+/// assert!(is_safe_xattr("com.apple.FinderInfo"));
+/// assert!(!is_safe_xattr("security.capability"));
+/// assert!(!is_safe_xattr("com.apple.quarantine"));
+/// ```
+pub fn is_safe_xattr(name: &str) -> bool {
+    !UNSAFE_XATTR_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -261,6 +644,92 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_validate_entry_path_control_characters() {
+        for name in ["bad\nname.txt", "dir\r/file", "tab\there", "esc\x1b[31m"] {
+            let result = validate_entry_path(Path::new(name));
+            assert!(matches!(result, Err(SecurityError::ControlCharacters(_))));
+        }
+
+        assert_eq!(sanitize_control_chars("a\nb\tc").as_deref(), Some("a_b_c"));
+        assert_eq!(sanitize_control_chars("plain/name.txt"), None);
+    }
+
+    #[test]
+    fn test_normalize_unicode() {
+        let composed = Path::new("Fran\u{e7}ais/r\u{e9}sum\u{e9}.txt");
+        let decomposed = Path::new("Franc\u{327}ais/re\u{301}sume\u{301}.txt");
+        assert_eq!(
+            normalize_unicode(decomposed, NormalizationForm::Nfc).as_deref(),
+            Some(composed)
+        );
+        assert_eq!(
+            normalize_unicode(composed, NormalizationForm::Nfd).as_deref(),
+            Some(decomposed)
+        );
+        assert_eq!(normalize_unicode(composed, NormalizationForm::Nfc), None);
+        assert_eq!(normalize_unicode(decomposed, NormalizationForm::Keep), None);
+        let plain = Path::new("plain.txt");
+        assert_eq!(normalize_unicode(plain, NormalizationForm::Nfd), None);
+    }
+
+    #[test]
+    fn test_sanitize_names() {
+        let all = NameSanitizer {
+            trailing_dots: true,
+            leading_dashes: true,
+            max_name_bytes: Some(16),
+        };
+        assert_eq!(
+            sanitize_names(Path::new("--help/dots..."), &all),
+            Some(PathBuf::from("_-help/dots___"))
+        );
+        assert_eq!(
+            sanitize_names(Path::new("a-very-long-file-name.txt"), &all),
+            Some(PathBuf::from("a-very-long-.txt"))
+        );
+        // Cut on a character boundary; an extension longer than half is dropped
+        assert_eq!(
+            sanitize_names(Path::new("ééééééééé.tar"), &all),
+            Some(PathBuf::from("éééééé.tar"))
+        );
+        assert_eq!(
+            sanitize_names(Path::new("x.verylongextension"), &all),
+            Some(PathBuf::from("x.verylongextens"))
+        );
+
+        let default = NameSanitizer::default();
+        assert_eq!(sanitize_names(Path::new("-rf/end. "), &default), None);
+        let long = "n".repeat(300);
+        let shortened = sanitize_names(Path::new(&long), &default).unwrap();
+        assert_eq!(shortened.as_os_str().len(), 255);
+    }
+
+    #[test]
+    fn test_sanitize_windows_names() {
+        let reserved = ["con", "Aux.tar.gz", "LPT1.txt", "com9 .log", "dir/nul"];
+        for name in reserved.into_iter().chain(["a|b", "end."]) {
+            assert!(!is_portable_path(Path::new(name)), "{}", name);
+        }
+        for name in ["console", "auxiliary.txt", "COM10", "LPT", "dir/.hidden"] {
+            assert!(is_portable_path(Path::new(name)), "{}", name);
+        }
+
+        assert_eq!(
+            sanitize_windows_names(Path::new("prn/Aux.tar.gz")),
+            Some(PathBuf::from("prn_/Aux_.tar.gz"))
+        );
+        assert_eq!(
+            sanitize_windows_names(Path::new("notes <draft>*.txt")),
+            Some(PathBuf::from("notes _draft__.txt"))
+        );
+        assert_eq!(
+            sanitize_windows_names(Path::new("trailing. ")),
+            Some(PathBuf::from("trailing__"))
+        );
+        assert_eq!(sanitize_windows_names(Path::new("dir/plain.txt")), None);
+    }
+
     #[test]
     fn test_check_size_limits_within() {
         // Within limit
@@ -306,7 +775,7 @@ mod tests {
     #[test]
     fn test_is_safe_entry_type_with_symlinks() {
         let mut options = ExtractOptions::default();
-        options.allow_symlinks = true;
+        options.symlink_policy = SymlinkPolicy::Preserve;
 
         assert!(is_safe_entry_type(EntryType::Symlink, &options));
         assert!(!is_safe_entry_type(EntryType::Hardlink, &options));
@@ -324,7 +793,7 @@ mod tests {
     #[test]
     fn test_is_safe_entry_type_with_both() {
         let mut options = ExtractOptions::default();
-        options.allow_symlinks = true;
+        options.symlink_policy = SymlinkPolicy::Preserve;
         options.allow_hardlinks = true;
 
         assert!(is_safe_entry_type(EntryType::Symlink, &options));
@@ -384,6 +853,45 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_validate_entry_path_limits() {
+        // 10,000 levels of nesting stop at the default depth limit
+        let deep = "d/".repeat(10_000) + "file.txt";
+        assert!(matches!(
+            validate_entry_path(Path::new(&deep)),
+            Err(SecurityError::PathTooDeep(_))
+        ));
+
+        let long = format!("{}/file.txt", "x".repeat(5000));
+        assert!(matches!(
+            validate_entry_path(Path::new(&long)),
+            Err(SecurityError::PathTooLong(_))
+        ));
+        assert!(
+            validate_entry_path_with_limits(Path::new(&long), &PathLimits::unlimited()).is_ok()
+        );
+
+        let limits = PathLimits {
+            max_depth: Some(3),
+            max_component_bytes: Some(8),
+            max_path_bytes: Some(20),
+        };
+        let check = |path: &str| validate_entry_path_with_limits(Path::new(path), &limits);
+        assert!(check("./a/b/c.txt").is_ok());
+        assert!(matches!(
+            check("a/b/c/d.txt"),
+            Err(SecurityError::PathTooDeep(_))
+        ));
+        assert!(matches!(
+            check("a/toolongname"),
+            Err(SecurityError::PathTooLong(_))
+        ));
+        assert!(matches!(
+            check("abcdefg/abcdefg/abcdefg"),
+            Err(SecurityError::PathTooLong(_))
+        ));
+    }
+
     #[test]
     fn test_validate_entry_path_edge_cases() {
         // Empty path components should be handled
@@ -427,6 +935,25 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_validate_symlink_target() {
+        // Targets next to and below the link
+        assert!(validate_symlink_target(Path::new(""), "file.txt").is_ok());
+        assert!(validate_symlink_target(Path::new("a/b"), "./c/d").is_ok());
+
+        // Leading ".." up to the output directory
+        assert!(validate_symlink_target(Path::new("a/b"), "../../c").is_ok());
+        assert!(validate_symlink_target(Path::new("a/b"), "../../..").is_err());
+        assert!(validate_symlink_target(Path::new(""), "..").is_err());
+
+        // ".." after a component, resolved through whatever that becomes
+        assert!(validate_symlink_target(Path::new("a"), "b/../c").is_err());
+
+        // Absolute and empty targets
+        assert!(validate_symlink_target(Path::new("a"), "/a").is_err());
+        assert!(validate_symlink_target(Path::new("a"), "").is_err());
+    }
+
     #[test]
     fn test_check_size_limits_boundary() {
         // Exact limit should pass
@@ -0,0 +1,103 @@
+//! Content type detection for archive entries.
+//!
+//! Files are classified from their first bytes and, for types without a
+//! reliable signature (most scripts), from their extension. The content policy
+//! in [`ExtractOptions`](crate::ExtractOptions) uses this to keep executables and
+//! scripts out of extractions from untrusted archives.
+
+use crate::types::ContentKind;
+use std::path::Path;
+
+/// Number of leading bytes read from each file to detect its content type.
+pub const SNIFF_LEN: usize = 512;
+
+/// Leading bytes of native executables and libraries.
+const EXECUTABLE_MAGIC: &[&[u8]] = &[
+    b"\x7fELF",          // ELF (Linux, BSD)
+    b"MZ",               // PE (Windows .exe, .dll)
+    b"\xfe\xed\xfa\xce", // Mach-O 32-bit
+    b"\xfe\xed\xfa\xcf", // Mach-O 64-bit
+    b"\xce\xfa\xed\xfe", // Mach-O 32-bit, little endian
+    b"\xcf\xfa\xed\xfe", // Mach-O 64-bit, little endian
+    b"\xca\xfe\xba\xbe", // Mach-O universal binary or Java class
+];
+
+/// Extensions of executables that may lack a recognizable header.
+const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    "exe", "dll", "com", "scr", "msi", "sys", "so", "dylib", "jar", "apk",
+];
+
+/// Extensions of files run by an interpreter or the shell.
+const SCRIPT_EXTENSIONS: &[&str] = &[
+    "sh",
+    "bash",
+    "zsh",
+    "csh",
+    "ksh",
+    "command",
+    "bat",
+    "cmd",
+    "ps1",
+    "psm1",
+    "vbs",
+    "vbe",
+    "js",
+    "jse",
+    "wsf",
+    "wsh",
+    "hta",
+    "py",
+    "pl",
+    "rb",
+    "php",
+    "applescript",
+    "scpt",
+];
+
+/// Classify a file from its name and its first bytes (up to [`SNIFF_LEN`]).
+pub fn sniff_content(name: &str, head: &[u8]) -> ContentKind {
+    if EXECUTABLE_MAGIC.iter().any(|magic| head.starts_with(magic)) {
+        return ContentKind::Executable;
+    }
+    if head.starts_with(b"#!") {
+        return ContentKind::Script;
+    }
+
+    let extension = Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+
+    match extension.as_deref() {
+        Some(ext) if EXECUTABLE_EXTENSIONS.contains(&ext) => ContentKind::Executable,
+        Some(ext) if SCRIPT_EXTENSIONS.contains(&ext) => ContentKind::Script,
+        _ => ContentKind::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_content() {
+        assert_eq!(
+            sniff_content("bin/tool", b"\x7fELF\x02\x01\x01"),
+            ContentKind::Executable
+        );
+        assert_eq!(
+            sniff_content("setup.txt", b"MZ\x90\x00"),
+            ContentKind::Executable
+        );
+        assert_eq!(
+            sniff_content("run", b"#!/bin/sh\necho hi\n"),
+            ContentKind::Script
+        );
+        assert_eq!(
+            sniff_content("INSTALL.BAT", b"@echo off"),
+            ContentKind::Script
+        );
+        assert_eq!(sniff_content("lib.DLL", b""), ContentKind::Executable);
+        assert_eq!(sniff_content("notes.txt", b"hello"), ContentKind::Other);
+    }
+}
@@ -1,6 +1,15 @@
 //! Type definitions for archive extraction.
 
+use crate::charset::FilenameEncoding;
+use crate::error::ExtractError;
+use crate::safety::EntryType;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
 use std::time::Duration;
 use ts_rs::TS;
 
@@ -14,6 +23,9 @@ pub struct ArchiveEntry {
     /// Whether this entry is a directory
     pub is_directory: bool,
 
+    /// What the entry is: a file, directory, link or special file
+    pub kind: EntryType,
+
     /// Uncompressed size in bytes
     #[ts(type = "number")]
     pub size: u64,
@@ -21,14 +33,184 @@ pub struct ArchiveEntry {
     /// Compressed size in bytes (if available)
     #[ts(optional, type = "number")]
     pub compressed_size: Option<u64>,
+
+    /// Last modification time in seconds since the Unix epoch (if recorded)
+    #[ts(optional, type = "number")]
+    pub modified: Option<u64>,
+}
+
+/// Field to sort archive entries by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    /// Entry path
+    Path,
+
+    /// Uncompressed size
+    Size,
+
+    /// Last modification time
+    Modified,
+}
+
+/// Sort order for an archive's entry list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct EntrySort {
+    /// Field to sort by
+    pub key: SortKey,
+
+    /// Whether to sort from largest/latest/last to smallest/earliest/first
+    pub descending: bool,
+}
+
+/// Compression applied to a tar archive or a single compressed file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// Not compressed
+    None,
+
+    /// gzip (.gz)
+    Gzip,
+
+    /// bzip2 (.bz2)
+    Bzip2,
+
+    /// xz (.xz)
+    Xz,
+}
+
+/// Archive format.
+///
+/// Displays and serializes as the format's name ("ZIP", "TAR.GZ", "7Z", ...),
+/// which [`str::parse`] turns back into the format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(
+    export,
+    export_to = "../../../src/lib/bindings/",
+    type = r#""ZIP" | "TAR" | "TAR.GZ" | "TAR.BZ2" | "TAR.XZ" | "GZIP" | "BZIP2" | "XZ" | "7Z" | "RAR""#
+)]
+#[serde(into = "String", try_from = "String")]
+pub enum Format {
+    /// ZIP archive (including ZIP64 and split sets)
+    Zip,
+
+    /// Tar archive, optionally compressed
+    Tar(Compression),
+
+    /// Single gzip-compressed file
+    Gzip,
+
+    /// Single bzip2-compressed file
+    Bzip2,
+
+    /// Single xz-compressed file
+    Xz,
+
+    /// 7-Zip archive
+    SevenZ,
+
+    /// RAR archive (read-only)
+    Rar,
+}
+
+impl Format {
+    /// Every format, in the order they are listed to users.
+    pub const ALL: [Format; 10] = [
+        Format::Zip,
+        Format::Tar(Compression::None),
+        Format::Tar(Compression::Gzip),
+        Format::Tar(Compression::Bzip2),
+        Format::Tar(Compression::Xz),
+        Format::Gzip,
+        Format::Bzip2,
+        Format::Xz,
+        Format::SevenZ,
+        Format::Rar,
+    ];
+
+    /// Name of the format, as displayed and serialized.
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Zip => "ZIP",
+            Format::Tar(Compression::None) => "TAR",
+            Format::Tar(Compression::Gzip) => "TAR.GZ",
+            Format::Tar(Compression::Bzip2) => "TAR.BZ2",
+            Format::Tar(Compression::Xz) => "TAR.XZ",
+            Format::Gzip => "GZIP",
+            Format::Bzip2 => "BZIP2",
+            Format::Xz => "XZ",
+            Format::SevenZ => "7Z",
+            Format::Rar => "RAR",
+        }
+    }
+
+    /// Compression wrapped around the archive data (none for formats that
+    /// compress each entry themselves).
+    pub fn compression(self) -> Compression {
+        match self {
+            Format::Tar(compression) => compression,
+            Format::Gzip => Compression::Gzip,
+            Format::Bzip2 => Compression::Bzip2,
+            Format::Xz => Compression::Xz,
+            Format::Zip | Format::SevenZ | Format::Rar => Compression::None,
+        }
+    }
+
+    /// Whether the format is a single compressed file rather than an archive
+    /// of entries.
+    pub fn is_single_file(self) -> bool {
+        matches!(self, Format::Gzip | Format::Bzip2 | Format::Xz)
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for Format {
+    type Err = ExtractError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Format::ALL
+            .into_iter()
+            .find(|format| format.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| ExtractError::UnsupportedFormat(name.to_string()))
+    }
+}
+
+impl From<Format> for String {
+    fn from(format: Format) -> Self {
+        format.name().to_string()
+    }
+}
+
+impl TryFrom<String> for Format {
+    type Error = ExtractError;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        name.parse()
+    }
 }
 
+/// Version of the serialized [`ArchiveInfo`] layout (`unarchive probe --json`).
+///
+/// Field names are part of the format: adding a field keeps the version, while
+/// renaming, removing or changing the meaning of one bumps it.
+pub const PROBE_SCHEMA_VERSION: u32 = 1;
+
 /// Metadata information about an archive.
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[ts(export, export_to = "../../../src/lib/bindings/")]
 pub struct ArchiveInfo {
-    /// Archive format (e.g., "ZIP", "TAR", "7Z")
-    pub format: String,
+    /// Version of this layout, see [`PROBE_SCHEMA_VERSION`]
+    pub schema_version: u32,
+
+    /// Archive format
+    pub format: Format,
 
     /// Number of entries in the archive
     #[ts(type = "number")]
@@ -42,91 +224,1746 @@ pub struct ArchiveInfo {
     #[ts(optional, type = "number")]
     pub uncompressed_estimate: Option<u64>,
 
+    /// Whether `uncompressed_estimate` comes from a compression trailer or index
+    /// rather than from the listed entries, and so may be approximate
+    pub uncompressed_is_estimate: bool,
+
     /// Whether the archive is password-protected
     pub encrypted: bool,
 
+    /// Whether the entry list itself is encrypted (RAR/7z header encryption), in
+    /// which case no entries are listed until the archive is probed with a password
+    pub header_encrypted: bool,
+
+    /// Name of the single top-level folder containing every entry (if any)
+    #[ts(optional)]
+    pub single_root: Option<String>,
+
+    /// Whether extraction would scatter several items directly into the output
+    /// directory (no common top-level folder)
+    pub is_tarbomb: bool,
+
+    /// Aggregate statistics computed from the entry list
+    pub summary: ArchiveSummary,
+
+    /// Rough memory and disk space needed to extract the archive
+    pub resources: ResourceHint,
+
+    /// Result of the structural integrity check (only set by `probe_deep`)
+    #[ts(optional)]
+    pub integrity: Option<Integrity>,
+
+    /// Result of checking the archive against a checksum file next to it, if
+    /// there is one (only set by `probe_deep`)
+    #[serde(default)]
+    #[ts(optional)]
+    pub checksum: Option<ChecksumCheck>,
+
     /// List of all entries in the archive
     pub entry_list: Vec<ArchiveEntry>,
 }
 
+/// Aggregate statistics about an archive's contents.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ArchiveSummary {
+    /// Number of file entries
+    #[ts(type = "number")]
+    pub file_count: u64,
+
+    /// Number of distinct directories, including ones only implied by file paths
+    #[ts(type = "number")]
+    pub directory_count: u64,
+
+    /// The largest file in the archive (if any)
+    #[ts(optional)]
+    pub largest_entry: Option<ArchiveEntry>,
+
+    /// Total compressed size divided by total uncompressed size, over files whose
+    /// compressed size is known (if any)
+    #[ts(optional)]
+    pub average_compression_ratio: Option<f64>,
+
+    /// File counts and sizes grouped by extension, largest total size first
+    pub extensions: Vec<ExtensionStats>,
+}
+
+/// Outcome of a structural integrity check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum Integrity {
+    /// Every structure checked is intact
+    Ok,
+
+    /// The archive ends before its structures say it should
+    Truncated,
+
+    /// A header, checksum or volume is damaged or missing
+    Corrupted {
+        /// Description of the first problem found
+        detail: String,
+    },
+}
+
+/// Completeness of a multi-volume archive (split 7z/ZIP or multi-part RAR).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct VolumeReport {
+    /// Paths of the volumes making up the set, in order, including missing ones
+    pub volumes: Vec<String>,
+
+    /// Volumes that are missing or damaged, in order (empty if the set is complete)
+    pub problems: Vec<VolumeProblem>,
+}
+
+impl VolumeReport {
+    /// Whether every volume is present and consistent.
+    pub fn is_complete(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// A missing or damaged volume of a multi-volume archive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct VolumeProblem {
+    /// Path of the volume
+    pub path: String,
+
+    /// What is wrong with it
+    pub issue: VolumeIssue,
+}
+
+impl fmt::Display for VolumeProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.issue {
+            VolumeIssue::Missing => write!(f, "Missing volume: {}", self.path),
+            VolumeIssue::Corrupted { detail } => {
+                write!(f, "Damaged volume: {} ({})", self.path, detail)
+            }
+        }
+    }
+}
+
+/// Problem found with one volume of a multi-volume archive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum VolumeIssue {
+    /// The volume file doesn't exist
+    Missing,
+
+    /// The volume's header doesn't fit its place in the set
+    Corrupted {
+        /// Description of the problem
+        detail: String,
+    },
+}
+
+/// Estimated resources needed to extract an archive, for preflight checks.
+///
+/// Figures are rough upper bounds derived from the listed sizes and the way each
+/// format is extracted; `None` means the uncompressed size is unknown.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ResourceHint {
+    /// Peak memory in bytes (the decoder's working memory, as every format is
+    /// streamed)
+    #[ts(optional, type = "number")]
+    pub peak_memory_bytes: Option<u64>,
+
+    /// Temporary disk space in bytes (none of the supported formats currently
+    /// need any, as entries are streamed into place)
+    #[ts(optional, type = "number")]
+    pub temp_space_bytes: Option<u64>,
+
+    /// Disk space in bytes needed in the output directory
+    #[ts(optional, type = "number")]
+    pub output_space_bytes: Option<u64>,
+
+    /// Files and directories created in the output directory, each using one
+    /// inode (if the entries could be listed)
+    #[ts(optional, type = "number")]
+    pub inodes_needed: Option<u64>,
+}
+
+/// What extracting an archive into a particular destination takes.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ExtractionPlan {
+    /// Estimated resources needed
+    pub resources: ResourceHint,
+
+    /// Problems with the destination to tell the user about before extracting
+    /// (e.g. a RAM-backed filesystem too small for the archive)
+    pub warnings: Vec<String>,
+}
+
+/// File count and size for one file extension.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ExtensionStats {
+    /// Lower-cased extension without the dot (empty for files without one)
+    pub extension: String,
+
+    /// Number of files with this extension
+    #[ts(type = "number")]
+    pub count: u64,
+
+    /// Total uncompressed size of those files in bytes
+    #[ts(type = "number")]
+    pub total_size: u64,
+}
+
 /// Options for extracting an archive.
 #[derive(Debug, Clone)]
 pub struct ExtractOptions {
     /// How to handle file conflicts during extraction
     pub overwrite: OverwriteMode,
 
+    /// Name for files written next to existing ones with
+    /// `OverwriteMode::Rename`, built from `{stem}`, `{n}` (counting from 1),
+    /// `{ext}` and `{timestamp}` (seconds since the Unix epoch), e.g.
+    /// `"{stem}-{n}.{ext}"`. For files without an extension the `.` before
+    /// `{ext}` is dropped. Templates without `{n}` get a single try. Unset
+    /// names them `"{stem} ({n}).{ext}"`.
+    pub rename_template: Option<String>,
+
+    /// Decides each conflict with `OverwriteMode::Ask` (see
+    /// [`OverwritePrompt`]). Without one, conflicts fail as with
+    /// `OverwriteMode::Error`.
+    pub overwrite_prompt: Option<OverwritePrompt>,
+
     /// Maximum total extracted size in bytes (default: 20 GB)
     pub size_limit_bytes: Option<u64>,
 
+    /// Maximum size of a single extracted file in bytes. Files recording a
+    /// larger size are handled as `oversized_entries` says; one growing past
+    /// it while it's written fails the extraction with
+    /// `ExtractError::EntryTooLarge` either way. Unset allows any size.
+    pub max_entry_size_bytes: Option<u64>,
+
+    /// What to do with files over `max_entry_size_bytes`
+    pub oversized_entries: OversizePolicy,
+
+    /// Largest ratio of an entry's size to its compressed size, where that is
+    /// known (ZIP entries and single compressed files such as `.gz`). An entry
+    /// declaring more, or expanding to more while it's written, fails the
+    /// extraction with `SecurityError::SuspectedArchiveBomb`. Unset allows
+    /// any ratio.
+    pub max_compression_ratio: Option<u32>,
+
+    /// Largest ratio of everything extracted to the size of the archive (all
+    /// volumes of a volume set), checked like `max_compression_ratio`. Unlike
+    /// that, it also covers formats that don't record compressed sizes, such
+    /// as compressed TAR and 7z. Unset allows any ratio.
+    pub max_expansion_ratio: Option<u32>,
+
     /// Number of leading path components to strip from extracted files
     pub strip_components: u32,
 
-    /// Whether to allow extraction of symbolic links
-    pub allow_symlinks: bool,
+    /// Whether to extract into a new folder inside the output directory named
+    /// after the archive (see [`SubfolderPolicy`]), listed in
+    /// `ExtractStats::subfolder`. A name already taken gets " (1)", " (2)",
+    /// ... appended.
+    pub create_subfolder: SubfolderPolicy,
 
-    /// Whether to allow extraction of hard links
+    /// What to do with the symbolic links stored in TAR, ZIP and 7z archives.
+    /// Unless they are skipped, a link whose target is absolute or leads
+    /// outside the output directory fails the extraction with
+    /// `SecurityError::SymlinkEscape` (see
+    /// [`crate::safety::validate_symlink_target`]).
+    pub symlink_policy: SymlinkPolicy,
+
+    /// Whether to recreate the hard links stored in TAR archives. A link is
+    /// made to the file extracted for the entry it names, or is a copy of it
+    /// where the filesystem can't link the two (across filesystems, FAT). Links
+    /// to entries that weren't extracted are skipped with a warning, as are
+    /// all links when disabled.
     pub allow_hardlinks: bool,
 
     /// Password for encrypted archives
     pub password: Option<String>,
+
+    /// Whether to check the destination filesystem's case sensitivity before
+    /// extracting. When enabled, entries whose paths differ only by case are
+    /// reported only if the destination is case-insensitive; when disabled they
+    /// are always reported.
+    pub detect_case_sensitivity: bool,
+
+    /// Whether to write a provenance file (`.extraction-info.json`) recording the
+    /// source archive, its hash, the date, tool version and options into the
+    /// output directory
+    pub write_provenance: bool,
+
+    /// Whether to hash every file with SHA-256 as its data is written, listing
+    /// the digests in `ExtractStats::sha256`. Files the RAR library writes
+    /// itself are read back once written instead.
+    pub hash_files: bool,
+
+    /// Whether to also write the digests into a `SHA256SUMS` file in the output
+    /// directory, which `sha256sum -c` checks from there. Implies `hash_files`;
+    /// an extracted file of the same name is replaced.
+    pub write_sha256sums: bool,
+
+    /// Whether to apply the Unix permission bits recorded in ZIP, TAR and 7z
+    /// entries to extracted files and directories. When disabled, they get the
+    /// default mode for new files. `None` preserves them for TAR archives only,
+    /// whose modes are reliably set by the tools that create them. Has no
+    /// effect on other platforms.
+    pub preserve_permissions: Option<bool>,
+
+    /// Whether to mask preserved permission bits with the process umask, so an
+    /// archive can't create files more permissive than the user allows (e.g.
+    /// 0777 entries from archives authored on Windows)
+    pub apply_umask: bool,
+
+    /// Whether to restore the extended attributes recorded in PAX tar entries
+    /// (`SCHILY.xattr.*` records, as written by GNU tar and bsdtar). Attributes
+    /// that [`crate::safety::is_safe_xattr`] rejects are skipped unless
+    /// `allow_unsafe_xattrs` is set. Filesystems without extended attributes
+    /// are skipped quietly. Only supported on Linux and macOS.
+    pub preserve_xattrs: bool,
+
+    /// Whether to also restore security-relevant extended attributes (SELinux
+    /// labels, file capabilities, ACLs, the macOS quarantine flag) when
+    /// preserving them. Only for archives from trusted sources, such as backups.
+    pub allow_unsafe_xattrs: bool,
+
+    /// Whether to give extracted files and directories the user and group IDs
+    /// recorded in TAR entries, as when restoring a backup. Only root can give
+    /// files away, so this is ignored with a warning for other users. IDs are
+    /// restored as recorded; the user and group names aren't looked up.
+    pub preserve_ownership: bool,
+
+    /// Whether extracted files and directories get the modification times
+    /// recorded in the archive or the time they are extracted
+    pub timestamp_policy: TimestampPolicy,
+
+    /// Fixed mode for every extracted file, overriding the archive's metadata and
+    /// the umask (Unix only)
+    pub file_mode: Option<u32>,
+
+    /// Fixed mode for every directory created during extraction (Unix only). The
+    /// mode is applied as each directory is created, so it must leave the
+    /// directory writable by its owner.
+    pub dir_mode: Option<u32>,
+
+    /// Whether to list the archive before extracting and fail early with
+    /// `ExtractError::InsufficientInodes` if the destination can't hold that
+    /// many files and directories. Archives with many entries also get a warning.
+    pub check_free_inodes: bool,
+
+    /// Whether to list the archive before extracting and compare the size of
+    /// its files with the free space on the destination, failing early with
+    /// `ExtractError::InsufficientSpace` or only warning when it's short (see
+    /// [`FreeSpaceCheck`]). Archives whose size can't be told in advance, such
+    /// as single compressed files, aren't checked.
+    pub check_free_space: FreeSpaceCheck,
+
+    /// Whether to replace control characters (NUL, newlines, ...) in entry names
+    /// with `_`, listing each renamed entry in `ExtractStats::renamed`. When
+    /// disabled, such entries fail with `SecurityError::ControlCharacters`.
+    pub sanitize_control_chars: bool,
+
+    /// Limits on how deep and long entry paths may be; paths beyond them fail
+    /// with `SecurityError::PathTooDeep` or `PathTooLong` (see [`PathLimits`])
+    pub path_limits: PathLimits,
+
+    /// Fixes for entry names that are legal but awkward to use: trailing dots
+    /// and spaces, leading dashes and overlong names (see [`NameSanitizer`]).
+    /// The default only shortens names longer than 255 bytes.
+    pub sanitize_names: NameSanitizer,
+
+    /// What to do with entry names Windows can't create, such as `CON`,
+    /// `aux.txt` or names containing `<>:"|?*` (see [`WindowsNamePolicy`]).
+    /// Defaults to `Rename` on Windows and `Allow` elsewhere; set it to `Rename`
+    /// or `Strict` to keep an extracted tree portable to Windows.
+    pub windows_names: WindowsNamePolicy,
+
+    /// Encoding of the ZIP entry names not marked as UTF-8, overriding the
+    /// one detected from them (see [`FilenameEncoding`]). Such names are
+    /// common in archives made on Windows, in its OEM code page.
+    pub filename_encoding: Option<FilenameEncoding>,
+
+    /// Unicode normalization form entry names are converted to, so names
+    /// written on different platforms don't extract as visually identical
+    /// duplicates and the entry filter sees one spelling of each (see
+    /// [`NormalizationForm`])
+    pub normalize_names: NormalizationForm,
+
+    /// Selects which entries to extract; entries it rejects are skipped without
+    /// being written (see [`EntryFilter`])
+    pub entry_filter: Option<EntryFilter>,
+
+    /// Inspects each file's content before it is written, deciding whether to
+    /// write it, skip it or abort the extraction (see [`PreWriteHook`])
+    pub pre_write_hook: Option<PreWriteHook>,
+
+    /// Rewrites each entry's path (after `strip_components`) before it is joined
+    /// to the output directory (see [`PathMapper`])
+    pub map_path: Option<PathMapper>,
+
+    /// Told what was done with every entry as it is handled, including
+    /// directories and entries that weren't written (see [`EntryObserver`])
+    pub on_entry: Option<EntryObserver>,
+
+    /// Receives structured progress events, with entry counts and totals,
+    /// alongside the progress callback (see [`ProgressListener`]). To know the
+    /// totals, ZIP, 7-Zip and RAR archives are listed before extracting, in
+    /// the `Scanning` phase; the size of a tar archive is estimated from its
+    /// compression trailer.
+    pub on_progress: Option<ProgressListener>,
+
+    /// Blocks or quarantines files whose detected content type is denied (see
+    /// [`ContentPolicy`]). Runs after the pre-write hook.
+    pub content_policy: Option<ContentPolicy>,
+
+    /// Whether to copy the archive's `com.apple.quarantine` attribute, set on
+    /// downloaded files, to every extracted file and folder so Gatekeeper still
+    /// checks them (as Archive Utility does). Only has an effect on macOS.
+    pub propagate_quarantine: bool,
+
+    /// Time budget for the whole extraction. When it runs out the cancel flag
+    /// is set, and the extraction stops at the next entry with
+    /// `ExtractError::TimedOut`.
+    pub max_duration: Option<Duration>,
+
+    /// Whether to write each file under a hidden temporary name (`.name.partial`)
+    /// in its destination folder and rename it into place once complete, so a
+    /// crash, cancellation or full disk never leaves a truncated file that looks
+    /// valid
+    pub atomic_writes: bool,
+
+    /// Whether to read back every file written once the extraction is done and
+    /// compare it with the archive: its CRC-32 where the archive records one
+    /// (ZIP, 7-Zip, RAR), its size otherwise. Files that differ are listed in
+    /// `ExtractStats::mismatches`.
+    pub verify_after_extract: bool,
+
+    /// Whether to hash the archive before extracting it and compare the digest
+    /// with a checksum file next to it (`archive.zip.sha512`, `.sha256` or
+    /// `.md5`, the first found), failing with `ExtractError::ChecksumMismatch`
+    /// if they differ. The result is in `ExtractStats::checksum`; archives
+    /// without a checksum file are extracted as usual.
+    pub verify_checksum: bool,
+
+    /// Number of chunks of decoded data (256 KiB each) that may wait for the
+    /// thread writing files out. Decoding runs at most that far ahead of the
+    /// disk before it waits; `0` writes every file on the decoding thread.
+    pub write_queue_depth: usize,
+
+    /// Number of threads decoding and writing the entries of a ZIP archive
+    /// opened from a file, each entry on one thread. With `1` the entries are
+    /// handled as in other formats; journaled extractions always use one.
+    pub threads: usize,
+
+    /// Whether to extract into a hidden staging folder next to the output
+    /// directory and rename it into place once everything is extracted, so a
+    /// failed or cancelled extraction leaves the destination untouched. The
+    /// output directory must be new or empty; otherwise the extraction fails
+    /// with `ExtractError::AlreadyExists` before anything is written.
+    pub transactional: bool,
+
+    /// Whether to remove the files and folders an extraction created when it
+    /// fails or is cancelled. When disabled, the files left behind are listed
+    /// in the `ExtractStats::partial_files` of an `ExtractError::Partial`.
+    pub cleanup_on_failure: bool,
+
+    /// What to do when an entry can't be extracted: a damaged entry, an unsafe
+    /// path, a file that can't be written. With `ErrorPolicy::Skip` the
+    /// failure is recorded in `ExtractStats::errors` and extraction goes on
+    /// with the next entry; files are then written on the decoding thread.
+    /// Cancellation, limits, a wrong password, a full disk or an archive that
+    /// can't be read any further stop the extraction either way.
+    pub on_error: ErrorPolicy,
+
+    /// Whether to write files around the page cache (`O_DIRECT` on Linux,
+    /// `F_NOCACHE` on macOS), so a huge extraction doesn't push everything else
+    /// out of the cache. Falls back to ordinary writes where the filesystem
+    /// doesn't allow it; RAR entries are always written by the unrar library.
+    pub bypass_page_cache: bool,
+
+    /// Size in bytes of the buffers archive files are read through and
+    /// extracted files are written through, so archives of many small files
+    /// don't cost a system call per header or write. `0` reads and writes
+    /// unbuffered.
+    pub buffer_size: usize,
+
+    /// Whether to leave runs of zeros in extracted files as holes instead of
+    /// writing them, so disk images and database dumps only take up the space
+    /// of their data. Covers the sparse regions of GNU tar sparse entries too,
+    /// which are read back as zeros. Sparse files aren't preallocated or
+    /// written around the page cache, and RAR entries are always written in
+    /// full. Only saves space on filesystems that support sparse files.
+    pub sparse_files: bool,
+
+    /// Folder for the temporary files an extraction needs: downloads spooled
+    /// before extracting, archives carved out of other files and RAR data read
+    /// from a stream. When unset they go in the folder holding the output
+    /// directory, under hidden names, so they're on the same volume instead of
+    /// in the system temp directory, which may be small or encrypted. The
+    /// staging folder of transactional extractions is always next to the
+    /// output directory, so that it can be renamed into place.
+    pub temp_dir: Option<PathBuf>,
+
+    /// Whether to read ZIP and 7-Zip archives from a memory map of the file
+    /// instead of through `buffer_size` buffers, saving a system call per read
+    /// when picking entries out of a large archive. Falls back to buffered
+    /// reads where the file can't be mapped. The archive must not be truncated
+    /// while it's extracted: reading a part of the map the file no longer
+    /// backs crashes the process with `SIGBUS`.
+    pub memory_map: bool,
+
+    /// Whether to keep a journal next to the output directory while extracting
+    /// (see [`crate::journal`]), so an extraction interrupted by a crash can be
+    /// resumed or rolled back. Files are then written on the decoding thread,
+    /// and archives read from a stream aren't journaled.
+    pub journal: bool,
+
+    /// Whether to read the end of a tar archive leniently: a block that isn't a
+    /// valid header where the next entry should start ends the archive instead
+    /// of failing the extraction, and anything but zero padding after the end
+    /// marker is warned about.
+    pub tolerate_trailing_data: bool,
+
+    /// Whether to read a tar archive past its end marker, extracting the
+    /// members of any tar archives concatenated after it (`tar -A`, appending
+    /// tools) instead of only the first archive's.
+    pub read_concatenated_tar: bool,
 }
 
+/// Default for [`ExtractOptions::buffer_size`].
+pub const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
+
 impl Default for ExtractOptions {
     fn default() -> Self {
         Self {
             overwrite: OverwriteMode::Rename,
+            rename_template: None,
+            overwrite_prompt: None,
             size_limit_bytes: Some(20 * 1024 * 1024 * 1024), // 20 GB
+            max_entry_size_bytes: None,
+            oversized_entries: OversizePolicy::Reject,
+            max_compression_ratio: None,
+            max_expansion_ratio: None,
             strip_components: 0,
-            allow_symlinks: false,
+            create_subfolder: SubfolderPolicy::Never,
+            symlink_policy: SymlinkPolicy::Skip,
             allow_hardlinks: false,
             password: None,
+            detect_case_sensitivity: true,
+            write_provenance: false,
+            hash_files: false,
+            write_sha256sums: false,
+            preserve_permissions: None,
+            apply_umask: true,
+            preserve_xattrs: false,
+            allow_unsafe_xattrs: false,
+            preserve_ownership: false,
+            timestamp_policy: TimestampPolicy::Preserve,
+            file_mode: None,
+            dir_mode: None,
+            check_free_inodes: false,
+            check_free_space: FreeSpaceCheck::Off,
+            sanitize_control_chars: true,
+            path_limits: PathLimits::default(),
+            sanitize_names: NameSanitizer::default(),
+            windows_names: WindowsNamePolicy::default(),
+            filename_encoding: None,
+            normalize_names: NormalizationForm::Keep,
+            entry_filter: None,
+            pre_write_hook: None,
+            map_path: None,
+            on_entry: None,
+            on_progress: None,
+            content_policy: None,
+            propagate_quarantine: false,
+            max_duration: None,
+            atomic_writes: true,
+            verify_after_extract: false,
+            verify_checksum: false,
+            write_queue_depth: 8,
+            threads: 1,
+            transactional: false,
+            cleanup_on_failure: true,
+            on_error: ErrorPolicy::Abort,
+            bypass_page_cache: false,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            sparse_files: false,
+            temp_dir: None,
+            memory_map: false,
+            journal: false,
+            tolerate_trailing_data: true,
+            read_concatenated_tar: false,
         }
     }
 }
 
-/// How to handle file conflicts during extraction.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum OverwriteMode {
-    /// Replace existing files
-    Replace,
+impl ExtractOptions {
+    /// Start building options from the defaults (see [`ExtractOptionsBuilder`])
+    pub fn builder() -> ExtractOptionsBuilder {
+        ExtractOptionsBuilder::default()
+    }
 
-    /// Skip files that already exist
-    Skip,
+    /// Whether entry permissions are applied for an archive in `format` (see
+    /// [`ExtractOptions::preserve_permissions`])
+    pub fn preserves_permissions(&self, format: Format) -> bool {
+        self.preserve_permissions
+            .unwrap_or(matches!(format, Format::Tar(_)))
+    }
 
-    /// Rename new files by appending (1), (2), etc.
-    Rename,
+    /// The probe options reading the archive the way these options extract it
+    pub(crate) fn probe_options(&self, cancel_flag: Arc<AtomicBool>) -> ProbeOptions {
+        ProbeOptions {
+            password: self.password.clone(),
+            read_concatenated_tar: self.read_concatenated_tar,
+            filename_encoding: self.filename_encoding,
+            on_progress: None,
+            cancel_flag: Some(cancel_flag),
+        }
+    }
+
+    /// Whether written files are hashed (see [`ExtractOptions::hash_files`])
+    pub(crate) fn hashes_files(&self) -> bool {
+        self.hash_files || self.write_sha256sums
+    }
 }
 
-/// Statistics about a completed extraction operation.
-#[derive(Debug, Clone, Serialize, Deserialize, TS)]
-#[ts(export, export_to = "../../../src/lib/bindings/")]
-pub struct ExtractStats {
-    /// Number of files successfully extracted
-    #[ts(type = "number")]
-    pub files_extracted: u64,
+/// Builds [`ExtractOptions`] one setting at a time, starting from the
+/// defaults.
+///
+/// Options are added from release to release; unlike a struct literal, code
+/// setting them through the builder keeps compiling when they are.
+///
+/// ```
+/// use extractor::{ExtractOptions, OverwriteMode};
+///
+/// let options = ExtractOptions::builder()
+///     .overwrite(OverwriteMode::Skip)
+///     .password("secret")
+///     .strip_components(1)
+///     .build();
+/// assert_eq!(options.password.as_deref(), Some("secret"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptionsBuilder {
+    options: ExtractOptions,
+}
 
-    /// Total bytes written to disk
-    #[ts(type = "number")]
-    pub bytes_written: u64,
+/// Setters for `ExtractOptionsBuilder` taking the option's own type
+macro_rules! setters {
+    ($($name:ident: $ty:ty),* $(,)?) => {
+        $(
+            #[doc = concat!("Set [`ExtractOptions::", stringify!($name), "`]")]
+            pub fn $name(mut self, $name: $ty) -> Self {
+                self.options.$name = $name;
+                self
+            }
+        )*
+    };
+}
 
-    /// Duration of the extraction operation (in seconds)
-    #[serde(with = "duration_serde")]
-    #[ts(type = "number")]
-    pub duration: Duration,
+impl ExtractOptionsBuilder {
+    setters! {
+        overwrite: OverwriteMode,
+        size_limit_bytes: Option<u64>,
+        max_entry_size_bytes: Option<u64>,
+        oversized_entries: OversizePolicy,
+        max_compression_ratio: Option<u32>,
+        max_expansion_ratio: Option<u32>,
+        strip_components: u32,
+        create_subfolder: SubfolderPolicy,
+        symlink_policy: SymlinkPolicy,
+        allow_hardlinks: bool,
+        detect_case_sensitivity: bool,
+        write_provenance: bool,
+        hash_files: bool,
+        write_sha256sums: bool,
+        apply_umask: bool,
+        preserve_xattrs: bool,
+        allow_unsafe_xattrs: bool,
+        preserve_ownership: bool,
+        timestamp_policy: TimestampPolicy,
+        check_free_inodes: bool,
+        check_free_space: FreeSpaceCheck,
+        sanitize_control_chars: bool,
+        path_limits: PathLimits,
+        sanitize_names: NameSanitizer,
+        windows_names: WindowsNamePolicy,
+        normalize_names: NormalizationForm,
+        propagate_quarantine: bool,
+        atomic_writes: bool,
+        verify_after_extract: bool,
+        verify_checksum: bool,
+        write_queue_depth: usize,
+        threads: usize,
+        transactional: bool,
+        cleanup_on_failure: bool,
+        on_error: ErrorPolicy,
+        bypass_page_cache: bool,
+        buffer_size: usize,
+        sparse_files: bool,
+        memory_map: bool,
+        journal: bool,
+        tolerate_trailing_data: bool,
+        read_concatenated_tar: bool,
+    }
 
-    /// Whether the extraction was cancelled
-    pub cancelled: bool,
+    /// Set [`ExtractOptions::rename_template`]
+    pub fn rename_template(mut self, template: impl Into<String>) -> Self {
+        self.options.rename_template = Some(template.into());
+        self
+    }
+
+    /// Set [`ExtractOptions::filename_encoding`]
+    pub fn filename_encoding(mut self, encoding: FilenameEncoding) -> Self {
+        self.options.filename_encoding = Some(encoding);
+        self
+    }
+
+    /// Set [`ExtractOptions::password`]
+    pub fn password(mut self, password: impl Into<String>) -> Self {
+        self.options.password = Some(password.into());
+        self
+    }
+
+    /// Set [`ExtractOptions::preserve_permissions`]
+    pub fn preserve_permissions(mut self, preserve: bool) -> Self {
+        self.options.preserve_permissions = Some(preserve);
+        self
+    }
+
+    /// Set [`ExtractOptions::file_mode`]
+    pub fn file_mode(mut self, mode: u32) -> Self {
+        self.options.file_mode = Some(mode);
+        self
+    }
+
+    /// Set [`ExtractOptions::dir_mode`]
+    pub fn dir_mode(mut self, mode: u32) -> Self {
+        self.options.dir_mode = Some(mode);
+        self
+    }
+
+    /// Set [`ExtractOptions::entry_filter`]
+    pub fn entry_filter(mut self, filter: EntryFilter) -> Self {
+        self.options.entry_filter = Some(filter);
+        self
+    }
+
+    /// Set [`ExtractOptions::overwrite_prompt`]
+    pub fn overwrite_prompt(mut self, prompt: OverwritePrompt) -> Self {
+        self.options.overwrite_prompt = Some(prompt);
+        self
+    }
+
+    /// Set [`ExtractOptions::pre_write_hook`]
+    pub fn pre_write_hook(mut self, hook: PreWriteHook) -> Self {
+        self.options.pre_write_hook = Some(hook);
+        self
+    }
+
+    /// Set [`ExtractOptions::map_path`]
+    pub fn map_path(mut self, mapper: PathMapper) -> Self {
+        self.options.map_path = Some(mapper);
+        self
+    }
+
+    /// Set [`ExtractOptions::on_entry`]
+    pub fn on_entry(mut self, observer: EntryObserver) -> Self {
+        self.options.on_entry = Some(observer);
+        self
+    }
+
+    /// Set [`ExtractOptions::on_progress`]
+    pub fn on_progress(mut self, listener: ProgressListener) -> Self {
+        self.options.on_progress = Some(listener);
+        self
+    }
+
+    /// Set [`ExtractOptions::content_policy`]
+    pub fn content_policy(mut self, policy: ContentPolicy) -> Self {
+        self.options.content_policy = Some(policy);
+        self
+    }
+
+    /// Set [`ExtractOptions::max_duration`]
+    pub fn max_duration(mut self, duration: Duration) -> Self {
+        self.options.max_duration = Some(duration);
+        self
+    }
+
+    /// Set [`ExtractOptions::temp_dir`]
+    pub fn temp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.options.temp_dir = Some(dir.into());
+        self
+    }
+
+    /// The options as set
+    pub fn build(self) -> ExtractOptions {
+        self.options
+    }
 }
 
-impl Default for ExtractStats {
-    fn default() -> Self {
-        Self {
-            files_extracted: 0,
-            bytes_written: 0,
-            duration: Duration::from_secs(0),
-            cancelled: false,
-        }
+/// Options for probing an archive (see [`crate::probe_with_options`]).
+///
+/// ```
+/// use extractor::ProbeOptions;
+///
+/// let options = ProbeOptions {
+///     password: Some("secret".to_string()),
+///     ..Default::default()
+/// };
+/// assert!(!options.read_concatenated_tar);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ProbeOptions {
+    /// Password for reading an encrypted entry list (RAR and 7-Zip)
+    pub password: Option<String>,
+
+    /// Whether to list the members of tar archives concatenated after the
+    /// first one's end marker (see [`ExtractOptions::read_concatenated_tar`])
+    pub read_concatenated_tar: bool,
+
+    /// Encoding of ZIP entry names not marked as UTF-8, instead of the one
+    /// detected from them (see [`ExtractOptions::filename_encoding`])
+    pub filename_encoding: Option<FilenameEncoding>,
+
+    /// Called as entries are listed (see [`ProbeProgress`])
+    pub on_progress: Option<ProbeProgress>,
+
+    /// Flag that stops the probe with `ExtractError::Cancelled` once set
+    pub cancel_flag: Option<Arc<AtomicBool>>,
+}
+
+/// Shared callback receiving the archive bytes scanned and the entries found
+/// so far while an archive is probed.
+#[derive(Clone)]
+pub struct ProbeProgress(Arc<dyn Fn(u64, u64) + Send + Sync>);
+
+impl ProbeProgress {
+    /// Wrap a function called with the bytes scanned and entries found.
+    pub fn new(progress: impl Fn(u64, u64) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(progress))
+    }
+
+    /// Report the bytes scanned and entries found so far.
+    pub fn report(&self, bytes_scanned: u64, entries_found: u64) {
+        (self.0)(bytes_scanned, entries_found)
+    }
+}
+
+impl fmt::Debug for ProbeProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProbeProgress(..)")
+    }
+}
+
+/// Predicate deciding which entries are extracted.
+///
+/// The entry passed in describes the archive member as listed by probing: its
+/// normalized path before `strip_components` is applied, its size and, where the
+/// format records them, its compressed size and modification time. The filter is
+/// shared so that options stay cheap to clone.
+#[derive(Clone)]
+pub struct EntryFilter(Arc<dyn Fn(&ArchiveEntry) -> bool + Send + Sync>);
+
+impl EntryFilter {
+    /// Wrap a predicate returning `true` for entries to extract.
+    pub fn new(filter: impl Fn(&ArchiveEntry) -> bool + Send + Sync + 'static) -> Self {
+        Self(Arc::new(filter))
+    }
+
+    /// Whether `entry` should be extracted.
+    pub fn matches(&self, entry: &ArchiveEntry) -> bool {
+        (self.0)(entry)
+    }
+}
+
+impl fmt::Debug for EntryFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EntryFilter(..)")
+    }
+}
+
+/// Hook rewriting the relative path each entry is extracted to.
+///
+/// Useful for prefixing paths with the archive name, lower-casing them or
+/// flattening media files into one folder. The returned path is validated again
+/// like an archive path, so it can't escape the output directory; returning an
+/// empty path skips the entry.
+#[derive(Clone)]
+pub struct PathMapper(Arc<dyn Fn(&Path) -> PathBuf + Send + Sync>);
+
+impl PathMapper {
+    /// Wrap a function mapping an entry's relative path to a new one.
+    pub fn new(mapper: impl Fn(&Path) -> PathBuf + Send + Sync + 'static) -> Self {
+        Self(Arc::new(mapper))
+    }
+
+    /// Map one relative path.
+    pub fn map(&self, path: &Path) -> PathBuf {
+        (self.0)(path)
+    }
+}
+
+impl fmt::Debug for PathMapper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PathMapper(..)")
+    }
+}
+
+/// What an extraction did with one entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum EntryAction {
+    /// The file was written where the archive puts it
+    Written,
+
+    /// The file was written under a new name, next to an existing file
+    Renamed,
+
+    /// The entry wasn't written: filtered out, kept from an earlier run,
+    /// already present, or rejected by the pre-write hook or content policy
+    Skipped,
+
+    /// The directory was created, or already existed
+    Directory,
+
+    /// The entry couldn't be extracted and was left out (see
+    /// `ExtractOptions::on_error`)
+    Failed,
+}
+
+impl fmt::Display for EntryAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self {
+            EntryAction::Written => "written",
+            EntryAction::Renamed => "renamed",
+            EntryAction::Skipped => "skipped",
+            EntryAction::Directory => "directory",
+            EntryAction::Failed => "failed",
+        };
+        f.write_str(action)
+    }
+}
+
+/// Callback told what was done with each entry, by the name it's reported
+/// under in progress.
+///
+/// Every entry an extraction gets to is reported once, so that counting them
+/// gives how far through the archive it is even when most entries are skipped.
+/// Files are reported once written, before the progress callback hears of
+/// them; the progress callback is also called for entries that aren't written.
+#[derive(Clone)]
+#[allow(clippy::type_complexity)]
+pub struct EntryObserver(Arc<dyn Fn(&str, EntryAction) + Send + Sync>);
+
+impl EntryObserver {
+    /// Wrap a function called with each entry's name and what became of it.
+    pub fn new(observer: impl Fn(&str, EntryAction) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(observer))
+    }
+
+    /// Report what was done with one entry.
+    pub fn notify(&self, name: &str, action: EntryAction) {
+        (self.0)(name, action)
+    }
+}
+
+impl fmt::Debug for EntryObserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EntryObserver(..)")
+    }
+}
+
+/// Stage of an extraction a [`ProgressEvent`] comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum ProgressPhase {
+    /// Listing the archive before anything is written
+    Scanning,
+
+    /// Writing entries out
+    Extracting,
+
+    /// Checking what was written, or testing an archive (see
+    /// [`crate::verify`])
+    Verifying,
+}
+
+/// Where an extraction is, reported to a [`ProgressListener`].
+///
+/// Events come for every entry once it's handled, with `action` set, and every
+/// few megabytes while a large file is written. Totals are `None` when the
+/// extraction doesn't know them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressEvent {
+    pub phase: ProgressPhase,
+
+    /// Position of the current entry among those extracted, from 0
+    pub entry_index: u64,
+
+    /// Number of entries in the archive
+    pub total_entries: Option<u64>,
+
+    /// Name of the current entry, relative to the output directory
+    pub path: String,
+
+    /// Bytes of the current entry written so far
+    pub entry_bytes: u64,
+
+    /// Size of the current entry, as recorded in the archive until it has
+    /// been written
+    pub entry_total: Option<u64>,
+
+    /// Bytes written by the whole extraction so far
+    pub bytes_written: u64,
+
+    /// Bytes the whole extraction will write
+    pub total_bytes: Option<u64>,
+
+    /// What became of the entry, on the event that finishes it
+    pub action: Option<EntryAction>,
+}
+
+/// Receiver of an extraction's structured progress.
+///
+/// Each method has a default that ignores the report, so sinks only implement
+/// what they need and keep compiling when methods are added. Methods returning
+/// `bool` return `true` to continue and `false` to cancel. Functions taking a
+/// [`ProgressEvent`] are sinks that receive every event but the warnings.
+pub trait ProgressSink: Send + Sync {
+    /// An entry is about to be handled: written, created or skipped
+    fn on_entry_start(&self, _event: &ProgressEvent) -> bool {
+        true
+    }
+
+    /// More of the current entry was written, every few megabytes of a large
+    /// file, or more of the archive was listed in the `Scanning` phase
+    fn on_bytes(&self, _event: &ProgressEvent) -> bool {
+        true
+    }
+
+    /// The entry was handled; `event.action` tells how
+    fn on_entry_done(&self, _event: &ProgressEvent) -> bool {
+        true
+    }
+
+    /// A non-fatal problem was noticed, as recorded in `ExtractStats::warnings`
+    fn on_warning(&self, _warning: &str) {}
+}
+
+impl<F> ProgressSink for F
+where
+    F: Fn(&ProgressEvent) -> bool + Send + Sync,
+{
+    fn on_entry_start(&self, event: &ProgressEvent) -> bool {
+        self(event)
+    }
+
+    fn on_bytes(&self, event: &ProgressEvent) -> bool {
+        self(event)
+    }
+
+    fn on_entry_done(&self, event: &ProgressEvent) -> bool {
+        self(event)
+    }
+}
+
+/// Shared [`ProgressSink`] receiving structured progress alongside the
+/// progress callback.
+#[derive(Clone)]
+pub struct ProgressListener(Arc<dyn ProgressSink>);
+
+impl ProgressListener {
+    /// Wrap a function called with each progress event, returning whether to
+    /// go on.
+    pub fn new(listener: impl Fn(&ProgressEvent) -> bool + Send + Sync + 'static) -> Self {
+        Self(Arc::new(listener))
+    }
+
+    /// Wrap a sink with its own handling of each kind of report.
+    pub fn from_sink(sink: impl ProgressSink + 'static) -> Self {
+        Self(Arc::new(sink))
+    }
+}
+
+impl ProgressSink for ProgressListener {
+    fn on_entry_start(&self, event: &ProgressEvent) -> bool {
+        self.0.on_entry_start(event)
+    }
+
+    fn on_bytes(&self, event: &ProgressEvent) -> bool {
+        self.0.on_bytes(event)
+    }
+
+    fn on_entry_done(&self, event: &ProgressEvent) -> bool {
+        self.0.on_entry_done(event)
+    }
+
+    fn on_warning(&self, warning: &str) {
+        self.0.on_warning(warning)
+    }
+}
+
+impl fmt::Debug for ProgressListener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressListener(..)")
+    }
+}
+
+/// What to do with a file after a [`PreWriteHook`] has inspected it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteDecision {
+    /// Write the file
+    Write,
+
+    /// Leave the file out and continue; counted in `ExtractStats::files_skipped`
+    Skip,
+
+    /// Stop the extraction with `ExtractError::Rejected`
+    Abort,
+}
+
+/// Content hook run before each file is written, for antivirus, DLP or MIME
+/// policy checks.
+///
+/// The hook gets the entry's listing metadata and a reader over its data. It may
+/// read as much as it needs: the bytes it reads are kept and written out
+/// afterwards, the first 8 MiB in memory and the rest in a temporary file in
+/// `ExtractOptions::temp_dir` (or next to the output directory). Directories are
+/// not passed to the hook.
+#[derive(Clone)]
+#[allow(clippy::type_complexity)]
+pub struct PreWriteHook(Arc<dyn Fn(&ArchiveEntry, &mut dyn Read) -> WriteDecision + Send + Sync>);
+
+impl PreWriteHook {
+    /// Wrap a function deciding what happens to each file.
+    pub fn new(
+        hook: impl Fn(&ArchiveEntry, &mut dyn Read) -> WriteDecision + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(hook))
+    }
+
+    /// Run the hook for one file.
+    pub fn inspect(&self, entry: &ArchiveEntry, data: &mut dyn Read) -> WriteDecision {
+        (self.0)(entry, data)
+    }
+}
+
+impl fmt::Debug for PreWriteHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PreWriteHook(..)")
+    }
+}
+
+/// Content type of a file, as detected from its first bytes and name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+#[serde(rename_all = "lowercase")]
+pub enum ContentKind {
+    /// Native program or library (ELF, PE, Mach-O, .exe, .dll, ...)
+    Executable,
+
+    /// Script run by a shell or interpreter (`#!` line, .sh, .bat, .ps1, ...)
+    Script,
+
+    /// Anything else
+    Other,
+}
+
+impl fmt::Display for ContentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ContentKind::Executable => "executable",
+            ContentKind::Script => "script",
+            ContentKind::Other => "other",
+        })
+    }
+}
+
+/// Policy keeping files of denied content types out of an extraction, for
+/// archives from untrusted sources.
+///
+/// Each file's first bytes are sniffed before it is written. Denied files are
+/// left out, or written to the quarantine folder without execute permission,
+/// and listed in `ExtractStats::blocked`.
+#[derive(Debug, Clone, Default)]
+pub struct ContentPolicy {
+    /// Content types that may not be extracted
+    pub deny: Vec<ContentKind>,
+
+    /// Folder receiving denied files (flattened to their file names) instead of
+    /// dropping them
+    pub quarantine_dir: Option<PathBuf>,
+}
+
+impl ContentPolicy {
+    /// Whether files of type `kind` are denied.
+    pub fn denies(&self, kind: ContentKind) -> bool {
+        self.deny.contains(&kind)
+    }
+}
+
+/// A file kept out of the output directory by the content policy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct BlockedEntry {
+    /// Entry path inside the archive
+    pub path: String,
+
+    /// Detected content type
+    pub kind: ContentKind,
+
+    /// Where the file was written instead, if it was quarantined
+    #[ts(optional)]
+    pub quarantined_to: Option<String>,
+}
+
+/// A written file that doesn't match what the archive records for its entry
+/// (see `ExtractOptions::verify_after_extract`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct VerifyMismatch {
+    /// Entry path inside the archive
+    pub path: String,
+
+    /// How the file differs, e.g. its CRC-32 or size
+    pub reason: String,
+}
+
+/// Result of testing an archive by decoding every entry without writing
+/// anything (see [`crate::verify`]).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct VerifyReport {
+    /// Whether the archive read to its end and every entry decoded and matched
+    /// its recorded CRC-32 and size
+    pub ok: bool,
+
+    /// Outcome for every entry other than directories, in archive order
+    pub entries: Vec<EntryCheck>,
+
+    /// Why reading stopped before the end of the archive, if it did
+    #[ts(optional)]
+    pub error: Option<String>,
+
+    /// Non-fatal problems noticed while reading (e.g. data after the archive)
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+/// How one entry fared when its archive was tested.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct EntryCheck {
+    /// Entry path inside the archive
+    pub path: String,
+
+    /// Number of bytes decoded
+    #[ts(type = "number")]
+    pub size: u64,
+
+    /// Why the entry failed, if it did
+    #[ts(optional)]
+    pub error: Option<String>,
+}
+
+/// Hash function of a checksum file published next to an archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumAlgorithm {
+    /// MD5, in an `.md5` file
+    Md5,
+    /// SHA-256, in a `.sha256` file
+    Sha256,
+    /// SHA-512, in a `.sha512` file
+    Sha512,
+}
+
+impl ChecksumAlgorithm {
+    /// Extension of the checksum file, added to the archive's file name
+    pub fn extension(self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Md5 => "md5",
+            ChecksumAlgorithm::Sha256 => "sha256",
+            ChecksumAlgorithm::Sha512 => "sha512",
+        }
+    }
+
+    /// Number of hex digits in a digest
+    pub(crate) fn hex_len(self) -> usize {
+        match self {
+            ChecksumAlgorithm::Md5 => 32,
+            ChecksumAlgorithm::Sha256 => 64,
+            ChecksumAlgorithm::Sha512 => 128,
+        }
+    }
+}
+
+impl fmt::Display for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChecksumAlgorithm::Md5 => "MD5",
+            ChecksumAlgorithm::Sha256 => "SHA-256",
+            ChecksumAlgorithm::Sha512 => "SHA-512",
+        })
+    }
+}
+
+/// Result of hashing an archive and comparing the digest with the one a
+/// checksum file next to it lists (see [`crate::verify_checksum`]).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ChecksumCheck {
+    /// Path of the checksum file, e.g. `archive.zip.sha256`
+    pub file: String,
+
+    /// Hash function the checksum file is for
+    pub algorithm: ChecksumAlgorithm,
+
+    /// Lowercase hex digest the checksum file lists for the archive
+    pub expected: String,
+
+    /// Lowercase hex digest of the archive
+    pub actual: String,
+
+    /// Whether the two are the same
+    pub matches: bool,
+}
+
+/// An entry an extraction left out because it couldn't be extracted (see
+/// `ExtractOptions::on_error`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct EntryError {
+    /// Entry path inside the archive
+    pub path: String,
+
+    /// Why it couldn't be extracted
+    pub error: String,
+}
+
+/// How to handle file conflicts during extraction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverwriteMode {
+    /// Replace existing files
+    Replace,
+
+    /// Skip files that already exist
+    Skip,
+
+    /// Rename new files by appending (1), (2), etc.
+    Rename,
+
+    /// Replace existing files only when the archive entry is newer. Entries
+    /// without a recorded modification time replace files whose size differs.
+    IfNewer,
+
+    /// Fail with `ExtractError::AlreadyExists` at the first entry whose destination
+    /// file already exists. Existing directories are merged into.
+    Error,
+
+    /// Ask `ExtractOptions::overwrite_prompt` what to do with each file that
+    /// already exists. Existing directories are merged into.
+    Ask,
+}
+
+/// Answer of an [`OverwritePrompt`] to an entry whose destination exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverwriteDecision {
+    /// Replace the existing file
+    Replace,
+
+    /// Keep the existing file and leave the entry out
+    Skip,
+
+    /// Write the entry next to the existing file, with (1), (2), etc. appended
+    Rename,
+
+    /// Stop the extraction with `ExtractError::AlreadyExists`
+    Abort,
+
+    /// Handle this conflict and every later one with the given mode, without
+    /// asking again. `OverwriteMode::Ask` here is taken as `Abort`.
+    ApplyToAll(OverwriteMode),
+}
+
+/// Prompt deciding what to do with each entry whose destination already
+/// exists, for `OverwriteMode::Ask`: a question on the terminal or a conflict
+/// dialog.
+///
+/// The prompt gets the path of the existing file and the entry's listing
+/// metadata. It is called on the thread reading the archive, which waits for
+/// its answer.
+#[derive(Clone)]
+#[allow(clippy::type_complexity)]
+pub struct OverwritePrompt(Arc<dyn Fn(&Path, &ArchiveEntry) -> OverwriteDecision + Send + Sync>);
+
+impl OverwritePrompt {
+    /// Wrap a function answering each conflict.
+    pub fn new(
+        prompt: impl Fn(&Path, &ArchiveEntry) -> OverwriteDecision + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(prompt))
+    }
+
+    /// Ask what to do with `entry`, whose destination `existing` exists.
+    pub fn ask(&self, existing: &Path, entry: &ArchiveEntry) -> OverwriteDecision {
+        (self.0)(existing, entry)
+    }
+}
+
+impl fmt::Debug for OverwritePrompt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OverwritePrompt(..)")
+    }
+}
+
+/// What to do with symbolic link entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkPolicy {
+    /// Leave links out, with a warning
+    Skip,
+
+    /// Create the links
+    Preserve,
+
+    /// Write a copy of what each link points to in its place, once the rest
+    /// of the archive is extracted. Links to something that isn't in the
+    /// archive are skipped with a warning. For destinations where links are
+    /// awkward, such as Windows without developer mode or a USB stick.
+    Materialize,
+}
+
+/// Whether to check the destination's free space before extracting (see
+/// `ExtractOptions::check_free_space`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+#[serde(rename_all = "lowercase")]
+pub enum FreeSpaceCheck {
+    /// Don't check
+    #[default]
+    Off,
+
+    /// Add a warning when the archive needs more space than is free, and
+    /// extract anyway
+    Warn,
+
+    /// Fail with `ExtractError::InsufficientSpace` before writing anything
+    Fail,
+}
+
+/// When to give an archive a folder of its own inside the output directory
+/// (see `ExtractOptions::create_subfolder`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+#[serde(rename_all = "lowercase")]
+pub enum SubfolderPolicy {
+    /// Only when its entries don't all sit in one top-level folder, so they
+    /// don't scatter into the output directory. Single compressed files such
+    /// as `.gz` never get one.
+    Auto,
+
+    /// Always
+    Always,
+
+    /// Never; entries go straight into the output directory
+    #[default]
+    Never,
+}
+
+/// What to do with an entry that can't be extracted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorPolicy {
+    /// Stop the extraction with the entry's error
+    Abort,
+
+    /// Leave the entry out, record why and go on with the rest of the archive
+    Skip,
+}
+
+/// Fixes applied to each component of an entry's output path, for names that
+/// extract but are awkward to use (see `ExtractOptions::sanitize_names`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct NameSanitizer {
+    /// Replace dots and spaces ending a name with `_`; Windows drops them, so
+    /// such files can't be opened there
+    pub trailing_dots: bool,
+
+    /// Replace a `-` starting a name with `_`, so the name isn't taken for an
+    /// option when passed to a command
+    pub leading_dashes: bool,
+
+    /// Shorten names longer than this many bytes, keeping their extension
+    /// (most filesystems allow 255)
+    pub max_name_bytes: Option<u32>,
+}
+
+impl Default for NameSanitizer {
+    fn default() -> Self {
+        Self {
+            trailing_dots: false,
+            leading_dashes: false,
+            max_name_bytes: Some(255),
+        }
+    }
+}
+
+/// Limits on the entry paths of an archive, so that pathologically nested or
+/// long ones are refused before anything is created for them (see
+/// [`crate::safety::validate_entry_path_with_limits`]).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct PathLimits {
+    /// Most directory levels a path may have, counting its file name
+    pub max_depth: Option<u32>,
+
+    /// Longest name a path component may have, in bytes. Applies before names
+    /// are shortened by `NameSanitizer::max_name_bytes`, so it's unset by
+    /// default.
+    pub max_component_bytes: Option<u32>,
+
+    /// Longest a whole path may be, in bytes
+    pub max_path_bytes: Option<u32>,
+}
+
+impl PathLimits {
+    /// No limits at all.
+    pub fn unlimited() -> Self {
+        Self {
+            max_depth: None,
+            max_component_bytes: None,
+            max_path_bytes: None,
+        }
+    }
+}
+
+impl Default for PathLimits {
+    /// 1024 levels and 4096 bytes (`PATH_MAX` on Linux), with components
+    /// left to the name sanitizer.
+    fn default() -> Self {
+        Self {
+            max_depth: Some(1024),
+            max_component_bytes: None,
+            max_path_bytes: Some(4096),
+        }
+    }
+}
+
+/// An entry extracted under a different name than the archive gives it, after
+/// its name was sanitized.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct RenamedEntry {
+    /// Entry path inside the archive
+    pub path: String,
+
+    /// Path it was extracted to, relative to the output directory
+    pub renamed_to: String,
+}
+
+/// Unicode normalization form of extracted entry names.
+///
+/// The same accented name can be stored precomposed (`é`, as Windows and
+/// Linux tools write it) or decomposed (`e` and a combining accent, as macOS
+/// tools often do). Both look alike but are different names on most
+/// filesystems.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+#[serde(rename_all = "lowercase")]
+pub enum NormalizationForm {
+    /// Keep names as the archive stores them
+    Keep,
+
+    /// Compose characters (NFC), the form most platforms use
+    Nfc,
+
+    /// Decompose characters (NFD), as HFS+ stores names on macOS
+    Nfd,
+}
+
+/// What to do with entry names Windows can't create: reserved device names
+/// such as `CON` or `aux.txt`, names containing `<>:"|?*`, and names ending in
+/// a dot or space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+#[serde(rename_all = "lowercase")]
+pub enum WindowsNamePolicy {
+    /// Write names as they are; on Windows, writing such an entry fails
+    Allow,
+
+    /// Fail the extraction with `SecurityError::NonPortableName`
+    Strict,
+
+    /// Replace the offending characters with `_` and add `_` to reserved
+    /// names (`aux.txt` becomes `aux_.txt`), listing each renamed entry in
+    /// `ExtractStats::renamed`
+    Rename,
+
+    /// Leave such entries out, recording each in the warnings
+    Skip,
+}
+
+impl Default for WindowsNamePolicy {
+    fn default() -> Self {
+        if cfg!(windows) {
+            Self::Rename
+        } else {
+            Self::Allow
+        }
+    }
+}
+
+/// What to do with files over `ExtractOptions::max_entry_size_bytes`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+#[serde(rename_all = "lowercase")]
+pub enum OversizePolicy {
+    /// Fail the extraction with `ExtractError::EntryTooLarge`
+    #[default]
+    Reject,
+
+    /// Leave such files out, recording each in the warnings
+    Skip,
+}
+
+/// Which modification times extracted files and directories get.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+#[serde(rename_all = "lowercase")]
+pub enum TimestampPolicy {
+    /// The modification times recorded in the archive, where there are any.
+    /// Directories get theirs once their contents are extracted.
+    Preserve,
+
+    /// The time of extraction
+    Now,
+}
+
+/// Statistics about a completed extraction operation.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ExtractStats {
+    /// Number of files successfully extracted
+    #[ts(type = "number")]
+    pub files_extracted: u64,
+
+    /// Total bytes written to disk
+    #[ts(type = "number")]
+    pub bytes_written: u64,
+
+    /// Duration of the extraction operation (in seconds)
+    #[serde(with = "duration_serde")]
+    #[ts(type = "number")]
+    pub duration: Duration,
+
+    /// Whether the extraction was cancelled
+    pub cancelled: bool,
+
+    /// Number of files left out because they already existed (see
+    /// `OverwriteMode::Skip` and `OverwriteMode::IfNewer`) or the pre-write
+    /// hook skipped them
+    #[serde(default)]
+    #[ts(type = "number")]
+    pub files_skipped: u64,
+
+    /// Number of directory entries extracted
+    #[serde(default)]
+    #[ts(type = "number")]
+    pub directories: u64,
+
+    /// Files blocked or quarantined by the content policy
+    #[serde(default)]
+    pub blocked: Vec<BlockedEntry>,
+
+    /// Non-fatal problems noticed during extraction (e.g. case collisions)
+    #[serde(default)]
+    pub warnings: Vec<String>,
+
+    /// Entries left out because they couldn't be extracted (see
+    /// `ExtractOptions::on_error`)
+    #[serde(default)]
+    pub errors: Vec<EntryError>,
+
+    /// Entries whose names were changed to make them safe or portable (see
+    /// `ExtractOptions::sanitize_control_chars`, `sanitize_names` and
+    /// `windows_names`)
+    #[serde(default)]
+    pub renamed: Vec<RenamedEntry>,
+
+    /// Files a failed extraction wrote and left in place, relative to the
+    /// output directory (see `ExtractOptions::cleanup_on_failure`)
+    #[serde(default)]
+    pub partial_files: Vec<String>,
+
+    /// Files that didn't match their entries when read back (see
+    /// `ExtractOptions::verify_after_extract`)
+    #[serde(default)]
+    pub mismatches: Vec<VerifyMismatch>,
+
+    /// Result of checking the archive against its checksum file, if it was
+    /// (see `ExtractOptions::verify_checksum`)
+    #[serde(default)]
+    #[ts(optional)]
+    pub checksum: Option<ChecksumCheck>,
+
+    /// Folder created for the archive's entries inside the output directory,
+    /// if one was (see `ExtractOptions::create_subfolder`)
+    #[serde(default)]
+    #[ts(optional)]
+    pub subfolder: Option<String>,
+
+    /// Lowercase hex SHA-256 of every file written, by its path relative to
+    /// the output directory (see `ExtractOptions::hash_files`)
+    #[serde(default)]
+    pub sha256: BTreeMap<String, String>,
+}
+
+impl Default for ExtractStats {
+    fn default() -> Self {
+        Self {
+            files_extracted: 0,
+            bytes_written: 0,
+            duration: Duration::from_secs(0),
+            cancelled: false,
+            files_skipped: 0,
+            directories: 0,
+            blocked: Vec::new(),
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            renamed: Vec::new(),
+            partial_files: Vec::new(),
+            mismatches: Vec::new(),
+            sha256: BTreeMap::new(),
+            checksum: None,
+            subfolder: None,
+        }
+    }
+}
+
+impl ExtractStats {
+    /// The digests in `sha256` in the format of a `SHA256SUMS` file: one line
+    /// of digest, two spaces and path per file.
+    pub fn sha256sums(&self) -> String {
+        self.sha256
+            .iter()
+            .map(|(path, digest)| format!("{}  {}\n", digest, path))
+            .collect()
     }
 }
 
@@ -0,0 +1,150 @@
+//! Testing archives by decoding every entry without writing anything.
+//!
+//! Backs `unarchive test` and the app's "Test archive" action. Each entry's
+//! data is decompressed into a sink and compared with the CRC-32 and size the
+//! archive records for it, the same comparison
+//! `ExtractOptions::verify_after_extract` makes with the files written.
+
+use crate::error::ExtractError;
+use crate::pipeline::PROGRESS_STEP;
+use crate::reader::open_archive_reader;
+use crate::types::{
+    EntryCheck, ExtractOptions, ProgressEvent, ProgressListener, ProgressPhase, ProgressSink,
+    VerifyReport,
+};
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Decode every entry of the archive at `path`, reporting which failed.
+pub(crate) fn verify_archive(
+    path: &Path,
+    options: &ExtractOptions,
+) -> Result<VerifyReport, ExtractError> {
+    if !path.exists() {
+        return Err(ExtractError::NotFound(path.to_path_buf()));
+    }
+
+    let format = crate::probe::detect_format(path)?;
+    let mut reader = open_archive_reader(path, format, options)?;
+    reader.set_tolerate_trailing_data(options.tolerate_trailing_data);
+    reader.set_read_concatenated(options.read_concatenated_tar);
+    if let Some(encoding) = options.filename_encoding {
+        reader.set_filename_encoding(encoding);
+    }
+    let single_file = reader.is_single_file();
+
+    let mut entries = Vec::new();
+    let mut bytes_checked = 0;
+    let read = reader.read_entries(&mut |entry, data| {
+        if entry.is_directory {
+            return Ok(true);
+        }
+        // A single compressed file's size is only an estimate
+        let size = (!single_file).then_some(entry.size);
+        let report = |entry_bytes, report: fn(&ProgressListener, &ProgressEvent) -> bool| {
+            let Some(listener) = &options.on_progress else {
+                return true;
+            };
+            let event = ProgressEvent {
+                phase: ProgressPhase::Verifying,
+                entry_index: entries.len() as u64,
+                total_entries: None,
+                path: entry.path.clone(),
+                entry_bytes,
+                entry_total: size,
+                bytes_written: bytes_checked + entry_bytes,
+                total_bytes: None,
+                action: None,
+            };
+            report(listener, &event)
+        };
+        if !report(0, ProgressListener::on_entry_start) {
+            return Err(ExtractError::Cancelled);
+        }
+        let mut tick = |checked| report(checked, ProgressListener::on_bytes);
+        let checked = data
+            .reader()
+            .and_then(|data| check_data(data, size, entry.crc32, options.buffer_size, &mut tick));
+        let (size, error) = match checked {
+            Ok((len, mismatch)) => (len, mismatch),
+            // Every other entry would fail the same way
+            Err(
+                e @ (ExtractError::PasswordRequired
+                | ExtractError::InvalidPassword
+                | ExtractError::Cancelled),
+            ) => return Err(e),
+            Err(e) => (0, Some(e.to_string())),
+        };
+        if !report(size, ProgressListener::on_entry_done) {
+            return Err(ExtractError::Cancelled);
+        }
+        bytes_checked += size;
+        entries.push(EntryCheck {
+            path: entry.path.clone(),
+            size,
+            error,
+        });
+        Ok(true)
+    });
+    let error = match read {
+        Ok(()) => None,
+        Err(
+            e @ (ExtractError::PasswordRequired
+            | ExtractError::InvalidPassword
+            | ExtractError::Cancelled),
+        ) => return Err(e),
+        Err(e) => Some(e.to_string()),
+    };
+
+    Ok(VerifyReport {
+        ok: error.is_none() && entries.iter().all(|entry| entry.error.is_none()),
+        entries,
+        error,
+        warnings: reader.take_warnings(),
+    })
+}
+
+/// Read `data` to its end, returning how many bytes it held and how they differ
+/// from the recorded `size` and `crc32`, if they do. Every [`PROGRESS_STEP`]
+/// bytes `tick` is called with the number read so far, and reading stops with
+/// [`ExtractError::Cancelled`] once it returns false.
+pub(crate) fn check_data(
+    data: &mut dyn Read,
+    size: Option<u64>,
+    crc32: Option<u32>,
+    buffer_size: usize,
+    tick: &mut dyn FnMut(u64) -> bool,
+) -> Result<(u64, Option<String>), ExtractError> {
+    let mut crc = flate2::Crc::new();
+    let mut len = 0;
+    let mut next_tick = PROGRESS_STEP;
+    let mut buf = vec![0; buffer_size.max(1)];
+    loop {
+        match data.read(&mut buf) {
+            Ok(0) => break,
+            Ok(read) => {
+                crc.update(&buf[..read]);
+                len += read as u64;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+        if len >= next_tick {
+            next_tick = len + PROGRESS_STEP;
+            if !tick(len) {
+                return Err(ExtractError::Cancelled);
+            }
+        }
+    }
+
+    let mismatch = match (size, crc32) {
+        (Some(size), _) if len != size => Some(format!("{} bytes, expected {}", len, size)),
+        (_, Some(crc32)) if crc.sum() != crc32 => Some(format!(
+            "CRC-32 is {:08x}, expected {:08x}",
+            crc.sum(),
+            crc32
+        )),
+        _ => None,
+    };
+    Ok((len, mismatch))
+}
@@ -0,0 +1,154 @@
+//! Restoring extended attributes recorded in archives.
+//!
+//! Only PAX tar archives carry extended attributes. They are set without
+//! following symlinks, and filesystems that don't support them (FAT, some
+//! network shares) are skipped rather than failing the extraction.
+
+use std::io;
+use std::path::Path;
+
+/// Set the extended attribute `name` of `path` to `value`.
+///
+/// Does nothing on platforms other than Linux and macOS.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub(crate) fn set_xattr(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let c_name = CString::new(name)?;
+
+    // SAFETY: both strings are NUL-terminated and value is valid for its length
+    #[cfg(target_os = "linux")]
+    let result = unsafe {
+        libc::lsetxattr(
+            c_path.as_ptr(),
+            c_name.as_ptr(),
+            value.as_ptr().cast(),
+            value.len(),
+            0,
+        )
+    };
+    // SAFETY: as above
+    #[cfg(target_os = "macos")]
+    let result = unsafe {
+        libc::setxattr(
+            c_path.as_ptr(),
+            c_name.as_ptr(),
+            value.as_ptr().cast(),
+            value.len(),
+            0,
+            libc::XATTR_NOFOLLOW,
+        )
+    };
+    if result == 0 {
+        return Ok(());
+    }
+    let error = io::Error::last_os_error();
+    match error.raw_os_error() {
+        Some(libc::ENOTSUP) => Ok(()),
+        _ => Err(error),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub(crate) fn set_xattr(_path: &Path, _name: &str, _value: &[u8]) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use crate::ExtractOptions;
+    use std::ffi::CString;
+    use std::fs::File;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    fn get_xattr(path: &Path, name: &str) -> Option<Vec<u8>> {
+        let c_path = CString::new(path.as_os_str().as_bytes()).unwrap();
+        let c_name = CString::new(name).unwrap();
+        let mut value = vec![0u8; 256];
+        // SAFETY: both strings are NUL-terminated and value holds value.len() bytes
+        let len = unsafe {
+            libc::lgetxattr(
+                c_path.as_ptr(),
+                c_name.as_ptr(),
+                value.as_mut_ptr().cast(),
+                value.len(),
+            )
+        };
+        if len < 0 {
+            return None;
+        }
+        value.truncate(len as usize);
+        Some(value)
+    }
+
+    /// A PAX record, whose length field counts its own digits
+    fn pax_record(key: &str, value: &str) -> String {
+        let rest = format!(" {}={}\n", key, value);
+        let mut len = rest.len() + 1;
+        while len.to_string().len() + rest.len() != len {
+            len += 1;
+        }
+        format!("{}{}", len, rest)
+    }
+
+    #[test]
+    fn test_extract_restores_safe_xattrs() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("test.tar");
+
+        let mut tar = tar::Builder::new(File::create(&archive_path).unwrap());
+        let records = pax_record("SCHILY.xattr.user.origin", "backup")
+            + &pax_record("SCHILY.xattr.trusted.origin", "backup");
+        let mut header = tar::Header::new_ustar();
+        header.set_entry_type(tar::EntryType::XHeader);
+        header.set_size(records.len() as u64);
+        header.set_cksum();
+        tar.append_data(&mut header, "PaxHeader/notes.txt", records.as_bytes())
+            .unwrap();
+        let mut header = tar::Header::new_ustar();
+        header.set_size(5);
+        header.set_cksum();
+        tar.append_data(&mut header, "notes.txt", b"hello".as_slice())
+            .unwrap();
+        tar.finish().unwrap();
+        drop(tar);
+
+        let output_dir = dir.path().join("output");
+        let options = ExtractOptions {
+            preserve_xattrs: true,
+            ..ExtractOptions::default()
+        };
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        crate::extract(
+            &archive_path,
+            &output_dir,
+            &options,
+            &|_, _, _| true,
+            cancel_flag,
+        )
+        .unwrap();
+
+        let file = output_dir.join("notes.txt");
+        if get_xattr(&file, "user.origin").is_none() && !supports_xattrs(dir.path()) {
+            return;
+        }
+        assert_eq!(
+            get_xattr(&file, "user.origin").as_deref(),
+            Some(&b"backup"[..])
+        );
+        assert_eq!(get_xattr(&file, "trusted.origin"), None);
+    }
+
+    /// Whether the filesystem holding `dir` takes user attributes
+    fn supports_xattrs(dir: &Path) -> bool {
+        let probe = dir.join("probe");
+        File::create(&probe).unwrap();
+        super::set_xattr(&probe, "user.probe", b"1").is_ok()
+            && get_xattr(&probe, "user.probe").is_some()
+    }
+}
@@ -0,0 +1,537 @@
+//! Streaming decoder for the xz format.
+//!
+//! lzma-rs only decompresses a whole xz stream into a writer, so xz archives
+//! used to be decompressed into memory before being read. This decoder reads
+//! the xz container itself and feeds each block's LZMA2 data through the
+//! streaming reader of `lzma_rust2`, so memory use is bounded by the
+//! dictionary size rather than the archive's. As with lzma-rs, only blocks
+//! using the LZMA2 filter alone can be decoded, which is what `xz` writes
+//! unless asked for BCJ or delta filters.
+
+use flate2::Crc;
+use lzma_rust2::LZMA2Reader;
+use sha2::{Digest, Sha256};
+use std::io::{self, Read};
+
+const HEADER_MAGIC: [u8; 6] = [0xFD, b'7', b'z', b'X', b'Z', 0];
+const FOOTER_MAGIC: [u8; 2] = *b"YZ";
+const LZMA2_FILTER: u64 = 0x21;
+
+static CRC64: crc::Crc<u64> = crc::Crc::<u64>::new(&crc::CRC_64_XZ);
+
+/// Decoder for one or more concatenated xz streams.
+pub(crate) struct XzDecoder<R> {
+    state: State<R>,
+    /// Stream flags of the current stream, repeated in its footer
+    flags: [u8; 2],
+    /// Unpadded and uncompressed size of each block of the current stream,
+    /// which its index must list
+    blocks: Vec<(u64, u64)>,
+}
+
+enum State<R> {
+    /// Before a stream header; only the first stream is required
+    Stream { input: Counted<R>, first: bool },
+    /// Before a block header or the index of a stream
+    Blocks(Counted<R>),
+    /// In the data of a block
+    Block(Box<Block<R>>),
+    /// After the last stream
+    Done,
+    /// After an error
+    Failed,
+}
+
+/// A block being decoded.
+struct Block<R> {
+    data: LZMA2Reader<Counted<R>>,
+    /// Offset of the block's data in the stream
+    data_start: u64,
+    header_len: u64,
+    compressed_size: Option<u64>,
+    uncompressed_size: Option<u64>,
+    /// Bytes decoded so far
+    decoded: u64,
+    check: Check,
+}
+
+impl<R: Read> XzDecoder<R> {
+    pub fn new(input: R) -> Self {
+        Self {
+            state: State::Stream {
+                input: Counted {
+                    inner: input,
+                    pos: 0,
+                },
+                first: true,
+            },
+            flags: [0; 2],
+            blocks: Vec::new(),
+        }
+    }
+
+    /// Read the header of the next stream, returning `None` at the end of the
+    /// input after a stream.
+    fn start_stream(
+        &mut self,
+        mut input: Counted<R>,
+        first: bool,
+    ) -> io::Result<Option<Counted<R>>> {
+        let mut header = [0u8; 12];
+        if !first {
+            // Streams can be followed by padding in groups of four null bytes
+            let len = loop {
+                let len = read_up_to(&mut input, &mut header[..4])?;
+                if len == 0 {
+                    return Ok(None);
+                }
+                if len < 4 || header[..4] != [0; 4] {
+                    break len;
+                }
+            };
+            if len < 4 || read_up_to(&mut input, &mut header[4..])? < 8 {
+                return Err(corrupted("Unexpected data after the end of the xz stream"));
+            }
+        } else {
+            input.read_exact(&mut header)?;
+        }
+
+        if header[..6] != HEADER_MAGIC {
+            return Err(corrupted(if first {
+                "Not an xz stream"
+            } else {
+                "Unexpected data after the end of the xz stream"
+            }));
+        }
+        check_crc32(&header[6..8], &header[8..12], "stream header")?;
+        if header[6] != 0 || header[7] & 0xF0 != 0 {
+            return Err(unsupported("Unsupported xz stream flags"));
+        }
+
+        self.flags = [header[6], header[7]];
+        self.blocks.clear();
+        input.pos = header.len() as u64;
+        Ok(Some(input))
+    }
+
+    /// Read the header of the next block, or the index and footer of the
+    /// stream when it has no more blocks.
+    fn next_block(&mut self, mut input: Counted<R>) -> io::Result<State<R>> {
+        let start = input.pos;
+        let mut size = [0u8; 1];
+        input.read_exact(&mut size)?;
+        if size[0] == 0 {
+            self.finish_stream(&mut input)?;
+            return Ok(State::Stream {
+                input,
+                first: false,
+            });
+        }
+
+        let header_len = (u64::from(size[0]) + 1) * 4;
+        let mut header = vec![0u8; header_len as usize];
+        header[0] = size[0];
+        input.read_exact(&mut header[1..])?;
+        let (fields, crc) = header.split_at(header.len() - 4);
+        check_crc32(fields, crc, "block header")?;
+
+        let flags = fields[1];
+        if flags & 0x3C != 0 {
+            return Err(unsupported("Unsupported xz block flags"));
+        }
+        let mut pos = 2;
+        let compressed_size = (flags & 0x40 != 0)
+            .then(|| varint(fields, &mut pos))
+            .transpose()?;
+        let uncompressed_size = (flags & 0x80 != 0)
+            .then(|| varint(fields, &mut pos))
+            .transpose()?;
+
+        let filters = (flags & 0x03) + 1;
+        let filter = varint(fields, &mut pos)?;
+        let props_len = varint(fields, &mut pos)?;
+        if filters != 1 || filter != LZMA2_FILTER || props_len != 1 {
+            return Err(unsupported(
+                "Only xz data compressed with the LZMA2 filter alone is supported",
+            ));
+        }
+        let dict_size = lzma2_dict_size(*fields.get(pos).ok_or_else(header_error)?)?;
+        if fields[pos + 1..].iter().any(|&b| b != 0) {
+            return Err(header_error());
+        }
+
+        let data_start = start + header_len;
+        Ok(State::Block(Box::new(Block {
+            data: LZMA2Reader::new(input, dict_size, None),
+            data_start,
+            header_len,
+            compressed_size,
+            uncompressed_size,
+            decoded: 0,
+            check: Check::new(self.flags[1] & 0x0F),
+        })))
+    }
+
+    /// Check the sizes, padding and check of a block whose data has all been
+    /// decoded.
+    fn finish_block(&mut self, block: Block<R>) -> io::Result<Counted<R>> {
+        let Block {
+            data,
+            data_start,
+            header_len,
+            compressed_size,
+            uncompressed_size,
+            decoded,
+            check,
+        } = block;
+        let mut input = data.into_inner();
+        let compressed = input.pos - data_start;
+        if compressed_size.is_some_and(|size| size != compressed)
+            || uncompressed_size.is_some_and(|size| size != decoded)
+        {
+            return Err(corrupted("xz block sizes don't match its header"));
+        }
+
+        skip_padding(&mut input)?;
+        let expected = check.finish();
+        let mut stored = vec![0u8; expected.len()];
+        input.read_exact(&mut stored)?;
+        if let Some(expected) = expected.verify() {
+            if stored != expected {
+                return Err(corrupted("xz data doesn't match its checksum"));
+            }
+        }
+
+        let unpadded = header_len + compressed + stored.len() as u64;
+        self.blocks.push((unpadded, decoded));
+        Ok(input)
+    }
+
+    /// Read the index and footer of a stream, whose index indicator has been
+    /// read, and check them against the blocks decoded.
+    fn finish_stream(&mut self, input: &mut Counted<R>) -> io::Result<()> {
+        let index_start = input.pos - 1;
+        let mut index = vec![0u8];
+        let records = read_varint(input, &mut index)?;
+        if records != self.blocks.len() as u64 {
+            return Err(corrupted("xz index doesn't match the stream's blocks"));
+        }
+        for i in 0..self.blocks.len() {
+            let record = (
+                read_varint(input, &mut index)?,
+                read_varint(input, &mut index)?,
+            );
+            if record != self.blocks[i] {
+                return Err(corrupted("xz index doesn't match the stream's blocks"));
+            }
+        }
+        let padding = (4 - index.len() % 4) % 4;
+        let mut rest = [0u8; 7];
+        input.read_exact(&mut rest[..padding + 4])?;
+        if rest[..padding].iter().any(|&b| b != 0) {
+            return Err(corrupted("Invalid xz index padding"));
+        }
+        index.extend_from_slice(&rest[..padding]);
+        check_crc32(&index, &rest[padding..padding + 4], "index")?;
+
+        let mut footer = [0u8; 12];
+        input.read_exact(&mut footer)?;
+        check_crc32(&footer[4..10], &footer[..4], "stream footer")?;
+        let backward_size = (u64::from(le_u32(&footer[4..8])) + 1) * 4;
+        if footer[10..] != FOOTER_MAGIC
+            || footer[8..10] != self.flags
+            || backward_size != input.pos - 12 - index_start
+        {
+            return Err(corrupted("Invalid xz stream footer"));
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for XzDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        // Left failed by any error returned below
+        loop {
+            self.state = match std::mem::replace(&mut self.state, State::Failed) {
+                State::Stream { input, first } => match self.start_stream(input, first)? {
+                    Some(input) => State::Blocks(input),
+                    None => State::Done,
+                },
+                State::Blocks(input) => self.next_block(input)?,
+                State::Block(mut block) => {
+                    let len = block.data.read(buf)?;
+                    if len > 0 {
+                        block.decoded += len as u64;
+                        block.check.update(&buf[..len]);
+                        self.state = State::Block(block);
+                        return Ok(len);
+                    }
+                    State::Blocks(self.finish_block(*block)?)
+                }
+                State::Done => {
+                    self.state = State::Done;
+                    return Ok(0);
+                }
+                State::Failed => {
+                    return Err(io::Error::other("The xz stream already failed to decode"))
+                }
+            };
+        }
+    }
+}
+
+/// Reader that keeps track of its position in the current stream.
+struct Counted<R> {
+    inner: R,
+    pos: u64,
+}
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+/// Check of the uncompressed data of a block.
+enum Check {
+    None,
+    Crc32(Crc),
+    Crc64(crc::Digest<'static, u64>),
+    Sha256(Sha256),
+    /// A check of this many bytes that isn't verified
+    Unknown(usize),
+}
+
+/// The value a block's check should have.
+enum CheckValue {
+    Known(Vec<u8>),
+    Unknown(usize),
+}
+
+impl Check {
+    fn new(check_type: u8) -> Self {
+        match check_type {
+            0x00 => Check::None,
+            0x01 => Check::Crc32(Crc::new()),
+            0x04 => Check::Crc64(CRC64.digest()),
+            0x0A => Check::Sha256(Sha256::new()),
+            // Reserved check types still have a set size
+            other => Check::Unknown(4 << (other.div_ceil(3) - 1)),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Check::Crc32(crc) => crc.update(data),
+            Check::Crc64(digest) => digest.update(data),
+            Check::Sha256(hasher) => hasher.update(data),
+            Check::None | Check::Unknown(_) => {}
+        }
+    }
+
+    fn finish(self) -> CheckValue {
+        match self {
+            Check::None => CheckValue::Known(Vec::new()),
+            Check::Crc32(crc) => CheckValue::Known(crc.sum().to_le_bytes().to_vec()),
+            Check::Crc64(digest) => CheckValue::Known(digest.finalize().to_le_bytes().to_vec()),
+            Check::Sha256(hasher) => CheckValue::Known(hasher.finalize().to_vec()),
+            Check::Unknown(len) => CheckValue::Unknown(len),
+        }
+    }
+}
+
+impl CheckValue {
+    fn len(&self) -> usize {
+        match self {
+            CheckValue::Known(value) => value.len(),
+            CheckValue::Unknown(len) => *len,
+        }
+    }
+
+    fn verify(self) -> Option<Vec<u8>> {
+        match self {
+            CheckValue::Known(value) => Some(value),
+            CheckValue::Unknown(_) => None,
+        }
+    }
+}
+
+/// Dictionary size encoded in the LZMA2 filter's properties byte.
+fn lzma2_dict_size(props: u8) -> io::Result<u32> {
+    match props {
+        0..=39 => Ok((2 | u32::from(props & 1)) << (props / 2 + 11)),
+        40 => Ok(u32::MAX),
+        _ => Err(header_error()),
+    }
+}
+
+/// Skip the null bytes padding a block to a multiple of four bytes.
+fn skip_padding<R: Read>(input: &mut Counted<R>) -> io::Result<()> {
+    let mut padding = [0u8; 3];
+    let len = ((4 - input.pos % 4) % 4) as usize;
+    input.read_exact(&mut padding[..len])?;
+    if padding[..len].iter().any(|&b| b != 0) {
+        return Err(corrupted("Invalid xz block padding"));
+    }
+    Ok(())
+}
+
+/// Decode a multibyte integer from a block header.
+fn varint(buf: &[u8], pos: &mut usize) -> io::Result<u64> {
+    let mut value = 0u64;
+    for i in 0..9 {
+        let byte = *buf.get(*pos).ok_or_else(header_error)?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7F) << (i * 7);
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(header_error())
+}
+
+/// Read a multibyte integer from `input`, appending its bytes to `record`.
+fn read_varint<R: Read>(input: &mut R, record: &mut Vec<u8>) -> io::Result<u64> {
+    let start = record.len();
+    let mut byte = [0u8; 1];
+    loop {
+        input.read_exact(&mut byte)?;
+        record.push(byte[0]);
+        if byte[0] & 0x80 == 0 || record.len() - start == 9 {
+            break;
+        }
+    }
+    varint(&record[start..], &mut 0).map_err(|_| corrupted("Invalid integer in the xz index"))
+}
+
+/// Read into `buf` until it's full or the input ends.
+fn read_up_to<R: Read>(input: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut total = 0;
+    while total < buf.len() {
+        match input.read(&mut buf[total..]) {
+            Ok(0) => break,
+            Ok(n) => total += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(total)
+}
+
+fn check_crc32(data: &[u8], stored: &[u8], what: &str) -> io::Result<()> {
+    let mut crc = Crc::new();
+    crc.update(data);
+    if crc.sum() != le_u32(stored) {
+        return Err(corrupted(&format!(
+            "xz {} doesn't match its checksum",
+            what
+        )));
+    }
+    Ok(())
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn header_error() -> io::Error {
+    corrupted("Invalid xz block header")
+}
+
+fn corrupted(problem: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, problem)
+}
+
+fn unsupported(problem: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, problem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xz2::stream::{Check as XzCheck, Stream};
+    use xz2::write::XzEncoder;
+
+    fn compress(data: &[u8], check: XzCheck) -> Vec<u8> {
+        use std::io::Write;
+
+        let stream = Stream::new_easy_encoder(6, check).unwrap();
+        let mut encoder = XzEncoder::new_stream(Vec::new(), stream);
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    /// Decode `xz` through a small buffer, as the tar reader does.
+    fn decode(xz: &[u8]) -> io::Result<Vec<u8>> {
+        let mut decoder = XzDecoder::new(xz);
+        let mut decoded = Vec::new();
+        let mut buf = [0u8; 1000];
+        loop {
+            match decoder.read(&mut buf)? {
+                0 => return Ok(decoded),
+                len => decoded.extend_from_slice(&buf[..len]),
+            }
+        }
+    }
+
+    fn sample(len: usize) -> Vec<u8> {
+        // Compressible, but not trivially
+        (0..len as u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 29) as u8 + b'a')
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_every_check() {
+        let data = sample(300_000);
+        for check in [
+            XzCheck::None,
+            XzCheck::Crc32,
+            XzCheck::Crc64,
+            XzCheck::Sha256,
+        ] {
+            assert_eq!(decode(&compress(&data, check)).unwrap(), data);
+        }
+        assert_eq!(decode(&compress(b"", XzCheck::Crc64)).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_decode_concatenated_streams() {
+        let mut xz = compress(b"first ", XzCheck::Crc64);
+        xz.extend_from_slice(&[0; 8]);
+        xz.extend(compress(b"second", XzCheck::Crc32));
+        assert_eq!(decode(&xz).unwrap(), b"first second");
+
+        xz.extend_from_slice(b"garbage!");
+        assert_eq!(decode(&xz).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_decode_damaged_streams() {
+        let data = sample(50_000);
+        let xz = compress(&data, XzCheck::Crc64);
+
+        let truncated = &xz[..xz.len() / 2];
+        assert_eq!(
+            decode(truncated).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+
+        // The check ends where the index starts, which the footer locates
+        let mut damaged = xz.clone();
+        let index_len = (le_u32(&xz[xz.len() - 8..]) as usize + 1) * 4;
+        let check_at = xz.len() - 12 - index_len - 8;
+        damaged[check_at] ^= 1;
+        assert_eq!(
+            decode(&damaged).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        assert!(decode(b"not xz at all").is_err());
+    }
+}
@@ -0,0 +1,351 @@
+//! Reading ZIP archives over 4 GB that were written without ZIP64.
+//!
+//! Some tools store the sizes and offsets of a ZIP archive in the 32-bit
+//! fields of the plain format even when they don't fit, so past 4 GB they wrap
+//! around. The central directory then points at the wrong places and entries
+//! read that way come out truncated. Such an archive is recognized by its
+//! length and the missing ZIP64 end record; its layout is worked out from the
+//! local headers actually found in the file, taking each recorded value as the
+//! true one modulo 4 GB.
+
+use crate::error::ExtractError;
+use flate2::read::DeflateDecoder;
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Where the 32-bit fields of a ZIP archive wrap around.
+pub(crate) const WRAP: u64 = 1 << 32;
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+const ZIP64_LOCATOR: u32 = 0x0706_4b50;
+
+/// Longest stretch at the end of an archive the end record can be in: the
+/// record and the longest comment.
+const END_SEARCH_LEN: u64 = 22 + u16::MAX as u64;
+
+/// An entry of a ZIP archive, as located in the file.
+#[derive(Debug, Clone)]
+pub(crate) struct LocatedEntry {
+    pub name: String,
+    pub flags: u16,
+    pub method: u16,
+    pub crc32: u32,
+    /// Offset of the entry's data
+    pub data_start: u64,
+    pub compressed_size: u64,
+    /// Uncompressed size; as recorded, and so possibly wrapped, unless the
+    /// entry is stored
+    pub size: u64,
+    /// Last modification time in seconds since the Unix epoch
+    pub modified: Option<u64>,
+    /// Unix permission bits, if made on Unix
+    pub mode: Option<u32>,
+    /// Whether the recorded offset or sizes were wrong
+    pub wrapped: bool,
+}
+
+impl LocatedEntry {
+    pub fn is_directory(&self) -> bool {
+        self.name.ends_with('/')
+    }
+
+    pub fn encrypted(&self) -> bool {
+        self.flags & 1 != 0
+    }
+}
+
+/// Whether the archive in `reader` is at least `wrap` bytes long but has no
+/// ZIP64 end record, so its 32-bit fields may have wrapped around.
+pub(crate) fn needs_locating<R: Read + Seek>(reader: &mut R, wrap: u64) -> io::Result<bool> {
+    let len = reader.seek(SeekFrom::End(0))?;
+    if len < wrap {
+        return Ok(false);
+    }
+    let Some(end) = find_end_record(reader, len)? else {
+        return Ok(false);
+    };
+    let has_zip64 = end >= 20 && {
+        reader.seek(SeekFrom::Start(end - 20))?;
+        read_u32(reader)? == ZIP64_LOCATOR
+    };
+    Ok(!has_zip64)
+}
+
+/// Work out where the entries of the archive in `reader` really are, with its
+/// 32-bit fields wrapping around at `wrap`.
+///
+/// Entries are taken to be stored in the order of the central directory, as
+/// every writer does; each is found at the first local header its recorded
+/// offset points to (modulo `wrap`) after the previous one, and its data runs
+/// up to the next entry or the central directory.
+pub(crate) fn locate_entries<R: Read + Seek>(
+    reader: &mut R,
+    wrap: u64,
+) -> Result<Vec<LocatedEntry>, ExtractError> {
+    let len = reader.seek(SeekFrom::End(0))?;
+    let end = find_end_record(reader, len)?
+        .ok_or_else(|| corrupted("the end of central directory record is missing"))?;
+    reader.seek(SeekFrom::Start(end + 12))?;
+    let directory_len = u64::from(read_u32(reader)?);
+    let directory_start = end
+        .checked_sub(directory_len)
+        .ok_or_else(|| corrupted("the central directory doesn't fit in the file"))?;
+
+    // The central directory, with its recorded offsets
+    let mut recorded = Vec::new();
+    reader.seek(SeekFrom::Start(directory_start))?;
+    let mut header = [0u8; 46];
+    let mut pos = directory_start;
+    while pos + 46 <= end {
+        reader.read_exact(&mut header)?;
+        if le_u32(&header[0..]) != CENTRAL_HEADER {
+            break;
+        }
+        let name_len = u64::from(le_u16(&header[28..]));
+        let extra_len = u64::from(le_u16(&header[30..]));
+        let comment_len = u64::from(le_u16(&header[32..]));
+        let mut name = vec![0u8; name_len as usize];
+        reader.read_exact(&mut name)?;
+        reader.seek(SeekFrom::Current((extra_len + comment_len) as i64))?;
+        pos += 46 + name_len + extra_len + comment_len;
+
+        let made_on_unix = header[5] == 3;
+        let external = le_u32(&header[38..]);
+        recorded.push((
+            LocatedEntry {
+                name: String::from_utf8_lossy(&name).into_owned(),
+                flags: le_u16(&header[8..]),
+                method: le_u16(&header[10..]),
+                crc32: le_u32(&header[16..]),
+                data_start: 0,
+                compressed_size: u64::from(le_u32(&header[20..])),
+                size: u64::from(le_u32(&header[24..])),
+                // Time, then date
+                modified: crate::probe::dos_to_unix(le_u32(&header[12..])),
+                mode: (made_on_unix && external >> 16 != 0).then_some(external >> 16),
+                wrapped: false,
+            },
+            u64::from(le_u32(&header[42..])),
+        ));
+    }
+
+    // Where each local header really is
+    let mut headers = Vec::with_capacity(recorded.len());
+    let mut earliest = 0;
+    for (entry, offset) in &recorded {
+        let mut candidate = *offset;
+        while candidate < earliest {
+            candidate += wrap;
+        }
+        let header = loop {
+            if candidate + 30 > directory_start {
+                return Err(corrupted(&format!(
+                    "no local header found for {}",
+                    entry.name
+                )));
+            }
+            if let Some(data_start) = local_data_start(reader, candidate, &entry.name)? {
+                break (candidate, data_start);
+            }
+            candidate += wrap;
+        };
+        earliest = header.1;
+        headers.push(header);
+    }
+
+    // Data runs up to the next entry; whole multiples of `wrap` were lost
+    let mut entries = Vec::with_capacity(recorded.len());
+    for (i, ((mut entry, offset), (header, data_start))) in
+        recorded.into_iter().zip(&headers).enumerate()
+    {
+        let boundary = headers.get(i + 1).map_or(directory_start, |next| next.0);
+        let available = boundary.saturating_sub(*data_start);
+        let recorded_size = entry.compressed_size;
+        if available > recorded_size {
+            entry.compressed_size += (available - recorded_size) / wrap * wrap;
+        }
+        if entry.method == 0 {
+            entry.size = entry.compressed_size;
+        }
+        entry.wrapped = *header != offset || entry.compressed_size != recorded_size;
+        entry.data_start = *data_start;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Reader over the uncompressed data of `entry`, read from `source`, failing
+/// at the end if the data doesn't match the recorded checksum.
+pub(crate) fn entry_reader<'a, R: Read + Seek + 'a>(
+    source: &'a mut R,
+    entry: &LocatedEntry,
+) -> Result<Box<dyn Read + 'a>, ExtractError> {
+    if entry.encrypted() {
+        return Err(ExtractError::UnsupportedFormat(format!(
+            "{} is encrypted in a ZIP archive over 4 GB written without ZIP64",
+            entry.name
+        )));
+    }
+    source.seek(SeekFrom::Start(entry.data_start))?;
+    let data = source.take(entry.compressed_size);
+    let data: Box<dyn Read + 'a> = match entry.method {
+        0 => Box::new(data),
+        8 => Box::new(DeflateDecoder::new(data)),
+        method => {
+            return Err(ExtractError::UnsupportedFormat(format!(
+                "{} uses compression method {} in a ZIP archive over 4 GB written without ZIP64",
+                entry.name, method
+            )))
+        }
+    };
+    Ok(Box::new(CheckedReader {
+        inner: data,
+        crc: flate2::Crc::new(),
+        expected: entry.crc32,
+    }))
+}
+
+/// Reader checking the CRC-32 of everything read through it once it ends.
+struct CheckedReader<R> {
+    inner: R,
+    crc: flate2::Crc,
+    expected: u32,
+}
+
+impl<R: Read> Read for CheckedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.crc.update(&buf[..read]);
+        if read == 0 && !buf.is_empty() && self.crc.sum() != self.expected {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid checksum",
+            ));
+        }
+        Ok(read)
+    }
+}
+
+/// Offset of the end of central directory record, searching back from the
+/// end of the `len` byte archive.
+fn find_end_record<R: Read + Seek>(reader: &mut R, len: u64) -> io::Result<Option<u64>> {
+    let start = len.saturating_sub(END_SEARCH_LEN);
+    reader.seek(SeekFrom::Start(start))?;
+    let mut tail = Vec::new();
+    reader.take(len - start).read_to_end(&mut tail)?;
+    let signature = END_OF_DIRECTORY.to_le_bytes();
+    Ok(tail
+        .windows(4)
+        .rposition(|window| window == signature)
+        .map(|at| start + at as u64))
+}
+
+/// Offset of the data after the local header at `offset`, if there is one
+/// there for `name`.
+fn local_data_start<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    name: &str,
+) -> io::Result<Option<u64>> {
+    let mut header = [0u8; 30];
+    reader.seek(SeekFrom::Start(offset))?;
+    reader.read_exact(&mut header)?;
+    let name_len = le_u16(&header[26..]);
+    if le_u32(&header) != LOCAL_HEADER || usize::from(name_len) != name.len() {
+        return Ok(None);
+    }
+    let extra_len = le_u16(&header[28..]);
+    Ok(Some(
+        offset + 30 + u64::from(name_len) + u64::from(extra_len),
+    ))
+}
+
+fn corrupted(problem: &str) -> ExtractError {
+    ExtractError::Corrupted(format!("ZIP archive over 4 GB without ZIP64: {}", problem))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn le_u16(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    /// Wrap the sizes and offsets of a ZIP archive around at 64 KiB, as a
+    /// writer without ZIP64 does at 4 GB.
+    fn wrap_at_64k(zip: &mut [u8]) {
+        let end = zip
+            .windows(4)
+            .rposition(|w| w == END_OF_DIRECTORY.to_le_bytes())
+            .unwrap();
+        let wrap_field = |zip: &mut [u8], at: usize| {
+            let value = le_u32(&zip[at..]) % (1 << 16);
+            zip[at..at + 4].copy_from_slice(&value.to_le_bytes());
+        };
+        let mut pos = le_u32(&zip[end + 16..]) as usize;
+        wrap_field(zip, end + 16);
+        while le_u32(&zip[pos..]) == CENTRAL_HEADER {
+            for field in [20, 24, 42] {
+                wrap_field(zip, pos + field);
+            }
+            let lens =
+                le_u16(&zip[pos + 28..]) + le_u16(&zip[pos + 30..]) + le_u16(&zip[pos + 32..]);
+            pos += 46 + lens as usize;
+        }
+    }
+
+    #[test]
+    fn test_locate_wrapped_entries() {
+        let big: Vec<u8> = (0..150_000u32).map(|i| (i % 251) as u8).collect();
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, method, content) in [
+            ("big.bin", zip::CompressionMethod::Stored, big.as_slice()),
+            (
+                "after.txt",
+                zip::CompressionMethod::Deflated,
+                b"after".as_slice(),
+            ),
+        ] {
+            let options = SimpleFileOptions::default().compression_method(method);
+            zip.start_file(name, options).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        let mut data = zip.finish().unwrap().into_inner();
+        wrap_at_64k(&mut data);
+
+        let mut source = Cursor::new(data);
+        assert!(needs_locating(&mut source, 1 << 16).unwrap());
+        assert!(!needs_locating(&mut source, WRAP).unwrap());
+
+        let entries = locate_entries(&mut source, 1 << 16).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].compressed_size, big.len() as u64);
+        assert_eq!(entries[0].size, big.len() as u64);
+        assert!(entries[0].wrapped);
+        // Its recorded offset was wrapped too
+        assert!(entries[1].wrapped);
+
+        for (entry, expected) in entries.iter().zip([big.as_slice(), b"after"]) {
+            let mut content = Vec::new();
+            entry_reader(&mut source, entry)
+                .unwrap()
+                .read_to_end(&mut content)
+                .unwrap();
+            assert_eq!(content, expected, "{}", entry.name);
+        }
+    }
+}
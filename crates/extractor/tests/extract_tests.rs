@@ -1,9 +1,17 @@
-use extractor::{extract, ExtractOptions, OverwriteMode};
+use extractor::{
+    extract, extract_entry_to_writer, open_reader, Archive, Compression, ContentKind,
+    ContentPolicy, EntryAction, EntryFilter, EntryObserver, EntryType, ErrorHint, ErrorPolicy,
+    ExtractError, ExtractOptions, FilenameEncoding, Format, NameSanitizer, NormalizationForm,
+    OversizePolicy, OverwriteDecision, OverwriteMode, OverwritePrompt, PathLimits, PathMapper,
+    PreWriteHook, ProgressEvent, ProgressListener, ProgressPhase, ProgressSink, SecurityError,
+    SymlinkPolicy, TimestampPolicy, WindowsNamePolicy, WriteDecision,
+};
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tempfile::TempDir;
 
 /// Helper to create a test ZIP archive
@@ -206,6 +214,179 @@ fn test_extract_with_overwrite_skip() {
     assert!(output_dir.join("data.json").exists());
 }
 
+#[test]
+fn test_extract_reports_skipped_entries_and_directories() {
+    use std::sync::atomic::AtomicU64;
+    use std::sync::Mutex;
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    let output_dir = temp_dir.path().join("output");
+
+    let mut zip = ZipWriter::new(File::create(&archive_path).unwrap());
+    zip.add_directory("docs/", SimpleFileOptions::default()).unwrap();
+    zip.start_file("docs/a.txt", SimpleFileOptions::default()).unwrap();
+    zip.write_all(b"first").unwrap();
+    zip.start_file("b.txt", SimpleFileOptions::default()).unwrap();
+    zip.write_all(b"second").unwrap();
+    zip.finish().unwrap();
+
+    let actions = Arc::new(Mutex::new(Vec::new()));
+    let seen = actions.clone();
+    let options = ExtractOptions {
+        overwrite: OverwriteMode::Skip,
+        on_entry: Some(EntryObserver::new(move |name, action| {
+            let name = name.rsplit('/').next().unwrap().to_string();
+            seen.lock().unwrap().push((name, action));
+        })),
+        ..Default::default()
+    };
+    let finished = Arc::new(AtomicU64::new(0));
+    let counter = finished.clone();
+    let progress_cb = move |_file: &str, _bytes: u64, total: Option<u64>| {
+        if total.is_some() {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+        true
+    };
+
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, Arc::new(AtomicBool::new(false))).unwrap();
+    assert_eq!(stats.files_extracted, 2);
+    assert_eq!(stats.directories, 1);
+    assert_eq!(stats.files_skipped, 0);
+    assert_eq!(
+        *actions.lock().unwrap(),
+        vec![
+            ("docs".to_string(), EntryAction::Directory),
+            ("a.txt".to_string(), EntryAction::Written),
+            ("b.txt".to_string(), EntryAction::Written),
+        ]
+    );
+    assert_eq!(finished.load(Ordering::Relaxed), 3);
+
+    // Everything is there already, yet every entry is still reported
+    actions.lock().unwrap().clear();
+    finished.store(0, Ordering::Relaxed);
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, Arc::new(AtomicBool::new(false))).unwrap();
+    assert_eq!(stats.files_extracted, 0);
+    assert_eq!(stats.files_skipped, 2);
+    assert_eq!(
+        *actions.lock().unwrap(),
+        vec![
+            ("docs".to_string(), EntryAction::Directory),
+            ("a.txt".to_string(), EntryAction::Skipped),
+            ("b.txt".to_string(), EntryAction::Skipped),
+        ]
+    );
+    assert_eq!(finished.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+fn test_extract_reports_structured_progress() {
+    use std::sync::Mutex;
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    let output_dir = temp_dir.path().join("output");
+    create_test_zip(&archive_path).unwrap();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let seen = events.clone();
+    let options = ExtractOptions::builder()
+        .on_progress(ProgressListener::new(move |event| {
+            seen.lock().unwrap().push(event.clone());
+            true
+        }))
+        .build();
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, Arc::new(AtomicBool::new(false))).unwrap();
+
+    // The archive is listed first, for the totals
+    let events = events.lock().unwrap();
+    let (scanning, extracting): (Vec<_>, Vec<_>) =
+        events.iter().partition(|event| event.phase == ProgressPhase::Scanning);
+    assert_eq!(scanning.last().unwrap().entry_index, 3);
+    // Each entry's start and end
+    assert_eq!(extracting.len(), 6);
+    let done: Vec<_> = extracting
+        .iter()
+        .filter(|event| event.action.is_some())
+        .collect();
+    let mut bytes_written = 0;
+    for (index, event) in done.iter().enumerate() {
+        assert_eq!(event.phase, ProgressPhase::Extracting);
+        assert_eq!(event.entry_index, index as u64);
+        assert_eq!(event.total_entries, Some(3));
+        assert_eq!(event.action, Some(EntryAction::Written));
+        assert_eq!(event.entry_total, Some(event.entry_bytes));
+        bytes_written += event.entry_bytes;
+        assert_eq!(event.bytes_written, bytes_written);
+        assert_eq!(event.total_bytes, Some(stats.bytes_written));
+    }
+    assert_eq!(done[0].path, "test.txt");
+    assert_eq!(bytes_written, stats.bytes_written);
+}
+
+#[test]
+fn test_extract_reports_to_progress_sink() {
+    use std::sync::Mutex;
+
+    /// Records the reports it gets, one line each
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl ProgressSink for Recorder {
+        fn on_entry_start(&self, event: &ProgressEvent) -> bool {
+            self.0.lock().unwrap().push(format!("start {}", event.path));
+            true
+        }
+
+        fn on_entry_done(&self, event: &ProgressEvent) -> bool {
+            let report = format!("{} {}", event.action.unwrap(), event.path);
+            self.0.lock().unwrap().push(report);
+            true
+        }
+
+        fn on_warning(&self, warning: &str) {
+            self.0.lock().unwrap().push(format!("warning {}", warning));
+        }
+    }
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    let output_dir = temp_dir.path().join("output");
+    create_test_zip(&archive_path).unwrap();
+
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let options = ExtractOptions::builder()
+        .on_progress(ProgressListener::from_sink(Recorder(reports.clone())))
+        .pre_write_hook(PreWriteHook::new(|entry, _| {
+            if entry.path == "data.json" {
+                WriteDecision::Skip
+            } else {
+                WriteDecision::Write
+            }
+        }))
+        .build();
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    extract(&archive_path, &output_dir, &options, &progress_cb, Arc::new(AtomicBool::new(false))).unwrap();
+
+    assert_eq!(
+        *reports.lock().unwrap(),
+        vec![
+            "start test.txt",
+            "written test.txt",
+            "start subdir/nested.txt",
+            "written subdir/nested.txt",
+            "start data.json",
+            "warning Skipped data.json (rejected by pre-write hook)",
+            "skipped data.json",
+        ]
+    );
+}
+
 #[test]
 fn test_extract_with_overwrite_replace() {
     let temp_dir = TempDir::new().unwrap();
@@ -237,7 +418,52 @@ fn test_extract_with_overwrite_replace() {
 }
 
 #[test]
-fn test_extract_with_cancellation() {
+fn test_extract_with_overwrite_if_newer() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.tar");
+    let output_dir = temp_dir.path().join("output");
+
+    // Create a TAR archive with one entry older and one newer than the files on disk
+    let mut tar = tar::Builder::new(File::create(&archive_path).unwrap());
+    for (name, mtime, content) in [
+        ("old.txt", 1_000_000_000u64, b"Archived old".as_slice()),
+        ("new.txt", 4_000_000_000u64, b"Archived new".as_slice()),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        header.set_cksum();
+        tar.append_data(&mut header, name, content).unwrap();
+    }
+    tar.finish().unwrap();
+    drop(tar);
+
+    // Create output directory and pre-existing files
+    fs::create_dir_all(&output_dir).unwrap();
+    fs::write(output_dir.join("old.txt"), b"Existing old").unwrap();
+    fs::write(output_dir.join("new.txt"), b"Existing new").unwrap();
+
+    // Extract with if-newer mode
+    let options = ExtractOptions {
+        overwrite: OverwriteMode::IfNewer,
+        ..ExtractOptions::default()
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+
+    // Only the newer entry should replace its file
+    assert_eq!(stats.files_extracted, 1);
+    let old = fs::read_to_string(output_dir.join("old.txt")).unwrap();
+    assert_eq!(old, "Existing old");
+    let new = fs::read_to_string(output_dir.join("new.txt")).unwrap();
+    assert_eq!(new, "Archived new");
+}
+
+#[test]
+fn test_extract_with_overwrite_error() {
     let temp_dir = TempDir::new().unwrap();
     let archive_path = temp_dir.path().join("test.zip");
     let output_dir = temp_dir.path().join("output");
@@ -245,138 +471,2973 @@ fn test_extract_with_cancellation() {
     // Create test archive
     create_test_zip(&archive_path).unwrap();
 
-    // Extract with cancellation
-    let options = ExtractOptions::default();
-    let cancel_flag = Arc::new(AtomicBool::new(false));
-    let cancel_flag_clone = cancel_flag.clone();
+    // Create output directory and pre-existing file
+    fs::create_dir_all(&output_dir).unwrap();
+    fs::write(output_dir.join("test.txt"), b"Existing content").unwrap();
 
-    let progress_cb = move |_file: &str, _bytes: u64, _total: Option<u64>| {
-        // Cancel after first file
-        cancel_flag_clone.store(true, Ordering::Relaxed);
-        false
+    // Extract with error mode
+    let options = ExtractOptions {
+        overwrite: OverwriteMode::Error,
+        ..ExtractOptions::default()
     };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
 
     let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
 
-    // Should return cancelled error
-    assert!(result.is_err());
-    match result.unwrap_err() {
-        extractor::ExtractError::Cancelled => {
-            // Expected
+    // Should fail on the existing file and leave it untouched
+    match result {
+        Err(extractor::ExtractError::AlreadyExists(path)) => {
+            assert_eq!(path, output_dir.join("test.txt"));
         }
-        e => panic!("Expected Cancelled error, got: {:?}", e),
+        other => panic!("Expected AlreadyExists, got {:?}", other),
     }
+    let content = fs::read_to_string(output_dir.join("test.txt")).unwrap();
+    assert_eq!(content, "Existing content");
 }
 
 #[test]
-fn test_extract_nonexistent_archive() {
+fn test_extract_with_overwrite_ask() {
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
     let temp_dir = TempDir::new().unwrap();
-    let archive_path = temp_dir.path().join("nonexistent.zip");
+    let archive_path = temp_dir.path().join("test.zip");
     let output_dir = temp_dir.path().join("output");
 
-    let options = ExtractOptions::default();
-    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let names = ["one.txt", "two.txt", "three.txt", "four.txt"];
+    let mut zip = ZipWriter::new(File::create(&archive_path).unwrap());
+    for name in names {
+        zip.start_file(name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"new").unwrap();
+    }
+    zip.finish().unwrap();
+    fs::create_dir_all(&output_dir).unwrap();
+    for name in names {
+        fs::write(output_dir.join(name), b"old").unwrap();
+    }
+
     let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
 
+    // Without a prompt the first conflict fails
+    let options = ExtractOptions {
+        overwrite: OverwriteMode::Ask,
+        ..ExtractOptions::default()
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
     let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+    assert!(matches!(result, Err(ExtractError::AlreadyExists(_))));
+
+    // Answering for all conflicts stops the questions
+    let asked = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let asked_clone = asked.clone();
+    let options = ExtractOptions {
+        overwrite: OverwriteMode::Ask,
+        overwrite_prompt: Some(OverwritePrompt::new(move |existing, entry| {
+            let name = existing.file_name().unwrap().to_string_lossy().to_string();
+            assert_eq!(name, entry.path);
+            asked_clone.lock().unwrap().push(name.clone());
+            match name.as_str() {
+                "one.txt" => OverwriteDecision::Replace,
+                "two.txt" => OverwriteDecision::Skip,
+                _ => OverwriteDecision::ApplyToAll(OverwriteMode::Rename),
+            }
+        })),
+        ..ExtractOptions::default()
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
 
-    assert!(result.is_err());
-    match result.unwrap_err() {
-        extractor::ExtractError::NotFound(_) => {
-            // Expected
-        }
-        e => panic!("Expected NotFound error, got: {:?}", e),
-    }
+    assert_eq!(*asked.lock().unwrap(), ["one.txt", "two.txt", "three.txt"]);
+    assert_eq!(stats.files_extracted, 3);
+    assert_eq!(stats.files_skipped, 1);
+    assert_eq!(fs::read_to_string(output_dir.join("one.txt")).unwrap(), "new");
+    assert_eq!(fs::read_to_string(output_dir.join("two.txt")).unwrap(), "old");
+    assert_eq!(fs::read_to_string(output_dir.join("three.txt")).unwrap(), "old");
+    assert_eq!(fs::read_to_string(output_dir.join("three (1).txt")).unwrap(), "new");
+    assert_eq!(fs::read_to_string(output_dir.join("four (1).txt")).unwrap(), "new");
 }
 
+#[cfg(unix)]
 #[test]
-fn test_extract_with_size_limit() {
+fn test_extract_preserves_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
     let temp_dir = TempDir::new().unwrap();
-    let archive_path = temp_dir.path().join("test.zip");
-    let output_dir = temp_dir.path().join("output");
+    let archive_path = temp_dir.path().join("test.tar");
+
+    // Create a TAR archive with an executable script
+    let mut tar = tar::Builder::new(File::create(&archive_path).unwrap());
+    let content = b"#!/bin/sh\necho hi\n";
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o750);
+    header.set_cksum();
+    tar.append_data(&mut header, "run.sh", content.as_slice()).unwrap();
+    tar.finish().unwrap();
+    drop(tar);
 
-    // Create test archive
-    create_test_zip(&archive_path).unwrap();
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    // Preserved: the archived mode is applied as-is
+    let preserved_dir = temp_dir.path().join("preserved");
+    let options = ExtractOptions {
+        preserve_permissions: Some(true),
+        ..ExtractOptions::default()
+    };
+    extract(&archive_path, &preserved_dir, &options, &progress_cb, cancel_flag.clone()).unwrap();
+    let mode = fs::metadata(preserved_dir.join("run.sh")).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o750);
+
+    // Not preserved: the file is not executable
+    let plain_dir = temp_dir.path().join("plain");
+    let options = ExtractOptions {
+        preserve_permissions: Some(false),
+        ..ExtractOptions::default()
+    };
+    extract(&archive_path, &plain_dir, &options, &progress_cb, cancel_flag).unwrap();
+    let mode = fs::metadata(plain_dir.join("run.sh")).unwrap().permissions().mode();
+    assert_eq!(mode & 0o111, 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_preserves_tar_permissions_by_default() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let tar_path = temp_dir.path().join("test.tar");
+
+    // A read-only directory holding an executable script
+    let mut tar = tar::Builder::new(File::create(&tar_path).unwrap());
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Directory);
+    header.set_size(0);
+    header.set_mode(0o555);
+    header.set_cksum();
+    tar.append_data(&mut header, "bin/", std::io::empty()).unwrap();
+    let content = b"#!/bin/sh\necho hi\n";
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o755);
+    header.set_cksum();
+    tar.append_data(&mut header, "bin/run.sh", content.as_slice()).unwrap();
+    tar.finish().unwrap();
+    drop(tar);
 
-    // Extract with very small size limit
-    let mut options = ExtractOptions::default();
-    options.size_limit_bytes = Some(10); // Only 10 bytes allowed
     let cancel_flag = Arc::new(AtomicBool::new(false));
     let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
 
-    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+    // The directory's mode is applied after its contents are written
+    let output_dir = temp_dir.path().join("tar");
+    let options = ExtractOptions {
+        apply_umask: false,
+        ..ExtractOptions::default()
+    };
+    extract(&tar_path, &output_dir, &options, &progress_cb, cancel_flag.clone()).unwrap();
+    let mode = fs::metadata(output_dir.join("bin/run.sh")).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o755);
+    let mode = fs::metadata(output_dir.join("bin")).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o555);
+    fs::set_permissions(output_dir.join("bin"), fs::Permissions::from_mode(0o755)).unwrap();
+
+    // ZIP modes are only applied when asked for
+    let zip_path = temp_dir.path().join("test.zip");
+    let mut zip = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+    let file_options = zip::write::SimpleFileOptions::default().unix_permissions(0o755);
+    zip.start_file("run.sh", file_options).unwrap();
+    zip.write_all(content).unwrap();
+    zip.finish().unwrap();
+
+    let output_dir = temp_dir.path().join("zip");
+    extract(&zip_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+    let mode = fs::metadata(output_dir.join("run.sh")).unwrap().permissions().mode();
+    assert_eq!(mode & 0o111, 0);
+}
 
-    // Should fail with size limit exceeded
-    assert!(result.is_err());
-    match result.unwrap_err() {
-        extractor::ExtractError::SizeLimitExceeded { .. } => {
-            // Expected
-        }
-        e => panic!("Expected SizeLimitExceeded error, got: {:?}", e),
-    }
+#[cfg(unix)]
+#[test]
+fn test_extract_creates_contained_symlinks() {
+    let temp_dir = TempDir::new().unwrap();
+    let tar_path = temp_dir.path().join("test.tar");
+
+    let mut tar = tar::Builder::new(File::create(&tar_path).unwrap());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(5);
+    header.set_cksum();
+    tar.append_data(&mut header, "lib/libfoo.so.1", b"hello".as_slice()).unwrap();
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Symlink);
+    header.set_size(0);
+    tar.append_link(&mut header, "bin/foo", "../lib/libfoo.so.1").unwrap();
+    tar.finish().unwrap();
+    drop(tar);
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    // Skipped unless allowed
+    let output_dir = temp_dir.path().join("skipped");
+    let options = ExtractOptions::default();
+    let stats = extract(&tar_path, &output_dir, &options, &progress_cb, cancel_flag.clone()).unwrap();
+    assert!(output_dir.join("bin/foo").symlink_metadata().is_err());
+    assert_eq!(stats.files_skipped, 1);
+    assert!(stats.warnings.iter().any(|w| w.contains("bin/foo")));
+
+    let options = ExtractOptions {
+        symlink_policy: SymlinkPolicy::Preserve,
+        ..ExtractOptions::default()
+    };
+    let output_dir = temp_dir.path().join("tar");
+    extract(&tar_path, &output_dir, &options, &progress_cb, cancel_flag.clone()).unwrap();
+    let link = output_dir.join("bin/foo");
+    assert_eq!(fs::read_link(&link).unwrap(), Path::new("../lib/libfoo.so.1"));
+    assert_eq!(fs::read_to_string(&link).unwrap(), "hello");
+
+    // ZIP stores the target as the data of an entry with a link's mode
+    let zip_path = temp_dir.path().join("test.zip");
+    let mut zip = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+    let file_options = zip::write::SimpleFileOptions::default();
+    zip.start_file("docs/readme.txt", file_options).unwrap();
+    zip.write_all(b"hello").unwrap();
+    zip.add_symlink("readme", "docs/readme.txt", file_options).unwrap();
+    zip.finish().unwrap();
+
+    let output_dir = temp_dir.path().join("zip");
+    extract(&zip_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+    assert_eq!(fs::read_link(output_dir.join("readme")).unwrap(), Path::new("docs/readme.txt"));
 }
 
 #[test]
-fn test_extract_progress_callback() {
+fn test_extract_materializes_symlinks() {
     let temp_dir = TempDir::new().unwrap();
-    let archive_path = temp_dir.path().join("test.zip");
+    let archive_path = temp_dir.path().join("test.tar");
+
+    // Links ahead of their targets, one of them through another link
+    let mut tar = tar::Builder::new(File::create(&archive_path).unwrap());
+    let links = [("current", "v1"), ("latest.txt", "current/notes.txt"), ("gone", "missing")];
+    for (link, target) in links {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        tar.append_link(&mut header, link, target).unwrap();
+    }
+    let mut header = tar::Header::new_gnu();
+    header.set_size(5);
+    header.set_cksum();
+    tar.append_data(&mut header, "v1/notes.txt", b"hello".as_slice()).unwrap();
+    tar.finish().unwrap();
+    drop(tar);
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
     let output_dir = temp_dir.path().join("output");
+    let options = ExtractOptions {
+        symlink_policy: SymlinkPolicy::Materialize,
+        ..ExtractOptions::default()
+    };
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
 
-    // Create test archive
-    create_test_zip(&archive_path).unwrap();
+    let current = output_dir.join("current");
+    assert!(!current.symlink_metadata().unwrap().file_type().is_symlink());
+    assert_eq!(fs::read_to_string(current.join("notes.txt")).unwrap(), "hello");
+    let latest = output_dir.join("latest.txt");
+    assert!(!latest.symlink_metadata().unwrap().file_type().is_symlink());
+    assert_eq!(fs::read_to_string(latest).unwrap(), "hello");
+
+    // A link to something that isn't in the archive is left out
+    assert!(output_dir.join("gone").symlink_metadata().is_err());
+    assert_eq!(stats.files_skipped, 1);
+    assert!(stats.warnings.iter().any(|w| w.contains("gone")));
+}
 
-    // Track progress calls
-    let progress_calls = Arc::new(AtomicBool::new(false));
-    let progress_calls_clone = progress_calls.clone();
+#[test]
+fn test_extract_creates_hardlinks() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.tar");
+
+    let mut tar = tar::Builder::new(File::create(&archive_path).unwrap());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(5);
+    header.set_cksum();
+    tar.append_data(&mut header, "bin/tool", b"hello".as_slice()).unwrap();
+    for (link, target) in [("bin/alias", "bin/tool"), ("bin/orphan", "bin/missing")] {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Link);
+        header.set_size(0);
+        tar.append_link(&mut header, link, target).unwrap();
+    }
+    tar.finish().unwrap();
+    drop(tar);
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
 
+    // Skipped unless allowed
+    let output_dir = temp_dir.path().join("skipped");
     let options = ExtractOptions::default();
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag.clone()).unwrap();
+    assert!(!output_dir.join("bin/alias").exists());
+    assert_eq!(stats.files_skipped, 2);
+
+    // Linked to the extracted file, which is stripped like the link itself
+    let output_dir = temp_dir.path().join("linked");
+    let options = ExtractOptions {
+        allow_hardlinks: true,
+        strip_components: 1,
+        ..ExtractOptions::default()
+    };
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+    assert_eq!(fs::read_to_string(output_dir.join("alias")).unwrap(), "hello");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let tool = fs::metadata(output_dir.join("tool")).unwrap();
+        let alias = fs::metadata(output_dir.join("alias")).unwrap();
+        assert_eq!(tool.ino(), alias.ino());
+    }
+
+    // A link to an entry that wasn't extracted is left out
+    assert!(!output_dir.join("orphan").exists());
+    assert_eq!(stats.files_skipped, 1);
+    assert!(stats.warnings.iter().any(|w| w.contains("orphan")));
+}
+
+#[test]
+fn test_extract_rejects_escaping_symlinks() {
+    let temp_dir = TempDir::new().unwrap();
     let cancel_flag = Arc::new(AtomicBool::new(false));
-    let progress_cb = move |_file: &str, _bytes: u64, _total: Option<u64>| {
-        progress_calls_clone.store(true, Ordering::Relaxed);
-        true
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    let options = ExtractOptions {
+        symlink_policy: SymlinkPolicy::Preserve,
+        ..ExtractOptions::default()
     };
 
-    let _stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+    for (i, target) in ["/etc/passwd", "../../etc/passwd", "sub/../../outside"].iter().enumerate() {
+        let archive_path = temp_dir.path().join(format!("test{}.tar", i));
+        let mut tar = tar::Builder::new(File::create(&archive_path).unwrap());
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        tar.append_link(&mut header, "dir/link", target).unwrap();
+        tar.finish().unwrap();
+        drop(tar);
+
+        let output_dir = temp_dir.path().join(format!("output{}", i));
+        let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag.clone());
+        match result {
+            Err(ExtractError::Security(SecurityError::SymlinkEscape(_))) => {}
+            other => panic!("Expected SymlinkEscape for {}, got {:?}", target, other),
+        }
+        assert!(output_dir.join("dir/link").symlink_metadata().is_err());
+    }
+}
 
-    // Verify progress callback was called
-    assert!(progress_calls.load(Ordering::Relaxed));
+#[cfg(unix)]
+#[test]
+fn test_extract_stays_inside_output_dir() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.tar");
+    let mut tar = tar::Builder::new(File::create(&archive_path).unwrap());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(7);
+    header.set_mode(0o644);
+    tar.append_data(&mut header, "sub/file.txt", &b"content"[..])
+        .unwrap();
+    tar.finish().unwrap();
+    drop(tar);
+
+    // A folder in the output directory that leads elsewhere isn't written through
+    let outside = temp_dir.path().join("outside");
+    let output_dir = temp_dir.path().join("output");
+    fs::create_dir_all(&outside).unwrap();
+    fs::create_dir_all(&output_dir).unwrap();
+    std::os::unix::fs::symlink(&outside, output_dir.join("sub")).unwrap();
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    let result = extract(
+        &archive_path,
+        &output_dir,
+        &ExtractOptions::default(),
+        &progress_cb,
+        cancel_flag,
+    );
+    assert!(result.is_err());
+    assert!(!outside.join("file.txt").exists());
 }
 
+#[cfg(unix)]
 #[test]
-#[ignore] // Password-protected archives require special handling
-fn test_extract_password_protected_without_password() {
-    // This test is ignored because compress-tools has limited password support
-    // In a real implementation with libarchive bindings, this would test:
-    // 1. Detecting password-protected archives
-    // 2. Returning PasswordRequired error when no password is provided
-    // 3. Returning InvalidPassword error when wrong password is provided
-    // 4. Successfully extracting when correct password is provided
+fn test_extract_permissions_stay_inside_output_dir() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.tar");
+    let mut tar = tar::Builder::new(File::create(&archive_path).unwrap());
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Directory);
+    header.set_size(0);
+    header.set_mode(0o700);
+    tar.append_data(&mut header, "sub/", std::io::empty()).unwrap();
+    let mut header = tar::Header::new_gnu();
+    header.set_size(7);
+    header.set_mode(0o644);
+    tar.append_data(&mut header, "file.txt", &b"content"[..])
+        .unwrap();
+    tar.finish().unwrap();
+    drop(tar);
+
+    let outside = temp_dir.path().join("outside");
+    let output_dir = temp_dir.path().join("output");
+    fs::create_dir_all(&outside).unwrap();
+    fs::set_permissions(&outside, fs::Permissions::from_mode(0o755)).unwrap();
+
+    // Folder permissions are applied last; by then the folder has been
+    // swapped for a link to elsewhere
+    let sub = output_dir.join("sub");
+    let (link, target) = (sub.clone(), outside.clone());
+    let progress_cb = move |_file: &str, _bytes: u64, _total: Option<u64>| {
+        if link.is_dir() && !link.is_symlink() {
+            fs::remove_dir(&link).unwrap();
+            std::os::unix::fs::symlink(&target, &link).unwrap();
+        }
+        true
+    };
+    let options = ExtractOptions {
+        preserve_permissions: Some(true),
+        timestamp_policy: TimestampPolicy::Now,
+        ..ExtractOptions::default()
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+
+    assert!(sub.is_symlink());
+    assert!(result.is_err());
+    let mode = fs::metadata(&outside).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o755);
 }
 
 #[test]
-fn test_extract_with_path_traversal_protection() {
-    // This test verifies that malicious archives with path traversal attempts
-    // are safely handled by the validation logic
-    // The actual path validation is tested in safety module tests
-    // Here we just verify that extraction doesn't fail catastrophically
-    
+fn test_extract_skips_special_files() {
     let temp_dir = TempDir::new().unwrap();
-    let archive_path = temp_dir.path().join("test.zip");
+    let archive_path = temp_dir.path().join("test.tar");
     let output_dir = temp_dir.path().join("output");
 
-    // Create a normal archive (malicious archives would be created differently)
-    create_test_zip(&archive_path).unwrap();
+    let mut tar = tar::Builder::new(File::create(&archive_path).unwrap());
+    let comment = "52 comment=written by git archive, not a member\n";
+    let mut header = tar::Header::new_ustar();
+    header.set_entry_type(tar::EntryType::XGlobalHeader);
+    header.set_size(comment.len() as u64);
+    header.set_cksum();
+    tar.append_data(&mut header, "pax_global_header", comment.as_bytes()).unwrap();
+    for (name, entry_type) in [("dev/null", tar::EntryType::Char), ("run/pipe", tar::EntryType::Fifo)] {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(entry_type);
+        header.set_size(0);
+        header.set_cksum();
+        tar.append_data(&mut header, name, std::io::empty()).unwrap();
+    }
+    let mut header = tar::Header::new_gnu();
+    header.set_size(5);
+    header.set_cksum();
+    tar.append_data(&mut header, "notes.txt", b"hello".as_slice()).unwrap();
+    tar.finish().unwrap();
+    drop(tar);
+
+    // Listed with their kind, but never extracted
+    let info = extractor::probe(&archive_path).unwrap();
+    let kinds: Vec<_> = info.entry_list.iter().map(|entry| (entry.path.as_str(), entry.kind)).collect();
+    assert_eq!(
+        kinds,
+        [("dev/null", EntryType::Other), ("run/pipe", EntryType::Other), ("notes.txt", EntryType::File)]
+    );
 
     let options = ExtractOptions::default();
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
     let cancel_flag = Arc::new(AtomicBool::new(false));
+
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+    assert_eq!(stats.files_extracted, 1);
+    assert_eq!(stats.files_skipped, 2);
+    assert!(stats.warnings.iter().any(|w| w.contains("dev/null") && w.contains("special files")));
+    assert!(!output_dir.join("dev/null").exists());
+    assert!(!output_dir.join("run/pipe").exists());
+    assert!(!output_dir.join("pax_global_header").exists());
+    assert_eq!(fs::read_to_string(output_dir.join("notes.txt")).unwrap(), "hello");
+}
+
+#[test]
+fn test_extract_windows_names() {
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+
+    let mut zip = ZipWriter::new(File::create(&archive_path).unwrap());
+    for name in ["aux.txt", "notes?.txt", "plain.txt"] {
+        zip.start_file(name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(name.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+
     let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    let extract_with = |policy: WindowsNamePolicy, output_dir: &Path| {
+        let options = ExtractOptions::builder().windows_names(policy).build();
+        extract(&archive_path, output_dir, &options, &progress_cb, Arc::new(AtomicBool::new(false)))
+    };
 
-    // Should extract successfully
-    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
-    assert!(result.is_ok());
-    
-    // Verify files are in the output directory, not outside it
-    assert!(output_dir.join("test.txt").exists());
-    assert!(!temp_dir.path().join("test.txt").exists()); // Not in parent
+    let output_dir = temp_dir.path().join("renamed");
+    let stats = extract_with(WindowsNamePolicy::Rename, &output_dir).unwrap();
+    assert_eq!(stats.files_extracted, 3);
+    assert_eq!(fs::read_to_string(output_dir.join("aux_.txt")).unwrap(), "aux.txt");
+    assert_eq!(fs::read_to_string(output_dir.join("notes_.txt")).unwrap(), "notes?.txt");
+    let renamed: Vec<_> = stats.renamed.iter().map(|r| (r.path.as_str(), r.renamed_to.as_str())).collect();
+    assert_eq!(renamed, [("aux.txt", "aux_.txt"), ("notes?.txt", "notes_.txt")]);
+
+    let output_dir = temp_dir.path().join("skipped");
+    let stats = extract_with(WindowsNamePolicy::Skip, &output_dir).unwrap();
+    assert_eq!(stats.files_extracted, 1);
+    assert_eq!(stats.files_skipped, 2);
+    assert!(output_dir.join("plain.txt").exists());
+    assert!(!output_dir.join("aux.txt").exists());
+
+    let result = extract_with(WindowsNamePolicy::Strict, &temp_dir.path().join("strict"));
+    assert!(matches!(result, Err(ExtractError::Security(SecurityError::NonPortableName(_)))));
 }
 
+#[test]
+fn test_extract_skips_failed_entries() {
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+
+    // An entry escaping the output directory and one whose data is damaged,
+    // between two good ones
+    let mut zip = ZipWriter::new(File::create(&archive_path).unwrap());
+    let stored = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    for (name, content) in [
+        ("first.txt", "first"),
+        ("../evil.txt", "evil"),
+        ("damaged.txt", "DAMAGED-DATA"),
+        ("last.txt", "last"),
+    ] {
+        zip.start_file(name, stored).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+    let mut bytes = fs::read(&archive_path).unwrap();
+    let at = bytes.windows(12).position(|w| w == b"DAMAGED-DATA").unwrap();
+    bytes[at] = b'X';
+    fs::write(&archive_path, bytes).unwrap();
+
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    // By default the first failure stops the extraction
+    let output_dir = temp_dir.path().join("aborted");
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let result = extract(&archive_path, &output_dir, &ExtractOptions::default(), &progress_cb, cancel_flag);
+    assert!(matches!(result, Err(ExtractError::Security(_))));
+
+    let output_dir = temp_dir.path().join("skipped");
+    let failed = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let failed_clone = failed.clone();
+    let options = ExtractOptions {
+        on_error: ErrorPolicy::Skip,
+        threads: 4,
+        on_entry: Some(EntryObserver::new(move |name, action| {
+            if action == EntryAction::Failed {
+                failed_clone.lock().unwrap().push(name.to_string());
+            }
+        })),
+        ..ExtractOptions::default()
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+
+    assert_eq!(stats.files_extracted, 2);
+    assert_eq!(fs::read_to_string(output_dir.join("first.txt")).unwrap(), "first");
+    assert_eq!(fs::read_to_string(output_dir.join("last.txt")).unwrap(), "last");
+    assert!(!output_dir.join("damaged.txt").exists());
+    assert!(!temp_dir.path().join("evil.txt").exists());
+    let paths: Vec<_> = stats.errors.iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(paths, ["../evil.txt", "damaged.txt"]);
+    assert_eq!(*failed.lock().unwrap(), ["../evil.txt", "damaged.txt"]);
+}
+
+#[test]
+fn test_extract_verifies_written_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    create_test_zip(&archive_path).unwrap();
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    let output_dir = temp_dir.path().join("output");
+    let options = ExtractOptions {
+        verify_after_extract: true,
+        ..ExtractOptions::default()
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+    assert_eq!(stats.files_extracted, 3);
+    assert!(stats.mismatches.is_empty());
+
+    // Files changed once written no longer match their entries
+    let output_dir = temp_dir.path().join("changed");
+    let changed = output_dir.clone();
+    let options = ExtractOptions {
+        verify_after_extract: true,
+        write_queue_depth: 0,
+        on_entry: Some(EntryObserver::new(move |name, _action| match name {
+            "test.txt" => fs::write(changed.join(name), b"Hello").unwrap(),
+            "data.json" => fs::write(changed.join(name), b"{\"key\": \"VALUE\"}").unwrap(),
+            _ => {}
+        })),
+        ..ExtractOptions::default()
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+    let mut mismatches: Vec<_> = stats
+        .mismatches
+        .iter()
+        .map(|mismatch| (mismatch.path.as_str(), mismatch.reason.as_str()))
+        .collect();
+    mismatches.sort();
+    assert_eq!(mismatches.len(), 2);
+    assert_eq!(mismatches[0].0, "data.json");
+    assert!(mismatches[0].1.starts_with("CRC-32 is"));
+    assert_eq!(mismatches[1], ("test.txt", "5 bytes, expected 13"));
+}
+
+#[test]
+fn test_extract_path_limits() {
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("deep.zip");
+    let deep = "d/".repeat(2000) + "file.txt";
+    let mut zip = ZipWriter::new(File::create(&archive_path).unwrap());
+    for name in ["shallow/file.txt", deep.as_str()] {
+        zip.start_file(name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"content").unwrap();
+    }
+    zip.finish().unwrap();
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    // Each path over the limits is reported as its entry's failure
+    let output_dir = temp_dir.path().join("output");
+    let options = ExtractOptions {
+        on_error: ErrorPolicy::Skip,
+        ..ExtractOptions::default()
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+    assert_eq!(stats.files_extracted, 1);
+    assert_eq!(stats.errors.len(), 1);
+    assert_eq!(stats.errors[0].path, deep);
+    assert!(stats.errors[0].error.contains("nested too deeply"));
+    assert!(!output_dir.join("d").exists());
+
+    // A lower limit catches the shallow file too
+    let output_dir = temp_dir.path().join("strict");
+    let options = ExtractOptions {
+        path_limits: PathLimits {
+            max_depth: Some(1),
+            ..PathLimits::default()
+        },
+        ..ExtractOptions::default()
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+    assert!(matches!(
+        result,
+        Err(ExtractError::Security(SecurityError::PathTooDeep(_)))
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_restores_ownership() {
+    use std::os::unix::fs::MetadataExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.tar");
+
+    let mut tar = tar::Builder::new(File::create(&archive_path).unwrap());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(5);
+    header.set_uid(1234);
+    header.set_gid(5678);
+    header.set_cksum();
+    tar.append_data(&mut header, "owned.txt", b"hello".as_slice()).unwrap();
+    tar.finish().unwrap();
+    drop(tar);
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    let options = ExtractOptions {
+        preserve_ownership: true,
+        ..ExtractOptions::default()
+    };
+    let output_dir = temp_dir.path().join("output");
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+
+    // Only root can give files away; others get a warning instead
+    let probe = temp_dir.path().join("probe");
+    File::create(&probe).unwrap();
+    let metadata = fs::metadata(output_dir.join("owned.txt")).unwrap();
+    if std::os::unix::fs::chown(&probe, Some(1234), None).is_ok() {
+        assert_eq!((metadata.uid(), metadata.gid()), (1234, 5678));
+        assert!(stats.warnings.is_empty());
+    } else {
+        assert_ne!(metadata.uid(), 1234);
+        assert!(stats.warnings.iter().any(|w| w.contains("ownership")));
+    }
+}
+
+#[test]
+fn test_extract_preserves_modification_times() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.tar");
+
+    let mut tar = tar::Builder::new(File::create(&archive_path).unwrap());
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Directory);
+    header.set_size(0);
+    header.set_mtime(1_500_000_000);
+    header.set_cksum();
+    tar.append_data(&mut header, "docs/", std::io::empty()).unwrap();
+    let mut header = tar::Header::new_gnu();
+    header.set_size(5);
+    header.set_mtime(1_600_000_000);
+    header.set_cksum();
+    tar.append_data(&mut header, "docs/readme.txt", b"hello".as_slice()).unwrap();
+    tar.finish().unwrap();
+    drop(tar);
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    let modified = |path: &Path| {
+        let time = fs::metadata(path).unwrap().modified().unwrap();
+        time.duration_since(UNIX_EPOCH).unwrap().as_secs()
+    };
+
+    // The directory keeps its time although its file was written after it
+    let preserved_dir = temp_dir.path().join("preserved");
+    let options = ExtractOptions::default();
+    extract(&archive_path, &preserved_dir, &options, &progress_cb, cancel_flag.clone()).unwrap();
+    assert_eq!(modified(&preserved_dir.join("docs/readme.txt")), 1_600_000_000);
+    assert_eq!(modified(&preserved_dir.join("docs")), 1_500_000_000);
+
+    let touched_dir = temp_dir.path().join("touched");
+    let options = ExtractOptions {
+        timestamp_policy: TimestampPolicy::Now,
+        ..ExtractOptions::default()
+    };
+    let before = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    extract(&archive_path, &touched_dir, &options, &progress_cb, cancel_flag).unwrap();
+    assert!(modified(&touched_dir.join("docs/readme.txt")) >= before);
+    assert!(modified(&touched_dir.join("docs")) >= before);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_with_fixed_modes() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.tar");
+
+    // World-writable entry, as produced by archivers on Windows
+    let mut tar = tar::Builder::new(File::create(&archive_path).unwrap());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(5);
+    header.set_mode(0o777);
+    header.set_cksum();
+    tar.append_data(&mut header, "docs/readme.txt", b"hello".as_slice()).unwrap();
+    tar.finish().unwrap();
+    drop(tar);
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    let output_dir = temp_dir.path().join("output");
+    let options = ExtractOptions {
+        preserve_permissions: Some(true),
+        file_mode: Some(0o640),
+        dir_mode: Some(0o750),
+        ..ExtractOptions::default()
+    };
+    extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+
+    let file_mode = fs::metadata(output_dir.join("docs/readme.txt")).unwrap().permissions().mode();
+    assert_eq!(file_mode & 0o7777, 0o640);
+    let dir_mode = fs::metadata(output_dir.join("docs")).unwrap().permissions().mode();
+    assert_eq!(dir_mode & 0o7777, 0o750);
+}
+
+#[test]
+fn test_extract_sanitizes_control_characters() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.tar");
+
+    let mut tar = tar::Builder::new(File::create(&archive_path).unwrap());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(5);
+    header.set_mode(0o644);
+    header.set_cksum();
+    tar.append_data(&mut header, "evil\nname.txt", b"hello".as_slice()).unwrap();
+    tar.finish().unwrap();
+    drop(tar);
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    // Sanitized by default, and listed as renamed
+    let output_dir = temp_dir.path().join("output");
+    let options = ExtractOptions::default();
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag.clone()).unwrap();
+    assert!(output_dir.join("evil_name.txt").exists());
+    assert_eq!(stats.renamed.len(), 1);
+    assert_eq!(stats.renamed[0].path, "evil\nname.txt");
+    assert_eq!(stats.renamed[0].renamed_to, "evil_name.txt");
+
+    // Rejected when sanitizing is disabled
+    let strict_dir = temp_dir.path().join("strict");
+    let options = ExtractOptions {
+        sanitize_control_chars: false,
+        ..ExtractOptions::default()
+    };
+    let result = extract(&archive_path, &strict_dir, &options, &progress_cb, cancel_flag);
+    assert!(matches!(
+        result,
+        Err(ExtractError::Security(SecurityError::ControlCharacters(_)))
+    ));
+}
+
+/// ZIP archive of `names` stored in `encoding` without the UTF-8 flag, as
+/// archivers on Windows write them
+fn create_legacy_zip(path: &Path, encoding: &'static encoding_rs::Encoding, names: &[&str]) {
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    // Written under ASCII placeholders of the same length, patched afterwards
+    let raw_names: Vec<Vec<u8>> = names.iter().map(|name| encoding.encode(name).0.into_owned()).collect();
+    let placeholders: Vec<String> =
+        raw_names.iter().enumerate().map(|(i, raw)| format!("{}{}", i, "_".repeat(raw.len() - 1))).collect();
+    let mut zip = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for placeholder in &placeholders {
+        zip.start_file(placeholder.as_str(), SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"data").unwrap();
+    }
+    let mut bytes = zip.finish().unwrap().into_inner();
+    for (placeholder, raw) in placeholders.iter().zip(&raw_names) {
+        let placeholder = placeholder.as_bytes();
+        let mut at = 0;
+        while let Some(found) = bytes[at..].windows(placeholder.len()).position(|w| w == placeholder) {
+            bytes[at + found..at + found + raw.len()].copy_from_slice(raw);
+            at += found + raw.len();
+        }
+    }
+    fs::write(path, bytes).unwrap();
+}
+
+#[test]
+fn test_extract_legacy_filename_encoding() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    let names = ["日本語のファイル名.txt", "資料/報告書.txt"];
+    create_legacy_zip(&archive_path, encoding_rs::SHIFT_JIS, &names);
+
+    // Detected when listing and extracting
+    let info = extractor::probe(&archive_path).unwrap();
+    let listed: Vec<_> = info.entry_list.iter().map(|entry| entry.path.as_str()).collect();
+    assert_eq!(listed, names);
+
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    let output_dir = temp_dir.path().join("output");
+    let options = ExtractOptions::default();
+    extract(&archive_path, &output_dir, &options, &progress_cb, Arc::new(AtomicBool::new(false))).unwrap();
+    for name in names {
+        assert_eq!(fs::read_to_string(output_dir.join(name)).unwrap(), "data");
+    }
+
+    // Overridden, as CP437
+    let cp437 = FilenameEncoding::for_label("cp437").unwrap();
+    let probe_options = extractor::ProbeOptions { filename_encoding: Some(cp437), ..Default::default() };
+    let info = extractor::probe_with_options(&archive_path, &probe_options).unwrap();
+    assert_ne!(info.entry_list[0].path, names[0]);
+    let output_dir = temp_dir.path().join("cp437");
+    let options = ExtractOptions::builder().filename_encoding(cp437).build();
+    extract(&archive_path, &output_dir, &options, &progress_cb, Arc::new(AtomicBool::new(false))).unwrap();
+    assert!(output_dir.join(&info.entry_list[0].path).exists());
+    assert!(!output_dir.join(names[0]).exists());
+}
+
+#[test]
+fn test_extract_normalizes_unicode_names() {
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+
+    // The same folder, written decomposed on macOS and composed elsewhere
+    let mut zip = ZipWriter::new(File::create(&archive_path).unwrap());
+    for name in ["re\u{301}sume\u{301}/mac.txt", "r\u{e9}sum\u{e9}/windows.txt", "skipped.txt"] {
+        zip.start_file(name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"data").unwrap();
+    }
+    zip.finish().unwrap();
+
+    let output_dir = temp_dir.path().join("output");
+    let options = ExtractOptions {
+        normalize_names: NormalizationForm::Nfc,
+        entry_filter: Some(EntryFilter::new(|entry| entry.path.starts_with("r\u{e9}sum\u{e9}/"))),
+        ..ExtractOptions::default()
+    };
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, Arc::new(AtomicBool::new(false))).unwrap();
+
+    assert_eq!(stats.files_extracted, 2);
+    let folders: Vec<_> = fs::read_dir(&output_dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    assert_eq!(folders, ["r\u{e9}sum\u{e9}"]);
+    assert!(output_dir.join("r\u{e9}sum\u{e9}/mac.txt").exists());
+    assert!(output_dir.join("r\u{e9}sum\u{e9}/windows.txt").exists());
+}
+
+#[test]
+fn test_extract_sanitizes_names() {
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    let long_name = format!("{}.txt", "x".repeat(300));
+
+    let mut zip = ZipWriter::new(File::create(&archive_path).unwrap());
+    for name in ["-rf", "docs/trailing. ", long_name.as_str(), "plain.txt"] {
+        zip.start_file(name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"data").unwrap();
+    }
+    zip.finish().unwrap();
+
+    let output_dir = temp_dir.path().join("output");
+    let sanitizer = NameSanitizer {
+        trailing_dots: true,
+        leading_dashes: true,
+        max_name_bytes: Some(100),
+    };
+    let options = ExtractOptions::builder().sanitize_names(sanitizer).build();
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, Arc::new(AtomicBool::new(false))).unwrap();
+
+    assert_eq!(stats.files_extracted, 4);
+    let shortened = format!("{}.txt", "x".repeat(96));
+    for name in ["_rf", "docs/trailing__", shortened.as_str(), "plain.txt"] {
+        assert_eq!(fs::read_to_string(output_dir.join(name)).unwrap(), "data");
+    }
+    let renamed: Vec<_> = stats.renamed.iter().map(|r| r.renamed_to.as_str()).collect();
+    assert_eq!(renamed, ["_rf", "docs/trailing__", shortened.as_str()]);
+}
+
+#[test]
+fn test_extract_with_entry_filter() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    let output_dir = temp_dir.path().join("output");
+
+    create_test_zip(&archive_path).unwrap();
+
+    // Keep only text files smaller than 14 bytes
+    let options = ExtractOptions {
+        entry_filter: Some(EntryFilter::new(|entry| {
+            entry.path.ends_with(".txt") && entry.size < 14
+        })),
+        ..ExtractOptions::default()
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+
+    assert!(output_dir.join("test.txt").exists());
+    assert!(!output_dir.join("subdir/nested.txt").exists());
+    assert!(!output_dir.join("data.json").exists());
+    assert_eq!(stats.files_extracted, 1);
+}
+
+#[test]
+fn test_extract_with_pre_write_hook() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    create_test_zip(&archive_path).unwrap();
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    // Skip files whose first bytes look like JSON; the rest is written intact
+    let output_dir = temp_dir.path().join("output");
+    let options = ExtractOptions {
+        pre_write_hook: Some(PreWriteHook::new(|_entry, data| {
+            let mut first = [0u8; 1];
+            match data.read(&mut first) {
+                Ok(1) if first[0] == b'{' => WriteDecision::Skip,
+                _ => WriteDecision::Write,
+            }
+        })),
+        ..ExtractOptions::default()
+    };
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag.clone()).unwrap();
+
+    assert_eq!(stats.files_skipped, 1);
+    assert!(!output_dir.join("data.json").exists());
+    assert_eq!(fs::read_to_string(output_dir.join("test.txt")).unwrap(), "Hello, World!");
+
+    // Abort stops the extraction with an error naming the entry
+    let abort_dir = temp_dir.path().join("abort");
+    let options = ExtractOptions {
+        pre_write_hook: Some(PreWriteHook::new(|_entry, _data| WriteDecision::Abort)),
+        ..ExtractOptions::default()
+    };
+    let result = extract(&archive_path, &abort_dir, &options, &progress_cb, cancel_flag);
+    assert!(matches!(result, Err(ExtractError::Rejected(path)) if path == "test.txt"));
+}
+
+#[test]
+fn test_extract_with_pre_write_hook_reading_large_file() {
+    use std::sync::atomic::AtomicU64;
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("large.zip");
+    let content: Vec<u8> = (0..10 * 1024 * 1024u32).map(|i| (i % 251) as u8).collect();
+    let mut zip = zip::ZipWriter::new(File::create(&archive_path).unwrap());
+    let stored = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    zip.start_file("large.bin", stored).unwrap();
+    zip.write_all(&content).unwrap();
+    zip.finish().unwrap();
+
+    // A hook scanning the whole file doesn't hold it all in memory, and the
+    // file still comes out intact
+    let scanned = Arc::new(AtomicU64::new(0));
+    let scanned_by_hook = scanned.clone();
+    let spool_dir = temp_dir.path().join("spool");
+    let options = ExtractOptions {
+        pre_write_hook: Some(PreWriteHook::new(move |_entry, data| {
+            let n = std::io::copy(data, &mut std::io::sink()).unwrap();
+            scanned_by_hook.store(n, Ordering::SeqCst);
+            WriteDecision::Write
+        })),
+        temp_dir: Some(spool_dir.clone()),
+        ..ExtractOptions::default()
+    };
+    let output_dir = temp_dir.path().join("output");
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    extract(&archive_path, &output_dir, &options, &progress_cb, Arc::new(AtomicBool::new(false))).unwrap();
+
+    assert_eq!(scanned.load(Ordering::SeqCst), content.len() as u64);
+    assert!(fs::read(output_dir.join("large.bin")).unwrap() == content);
+    // The spooled data went to an anonymous file, leaving nothing behind
+    assert_eq!(fs::read_dir(&spool_dir).unwrap().count(), 0);
+}
+
+#[test]
+fn test_extract_with_path_mapping() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    create_test_zip(&archive_path).unwrap();
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    // Flatten everything into one folder
+    let output_dir = temp_dir.path().join("output");
+    let options = ExtractOptions {
+        map_path: Some(PathMapper::new(|path| {
+            Path::new("flat").join(path.file_name().unwrap())
+        })),
+        ..ExtractOptions::default()
+    };
+    extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag.clone()).unwrap();
+    assert!(output_dir.join("flat/test.txt").exists());
+    assert!(output_dir.join("flat/nested.txt").exists());
+    assert!(!output_dir.join("subdir").exists());
+
+    // Mapped paths are validated again
+    let escape_dir = temp_dir.path().join("escape");
+    let options = ExtractOptions {
+        map_path: Some(PathMapper::new(|path| Path::new("..").join(path))),
+        ..ExtractOptions::default()
+    };
+    let result = extract(&archive_path, &escape_dir, &options, &progress_cb, cancel_flag);
+    assert!(matches!(
+        result,
+        Err(ExtractError::Security(SecurityError::PathTraversal(_)))
+    ));
+}
+
+#[test]
+fn test_extract_with_content_policy() {
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("untrusted.zip");
+    let mut zip = ZipWriter::new(File::create(&archive_path).unwrap());
+    let files: [(&str, &[u8]); 3] = [
+        ("readme.txt", b"Just text"),
+        ("bin/tool", b"\x7fELF\x02\x01\x01 rest of binary"),
+        ("install.sh", b"echo installing"),
+    ];
+    for (name, data) in files {
+        zip.start_file(name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(data).unwrap();
+    }
+    zip.finish().unwrap();
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    // Blocked files are left out and reported
+    let output_dir = temp_dir.path().join("blocked");
+    let options = ExtractOptions {
+        content_policy: Some(ContentPolicy {
+            deny: vec![ContentKind::Executable, ContentKind::Script],
+            quarantine_dir: None,
+        }),
+        ..ExtractOptions::default()
+    };
+    let stats = extract(
+        &archive_path,
+        &output_dir,
+        &options,
+        &progress_cb,
+        cancel_flag.clone(),
+    )
+    .unwrap();
+    assert_eq!(stats.files_extracted, 1);
+    assert!(output_dir.join("readme.txt").exists());
+    assert!(!output_dir.join("bin/tool").exists());
+    assert!(!output_dir.join("install.sh").exists());
+    let mut blocked: Vec<_> = stats
+        .blocked
+        .iter()
+        .map(|b| (b.path.as_str(), b.kind))
+        .collect();
+    blocked.sort_by_key(|(path, _)| *path);
+    assert_eq!(
+        blocked,
+        [
+            ("bin/tool", ContentKind::Executable),
+            ("install.sh", ContentKind::Script)
+        ]
+    );
+
+    // Quarantined files keep their full content
+    let output_dir = temp_dir.path().join("quarantined");
+    let quarantine_dir = temp_dir.path().join("quarantine");
+    let options = ExtractOptions {
+        content_policy: Some(ContentPolicy {
+            deny: vec![ContentKind::Executable],
+            quarantine_dir: Some(quarantine_dir.clone()),
+        }),
+        ..ExtractOptions::default()
+    };
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+    assert_eq!(stats.files_extracted, 2);
+    assert_eq!(stats.blocked.len(), 1);
+    assert_eq!(
+        fs::read(quarantine_dir.join("tool")).unwrap(),
+        b"\x7fELF\x02\x01\x01 rest of binary"
+    );
+    assert_eq!(
+        stats.blocked[0].quarantined_to.as_deref(),
+        Some(quarantine_dir.join("tool").to_string_lossy().as_ref())
+    );
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = fs::metadata(quarantine_dir.join("tool")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
+
+#[test]
+fn test_extract_entry_to_writer() {
+    let temp_dir = TempDir::new().unwrap();
+    let zip_path = temp_dir.path().join("test.zip");
+    let tar_path = temp_dir.path().join("test.tar.gz");
+    create_test_zip(&zip_path).unwrap();
+    create_test_tar_gz(&tar_path).unwrap();
+
+    let options = ExtractOptions::default();
+
+    let mut buf = Vec::new();
+    let written =
+        extract_entry_to_writer(&zip_path, "subdir/nested.txt", &mut buf, &options).unwrap();
+    assert_eq!(buf, b"Nested content");
+    assert_eq!(written, buf.len() as u64);
+
+    let mut buf = Vec::new();
+    extract_entry_to_writer(&tar_path, "./test.txt", &mut buf, &options).unwrap();
+    assert_eq!(buf, b"Hello from TAR!");
+
+    // Directories and unknown names aren't entries to read
+    for name in ["subdir", "missing.txt"] {
+        let result = extract_entry_to_writer(&zip_path, name, &mut Vec::new(), &options);
+        assert!(matches!(result, Err(ExtractError::EntryNotFound(_))));
+    }
+
+    let options = ExtractOptions {
+        size_limit_bytes: Some(4),
+        ..ExtractOptions::default()
+    };
+    let result = extract_entry_to_writer(&zip_path, "test.txt", &mut Vec::new(), &options);
+    assert!(matches!(
+        result,
+        Err(ExtractError::SizeLimitExceeded { .. })
+    ));
+}
+
+#[test]
+fn test_archive_entry_reader() {
+    use std::io::Read;
+
+    let temp_dir = TempDir::new().unwrap();
+    let tar_path = temp_dir.path().join("test.tar.gz");
+    create_test_tar_gz(&tar_path).unwrap();
+
+    let archive = Archive::open(&tar_path).unwrap();
+    assert_eq!(archive.format(), Format::Tar(Compression::Gzip));
+
+    let mut content = String::new();
+    archive
+        .entry_reader("subdir/nested.txt")
+        .unwrap()
+        .read_to_string(&mut content)
+        .unwrap();
+    assert_eq!(content, "Nested in TAR");
+
+    assert!(matches!(
+        archive.entry_reader("missing.txt"),
+        Err(ExtractError::EntryNotFound(_))
+    ));
+
+    // Large entries can be read partially; dropping the reader stops the rest
+    let zip_path = temp_dir.path().join("large.zip");
+    {
+        use zip::write::{SimpleFileOptions, ZipWriter};
+
+        let mut zip = ZipWriter::new(File::create(&zip_path).unwrap());
+        let options = SimpleFileOptions::default();
+        zip.start_file("big.bin", options).unwrap();
+        zip.write_all(&vec![7u8; 4 * 1024 * 1024]).unwrap();
+        zip.finish().unwrap();
+    }
+    let mut reader = Archive::open(&zip_path)
+        .unwrap()
+        .entry_reader("big.bin")
+        .unwrap();
+    let mut head = [0u8; 16];
+    reader.read_exact(&mut head).unwrap();
+    assert_eq!(head, [7u8; 16]);
+    drop(reader);
+}
+
+#[test]
+fn test_archive_reader_lists_and_reads_entries() {
+    let temp_dir = TempDir::new().unwrap();
+    let zip_path = temp_dir.path().join("test.zip");
+    create_test_zip(&zip_path).unwrap();
+    let tar_path = temp_dir.path().join("test.tar.gz");
+    create_test_tar_gz(&tar_path).unwrap();
+
+    let mut reader = open_reader(&zip_path, Format::Zip, None).unwrap();
+    let names: Vec<String> = reader
+        .entries()
+        .unwrap()
+        .map(|entry| entry.unwrap().path)
+        .collect();
+    assert_eq!(names, ["test.txt", "subdir/nested.txt", "data.json"]);
+
+    // Every format hands out entries the same way
+    let mut contents = Vec::new();
+    let mut reader = open_reader(&tar_path, Format::Tar(Compression::Gzip), None).unwrap();
+    reader
+        .read_entries(&mut |entry, data| {
+            let mut content = String::new();
+            data.reader()?.read_to_string(&mut content)?;
+            contents.push((entry.path.clone(), content));
+            Ok(true)
+        })
+        .unwrap();
+    assert_eq!(
+        contents,
+        [
+            ("test.txt".to_string(), "Hello from TAR!".to_string()),
+            ("subdir/nested.txt".to_string(), "Nested in TAR".to_string()),
+        ]
+    );
+
+    assert!(matches!(
+        "CAB".parse::<Format>(),
+        Err(ExtractError::UnsupportedFormat(_))
+    ));
+}
+
+#[test]
+fn test_extract_with_time_limit() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    let output_dir = temp_dir.path().join("output");
+    create_test_zip(&archive_path).unwrap();
+
+    // Every entry takes longer than the whole budget
+    let options = ExtractOptions {
+        max_duration: Some(Duration::from_millis(20)),
+        pre_write_hook: Some(PreWriteHook::new(|_, _| {
+            std::thread::sleep(Duration::from_millis(100));
+            WriteDecision::Write
+        })),
+        ..ExtractOptions::default()
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    let result = extract(
+        &archive_path,
+        &output_dir,
+        &options,
+        &progress_cb,
+        cancel_flag.clone(),
+    );
+    assert!(matches!(result, Err(ExtractError::TimedOut(_))));
+    assert!(cancel_flag.load(Ordering::Relaxed));
+
+    // Jobs finishing in time are unaffected
+    let options = ExtractOptions {
+        max_duration: Some(Duration::from_secs(60)),
+        ..ExtractOptions::default()
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+    assert_eq!(stats.files_extracted, 3);
+}
+
+#[test]
+fn test_extract_with_cancellation() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    let output_dir = temp_dir.path().join("output");
+
+    // Create test archive
+    create_test_zip(&archive_path).unwrap();
+
+    // Extract with cancellation
+    let options = ExtractOptions::default();
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let cancel_flag_clone = cancel_flag.clone();
+
+    let progress_cb = move |_file: &str, _bytes: u64, _total: Option<u64>| {
+        // Cancel after first file
+        cancel_flag_clone.store(true, Ordering::Relaxed);
+        false
+    };
+
+    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+
+    // Should return cancelled error
+    assert!(result.is_err());
+    match result.unwrap_err() {
+        extractor::ExtractError::Cancelled => {
+            // Expected
+        }
+        e => panic!("Expected Cancelled error, got: {:?}", e),
+    }
+}
+
+#[test]
+fn test_extract_nonexistent_archive() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("nonexistent.zip");
+    let output_dir = temp_dir.path().join("output");
+
+    let options = ExtractOptions::default();
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+
+    assert!(result.is_err());
+    match result.unwrap_err() {
+        extractor::ExtractError::NotFound(_) => {
+            // Expected
+        }
+        e => panic!("Expected NotFound error, got: {:?}", e),
+    }
+}
+
+#[test]
+fn test_extract_with_size_limit() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    let output_dir = temp_dir.path().join("output");
+
+    // Create test archive
+    create_test_zip(&archive_path).unwrap();
+
+    // Extract with very small size limit
+    let mut options = ExtractOptions::default();
+    options.size_limit_bytes = Some(10); // Only 10 bytes allowed
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+
+    // Should fail with size limit exceeded
+    assert!(result.is_err());
+    match result.unwrap_err() {
+        extractor::ExtractError::SizeLimitExceeded { .. } => {
+            // Expected
+        }
+        e => panic!("Expected SizeLimitExceeded error, got: {:?}", e),
+    }
+}
+
+#[test]
+fn test_extract_atomic_writes() {
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("damaged.zip");
+    let content = b"Stored data that gets damaged near its end. ".repeat(64);
+
+    let mut zip = ZipWriter::new(File::create(&archive_path).unwrap());
+    let stored = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    zip.start_file("data.bin", stored).unwrap();
+    zip.write_all(&content).unwrap();
+    zip.finish().unwrap();
+
+    // Corrupt the last stored byte, so the checksum fails once the data is written
+    let mut bytes = fs::read(&archive_path).unwrap();
+    let start = bytes
+        .windows(content.len())
+        .position(|window| window == content.as_slice())
+        .unwrap();
+    bytes[start + content.len() - 1] ^= 0xff;
+    fs::write(&archive_path, bytes).unwrap();
+
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    for atomic_writes in [true, false] {
+        let output_dir = temp_dir.path().join(format!("output-{}", atomic_writes));
+        let options = ExtractOptions {
+            atomic_writes,
+            cleanup_on_failure: false,
+            ..ExtractOptions::default()
+        };
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+
+        let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+        assert!(result.is_err());
+
+        // Only direct writes leave the damaged file behind
+        let names: Vec<_> = fs::read_dir(&output_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        if atomic_writes {
+            assert!(names.is_empty(), "{:?}", names);
+        } else {
+            assert_eq!(names, ["data.bin"]);
+        }
+    }
+}
+
+#[test]
+fn test_extract_transactional() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    create_test_zip(&archive_path).unwrap();
+
+    let options = ExtractOptions {
+        transactional: true,
+        ..ExtractOptions::default()
+    };
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    let leftovers = |dir: &Path| {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.contains(".staging-"))
+            .count()
+    };
+
+    // Success moves the staged folder into place
+    let output_dir = temp_dir.path().join("output");
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+    assert_eq!(stats.files_extracted, 3);
+    assert!(output_dir.join("test.txt").exists());
+    assert_eq!(leftovers(temp_dir.path()), 0);
+
+    // A folder that already has files can't be swapped in one step
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+    assert!(matches!(result, Err(ExtractError::AlreadyExists(_))));
+
+    // Failing partway leaves neither the output folder nor the staging folder
+    let output_dir = temp_dir.path().join("limited");
+    let options = ExtractOptions {
+        size_limit_bytes: Some(20),
+        ..options
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+    assert!(matches!(result, Err(ExtractError::SizeLimitExceeded { .. })));
+    assert!(!output_dir.exists());
+    assert_eq!(leftovers(temp_dir.path()), 0);
+}
+
+#[test]
+fn test_extract_cleanup_on_failure() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    create_test_zip(&archive_path).unwrap();
+
+    // Cancel once the nested file is written
+    let progress_cb = |file: &str, _bytes: u64, _total: Option<u64>| !file.contains("nested");
+
+    // Cleanup removes what the extraction created, but not what was there before
+    let options = ExtractOptions::default();
+    let output_dir = temp_dir.path().join("cleaned");
+    fs::create_dir(&output_dir).unwrap();
+    fs::write(output_dir.join("keep.txt"), "mine").unwrap();
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+    assert!(matches!(result, Err(ExtractError::Cancelled)));
+    let names: Vec<_> = fs::read_dir(&output_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(names, ["keep.txt"]);
+
+    // A new output folder is removed along with its contents
+    let output_dir = temp_dir.path().join("new/output");
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+    assert!(result.is_err());
+    assert!(!temp_dir.path().join("new").exists());
+
+    // Without cleanup the files are left and reported
+    let options = ExtractOptions {
+        cleanup_on_failure: false,
+        ..options
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+    let Err(ExtractError::Partial { error, stats }) = result else {
+        panic!("expected partial output, got {:?}", result);
+    };
+    assert!(matches!(*error, ExtractError::Cancelled));
+    let mut partial_files = stats.partial_files.clone();
+    partial_files.sort();
+    let nested = Path::new("subdir").join("nested.txt");
+    assert_eq!(partial_files, [nested.to_string_lossy(), "test.txt".into()]);
+    assert!(output_dir.join("test.txt").exists());
+}
+
+#[test]
+fn test_extract_journal() {
+    use extractor::journal::{journal_path, read_journal, resume_extraction, rollback};
+    use extractor::ExtractionJournal;
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    create_test_zip(&archive_path).unwrap();
+    let output_dir = temp_dir.path().join("output");
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    // The journal only exists while the extraction runs
+    let options = ExtractOptions {
+        journal: true,
+        ..ExtractOptions::default()
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+    assert!(!journal_path(&output_dir).exists());
+    assert!(read_journal(&output_dir).unwrap().is_none());
+
+    // Journal of a run that crashed after one entry, partway through another
+    let resumed_dir = temp_dir.path().join("resumed");
+    fs::create_dir(&resumed_dir).unwrap();
+    fs::write(resumed_dir.join("test.txt"), "from the first run").unwrap();
+    fs::write(resumed_dir.join("data.json"), "{\"ke").unwrap();
+    let crashed = ExtractionJournal {
+        archive_path: archive_path.to_string_lossy().to_string(),
+        output_dir: resumed_dir.to_string_lossy().to_string(),
+        entries_completed: 1,
+        bytes_written: 13,
+        completed: vec!["test.txt".to_string()],
+        current_file: Some(resumed_dir.join("data.json").to_string_lossy().to_string()),
+        created_files: vec![resumed_dir.join("test.txt").to_string_lossy().to_string()],
+        created_dirs: vec![resumed_dir.to_string_lossy().to_string()],
+    };
+    let crashed_json = serde_json::to_vec(&crashed).unwrap();
+    fs::write(journal_path(&resumed_dir), &crashed_json).unwrap();
+
+    // Resuming skips the completed entry and redoes the interrupted one
+    let journal = read_journal(&resumed_dir).unwrap().unwrap();
+    assert_eq!(journal.completed, ["test.txt"]);
+    let options = ExtractOptions {
+        overwrite: OverwriteMode::Error,
+        ..ExtractOptions::default()
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let stats = resume_extraction(&resumed_dir, &options, &progress_cb, cancel_flag).unwrap();
+    assert_eq!(stats.files_extracted, 2);
+    assert_eq!(
+        fs::read_to_string(resumed_dir.join("test.txt")).unwrap(),
+        "from the first run"
+    );
+    assert_eq!(
+        fs::read_to_string(resumed_dir.join("data.json")).unwrap(),
+        "{\"key\": \"value\"}"
+    );
+    assert!(!journal_path(&resumed_dir).exists());
+
+    // Rolling back removes what the journal says was created
+    fs::write(journal_path(&resumed_dir), &crashed_json).unwrap();
+    fs::remove_dir_all(resumed_dir.join("subdir")).unwrap();
+    fs::remove_file(resumed_dir.join("data.json")).unwrap();
+    rollback(&read_journal(&resumed_dir).unwrap().unwrap()).unwrap();
+    assert!(!resumed_dir.exists());
+    assert!(!journal_path(&resumed_dir).exists());
+}
+
+#[test]
+fn test_extract_journal_outside_output_dir() {
+    use extractor::journal::{journal_path, read_journal, resume_extraction, rollback};
+    use extractor::ExtractionJournal;
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    create_test_zip(&archive_path).unwrap();
+    let output_dir = temp_dir.path().join("output");
+    fs::create_dir(&output_dir).unwrap();
+    fs::write(output_dir.join("test.txt"), "from the first run").unwrap();
+    let outside = temp_dir.path().join("outside.txt");
+    fs::write(&outside, "not extracted").unwrap();
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    // A journal naming a file outside the output directory, directly or
+    // through `..`
+    let escaping = output_dir.join("..").join("outside.txt");
+    for planted in [&outside, &escaping] {
+        let planted = planted.to_string_lossy().to_string();
+        let journal = ExtractionJournal {
+            archive_path: archive_path.to_string_lossy().to_string(),
+            output_dir: output_dir.to_string_lossy().to_string(),
+            entries_completed: 1,
+            bytes_written: 13,
+            completed: vec!["test.txt".to_string()],
+            current_file: Some(planted.clone()),
+            created_files: vec![
+                output_dir.join("test.txt").to_string_lossy().to_string(),
+                planted,
+            ],
+            created_dirs: vec![output_dir.to_string_lossy().to_string()],
+        };
+        fs::write(journal_path(&output_dir), serde_json::to_vec(&journal).unwrap()).unwrap();
+
+        let journal = read_journal(&output_dir).unwrap().unwrap();
+        assert!(matches!(
+            rollback(&journal),
+            Err(ExtractError::Security(SecurityError::PathTraversal(_)))
+        ));
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        assert!(matches!(
+            resume_extraction(&output_dir, &ExtractOptions::default(), &progress_cb, cancel_flag),
+            Err(ExtractError::Security(SecurityError::PathTraversal(_)))
+        ));
+        assert_eq!(fs::read_to_string(&outside).unwrap(), "not extracted");
+        assert!(output_dir.join("test.txt").exists());
+    }
+
+    // A journal for another output directory isn't read at all
+    let journal = ExtractionJournal {
+        archive_path: archive_path.to_string_lossy().to_string(),
+        output_dir: temp_dir.path().to_string_lossy().to_string(),
+        entries_completed: 0,
+        bytes_written: 0,
+        completed: Vec::new(),
+        current_file: None,
+        created_files: vec![outside.to_string_lossy().to_string()],
+        created_dirs: Vec::new(),
+    };
+    fs::write(journal_path(&output_dir), serde_json::to_vec(&journal).unwrap()).unwrap();
+    assert!(matches!(
+        read_journal(&output_dir),
+        Err(ExtractError::Security(SecurityError::PathTraversal(_)))
+    ));
+    assert!(outside.exists());
+}
+
+#[test]
+fn test_extract_tar_trailing_data() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let mut tar = tar::Builder::new(Vec::new());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(5);
+    header.set_mode(0o644);
+    header.set_cksum();
+    tar.append_data(&mut header, "hello.txt", b"hello".as_slice()).unwrap();
+    let with_end = tar.into_inner().unwrap();
+
+    // The entry's header and data block, without the zero blocks ending it
+    let without_end = with_end[..1024].to_vec();
+
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    for (name, mut bytes) in [("signed", with_end), ("unterminated", without_end)] {
+        bytes.extend_from_slice(&[0xAB; 1000]);
+        let archive_path = temp_dir.path().join(format!("{}.tar", name));
+        fs::write(&archive_path, bytes).unwrap();
+
+        // Tolerated with a warning by default
+        let output_dir = temp_dir.path().join(name);
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        let stats = extract(
+            &archive_path,
+            &output_dir,
+            &ExtractOptions::default(),
+            &progress_cb,
+            cancel_flag,
+        )
+        .unwrap();
+        assert_eq!(stats.files_extracted, 1);
+        assert_eq!(fs::read_to_string(output_dir.join("hello.txt")).unwrap(), "hello");
+        assert!(
+            stats.warnings.iter().any(|w| w.starts_with("Ignored")),
+            "{:?}",
+            stats.warnings
+        );
+    }
+
+    // Without tolerance a missing end marker fails
+    let options = ExtractOptions {
+        tolerate_trailing_data: false,
+        ..ExtractOptions::default()
+    };
+    let archive_path = temp_dir.path().join("unterminated.tar");
+    let output_dir = temp_dir.path().join("strict");
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_extract_concatenated_tar() {
+    let temp_dir = TempDir::new().unwrap();
+
+    // What `tar -A` leaves: a second archive after the first one's end marker
+    let mut data = Vec::new();
+    for name in ["first.txt", "second.txt"] {
+        let mut tar = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, name, b"data".as_slice()).unwrap();
+        data.extend(tar.into_inner().unwrap());
+    }
+    let archive_path = temp_dir.path().join("joined.tar");
+    fs::write(&archive_path, data).unwrap();
+
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    for (read_concatenated_tar, files) in [(false, 1), (true, 2)] {
+        let output_dir = temp_dir.path().join(format!("output-{}", files));
+        let options = ExtractOptions {
+            read_concatenated_tar,
+            ..ExtractOptions::default()
+        };
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+        assert_eq!(stats.files_extracted, files);
+        assert_eq!(output_dir.join("second.txt").exists(), read_concatenated_tar);
+
+        // Without reading on, the ignored archive is pointed out
+        let pointed_out = stats.warnings.iter().any(|w| w.contains("concatenated"));
+        assert_eq!(pointed_out, !read_concatenated_tar, "{:?}", stats.warnings);
+    }
+}
+
+#[test]
+fn test_extract_write_queue_depth() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.tar");
+
+    // A file spanning several chunks, and a name that appears twice
+    let large: Vec<u8> = (0..700_000u32).map(|i| (i % 251) as u8).collect();
+    let mut tar = tar::Builder::new(File::create(&archive_path).unwrap());
+    for (name, content) in [
+        ("large.bin", large.as_slice()),
+        ("dup.txt", b"first".as_slice()),
+        ("dup.txt", b"second".as_slice()),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, name, content).unwrap();
+    }
+    tar.finish().unwrap();
+    drop(tar);
+
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    for write_queue_depth in [0, 1, 8] {
+        let output_dir = temp_dir.path().join(format!("output-{}", write_queue_depth));
+        let options = ExtractOptions {
+            write_queue_depth,
+            ..ExtractOptions::default()
+        };
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+
+        let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+
+        // The second copy of a name is renamed, even while the first is queued
+        assert_eq!(stats.files_extracted, 3);
+        assert_eq!(stats.bytes_written, large.len() as u64 + 11);
+        assert_eq!(fs::read(output_dir.join("large.bin")).unwrap(), large);
+        assert_eq!(fs::read_to_string(output_dir.join("dup.txt")).unwrap(), "first");
+        assert_eq!(
+            fs::read_to_string(output_dir.join("dup (1).txt")).unwrap(),
+            "second"
+        );
+    }
+
+    // A file over the size limit is dropped by the writer, not left behind
+    let output_dir = temp_dir.path().join("output-limited");
+    let options = ExtractOptions {
+        size_limit_bytes: Some(500_000),
+        cleanup_on_failure: false,
+        ..ExtractOptions::default()
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+    assert!(matches!(result, Err(ExtractError::SizeLimitExceeded { .. })));
+    assert_eq!(fs::read_dir(&output_dir).unwrap().count(), 0);
+}
+
+#[test]
+fn test_extract_max_entry_size() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    create_test_zip(&archive_path).unwrap();
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    // data.json is the only file over 14 bytes
+    let output_dir = temp_dir.path().join("rejected");
+    let options = ExtractOptions {
+        max_entry_size_bytes: Some(14),
+        ..ExtractOptions::default()
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+    match result {
+        Err(ExtractError::EntryTooLarge { entry, size, limit }) => {
+            assert_eq!((entry.as_str(), size, limit), ("data.json", 16, 14));
+        }
+        other => panic!("Expected EntryTooLarge, got: {:?}", other),
+    }
+
+    let output_dir = temp_dir.path().join("skipped");
+    let options = ExtractOptions {
+        max_entry_size_bytes: Some(14),
+        oversized_entries: OversizePolicy::Skip,
+        ..ExtractOptions::default()
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+    assert_eq!(stats.files_extracted, 2);
+    assert_eq!(stats.files_skipped, 1);
+    assert!(stats.warnings.iter().any(|w| w.contains("data.json")));
+    assert!(output_dir.join("subdir/nested.txt").exists());
+    assert!(!output_dir.join("data.json").exists());
+}
+
+#[test]
+fn test_extract_compression_ratio_limits() {
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("bomb.zip");
+    let mut zip = ZipWriter::new(File::create(&archive_path).unwrap());
+    zip.start_file("note.txt", SimpleFileOptions::default()).unwrap();
+    zip.write_all(b"not compressible much").unwrap();
+    zip.start_file("zeros.bin", SimpleFileOptions::default()).unwrap();
+    zip.write_all(&vec![0u8; 4 << 20]).unwrap();
+    zip.finish().unwrap();
+
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    let extract_with = |name: &str, options: ExtractOptions| {
+        let output_dir = temp_dir.path().join(name);
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+        (result, output_dir)
+    };
+
+    // Megabytes of zeros deflate to a few kilobytes
+    let (result, output_dir) = extract_with(
+        "entry-ratio",
+        ExtractOptions {
+            max_compression_ratio: Some(100),
+            cleanup_on_failure: false,
+            ..ExtractOptions::default()
+        },
+    );
+    match result.unwrap_err().root_cause() {
+        ExtractError::Security(SecurityError::SuspectedArchiveBomb(message)) => {
+            assert!(message.contains("zeros.bin"), "{}", message);
+        }
+        other => panic!("Expected SuspectedArchiveBomb, got: {:?}", other),
+    }
+    assert!(output_dir.join("note.txt").exists());
+    assert!(!output_dir.join("zeros.bin").exists());
+
+    // The whole archive measured against its size, with files written at once
+    let (result, _) = extract_with(
+        "total-ratio",
+        ExtractOptions {
+            max_expansion_ratio: Some(100),
+            threads: 2,
+            ..ExtractOptions::default()
+        },
+    );
+    assert!(matches!(
+        result,
+        Err(ExtractError::Security(SecurityError::SuspectedArchiveBomb(_)))
+    ));
+
+    // Generous limits let it through
+    let (result, output_dir) = extract_with(
+        "generous",
+        ExtractOptions {
+            max_compression_ratio: Some(10_000),
+            max_expansion_ratio: Some(10_000),
+            ..ExtractOptions::default()
+        },
+    );
+    assert_eq!(result.unwrap().files_extracted, 2);
+    assert_eq!(fs::metadata(output_dir.join("zeros.bin")).unwrap().len(), 4 << 20);
+}
+
+#[test]
+fn test_extract_zip_with_threads() {
+    use std::sync::atomic::AtomicU64;
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+
+    // Files of different sizes, so workers finish out of order
+    let files: Vec<(String, Vec<u8>)> = (0..24u32)
+        .map(|i| {
+            let len = if i % 6 == 0 { 400_000 } else { 100 * i as usize };
+            let content = (0..len).map(|b| (b as u32 * (i + 1) % 251) as u8).collect();
+            (format!("dir{}/file{}.bin", i % 3, i), content)
+        })
+        .collect();
+    let total: u64 = files.iter().map(|(_, content)| content.len() as u64).sum();
+    let mut zip = ZipWriter::new(File::create(&archive_path).unwrap());
+    for (name, content) in &files {
+        zip.start_file(name.as_str(), SimpleFileOptions::default()).unwrap();
+        zip.write_all(content).unwrap();
+    }
+    zip.finish().unwrap();
+
+    let output_dir = temp_dir.path().join("output");
+    let options = ExtractOptions {
+        threads: 4,
+        ..ExtractOptions::default()
+    };
+    let reported = Arc::new(AtomicU64::new(0));
+    let last_total = AtomicU64::new(0);
+    let counter = reported.clone();
+    let progress_cb = move |_file: &str, bytes: u64, _total: Option<u64>| {
+        // Totals only grow, whichever worker finishes first
+        assert!(bytes >= last_total.swap(bytes, Ordering::Relaxed));
+        counter.fetch_add(1, Ordering::Relaxed);
+        true
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+
+    assert_eq!(stats.files_extracted, files.len() as u64);
+    assert_eq!(stats.bytes_written, total);
+    assert_eq!(reported.load(Ordering::Relaxed), files.len() as u64);
+    for (name, content) in &files {
+        assert_eq!(&fs::read(output_dir.join(name)).unwrap(), content, "{}", name);
+    }
+
+    // The size limit holds across the files being written at once
+    let output_dir = temp_dir.path().join("limited");
+    let options = ExtractOptions {
+        threads: 4,
+        size_limit_bytes: Some(total / 2),
+        ..ExtractOptions::default()
+    };
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+    assert!(matches!(result, Err(ExtractError::SizeLimitExceeded { .. })));
+    assert!(!output_dir.exists());
+
+    // Stopping from the progress callback cancels the workers
+    let output_dir = temp_dir.path().join("cancelled");
+    let options = ExtractOptions {
+        threads: 4,
+        ..ExtractOptions::default()
+    };
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| false;
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+    assert!(matches!(result, Err(ExtractError::Cancelled)));
+}
+
+#[test]
+fn test_extract_bypass_page_cache() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.tar");
+
+    // Larger than a direct I/O block, with an unaligned tail
+    let large: Vec<u8> = (0..1_500_001u32).map(|i| (i % 251) as u8).collect();
+    let mut tar = tar::Builder::new(File::create(&archive_path).unwrap());
+    for (name, content) in [
+        ("large.bin", large.as_slice()),
+        ("small.txt", b"small".as_slice()),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, name, content).unwrap();
+    }
+    tar.finish().unwrap();
+    drop(tar);
+
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    for depth in [0, 8] {
+        let output_dir = temp_dir.path().join(format!("output-{}", depth));
+        let options = ExtractOptions {
+            bypass_page_cache: true,
+            write_queue_depth: depth,
+            ..ExtractOptions::default()
+        };
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+
+        let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+        assert_eq!(stats.files_extracted, 2);
+        assert_eq!(fs::read(output_dir.join("large.bin")).unwrap(), large);
+        assert_eq!(fs::read_to_string(output_dir.join("small.txt")).unwrap(), "small");
+    }
+}
+
+#[test]
+fn test_extract_sparse_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.tar");
+    let output_dir = temp_dir.path().join("output");
+
+    // A disk image that's mostly zeros, ending in a hole
+    let mut image = vec![0u8; 2 * 1024 * 1024];
+    image[4096..4100].copy_from_slice(b"boot");
+    image[1_000_003] = 1;
+    let mut tar = tar::Builder::new(File::create(&archive_path).unwrap());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(image.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    tar.append_data(&mut header, "disk.img", image.as_slice()).unwrap();
+    tar.finish().unwrap();
+    drop(tar);
+
+    let options = ExtractOptions {
+        sparse_files: true,
+        ..ExtractOptions::default()
+    };
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+
+    extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+    assert_eq!(fs::read(output_dir.join("disk.img")).unwrap(), image);
+}
+
+#[test]
+fn test_extract_progress_callback() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    let output_dir = temp_dir.path().join("output");
+
+    // Create test archive
+    create_test_zip(&archive_path).unwrap();
+
+    // Track progress calls
+    let progress_calls = Arc::new(AtomicBool::new(false));
+    let progress_calls_clone = progress_calls.clone();
+
+    let options = ExtractOptions::default();
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = move |_file: &str, _bytes: u64, _total: Option<u64>| {
+        progress_calls_clone.store(true, Ordering::Relaxed);
+        true
+    };
+
+    let _stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+
+    // Verify progress callback was called
+    assert!(progress_calls.load(Ordering::Relaxed));
+}
+
+#[test]
+#[ignore] // Password-protected archives require special handling
+fn test_extract_password_protected_without_password() {
+    // This test is ignored because compress-tools has limited password support
+    // In a real implementation with libarchive bindings, this would test:
+    // 1. Detecting password-protected archives
+    // 2. Returning PasswordRequired error when no password is provided
+    // 3. Returning InvalidPassword error when wrong password is provided
+    // 4. Successfully extracting when correct password is provided
+}
+
+#[test]
+fn test_extract_with_path_traversal_protection() {
+    // This test verifies that malicious archives with path traversal attempts
+    // are safely handled by the validation logic
+    // The actual path validation is tested in safety module tests
+    // Here we just verify that extraction doesn't fail catastrophically
+    
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    let output_dir = temp_dir.path().join("output");
+
+    // Create a normal archive (malicious archives would be created differently)
+    create_test_zip(&archive_path).unwrap();
+
+    let options = ExtractOptions::default();
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    // Should extract successfully
+    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+    assert!(result.is_ok());
+    
+    // Verify files are in the output directory, not outside it
+    assert!(output_dir.join("test.txt").exists());
+    assert!(!temp_dir.path().join("test.txt").exists()); // Not in parent
+}
+
+
+#[test]
+fn test_extract_reports_case_collisions() {
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("case.zip");
+    let output_dir = temp_dir.path().join("output");
+
+    let file = File::create(&archive_path).unwrap();
+    let mut zip = ZipWriter::new(file);
+    zip.start_file("README.txt", SimpleFileOptions::default()).unwrap();
+    zip.write_all(b"upper").unwrap();
+    zip.start_file("readme.txt", SimpleFileOptions::default()).unwrap();
+    zip.write_all(b"lower").unwrap();
+    zip.finish().unwrap();
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_: &str, _: u64, _: Option<u64>| true;
+
+    // Without detection, collisions are always reported
+    let options = ExtractOptions {
+        detect_case_sensitivity: false,
+        ..ExtractOptions::default()
+    };
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag.clone()).unwrap();
+    assert_eq!(stats.warnings.len(), 1);
+    assert!(stats.warnings[0].contains("readme.txt"));
+
+    // With detection, they are reported only on case-insensitive destinations
+    let probe = output_dir.join("Probe");
+    fs::write(&probe, b"").unwrap();
+    let case_insensitive = output_dir.join("PROBE").exists();
+    fs::remove_file(&probe).unwrap();
+
+    let output_dir = temp_dir.path().join("output2");
+    let stats = extract(&archive_path, &output_dir, &ExtractOptions::default(), &progress_cb, cancel_flag).unwrap();
+    assert_eq!(stats.warnings.is_empty(), !case_insensitive);
+}
+
+#[test]
+fn test_extract_writes_provenance_file() {
+    use extractor::{ExtractionInfo, PROVENANCE_FILE_NAME};
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    create_test_zip(&archive_path).unwrap();
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_: &str, _: u64, _: Option<u64>| true;
+
+    // Off by default
+    let output_dir = temp_dir.path().join("plain");
+    extract(&archive_path, &output_dir, &ExtractOptions::default(), &progress_cb, cancel_flag.clone()).unwrap();
+    assert!(!output_dir.join(PROVENANCE_FILE_NAME).exists());
+
+    let output_dir = temp_dir.path().join("traced");
+    let options = ExtractOptions {
+        write_provenance: true,
+        password: Some("secret".to_string()),
+        ..ExtractOptions::default()
+    };
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+
+    let json = fs::read_to_string(output_dir.join(PROVENANCE_FILE_NAME)).unwrap();
+    assert!(!json.contains("secret"));
+
+    let info: ExtractionInfo = serde_json::from_str(&json).unwrap();
+    assert!(info.source_archive.ends_with("test.zip"));
+    assert_eq!(info.sha256.len(), 64);
+    assert!(info.sha256.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(info.files_extracted, stats.files_extracted);
+    assert!(info.tool.starts_with("extractor "));
+}
+
+#[test]
+fn test_extract_writes_sha256sums() {
+    use extractor::SHA256SUMS_FILE_NAME;
+    use sha2::{Digest, Sha256};
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    create_test_zip(&archive_path).unwrap();
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_: &str, _: u64, _: Option<u64>| true;
+
+    let output_dir = temp_dir.path().join("plain");
+    let stats = extract(&archive_path, &output_dir, &ExtractOptions::default(), &progress_cb, cancel_flag.clone()).unwrap();
+    assert!(stats.sha256.is_empty());
+    assert!(!output_dir.join(SHA256SUMS_FILE_NAME).exists());
+
+    // On the writing thread, the decoding thread and worker threads
+    for (name, write_queue_depth, threads) in [("queued", 8, 1), ("direct", 0, 1), ("workers", 8, 2)] {
+        let output_dir = temp_dir.path().join(name);
+        let options = ExtractOptions {
+            write_sha256sums: true,
+            write_queue_depth,
+            threads,
+            ..ExtractOptions::default()
+        };
+        let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag.clone()).unwrap();
+
+        assert_eq!(stats.sha256.len() as u64, stats.files_extracted, "{}", name);
+        for (path, digest) in &stats.sha256 {
+            let data = fs::read(output_dir.join(path)).unwrap();
+            let expected: String = Sha256::digest(&data).iter().map(|b| format!("{:02x}", b)).collect();
+            assert_eq!(digest, &expected, "{}: {}", name, path);
+        }
+        assert_eq!(
+            stats.sha256["test.txt"],
+            "dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f"
+        );
+        assert!(stats.sha256.contains_key("subdir/nested.txt"));
+
+        let sums = fs::read_to_string(output_dir.join(SHA256SUMS_FILE_NAME)).unwrap();
+        assert_eq!(sums, stats.sha256sums());
+        assert!(sums.contains("dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f  test.txt\n"));
+    }
+}
+
+#[test]
+fn test_extract_verifies_checksum_file() {
+    use extractor::{verify_checksum, ChecksumAlgorithm};
+    use sha2::{Digest, Sha256};
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    create_test_zip(&archive_path).unwrap();
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_: &str, _: u64, _: Option<u64>| true;
+    let options = ExtractOptions {
+        verify_checksum: true,
+        ..ExtractOptions::default()
+    };
+
+    // Nothing to check against
+    assert_eq!(verify_checksum(&archive_path).unwrap(), None);
+    let output_dir = temp_dir.path().join("unchecked");
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag.clone()).unwrap();
+    assert_eq!(stats.checksum, None);
+
+    // A wrong MD5 stops the extraction before anything is written
+    fs::write(temp_dir.path().join("test.zip.md5"), format!("{}  test.zip\n", "0".repeat(32))).unwrap();
+    let output_dir = temp_dir.path().join("mismatch");
+    let err = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag.clone()).unwrap_err();
+    match err {
+        ExtractError::ChecksumMismatch(check) => {
+            assert_eq!(check.algorithm, ChecksumAlgorithm::Md5);
+            assert_eq!(check.expected, "0".repeat(32));
+            assert_eq!(check.actual.len(), 32);
+            assert!(!check.matches);
+        }
+        other => panic!("expected a checksum mismatch, got {:?}", other),
+    }
+    assert!(!output_dir.join("test.txt").exists());
+
+    // A SHA-256 file is preferred to the MD5 one
+    let digest: String = Sha256::digest(fs::read(&archive_path).unwrap()).iter().map(|b| format!("{:02x}", b)).collect();
+    fs::write(temp_dir.path().join("test.zip.sha256"), format!("SHA256 (test.zip) = {}\n", digest.to_uppercase())).unwrap();
+    let check = verify_checksum(&archive_path).unwrap().unwrap();
+    assert_eq!(check.algorithm, ChecksumAlgorithm::Sha256);
+    assert_eq!(check.actual, digest);
+    assert!(check.matches);
+
+    let output_dir = temp_dir.path().join("checked");
+    let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+    assert_eq!(stats.checksum, Some(check));
+    assert!(output_dir.join("test.txt").exists());
+
+    // Only checked when asked to
+    fs::write(temp_dir.path().join("test.zip.sha256"), "no digest here\n").unwrap();
+    assert!(verify_checksum(&archive_path).is_err());
+    let output_dir = temp_dir.path().join("plain");
+    extract(&archive_path, &output_dir, &ExtractOptions::default(), &progress_cb, Arc::new(AtomicBool::new(false))).unwrap();
+}
+
+#[test]
+fn test_extract_into_subfolder() {
+    use extractor::SubfolderPolicy;
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let loose_path = temp_dir.path().join("loose.zip");
+    create_test_zip(&loose_path).unwrap();
+    let rooted_path = temp_dir.path().join("rooted.zip");
+    let mut zip = ZipWriter::new(File::create(&rooted_path).unwrap());
+    zip.start_file("project/readme.txt", SimpleFileOptions::default()).unwrap();
+    zip.write_all(b"Read me").unwrap();
+    zip.start_file("project/src/main.rs", SimpleFileOptions::default()).unwrap();
+    zip.write_all(b"fn main() {}").unwrap();
+    zip.finish().unwrap();
+    let gz_path = temp_dir.path().join("notes.txt.gz");
+    let mut gz = flate2::write::GzEncoder::new(File::create(&gz_path).unwrap(), flate2::Compression::default());
+    gz.write_all(b"Some notes").unwrap();
+    gz.finish().unwrap();
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_: &str, _: u64, _: Option<u64>| true;
+    let output_dir = temp_dir.path().join("output");
+    let extract_with = |archive: &Path, create_subfolder| {
+        let options = ExtractOptions {
+            create_subfolder,
+            ..ExtractOptions::default()
+        };
+        extract(archive, &output_dir, &options, &progress_cb, cancel_flag.clone()).unwrap()
+    };
+
+    // Entries that would scatter into the output directory get a folder
+    let stats = extract_with(&loose_path, SubfolderPolicy::Auto);
+    let subfolder = output_dir.join("loose");
+    assert_eq!(stats.subfolder, Some(subfolder.display().to_string()));
+    assert!(subfolder.join("test.txt").exists());
+    assert!(subfolder.join("subdir/nested.txt").exists());
+    assert!(!output_dir.join("test.txt").exists());
+
+    // A single top-level folder or compressed file is already contained
+    let stats = extract_with(&rooted_path, SubfolderPolicy::Auto);
+    assert_eq!(stats.subfolder, None);
+    assert!(output_dir.join("project/src/main.rs").exists());
+    let stats = extract_with(&gz_path, SubfolderPolicy::Auto);
+    assert_eq!(stats.subfolder, None);
+    assert!(output_dir.join("notes.txt").exists());
+
+    // Always, next to an earlier folder of the same name
+    let stats = extract_with(&rooted_path, SubfolderPolicy::Always);
+    assert!(output_dir.join("rooted/project/readme.txt").exists());
+    assert_eq!(stats.files_extracted, 2);
+    let stats = extract_with(&rooted_path, SubfolderPolicy::Always);
+    let subfolder = output_dir.join("rooted (1)");
+    assert_eq!(stats.subfolder, Some(subfolder.display().to_string()));
+    assert!(subfolder.join("project/readme.txt").exists());
+
+    let stats = extract_with(&loose_path, SubfolderPolicy::Never);
+    assert_eq!(stats.subfolder, None);
+    assert!(output_dir.join("test.txt").exists());
+}
+
+#[test]
+fn test_extract_at_offset() {
+    use extractor::extract_at_offset;
+
+    let temp_dir = TempDir::new().unwrap();
+    let zip_path = temp_dir.path().join("inner.zip");
+    create_test_zip(&zip_path).unwrap();
+
+    // Prepend a fake installer stub
+    let mut blob = b"#!/bin/sh\necho installer stub\nexit 0\n".to_vec();
+    let offset = blob.len() as u64;
+    blob.extend(fs::read(&zip_path).unwrap());
+    let blob_path = temp_dir.path().join("setup.run");
+    fs::write(&blob_path, &blob).unwrap();
+
+    let output_dir = temp_dir.path().join("output");
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_: &str, _: u64, _: Option<u64>| true;
+
+    let stats = extract_at_offset(
+        &blob_path,
+        offset,
+        &output_dir,
+        &ExtractOptions::default(),
+        &progress_cb,
+        cancel_flag,
+    )
+    .unwrap();
+
+    assert_eq!(stats.files_extracted, 3);
+    assert_eq!(fs::read_to_string(output_dir.join("test.txt")).unwrap(), "Hello, World!");
+}
+
+#[test]
+fn test_extract_at_offset_temp_dir() {
+    use extractor::extract_at_offset;
+
+    let temp_dir = TempDir::new().unwrap();
+    let zip_path = temp_dir.path().join("inner.zip");
+    create_test_zip(&zip_path).unwrap();
+    let mut blob = b"stub".to_vec();
+    blob.extend(fs::read(&zip_path).unwrap());
+    let blob_path = temp_dir.path().join("dest").join("setup.run");
+    fs::create_dir_all(blob_path.parent().unwrap()).unwrap();
+    fs::write(&blob_path, &blob).unwrap();
+
+    let scratch = temp_dir.path().join("scratch");
+    let progress_cb = |_: &str, _: u64, _: Option<u64>| true;
+    for (name, temp_dir_option) in [("configured", Some(scratch.clone())), ("default", None)] {
+        let output_dir = temp_dir.path().join("dest").join(name);
+        let options = ExtractOptions {
+            temp_dir: temp_dir_option,
+            ..Default::default()
+        };
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        let stats = extract_at_offset(&blob_path, 4, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+        assert_eq!(stats.files_extracted, 3);
+    }
+
+    // The carved copies went in the configured folder or next to the
+    // destination, and were removed afterwards
+    assert_eq!(fs::read_dir(&scratch).unwrap().count(), 0);
+    let mut left: Vec<_> = fs::read_dir(temp_dir.path().join("dest"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    left.sort();
+    assert_eq!(left, ["configured", "default", "setup.run"]);
+}
+
+#[test]
+fn test_extract_from_reader() {
+    use extractor::extract_from_reader;
+    use std::io::Cursor;
+
+    let temp_dir = TempDir::new().unwrap();
+    let zip_path = temp_dir.path().join("test.zip");
+    let tar_path = temp_dir.path().join("test.tar.gz");
+    create_test_zip(&zip_path).unwrap();
+    create_test_tar_gz(&tar_path).unwrap();
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_: &str, _: u64, _: Option<u64>| true;
+    let options = ExtractOptions::default();
+
+    // An already-open file handle
+    let zip_output = temp_dir.path().join("zip_output");
+    let stats = extract_from_reader(
+        File::open(&zip_path).unwrap(),
+        &zip_output,
+        &options,
+        &progress_cb,
+        cancel_flag.clone(),
+    )
+    .unwrap();
+    assert_eq!(stats.files_extracted, 3);
+    assert_eq!(
+        fs::read_to_string(zip_output.join("test.txt")).unwrap(),
+        "Hello, World!"
+    );
+
+    // Archive data held in memory
+    let tar_output = temp_dir.path().join("tar_output");
+    let data = Cursor::new(fs::read(&tar_path).unwrap());
+    let stats = extract_from_reader(
+        data,
+        &tar_output,
+        &options,
+        &progress_cb,
+        cancel_flag.clone(),
+    )
+    .unwrap();
+    assert!(stats.files_extracted > 0);
+
+    let result = extract_from_reader(
+        Cursor::new(b"not an archive".to_vec()),
+        &temp_dir.path().join("bad_output"),
+        &options,
+        &progress_cb,
+        cancel_flag,
+    );
+    assert!(matches!(result, Err(ExtractError::UnsupportedFormat(_))));
+}
+
+#[test]
+fn test_extract_bytes() {
+    use extractor::extract_bytes;
+
+    let temp_dir = TempDir::new().unwrap();
+    let zip_path = temp_dir.path().join("test.zip");
+    create_test_zip(&zip_path).unwrap();
+    let data = fs::read(&zip_path).unwrap();
+
+    let output_dir = temp_dir.path().join("output");
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let progress_cb = |_: &str, _: u64, _: Option<u64>| true;
+
+    let stats = extract_bytes(
+        &data,
+        &output_dir,
+        &ExtractOptions::default(),
+        &progress_cb,
+        cancel_flag,
+    )
+    .unwrap();
+
+    assert_eq!(stats.files_extracted, 3);
+    assert_eq!(
+        fs::read_to_string(output_dir.join("test.txt")).unwrap(),
+        "Hello, World!"
+    );
+}
+
+#[test]
+fn test_extract_zip_with_understated_size() {
+    use zip::write::{SimpleFileOptions, ZipWriter};
+    use zip::CompressionMethod;
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+
+    let content: Vec<u8> = (0..10_000u32).map(|b| (b % 251) as u8).collect();
+    let mut zip = ZipWriter::new(File::create(&archive_path).unwrap());
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    zip.start_file("big.bin", stored).unwrap();
+    zip.write_all(&content).unwrap();
+    zip.finish().unwrap();
+
+    // Record a smaller uncompressed size in the central directory, as tools
+    // without ZIP64 do for entries over 4 GB
+    let mut bytes = fs::read(&archive_path).unwrap();
+    let header = bytes.windows(4).position(|w| w == b"PK\x01\x02").unwrap();
+    bytes[header + 24..header + 28].copy_from_slice(&1000u32.to_le_bytes());
+    fs::write(&archive_path, &bytes).unwrap();
+
+    for threads in [1, 2] {
+        let output_dir = temp_dir.path().join(format!("output{}", threads));
+        let options = ExtractOptions {
+            threads,
+            ..ExtractOptions::default()
+        };
+        let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+
+        assert_eq!(fs::read(output_dir.join("big.bin")).unwrap(), content);
+        assert_eq!(stats.warnings.len(), 1, "{:?}", stats.warnings);
+        assert!(stats.warnings[0].contains("big.bin"));
+    }
+}
+
+#[test]
+fn test_extract_tar_xz_streamed() {
+    use tar::{Builder, Header};
+    use xz2::write::XzEncoder;
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.tar.xz");
+
+    // Larger than the decoder's buffers, so it's read in many pieces
+    let big: Vec<u8> = (0..3_000_000u32).map(|i| (i * 7 % 253) as u8).collect();
+    let mut tar = Builder::new(XzEncoder::new(File::create(&archive_path).unwrap(), 6));
+    for (name, content) in [("big.bin", big.as_slice()), ("small.txt", b"small".as_slice())] {
+        let mut header = Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, name, content).unwrap();
+    }
+    tar.into_inner().unwrap().finish().unwrap();
+
+    let output_dir = temp_dir.path().join("output");
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let stats = extract(&archive_path, &output_dir, &ExtractOptions::default(), &progress_cb, cancel_flag).unwrap();
+
+    assert_eq!(stats.files_extracted, 2);
+    assert_eq!(fs::read(output_dir.join("big.bin")).unwrap(), big);
+    assert_eq!(fs::read_to_string(output_dir.join("small.txt")).unwrap(), "small");
+}
+
+#[test]
+fn test_extract_with_buffer_sizes() {
+    use tar::{Builder, Header};
+    use zip::write::SimpleFileOptions;
+
+    let temp_dir = TempDir::new().unwrap();
+    let files: Vec<(String, Vec<u8>)> = (0..200u32)
+        .map(|i| (format!("dir{}/file{}.txt", i % 7, i), vec![(i % 251) as u8; i as usize * 37]))
+        .collect();
+
+    let tar_path = temp_dir.path().join("small.tar");
+    let mut tar = Builder::new(File::create(&tar_path).unwrap());
+    for (name, content) in &files {
+        let mut header = Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, name, content.as_slice()).unwrap();
+    }
+    tar.into_inner().unwrap();
+
+    let zip_path = temp_dir.path().join("small.zip");
+    let mut zip = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+    for (name, content) in &files {
+        zip.start_file(name.as_str(), SimpleFileOptions::default()).unwrap();
+        zip.write_all(content).unwrap();
+    }
+    zip.finish().unwrap();
+
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    for archive_path in [&tar_path, &zip_path] {
+        // Unbuffered, smaller than any header, and the default
+        for buffer_size in [0, 1, extractor::DEFAULT_BUFFER_SIZE] {
+            for (threads, write_queue_depth) in [(1, 0), (1, 8), (2, 8)] {
+                let output_dir = temp_dir.path().join(format!(
+                    "out-{}-{}-{}-{}",
+                    archive_path.extension().unwrap().to_string_lossy(),
+                    buffer_size,
+                    threads,
+                    write_queue_depth
+                ));
+                let options = ExtractOptions {
+                    buffer_size,
+                    threads,
+                    write_queue_depth,
+                    ..Default::default()
+                };
+                let cancel_flag = Arc::new(AtomicBool::new(false));
+                let stats = extract(archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+
+                assert_eq!(stats.files_extracted, files.len() as u64);
+                for (name, content) in &files {
+                    assert_eq!(&fs::read(output_dir.join(name)).unwrap(), content, "{}", name);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_extract_memory_mapped() {
+    use sevenz_rust2::{SevenZArchiveEntry, SevenZWriter};
+    use zip::write::SimpleFileOptions;
+
+    let temp_dir = TempDir::new().unwrap();
+    let files: Vec<(String, Vec<u8>)> = (0..40u32)
+        .map(|i| (format!("dir/file{}.bin", i), vec![(i * 3) as u8; 1000 + i as usize * 997]))
+        .collect();
+
+    let zip_path = temp_dir.path().join("mapped.zip");
+    let mut zip = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+    for (name, content) in &files {
+        zip.start_file(name.as_str(), SimpleFileOptions::default()).unwrap();
+        zip.write_all(content).unwrap();
+    }
+    zip.finish().unwrap();
+
+    let sz_path = temp_dir.path().join("mapped.7z");
+    let mut sz = SevenZWriter::new(File::create(&sz_path).unwrap()).unwrap();
+    for (name, content) in &files {
+        let entry = SevenZArchiveEntry::from_path(Path::new(name), name.to_string());
+        sz.push_archive_entry(entry, Some(content.as_slice())).unwrap();
+    }
+    sz.finish().unwrap();
+
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    for (archive_path, threads) in [(&zip_path, 1), (&zip_path, 3), (&sz_path, 1)] {
+        let output_dir = temp_dir.path().join(format!(
+            "out-{}-{}",
+            archive_path.extension().unwrap().to_string_lossy(),
+            threads
+        ));
+        let options = ExtractOptions {
+            memory_map: true,
+            threads,
+            ..Default::default()
+        };
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        let stats = extract(archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+
+        assert_eq!(stats.files_extracted, files.len() as u64);
+        for (name, content) in &files {
+            assert_eq!(&fs::read(output_dir.join(name)).unwrap(), content, "{}", name);
+        }
+    }
+
+    // Picking a few entries out of the mapped archive
+    let output_dir = temp_dir.path().join("selected");
+    let options = ExtractOptions {
+        memory_map: true,
+        entry_filter: Some(EntryFilter::new(|entry| entry.path.ends_with("7.bin"))),
+        ..Default::default()
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let stats = extract(&zip_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+    assert_eq!(stats.files_extracted, 4);
+    assert_eq!(fs::read(output_dir.join("dir/file37.bin")).unwrap(), files[37].1);
+}
+
+#[test]
+fn test_extract_7z_streams_entries() {
+    use sevenz_rust2::{SevenZArchiveEntry, SevenZWriter};
+    use std::sync::Mutex;
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.7z");
+
+    let big: Vec<u8> = (0..2_000_000u32).map(|i| (i * 13 % 241) as u8).collect();
+    let files = [
+        ("a.txt", b"first".to_vec()),
+        ("dir/big.bin", big),
+        ("c.txt", b"last".to_vec()),
+    ];
+    let mut sz = SevenZWriter::new(File::create(&archive_path).unwrap()).unwrap();
+    for (name, content) in &files {
+        let entry = SevenZArchiveEntry::from_path(Path::new(name), name.to_string());
+        sz.push_archive_entry(entry, Some(content.as_slice())).unwrap();
+    }
+    sz.finish().unwrap();
+
+    // Every entry is reported as it is written straight to the output folder
+    let output_dir = temp_dir.path().join("output");
+    let reported = Arc::new(Mutex::new(Vec::new()));
+    let seen = reported.clone();
+    let progress_cb = move |file: &str, _bytes: u64, _total: Option<u64>| {
+        seen.lock().unwrap().push(file.to_string());
+        true
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let stats = extract(&archive_path, &output_dir, &ExtractOptions::default(), &progress_cb, cancel_flag).unwrap();
+
+    assert_eq!(stats.files_extracted, 3);
+    for (name, content) in &files {
+        assert_eq!(&fs::read(output_dir.join(name)).unwrap(), content, "{}", name);
+        assert!(reported.lock().unwrap().iter().any(|file| file.ends_with(name)));
+    }
+
+    // The size limit stops decompression partway through the archive
+    let output_dir = temp_dir.path().join("limited");
+    let options = ExtractOptions {
+        size_limit_bytes: Some(1_000_000),
+        ..ExtractOptions::default()
+    };
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+    assert!(matches!(result, Err(ExtractError::SizeLimitExceeded { .. })));
+    assert!(!output_dir.exists());
+}
+
+/// Write `content` as `secret.bin` to an AES-encrypted 7z archive at `path`
+/// with the password "secret", encrypting the file list too if `encrypt_header`.
+fn create_encrypted_7z(path: &Path, content: &[u8], encrypt_header: bool) {
+    use sevenz_rust2::{
+        AesEncoderOptions, SevenZArchiveEntry, SevenZMethod, SevenZMethodConfiguration,
+        SevenZWriter,
+    };
+
+    let mut sz = SevenZWriter::new(File::create(path).unwrap()).unwrap();
+    sz.set_content_methods(vec![
+        SevenZMethodConfiguration::new(SevenZMethod::AES256SHA256)
+            .with_options(AesEncoderOptions::new("secret".into()).into()),
+        SevenZMethod::LZMA2.into(),
+    ]);
+    sz.set_encrypt_header(encrypt_header);
+    let entry = SevenZArchiveEntry::from_path(Path::new("secret.bin"), "secret.bin".to_string());
+    sz.push_archive_entry(entry, Some(content)).unwrap();
+    sz.finish().unwrap();
+}
+
+#[test]
+fn test_extract_encrypted_7z() {
+    let temp_dir = TempDir::new().unwrap();
+    let content: Vec<u8> = (0..100_000u32).map(|i| (i * 31 % 199) as u8).collect();
+
+    // With the file list left readable, and encrypted along with the data
+    for encrypt_header in [false, true] {
+        let archive_path = temp_dir.path().join(format!("secret{}.7z", encrypt_header));
+        create_encrypted_7z(&archive_path, &content, encrypt_header);
+
+        let extract_with = |password: Option<&str>| {
+            let output_dir = temp_dir.path().join(format!("{}-{:?}", encrypt_header, password));
+            let options = ExtractOptions {
+                password: password.map(str::to_string),
+                ..ExtractOptions::default()
+            };
+            let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+            let cancel_flag = Arc::new(AtomicBool::new(false));
+            extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag)
+        };
+
+        assert!(matches!(extract_with(None), Err(ExtractError::PasswordRequired)));
+        assert!(matches!(extract_with(Some("wrong")), Err(ExtractError::InvalidPassword)));
+    }
+}
+
+#[test]
+fn test_extract_encrypted_7z_with_password() {
+    let temp_dir = TempDir::new().unwrap();
+    let content: Vec<u8> = (0..100_000u32).map(|i| (i * 31 % 199) as u8).collect();
+    let options = ExtractOptions {
+        password: Some("secret".to_string()),
+        ..ExtractOptions::default()
+    };
+
+    for encrypt_header in [false, true] {
+        let archive_path = temp_dir.path().join(format!("secret{}.7z", encrypt_header));
+        create_encrypted_7z(&archive_path, &content, encrypt_header);
+
+        let output_dir = temp_dir.path().join(format!("output{}", encrypt_header));
+        let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        let stats = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag).unwrap();
+        assert_eq!(stats.files_extracted, 1);
+        assert_eq!(fs::read(output_dir.join("secret.bin")).unwrap(), content);
+
+        // Single entries are decrypted the same way
+        let mut buf = Vec::new();
+        extract_entry_to_writer(&archive_path, "secret.bin", &mut buf, &options).unwrap();
+        assert_eq!(buf, content);
+    }
+}
+
+#[test]
+fn test_extract_refuses_multipart_before_writing() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip.002");
+    create_test_zip(&archive_path).unwrap();
+    let output_dir = temp_dir.path().join("output");
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+
+    for transactional in [false, true] {
+        let options = ExtractOptions {
+            transactional,
+            ..ExtractOptions::default()
+        };
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        let result = extract(&archive_path, &output_dir, &options, &progress_cb, cancel_flag);
+        assert!(matches!(result, Err(ExtractError::UnsupportedFormat(_))));
+
+        // Neither the output folder nor a journal or staging folder is created
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+}
+
+#[test]
+fn test_error_hints() {
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("test.zip");
+    create_test_zip(&archive_path).unwrap();
+    let progress_cb = |_file: &str, _bytes: u64, _total: Option<u64>| true;
+    let extract_with = |archive: &Path, options: &ExtractOptions| {
+        let output_dir = temp_dir.path().join("output");
+        let _ = fs::remove_dir_all(&output_dir);
+        extract(archive, &output_dir, options, &progress_cb, Arc::new(AtomicBool::new(false)))
+            .unwrap_err()
+    };
+
+    // Cut short, as by an interrupted download
+    let data = fs::read(&archive_path).unwrap();
+    let truncated = temp_dir.path().join("truncated.zip");
+    fs::write(&truncated, &data[..data.len() / 2]).unwrap();
+    let error = extract_with(&truncated, &ExtractOptions::default());
+    assert_eq!(error.hints(), vec![ErrorHint::Redownload], "{}", error);
+
+    let options = ExtractOptions {
+        size_limit_bytes: Some(1),
+        ..ExtractOptions::default()
+    };
+    let error = extract_with(&archive_path, &options);
+    assert_eq!(error.hints(), vec![ErrorHint::RaiseSizeLimit]);
+    assert_eq!(ErrorHint::RaiseSizeLimit.code(), "raise_size_limit");
+
+    let not_archive = temp_dir.path().join("notes.txt");
+    fs::write(&not_archive, "just text").unwrap();
+    let error = extract_with(&not_archive, &ExtractOptions::default());
+    assert_eq!(error.hints(), vec![ErrorHint::NotAnArchive], "{}", error);
+}
+
+#[test]
+fn test_extract_reports_progress_within_large_file() {
+    use std::sync::Mutex;
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive_path = temp_dir.path().join("large.zip");
+    let content = vec![0x5au8; 20 * 1024 * 1024];
+    let mut zip = ZipWriter::new(File::create(&archive_path).unwrap());
+    zip.start_file("large.bin", SimpleFileOptions::default()).unwrap();
+    zip.write_all(&content).unwrap();
+    zip.finish().unwrap();
+
+    // Written on this thread and by the writer thread
+    for write_queue_depth in [0, 8] {
+        let options = ExtractOptions {
+            write_queue_depth,
+            ..ExtractOptions::default()
+        };
+        let run = |cancel_at: Option<u64>| {
+            let output_dir = temp_dir.path().join(format!("out-{}-{:?}", write_queue_depth, cancel_at));
+            let reports = Arc::new(Mutex::new(Vec::new()));
+            let recorded = reports.clone();
+            let progress_cb = move |_file: &str, bytes: u64, total: Option<u64>| {
+                recorded.lock().unwrap().push((bytes, total));
+                cancel_at.is_none_or(|at| bytes < at)
+            };
+            let result = extract(&archive_path, &output_dir, &options, &progress_cb, Arc::new(AtomicBool::new(false)));
+            let reports = reports.lock().unwrap().clone();
+            (result, reports, output_dir)
+        };
+
+        // Interim reports come without the file's size, before the final one
+        let (result, reports, output_dir) = run(None);
+        result.unwrap();
+        let interim: Vec<u64> = reports.iter().filter(|(_, total)| total.is_none()).map(|(bytes, _)| *bytes).collect();
+        assert!(interim.len() >= 2, "{:?}", reports);
+        assert!(interim.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(reports.last(), Some(&(content.len() as u64, Some(content.len() as u64))));
+        assert_eq!(fs::read(output_dir.join("large.bin")).unwrap(), content);
+
+        // Cancelling midway stops the file
+        let (result, reports, _) = run(Some(1));
+        assert!(matches!(result, Err(ExtractError::Cancelled)), "{:?}", result);
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].1, None);
+    }
+}
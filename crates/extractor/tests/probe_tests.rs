@@ -1,11 +1,17 @@
 //! Integration tests for archive probing functionality.
 
-use extractor::{probe, ExtractError};
+use extractor::{
+    estimate_uncompressed_size, plan_extraction, probe, probe_at_offset, probe_bytes,
+    probe_deep, probe_with_options, probe_with_password, probe_with_progress, scan_signatures,
+    verify, ExtractError, ExtractOptions, Format, Integrity, ProbeOptions, ProbeProgress,
+    ProgressListener, ProgressPhase, PROBE_SCHEMA_VERSION,
+};
 use flate2::write::GzEncoder;
 use flate2::Compression;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
 use tempfile::TempDir;
 
 /// Helper function to create a test archive directory
@@ -139,7 +145,7 @@ fn test_probe_zip_archive() {
     // Probe the archive
     let info = probe(&archive_path).expect("Failed to probe archive");
     
-    assert_eq!(info.format, "ZIP");
+    assert_eq!(info.format, Format::Zip);
     assert_eq!(info.entries, 1);
     assert!(info.compressed_bytes.is_some());
     assert!(!info.encrypted);
@@ -157,7 +163,7 @@ fn test_probe_tar_gz_archive() {
     // Probe the archive
     let info = probe(&archive_path).expect("Failed to probe archive");
     
-    assert_eq!(info.format, "TAR.GZ");
+    assert_eq!(info.format, Format::Tar(extractor::Compression::Gzip));
     assert_eq!(info.entries, 1);
     assert!(info.compressed_bytes.is_some());
     
@@ -181,7 +187,7 @@ fn test_probe_tar_bz2_archive() {
     // Probe the archive
     let info = probe(&archive_path).expect("Failed to probe archive");
     
-    assert_eq!(info.format, "TAR.BZ2");
+    assert_eq!(info.format, Format::Tar(extractor::Compression::Bzip2));
     assert_eq!(info.entries, 1);
     assert!(info.compressed_bytes.is_some());
     
@@ -205,7 +211,7 @@ fn test_probe_tar_xz_archive() {
     // Probe the archive
     let info = probe(&archive_path).expect("Failed to probe archive");
     
-    assert_eq!(info.format, "TAR.XZ");
+    assert_eq!(info.format, Format::Tar(extractor::Compression::Xz));
     assert_eq!(info.entries, 1);
     assert!(info.compressed_bytes.is_some());
     
@@ -217,6 +223,208 @@ fn test_probe_tar_xz_archive() {
     assert_eq!(entry.size, 10); // "Hello, XZ!" is 10 bytes
 }
 
+#[test]
+fn test_probe_bytes() {
+    let temp_dir = setup_test_dir();
+    let archive_path = temp_dir.path().join("test.zip");
+    create_zip_archive(&archive_path, &[("a.txt", b"alpha"), ("b/c.txt", b"gamma")])
+        .expect("Failed to create ZIP");
+    let data = std::fs::read(&archive_path).unwrap();
+
+    let info = probe_bytes(&data).expect("Failed to probe bytes");
+    assert_eq!(info.format, Format::Zip);
+    assert_eq!(info.entries, 2);
+    assert_eq!(info.compressed_bytes, Some(data.len() as u64));
+    let paths: Vec<&str> = info.entry_list.iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(paths, vec!["a.txt", "b/c.txt"]);
+
+    assert!(matches!(
+        probe_bytes(b"plain text"),
+        Err(ExtractError::UnsupportedFormat(_))
+    ));
+}
+
+#[test]
+fn test_probe_single_gzip_file_reports_real_size() {
+    let temp_dir = setup_test_dir();
+    let archive_path = temp_dir.path().join("notes.txt.gz");
+    
+    // Highly compressible content so compressed and real sizes clearly differ
+    let content = vec![b'a'; 64 * 1024];
+    let file = File::create(&archive_path).expect("Failed to create GZIP");
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    encoder.write_all(&content).expect("Failed to write GZIP");
+    encoder.finish().expect("Failed to finish GZIP");
+    
+    let info = probe(&archive_path).expect("Failed to probe archive");
+    
+    assert_eq!(info.format, Format::Gzip);
+    assert_eq!(info.entries, 1);
+    let entry = &info.entry_list[0];
+    assert_eq!(entry.path, "notes.txt");
+    assert_eq!(entry.size, content.len() as u64);
+    assert_eq!(info.uncompressed_estimate, Some(content.len() as u64));
+}
+
+#[test]
+fn test_estimate_uncompressed_size_from_trailer() {
+    use std::io::Read;
+
+    let temp_dir = setup_test_dir();
+    let archive_path = temp_dir.path().join("test.tar.gz");
+    create_tar_gz_archive(&archive_path, &[("a.txt", b"Hello"), ("b.txt", &[b'b'; 4096])])
+        .expect("Failed to create TAR.GZ");
+
+    // ISIZE should match the length of the decompressed tar stream
+    let mut tar_bytes = Vec::new();
+    flate2::read::GzDecoder::new(File::open(&archive_path).unwrap())
+        .read_to_end(&mut tar_bytes)
+        .unwrap();
+    let estimate = estimate_uncompressed_size(&archive_path).expect("Failed to estimate");
+    assert_eq!(estimate, Some(tar_bytes.len() as u64));
+
+    // Listed archives report exact sizes
+    let info = probe(&archive_path).expect("Failed to probe archive");
+    assert!(!info.uncompressed_is_estimate);
+
+    // Formats without a size trailer give no estimate
+    let zip_path = temp_dir.path().join("test.zip");
+    create_zip_archive(&zip_path, &[("a.txt", b"Hello")]).expect("Failed to create ZIP");
+    assert_eq!(estimate_uncompressed_size(&zip_path).unwrap(), None);
+}
+
+#[test]
+fn test_probe_embedded_archives() {
+    let temp_dir = setup_test_dir();
+
+    // Build a "firmware" blob: junk, a ZIP, more junk, a TAR.GZ
+    let zip_path = temp_dir.path().join("inner.zip");
+    create_zip_archive(&zip_path, &[("a.txt", b"Hello"), ("b.txt", b"World")])
+        .expect("Failed to create ZIP");
+    let tgz_path = temp_dir.path().join("inner.tar.gz");
+    create_tar_gz_archive(&tgz_path, &[("c.txt", b"Tarred")]).expect("Failed to create TAR.GZ");
+
+    let mut blob = vec![0xAA; 1000];
+    blob.extend(std::fs::read(&zip_path).unwrap());
+    let tgz_offset = blob.len() as u64 + 300;
+    blob.extend(vec![0xAA; 300]);
+    blob.extend(std::fs::read(&tgz_path).unwrap());
+    let blob_path = create_test_file(&temp_dir, "firmware.bin", &blob);
+
+    let matches = scan_signatures(&blob_path).expect("Failed to scan");
+    let found: Vec<(u64, Format)> = matches.iter().map(|m| (m.offset, m.format)).collect();
+    assert_eq!(found, vec![(1000, Format::Zip), (tgz_offset, Format::Gzip)]);
+
+    let info = probe_at_offset(&blob_path, 1000, &ProbeOptions::default()).expect("Failed to probe ZIP");
+    assert_eq!(info.format, Format::Zip);
+    assert_eq!(info.entries, 2);
+
+    // The gzip stream is recognized as a tarball
+    let info = probe_at_offset(&blob_path, tgz_offset, &ProbeOptions::default()).expect("Failed to probe TAR.GZ");
+    assert_eq!(info.format, Format::Tar(extractor::Compression::Gzip));
+    assert_eq!(info.entry_list[0].path, "c.txt");
+
+    assert!(matches!(
+        probe_at_offset(&blob_path, 10, &ProbeOptions::default()),
+        Err(ExtractError::UnsupportedFormat(_))
+    ));
+}
+
+#[test]
+fn test_probe_cache_reuses_unchanged_archives() {
+    use extractor::ProbeCache;
+
+    let temp_dir = setup_test_dir();
+    let archive_path = temp_dir.path().join("cached.zip");
+    create_zip_archive(&archive_path, &[("a.txt", b"Hello")]).expect("Failed to create ZIP");
+
+    let cache = ProbeCache::new(1);
+    let first = cache.probe(&archive_path).expect("Failed to probe archive");
+    assert_eq!(first.entries, 1);
+    assert_eq!(cache.len(), 1);
+
+    let again = cache.probe(&archive_path).expect("Failed to probe archive");
+    assert_eq!(again.entries, 1);
+    assert_eq!(cache.len(), 1);
+
+    // Rewriting the archive changes its fingerprint, so it is probed again
+    create_zip_archive(&archive_path, &[("a.txt", b"Hello"), ("b.txt", b"World")])
+        .expect("Failed to rewrite ZIP");
+    let changed = cache.probe(&archive_path).expect("Failed to probe archive");
+    assert_eq!(changed.entries, 2);
+
+    // Capacity of one evicts the older archive
+    let other_path = temp_dir.path().join("other.zip");
+    create_zip_archive(&other_path, &[("c.txt", b"!")]).expect("Failed to create ZIP");
+    cache.probe(&other_path).expect("Failed to probe archive");
+    assert_eq!(cache.len(), 1);
+
+    cache.invalidate(&other_path);
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn test_probe_with_password_lists_unencrypted_headers() {
+    let temp_dir = setup_test_dir();
+    let archive_path = temp_dir.path().join("test.7z");
+    create_7z_archive(&archive_path, &[("test.txt", b"Hello, World!")])
+        .expect("Failed to create 7Z");
+
+    // Plain headers are readable with or without a password
+    let info = probe(&archive_path).expect("Failed to probe archive");
+    assert!(!info.header_encrypted);
+    assert_eq!(info.entries, 1);
+
+    let cancel_flag = std::sync::Arc::new(AtomicBool::new(false));
+    let info = probe_with_password(&archive_path, "secret", &|_, _| {}, cancel_flag)
+        .expect("Failed to probe archive with password");
+    assert!(!info.header_encrypted);
+    assert_eq!(info.entries, 1);
+}
+
+#[test]
+fn test_probe_deep_reports_integrity() {
+    let temp_dir = setup_test_dir();
+    let content: Vec<u8> = (0..64 * 1024u32).map(|i| (i * 7 % 251) as u8).collect();
+
+    // Intact archives pass
+    let zip_path = temp_dir.path().join("test.zip");
+    create_zip_archive(&zip_path, &[("data.bin", &content)]).expect("Failed to create ZIP");
+    let tar_gz_path = temp_dir.path().join("test.tar.gz");
+    create_tar_gz_archive(&tar_gz_path, &[("data.bin", &content)])
+        .expect("Failed to create TAR.GZ");
+    for path in [&zip_path, &tar_gz_path] {
+        let info = probe_deep(path, &ProbeOptions::default()).expect("Failed to probe archive");
+        assert_eq!(info.integrity, Some(Integrity::Ok));
+    }
+
+    // Plain probes don't check
+    assert_eq!(probe(&zip_path).unwrap().integrity, None);
+
+    // Cutting the files short is reported as truncation
+    for (path, name) in [(&zip_path, "cut.zip"), (&tar_gz_path, "cut.tar.gz")] {
+        let data = std::fs::read(path).unwrap();
+        let cut = create_test_file(&temp_dir, name, &data[..data.len() / 2]);
+        let info = probe_deep(&cut, &ProbeOptions::default()).expect("Failed to probe archive");
+        assert_eq!(info.integrity, Some(Integrity::Truncated));
+    }
+
+    // A damaged tar header fails its checksum
+    let mut tar_data = Vec::new();
+    {
+        let mut tar = tar::Builder::new(&mut tar_data);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_cksum();
+        tar.append_data(&mut header, "data.bin", content.as_slice()).unwrap();
+        tar.finish().unwrap();
+    }
+    tar_data[0] ^= 0xFF;
+    let damaged = create_test_file(&temp_dir, "damaged.tar", &tar_data);
+    let info = probe_deep(&damaged, &ProbeOptions::default()).expect("Failed to probe archive");
+    assert!(matches!(info.integrity, Some(Integrity::Corrupted { .. })));
+}
+
 #[test]
 fn test_probe_nonexistent_file() {
     let temp_dir = setup_test_dir();
@@ -243,7 +451,7 @@ fn test_probe_corrupted_archive() {
     match result {
         Ok(info) => {
             // If it succeeds, it should at least detect the format
-            assert_eq!(info.format, "ZIP");
+            assert_eq!(info.format, Format::Zip);
             // And likely report 0 entries or mark as encrypted (corrupted archives may appear encrypted)
         }
         Err(_) => {
@@ -263,7 +471,7 @@ fn test_probe_empty_archive() {
     // Probe the archive
     let info = probe(&archive_path).expect("Failed to probe empty archive");
     
-    assert_eq!(info.format, "ZIP");
+    assert_eq!(info.format, Format::Zip);
     assert_eq!(info.entries, 0);
     assert!(info.compressed_bytes.is_some());
 }
@@ -287,11 +495,74 @@ fn test_probe_multiple_files() {
     // Probe the archive
     let info = probe(&archive_path).expect("Failed to probe archive");
     
-    assert_eq!(info.format, "ZIP");
+    assert_eq!(info.format, Format::Zip);
     assert_eq!(info.entries, 3);
     assert!(info.compressed_bytes.is_some());
 }
 
+#[test]
+fn test_probe_with_progress_reports_entries() {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    let temp_dir = setup_test_dir();
+    let archive_path = temp_dir.path().join("progress.tar.gz");
+    
+    create_tar_gz_archive(
+        &archive_path,
+        &[
+            ("file1.txt", b"Content 1"),
+            ("file2.txt", b"Content 2"),
+            ("file3.txt", b"Content 3"),
+        ],
+    )
+    .expect("Failed to create TAR.GZ");
+    
+    // Track the latest values reported by the callback
+    let last_entries = Arc::new(AtomicU64::new(0));
+    let last_bytes = Arc::new(AtomicU64::new(0));
+    let last_entries_clone = last_entries.clone();
+    let last_bytes_clone = last_bytes.clone();
+    let progress_cb = move |bytes_scanned: u64, entries_found: u64| {
+        last_bytes_clone.store(bytes_scanned, Ordering::Relaxed);
+        last_entries_clone.store(entries_found, Ordering::Relaxed);
+    };
+    
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let info = probe_with_progress(&archive_path, &progress_cb, cancel_flag)
+        .expect("Failed to probe archive");
+    
+    assert_eq!(info.entries, 3);
+    assert_eq!(last_entries.load(Ordering::Relaxed), 3);
+    assert!(last_bytes.load(Ordering::Relaxed) > 0);
+}
+
+#[test]
+fn test_probe_with_progress_cancelled() {
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    let temp_dir = setup_test_dir();
+    let archive_path = temp_dir.path().join("cancel.tar.gz");
+    
+    create_tar_gz_archive(
+        &archive_path,
+        &[("file1.txt", b"Content 1"), ("file2.txt", b"Content 2")],
+    )
+    .expect("Failed to create TAR.GZ");
+    
+    // Cancel as soon as the first entry is reported
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let cancel_flag_clone = cancel_flag.clone();
+    let progress_cb = move |_bytes_scanned: u64, _entries_found: u64| {
+        cancel_flag_clone.store(true, Ordering::Relaxed);
+    };
+    
+    let result = probe_with_progress(&archive_path, &progress_cb, cancel_flag);
+    
+    assert!(matches!(result, Err(ExtractError::Cancelled)));
+}
+
 #[test]
 fn test_probe_entry_list() {
     let temp_dir = setup_test_dir();
@@ -319,7 +590,7 @@ fn test_probe_entry_list() {
     // Probe the archive
     let info = probe(&archive_path).expect("Failed to probe archive");
     
-    assert_eq!(info.format, "ZIP");
+    assert_eq!(info.format, Format::Zip);
     assert_eq!(info.entries, 3);
     assert_eq!(info.entry_list.len(), 3);
     
@@ -372,7 +643,7 @@ fn test_probe_format_detection_tar() {
     // Probe the archive
     let info = probe(&archive_path).expect("Failed to probe archive");
     
-    assert_eq!(info.format, "TAR");
+    assert_eq!(info.format, Format::Tar(extractor::Compression::None));
     assert_eq!(info.entries, 1);
 }
 
@@ -403,7 +674,7 @@ fn test_probe_7z_archive() {
     // Probe the archive
     let info = probe(&archive_path).expect("Failed to probe archive");
     
-    assert_eq!(info.format, "7Z");
+    assert_eq!(info.format, Format::SevenZ);
     assert_eq!(info.entries, 1);
     assert!(info.compressed_bytes.is_some());
     
@@ -428,3 +699,192 @@ fn test_probe_iso_archive() {
     // This test would require a pre-created ISO image or a library that can create them
     // For now, we mark it as ignored and would implement it with proper test fixtures
 }
+
+#[test]
+fn test_probe_json_schema_is_stable() {
+    let temp_dir = setup_test_dir();
+    let archive_path = temp_dir.path().join("test.zip");
+    create_zip_archive(&archive_path, &[("a.txt", b"hello")]).unwrap();
+
+    let info = probe(&archive_path).unwrap();
+    assert_eq!(info.schema_version, PROBE_SCHEMA_VERSION);
+
+    // Scripts rely on these names; renaming one requires a schema version bump
+    let json = serde_json::to_value(&info).unwrap();
+    let keys: Vec<&str> = json.as_object().unwrap().keys().map(String::as_str).collect();
+    for key in [
+        "schema_version",
+        "format",
+        "entries",
+        "compressed_bytes",
+        "uncompressed_estimate",
+        "uncompressed_is_estimate",
+        "encrypted",
+        "header_encrypted",
+        "single_root",
+        "is_tarbomb",
+        "summary",
+        "resources",
+        "entry_list",
+    ] {
+        assert!(keys.contains(&key), "missing key {}", key);
+    }
+
+    let entry = &json["entry_list"][0];
+    for key in ["path", "is_directory", "size", "compressed_size", "modified"] {
+        assert!(entry.get(key).is_some(), "missing entry key {}", key);
+    }
+}
+
+#[test]
+fn test_probe_reports_inodes_needed() {
+    let temp_dir = setup_test_dir();
+    let archive_path = temp_dir.path().join("test.zip");
+    create_zip_archive(&archive_path, &[("docs/a.txt", b"a"), ("docs/b.txt", b"b")]).unwrap();
+
+    // Two files plus the implied "docs" folder
+    let info = probe(&archive_path).unwrap();
+    assert_eq!(info.resources.inodes_needed, Some(3));
+}
+
+#[test]
+fn test_plan_extraction() {
+    let temp_dir = setup_test_dir();
+    let archive_path = temp_dir.path().join("test.zip");
+    create_zip_archive(&archive_path, &[("docs/a.txt", b"a"), ("docs/b.txt", b"b")]).unwrap();
+
+    let plan = plan_extraction(&archive_path, &temp_dir.path().join("output")).unwrap();
+    assert_eq!(plan.resources.inodes_needed, Some(3));
+    if extractor::memory_backed_space(temp_dir.path()).is_none() {
+        assert!(plan.warnings.is_empty());
+    }
+}
+
+#[test]
+fn test_probe_concatenated_tar() {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    let temp_dir = setup_test_dir();
+
+    // Two tar archives back to back, each with its own end marker
+    let mut data = Vec::new();
+    for name in ["first.txt", "second.txt"] {
+        let mut tar = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, name, b"data".as_slice()).unwrap();
+        data.extend(tar.into_inner().unwrap());
+    }
+    let archive_path = create_test_file(&temp_dir, "joined.tar", &data);
+
+    // Only the first archive without reading past its end marker
+    let info = probe(&archive_path).unwrap();
+    assert_eq!(info.entries, 1);
+
+    let entries_reported = Arc::new(AtomicU64::new(0));
+    let reported = entries_reported.clone();
+    let options = ProbeOptions {
+        read_concatenated_tar: true,
+        on_progress: Some(ProbeProgress::new(move |_, entries_found| {
+            reported.store(entries_found, Ordering::Relaxed);
+        })),
+        ..Default::default()
+    };
+    let info = probe_with_options(&archive_path, &options).unwrap();
+    assert_eq!(info.entries, 2);
+    assert_eq!(info.entry_list[1].path, "second.txt");
+    assert_eq!(entries_reported.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn test_verify_archive() {
+    let temp_dir = setup_test_dir();
+    let options = ExtractOptions::default();
+
+    let zip_path = temp_dir.path().join("test.zip");
+    let mut zip = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+    let stored = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Stored);
+    zip.add_directory("docs/", stored).unwrap();
+    zip.start_file("docs/a.txt", stored).unwrap();
+    zip.write_all(b"first entry").unwrap();
+    zip.start_file("b.txt", stored).unwrap();
+    zip.write_all(b"second entry").unwrap();
+    zip.finish().unwrap();
+
+    let report = verify(&zip_path, &options).unwrap();
+    assert!(report.ok);
+    assert_eq!(report.entries.len(), 2);
+    assert_eq!(report.entries[1].path, "b.txt");
+    assert_eq!(report.entries[1].size, 12);
+
+    // A damaged entry fails on its own, and the others still pass
+    let mut data = std::fs::read(&zip_path).unwrap();
+    let at = data.windows(12).position(|w| w == b"second entry").unwrap();
+    data[at] = b'S';
+    std::fs::write(&zip_path, data).unwrap();
+    let report = verify(&zip_path, &options).unwrap();
+    assert!(!report.ok);
+    assert!(report.error.is_none());
+    assert!(report.entries[0].error.is_none());
+    assert!(report.entries[1].error.is_some());
+
+    // A truncated stream stops the test
+    let tar_path = temp_dir.path().join("test.tar.gz");
+    let content = vec![7u8; 64 * 1024];
+    create_tar_gz_archive(&tar_path, &[("big.bin", &content), ("small.txt", b"small")]).unwrap();
+    let data = std::fs::read(&tar_path).unwrap();
+    std::fs::write(&tar_path, &data[..data.len() / 2]).unwrap();
+    let report = verify(&tar_path, &options).unwrap();
+    assert!(!report.ok);
+    assert!(report.entries[0].error.is_some());
+    assert!(report.error.is_some());
+
+    let missing = temp_dir.path().join("missing.zip");
+    assert!(matches!(verify(&missing, &options), Err(ExtractError::NotFound(_))));
+}
+
+#[test]
+fn test_verify_reports_progress_and_cancels() {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    let temp_dir = setup_test_dir();
+    let zip_path = temp_dir.path().join("test.zip");
+    let files: &[(&str, &[u8])] = &[("a.txt", b"first entry"), ("b.txt", b"second entry")];
+    create_zip_archive(&zip_path, files).expect("Failed to create ZIP");
+
+    // Each entry is reported as it is checked
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events_clone = events.clone();
+    let options = ExtractOptions {
+        on_progress: Some(ProgressListener::new(move |event| {
+            events_clone.lock().unwrap().push(event.clone());
+            true
+        })),
+        ..ExtractOptions::default()
+    };
+    let report = verify(&zip_path, &options).unwrap();
+    assert!(report.ok);
+    let events = events.lock().unwrap();
+    assert!(events.iter().all(|event| event.phase == ProgressPhase::Verifying));
+    let last = events.last().unwrap();
+    assert_eq!((last.path.as_str(), last.entry_index), ("b.txt", 1));
+    assert_eq!(last.bytes_written, 23);
+
+    // Returning false from the listener cancels the test
+    let reported = Arc::new(AtomicU64::new(0));
+    let reported_clone = reported.clone();
+    let options = ExtractOptions {
+        on_progress: Some(ProgressListener::new(move |_| {
+            reported_clone.fetch_add(1, Ordering::Relaxed);
+            false
+        })),
+        ..ExtractOptions::default()
+    };
+    assert!(matches!(verify(&zip_path, &options), Err(ExtractError::Cancelled)));
+    assert_eq!(reported.load(Ordering::Relaxed), 1);
+}
@@ -14,11 +14,40 @@ fn main() {
     unarchiver_lib::commands::JobStatus::export().expect("Failed to export JobStatus");
     unarchiver_lib::commands::PasswordRequiredEvent::export()
         .expect("Failed to export PasswordRequiredEvent");
+    unarchiver_lib::commands::ProbeProgressEvent::export()
+        .expect("Failed to export ProbeProgressEvent");
+    unarchiver_lib::commands::JobKind::export().expect("Failed to export JobKind");
+    unarchiver_lib::commands::JobStage::export().expect("Failed to export JobStage");
+    unarchiver_lib::commands::JobEvent::export().expect("Failed to export JobEvent");
+    unarchiver_lib::commands::JobSummary::export().expect("Failed to export JobSummary");
+    unarchiver_lib::commands::FolderJob::export().expect("Failed to export FolderJob");
+    unarchiver_lib::commands::Diagnostics::export().expect("Failed to export Diagnostics");
     unarchiver_lib::commands::FileSystemEntry::export().expect("Failed to export FileSystemEntry");
+    unarchiver_lib::commands::OpenAction::export().expect("Failed to export OpenAction");
     unarchiver_lib::commands::SettingsData::export().expect("Failed to export SettingsData");
+    unarchiver_lib::commands::ExtractStartedEvent::export()
+        .expect("Failed to export ExtractStartedEvent");
+    unarchiver_lib::commands::CrashedJob::export().expect("Failed to export CrashedJob");
+    unarchiver_lib::commands::CrashedJobAction::export()
+        .expect("Failed to export CrashedJobAction");
 
     extractor::ArchiveInfo::export().expect("Failed to export ArchiveInfo");
+    extractor::ArchiveSummary::export().expect("Failed to export ArchiveSummary");
+    extractor::ExtensionStats::export().expect("Failed to export ExtensionStats");
+    extractor::ResourceHint::export().expect("Failed to export ResourceHint");
+    extractor::Integrity::export().expect("Failed to export Integrity");
+    extractor::EntrySort::export().expect("Failed to export EntrySort");
+    extractor::SortKey::export().expect("Failed to export SortKey");
+    extractor::SignatureMatch::export().expect("Failed to export SignatureMatch");
     extractor::ExtractStats::export().expect("Failed to export ExtractStats");
+    extractor::ExtractionPlan::export().expect("Failed to export ExtractionPlan");
+    extractor::ExtractionJournal::export().expect("Failed to export ExtractionJournal");
+    extractor::BlockedEntry::export().expect("Failed to export BlockedEntry");
+    extractor::ContentKind::export().expect("Failed to export ContentKind");
+    extractor::VolumeReport::export().expect("Failed to export VolumeReport");
+    extractor::VolumeProblem::export().expect("Failed to export VolumeProblem");
+    extractor::VolumeIssue::export().expect("Failed to export VolumeIssue");
+    extractor::Format::export().expect("Failed to export Format");
 
     println!("✓ TypeScript types exported successfully to src/lib/bindings/");
 }
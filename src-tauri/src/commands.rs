@@ -1,27 +1,141 @@
-use crate::state::{AppState, JobHandle};
-use extractor::{ExtractOptions, ExtractStats, OverwriteMode};
+use crate::journal::{self, JobJournal, Journal};
+use crate::library::Bookmark;
+use crate::state::{AppState, JobHandle, JobTask, WindowSession, MAIN_WINDOW};
+use crate::tray;
+use extractor::{
+    ContentKind, ContentPolicy, EntryAction, ErrorHint, ErrorPolicy, ExtractOptions, ExtractStats,
+    FilenameEncoding, FreeSpaceCheck, NameSanitizer, NormalizationForm, OversizePolicy,
+    OverwriteMode, PathLimits, ProgressListener, ProgressPhase, SubfolderPolicy, SymlinkPolicy,
+    TimestampPolicy, WindowsNamePolicy,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter, Manager, State};
+use tauri::{AppHandle, Emitter, Manager, State, WebviewUrl, WebviewWindowBuilder, Window};
 use ts_rs::TS;
 use uuid::Uuid;
 
+pub use crate::journal::{CrashedJob, CrashedJobAction};
+
 /// DTO for extraction options from frontend
-#[derive(Debug, Deserialize, TS)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
 #[ts(export, export_to = "../../src/lib/bindings/")]
 #[serde(rename_all = "camelCase")]
 pub struct ExtractOptionsDTO {
     pub overwrite: String,
+    #[ts(optional)]
+    pub rename_template: Option<String>,
     #[ts(optional, type = "number")]
     pub size_limit_bytes: Option<u64>,
+    /// Largest size of a single file in bytes
+    #[ts(optional, type = "number")]
+    pub max_entry_size_bytes: Option<u64>,
+    /// What to do with files over `max_entry_size_bytes`; unset fails the
+    /// extraction
+    #[ts(optional)]
+    pub oversized_entries: Option<OversizePolicy>,
+    /// Largest ratio of an entry's size to its compressed size
+    #[ts(optional)]
+    pub max_compression_ratio: Option<u32>,
+    /// Largest ratio of everything extracted to the archive's size
+    #[ts(optional)]
+    pub max_expansion_ratio: Option<u32>,
     #[ts(type = "number")]
     pub strip_components: u32,
+    /// Whether to extract into a folder named after the archive; unset never
+    /// does
+    #[ts(optional)]
+    pub create_subfolder: Option<SubfolderPolicy>,
     pub allow_symlinks: bool,
     pub allow_hardlinks: bool,
+    /// What to do with symbolic links; unset creates them if `allowSymlinks`
+    /// and skips them otherwise
+    #[ts(optional)]
+    pub symlink_policy: Option<SymlinkPolicy>,
     #[ts(optional)]
     pub password: Option<String>,
+    #[ts(optional)]
+    pub detect_case_sensitivity: Option<bool>,
+    #[ts(optional)]
+    pub write_provenance: Option<bool>,
+    #[ts(optional)]
+    pub hash_files: Option<bool>,
+    #[ts(optional)]
+    pub write_sha256sums: Option<bool>,
+    #[ts(optional)]
+    pub preserve_permissions: Option<bool>,
+    #[ts(optional)]
+    pub apply_umask: Option<bool>,
+    #[ts(optional)]
+    pub preserve_xattrs: Option<bool>,
+    #[ts(optional)]
+    pub timestamp_policy: Option<TimestampPolicy>,
+    #[ts(optional)]
+    pub file_mode: Option<u32>,
+    #[ts(optional)]
+    pub dir_mode: Option<u32>,
+    #[ts(optional)]
+    pub check_free_inodes: Option<bool>,
+    /// Whether to compare the archive's size with the destination's free
+    /// space first; unset doesn't
+    #[ts(optional)]
+    pub check_free_space: Option<FreeSpaceCheck>,
+    #[ts(optional)]
+    pub sanitize_control_chars: Option<bool>,
+    #[ts(optional)]
+    pub path_limits: Option<PathLimits>,
+    #[ts(optional)]
+    pub sanitize_names: Option<NameSanitizer>,
+    #[ts(optional)]
+    pub windows_names: Option<WindowsNamePolicy>,
+    /// Encoding label for ZIP entry names not marked as UTF-8 (e.g. "cp932");
+    /// unset or unknown labels detect it
+    #[ts(optional)]
+    pub filename_encoding: Option<String>,
+    #[ts(optional)]
+    pub normalize_names: Option<NormalizationForm>,
+    #[ts(optional)]
+    pub deny_content: Option<Vec<ContentKind>>,
+    #[ts(optional)]
+    pub quarantine_dir: Option<String>,
+    #[ts(optional)]
+    pub propagate_quarantine: Option<bool>,
+    #[ts(optional, type = "number")]
+    pub max_duration_secs: Option<u64>,
+    #[ts(optional)]
+    pub atomic_writes: Option<bool>,
+    #[ts(optional)]
+    pub verify_after_extract: Option<bool>,
+    #[ts(optional)]
+    pub verify_checksum: Option<bool>,
+    #[ts(optional)]
+    pub write_queue_depth: Option<u32>,
+    #[ts(optional)]
+    pub threads: Option<u32>,
+    #[ts(optional)]
+    pub transactional: Option<bool>,
+    #[ts(optional)]
+    pub cleanup_on_failure: Option<bool>,
+    #[ts(optional)]
+    pub on_error: Option<ErrorPolicy>,
+    #[ts(optional)]
+    pub bypass_page_cache: Option<bool>,
+    #[ts(optional)]
+    pub buffer_size: Option<u32>,
+    #[ts(optional)]
+    pub sparse_files: Option<bool>,
+    #[ts(optional)]
+    pub temp_dir: Option<String>,
+    #[ts(optional)]
+    pub memory_map: Option<bool>,
+    #[ts(optional)]
+    pub journal: Option<bool>,
+    #[ts(optional)]
+    pub tolerate_trailing_data: Option<bool>,
+    #[ts(optional)]
+    pub read_concatenated_tar: Option<bool>,
 }
 
 impl From<ExtractOptionsDTO> for ExtractOptions {
@@ -29,16 +143,87 @@ impl From<ExtractOptionsDTO> for ExtractOptions {
         let overwrite = match dto.overwrite.as_str() {
             "replace" => OverwriteMode::Replace,
             "skip" => OverwriteMode::Skip,
+            "ifnewer" => OverwriteMode::IfNewer,
+            "error" => OverwriteMode::Error,
             _ => OverwriteMode::Rename,
         };
 
         ExtractOptions {
             overwrite,
+            rename_template: dto.rename_template,
+            overwrite_prompt: None,
             size_limit_bytes: dto.size_limit_bytes,
+            max_entry_size_bytes: dto.max_entry_size_bytes,
+            oversized_entries: dto.oversized_entries.unwrap_or_default(),
+            max_compression_ratio: dto.max_compression_ratio,
+            max_expansion_ratio: dto.max_expansion_ratio,
             strip_components: dto.strip_components,
-            allow_symlinks: dto.allow_symlinks,
+            create_subfolder: dto.create_subfolder.unwrap_or_default(),
+            symlink_policy: dto.symlink_policy.unwrap_or(if dto.allow_symlinks {
+                SymlinkPolicy::Preserve
+            } else {
+                SymlinkPolicy::Skip
+            }),
             allow_hardlinks: dto.allow_hardlinks,
             password: dto.password,
+            detect_case_sensitivity: dto.detect_case_sensitivity.unwrap_or(true),
+            write_provenance: dto.write_provenance.unwrap_or(false),
+            hash_files: dto.hash_files.unwrap_or(false),
+            write_sha256sums: dto.write_sha256sums.unwrap_or(false),
+            preserve_permissions: dto.preserve_permissions,
+            apply_umask: dto.apply_umask.unwrap_or(true),
+            preserve_xattrs: dto.preserve_xattrs.unwrap_or(false),
+            allow_unsafe_xattrs: false,
+            preserve_ownership: false,
+            timestamp_policy: dto.timestamp_policy.unwrap_or(TimestampPolicy::Preserve),
+            file_mode: dto.file_mode,
+            dir_mode: dto.dir_mode,
+            check_free_inodes: dto.check_free_inodes.unwrap_or(false),
+            check_free_space: dto.check_free_space.unwrap_or_default(),
+            sanitize_control_chars: dto.sanitize_control_chars.unwrap_or(true),
+            path_limits: dto.path_limits.unwrap_or_default(),
+            sanitize_names: dto.sanitize_names.unwrap_or_default(),
+            windows_names: dto.windows_names.unwrap_or_default(),
+            filename_encoding: dto
+                .filename_encoding
+                .as_deref()
+                .and_then(FilenameEncoding::for_label),
+            normalize_names: dto.normalize_names.unwrap_or(NormalizationForm::Keep),
+            entry_filter: None,
+            pre_write_hook: None,
+            map_path: None,
+            on_entry: None,
+            on_progress: None,
+            content_policy: dto
+                .deny_content
+                .filter(|deny| !deny.is_empty())
+                .map(|deny| ContentPolicy {
+                    deny,
+                    quarantine_dir: dto.quarantine_dir.map(PathBuf::from),
+                }),
+            propagate_quarantine: dto.propagate_quarantine.unwrap_or(false),
+            max_duration: dto.max_duration_secs.map(std::time::Duration::from_secs),
+            atomic_writes: dto.atomic_writes.unwrap_or(true),
+            verify_after_extract: dto.verify_after_extract.unwrap_or(false),
+            verify_checksum: dto.verify_checksum.unwrap_or(false),
+            write_queue_depth: match dto.write_queue_depth {
+                Some(depth) => depth as usize,
+                None => ExtractOptions::default().write_queue_depth,
+            },
+            threads: dto.threads.map_or(1, |threads| threads as usize),
+            transactional: dto.transactional.unwrap_or(false),
+            cleanup_on_failure: dto.cleanup_on_failure.unwrap_or(true),
+            on_error: dto.on_error.unwrap_or(ErrorPolicy::Abort),
+            bypass_page_cache: dto.bypass_page_cache.unwrap_or(false),
+            buffer_size: dto
+                .buffer_size
+                .map_or(extractor::DEFAULT_BUFFER_SIZE, |size| size as usize),
+            sparse_files: dto.sparse_files.unwrap_or(false),
+            temp_dir: dto.temp_dir.map(PathBuf::from),
+            memory_map: dto.memory_map.unwrap_or(false),
+            journal: dto.journal.unwrap_or(false),
+            tolerate_trailing_data: dto.tolerate_trailing_data.unwrap_or(true),
+            read_concatenated_tar: dto.read_concatenated_tar.unwrap_or(false),
         }
     }
 }
@@ -50,11 +235,26 @@ impl From<ExtractOptionsDTO> for ExtractOptions {
 pub struct ProgressEvent {
     pub job_id: String,
     pub archive_path: String,
+    pub phase: ProgressPhase,
     pub current_file: String,
+    /// Position of the current entry among those extracted, from 0
+    #[ts(type = "number")]
+    pub entry_index: u64,
+    #[ts(optional, type = "number")]
+    pub total_entries: Option<u64>,
+    /// Bytes of the current entry written so far, and its size
+    #[ts(type = "number")]
+    pub entry_bytes: u64,
+    #[ts(optional, type = "number")]
+    pub entry_total: Option<u64>,
+    /// Bytes the extraction has written so far, and will write in all
     #[ts(type = "number")]
     pub bytes_written: u64,
     #[ts(optional, type = "number")]
     pub total_bytes: Option<u64>,
+    /// What became of the entry, on the report that finishes it
+    #[ts(optional)]
+    pub action: Option<EntryAction>,
 }
 
 /// Completion event payload
@@ -69,6 +269,18 @@ pub struct CompletionEvent {
     pub stats: Option<ExtractStats>,
     #[ts(optional)]
     pub error: Option<String>,
+    /// Suggested next steps for a failure
+    pub hints: Vec<ErrorHint>,
+}
+
+impl CompletionEvent {
+    fn emit(self, app: &AppHandle) {
+        app.state::<AppState>()
+            .job_events
+            .lock()
+            .record_completion(&self);
+        emit_to_job(app, &self.job_id.clone(), "extract_done", self);
+    }
 }
 
 #[derive(Debug, Clone, Serialize, TS)]
@@ -89,234 +301,995 @@ pub struct PasswordRequiredEvent {
     pub archive_path: String,
 }
 
+impl PasswordRequiredEvent {
+    fn emit(self, app: &AppHandle) {
+        app.state::<AppState>()
+            .job_events
+            .lock()
+            .record_password_required(&self);
+        emit_to_job(app, &self.job_id.clone(), "password_required", self);
+    }
+}
+
+/// Probe progress event payload
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../src/lib/bindings/")]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeProgressEvent {
+    pub archive_path: String,
+    #[ts(type = "number")]
+    pub bytes_scanned: u64,
+    #[ts(type = "number")]
+    pub entries_found: u64,
+}
+
+/// Kind of long-running operation tracked as a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../src/lib/bindings/")]
+#[serde(rename_all = "lowercase")]
+pub enum JobKind {
+    Probe,
+    Extract,
+    Verify,
+}
+
+/// Lifecycle stage shared by all job kinds
+#[derive(Debug, Clone, Copy, Serialize, TS)]
+#[ts(export, export_to = "../../src/lib/bindings/")]
+#[serde(rename_all = "lowercase")]
+pub enum JobStage {
+    Queued,
+    Started,
+    Progress,
+    Done,
+}
+
+/// Job lifecycle event payload, emitted for every job kind
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../src/lib/bindings/")]
+#[serde(rename_all = "camelCase")]
+pub struct JobEvent {
+    pub job_id: String,
+    pub kind: JobKind,
+    pub stage: JobStage,
+    #[ts(optional)]
+    pub archive_path: Option<String>,
+    #[ts(optional)]
+    pub current_item: Option<String>,
+    #[ts(optional, type = "number")]
+    pub processed: Option<u64>,
+    #[ts(optional, type = "number")]
+    pub total: Option<u64>,
+    #[ts(optional)]
+    pub status: Option<JobStatus>,
+    #[ts(optional)]
+    pub error: Option<String>,
+}
+
+impl JobEvent {
+    fn new(job_id: &str, kind: JobKind, stage: JobStage) -> Self {
+        Self {
+            job_id: job_id.to_string(),
+            kind,
+            stage,
+            archive_path: None,
+            current_item: None,
+            processed: None,
+            total: None,
+            status: None,
+            error: None,
+        }
+    }
+
+    fn emit(self, app: &AppHandle) {
+        app.state::<AppState>().job_events.lock().record(&self);
+        tray::job_changed(app, self.stage);
+        emit_to_job(app, &self.job_id.clone(), "job_event", self);
+    }
+}
+
+/// Send an event about `job_id` to the window that started the job
+fn emit_to_job<S: Serialize + Clone>(app: &AppHandle, job_id: &str, event: &str, payload: S) {
+    let window = app.state::<AppState>().job_window(job_id);
+    let _ = app.emit_to(window.as_str(), event, payload);
+}
+
+/// Key events of a job so far, replayed to windows that connect while it runs
+/// or after it finished
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export, export_to = "../../src/lib/bindings/")]
+#[serde(rename_all = "camelCase")]
+pub struct JobEventLog {
+    /// The latest of the job's queued and started events
+    #[ts(optional)]
+    pub started: Option<JobEvent>,
+    /// The latest progress event
+    #[ts(optional)]
+    pub progress: Option<JobEvent>,
+    /// Set while the job waits for a password
+    #[ts(optional)]
+    pub password_required: Option<PasswordRequiredEvent>,
+    /// Completion events of the archives extracted so far
+    pub archives_done: Vec<CompletionEvent>,
+    /// Set once the job has finished
+    #[ts(optional)]
+    pub done: Option<JobEvent>,
+}
+
+/// Bytes written per second by a job over a short interval
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../src/lib/bindings/")]
+#[serde(rename_all = "camelCase")]
+pub struct ThroughputSample {
+    /// Milliseconds since the job started, at the end of the interval
+    #[ts(type = "number")]
+    pub elapsed_ms: u64,
+    #[ts(type = "number")]
+    pub bytes_per_sec: u64,
+}
+
+/// Recent throughput of an extraction or test job, for drawing a live speed
+/// graph
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../src/lib/bindings/")]
+#[serde(rename_all = "camelCase")]
+pub struct JobMetrics {
+    /// Bytes written (or, for a test, checked) so far, across the job's
+    /// archives
+    #[ts(type = "number")]
+    pub bytes_written: u64,
+    /// Bytes written per second since the job started
+    #[ts(type = "number")]
+    pub average_bytes_per_sec: u64,
+    /// Samples of about half a second each over the last minute, oldest first
+    pub samples: Vec<ThroughputSample>,
+}
+
+/// Summary of an active job
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../src/lib/bindings/")]
+#[serde(rename_all = "camelCase")]
+pub struct JobSummary {
+    pub job_id: String,
+    pub kind: JobKind,
+    pub archive_paths: Vec<String>,
+    /// Archive the job is working on, once it reported progress
+    #[ts(optional)]
+    pub archive_path: Option<String>,
+    #[ts(optional, type = "number")]
+    pub processed: Option<u64>,
+    #[ts(optional, type = "number")]
+    pub total: Option<u64>,
+}
+
+/// Archives found by [`extract_folder`] and the job extracting them
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../src/lib/bindings/")]
+#[serde(rename_all = "camelCase")]
+pub struct FolderJob {
+    pub job_id: String,
+    pub archive_paths: Vec<String>,
+}
+
+/// Where one archive of an extraction job goes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) enum Destination {
+    /// Straight into this directory
+    Dir(PathBuf),
+    /// Into a new folder named after the archive inside this directory, picked
+    /// when its turn comes so archives sharing a name get separate folders
+    NewFolderIn(PathBuf),
+}
+
+impl Destination {
+    pub(crate) fn resolve(&self, archive_path: &Path) -> PathBuf {
+        match self {
+            Destination::Dir(dir) => dir.clone(),
+            Destination::NewFolderIn(parent) => extractor::unique_output_dir(parent, archive_path),
+        }
+    }
+}
+
 /// Extract one or more archives
 #[tauri::command]
 pub async fn extract(
     app: AppHandle,
+    window: Window,
     state: State<'_, AppState>,
     input_paths: Vec<String>,
     out_dir: String,
     options: ExtractOptionsDTO,
 ) -> Result<String, String> {
+    let output_dir = PathBuf::from(out_dir);
+    let archives = input_paths
+        .into_iter()
+        .map(|path| (path, Destination::Dir(output_dir.clone())))
+        .collect();
+
     // Generate unique job ID
     let job_id = Uuid::new_v4().to_string();
+    start_extract_job(
+        app,
+        &state,
+        window.label(),
+        job_id.clone(),
+        archives,
+        options,
+        false,
+    );
+
+    Ok(job_id)
+}
 
-    // Convert options
+/// Payload of `extract_started`, sent for jobs the backend starts on its own
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../src/lib/bindings/")]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractStartedEvent {
+    pub job_id: String,
+    pub archive_path: String,
+    pub output_dir: String,
+}
+
+/// Extract an archive opened from Finder straight away with the saved settings,
+/// into a new folder next to it, announcing the job to the frontend first (to
+/// the archive's own window if it has one)
+pub(crate) fn extract_opened(app: &AppHandle, settings: &SettingsData, archive_path: String) {
+    let archive = PathBuf::from(&archive_path);
+    let parent = archive.parent().unwrap_or(Path::new("."));
+    let output_dir = extractor::unique_output_dir(parent, &archive);
+
+    let job_id = Uuid::new_v4().to_string();
+    let started = ExtractStartedEvent {
+        job_id: job_id.clone(),
+        archive_path: archive_path.clone(),
+        output_dir: output_dir.to_string_lossy().to_string(),
+    };
+    let state = app.state::<AppState>();
+    let window = state
+        .archive_window(&archive_path)
+        .unwrap_or_else(|| MAIN_WINDOW.to_string());
+    let _ = app.emit_to(window.as_str(), "extract_started", started);
+
+    start_extract_job(
+        app.clone(),
+        &state,
+        &window,
+        job_id,
+        vec![(archive_path, Destination::Dir(output_dir))],
+        settings.extract_options(),
+        false,
+    );
+}
+
+/// Extract every archive in a folder as one batch job
+///
+/// Each archive is extracted into a folder of its own next to it, as a single
+/// extraction would be. Multi-volume sets are extracted once, through their
+/// first volume, and one failed archive doesn't stop the rest of the batch.
+#[tauri::command]
+pub async fn extract_folder(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, AppState>,
+    path: String,
+    recursive: bool,
+    options: ExtractOptionsDTO,
+) -> Result<FolderJob, String> {
+    let folder = PathBuf::from(&path);
+    let found = extractor::find_archives(&folder, recursive)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    if found.is_empty() {
+        return Err(format!("No archives found in {}", path));
+    }
+
+    let archives: Vec<(String, Destination)> = found
+        .into_iter()
+        .map(|archive| {
+            let parent = archive.parent().unwrap_or(&folder).to_path_buf();
+            (
+                archive.to_string_lossy().to_string(),
+                Destination::NewFolderIn(parent),
+            )
+        })
+        .collect();
+    let archive_paths = archives.iter().map(|(path, _)| path.clone()).collect();
+
+    let job_id = Uuid::new_v4().to_string();
+    start_extract_job(
+        app,
+        &state,
+        window.label(),
+        job_id.clone(),
+        archives,
+        options,
+        true,
+    );
+
+    Ok(FolderJob {
+        job_id,
+        archive_paths,
+    })
+}
+
+/// Resume, clean up after or forget a job from an earlier session that never
+/// finished, as reported in the `crashed_jobs` event
+///
+/// Returns the id of the new job when resuming. Either way the old job's
+/// journal is deleted, so it isn't reported again.
+#[tauri::command]
+pub async fn resolve_crashed_job(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, AppState>,
+    job_id: String,
+    action: CrashedJobAction,
+) -> Result<Option<String>, String> {
+    if state.jobs.lock().contains_key(&job_id) {
+        return Err(format!("Job {} is still running", job_id));
+    }
+    let crashed = journal::read(&app, &job_id)?;
+
+    let resumed = match action {
+        CrashedJobAction::Resume => resume_crashed_job(&app, &state, window.label(), &crashed)?,
+        CrashedJobAction::Cleanup => {
+            journal::clean_up(&crashed)
+                .map_err(|e| format!("Failed to clean up after job {}: {}", job_id, e))?;
+            None
+        }
+        CrashedJobAction::Dismiss => None,
+    };
+
+    journal::remove(&app, &job_id)
+        .map_err(|e| format!("Failed to delete the journal of job {}: {}", job_id, e))?;
+    Ok(resumed)
+}
+
+/// Read the journal the extractor keeps next to `output_dir`, left behind if
+/// an extraction started with the `journal` option never finished
+#[tauri::command]
+pub async fn read_extraction_journal(
+    output_dir: String,
+) -> Result<Option<extractor::ExtractionJournal>, String> {
+    extractor::read_journal(Path::new(&output_dir))
+        .map_err(|e| format!("Failed to read the extraction journal: {}", e))
+}
+
+/// Start a job extracting what `crashed` didn't finish, announced to the
+/// frontend like jobs opened from Finder
+///
+/// The interrupted archive is extracted again into the same folder, keeping the
+/// files that were completely written before the crash.
+fn resume_crashed_job(
+    app: &AppHandle,
+    state: &AppState,
+    window: &str,
+    crashed: &JobJournal,
+) -> Result<Option<String>, String> {
+    let mut archives = crashed.archives[crashed.completed.min(crashed.archives.len())..].to_vec();
+    let mut options = crashed.options.clone();
+
+    let Some((archive_path, destination)) = archives.first_mut() else {
+        return Ok(None);
+    };
+    if let Some(current) = &crashed.current {
+        if current.output_dir.exists() {
+            journal::remove_partial_files(&current.output_dir).map_err(|e| {
+                format!("Failed to clean up {}: {}", current.output_dir.display(), e)
+            })?;
+        }
+        *destination = Destination::Dir(current.output_dir.clone());
+        options.overwrite = "skip".to_string();
+    }
+    let output_dir = destination.resolve(Path::new(archive_path));
+    *destination = Destination::Dir(output_dir.clone());
+
+    let job_id = Uuid::new_v4().to_string();
+    let started = ExtractStartedEvent {
+        job_id: job_id.clone(),
+        archive_path: archive_path.clone(),
+        output_dir: output_dir.to_string_lossy().to_string(),
+    };
+    let _ = app.emit_to(window, "extract_started", started);
+
+    start_extract_job(
+        app.clone(),
+        state,
+        window,
+        job_id.clone(),
+        archives,
+        options,
+        crashed.keep_going,
+    );
+    Ok(Some(job_id))
+}
+
+/// Start job `job_id`, extracting `archives` one after the other
+///
+/// Unless `keep_going` is set, the first archive that fails ends the job. The
+/// job keeps a journal for as long as it runs (see [`crate::journal`]), and its
+/// events go to the window labelled `window`.
+fn start_extract_job(
+    app: AppHandle,
+    state: &AppState,
+    window: &str,
+    job_id: String,
+    archives: Vec<(String, Destination)>,
+    options: ExtractOptionsDTO,
+    keep_going: bool,
+) {
+    let journal = Journal::create(&app, &job_id, &archives, &options, keep_going).map(Arc::new);
     let mut extract_options: ExtractOptions = options.into();
-    let output_dir = PathBuf::from(out_dir);
 
     // Create cancel flag
     let cancel_flag = Arc::new(AtomicBool::new(false));
     let cancel_flag_clone = cancel_flag.clone();
 
+    // Shared with other jobs so parallel extractions stay under the fd limit
+    let fd_budget = state.fd_budget.clone();
+    let paused = state.paused.clone();
+
+    for (archive_path, _) in &archives {
+        state.remember_archive(archive_path);
+    }
+
     // Create password channel (using mpsc for potential multiple retries)
     let (password_tx, mut password_rx) = tokio::sync::mpsc::channel::<String>(1);
 
+    state.attach_job(window, &job_id);
+    JobEvent::new(&job_id, JobKind::Extract, JobStage::Queued).emit(&app);
+
     // Clone for the task
     let job_id_clone = job_id.clone();
     let app_clone = app.clone();
+    let job_id_for_done = job_id.clone();
+    let app_for_done = app.clone();
+    let archive_paths = archives.iter().map(|(path, _)| path.clone()).collect();
 
     // Spawn the extraction task
     let task = tokio::spawn(async move {
-        let mut final_stats = None;
-
-        for input_path in input_paths {
-            let archive_path = PathBuf::from(&input_path);
-            let archive_path_str = input_path.clone();
+        JobEvent::new(&job_id_clone, JobKind::Extract, JobStage::Started).emit(&app_clone);
+
+        let result = async move {
+            let mut final_stats = None;
+            let mut first_error = None;
+
+            for (input_path, destination) in archives {
+                let archive_path = PathBuf::from(&input_path);
+                let archive_path_str = input_path.clone();
+                let output_dir = destination.resolve(&archive_path);
+                if let Some(journal) = &journal {
+                    journal.start_archive(&output_dir);
+                }
 
-            // Try extraction with retry for password
-            let mut retry_count = 0;
-            let max_retries = 3;
+                // Try extraction with retry for password
+                let mut retry_count = 0;
+                let max_retries = 3;
+
+                loop {
+                    // Clone for progress callback
+                    let job_id_for_progress = job_id_clone.clone();
+                    let app_for_progress = app_clone.clone();
+                    let archive_for_progress = archive_path_str.clone();
+                    let journal_for_progress = journal.clone();
+                    let paused_for_progress = paused.clone();
+                    let cancel_flag_for_progress = cancel_flag_clone.clone();
+
+                    // Create progress callback
+                    let progress_callback =
+                        move |current_file: &str, bytes_written: u64, _total_bytes: Option<u64>| {
+                            // Hold the extraction here while all jobs are paused
+                            wait_while_paused(&paused_for_progress, &cancel_flag_for_progress);
+
+                            if let Some(journal) = &journal_for_progress {
+                                journal.progress(current_file, bytes_written);
+                            }
 
-            loop {
-                // Clone for progress callback
-                let job_id_for_progress = job_id_clone.clone();
-                let app_for_progress = app_clone.clone();
-                let archive_for_progress = archive_path_str.clone();
+                            true // Continue extraction
+                        };
 
-                // Create progress callback
-                let progress_callback =
-                    move |current_file: &str, bytes_written: u64, total_bytes: Option<u64>| {
+                    // Structured progress goes to the frontend
+                    let progress_listener = ProgressListener::new(move |progress| {
                         let event = ProgressEvent {
                             job_id: job_id_for_progress.clone(),
                             archive_path: archive_for_progress.clone(),
-                            current_file: current_file.to_string(),
-                            bytes_written,
-                            total_bytes,
+                            phase: progress.phase,
+                            current_file: progress.path.clone(),
+                            entry_index: progress.entry_index,
+                            total_entries: progress.total_entries,
+                            entry_bytes: progress.entry_bytes,
+                            entry_total: progress.entry_total,
+                            bytes_written: progress.bytes_written,
+                            total_bytes: progress.total_bytes,
+                            action: progress.action,
                         };
 
-                        let _ = app_for_progress.emit_to("main", "extract_progress", event);
-                        true // Continue extraction
+                        emit_to_job(
+                            &app_for_progress,
+                            &job_id_for_progress,
+                            "extract_progress",
+                            event,
+                        );
+
+                        JobEvent {
+                            archive_path: Some(archive_for_progress.clone()),
+                            current_item: Some(progress.path.clone()),
+                            processed: Some(progress.bytes_written),
+                            total: progress.total_bytes,
+                            ..JobEvent::new(
+                                &job_id_for_progress,
+                                JobKind::Extract,
+                                JobStage::Progress,
+                            )
+                        }
+                        .emit(&app_for_progress);
+
+                        true
+                    });
+
+                    // Run extraction in blocking context
+                    let archive_path_for_blocking = archive_path.clone();
+                    let archive_path_str_for_blocking = archive_path_str.clone();
+                    let output_dir_for_blocking = output_dir.clone();
+                    let options_for_blocking = ExtractOptions {
+                        on_progress: Some(progress_listener),
+                        ..extract_options.clone()
                     };
+                    let cancel_flag_for_blocking = cancel_flag_clone.clone();
+                    let fd_budget_for_blocking = fd_budget.clone();
+                    // Parallel workers hold handles of their own; a job wider
+                    // than the whole budget gets all of it rather than waiting
+                    let fds = extractor::fds_per_extraction(extract_options.threads)
+                        .min(fd_budget.capacity());
+
+                    let result = tokio::task::spawn_blocking(move || {
+                        // Queue behind other jobs until enough file handles are free
+                        let _permit = fd_budget_for_blocking.acquire(fds);
+
+                        // Downloads are unpacked as they stream in
+                        if is_url(&archive_path_str_for_blocking) {
+                            return extractor::extract_url(
+                                &archive_path_str_for_blocking,
+                                &output_dir_for_blocking,
+                                &options_for_blocking,
+                                &progress_callback,
+                                cancel_flag_for_blocking,
+                            );
+                        }
 
-                // Run extraction in blocking context
-                let archive_path_for_blocking = archive_path.clone();
-                let output_dir_for_blocking = output_dir.clone();
-                let options_for_blocking = extract_options.clone();
-                let cancel_flag_for_blocking = cancel_flag_clone.clone();
-
-                let result = tokio::task::spawn_blocking(move || {
-                    extractor::extract(
-                        &archive_path_for_blocking,
-                        &output_dir_for_blocking,
-                        &options_for_blocking,
-                        &progress_callback,
-                        cancel_flag_for_blocking,
-                    )
-                })
-                .await;
-
-                match result {
-                    Ok(Ok(stats)) => {
-                        final_stats = Some(stats);
-
-                        // Emit completion event for this archive
-                        let completion = CompletionEvent {
-                            job_id: job_id_clone.clone(),
-                            archive_path: archive_path_str,
-                            status: JobStatus::Success,
-                            stats: final_stats.clone(),
-                            error: None,
-                        };
-                        let _ = app_clone.emit_to("main", "extract_done", completion);
-                        break; // Success, move to next archive
-                    }
-                    Ok(Err(e)) => {
-                        // Check if password is required
-                        if matches!(
-                            e,
-                            extractor::ExtractError::PasswordRequired
-                                | extractor::ExtractError::InvalidPassword
-                        ) && retry_count < max_retries
-                        {
-                            retry_count += 1;
-
-                            // Emit password_required event
-                            let password_event = PasswordRequiredEvent {
+                        extractor::extract(
+                            &archive_path_for_blocking,
+                            &output_dir_for_blocking,
+                            &options_for_blocking,
+                            &progress_callback,
+                            cancel_flag_for_blocking,
+                        )
+                    })
+                    .await;
+
+                    match result {
+                        Ok(Ok(stats)) => {
+                            final_stats = Some(stats);
+
+                            // Emit completion event for this archive
+                            let completion = CompletionEvent {
                                 job_id: job_id_clone.clone(),
-                                archive_path: archive_path_str.clone(),
+                                archive_path: archive_path_str,
+                                status: JobStatus::Success,
+                                stats: final_stats.clone(),
+                                error: None,
+                                hints: Vec::new(),
                             };
-                            let _ = app_clone.emit_to("main", "password_required", password_event);
-
-                            // Wait for password from frontend (with timeout)
-                            match tokio::time::timeout(
-                                tokio::time::Duration::from_secs(300), // 5 minute timeout
-                                password_rx.recv(),
-                            )
-                            .await
+                            completion.emit(&app_clone);
+                            if let Some(journal) = &journal {
+                                journal.finish_archive();
+                            }
+                            break; // Success, move to next archive
+                        }
+                        Ok(Err(e)) => {
+                            // Check if password is required
+                            if matches!(
+                                e,
+                                extractor::ExtractError::PasswordRequired
+                                    | extractor::ExtractError::InvalidPassword
+                            ) && retry_count < max_retries
                             {
-                                Ok(Some(password)) => {
-                                    // Update options with the provided password
-                                    extract_options.password = Some(password);
-                                    continue; // Retry extraction
-                                }
-                                Ok(None) | Err(_) => {
-                                    // Channel closed or timeout - treat as cancellation
-                                    let completion = CompletionEvent {
-                                        job_id: job_id_clone.clone(),
-                                        archive_path: archive_path_str.clone(),
-                                        status: JobStatus::Cancelled,
-                                        stats: None,
-                                        error: Some(
-                                            "Password prompt timed out or was cancelled"
-                                                .to_string(),
-                                        ),
-                                    };
-                                    let _ = app_clone.emit_to("main", "extract_done", completion);
-                                    return Err(extractor::ExtractError::Cancelled);
+                                retry_count += 1;
+
+                                // Emit password_required event
+                                let password_event = PasswordRequiredEvent {
+                                    job_id: job_id_clone.clone(),
+                                    archive_path: archive_path_str.clone(),
+                                };
+                                password_event.emit(&app_clone);
+
+                                // Wait for password from frontend (with timeout)
+                                match tokio::time::timeout(
+                                    tokio::time::Duration::from_secs(300), // 5 minute timeout
+                                    password_rx.recv(),
+                                )
+                                .await
+                                {
+                                    Ok(Some(password)) => {
+                                        // Update options with the provided password
+                                        extract_options.password = Some(password);
+                                        continue; // Retry extraction
+                                    }
+                                    Ok(None) | Err(_) => {
+                                        // Channel closed or timeout - treat as cancellation
+                                        let completion = CompletionEvent {
+                                            job_id: job_id_clone.clone(),
+                                            archive_path: archive_path_str.clone(),
+                                            status: JobStatus::Cancelled,
+                                            stats: None,
+                                            error: Some(
+                                                "Password prompt timed out or was cancelled"
+                                                    .to_string(),
+                                            ),
+                                            hints: Vec::new(),
+                                        };
+                                        completion.emit(&app_clone);
+                                        return Err(extractor::ExtractError::Cancelled);
+                                    }
                                 }
                             }
-                        }
 
-                        let error_msg = e.to_string();
+                            let error_msg = e.to_string();
 
-                        let status = if matches!(e, extractor::ExtractError::Cancelled) {
-                            JobStatus::Cancelled
-                        } else {
-                            JobStatus::Failed
-                        };
+                            let status = if matches!(e, extractor::ExtractError::Cancelled) {
+                                JobStatus::Cancelled
+                            } else {
+                                JobStatus::Failed
+                            };
 
-                        let completion = CompletionEvent {
-                            job_id: job_id_clone.clone(),
-                            archive_path: archive_path_str,
-                            status,
-                            stats: None,
-                            error: Some(error_msg),
-                        };
-                        let _ = app_clone.emit_to("main", "extract_done", completion);
+                            let completion = CompletionEvent {
+                                job_id: job_id_clone.clone(),
+                                archive_path: archive_path_str,
+                                status,
+                                stats: None,
+                                error: Some(error_msg),
+                                hints: e.hints(),
+                            };
+                            completion.emit(&app_clone);
 
-                        // Stop processing remaining archives on error
-                        return Err(e);
-                    }
-                    Err(join_err) => {
-                        let err = extractor::ExtractError::Io(std::io::Error::new(
-                            std::io::ErrorKind::Other,
-                            format!("Task join error: {}", join_err),
-                        ));
-                        let error_msg = err.to_string();
-
-                        let completion = CompletionEvent {
-                            job_id: job_id_clone.clone(),
-                            archive_path: archive_path_str,
-                            status: JobStatus::Failed,
-                            stats: None,
-                            error: Some(error_msg),
-                        };
-                        let _ = app_clone.emit_to("main", "extract_done", completion);
+                            // Stop processing remaining archives on error, unless
+                            // this is a batch that reports failures as it goes
+                            if !keep_going || matches!(e, extractor::ExtractError::Cancelled) {
+                                return Err(e);
+                            }
+                            first_error.get_or_insert(e);
+                            if let Some(journal) = &journal {
+                                journal.finish_archive();
+                            }
+                            break;
+                        }
+                        Err(join_err) => {
+                            let err = extractor::ExtractError::Io(std::io::Error::new(
+                                std::io::ErrorKind::Other,
+                                format!("Task join error: {}", join_err),
+                            ));
+                            let error_msg = err.to_string();
+
+                            let completion = CompletionEvent {
+                                job_id: job_id_clone.clone(),
+                                archive_path: archive_path_str,
+                                status: JobStatus::Failed,
+                                stats: None,
+                                error: Some(error_msg),
+                                hints: Vec::new(),
+                            };
+                            completion.emit(&app_clone);
 
-                        return Err(err);
+                            return Err(err);
+                        }
                     }
                 }
             }
+
+            match first_error {
+                Some(e) => Err(e),
+                None => Ok(final_stats.unwrap_or_default()),
+            }
+        }
+        .await;
+
+        // Emit the shared lifecycle completion event
+        let (status, error) = match &result {
+            Ok(_) => (JobStatus::Success, None),
+            Err(extractor::ExtractError::Cancelled) => (JobStatus::Cancelled, None),
+            Err(e) => (JobStatus::Failed, Some(e.to_string())),
+        };
+        JobEvent {
+            status: Some(status),
+            error,
+            ..JobEvent::new(&job_id_for_done, JobKind::Extract, JobStage::Done)
         }
+        .emit(&app_for_done);
+        app_for_done
+            .state::<AppState>()
+            .detach_job(&job_id_for_done);
 
-        Ok(final_stats.unwrap_or_default())
+        result
     });
 
     // Store job handle
     let job_handle = JobHandle {
+        archive_paths,
         cancel_flag,
-        task,
+        task: JobTask::Extract(task),
         password_sender: Some(password_tx),
     };
 
-    state.jobs.lock().insert(job_id.clone(), job_handle);
+    state.jobs.lock().insert(job_id, job_handle);
+}
 
-    Ok(job_id)
+/// Block while all jobs are paused, returning early once `cancel_flag` is set
+fn wait_while_paused(paused: &AtomicBool, cancel_flag: &AtomicBool) {
+    while paused.load(Ordering::Relaxed) && !cancel_flag.load(Ordering::Relaxed) {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
 }
 
 /// Probe archive metadata without extracting
 #[tauri::command]
-pub async fn probe(path: String) -> Result<extractor::ArchiveInfo, String> {
-    let archive_path = PathBuf::from(path);
+pub async fn probe(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, AppState>,
+    path: String,
+    sort: Option<extractor::EntrySort>,
+    password: Option<String>,
+) -> Result<extractor::ArchiveInfo, String> {
+    let archive_path = PathBuf::from(&path);
+
+    // Register the probe as a job so cancel_probe and list_jobs can see it
+    let job_id = Uuid::new_v4().to_string();
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    state.jobs.lock().insert(
+        job_id.clone(),
+        JobHandle {
+            archive_paths: vec![path.clone()],
+            cancel_flag: cancel_flag.clone(),
+            task: JobTask::Probe,
+            password_sender: None,
+        },
+    );
+    state.attach_job(window.label(), &job_id);
+    state.remember_archive(&path);
+
+    JobEvent {
+        archive_path: Some(path.clone()),
+        ..JobEvent::new(&job_id, JobKind::Probe, JobStage::Started)
+    }
+    .emit(&app);
+
+    // Throttle progress events so huge archives don't flood the webview
+    let archive_path_for_progress = path.clone();
+    let job_id_for_progress = job_id.clone();
+    let app_for_progress = app.clone();
+    let last_emit = parking_lot::Mutex::new(std::time::Instant::now());
+    let progress_callback = move |bytes_scanned: u64, entries_found: u64| {
+        let mut last = last_emit.lock();
+        if last.elapsed() < std::time::Duration::from_millis(100) {
+            return;
+        }
+        *last = std::time::Instant::now();
+
+        let event = ProbeProgressEvent {
+            archive_path: archive_path_for_progress.clone(),
+            bytes_scanned,
+            entries_found,
+        };
+        emit_to_job(
+            &app_for_progress,
+            &job_id_for_progress,
+            "probe_progress",
+            event,
+        );
+
+        JobEvent {
+            archive_path: Some(archive_path_for_progress.clone()),
+            processed: Some(entries_found),
+            ..JobEvent::new(&job_id_for_progress, JobKind::Probe, JobStage::Progress)
+        }
+        .emit(&app_for_progress);
+    };
 
     // Run probe in blocking context since it does I/O
-    tokio::task::spawn_blocking(move || {
-        extractor::probe(&archive_path)
+    // Repeated probes of an unchanged archive are served from the cache; probes
+    // with a password (for encrypted entry lists) always read the archive
+    let cancel_flag_for_blocking = cancel_flag.clone();
+    let probe_cache = state.probe_cache.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let mut info = match password {
+            Some(password) => extractor::probe_with_password(
+                &archive_path,
+                &password,
+                &progress_callback,
+                cancel_flag_for_blocking,
+            )?,
+            None => probe_cache.probe_with_progress(
+                &archive_path,
+                &progress_callback,
+                cancel_flag_for_blocking,
+            )?,
+        };
+
+        // Sort here so the webview never has to sort huge entry lists itself
+        if let Some(sort) = sort {
+            extractor::sort_entries(&mut info.entry_list, sort);
+        }
+
+        Ok(info)
     })
-    .await
-    .map_err(|e| format!("Task join error: {}", e))?
-    .map_err(|e| {
-        // Convert ExtractError to user-friendly message
-        match e {
-            extractor::ExtractError::NotFound(_) => {
-                "Archive file not found. Please check the file path.".to_string()
-            }
-            extractor::ExtractError::UnsupportedFormat(fmt) => {
-                format!("Unsupported archive format: {}. Supported formats include ZIP, TAR, 7Z, and RAR.", fmt)
-            }
-            extractor::ExtractError::Corrupted(msg) => {
-                format!("Archive appears to be corrupted: {}", msg)
-            }
-            extractor::ExtractError::Io(io_err) => {
-                format!("Failed to read archive: {}", io_err)
+    .await;
+
+    // Unregister the probe job; it finishes even if the task panicked
+    state.jobs.lock().remove(&job_id);
+
+    let result = result.map_err(|e| format!("Task join error: {}", e));
+    let (status, error) = match &result {
+        Ok(Ok(_)) => (JobStatus::Success, None),
+        Ok(Err(extractor::ExtractError::Cancelled)) => (JobStatus::Cancelled, None),
+        Ok(Err(e)) => (JobStatus::Failed, Some(e.to_string())),
+        Err(e) => (JobStatus::Failed, Some(e.clone())),
+    };
+    JobEvent {
+        archive_path: Some(path.clone()),
+        status: Some(status),
+        error,
+        ..JobEvent::new(&job_id, JobKind::Probe, JobStage::Done)
+    }
+    .emit(&app);
+    state.detach_job(&job_id);
+
+    result?
+        .map_err(|e| {
+            // Convert ExtractError to user-friendly message
+            match e {
+                extractor::ExtractError::NotFound(_) => {
+                    "Archive file not found. Please check the file path.".to_string()
+                }
+                extractor::ExtractError::UnsupportedFormat(fmt) => {
+                    format!("Unsupported archive format: {}. Supported formats include ZIP, TAR, 7Z, and RAR.", fmt)
+                }
+                extractor::ExtractError::Corrupted(msg) => {
+                    format!("Archive appears to be corrupted: {}", msg)
+                }
+                extractor::ExtractError::Io(io_err) => {
+                    format!("Failed to read archive: {}", io_err)
+                }
+                extractor::ExtractError::Cancelled => "Probe cancelled".to_string(),
+                _ => e.to_string(),
             }
-            _ => e.to_string(),
+        })
+}
+
+/// Test an archive by decoding every file without extracting anything
+#[tauri::command]
+pub async fn test_archive(
+    app: AppHandle,
+    window: Window,
+    state: State<'_, AppState>,
+    path: String,
+    password: Option<String>,
+) -> Result<extractor::VerifyReport, String> {
+    // Register the test as a job so cancel_job, list_jobs and the tray see it
+    let job_id = Uuid::new_v4().to_string();
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    state.jobs.lock().insert(
+        job_id.clone(),
+        JobHandle {
+            archive_paths: vec![path.clone()],
+            cancel_flag: cancel_flag.clone(),
+            task: JobTask::Verify,
+            password_sender: None,
+        },
+    );
+    state.attach_job(window.label(), &job_id);
+    state.remember_archive(&path);
+
+    JobEvent {
+        archive_path: Some(path.clone()),
+        ..JobEvent::new(&job_id, JobKind::Verify, JobStage::Queued)
+    }
+    .emit(&app);
+
+    // Report each file as it is checked, stopping once the job is cancelled
+    let job_id_for_progress = job_id.clone();
+    let app_for_progress = app.clone();
+    let archive_for_progress = path.clone();
+    let paused = state.paused.clone();
+    let cancel_flag_for_progress = cancel_flag.clone();
+    let progress_listener = ProgressListener::new(move |progress| {
+        // Hold the test here while all jobs are paused
+        wait_while_paused(&paused, &cancel_flag_for_progress);
+
+        JobEvent {
+            archive_path: Some(archive_for_progress.clone()),
+            current_item: Some(progress.path.clone()),
+            processed: Some(progress.bytes_written),
+            total: progress.total_bytes,
+            ..JobEvent::new(&job_id_for_progress, JobKind::Verify, JobStage::Progress)
         }
+        .emit(&app_for_progress);
+
+        !cancel_flag_for_progress.load(Ordering::Relaxed)
+    });
+    let options = ExtractOptions {
+        password,
+        on_progress: Some(progress_listener),
+        ..ExtractOptions::default()
+    };
+
+    let job_id_for_blocking = job_id.clone();
+    let app_for_blocking = app.clone();
+    let archive_path = PathBuf::from(&path);
+    let result = tokio::task::spawn_blocking(move || {
+        JobEvent::new(&job_id_for_blocking, JobKind::Verify, JobStage::Started)
+            .emit(&app_for_blocking);
+        extractor::verify(&archive_path, &options)
     })
+    .await;
+
+    // Unregister the test job; it finishes even if the task panicked
+    state.jobs.lock().remove(&job_id);
+
+    let result = result.map_err(|e| format!("Task join error: {}", e));
+    let (status, error) = match &result {
+        Ok(Ok(report)) if report.ok => (JobStatus::Success, None),
+        Ok(Ok(_)) => (
+            JobStatus::Failed,
+            Some("The archive failed the test".to_string()),
+        ),
+        Ok(Err(extractor::ExtractError::Cancelled)) => (JobStatus::Cancelled, None),
+        Ok(Err(e)) => (JobStatus::Failed, Some(e.to_string())),
+        Err(e) => (JobStatus::Failed, Some(e.clone())),
+    };
+    JobEvent {
+        archive_path: Some(path),
+        status: Some(status),
+        error,
+        ..JobEvent::new(&job_id, JobKind::Verify, JobStage::Done)
+    }
+    .emit(&app);
+    state.detach_job(&job_id);
+
+    result?.map_err(|e| e.to_string())
+}
+
+/// Check an archive against a checksum file next to it, if there is one
+#[tauri::command]
+pub async fn verify_checksum(path: String) -> Result<Option<extractor::ChecksumCheck>, String> {
+    tokio::task::spawn_blocking(move || extractor::verify_checksum(Path::new(&path)))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+        .map_err(|e| e.to_string())
+}
+
+/// Cancel any in-flight probes of the given archive
+#[tauri::command]
+pub async fn cancel_probe(state: State<'_, AppState>, path: String) -> Result<(), String> {
+    let jobs = state.jobs.lock();
+
+    let mut found = false;
+    for job_handle in jobs.values() {
+        if matches!(job_handle.task, JobTask::Probe) && job_handle.archive_paths.contains(&path) {
+            job_handle
+                .cancel_flag
+                .store(true, std::sync::atomic::Ordering::Relaxed);
+            found = true;
+        }
+    }
+
+    if found {
+        Ok(())
+    } else {
+        Err(format!("No probe running for: {}", path))
+    }
 }
 
-/// Cancel a running extraction job
+/// Cancel a running job of any kind
 #[tauri::command]
 pub async fn cancel_job(state: State<'_, AppState>, job_id: String) -> Result<(), String> {
     // Look up and remove the job handle
@@ -331,8 +1304,11 @@ pub async fn cancel_job(state: State<'_, AppState>, job_id: String) -> Result<()
             .cancel_flag
             .store(true, std::sync::atomic::Ordering::Relaxed);
 
-        // Wait for the task to complete (it should abort soon)
-        let _ = job_handle.task.await;
+        // Wait for a background task to complete (it should abort soon);
+        // probes are awaited by their own command
+        if let JobTask::Extract(task) = job_handle.task {
+            let _ = task.await;
+        }
 
         Ok(())
     } else {
@@ -340,6 +1316,201 @@ pub async fn cancel_job(state: State<'_, AppState>, job_id: String) -> Result<()
     }
 }
 
+/// List active jobs of every kind
+#[tauri::command]
+pub async fn list_jobs(state: State<'_, AppState>) -> Result<Vec<JobSummary>, String> {
+    Ok(active_jobs(&state))
+}
+
+/// Summaries of the active jobs, with the latest progress each reported
+pub(crate) fn active_jobs(state: &AppState) -> Vec<JobSummary> {
+    let mut jobs = state.jobs.lock();
+
+    // Drop jobs whose background task has already finished
+    jobs.retain(|_, job_handle| !job_handle.task.is_finished());
+
+    let job_events = state.job_events.lock();
+    jobs.iter()
+        .map(|(job_id, job_handle)| {
+            let progress = job_events.progress(job_id);
+            JobSummary {
+                job_id: job_id.clone(),
+                kind: job_handle.task.kind(),
+                archive_paths: job_handle.archive_paths.clone(),
+                archive_path: progress.and_then(|event| event.archive_path.clone()),
+                processed: progress.and_then(|event| event.processed),
+                total: progress.and_then(|event| event.total),
+            }
+        })
+        .collect()
+}
+
+/// Archives browsed or extracted lately, most recent first
+#[tauri::command]
+pub async fn list_recent_archives(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    Ok(state.library.lock().recent_archives())
+}
+
+/// Bookmarked archives whose path or tags contain `query`, limited to those
+/// tagged `tag` when given, newest first
+#[tauri::command]
+pub async fn list_bookmarks(
+    state: State<'_, AppState>,
+    query: Option<String>,
+    tag: Option<String>,
+) -> Result<Vec<Bookmark>, String> {
+    Ok(state
+        .library
+        .lock()
+        .search(query.as_deref(), tag.as_deref()))
+}
+
+/// Bookmark an archive with `tags`, or replace the tags of its bookmark
+#[tauri::command]
+pub async fn bookmark_archive(
+    state: State<'_, AppState>,
+    path: String,
+    tags: Vec<String>,
+) -> Result<Bookmark, String> {
+    Ok(state.library.lock().bookmark(&path, &tags))
+}
+
+/// Remove the bookmark of an archive
+#[tauri::command]
+pub async fn remove_bookmark(state: State<'_, AppState>, path: String) -> Result<(), String> {
+    if state.library.lock().remove_bookmark(&path) {
+        Ok(())
+    } else {
+        Err(format!("{} isn't bookmarked", path))
+    }
+}
+
+/// Every tag given to bookmarked archives, sorted
+#[tauri::command]
+pub async fn list_bookmark_tags(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    Ok(state.library.lock().tags())
+}
+
+/// Pause or resume every running extraction
+///
+/// Paused extractions hold still at their next progress report, keeping their
+/// open files, until resumed or cancelled. Every window is told through a
+/// `jobs_paused` event.
+#[tauri::command]
+pub async fn set_jobs_paused(app: AppHandle, paused: bool) -> Result<(), String> {
+    set_paused(&app, paused);
+    Ok(())
+}
+
+/// Whether running extractions are paused
+#[tauri::command]
+pub async fn jobs_paused(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.paused.load(Ordering::Relaxed))
+}
+
+pub(crate) fn set_paused(app: &AppHandle, paused: bool) {
+    app.state::<AppState>()
+        .paused
+        .store(paused, Ordering::Relaxed);
+    let _ = app.emit("jobs_paused", paused);
+    tray::refresh(app);
+}
+
+/// Open `path` in a window of its own, or bring forward the window already
+/// showing it, returning the window's label
+///
+/// The window loads the app with the archive in its `archive` query parameter,
+/// and events of the jobs started from it are sent to it alone.
+#[tauri::command]
+pub async fn open_archive_window(app: AppHandle, path: String) -> Result<String, String> {
+    show_archive_window(&app, path)
+}
+
+pub(crate) fn show_archive_window(app: &AppHandle, path: String) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    if let Some(label) = state.archive_window(&path) {
+        if let Some(window) = app.get_webview_window(&label) {
+            window
+                .set_focus()
+                .map_err(|e| format!("Failed to focus window: {}", e))?;
+            return Ok(label);
+        }
+    }
+
+    let label = format!("archive-{}", Uuid::new_v4().simple());
+    let title = Path::new(&path)
+        .file_name()
+        .map_or_else(|| path.clone(), |name| name.to_string_lossy().to_string());
+    let url = format!("index.html?archive={}", urlencoding::encode(&path));
+    WebviewWindowBuilder::new(app, &label, WebviewUrl::App(url.into()))
+        .title(title)
+        .inner_size(800.0, 600.0)
+        .build()
+        .map_err(|e| format!("Failed to open window: {}", e))?;
+
+    state.sessions.lock().insert(
+        label.clone(),
+        WindowSession {
+            archive_path: Some(path),
+            ..WindowSession::default()
+        },
+    );
+    Ok(label)
+}
+
+/// Replay the key events of a job, so a reloaded or newly opened window can
+/// show its current state
+#[tauri::command]
+pub async fn sync_job_events(
+    state: State<'_, AppState>,
+    job_id: String,
+) -> Result<JobEventLog, String> {
+    state
+        .job_events
+        .lock()
+        .get(&job_id)
+        .ok_or_else(|| format!("Job not found: {}", job_id))
+}
+
+/// Throughput history of an extraction or test job, sampled from its progress
+#[tauri::command]
+pub async fn get_job_metrics(
+    state: State<'_, AppState>,
+    job_id: String,
+) -> Result<JobMetrics, String> {
+    state
+        .job_events
+        .lock()
+        .metrics(&job_id)
+        .ok_or_else(|| format!("Job not found: {}", job_id))
+}
+
+/// Runtime limits that affect extraction, for troubleshooting
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../src/lib/bindings/")]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostics {
+    /// Soft limit on open files for the process (`ulimit -n`), if any
+    #[ts(optional, type = "number")]
+    pub open_file_limit: Option<u64>,
+    /// File handles that concurrent extractions may hold in total
+    #[ts(type = "number")]
+    pub fd_budget: usize,
+    /// File handles currently held by running extractions
+    #[ts(type = "number")]
+    pub fds_in_use: usize,
+}
+
+/// Report runtime limits such as the active file handle budget
+#[tauri::command]
+pub async fn get_diagnostics(state: State<'_, AppState>) -> Result<Diagnostics, String> {
+    Ok(Diagnostics {
+        open_file_limit: extractor::open_file_limit(),
+        fd_budget: state.fd_budget.capacity(),
+        fds_in_use: state.fd_budget.in_use(),
+    })
+}
+
 /// Provide password for a password-protected archive
 #[tauri::command]
 pub async fn provide_password(
@@ -363,6 +1534,7 @@ pub async fn provide_password(
             .send(password)
             .await
             .map_err(|_| "Failed to send password to extraction task".to_string())?;
+        state.job_events.lock().password_provided(&job_id);
         Ok(())
     } else {
         Err(format!("Job not found: {}", job_id))
@@ -444,7 +1616,7 @@ pub async fn list_directory(path: String) -> Result<Vec<FileSystemEntry>, String
 
             // Check if file is an archive based on extension
             let is_archive = if !is_directory {
-                is_archive_file(&entry_path)
+                extractor::is_supported_archive(&entry_path)
             } else {
                 false
             };
@@ -582,27 +1754,27 @@ pub async fn get_unique_output_path(archive_path: String) -> Result<String, Stri
         .parent()
         .ok_or_else(|| "Failed to get parent directory".to_string())?;
 
-    // Get the archive filename without extension
-    let base_name = archive
-        .file_stem()
-        .ok_or_else(|| "Failed to get archive filename".to_string())?
-        .to_string_lossy()
-        .to_string();
-
-    // Start with the base name
-    let mut output_path = parent_dir.join(&base_name);
-    let mut counter = 1;
-
-    // Keep incrementing until we find a unique name
-    while output_path.exists() {
-        let new_name = format!("{} ({})", base_name, counter);
-        output_path = parent_dir.join(new_name);
-        counter += 1;
+    // Name the folder after the archive, numbering it when that name is taken
+    if archive.file_stem().is_none() {
+        return Err("Failed to get archive filename".to_string());
     }
+    let output_path = extractor::unique_output_dir(parent_dir, &archive);
 
     Ok(output_path.to_string_lossy().to_string())
 }
 
+/// What opening an archive from Finder does
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/lib/bindings/")]
+#[serde(rename_all = "lowercase")]
+pub enum OpenAction {
+    /// Show the archive in the browser view
+    #[default]
+    Browse,
+    /// Extract it right away with the saved settings
+    Extract,
+}
+
 /// Settings structure for persistence
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[ts(export, export_to = "../../src/lib/bindings/")]
@@ -616,6 +1788,40 @@ pub struct SettingsData {
     pub allow_symlinks: bool,
     pub allow_hardlinks: bool,
     pub has_seen_permission_dialog: bool,
+    pub propagate_quarantine: bool,
+    /// Action for archives opened from Finder, by lowercase extension; archives
+    /// with an extension that isn't listed are browsed
+    #[serde(default)]
+    pub open_actions: HashMap<String, OpenAction>,
+    /// Folder for temporary files; unset puts them next to the destination
+    #[serde(default)]
+    #[ts(optional)]
+    pub temp_dir: Option<String>,
+}
+
+impl SettingsData {
+    /// What opening `path` from Finder should do
+    pub fn open_action(&self, path: &Path) -> OpenAction {
+        path.extension()
+            .and_then(|ext| self.open_actions.get(&ext.to_string_lossy().to_lowercase()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Extraction options matching these settings, as the frontend would send them
+    pub fn extract_options(&self) -> ExtractOptionsDTO {
+        ExtractOptionsDTO {
+            overwrite: self.overwrite_mode.clone(),
+            size_limit_bytes: (self.size_limit_gb > 0.0)
+                .then_some((self.size_limit_gb * 1024.0 * 1024.0 * 1024.0) as u64),
+            strip_components: self.strip_components,
+            allow_symlinks: self.allow_symlinks,
+            allow_hardlinks: self.allow_hardlinks,
+            propagate_quarantine: Some(self.propagate_quarantine),
+            temp_dir: self.temp_dir.clone(),
+            ..Default::default()
+        }
+    }
 }
 
 impl Default for SettingsData {
@@ -627,6 +1833,9 @@ impl Default for SettingsData {
             allow_symlinks: false,
             allow_hardlinks: false,
             has_seen_permission_dialog: false,
+            propagate_quarantine: true,
+            open_actions: HashMap::new(),
+            temp_dir: None,
         }
     }
 }
@@ -703,9 +1912,16 @@ pub async fn load_settings(app: AppHandle) -> Result<SettingsData, String> {
                             serde_json::Value::Bool(false),
                         );
                     }
+                    // Settings saved before quarantine tagging keep it on
+                    if !obj.contains_key("propagateQuarantine") {
+                        obj.insert(
+                            "propagateQuarantine".to_string(),
+                            serde_json::Value::Bool(true),
+                        );
+                    }
                 }
 
-                // Try to deserialize again with the added field
+                // Try to deserialize again with the added fields
                 serde_json::from_value(value).unwrap_or_else(|_| {
                     eprintln!("Still failed to parse. Using defaults.");
                     SettingsData::default()
@@ -719,55 +1935,7 @@ pub async fn load_settings(app: AppHandle) -> Result<SettingsData, String> {
     Ok(settings)
 }
 
-/// Helper function to check if a file is an archive based on extension
-fn is_archive_file(path: &Path) -> bool {
-    const ARCHIVE_EXTENSIONS: &[&str] = &[
-        "zip", "7z", "rar", "tar", "gz", "bz2", "xz", "tgz", "tbz2", "txz",
-    ];
-
-    let filename = path
-        .file_name()
-        .and_then(|f| f.to_str())
-        .unwrap_or("")
-        .to_lowercase();
-
-    // Check for multi-part archives
-    // RAR: .part1.rar, .part01.rar, .r00, .r01, etc.
-    if filename.contains(".part") && filename.ends_with(".rar") {
-        return true;
-    }
-
-    // 7z: .7z.001, .7z.002, etc.
-    if filename.contains(".7z.") {
-        if let Some(ext) = path.extension() {
-            if ext.to_string_lossy().chars().all(|c| c.is_ascii_digit()) {
-                return true;
-            }
-        }
-    }
-
-    // ZIP: .zip.001, .zip.002, etc.
-    if filename.contains(".zip.") {
-        if let Some(ext) = path.extension() {
-            if ext.to_string_lossy().chars().all(|c| c.is_ascii_digit()) {
-                return true;
-            }
-        }
-    }
-
-    // Check standard extensions
-    if let Some(ext) = path.extension() {
-        let ext_lower = ext.to_string_lossy().to_lowercase();
-
-        // Check for .rXX extensions (RAR multi-part)
-        if ext_lower.starts_with('r') && ext_lower.len() >= 2 {
-            if ext_lower[1..].chars().all(|c| c.is_ascii_digit()) {
-                return true;
-            }
-        }
-
-        ARCHIVE_EXTENSIONS.contains(&ext_lower.as_str())
-    } else {
-        false
-    }
+/// Whether an input path is actually an http(s) URL to extract from
+fn is_url(input: &str) -> bool {
+    input.starts_with("http://") || input.starts_with("https://")
 }
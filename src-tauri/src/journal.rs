@@ -0,0 +1,287 @@
+//! Journals of running extraction jobs, for recovering after a crash.
+//!
+//! Every extraction job keeps a small JSON file under `jobs/` in the app data
+//! directory saying what it extracts, where to, with which options and how far
+//! it got. The file goes away with the job, however the job ends, so a journal
+//! found at startup belongs to a job from a session that crashed or was killed.
+//! Those are reported to the frontend in a `crashed_jobs` event, and the user
+//! picks for each whether to resume it, clean up after it or forget it.
+
+use crate::commands::{Destination, ExtractOptionsDTO};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use ts_rs::TS;
+
+/// Progress is saved at most this often; the start and end of each archive are
+/// always saved.
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Everything needed to resume or clean up after a job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct JobJournal {
+    pub job_id: String,
+    pub archives: Vec<(String, Destination)>,
+    /// Options the job runs with, without the password
+    pub options: ExtractOptionsDTO,
+    pub keep_going: bool,
+    /// How many archives at the front of `archives` are done with
+    pub completed: usize,
+    /// The archive being extracted, if any
+    pub current: Option<Checkpoint>,
+}
+
+/// How far the job got with the archive it was extracting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Checkpoint {
+    pub output_dir: PathBuf,
+    /// Whether the job created `output_dir`, so it can be removed on cleanup
+    pub created_output_dir: bool,
+    pub current_file: Option<String>,
+    pub bytes_written: u64,
+}
+
+/// The journal of a running job, removed from disk when dropped
+pub(crate) struct Journal {
+    path: PathBuf,
+    inner: Mutex<(JobJournal, Instant)>,
+}
+
+impl Journal {
+    /// Start the journal of a job. Journaling is best effort: when the journal
+    /// can't be written the job runs without one.
+    pub fn create(
+        app: &AppHandle,
+        job_id: &str,
+        archives: &[(String, Destination)],
+        options: &ExtractOptionsDTO,
+        keep_going: bool,
+    ) -> Option<Journal> {
+        let journal = JobJournal {
+            job_id: job_id.to_string(),
+            archives: archives.to_vec(),
+            options: ExtractOptionsDTO {
+                password: None,
+                ..options.clone()
+            },
+            keep_going,
+            completed: 0,
+            current: None,
+        };
+
+        let path = journal_path(app, job_id)?;
+        if let Err(e) = write_journal(&path, &journal) {
+            eprintln!("Failed to write job journal {}: {}", path.display(), e);
+            return None;
+        }
+        Some(Journal {
+            path,
+            inner: Mutex::new((journal, Instant::now())),
+        })
+    }
+
+    /// Record that extraction of the next archive into `output_dir` begins.
+    pub fn start_archive(&self, output_dir: &Path) {
+        let current = Checkpoint {
+            output_dir: output_dir.to_path_buf(),
+            created_output_dir: !output_dir.exists(),
+            current_file: None,
+            bytes_written: 0,
+        };
+        self.update(true, |journal| journal.current = Some(current));
+    }
+
+    /// Record progress on the current archive, saving it if the last save was a
+    /// while ago.
+    pub fn progress(&self, current_file: &str, bytes_written: u64) {
+        self.update(false, |journal| {
+            if let Some(current) = journal.current.as_mut() {
+                current.current_file = Some(current_file.to_string());
+                current.bytes_written = bytes_written;
+            }
+        });
+    }
+
+    /// Record that the current archive is done with, whether or not it
+    /// extracted.
+    pub fn finish_archive(&self) {
+        self.update(true, |journal| {
+            journal.completed += 1;
+            journal.current = None;
+        });
+    }
+
+    fn update(&self, force: bool, change: impl FnOnce(&mut JobJournal)) {
+        let mut inner = self.inner.lock();
+        let (journal, last_write) = &mut *inner;
+        change(journal);
+
+        if force || last_write.elapsed() >= CHECKPOINT_INTERVAL {
+            if let Err(e) = write_journal(&self.path, journal) {
+                eprintln!(
+                    "Failed to update job journal {}: {}",
+                    self.path.display(),
+                    e
+                );
+            }
+            *last_write = Instant::now();
+        }
+    }
+}
+
+impl Drop for Journal {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// A job from an earlier session that never finished
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../src/lib/bindings/")]
+#[serde(rename_all = "camelCase")]
+pub struct CrashedJob {
+    pub job_id: String,
+    /// Archives the job didn't get to finish, the interrupted one first
+    pub archive_paths: Vec<String>,
+    /// Where the interrupted archive was being extracted to
+    #[ts(optional)]
+    pub output_dir: Option<String>,
+    /// Last file the interrupted archive was known to be writing
+    #[ts(optional)]
+    pub current_file: Option<String>,
+    #[ts(type = "number")]
+    pub bytes_written: u64,
+}
+
+impl From<&JobJournal> for CrashedJob {
+    fn from(journal: &JobJournal) -> Self {
+        let current = journal.current.as_ref();
+        CrashedJob {
+            job_id: journal.job_id.clone(),
+            archive_paths: journal
+                .archives
+                .iter()
+                .skip(journal.completed)
+                .map(|(path, _)| path.clone())
+                .collect(),
+            output_dir: current.map(|c| c.output_dir.to_string_lossy().to_string()),
+            current_file: current.and_then(|c| c.current_file.clone()),
+            bytes_written: current.map_or(0, |c| c.bytes_written),
+        }
+    }
+}
+
+/// What to do about a crashed job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, TS)]
+#[ts(export, export_to = "../../src/lib/bindings/")]
+#[serde(rename_all = "lowercase")]
+pub enum CrashedJobAction {
+    /// Extract the archives it didn't finish, keeping files already written
+    Resume,
+    /// Remove what the interrupted archive left behind
+    Cleanup,
+    /// Forget about the job and leave its files as they are
+    Dismiss,
+}
+
+/// Journals left behind by jobs of earlier sessions. Unreadable journals are
+/// skipped.
+pub(crate) fn crashed_jobs(app: &AppHandle) -> Vec<JobJournal> {
+    let Some(dir) = journal_dir(app) else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+
+    let mut journals: Vec<JobJournal> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| {
+            let json = fs::read(entry.path()).ok()?;
+            serde_json::from_slice(&json).ok()
+        })
+        .collect();
+    journals.sort_by(|a, b| a.job_id.cmp(&b.job_id));
+    journals
+}
+
+/// Read the journal of job `job_id`.
+pub(crate) fn read(app: &AppHandle, job_id: &str) -> Result<JobJournal, String> {
+    let missing = || format!("No crashed job with id {}", job_id);
+    let path = journal_path(app, job_id).ok_or_else(missing)?;
+    let json = fs::read(&path).map_err(|_| missing())?;
+    serde_json::from_slice(&json).map_err(|e| format!("Failed to read job journal: {}", e))
+}
+
+/// Delete the journal of job `job_id`.
+pub(crate) fn remove(app: &AppHandle, job_id: &str) -> io::Result<()> {
+    match journal_path(app, job_id) {
+        Some(path) => fs::remove_file(path),
+        None => Ok(()),
+    }
+}
+
+/// Remove what the interrupted archive of `journal` left behind: its output
+/// folder if the job created it, otherwise just the files it was partway
+/// through writing.
+pub(crate) fn clean_up(journal: &JobJournal) -> io::Result<()> {
+    let Some(current) = &journal.current else {
+        return Ok(());
+    };
+    if !current.output_dir.exists() {
+        return Ok(());
+    }
+    if current.created_output_dir {
+        return fs::remove_dir_all(&current.output_dir);
+    }
+    remove_partial_files(&current.output_dir)
+}
+
+/// Delete the partially written files under `dir`.
+pub(crate) fn remove_partial_files(dir: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            remove_partial_files(&entry.path())?;
+        } else if file_type.is_file() && extractor::is_partial_file(&entry.path()) {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+fn journal_dir(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_data_dir().ok().map(|dir| dir.join("jobs"))
+}
+
+fn journal_path(app: &AppHandle, job_id: &str) -> Option<PathBuf> {
+    // Job ids come from the frontend in resolve_crashed_job
+    if job_id.is_empty()
+        || !job_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return None;
+    }
+    journal_dir(app).map(|dir| dir.join(format!("{}.json", job_id)))
+}
+
+/// Replace the journal at `path` in one step, so a crash mid-write never leaves
+/// it half written.
+fn write_journal(path: &Path, journal: &JobJournal) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_vec_pretty(journal)?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, path)
+}
@@ -1,5 +1,9 @@
 pub mod commands;
+mod journal;
+mod library;
+mod open_policy;
 mod state;
+mod tray;
 
 use state::AppState;
 use std::path::PathBuf;
@@ -59,17 +63,35 @@ pub fn run() {
                 if let Some(window) = app.get_webview_window("main") {
                     // Bring window to front
                     let _ = window.set_focus();
-                    // Emit the files_opened event
-                    let _ = window.emit("files_opened", archive_paths);
                 }
+                // Browse or extract them, as the settings say
+                open_policy::route_opened_files(app, archive_paths);
             }
         }))
         .manage(AppState::new())
         .manage(PendingOpens::default())
         .invoke_handler(tauri::generate_handler![
             commands::extract,
+            commands::extract_folder,
+            commands::resolve_crashed_job,
+            commands::read_extraction_journal,
             commands::probe,
+            commands::cancel_probe,
+            commands::test_archive,
+            commands::verify_checksum,
             commands::cancel_job,
+            commands::list_jobs,
+            commands::list_recent_archives,
+            commands::list_bookmarks,
+            commands::bookmark_archive,
+            commands::remove_bookmark,
+            commands::list_bookmark_tags,
+            commands::set_jobs_paused,
+            commands::jobs_paused,
+            commands::sync_job_events,
+            commands::get_job_metrics,
+            commands::open_archive_window,
+            commands::get_diagnostics,
             commands::provide_password,
             commands::list_directory,
             commands::get_home_directory,
@@ -82,6 +104,16 @@ pub fn run() {
             commands::open_system_settings,
         ])
         .setup(|app| {
+            // Bookmarks and recent archives of earlier sessions
+            if let Ok(app_data_dir) = app.path().app_data_dir() {
+                *app.state::<AppState>().library.lock() = library::Library::load(&app_data_dir);
+            }
+
+            // Menu-bar icon for following extractions with the window closed
+            if let Err(e) = tray::create(app.handle()) {
+                eprintln!("Failed to create tray icon: {}", e);
+            }
+
             // Flush any pending file opens that were buffered before window was ready
             let pending_state = app.state::<PendingOpens>();
             let pending = pending_state.take_all();
@@ -110,14 +142,27 @@ pub fn run() {
                     let app_handle_clone = app.handle().clone();
                     std::thread::spawn(move || {
                         std::thread::sleep(std::time::Duration::from_millis(500));
-                        if let Some(window) = app_handle_clone.get_webview_window("main") {
-                            eprintln!("Emitting files_opened event for pending files");
-                            let _ = window.emit("files_opened", archive_paths);
-                        }
+                        eprintln!("Routing pending file opens");
+                        open_policy::route_opened_files(&app_handle_clone, archive_paths);
                     });
                 }
             }
 
+            // Offer to resume or clean up after jobs that were still running when
+            // an earlier session crashed
+            let crashed: Vec<journal::CrashedJob> = journal::crashed_jobs(app.handle())
+                .iter()
+                .map(journal::CrashedJob::from)
+                .collect();
+            if !crashed.is_empty() {
+                let app_handle_clone = app.handle().clone();
+                std::thread::spawn(move || {
+                    std::thread::sleep(std::time::Duration::from_millis(500));
+                    eprintln!("Reporting {} crashed jobs", crashed.len());
+                    let _ = app_handle_clone.emit_to("main", "crashed_jobs", crashed);
+                });
+            }
+
             // Listen for deep-link events (file associations on macOS)
             let app_handle = app.handle().clone();
 
@@ -178,10 +223,8 @@ pub fn run() {
                             .collect();
 
                         if !archive_paths.is_empty() {
-                            eprintln!("Emitting files_opened event for: {:?}", archive_paths);
-                            if let Some(window) = handle_clone.get_webview_window("main") {
-                                let _ = window.emit("files_opened", archive_paths);
-                            }
+                            eprintln!("Routing opened files: {:?}", archive_paths);
+                            open_policy::route_opened_files(&handle_clone, archive_paths);
                         }
                     }
                 });
@@ -215,9 +258,25 @@ pub fn run() {
 
                 if !archive_paths.is_empty() {
                     eprintln!("Emitting files_opened event for: {:?}", archive_paths);
-                    let _ = window.emit("files_opened", archive_paths);
+                    let _ = window.emit_to(window.label(), "files_opened", archive_paths);
+                }
+            }
+
+            // Keep running extractions going from the tray when the main window
+            // is closed; the tray's "Show Unarchiver" brings it back
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                let extracting = commands::active_jobs(&window.state::<AppState>())
+                    .iter()
+                    .any(|job| job.kind == commands::JobKind::Extract);
+                if window.label() == state::MAIN_WINDOW && extracting {
+                    api.prevent_close();
+                    let _ = window.hide();
                 }
             }
+
+            if let tauri::WindowEvent::Destroyed = event {
+                window.state::<AppState>().close_window(window.label());
+            }
         })
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
@@ -257,9 +316,9 @@ pub fn run() {
                     if !archive_paths.is_empty() {
                         eprintln!("Found archives: {:?}", archive_paths);
 
-                        if let Some(window) = app_handle.get_webview_window("main") {
-                            eprintln!("Emitting to existing window");
-                            let _ = window.emit("files_opened", &archive_paths);
+                        if app_handle.get_webview_window("main").is_some() {
+                            eprintln!("Routing to existing window");
+                            open_policy::route_opened_files(app_handle, archive_paths);
                         } else {
                             eprintln!("Window not ready, buffering paths");
                             // Buffer if window not ready yet
@@ -0,0 +1,170 @@
+//! Archives the user bookmarked, with their tags, and recently used archives.
+//!
+//! The library is kept in `library.json` in the app data directory, next to the
+//! settings, and saved after every change. Saving is best effort: when the file
+//! can't be written the library still works for the session.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use ts_rs::TS;
+
+/// Name of the library file in the app data directory
+const LIBRARY_FILE: &str = "library.json";
+
+/// Number of archives listed as recent
+const RECENT_ARCHIVES_KEPT: usize = 10;
+
+/// A bookmarked archive
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/lib/bindings/")]
+#[serde(rename_all = "camelCase")]
+pub struct Bookmark {
+    pub path: String,
+    /// Lowercase tags, sorted
+    pub tags: Vec<String>,
+    /// When the archive was bookmarked, in seconds since the Unix epoch
+    #[ts(type = "number")]
+    pub added: u64,
+}
+
+impl Bookmark {
+    /// Whether the bookmark's path or one of its tags contains `query`, which
+    /// must be lowercase
+    fn matches(&self, query: &str) -> bool {
+        self.path.to_lowercase().contains(query) || self.tags.iter().any(|t| t.contains(query))
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Library {
+    /// Bookmarks, oldest first
+    bookmarks: Vec<Bookmark>,
+    /// Archives browsed or extracted lately, most recent first
+    recent_archives: VecDeque<String>,
+    /// Where the library is saved, once loaded
+    #[serde(skip)]
+    file: Option<PathBuf>,
+}
+
+impl Library {
+    /// Load the library saved in `app_data_dir`, starting an empty one if there
+    /// is none or it can't be read
+    pub fn load(app_data_dir: &Path) -> Self {
+        let file = app_data_dir.join(LIBRARY_FILE);
+        let library = match fs::read_to_string(&file) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("Failed to parse library file: {}", e);
+                Library::default()
+            }),
+            Err(_) => Library::default(),
+        };
+        Library {
+            file: Some(file),
+            ..library
+        }
+    }
+
+    fn save(&self) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        let saved = file
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| {
+                let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+                fs::write(file, json)
+            });
+        if let Err(e) = saved {
+            eprintln!("Failed to save library file: {}", e);
+        }
+    }
+
+    /// Move `archive_path` to the front of the recent archives, forgetting the
+    /// oldest once too many are listed
+    pub fn remember(&mut self, archive_path: &str) {
+        if self.recent_archives.front().map(String::as_str) == Some(archive_path) {
+            return;
+        }
+        self.recent_archives.retain(|path| path != archive_path);
+        self.recent_archives.push_front(archive_path.to_string());
+        self.recent_archives.truncate(RECENT_ARCHIVES_KEPT);
+        self.save();
+    }
+
+    /// Archives browsed or extracted lately, most recent first
+    pub fn recent_archives(&self) -> Vec<String> {
+        self.recent_archives.iter().cloned().collect()
+    }
+
+    /// Bookmark `path` with `tags`, or replace the tags of its bookmark
+    pub fn bookmark(&mut self, path: &str, tags: &[String]) -> Bookmark {
+        let tags = normalize_tags(tags);
+        let bookmark = match self.bookmarks.iter_mut().find(|b| b.path == path) {
+            Some(bookmark) => {
+                bookmark.tags = tags;
+                bookmark.clone()
+            }
+            None => {
+                let bookmark = Bookmark {
+                    path: path.to_string(),
+                    tags,
+                    added: SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |d| d.as_secs()),
+                };
+                self.bookmarks.push(bookmark.clone());
+                bookmark
+            }
+        };
+        self.save();
+        bookmark
+    }
+
+    /// Remove the bookmark of `path`, returning whether there was one
+    pub fn remove_bookmark(&mut self, path: &str) -> bool {
+        let before = self.bookmarks.len();
+        self.bookmarks.retain(|b| b.path != path);
+        let removed = self.bookmarks.len() < before;
+        if removed {
+            self.save();
+        }
+        removed
+    }
+
+    /// Bookmarks whose path or tags contain `query` (ignoring case) and that
+    /// carry `tag`, newest first
+    pub fn search(&self, query: Option<&str>, tag: Option<&str>) -> Vec<Bookmark> {
+        let query = query.map(|q| q.trim().to_lowercase()).unwrap_or_default();
+        let tag = tag.map(|t| t.trim().to_lowercase());
+        self.bookmarks
+            .iter()
+            .rev()
+            .filter(|b| b.matches(&query))
+            .filter(|b| tag.as_ref().is_none_or(|tag| b.tags.contains(tag)))
+            .cloned()
+            .collect()
+    }
+
+    /// Every tag in use, sorted
+    pub fn tags(&self) -> Vec<String> {
+        let tags: Vec<String> = self.bookmarks.iter().flat_map(|b| b.tags.clone()).collect();
+        normalize_tags(&tags)
+    }
+}
+
+/// Trim and lowercase `tags`, dropping empty and duplicate ones, sorted
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut tags: Vec<String> = tags
+        .iter()
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
@@ -0,0 +1,54 @@
+//! Deciding what happens to archives opened from Finder.
+//!
+//! Opening an archive normally shows it in the browser view, but the settings
+//! can ask for some formats to be extracted right away instead. Every way a file
+//! reaches the app (file associations, a second launch, deep links) goes through
+//! [`route_opened_files`], so that choice is made in one place.
+
+use crate::commands::{self, OpenAction};
+use crate::state::{AppState, MAIN_WINDOW};
+use std::path::Path;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Extract the archives whose extension is set to extract on open, and send the
+/// rest to the frontend in a `files_opened` event to browse. Archives that
+/// already have a window of their own are sent to it, the others to the main
+/// window.
+pub fn route_opened_files(app: &AppHandle, paths: Vec<String>) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let settings = commands::load_settings(app.clone())
+            .await
+            .unwrap_or_default();
+
+        let (extract, browse): (Vec<String>, Vec<String>) = paths
+            .into_iter()
+            .partition(|path| settings.open_action(Path::new(path)) == OpenAction::Extract);
+
+        for archive_path in extract {
+            eprintln!("Extracting opened archive: {}", archive_path);
+            commands::extract_opened(&app, &settings, archive_path);
+        }
+
+        let state = app.state::<AppState>();
+        let mut by_window: Vec<(String, Vec<String>)> = Vec::new();
+        for archive_path in browse {
+            let label = state
+                .archive_window(&archive_path)
+                .unwrap_or_else(|| MAIN_WINDOW.to_string());
+            match by_window.iter_mut().find(|(window, _)| *window == label) {
+                Some((_, paths)) => paths.push(archive_path),
+                None => by_window.push((label, vec![archive_path])),
+            }
+        }
+
+        for (label, paths) in by_window {
+            if let Some(window) = app.get_webview_window(&label) {
+                if label != MAIN_WINDOW {
+                    let _ = window.set_focus();
+                }
+                let _ = window.emit_to(label.as_str(), "files_opened", paths);
+            }
+        }
+    });
+}
@@ -1,31 +1,324 @@
+use crate::commands::{
+    CompletionEvent, JobEvent, JobEventLog, JobKind, JobMetrics, JobStage, PasswordRequiredEvent,
+    ThroughputSample,
+};
+use crate::library::Library;
 use parking_lot::Mutex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
 
-/// Handle for a running extraction job
+/// Kind-specific part of a running job
+pub enum JobTask {
+    /// Extraction of one or more archives, running as a background task
+    Extract(JoinHandle<Result<extractor::ExtractStats, extractor::ExtractError>>),
+    /// Archive probe; the invoking command awaits the work itself
+    Probe,
+    /// Archive test; the invoking command awaits the work itself
+    Verify,
+}
+
+impl JobTask {
+    /// The kind of job this task belongs to
+    pub fn kind(&self) -> JobKind {
+        match self {
+            JobTask::Extract(_) => JobKind::Extract,
+            JobTask::Probe => JobKind::Probe,
+            JobTask::Verify => JobKind::Verify,
+        }
+    }
+
+    /// Whether the underlying work has finished
+    pub fn is_finished(&self) -> bool {
+        match self {
+            JobTask::Extract(handle) => handle.is_finished(),
+            JobTask::Probe | JobTask::Verify => false,
+        }
+    }
+}
+
+/// Handle for a running job
 pub struct JobHandle {
+    /// Archives the job operates on
+    pub archive_paths: Vec<String>,
     /// Flag to signal cancellation
     pub cancel_flag: Arc<AtomicBool>,
-    /// The async task handle
-    pub task: JoinHandle<Result<extractor::ExtractStats, extractor::ExtractError>>,
+    /// The kind-specific task
+    pub task: JobTask,
     /// Optional sender for password retry
     pub password_sender: Option<mpsc::Sender<String>>,
 }
 
-/// Application state managing all active extraction jobs
+/// Label of the window the app starts with
+pub const MAIN_WINDOW: &str = "main";
+
+/// State of one window, for routing events to it
 #[derive(Default)]
+pub struct WindowSession {
+    /// Archive the window was opened for, if it was
+    pub archive_path: Option<String>,
+    /// Jobs started from the window that haven't finished yet
+    pub jobs: HashSet<String>,
+}
+
+/// Application state managing all active jobs
 pub struct AppState {
     /// Map of job_id to JobHandle
     pub jobs: Arc<Mutex<HashMap<String, JobHandle>>>,
+    /// Probe results of recently viewed archives
+    pub probe_cache: Arc<extractor::ProbeCache>,
+    /// File handles shared by concurrently running extractions
+    pub fd_budget: Arc<extractor::FdBudget>,
+    /// Key events of running and recently finished jobs
+    pub job_events: Arc<Mutex<JobEvents>>,
+    /// Map of window label to what the window shows
+    pub sessions: Arc<Mutex<HashMap<String, WindowSession>>>,
+    /// Set while the user has paused all extractions
+    pub paused: Arc<AtomicBool>,
+    /// Bookmarked and recently used archives, loaded once the app starts
+    pub library: Arc<Mutex<Library>>,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AppState {
     pub fn new() -> Self {
         Self {
             jobs: Arc::new(Mutex::new(HashMap::new())),
+            probe_cache: Arc::new(extractor::ProbeCache::default()),
+            fd_budget: Arc::new(fd_budget_from_env()),
+            job_events: Arc::new(Mutex::new(JobEvents::default())),
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            paused: Arc::new(AtomicBool::new(false)),
+            library: Arc::new(Mutex::new(Library::default())),
+        }
+    }
+
+    /// Move `archive_path` to the front of the recent archives
+    pub fn remember_archive(&self, archive_path: &str) {
+        self.library.lock().remember(archive_path);
+    }
+
+    /// Record that `job_id` was started from the window labelled `window`
+    pub fn attach_job(&self, window: &str, job_id: &str) {
+        self.sessions
+            .lock()
+            .entry(window.to_string())
+            .or_default()
+            .jobs
+            .insert(job_id.to_string());
+    }
+
+    /// Forget which window started `job_id`, once the job has finished
+    pub fn detach_job(&self, job_id: &str) {
+        for session in self.sessions.lock().values_mut() {
+            session.jobs.remove(job_id);
+        }
+    }
+
+    /// Label of the window that started `job_id`, or of the main window when
+    /// the backend started it or its window has closed
+    pub fn job_window(&self, job_id: &str) -> String {
+        self.sessions
+            .lock()
+            .iter()
+            .find(|(_, session)| session.jobs.contains(job_id))
+            .map_or_else(|| MAIN_WINDOW.to_string(), |(label, _)| label.clone())
+    }
+
+    /// Label of the window opened for `archive_path`, if one is open
+    pub fn archive_window(&self, archive_path: &str) -> Option<String> {
+        self.sessions
+            .lock()
+            .iter()
+            .find(|(_, session)| session.archive_path.as_deref() == Some(archive_path))
+            .map(|(label, _)| label.clone())
+    }
+
+    /// Forget the session of a window that was closed
+    pub fn close_window(&self, window: &str) {
+        self.sessions.lock().remove(window);
+    }
+}
+
+/// Number of finished jobs whose events are kept
+const FINISHED_JOBS_KEPT: usize = 32;
+
+/// Shortest time between two throughput samples
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Number of throughput samples kept per job, a minute's worth
+const SAMPLES_KEPT: usize = 120;
+
+/// Throughput of an extraction or test job, sampled from its progress events
+struct Throughput {
+    started: Instant,
+    /// When the latest sample was taken
+    sampled: Instant,
+    /// Bytes written by the job when the latest sample was taken
+    sampled_bytes: u64,
+    /// Bytes written or tested by the job so far, across its archives
+    bytes_written: u64,
+    /// Bytes written to the archive being extracted, as last reported
+    archive_bytes: u64,
+    /// Recent samples, oldest first
+    samples: VecDeque<ThroughputSample>,
+}
+
+impl Throughput {
+    fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            sampled: now,
+            sampled_bytes: 0,
+            bytes_written: 0,
+            archive_bytes: 0,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Count the bytes written to the current archive, `processed`, taking a
+    /// sample if enough time has passed since the last one
+    fn record(&mut self, processed: u64) {
+        // Progress starts over with each archive of the job
+        let written = if processed < self.archive_bytes {
+            processed
+        } else {
+            processed - self.archive_bytes
+        };
+        self.archive_bytes = processed;
+        self.bytes_written += written;
+
+        let elapsed = self.sampled.elapsed();
+        if elapsed < SAMPLE_INTERVAL {
+            return;
+        }
+        let bytes = self.bytes_written - self.sampled_bytes;
+        self.samples.push_back(ThroughputSample {
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            bytes_per_sec: (bytes as f64 / elapsed.as_secs_f64()) as u64,
+        });
+        if self.samples.len() > SAMPLES_KEPT {
+            self.samples.pop_front();
+        }
+        self.sampled = Instant::now();
+        self.sampled_bytes = self.bytes_written;
+    }
+
+    fn metrics(&self) -> JobMetrics {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        JobMetrics {
+            bytes_written: self.bytes_written,
+            average_bytes_per_sec: if elapsed > 0.0 {
+                (self.bytes_written as f64 / elapsed) as u64
+            } else {
+                0
+            },
+            samples: self.samples.iter().cloned().collect(),
+        }
+    }
+}
+
+/// Key events of each job, kept so that windows connecting late can catch up
+#[derive(Default)]
+pub struct JobEvents {
+    logs: HashMap<String, JobEventLog>,
+    /// Throughput of extraction and test jobs
+    throughput: HashMap<String, Throughput>,
+    /// Finished jobs, oldest first
+    finished: VecDeque<String>,
+}
+
+impl JobEvents {
+    /// Record a lifecycle event, forgetting the oldest finished jobs once
+    /// too many have finished
+    pub fn record(&mut self, event: &JobEvent) {
+        let log = self.logs.entry(event.job_id.clone()).or_default();
+        match event.stage {
+            JobStage::Queued | JobStage::Started => {
+                log.started = Some(event.clone());
+                if matches!(event.kind, JobKind::Extract | JobKind::Verify) {
+                    self.throughput
+                        .entry(event.job_id.clone())
+                        .or_insert_with(Throughput::new);
+                }
+            }
+            JobStage::Progress => {
+                log.progress = Some(event.clone());
+                log.password_required = None;
+                if let (Some(throughput), Some(processed)) =
+                    (self.throughput.get_mut(&event.job_id), event.processed)
+                {
+                    throughput.record(processed);
+                }
+            }
+            JobStage::Done => {
+                log.done = Some(event.clone());
+                log.password_required = None;
+                self.finished.push_back(event.job_id.clone());
+                while self.finished.len() > FINISHED_JOBS_KEPT {
+                    if let Some(job_id) = self.finished.pop_front() {
+                        self.logs.remove(&job_id);
+                        self.throughput.remove(&job_id);
+                    }
+                }
+            }
         }
     }
+
+    pub fn record_password_required(&mut self, event: &PasswordRequiredEvent) {
+        let log = self.logs.entry(event.job_id.clone()).or_default();
+        log.password_required = Some(event.clone());
+    }
+
+    /// Forget that `job_id` waits for a password once one was sent to it
+    pub fn password_provided(&mut self, job_id: &str) {
+        if let Some(log) = self.logs.get_mut(job_id) {
+            log.password_required = None;
+        }
+    }
+
+    pub fn record_completion(&mut self, event: &CompletionEvent) {
+        let log = self.logs.entry(event.job_id.clone()).or_default();
+        log.archives_done.push(event.clone());
+    }
+
+    /// The events recorded for `job_id`, if it is running or finished recently
+    pub fn get(&self, job_id: &str) -> Option<JobEventLog> {
+        self.logs.get(job_id).cloned()
+    }
+
+    /// Throughput of the extraction or test job `job_id`, if it is running or
+    /// finished recently
+    pub fn metrics(&self, job_id: &str) -> Option<JobMetrics> {
+        self.throughput.get(job_id).map(Throughput::metrics)
+    }
+
+    /// The latest progress event of `job_id`, if it reported any
+    pub fn progress(&self, job_id: &str) -> Option<&JobEvent> {
+        self.logs.get(job_id)?.progress.as_ref()
+    }
+}
+
+/// Environment variable overriding the number of file handles extractions may use
+pub const MAX_OPEN_FILES_ENV: &str = "UNARCHIVER_MAX_OPEN_FILES";
+
+/// Size the file handle budget from `UNARCHIVER_MAX_OPEN_FILES`, falling back to
+/// the process's open file limit.
+fn fd_budget_from_env() -> extractor::FdBudget {
+    match std::env::var(MAX_OPEN_FILES_ENV)
+        .ok()
+        .and_then(|value| value.parse().ok())
+    {
+        Some(max) => extractor::FdBudget::new(max),
+        None => extractor::FdBudget::from_system_limit(),
+    }
 }
@@ -0,0 +1,232 @@
+//! Menu-bar (tray) icon for following extractions without a window open.
+//!
+//! The menu lists running extractions and archive tests with their progress,
+//! offers to pause or resume them all, and reopens recently used archives. It is rebuilt as jobs
+//! change, at most once a second while they only report progress.
+
+use crate::commands::{self, JobKind, JobStage, JobSummary};
+use crate::state::{AppState, MAIN_WINDOW};
+use parking_lot::Mutex;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use tauri::menu::{IsMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Wry};
+
+const TRAY_ID: &str = "main";
+
+/// Prefix of the menu ids of recent archives, followed by the archive path
+const RECENT_PREFIX: &str = "recent:";
+
+/// Shortest time between two menu rebuilds caused by progress events
+const PROGRESS_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// When the menu was last rebuilt
+#[derive(Default)]
+struct LastRefresh(Mutex<Option<Instant>>);
+
+/// Add the tray icon to the menu bar
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    app.manage(LastRefresh::default());
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Unarchiver")
+        .menu(&build_menu(app)?)
+        .show_menu_on_left_click(true)
+        .on_menu_event(handle_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    Ok(())
+}
+
+/// Rebuild the menu after a job event, skipping progress events that arrive
+/// soon after the last rebuild
+pub fn job_changed(app: &AppHandle, stage: JobStage) {
+    if matches!(stage, JobStage::Progress) {
+        let Some(last_refresh) = app.try_state::<LastRefresh>() else {
+            return;
+        };
+        let last = *last_refresh.0.lock();
+        if last.is_some_and(|last| last.elapsed() < PROGRESS_REFRESH_INTERVAL) {
+            return;
+        }
+    }
+    refresh(app);
+}
+
+/// Rebuild the menu from the current jobs and recent archives
+pub fn refresh(app: &AppHandle) {
+    let (Some(tray), Some(last_refresh)) =
+        (app.tray_by_id(TRAY_ID), app.try_state::<LastRefresh>())
+    else {
+        return;
+    };
+    *last_refresh.0.lock() = Some(Instant::now());
+
+    match build_menu(app) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => eprintln!("Failed to update tray menu: {}", e),
+    }
+}
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let state = app.state::<AppState>();
+    let paused = state.paused.load(Ordering::Relaxed);
+    let jobs: Vec<JobSummary> = commands::active_jobs(&state)
+        .into_iter()
+        .filter(|job| matches!(job.kind, JobKind::Extract | JobKind::Verify))
+        .collect();
+    let recent: Vec<String> = state.library.lock().recent_archives();
+
+    let mut items: Vec<Box<dyn IsMenuItem<Wry>>> = Vec::new();
+    if jobs.is_empty() {
+        items.push(Box::new(MenuItem::new(
+            app,
+            "No active jobs",
+            false,
+            None::<&str>,
+        )?));
+    }
+    for job in &jobs {
+        items.push(Box::new(MenuItem::new(
+            app,
+            job_label(job, paused),
+            false,
+            None::<&str>,
+        )?));
+    }
+
+    let pause_label = if paused { "Resume All" } else { "Pause All" };
+    items.push(Box::new(MenuItem::with_id(
+        app,
+        "pause",
+        pause_label,
+        paused || !jobs.is_empty(),
+        None::<&str>,
+    )?));
+    items.push(Box::new(PredefinedMenuItem::separator(app)?));
+
+    let recent_items = recent
+        .iter()
+        .map(|path| {
+            MenuItem::with_id(
+                app,
+                format!("{}{}", RECENT_PREFIX, path),
+                file_name(path),
+                true,
+                None::<&str>,
+            )
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let recent_refs: Vec<&dyn IsMenuItem<Wry>> = recent_items
+        .iter()
+        .map(|item| item as &dyn IsMenuItem<Wry>)
+        .collect();
+    items.push(Box::new(Submenu::with_items(
+        app,
+        "Recent Archives",
+        !recent_items.is_empty(),
+        &recent_refs,
+    )?));
+    items.push(Box::new(PredefinedMenuItem::separator(app)?));
+
+    items.push(Box::new(MenuItem::with_id(
+        app,
+        "show",
+        "Show Unarchiver",
+        true,
+        None::<&str>,
+    )?));
+    items.push(Box::new(MenuItem::with_id(
+        app,
+        "quit",
+        "Quit Unarchiver",
+        true,
+        None::<&str>,
+    )?));
+
+    let item_refs: Vec<&dyn IsMenuItem<Wry>> = items.iter().map(|item| item.as_ref()).collect();
+    Menu::with_items(app, &item_refs)
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        "pause" => {
+            let paused = app.state::<AppState>().paused.load(Ordering::Relaxed);
+            commands::set_paused(app, !paused);
+        }
+        "show" => {
+            if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }
+        "quit" => app.exit(0),
+        id => {
+            if let Some(path) = id.strip_prefix(RECENT_PREFIX) {
+                if let Err(e) = commands::show_archive_window(app, path.to_string()) {
+                    eprintln!("Failed to open recent archive: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Menu text for a running extraction, such as "photos.zip — 42%", or test,
+/// such as "Testing photos.zip — 12.0 MB"
+fn job_label(job: &JobSummary, paused: bool) -> String {
+    let path = job
+        .archive_path
+        .as_deref()
+        .or(job.archive_paths.first().map(String::as_str))
+        .unwrap_or_default();
+    let mut label = match job.kind {
+        JobKind::Verify => format!("Testing {}", file_name(path)),
+        _ => file_name(path),
+    };
+    if job.archive_paths.len() > 1 {
+        label.push_str(&format!(" (+{} more)", job.archive_paths.len() - 1));
+    }
+
+    let progress = match (job.processed, job.total) {
+        (Some(processed), Some(total)) if total > 0 => {
+            Some(format!("{}%", processed.saturating_mul(100) / total))
+        }
+        (Some(processed), _) => Some(format_size(processed)),
+        _ => None,
+    };
+    if let Some(progress) = progress {
+        label.push_str(" — ");
+        label.push_str(&progress);
+    }
+    if paused {
+        label.push_str(" (paused)");
+    }
+    label
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path).file_name().map_or_else(
+        || path.to_string(),
+        |name| name.to_string_lossy().to_string(),
+    )
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
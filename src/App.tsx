@@ -17,6 +17,7 @@ import {
 	Sun,
 } from "lucide-react";
 import { useEffect, useRef, useState } from "react";
+import CrashedJobsDialog from "./components/CrashedJobsDialog";
 import MainLayout from "./components/MainLayout";
 import PasswordPrompt from "./components/PasswordPrompt";
 import PermissionDialog from "./components/PermissionDialog";
@@ -25,16 +26,22 @@ import Settings from "./components/Settings";
 import ToastContainer from "./components/ToastContainer";
 import type {
 	CompletionEvent,
+	CrashedJob,
+	ExtractStartedEvent,
 	PasswordRequiredEvent,
 	ProgressEvent,
 } from "./lib/api";
 import {
 	onCompletion,
+	onCrashedJobs,
+	onExtractStarted,
 	onFilesOpened,
 	onPasswordRequired,
 	onProgress,
+	windowArchivePath,
 } from "./lib/api";
 import {
+	addToQueue,
 	queueMap,
 	setTheme,
 	settingsModalAtom,
@@ -57,6 +64,7 @@ function App() {
 	});
 	const [isQueueDrawerOpen, setIsQueueDrawerOpen] = useState(false);
 	const [showPermissionDialog, setShowPermissionDialog] = useState(false);
+	const [crashedJobs, setCrashedJobs] = useState<CrashedJob[]>([]);
 	const completedJobsRef = useRef<Set<string>>(new Set());
 
 	// Count active queue items (pending or extracting)
@@ -91,6 +99,8 @@ function App() {
 		let unlistenCompletion: (() => void) | undefined;
 		let unlistenPassword: (() => void) | undefined;
 		let unlistenFilesOpened: (() => void) | undefined;
+		let unlistenExtractStarted: (() => void) | undefined;
+		let unlistenCrashedJobs: (() => void) | undefined;
 
 		const setupListeners = async () => {
 			console.log("Setting up event listeners...");
@@ -98,13 +108,22 @@ function App() {
 			// Progress events
 			unlistenProgress = await onProgress((event: ProgressEvent) => {
 				console.log("Progress event received:", event);
+				const previous = queueMap.get()[event.jobId]?.progress;
+				const written =
+					event.action === "written" || event.action === "renamed";
 				updateQueueItem(event.jobId, {
 					status: "extracting",
 					progress: {
 						currentFile: event.currentFile,
 						bytesWritten: event.bytesWritten,
 						totalBytes: event.totalBytes,
-						filesExtracted: 0, // Not provided by backend
+						filesExtracted:
+							(previous?.filesExtracted ?? 0) + (written ? 1 : 0),
+						filesSkipped:
+							(previous?.filesSkipped ?? 0) +
+							(event.action === "skipped" ? 1 : 0),
+						entriesDone: event.entryIndex + (event.action ? 1 : 0),
+						totalEntries: event.totalEntries,
 					},
 				});
 			});
@@ -155,6 +174,7 @@ function App() {
 					status,
 					stats: event.stats,
 					error: event.error || undefined,
+					hints: event.hints,
 					progress: undefined,
 				});
 
@@ -172,6 +192,26 @@ function App() {
 				}
 			});
 
+			// Extractions started by the backend (archives set to extract on open)
+			unlistenExtractStarted = await onExtractStarted(
+				(event: ExtractStartedEvent) => {
+					addToQueue({
+						id: event.jobId,
+						archivePath: event.archivePath,
+						outputDir: event.outputDir,
+						status: "pending",
+					});
+
+					const archiveName = event.archivePath.split("/").pop() || "Archive";
+					showSuccess(`Extraction started: ${archiveName}`);
+				},
+			);
+
+			// Extractions an earlier session didn't finish before crashing
+			unlistenCrashedJobs = await onCrashedJobs((jobs: CrashedJob[]) => {
+				setCrashedJobs(jobs);
+			});
+
 			// Password required events
 			unlistenPassword = await onPasswordRequired(
 				(event: PasswordRequiredEvent) => {
@@ -187,34 +227,39 @@ function App() {
 			unlistenFilesOpened = await onFilesOpened(async (paths: string[]) => {
 				console.log("files_opened event received with paths:", paths);
 
-				// Import dynamically to avoid circular dependencies
-				const { currentDirectoryAtom, selectedArchiveAtom } = await import(
-					"./lib/store"
-				);
-
 				// Process the first archive (if multiple files are opened, just handle the first one)
 				if (paths.length > 0) {
-					const archivePath = paths[0];
-					console.log("Processing archive:", archivePath);
+					await showArchive(paths[0]);
+				}
+			});
 
-					// Get the directory containing the archive
-					const directory = archivePath.substring(
-						0,
-						archivePath.lastIndexOf("/"),
-					);
-					console.log("Navigating to directory:", directory);
+			// Windows opened for one archive show it right away
+			const windowArchive = windowArchivePath();
+			if (windowArchive) {
+				await showArchive(windowArchive);
+			}
+		};
 
-					// Navigate to the directory
-					currentDirectoryAtom.set(directory);
+		const showArchive = async (archivePath: string) => {
+			// Import dynamically to avoid circular dependencies
+			const { currentDirectoryAtom, selectedArchiveAtom } = await import(
+				"./lib/store"
+			);
+			console.log("Processing archive:", archivePath);
 
-					// Select the archive (this will show it in the preview)
-					selectedArchiveAtom.set(archivePath);
-					console.log("Archive selected:", archivePath);
+			// Get the directory containing the archive
+			const directory = archivePath.substring(0, archivePath.lastIndexOf("/"));
+			console.log("Navigating to directory:", directory);
 
-					const archiveName = archivePath.split("/").pop() || "Archive";
-					showSuccess(`Opened: ${archiveName}`);
-				}
-			});
+			// Navigate to the directory
+			currentDirectoryAtom.set(directory);
+
+			// Select the archive (this will show it in the preview)
+			selectedArchiveAtom.set(archivePath);
+			console.log("Archive selected:", archivePath);
+
+			const archiveName = archivePath.split("/").pop() || "Archive";
+			showSuccess(`Opened: ${archiveName}`);
 		};
 
 		setupListeners();
@@ -225,6 +270,8 @@ function App() {
 			unlistenCompletion?.();
 			unlistenPassword?.();
 			unlistenFilesOpened?.();
+			unlistenExtractStarted?.();
+			unlistenCrashedJobs?.();
 		};
 	}, []);
 
@@ -358,6 +405,14 @@ function App() {
 				}}
 			/>
 
+			{/* Interrupted Extractions, one at a time */}
+			<CrashedJobsDialog
+				jobs={crashedJobs}
+				onResolved={(jobId) =>
+					setCrashedJobs((jobs) => jobs.filter((job) => job.jobId !== jobId))
+				}
+			/>
+
 			{/* Settings Modal */}
 			<Settings />
 
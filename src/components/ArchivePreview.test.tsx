@@ -6,8 +6,22 @@ import ArchivePreview from "./ArchivePreview";
 // Mock the API module
 vi.mock("../lib/api", () => ({
 	probeArchive: vi.fn(),
+	listBookmarks: vi.fn().mockResolvedValue([]),
+	bookmarkArchive: vi.fn(),
+	removeBookmark: vi.fn(),
+	testArchive: vi.fn(),
 }));
 
+// Probe fields the tests below don't care about
+const probeDefaults = {
+	schema_version: 1,
+	uncompressed_is_estimate: false,
+	header_encrypted: false,
+	is_tarbomb: false,
+	summary: { file_count: 0, directory_count: 0, extensions: [] },
+	resources: {},
+};
+
 describe("ArchivePreview", () => {
 	beforeEach(() => {
 		vi.clearAllMocks();
@@ -32,6 +46,7 @@ describe("ArchivePreview", () => {
 								format: "ZIP",
 								entries: 2,
 								encrypted: false,
+								...probeDefaults,
 								entry_list: [],
 							}),
 						100,
@@ -52,10 +67,11 @@ describe("ArchivePreview", () => {
 			entries: 3,
 			encrypted: false,
 			uncompressed_estimate: 1024000,
+			...probeDefaults,
 			entry_list: [
-				{ path: "file1.txt", is_directory: false, size: 512000 },
-				{ path: "file2.txt", is_directory: false, size: 512000 },
-				{ path: "folder/", is_directory: true, size: 0 },
+				{ path: "file1.txt", is_directory: false, kind: "file", size: 512000 },
+				{ path: "file2.txt", is_directory: false, kind: "file", size: 512000 },
+				{ path: "folder/", is_directory: true, kind: "directory", size: 0 },
 			],
 		};
 
@@ -77,7 +93,10 @@ describe("ArchivePreview", () => {
 			format: "ZIP",
 			entries: 1,
 			encrypted: true,
-			entry_list: [{ path: "secret.txt", is_directory: false, size: 1024 }],
+			...probeDefaults,
+			entry_list: [
+				{ path: "secret.txt", is_directory: false, kind: "file", size: 1024 },
+			],
 		};
 
 		vi.mocked(api.probeArchive).mockResolvedValue(mockArchiveInfo);
@@ -114,6 +133,7 @@ describe("ArchivePreview", () => {
 			format: "ZIP",
 			entries: 0,
 			encrypted: false,
+			...probeDefaults,
 			entry_list: [],
 		};
 
@@ -11,16 +11,30 @@ import {
 	Folder,
 	Lock,
 	RefreshCw,
+	ShieldCheck,
+	Star,
 } from "lucide-react";
 import { useCallback, useEffect, useState } from "react";
 import type { ArchiveEntry, ArchiveInfo } from "../lib/api";
-import { probeArchive } from "../lib/api";
-import { showError, showWarning } from "../lib/toast";
+import {
+	bookmarkArchive,
+	listBookmarks,
+	probeArchive,
+	removeBookmark,
+	testArchive,
+} from "../lib/api";
+import { showError, showSuccess, showWarning } from "../lib/toast";
+import Bookmarks from "./Bookmarks";
 import SplitExtractButton from "./SplitExtractButton";
 
+// Mirrors MANY_ENTRIES_THRESHOLD in the extractor's preflight checks
+const MANY_ENTRIES_THRESHOLD = 100_000;
+
 interface ArchivePreviewProps {
 	archivePath: string | null;
 	onExtract: (customOutputDir?: string) => void;
+	/** Opens a bookmarked archive; the start screen lists bookmarks when set */
+	onSelectArchive?: (path: string) => void;
 }
 
 interface TreeNode {
@@ -36,12 +50,15 @@ interface TreeNode {
 export default function ArchivePreview({
 	archivePath,
 	onExtract,
+	onSelectArchive,
 }: ArchivePreviewProps) {
 	const [isLoading, setIsLoading] = useState(false);
 	const [archiveInfo, setArchiveInfo] = useState<ArchiveInfo | null>(null);
 	const [error, setError] = useState<string | null>(null);
 	const [tree, setTree] = useState<TreeNode[]>([]);
 	const [_retryCount, setRetryCount] = useState(0);
+	const [isBookmarked, setIsBookmarked] = useState(false);
+	const [isTesting, setIsTesting] = useState(false);
 
 	// Build tree structure from flat entry list
 	const buildTree = useCallback((entries: ArchiveEntry[]): TreeNode[] => {
@@ -110,11 +127,23 @@ export default function ArchivePreview({
 				setRetryCount(0); // Reset retry count on success
 
 				// Show warning if archive is encrypted
-				if (info.encrypted) {
+				if (info.header_encrypted) {
+					showWarning(
+						"This archive's file list is encrypted. Its contents can't be previewed without the password.",
+					);
+				} else if (info.encrypted) {
 					showWarning(
 						"This archive is password-protected. You will be prompted for a password during extraction.",
 					);
 				}
+
+				// Many small files can exhaust inodes before disk space
+				const inodes = info.resources.inodes_needed ?? 0;
+				if (inodes >= MANY_ENTRIES_THRESHOLD) {
+					showWarning(
+						`This archive creates ${inodes.toLocaleString()} files and folders. Extraction may take a while.`,
+					);
+				}
 			} catch (err) {
 				const errorMsg =
 					err instanceof Error ? err.message : "Failed to load archive";
@@ -151,6 +180,62 @@ export default function ArchivePreview({
 		loadArchive();
 	}, [archivePath, buildTree]);
 
+	// Whether the archive is bookmarked
+	useEffect(() => {
+		setIsBookmarked(false);
+		if (!archivePath) return;
+
+		let cancelled = false;
+		listBookmarks(archivePath)
+			.then((found) => {
+				if (!cancelled) {
+					setIsBookmarked(found.some((b) => b.path === archivePath));
+				}
+			})
+			.catch((err) => console.error("Failed to load bookmarks:", err));
+		return () => {
+			cancelled = true;
+		};
+	}, [archivePath]);
+
+	const toggleBookmark = async () => {
+		if (!archivePath) return;
+		try {
+			if (isBookmarked) {
+				await removeBookmark(archivePath);
+			} else {
+				await bookmarkArchive(archivePath);
+			}
+			setIsBookmarked(!isBookmarked);
+		} catch (err) {
+			showError(`Failed to update bookmark: ${err}`);
+		}
+	};
+
+	// Decode every file without extracting, to catch damage before extraction
+	const runTest = async () => {
+		if (!archivePath) return;
+		setIsTesting(true);
+		try {
+			const report = await testArchive(archivePath);
+			const failed = report.entries.filter((entry) => entry.error);
+			if (report.ok) {
+				showSuccess(`All ${report.entries.length} files passed the test`);
+			} else if (report.error) {
+				showError(`Archive test failed: ${report.error}`);
+			} else {
+				const names = failed.map((entry) => entry.path).join(", ");
+				showError(
+					`${failed.length} of ${report.entries.length} files are damaged: ${names}`,
+				);
+			}
+		} catch (err) {
+			showError(`Failed to test archive: ${err}`);
+		} finally {
+			setIsTesting(false);
+		}
+	};
+
 	const toggleFolder = useCallback((nodePath: number[]) => {
 		setTree((prevTree) => {
 			// Helper function to deeply clone and update a node
@@ -326,6 +411,11 @@ export default function ArchivePreview({
 				<div className="flex flex-col items-center justify-center h-full text-default-400">
 					<FileArchive className="w-16 h-16 mb-4 opacity-50" />
 					<p className="text-sm">Select an archive to preview its contents</p>
+					{onSelectArchive && (
+						<div className="mt-6 w-full flex justify-center px-4">
+							<Bookmarks onSelect={onSelectArchive} />
+						</div>
+					)}
 				</div>
 			);
 		}
@@ -389,6 +479,33 @@ export default function ArchivePreview({
 					<h3 className="text-lg font-semibold truncate flex-1">
 						{archivePath ? getArchiveName() : "Archive Preview"}
 					</h3>
+					{archivePath && (
+						<Button
+							isIconOnly
+							size="sm"
+							variant="light"
+							className="ml-2 flex-shrink-0"
+							aria-label={isBookmarked ? "Remove bookmark" : "Bookmark archive"}
+							onPress={toggleBookmark}
+						>
+							<Star
+								className={`w-4 h-4 ${isBookmarked ? "fill-current text-warning" : "text-default-400"}`}
+							/>
+						</Button>
+					)}
+					{archivePath && archiveInfo && (
+						<Button
+							isIconOnly
+							size="sm"
+							variant="light"
+							className="ml-2 flex-shrink-0"
+							aria-label="Test archive"
+							isLoading={isTesting}
+							onPress={runTest}
+						>
+							<ShieldCheck className="w-4 h-4 text-default-400" />
+						</Button>
+					)}
 					{archivePath && archiveInfo && (
 						<div className="ml-2 flex-shrink-0">
 							<SplitExtractButton onExtract={onExtract} />
@@ -0,0 +1,185 @@
+import { Button } from "@heroui/button";
+import { Chip } from "@heroui/chip";
+import { Input } from "@heroui/input";
+import { FileArchive, Search, Star } from "lucide-react";
+import { useCallback, useEffect, useState } from "react";
+import type { Bookmark } from "../lib/api";
+import {
+	bookmarkArchive,
+	listBookmarks,
+	listBookmarkTags,
+	removeBookmark,
+} from "../lib/api";
+import { showError } from "../lib/toast";
+
+interface BookmarksProps {
+	onSelect: (path: string) => void;
+}
+
+function fileName(path: string): string {
+	const parts = path.split("/");
+	return parts[parts.length - 1] || path;
+}
+
+export default function Bookmarks({ onSelect }: BookmarksProps) {
+	const [query, setQuery] = useState("");
+	const [tag, setTag] = useState<string | null>(null);
+	const [bookmarks, setBookmarks] = useState<Bookmark[]>([]);
+	const [tags, setTags] = useState<string[]>([]);
+	const [newTags, setNewTags] = useState<Record<string, string>>({});
+
+	const load = useCallback(async () => {
+		try {
+			const [found, allTags] = await Promise.all([
+				listBookmarks(query || undefined, tag ?? undefined),
+				listBookmarkTags(),
+			]);
+			setBookmarks(found);
+			setTags(allTags);
+			// Drop the tag filter once no bookmark carries it
+			if (tag && !allTags.includes(tag)) {
+				setTag(null);
+			}
+		} catch (error) {
+			console.error("Failed to load bookmarks:", error);
+		}
+	}, [query, tag]);
+
+	useEffect(() => {
+		load();
+	}, [load]);
+
+	const saveTags = async (bookmark: Bookmark, tags: string[]) => {
+		try {
+			await bookmarkArchive(bookmark.path, tags);
+			await load();
+		} catch (error) {
+			showError(`Failed to update bookmark: ${error}`);
+		}
+	};
+
+	const handleAddTag = async (bookmark: Bookmark) => {
+		const added = (newTags[bookmark.path] ?? "")
+			.split(",")
+			.map((t) => t.trim())
+			.filter(Boolean);
+		if (added.length === 0) return;
+		setNewTags((prev) => ({ ...prev, [bookmark.path]: "" }));
+		await saveTags(bookmark, [...bookmark.tags, ...added]);
+	};
+
+	const handleRemove = async (path: string) => {
+		try {
+			await removeBookmark(path);
+			await load();
+		} catch (error) {
+			showError(`Failed to remove bookmark: ${error}`);
+		}
+	};
+
+	if (bookmarks.length === 0 && !query && !tag) {
+		return null;
+	}
+
+	return (
+		<div className="w-full max-w-md flex flex-col gap-2">
+			<Input
+				size="sm"
+				aria-label="Search bookmarks"
+				placeholder="Search bookmarks by name or tag"
+				startContent={<Search className="w-4 h-4 text-default-400" />}
+				value={query}
+				onValueChange={setQuery}
+				isClearable
+				onClear={() => setQuery("")}
+			/>
+
+			{tags.length > 0 && (
+				<div className="flex flex-wrap gap-1">
+					{tags.map((t) => (
+						<Chip
+							key={t}
+							size="sm"
+							variant={t === tag ? "solid" : "flat"}
+							color={t === tag ? "primary" : "default"}
+							className="cursor-pointer"
+							onClick={() => setTag(t === tag ? null : t)}
+						>
+							{t}
+						</Chip>
+					))}
+				</div>
+			)}
+
+			<div aria-label="Bookmarked archives" className="flex flex-col gap-1">
+				{bookmarks.length === 0 && (
+					<p className="text-xs text-default-400 text-center py-2">
+						No bookmarks match
+					</p>
+				)}
+				{bookmarks.map((bookmark) => (
+					<div
+						key={bookmark.path}
+						className="flex flex-col gap-1 px-2 py-1.5 rounded-md hover:bg-default-100 dark:hover:bg-default-50"
+					>
+						<div className="flex items-center gap-2">
+							<button
+								type="button"
+								className="flex items-center gap-2 flex-1 min-w-0 border-0 bg-transparent text-left cursor-pointer"
+								onClick={() => onSelect(bookmark.path)}
+								title={bookmark.path}
+							>
+								<FileArchive className="w-4 h-4 flex-shrink-0 text-default-500" />
+								<span className="text-sm truncate text-default-700">
+									{fileName(bookmark.path)}
+								</span>
+							</button>
+							<Button
+								isIconOnly
+								size="sm"
+								variant="light"
+								aria-label={`Remove bookmark of ${fileName(bookmark.path)}`}
+								onPress={() => handleRemove(bookmark.path)}
+							>
+								<Star className="w-4 h-4 fill-current text-warning" />
+							</Button>
+						</div>
+						<div className="flex flex-wrap items-center gap-1 pl-6">
+							{bookmark.tags.map((t) => (
+								<Chip
+									key={t}
+									size="sm"
+									variant="flat"
+									onClose={() =>
+										saveTags(
+											bookmark,
+											bookmark.tags.filter((other) => other !== t),
+										)
+									}
+								>
+									{t}
+								</Chip>
+							))}
+							<input
+								type="text"
+								aria-label={`Add tags to ${fileName(bookmark.path)}`}
+								placeholder="Add tag"
+								className="text-xs bg-transparent outline-none w-20 text-default-500"
+								value={newTags[bookmark.path] ?? ""}
+								onChange={(e) =>
+									setNewTags((prev) => ({
+										...prev,
+										[bookmark.path]: e.target.value,
+									}))
+								}
+								onKeyDown={(e) => {
+									if (e.key === "Enter") handleAddTag(bookmark);
+								}}
+							/>
+						</div>
+					</div>
+				))}
+			</div>
+		</div>
+	);
+}
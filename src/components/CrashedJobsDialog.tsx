@@ -0,0 +1,110 @@
+import { Button } from "@heroui/button";
+import {
+	Modal,
+	ModalBody,
+	ModalContent,
+	ModalFooter,
+	ModalHeader,
+} from "@heroui/modal";
+import { AlertTriangle } from "lucide-react";
+import { useState } from "react";
+import type { CrashedJob, CrashedJobAction } from "../lib/api";
+import { resolveCrashedJob } from "../lib/api";
+import { showError } from "../lib/toast";
+
+interface CrashedJobsDialogProps {
+	jobs: CrashedJob[];
+	onResolved: (jobId: string) => void;
+}
+
+export default function CrashedJobsDialog({
+	jobs,
+	onResolved,
+}: CrashedJobsDialogProps) {
+	const [busyJobId, setBusyJobId] = useState<string | null>(null);
+	const job = jobs[0];
+
+	const handleAction = async (action: CrashedJobAction) => {
+		if (!job) return;
+		setBusyJobId(job.jobId);
+		try {
+			// Resumed jobs show up in the queue through extract_started
+			await resolveCrashedJob(job.jobId, action);
+		} catch (err) {
+			showError(`Failed to ${action} the interrupted extraction: ${err}`);
+		} finally {
+			setBusyJobId(null);
+			onResolved(job.jobId);
+		}
+	};
+
+	const archiveName = (path: string) => path.split("/").pop() || path;
+
+	return (
+		<Modal
+			isOpen={job !== undefined}
+			onClose={() => handleAction("dismiss")}
+			size="lg"
+		>
+			<ModalContent>
+				<ModalHeader className="flex gap-2 items-center">
+					<AlertTriangle className="w-5 h-5 text-warning" />
+					<span>Extraction Interrupted</span>
+				</ModalHeader>
+				{job && (
+					<ModalBody>
+						<p className="text-sm text-default-600 mb-3">
+							Unarchive closed unexpectedly while extracting{" "}
+							<strong>{archiveName(job.archivePaths[0] ?? "")}</strong>
+							{job.archivePaths.length > 1 &&
+								` and ${job.archivePaths.length - 1} more archive(s)`}
+							.
+						</p>
+						{job.outputDir && (
+							<div className="bg-default-100 rounded-lg p-4 mb-3">
+								<p className="text-sm font-medium mb-1">Extracting to:</p>
+								<p className="text-sm text-default-600 break-all">
+									{job.outputDir}
+								</p>
+								{job.currentFile && (
+									<p className="text-xs text-default-500 mt-2 break-all">
+										Last file: {job.currentFile}
+									</p>
+								)}
+							</div>
+						)}
+						<p className="text-xs text-default-500">
+							Resume picks up where it stopped, keeping the files already
+							extracted. Clean Up removes what the interrupted extraction left
+							behind.
+						</p>
+					</ModalBody>
+				)}
+				<ModalFooter>
+					<Button
+						variant="light"
+						isDisabled={busyJobId !== null}
+						onPress={() => handleAction("dismiss")}
+					>
+						Ignore
+					</Button>
+					<Button
+						variant="light"
+						color="danger"
+						isDisabled={busyJobId !== null}
+						onPress={() => handleAction("cleanup")}
+					>
+						Clean Up
+					</Button>
+					<Button
+						color="primary"
+						isLoading={busyJobId !== null}
+						onPress={() => handleAction("resume")}
+					>
+						Resume
+					</Button>
+				</ModalFooter>
+			</ModalContent>
+		</Modal>
+	);
+}
@@ -251,6 +251,7 @@ export default function MainLayout() {
 				<ArchivePreview
 					archivePath={selectedArchive}
 					onExtract={handleExtract}
+					onSelectArchive={handleArchiveSelect}
 				/>
 			</div>
 		</div>
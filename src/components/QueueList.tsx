@@ -6,6 +6,7 @@ import { useStore } from "@nanostores/react";
 import { CheckCircle, Clock, Loader2, Trash2, X, XCircle } from "lucide-react";
 import { cancelJob } from "../lib/api";
 import { queueMap, removeFromQueue } from "../lib/store";
+import type { ErrorHint } from "../lib/api";
 import type { JobStatus, QueueItem } from "../lib/types";
 
 function formatBytes(bytes: number): string {
@@ -48,6 +49,28 @@ function getStatusIcon(status: JobStatus) {
 	}
 }
 
+// Next steps shown under a failed extraction
+const HINT_ADVICE: Record<ErrorHint, string> = {
+	missing_volume:
+		"Make sure every part of the multi-part set is in the same folder.",
+	enter_password: "Extract again and enter the archive's password.",
+	retry_password:
+		"Check the password, including its letter case and keyboard layout.",
+	newer_format_version:
+		"The archive was made by a newer archiver version than Unarchiver supports.",
+	redownload:
+		"The archive seems incomplete or damaged. Download or copy it again.",
+	not_an_archive:
+		"Check that the file is an archive; its extension may not match its contents.",
+	free_disk_space: "Free up space on the destination or extract elsewhere.",
+	check_permissions:
+		"Check that the archive can be read and the destination written.",
+	raise_size_limit: "Raise or remove the size limit in Settings.",
+	raise_time_limit: "Raise or remove the time limit.",
+	choose_overwrite_mode:
+		"Choose another overwrite mode in Settings or an empty destination.",
+};
+
 interface QueueItemCardProps {
 	item: QueueItem;
 }
@@ -73,9 +96,14 @@ function QueueItemCard({ item }: QueueItemCardProps) {
 		item.status === "failed" ||
 		item.status === "cancelled";
 
+	// By bytes when the job's size is known, otherwise by entries
 	const progressPercentage = item.progress?.totalBytes
 		? Math.round((item.progress.bytesWritten / item.progress.totalBytes) * 100)
-		: 0;
+		: item.progress?.totalEntries
+			? Math.round(
+					(item.progress.entriesDone / item.progress.totalEntries) * 100,
+				)
+			: 0;
 
 	return (
 		<Card className="mb-3">
@@ -138,6 +166,12 @@ function QueueItemCard({ item }: QueueItemCardProps) {
 								<span className="font-medium">Files extracted:</span>{" "}
 								{item.progress.filesExtracted}
 							</p>
+							{item.progress.filesSkipped > 0 && (
+								<p>
+									<span className="font-medium">Skipped:</span>{" "}
+									{item.progress.filesSkipped}
+								</p>
+							)}
 							{item.progress.totalBytes && (
 								<p>
 									<span className="font-medium">Progress:</span>{" "}
@@ -155,12 +189,23 @@ function QueueItemCard({ item }: QueueItemCardProps) {
 							{item.stats.files_extracted} files (
 							{formatBytes(item.stats.bytes_written)})
 						</p>
+						{item.stats.blocked.length > 0 && (
+							<p className="text-warning-600">
+								<span className="font-medium">Blocked:</span>{" "}
+								{item.stats.blocked.length} files by content policy
+							</p>
+						)}
 					</div>
 				)}
 				{item.status === "failed" && (
 					<div className="text-sm text-danger-600">
 						<p className="font-medium">Error:</p>
 						<p>{item.error || "Extraction failed with no error message"}</p>
+						{item.hints?.map((hint) => (
+							<p key={hint} className="text-xs text-default-500 mt-1">
+								{HINT_ADVICE[hint]}
+							</p>
+						))}
 					</div>
 				)}
 			</CardBody>
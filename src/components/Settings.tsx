@@ -15,6 +15,7 @@ import { invoke } from "@tauri-apps/api/core";
 import { RotateCcw, Settings as SettingsIcon } from "lucide-react";
 import { useEffect } from "react";
 
+import type { OpenAction } from "../lib/bindings/OpenAction";
 import type { SettingsData } from "../lib/bindings/SettingsData";
 import {
 	resetSettings,
@@ -27,6 +28,20 @@ import {
 import { showError } from "../lib/toast";
 import type { OverwriteMode, Theme } from "../lib/types";
 
+// Extensions whose open action can be chosen, with the format they stand for
+const OPEN_ACTION_EXTENSIONS = [
+	{ extension: "zip", label: "ZIP" },
+	{ extension: "7z", label: "7-Zip" },
+	{ extension: "rar", label: "RAR" },
+	{ extension: "tar", label: "TAR" },
+	{ extension: "tgz", label: "TAR + gzip" },
+	{ extension: "tbz2", label: "TAR + bzip2" },
+	{ extension: "txz", label: "TAR + xz" },
+	{ extension: "gz", label: "gzip" },
+	{ extension: "bz2", label: "bzip2" },
+	{ extension: "xz", label: "xz" },
+];
+
 export default function Settings() {
 	const settings = useStore(settingsAtom);
 	const theme = useStore(themeAtom);
@@ -46,6 +61,9 @@ export default function Settings() {
 						allowSymlinks: loaded.allowSymlinks,
 						allowHardlinks: loaded.allowHardlinks,
 						hasSeenPermissionDialog: loaded.hasSeenPermissionDialog,
+						propagateQuarantine: loaded.propagateQuarantine,
+						openActions: loaded.openActions,
+						tempDir: loaded.tempDir,
 					});
 				}
 			} catch (error) {
@@ -57,6 +75,10 @@ export default function Settings() {
 
 	const saveSettings = async () => {
 		try {
+			// Read the store rather than the render's snapshot, so a change made
+			// just before saving is included (the backend reads this file too)
+			const settings = settingsAtom.get();
+
 			// Map frontend field names to backend
 			const settingsData: SettingsData = {
 				overwriteMode: settings.overwriteMode,
@@ -65,6 +87,9 @@ export default function Settings() {
 				allowSymlinks: settings.allowSymlinks,
 				allowHardlinks: settings.allowHardlinks,
 				hasSeenPermissionDialog: settings.hasSeenPermissionDialog,
+				propagateQuarantine: settings.propagateQuarantine,
+				openActions: settings.openActions,
+				tempDir: settings.tempDir,
 			};
 			await invoke("save_settings", { settings: settingsData });
 		} catch (error) {
@@ -94,6 +119,11 @@ export default function Settings() {
 		}
 	};
 
+	const handleTempDirChange = (value: string) => {
+		updateSettings({ tempDir: value.trim() || undefined });
+		saveSettings();
+	};
+
 	const handleSymlinksChange = (checked: boolean) => {
 		updateSettings({ allowSymlinks: checked });
 		saveSettings();
@@ -104,6 +134,21 @@ export default function Settings() {
 		saveSettings();
 	};
 
+	const handleQuarantineChange = (checked: boolean) => {
+		updateSettings({ propagateQuarantine: checked });
+		saveSettings();
+	};
+
+	const handleOpenActionChange = (extension: string, value: string) => {
+		updateSettings({
+			openActions: {
+				...settingsAtom.get().openActions,
+				[extension]: value as OpenAction,
+			},
+		});
+		saveSettings();
+	};
+
 	const handleThemeChange = (value: string) => {
 		setTheme(value as Theme);
 	};
@@ -148,6 +193,10 @@ export default function Settings() {
 							</SelectItem>
 							<SelectItem key="skip">Skip - Keep existing files</SelectItem>
 							<SelectItem key="rename">Rename - Add (1), (2), etc.</SelectItem>
+							<SelectItem key="ifnewer">
+								If Newer - Replace only older files
+							</SelectItem>
+							<SelectItem key="error">Error - Fail if a file exists</SelectItem>
 						</Select>
 
 						<Input
@@ -171,6 +220,14 @@ export default function Settings() {
 							min={0}
 							step={1}
 						/>
+
+						<Input
+							label="Temporary Files Folder"
+							placeholder="Next to the destination"
+							value={settings.tempDir ?? ""}
+							onValueChange={handleTempDirChange}
+							description="Where downloads and other temporary files are kept while extracting"
+						/>
 					</div>
 
 					<Divider />
@@ -204,6 +261,50 @@ export default function Settings() {
 								</span>
 							</div>
 						</Switch>
+
+						<Switch
+							isSelected={settings.propagateQuarantine}
+							onValueChange={handleQuarantineChange}
+						>
+							<div className="flex flex-col">
+								<span className="text-sm">Quarantine Downloaded Files</span>
+								<span className="text-xs text-default-400">
+									Have Gatekeeper check apps extracted from downloaded archives
+								</span>
+							</div>
+						</Switch>
+					</div>
+
+					<Divider />
+
+					{/* Opening Archives */}
+					<div className="space-y-4">
+						<div>
+							<h4 className="text-sm font-semibold text-default-700">
+								Opening Archives
+							</h4>
+							<p className="text-xs text-default-400">
+								What happens when you open an archive from Finder
+							</p>
+						</div>
+
+						<div className="grid grid-cols-2 gap-3">
+							{OPEN_ACTION_EXTENSIONS.map(({ extension, label }) => (
+								<Select
+									key={extension}
+									label={`.${extension} (${label})`}
+									selectedKeys={[
+										settings.openActions[extension] ?? "browse",
+									]}
+									onChange={(e) =>
+										handleOpenActionChange(extension, e.target.value)
+									}
+								>
+									<SelectItem key="browse">Show in browser</SelectItem>
+									<SelectItem key="extract">Extract immediately</SelectItem>
+								</Select>
+							))}
+						</div>
 					</div>
 
 					<Divider />
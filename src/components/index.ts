@@ -1,4 +1,5 @@
 export { default as ArchivePreview } from "./ArchivePreview";
+export { default as CrashedJobsDialog } from "./CrashedJobsDialog";
 export { default as DropZone } from "./DropZone";
 export { default as FileExplorer } from "./FileExplorer";
 export { default as MainLayout } from "./MainLayout";
@@ -1,24 +1,91 @@
 import { invoke } from "@tauri-apps/api/core";
-import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { UnlistenFn } from "@tauri-apps/api/event";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 import type { Settings } from "./types";
 
 export type { ArchiveEntry } from "./bindings/ArchiveEntry";
 // Import and re-export generated types from Rust
 export type { ArchiveInfo } from "./bindings/ArchiveInfo";
+export type { ArchiveSummary } from "./bindings/ArchiveSummary";
+export type { BlockedEntry } from "./bindings/BlockedEntry";
+export type { Bookmark } from "./bindings/Bookmark";
+export type { ChecksumAlgorithm } from "./bindings/ChecksumAlgorithm";
+export type { ChecksumCheck } from "./bindings/ChecksumCheck";
 export type { CompletionEvent } from "./bindings/CompletionEvent";
+export type { ContentKind } from "./bindings/ContentKind";
+export type { CrashedJob } from "./bindings/CrashedJob";
+export type { CrashedJobAction } from "./bindings/CrashedJobAction";
+export type { Diagnostics } from "./bindings/Diagnostics";
 export type { ExtractOptionsDTO } from "./bindings/ExtractOptionsDTO";
+export type { ExtractStartedEvent } from "./bindings/ExtractStartedEvent";
+export type { EntryAction } from "./bindings/EntryAction";
+export type { EntryCheck } from "./bindings/EntryCheck";
+export type { EntryError } from "./bindings/EntryError";
+export type { EntrySort } from "./bindings/EntrySort";
+export type { EntryType } from "./bindings/EntryType";
+export type { ErrorHint } from "./bindings/ErrorHint";
+export type { ErrorPolicy } from "./bindings/ErrorPolicy";
+export type { ExtensionStats } from "./bindings/ExtensionStats";
 export type { ExtractStats } from "./bindings/ExtractStats";
+export type { ExtractionJournal } from "./bindings/ExtractionJournal";
+export type { ExtractionPlan } from "./bindings/ExtractionPlan";
 export type { FileSystemEntry } from "./bindings/FileSystemEntry";
+export type { FolderJob } from "./bindings/FolderJob";
+export type { Format } from "./bindings/Format";
+export type { FreeSpaceCheck } from "./bindings/FreeSpaceCheck";
+export type { Integrity } from "./bindings/Integrity";
+export type { JobEvent } from "./bindings/JobEvent";
+export type { JobEventLog } from "./bindings/JobEventLog";
+export type { JobKind } from "./bindings/JobKind";
+export type { JobMetrics } from "./bindings/JobMetrics";
+export type { JobStage } from "./bindings/JobStage";
 export type { JobStatus } from "./bindings/JobStatus";
+export type { JobSummary } from "./bindings/JobSummary";
+export type { NameSanitizer } from "./bindings/NameSanitizer";
+export type { NormalizationForm } from "./bindings/NormalizationForm";
+export type { OpenAction } from "./bindings/OpenAction";
+export type { OversizePolicy } from "./bindings/OversizePolicy";
 export type { PasswordRequiredEvent } from "./bindings/PasswordRequiredEvent";
+export type { PathLimits } from "./bindings/PathLimits";
+export type { ProbeProgressEvent } from "./bindings/ProbeProgressEvent";
 export type { ProgressEvent } from "./bindings/ProgressEvent";
+export type { ProgressPhase } from "./bindings/ProgressPhase";
+export type { RenamedEntry } from "./bindings/RenamedEntry";
+export type { ResourceHint } from "./bindings/ResourceHint";
+export type { SignatureMatch } from "./bindings/SignatureMatch";
+export type { SortKey } from "./bindings/SortKey";
+export type { SubfolderPolicy } from "./bindings/SubfolderPolicy";
+export type { SymlinkPolicy } from "./bindings/SymlinkPolicy";
+export type { ThroughputSample } from "./bindings/ThroughputSample";
+export type { TimestampPolicy } from "./bindings/TimestampPolicy";
+export type { VerifyMismatch } from "./bindings/VerifyMismatch";
+export type { VerifyReport } from "./bindings/VerifyReport";
+export type { VolumeIssue } from "./bindings/VolumeIssue";
+export type { VolumeProblem } from "./bindings/VolumeProblem";
+export type { VolumeReport } from "./bindings/VolumeReport";
+export type { WindowsNamePolicy } from "./bindings/WindowsNamePolicy";
 
 import type { ArchiveInfo } from "./bindings/ArchiveInfo";
+import type { Bookmark } from "./bindings/Bookmark";
+import type { ChecksumCheck } from "./bindings/ChecksumCheck";
 import type { CompletionEvent } from "./bindings/CompletionEvent";
+import type { CrashedJob } from "./bindings/CrashedJob";
+import type { CrashedJobAction } from "./bindings/CrashedJobAction";
+import type { Diagnostics } from "./bindings/Diagnostics";
+import type { EntrySort } from "./bindings/EntrySort";
 import type { ExtractOptionsDTO } from "./bindings/ExtractOptionsDTO";
+import type { ExtractStartedEvent } from "./bindings/ExtractStartedEvent";
+import type { ExtractionJournal } from "./bindings/ExtractionJournal";
 import type { FileSystemEntry } from "./bindings/FileSystemEntry";
+import type { FolderJob } from "./bindings/FolderJob";
+import type { JobEvent } from "./bindings/JobEvent";
+import type { JobEventLog } from "./bindings/JobEventLog";
+import type { JobMetrics } from "./bindings/JobMetrics";
+import type { JobSummary } from "./bindings/JobSummary";
 import type { PasswordRequiredEvent } from "./bindings/PasswordRequiredEvent";
+import type { ProbeProgressEvent } from "./bindings/ProbeProgressEvent";
 import type { ProgressEvent } from "./bindings/ProgressEvent";
+import type { VerifyReport } from "./bindings/VerifyReport";
 
 // Convert Settings to ExtractOptionsDTO
 function settingsToOptions(
@@ -35,6 +102,8 @@ function settingsToOptions(
 		allowSymlinks: settings.allowSymlinks,
 		allowHardlinks: settings.allowHardlinks,
 		password,
+		propagateQuarantine: settings.propagateQuarantine,
+		tempDir: settings.tempDir,
 	};
 }
 
@@ -60,23 +129,221 @@ export async function extractArchives(
 	});
 }
 
+/**
+ * Extract every archive in a folder as one batch job, each into a folder of
+ * its own next to it
+ * @param path - Folder to look for archives in
+ * @param recursive - Whether to look in subfolders too
+ * @param settings - Extraction settings
+ * @returns The job ID and the archives it will extract, in order
+ */
+export async function extractFolder(
+	path: string,
+	recursive: boolean,
+	settings: Settings,
+): Promise<FolderJob> {
+	const options = settingsToOptions(settings);
+	return await invoke<FolderJob>("extract_folder", {
+		path,
+		recursive,
+		options,
+	});
+}
+
 /**
  * Probe archive metadata without extracting
  * @param path - Archive file path
+ * @param sort - Optional order for the entry list (sorted in Rust)
+ * @param password - Optional password for archives whose entry list is encrypted
  * @returns Archive information
  */
-export async function probeArchive(path: string): Promise<ArchiveInfo> {
-	return await invoke<ArchiveInfo>("probe", { path });
+export async function probeArchive(
+	path: string,
+	sort?: EntrySort,
+	password?: string,
+): Promise<ArchiveInfo> {
+	return await invoke<ArchiveInfo>("probe", { path, sort, password });
 }
 
 /**
- * Cancel an in-progress extraction job
+ * Test an archive by decoding every file without extracting anything
+ * @param path - Archive file path
+ * @param password - Optional password for encrypted archives
+ * @returns Outcome for the archive and each of its files
+ */
+export async function testArchive(
+	path: string,
+	password?: string,
+): Promise<VerifyReport> {
+	return await invoke<VerifyReport>("test_archive", { path, password });
+}
+
+/**
+ * Check an archive against a .sha512, .sha256 or .md5 file next to it
+ * @param path - Archive file path
+ * @returns Outcome of the comparison, or null if there is no checksum file
+ */
+export async function verifyChecksum(
+	path: string,
+): Promise<ChecksumCheck | null> {
+	return await invoke<ChecksumCheck | null>("verify_checksum", { path });
+}
+
+/**
+ * Cancel any in-progress probe of an archive
+ * @param path - Archive file path being probed
+ */
+export async function cancelProbe(path: string): Promise<void> {
+	await invoke("cancel_probe", { path });
+}
+
+/**
+ * Cancel an in-progress job (extraction or probe)
  * @param jobId - Job ID to cancel
  */
 export async function cancelJob(jobId: string): Promise<void> {
 	await invoke("cancel_job", { jobId });
 }
 
+/**
+ * Resume, clean up after or forget an extraction that was interrupted by a
+ * crash in an earlier session
+ * @param jobId - ID of the interrupted job, from the crashed_jobs event
+ * @param action - What to do about it
+ * @returns ID of the new job when resuming
+ */
+export async function resolveCrashedJob(
+	jobId: string,
+	action: CrashedJobAction,
+): Promise<string | null> {
+	return await invoke<string | null>("resolve_crashed_job", { jobId, action });
+}
+
+/**
+ * Read the journal of an extraction started with the `journal` option that
+ * never finished
+ * @param outputDir - Folder the archive was being extracted into
+ * @returns The journal, or null if there is none
+ */
+export async function readExtractionJournal(
+	outputDir: string,
+): Promise<ExtractionJournal | null> {
+	return await invoke<ExtractionJournal | null>("read_extraction_journal", {
+		outputDir,
+	});
+}
+
+/**
+ * List all active jobs, regardless of kind
+ * @returns Summaries of the running jobs
+ */
+export async function listJobs(): Promise<JobSummary[]> {
+	return await invoke<JobSummary[]>("list_jobs");
+}
+
+/**
+ * List archives browsed or extracted lately
+ * @returns Archive paths, most recent first
+ */
+export async function listRecentArchives(): Promise<string[]> {
+	return await invoke<string[]>("list_recent_archives");
+}
+
+/**
+ * List bookmarked archives
+ * @param query - Text the path or a tag must contain, ignoring case
+ * @param tag - Tag the bookmarks must carry
+ * @returns Matching bookmarks, newest first
+ */
+export async function listBookmarks(
+	query?: string,
+	tag?: string,
+): Promise<Bookmark[]> {
+	return await invoke<Bookmark[]>("list_bookmarks", { query, tag });
+}
+
+/**
+ * Bookmark an archive, or replace the tags of its bookmark
+ * @param path - Path to the archive
+ * @param tags - Tags such as "work" or "photos"; stored lowercase
+ * @returns The saved bookmark
+ */
+export async function bookmarkArchive(
+	path: string,
+	tags: string[] = [],
+): Promise<Bookmark> {
+	return await invoke<Bookmark>("bookmark_archive", { path, tags });
+}
+
+/**
+ * Remove the bookmark of an archive
+ * @param path - Path to the bookmarked archive
+ */
+export async function removeBookmark(path: string): Promise<void> {
+	await invoke("remove_bookmark", { path });
+}
+
+/**
+ * List every tag given to bookmarked archives
+ * @returns Tags, sorted
+ */
+export async function listBookmarkTags(): Promise<string[]> {
+	return await invoke<string[]>("list_bookmark_tags");
+}
+
+/**
+ * Pause or resume every running extraction. Paused extractions keep their
+ * place and continue once resumed.
+ * @param paused - Whether to pause (true) or resume (false)
+ */
+export async function setJobsPaused(paused: boolean): Promise<void> {
+	await invoke("set_jobs_paused", { paused });
+}
+
+/**
+ * Check whether running extractions are paused
+ * @returns True while paused
+ */
+export async function jobsPaused(): Promise<boolean> {
+	return await invoke<boolean>("jobs_paused");
+}
+
+/**
+ * Open an archive in a window of its own, or focus the window already showing
+ * it. Events of jobs started from a window are only sent to that window.
+ * @param path - Archive file path
+ * @returns Label of the archive's window
+ */
+export async function openArchiveWindow(path: string): Promise<string> {
+	return await invoke<string>("open_archive_window", { path });
+}
+
+/**
+ * Replay the key events of a job, to catch up after a reload or in a new window
+ * @param jobId - Job ID to replay, running or recently finished
+ * @returns The job's latest lifecycle, progress and password events
+ */
+export async function syncJobEvents(jobId: string): Promise<JobEventLog> {
+	return await invoke<JobEventLog>("sync_job_events", { jobId });
+}
+
+/**
+ * Get the recent throughput of an extraction job, for a live speed graph
+ * @param jobId - Job ID, running or recently finished
+ * @returns Bytes written, average speed and samples over the last minute
+ */
+export async function getJobMetrics(jobId: string): Promise<JobMetrics> {
+	return await invoke<JobMetrics>("get_job_metrics", { jobId });
+}
+
+/**
+ * Get runtime limits that affect extraction, such as the file handle budget
+ * @returns Current diagnostics
+ */
+export async function getDiagnostics(): Promise<Diagnostics> {
+	return await invoke<Diagnostics>("get_diagnostics");
+}
+
 /**
  * Provide password for a password-protected archive
  * @param jobId - Job ID that requires password
@@ -97,7 +364,46 @@ export async function providePassword(
 export async function onProgress(
 	callback: (event: ProgressEvent) => void,
 ): Promise<UnlistenFn> {
-	return await listen<ProgressEvent>("extract_progress", (event) => {
+	return await getCurrentWebviewWindow().listen<ProgressEvent>("extract_progress", (event) => {
+		callback(event.payload);
+	});
+}
+
+/**
+ * Listen for probe progress events (emitted while large archives are scanned)
+ * @param callback - Function to call on probe progress updates
+ * @returns Unlisten function to stop listening
+ */
+export async function onProbeProgress(
+	callback: (event: ProbeProgressEvent) => void,
+): Promise<UnlistenFn> {
+	return await getCurrentWebviewWindow().listen<ProbeProgressEvent>("probe_progress", (event) => {
+		callback(event.payload);
+	});
+}
+
+/**
+ * Listen for extractions being paused or resumed, from any window or the tray
+ * @param callback - Function to call with whether extractions are now paused
+ * @returns Unlisten function to stop listening
+ */
+export async function onJobsPaused(
+	callback: (paused: boolean) => void,
+): Promise<UnlistenFn> {
+	return await getCurrentWebviewWindow().listen<boolean>("jobs_paused", (event) => {
+		callback(event.payload);
+	});
+}
+
+/**
+ * Listen for job lifecycle events (queued/started/progress/done) of every job kind
+ * @param callback - Function to call on each lifecycle event
+ * @returns Unlisten function to stop listening
+ */
+export async function onJobEvent(
+	callback: (event: JobEvent) => void,
+): Promise<UnlistenFn> {
+	return await getCurrentWebviewWindow().listen<JobEvent>("job_event", (event) => {
 		callback(event.payload);
 	});
 }
@@ -110,7 +416,35 @@ export async function onProgress(
 export async function onCompletion(
 	callback: (event: CompletionEvent) => void,
 ): Promise<UnlistenFn> {
-	return await listen<CompletionEvent>("extract_done", (event) => {
+	return await getCurrentWebviewWindow().listen<CompletionEvent>("extract_done", (event) => {
+		callback(event.payload);
+	});
+}
+
+/**
+ * Listen for extractions the backend started on its own, such as archives
+ * opened from Finder whose format is set to extract immediately
+ * @param callback - Function to call with the new job
+ * @returns Unlisten function to stop listening
+ */
+export async function onExtractStarted(
+	callback: (event: ExtractStartedEvent) => void,
+): Promise<UnlistenFn> {
+	return await getCurrentWebviewWindow().listen<ExtractStartedEvent>("extract_started", (event) => {
+		callback(event.payload);
+	});
+}
+
+/**
+ * Listen for extractions left unfinished by an earlier session that crashed,
+ * reported once at startup
+ * @param callback - Function to call with the interrupted jobs
+ * @returns Unlisten function to stop listening
+ */
+export async function onCrashedJobs(
+	callback: (jobs: CrashedJob[]) => void,
+): Promise<UnlistenFn> {
+	return await getCurrentWebviewWindow().listen<CrashedJob[]>("crashed_jobs", (event) => {
 		callback(event.payload);
 	});
 }
@@ -123,11 +457,18 @@ export async function onCompletion(
 export async function onPasswordRequired(
 	callback: (event: PasswordRequiredEvent) => void,
 ): Promise<UnlistenFn> {
-	return await listen<PasswordRequiredEvent>("password_required", (event) => {
+	return await getCurrentWebviewWindow().listen<PasswordRequiredEvent>("password_required", (event) => {
 		callback(event.payload);
 	});
 }
 
+/**
+ * Archive this window was opened for by `openArchiveWindow`, if any
+ */
+export function windowArchivePath(): string | null {
+	return new URLSearchParams(window.location.search).get("archive");
+}
+
 /**
  * Listen for files opened events (when archives are opened from Finder)
  * @param callback - Function to call when files are opened
@@ -136,7 +477,7 @@ export async function onPasswordRequired(
 export async function onFilesOpened(
 	callback: (paths: string[]) => void,
 ): Promise<UnlistenFn> {
-	return await listen<string[]>("files_opened", (event) => {
+	return await getCurrentWebviewWindow().listen<string[]>("files_opened", (event) => {
 		callback(event.payload);
 	});
 }
@@ -1,4 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EntryType } from "./EntryType";
 
 /**
  * Individual entry within an archive.
@@ -12,6 +13,10 @@ path: string,
  * Whether this entry is a directory
  */
 is_directory: boolean, 
+/**
+ * What the entry is: a file, directory, link or special file
+ */
+kind: EntryType, 
 /**
  * Uncompressed size in bytes
  */
@@ -19,4 +24,8 @@ size: number,
 /**
  * Compressed size in bytes (if available)
  */
-compressed_size?: number, };
+compressed_size?: number, 
+/**
+ * Last modification time in seconds since the Unix epoch (if recorded)
+ */
+modified?: number, };
@@ -1,14 +1,23 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { ArchiveEntry } from "./ArchiveEntry";
+import type { ArchiveSummary } from "./ArchiveSummary";
+import type { ChecksumCheck } from "./ChecksumCheck";
+import type { Format } from "./Format";
+import type { Integrity } from "./Integrity";
+import type { ResourceHint } from "./ResourceHint";
 
 /**
  * Metadata information about an archive.
  */
 export type ArchiveInfo = { 
 /**
- * Archive format (e.g., "ZIP", "TAR", "7Z")
+ * Version of this layout, see [`PROBE_SCHEMA_VERSION`]
  */
-format: string, 
+schema_version: number, 
+/**
+ * Archive format
+ */
+format: Format, 
 /**
  * Number of entries in the archive
  */
@@ -21,10 +30,46 @@ compressed_bytes?: number,
  * Estimated uncompressed size in bytes (if available)
  */
 uncompressed_estimate?: number, 
+/**
+ * Whether `uncompressed_estimate` comes from a compression trailer or index
+ * rather than from the listed entries, and so may be approximate
+ */
+uncompressed_is_estimate: boolean, 
 /**
  * Whether the archive is password-protected
  */
 encrypted: boolean, 
+/**
+ * Whether the entry list itself is encrypted (RAR/7z header encryption), in
+ * which case no entries are listed until the archive is probed with a password
+ */
+header_encrypted: boolean, 
+/**
+ * Name of the single top-level folder containing every entry (if any)
+ */
+single_root?: string, 
+/**
+ * Whether extraction would scatter several items directly into the output
+ * directory (no common top-level folder)
+ */
+is_tarbomb: boolean, 
+/**
+ * Aggregate statistics computed from the entry list
+ */
+summary: ArchiveSummary, 
+/**
+ * Rough memory and disk space needed to extract the archive
+ */
+resources: ResourceHint, 
+/**
+ * Result of the structural integrity check (only set by `probe_deep`)
+ */
+integrity?: Integrity, 
+/**
+ * Result of checking the archive against a checksum file next to it, if
+ * there is one (only set by `probe_deep`)
+ */
+checksum?: ChecksumCheck, 
 /**
  * List of all entries in the archive
  */
@@ -0,0 +1,29 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ArchiveEntry } from "./ArchiveEntry";
+import type { ExtensionStats } from "./ExtensionStats";
+
+/**
+ * Aggregate statistics about an archive's contents.
+ */
+export type ArchiveSummary = { 
+/**
+ * Number of file entries
+ */
+file_count: number, 
+/**
+ * Number of distinct directories, including ones only implied by file paths
+ */
+directory_count: number, 
+/**
+ * The largest file in the archive (if any)
+ */
+largest_entry?: ArchiveEntry, 
+/**
+ * Total compressed size divided by total uncompressed size, over files whose
+ * compressed size is known (if any)
+ */
+average_compression_ratio?: number, 
+/**
+ * File counts and sizes grouped by extension, largest total size first
+ */
+extensions: Array<ExtensionStats>, };
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ContentKind } from "./ContentKind";
+
+/**
+ * A file kept out of the output directory by the content policy.
+ */
+export type BlockedEntry = { 
+/**
+ * Entry path inside the archive
+ */
+path: string, 
+/**
+ * Detected content type
+ */
+kind: ContentKind, 
+/**
+ * Where the file was written instead, if it was quarantined
+ */
+quarantined_to?: string, };
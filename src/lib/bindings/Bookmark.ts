@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A bookmarked archive
+ */
+export type Bookmark = { path: string, 
+/**
+ * Lowercase tags, sorted
+ */
+tags: Array<string>, 
+/**
+ * When the archive was bookmarked, in seconds since the Unix epoch
+ */
+added: number, };
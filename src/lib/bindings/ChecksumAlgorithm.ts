@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Hash function of a checksum file published next to an archive.
+ */
+export type ChecksumAlgorithm = "md5" | "sha256" | "sha512";
@@ -0,0 +1,28 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChecksumAlgorithm } from "./ChecksumAlgorithm";
+
+/**
+ * Result of hashing an archive and comparing the digest with the one a
+ * checksum file next to it lists (see [`crate::verify_checksum`]).
+ */
+export type ChecksumCheck = { 
+/**
+ * Path of the checksum file, e.g. `archive.zip.sha256`
+ */
+file: string, 
+/**
+ * Hash function the checksum file is for
+ */
+algorithm: ChecksumAlgorithm, 
+/**
+ * Lowercase hex digest the checksum file lists for the archive
+ */
+expected: string, 
+/**
+ * Lowercase hex digest of the archive
+ */
+actual: string, 
+/**
+ * Whether the two are the same
+ */
+matches: boolean, };
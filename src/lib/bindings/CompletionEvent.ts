@@ -1,8 +1,13 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ErrorHint } from "../../../../src/lib/bindings/ErrorHint";
 import type { ExtractStats } from "../../../../src/lib/bindings/ExtractStats";
 import type { JobStatus } from "./JobStatus";
 
 /**
  * Completion event payload
  */
-export type CompletionEvent = { jobId: string, archivePath: string, status: JobStatus, stats?: ExtractStats, error?: string, };
+export type CompletionEvent = { jobId: string, archivePath: string, status: JobStatus, stats?: ExtractStats, error?: string, 
+/**
+ * Suggested next steps for a failure
+ */
+hints: Array<ErrorHint>, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Content type of a file, as detected from its first bytes and name.
+ */
+export type ContentKind = "executable" | "script" | "other";
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A job from an earlier session that never finished
+ */
+export type CrashedJob = { jobId: string, 
+/**
+ * Archives the job didn't get to finish, the interrupted one first
+ */
+archivePaths: Array<string>, 
+/**
+ * Where the interrupted archive was being extracted to
+ */
+outputDir?: string, 
+/**
+ * Last file the interrupted archive was known to be writing
+ */
+currentFile?: string, bytesWritten: number, };
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Runtime limits that affect extraction, for troubleshooting
+ */
+export type Diagnostics = { 
+/**
+ * Soft limit on open files for the process (`ulimit -n`), if any
+ */
+openFileLimit?: number, 
+/**
+ * File handles that concurrent extractions may hold in total
+ */
+fdBudget: number, 
+/**
+ * File handles currently held by running extractions
+ */
+fdsInUse: number, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What an extraction did with one entry.
+ */
+export type EntryAction = "written" | "renamed" | "skipped" | "directory" | "failed";
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How one entry fared when its archive was tested.
+ */
+export type EntryCheck = { 
+/**
+ * Entry path inside the archive
+ */
+path: string, 
+/**
+ * Number of bytes decoded
+ */
+size: number, 
+/**
+ * Why the entry failed, if it did
+ */
+error?: string, };
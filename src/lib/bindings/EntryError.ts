@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * An entry an extraction left out because it couldn't be extracted (see
+ * `ExtractOptions::on_error`).
+ */
+export type EntryError = { 
+/**
+ * Entry path inside the archive
+ */
+path: string, 
+/**
+ * Why it couldn't be extracted
+ */
+error: string, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SortKey } from "./SortKey";
+
+/**
+ * Sort order for an archive's entry list.
+ */
+export type EntrySort = { 
+/**
+ * Field to sort by
+ */
+key: SortKey, 
+/**
+ * Whether to sort from largest/latest/last to smallest/earliest/first
+ */
+descending: boolean, };
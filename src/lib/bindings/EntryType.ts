@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Kind of an archive entry, which decides whether it's extracted (see
+ * [`is_safe_entry_type`]).
+ */
+export type EntryType = "file" | "directory" | "symlink" | "hardlink" | "other";
@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Suggested next step after a failed extraction.
+ *
+ * Frontends show [`ErrorHint::advice`] or their own wording keyed on the
+ * serialized code, such as `"missing_volume"`.
+ */
+export type ErrorHint = "missing_volume" | "enter_password" | "retry_password" | "newer_format_version" | "redownload" | "not_an_archive" | "free_disk_space" | "check_permissions" | "raise_size_limit" | "raise_time_limit" | "choose_overwrite_mode";
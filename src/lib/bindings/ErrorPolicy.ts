@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What to do with an entry that can't be extracted.
+ */
+export type ErrorPolicy = "abort" | "skip";
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * File count and size for one file extension.
+ */
+export type ExtensionStats = { 
+/**
+ * Lower-cased extension without the dot (empty for files without one)
+ */
+extension: string, 
+/**
+ * Number of files with this extension
+ */
+count: number, 
+/**
+ * Total uncompressed size of those files in bytes
+ */
+total_size: number, };
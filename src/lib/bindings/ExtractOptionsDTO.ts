@@ -1,6 +1,54 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ContentKind } from "../../../../src/lib/bindings/ContentKind";
+import type { ErrorPolicy } from "../../../../src/lib/bindings/ErrorPolicy";
+import type { FreeSpaceCheck } from "../../../../src/lib/bindings/FreeSpaceCheck";
+import type { NameSanitizer } from "../../../../src/lib/bindings/NameSanitizer";
+import type { NormalizationForm } from "../../../../src/lib/bindings/NormalizationForm";
+import type { OversizePolicy } from "../../../../src/lib/bindings/OversizePolicy";
+import type { PathLimits } from "../../../../src/lib/bindings/PathLimits";
+import type { SubfolderPolicy } from "../../../../src/lib/bindings/SubfolderPolicy";
+import type { SymlinkPolicy } from "../../../../src/lib/bindings/SymlinkPolicy";
+import type { TimestampPolicy } from "../../../../src/lib/bindings/TimestampPolicy";
+import type { WindowsNamePolicy } from "../../../../src/lib/bindings/WindowsNamePolicy";
 
 /**
  * DTO for extraction options from frontend
  */
-export type ExtractOptionsDTO = { overwrite: string, sizeLimitBytes?: number, stripComponents: number, allowSymlinks: boolean, allowHardlinks: boolean, password?: string, };
+export type ExtractOptionsDTO = { overwrite: string, renameTemplate?: string, sizeLimitBytes?: number, 
+/**
+ * Largest size of a single file in bytes
+ */
+maxEntrySizeBytes?: number, 
+/**
+ * What to do with files over `max_entry_size_bytes`; unset fails the
+ * extraction
+ */
+oversizedEntries?: OversizePolicy, 
+/**
+ * Largest ratio of an entry's size to its compressed size
+ */
+maxCompressionRatio?: number, 
+/**
+ * Largest ratio of everything extracted to the archive's size
+ */
+maxExpansionRatio?: number, stripComponents: number, 
+/**
+ * Whether to extract into a folder named after the archive; unset never
+ * does
+ */
+createSubfolder?: SubfolderPolicy, allowSymlinks: boolean, allowHardlinks: boolean, 
+/**
+ * What to do with symbolic links; unset creates them if `allowSymlinks`
+ * and skips them otherwise
+ */
+symlinkPolicy?: SymlinkPolicy, password?: string, detectCaseSensitivity?: boolean, writeProvenance?: boolean, hashFiles?: boolean, writeSha256sums?: boolean, preservePermissions?: boolean, applyUmask?: boolean, preserveXattrs?: boolean, timestampPolicy?: TimestampPolicy, fileMode?: number, dirMode?: number, checkFreeInodes?: boolean, 
+/**
+ * Whether to compare the archive's size with the destination's free
+ * space first; unset doesn't
+ */
+checkFreeSpace?: FreeSpaceCheck, sanitizeControlChars?: boolean, pathLimits?: PathLimits, sanitizeNames?: NameSanitizer, windowsNames?: WindowsNamePolicy, 
+/**
+ * Encoding label for ZIP entry names not marked as UTF-8 (e.g. "cp932");
+ * unset or unknown labels detect it
+ */
+filenameEncoding?: string, normalizeNames?: NormalizationForm, denyContent?: Array<ContentKind>, quarantineDir?: string, propagateQuarantine?: boolean, maxDurationSecs?: number, atomicWrites?: boolean, verifyAfterExtract?: boolean, verifyChecksum?: boolean, writeQueueDepth?: number, threads?: number, transactional?: boolean, cleanupOnFailure?: boolean, onError?: ErrorPolicy, bypassPageCache?: boolean, bufferSize?: number, sparseFiles?: boolean, tempDir?: string, memoryMap?: boolean, journal?: boolean, tolerateTrailingData?: boolean, readConcatenatedTar?: boolean, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Payload of `extract_started`, sent for jobs the backend starts on its own
+ */
+export type ExtractStartedEvent = { jobId: string, archivePath: string, outputDir: string, };
@@ -1,4 +1,9 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BlockedEntry } from "./BlockedEntry";
+import type { ChecksumCheck } from "./ChecksumCheck";
+import type { EntryError } from "./EntryError";
+import type { RenamedEntry } from "./RenamedEntry";
+import type { VerifyMismatch } from "./VerifyMismatch";
 
 /**
  * Statistics about a completed extraction operation.
@@ -19,4 +24,58 @@ duration: number,
 /**
  * Whether the extraction was cancelled
  */
-cancelled: boolean, };
+cancelled: boolean, 
+/**
+ * Number of files left out because they already existed (see
+ * `OverwriteMode::Skip` and `OverwriteMode::IfNewer`) or the pre-write
+ * hook skipped them
+ */
+files_skipped: number, 
+/**
+ * Number of directory entries extracted
+ */
+directories: number, 
+/**
+ * Files blocked or quarantined by the content policy
+ */
+blocked: Array<BlockedEntry>, 
+/**
+ * Non-fatal problems noticed during extraction (e.g. case collisions)
+ */
+warnings: Array<string>, 
+/**
+ * Entries left out because they couldn't be extracted (see
+ * `ExtractOptions::on_error`)
+ */
+errors: Array<EntryError>, 
+/**
+ * Entries whose names were changed to make them safe or portable (see
+ * `ExtractOptions::sanitize_control_chars`, `sanitize_names` and
+ * `windows_names`)
+ */
+renamed: Array<RenamedEntry>, 
+/**
+ * Files a failed extraction wrote and left in place, relative to the
+ * output directory (see `ExtractOptions::cleanup_on_failure`)
+ */
+partial_files: Array<string>, 
+/**
+ * Files that didn't match their entries when read back (see
+ * `ExtractOptions::verify_after_extract`)
+ */
+mismatches: Array<VerifyMismatch>, 
+/**
+ * Result of checking the archive against its checksum file, if it was
+ * (see `ExtractOptions::verify_checksum`)
+ */
+checksum?: ChecksumCheck, 
+/**
+ * Folder created for the archive's entries inside the output directory,
+ * if one was (see `ExtractOptions::create_subfolder`)
+ */
+subfolder?: string, 
+/**
+ * Lowercase hex SHA-256 of every file written, by its path relative to
+ * the output directory (see `ExtractOptions::hash_files`)
+ */
+sha256: { [key: string]: string }, };
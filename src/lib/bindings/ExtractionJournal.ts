@@ -0,0 +1,39 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What an extraction had done when its journal was last saved.
+ */
+export type ExtractionJournal = { 
+/**
+ * Archive being extracted
+ */
+archive_path: string, 
+/**
+ * Directory it is extracted to
+ */
+output_dir: string, 
+/**
+ * Number of entries written completely
+ */
+entries_completed: number, 
+/**
+ * Bytes written by the completed entries
+ */
+bytes_written: number, 
+/**
+ * Archive paths of the entries written completely
+ */
+completed: Array<string>, 
+/**
+ * File being written when the journal was saved
+ */
+current_file?: string, 
+/**
+ * Files the extraction created
+ */
+created_files: Array<string>, 
+/**
+ * Folders the extraction created, including the output directory if it
+ * didn't exist
+ */
+created_dirs: Array<string>, };
@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ResourceHint } from "./ResourceHint";
+
+/**
+ * What extracting an archive into a particular destination takes.
+ */
+export type ExtractionPlan = { 
+/**
+ * Estimated resources needed
+ */
+resources: ResourceHint, 
+/**
+ * Problems with the destination to tell the user about before extracting
+ * (e.g. a RAM-backed filesystem too small for the archive)
+ */
+warnings: Array<string>, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Archives found by [`extract_folder`] and the job extracting them
+ */
+export type FolderJob = { jobId: string, archivePaths: Array<string>, };
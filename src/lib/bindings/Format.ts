@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Archive format.
+ *
+ * Displays and serializes as the format's name ("ZIP", "TAR.GZ", "7Z", ...),
+ * which [`str::parse`] turns back into the format.
+ */
+export type Format = "ZIP" | "TAR" | "TAR.GZ" | "TAR.BZ2" | "TAR.XZ" | "GZIP" | "BZIP2" | "XZ" | "7Z" | "RAR";
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Whether to check the destination's free space before extracting (see
+ * `ExtractOptions::check_free_space`).
+ */
+export type FreeSpaceCheck = "off" | "warn" | "fail";
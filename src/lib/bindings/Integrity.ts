@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Outcome of a structural integrity check.
+ */
+export type Integrity = { "status": "ok" } | { "status": "truncated" } | { "status": "corrupted", 
+/**
+ * Description of the first problem found
+ */
+detail: string, };
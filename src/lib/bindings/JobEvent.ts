@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JobKind } from "./JobKind";
+import type { JobStage } from "./JobStage";
+import type { JobStatus } from "./JobStatus";
+
+/**
+ * Job lifecycle event payload, emitted for every job kind
+ */
+export type JobEvent = { jobId: string, kind: JobKind, stage: JobStage, archivePath?: string, currentItem?: string, processed?: number, total?: number, status?: JobStatus, error?: string, };
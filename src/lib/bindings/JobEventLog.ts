@@ -0,0 +1,30 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CompletionEvent } from "./CompletionEvent";
+import type { JobEvent } from "./JobEvent";
+import type { PasswordRequiredEvent } from "./PasswordRequiredEvent";
+
+/**
+ * Key events of a job so far, replayed to windows that connect while it runs
+ * or after it finished
+ */
+export type JobEventLog = { 
+/**
+ * The latest of the job's queued and started events
+ */
+started?: JobEvent, 
+/**
+ * The latest progress event
+ */
+progress?: JobEvent, 
+/**
+ * Set while the job waits for a password
+ */
+passwordRequired?: PasswordRequiredEvent, 
+/**
+ * Completion events of the archives extracted so far
+ */
+archivesDone: Array<CompletionEvent>, 
+/**
+ * Set once the job has finished
+ */
+done?: JobEvent, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Kind of long-running operation tracked as a job
+ */
+export type JobKind = "probe" | "extract" | "verify";
@@ -0,0 +1,21 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ThroughputSample } from "./ThroughputSample";
+
+/**
+ * Recent throughput of an extraction or test job, for drawing a live speed
+ * graph
+ */
+export type JobMetrics = { 
+/**
+ * Bytes written (or, for a test, checked) so far, across the job's
+ * archives
+ */
+bytesWritten: number, 
+/**
+ * Bytes written per second since the job started
+ */
+averageBytesPerSec: number, 
+/**
+ * Samples of about half a second each over the last minute, oldest first
+ */
+samples: Array<ThroughputSample>, };
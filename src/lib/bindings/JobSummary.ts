@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JobKind } from "./JobKind";
+
+/**
+ * Summary of an active job
+ */
+export type JobSummary = { jobId: string, kind: JobKind, archivePaths: Array<string>, 
+/**
+ * Archive the job is working on, once it reported progress
+ */
+archivePath?: string, processed?: number, total?: number, };
@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Fixes applied to each component of an entry's output path, for names that
+ * extract but are awkward to use (see `ExtractOptions::sanitize_names`).
+ */
+export type NameSanitizer = { 
+/**
+ * Replace dots and spaces ending a name with `_`; Windows drops them, so
+ * such files can't be opened there
+ */
+trailing_dots: boolean, 
+/**
+ * Replace a `-` starting a name with `_`, so the name isn't taken for an
+ * option when passed to a command
+ */
+leading_dashes: boolean, 
+/**
+ * Shorten names longer than this many bytes, keeping their extension
+ * (most filesystems allow 255)
+ */
+max_name_bytes: number | null, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Unicode normalization form of extracted entry names.
+ *
+ * The same accented name can be stored precomposed (`é`, as Windows and
+ * Linux tools write it) or decomposed (`e` and a combining accent, as macOS
+ * tools often do). Both look alike but are different names on most
+ * filesystems.
+ */
+export type NormalizationForm = "keep" | "nfc" | "nfd";
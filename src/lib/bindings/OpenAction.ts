@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What opening an archive from Finder does
+ */
+export type OpenAction = "browse" | "extract";
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What to do with files over `ExtractOptions::max_entry_size_bytes`.
+ */
+export type OversizePolicy = "reject" | "skip";
@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Limits on the entry paths of an archive, so that pathologically nested or
+ * long ones are refused before anything is created for them (see
+ * [`crate::safety::validate_entry_path_with_limits`]).
+ */
+export type PathLimits = { 
+/**
+ * Most directory levels a path may have, counting its file name
+ */
+max_depth: number | null, 
+/**
+ * Longest name a path component may have, in bytes. Applies before names
+ * are shortened by `NameSanitizer::max_name_bytes`, so it's unset by
+ * default.
+ */
+max_component_bytes: number | null, 
+/**
+ * Longest a whole path may be, in bytes
+ */
+max_path_bytes: number | null, };
@@ -1,6 +1,24 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EntryAction } from "../../../../src/lib/bindings/EntryAction";
+import type { ProgressPhase } from "../../../../src/lib/bindings/ProgressPhase";
 
 /**
  * Progress event payload
  */
-export type ProgressEvent = { jobId: string, archivePath: string, currentFile: string, bytesWritten: number, totalBytes?: number, };
+export type ProgressEvent = { jobId: string, archivePath: string, phase: ProgressPhase, currentFile: string, 
+/**
+ * Position of the current entry among those extracted, from 0
+ */
+entryIndex: number, totalEntries?: number, 
+/**
+ * Bytes of the current entry written so far, and its size
+ */
+entryBytes: number, entryTotal?: number, 
+/**
+ * Bytes the extraction has written so far, and will write in all
+ */
+bytesWritten: number, totalBytes?: number, 
+/**
+ * What became of the entry, on the report that finishes it
+ */
+action?: EntryAction, };
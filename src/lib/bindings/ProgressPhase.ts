@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Stage of an extraction a [`ProgressEvent`] comes from.
+ */
+export type ProgressPhase = "scanning" | "extracting" | "verifying";
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * An entry extracted under a different name than the archive gives it, after
+ * its name was sanitized.
+ */
+export type RenamedEntry = { 
+/**
+ * Entry path inside the archive
+ */
+path: string, 
+/**
+ * Path it was extracted to, relative to the output directory
+ */
+renamed_to: string, };
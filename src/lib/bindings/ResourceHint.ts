@@ -0,0 +1,28 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Estimated resources needed to extract an archive, for preflight checks.
+ *
+ * Figures are rough upper bounds derived from the listed sizes and the way each
+ * format is extracted; `None` means the uncompressed size is unknown.
+ */
+export type ResourceHint = { 
+/**
+ * Peak memory in bytes (the decoder's working memory, as every format is
+ * streamed)
+ */
+peak_memory_bytes?: number, 
+/**
+ * Temporary disk space in bytes (none of the supported formats currently
+ * need any, as entries are streamed into place)
+ */
+temp_space_bytes?: number, 
+/**
+ * Disk space in bytes needed in the output directory
+ */
+output_space_bytes?: number, 
+/**
+ * Files and directories created in the output directory, each using one
+ * inode (if the entries could be listed)
+ */
+inodes_needed?: number, };
@@ -1,6 +1,16 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { OpenAction } from "./OpenAction";
 
 /**
  * Settings structure for persistence
  */
-export type SettingsData = { overwriteMode: string, sizeLimitGb: number, stripComponents: number, allowSymlinks: boolean, allowHardlinks: boolean, hasSeenPermissionDialog: boolean, };
+export type SettingsData = { overwriteMode: string, sizeLimitGb: number, stripComponents: number, allowSymlinks: boolean, allowHardlinks: boolean, hasSeenPermissionDialog: boolean, propagateQuarantine: boolean, 
+/**
+ * Action for archives opened from Finder, by lowercase extension; archives
+ * with an extension that isn't listed are browsed
+ */
+openActions: { [key: string]: OpenAction }, 
+/**
+ * Folder for temporary files; unset puts them next to the destination
+ */
+tempDir?: string, };
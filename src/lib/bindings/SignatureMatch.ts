@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Format } from "./Format";
+
+/**
+ * A candidate archive found inside another file.
+ */
+export type SignatureMatch = { 
+/**
+ * Byte offset where the embedded archive starts
+ */
+offset: number, 
+/**
+ * Archive format suggested by the signature
+ */
+format: Format, };
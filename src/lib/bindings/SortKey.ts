@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Field to sort archive entries by.
+ */
+export type SortKey = "path" | "size" | "modified";
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * When to give an archive a folder of its own inside the output directory
+ * (see `ExtractOptions::create_subfolder`).
+ */
+export type SubfolderPolicy = "auto" | "always" | "never";
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Bytes written per second by a job over a short interval
+ */
+export type ThroughputSample = { 
+/**
+ * Milliseconds since the job started, at the end of the interval
+ */
+elapsedMs: number, bytesPerSec: number, };
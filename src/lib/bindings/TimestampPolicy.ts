@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Which modification times extracted files and directories get.
+ */
+export type TimestampPolicy = "preserve" | "now";
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A written file that doesn't match what the archive records for its entry
+ * (see `ExtractOptions::verify_after_extract`).
+ */
+export type VerifyMismatch = { 
+/**
+ * Entry path inside the archive
+ */
+path: string, 
+/**
+ * How the file differs, e.g. its CRC-32 or size
+ */
+reason: string, };
@@ -0,0 +1,25 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EntryCheck } from "./EntryCheck";
+
+/**
+ * Result of testing an archive by decoding every entry without writing
+ * anything (see [`crate::verify`]).
+ */
+export type VerifyReport = { 
+/**
+ * Whether the archive read to its end and every entry decoded and matched
+ * its recorded CRC-32 and size
+ */
+ok: boolean, 
+/**
+ * Outcome for every entry other than directories, in archive order
+ */
+entries: Array<EntryCheck>, 
+/**
+ * Why reading stopped before the end of the archive, if it did
+ */
+error?: string, 
+/**
+ * Non-fatal problems noticed while reading (e.g. data after the archive)
+ */
+warnings: Array<string>, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Problem found with one volume of a multi-volume archive.
+ */
+export type VolumeIssue = { "status": "missing" } | { "status": "corrupted", 
+/**
+ * Description of the problem
+ */
+detail: string, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { VolumeIssue } from "./VolumeIssue";
+
+/**
+ * A missing or damaged volume of a multi-volume archive.
+ */
+export type VolumeProblem = { 
+/**
+ * Path of the volume
+ */
+path: string, 
+/**
+ * What is wrong with it
+ */
+issue: VolumeIssue, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { VolumeProblem } from "./VolumeProblem";
+
+/**
+ * Completeness of a multi-volume archive (split 7z/ZIP or multi-part RAR).
+ */
+export type VolumeReport = { 
+/**
+ * Paths of the volumes making up the set, in order, including missing ones
+ */
+volumes: Array<string>, 
+/**
+ * Volumes that are missing or damaged, in order (empty if the set is complete)
+ */
+problems: Array<VolumeProblem>, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What to do with entry names Windows can't create: reserved device names
+ * such as `CON` or `aux.txt`, names containing `<>:"|?*`, and names ending in
+ * a dot or space.
+ */
+export type WindowsNamePolicy = "allow" | "strict" | "rename" | "skip";
@@ -10,6 +10,8 @@ const defaultSettings: Settings = {
 	allowSymlinks: false,
 	allowHardlinks: false,
 	hasSeenPermissionDialog: false,
+	propagateQuarantine: true,
+	openActions: {},
 };
 
 // Theme atom - stores the current theme preference
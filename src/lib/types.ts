@@ -7,7 +7,7 @@ export type JobStatus =
 	| "failed"
 	| "cancelled";
 
-export type OverwriteMode = "replace" | "skip" | "rename";
+export type OverwriteMode = "replace" | "skip" | "rename" | "ifnewer" | "error";
 
 export type Theme = "light" | "dark" | "system";
 
@@ -16,6 +16,9 @@ export interface Progress {
 	bytesWritten: number;
 	totalBytes?: number;
 	filesExtracted: number;
+	filesSkipped: number;
+	entriesDone: number;
+	totalEntries?: number;
 }
 
 export interface QueueItem {
@@ -25,6 +28,7 @@ export interface QueueItem {
 	status: JobStatus;
 	progress?: Progress;
 	error?: string;
+	hints?: import("./bindings/ErrorHint").ErrorHint[];
 	stats?: import("./bindings/ExtractStats").ExtractStats;
 }
 
@@ -35,6 +39,11 @@ export interface Settings {
 	allowSymlinks: boolean;
 	allowHardlinks: boolean;
 	hasSeenPermissionDialog: boolean;
+	propagateQuarantine: boolean;
+	// Action for archives opened from Finder, by lowercase extension
+	openActions: Partial<Record<string, import("./bindings/OpenAction").OpenAction>>;
+	// Folder for temporary files; unset puts them next to the destination
+	tempDir?: string;
 }
 
 export interface AppStore {